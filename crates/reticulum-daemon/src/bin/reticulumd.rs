@@ -1,43 +1,78 @@
 #![allow(clippy::items_after_test_module)]
 
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
 use clap::Parser;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, OnceLock};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpListener;
 use tokio::task::LocalSet;
 
-use reticulum::destination::{DestinationName, SingleInputDestination};
+use reticulum::destination::{
+    lxmf_delivery_hash_from_identity_hash, DestinationName, SingleInputDestination,
+};
 use reticulum::hash::AddressHash;
 use reticulum::identity::{Identity, PrivateIdentity};
-use reticulum::iface::tcp_client::TcpClient;
-use reticulum::iface::tcp_server::TcpServer;
+use reticulum::iface::tcp_client::{TcpClient, TcpClientStatus};
+use reticulum::iface::tcp_server::{TcpServer, TcpServerStatus};
+use reticulum::iface::{InterfaceTraffic, TrafficHistory};
 use reticulum::packet::{
     ContextFlag, DestinationType, Header, HeaderType, IfacFlag, Packet, PacketContext,
     PacketDataBuffer, PacketType, PropagationType,
 };
-use reticulum::rpc::{http, AnnounceBridge, InterfaceRecord, OutboundBridge, RpcDaemon};
+use reticulum::resource::{ResourceComplete, ResourceCounts, ResourceEvent, ResourceEventKind};
+use reticulum::rpc::{
+    http, parse_source_private_key, AnnounceBridge, AnnounceDetails, AnnounceFuture,
+    BandwidthBridge, DestinationInterfaceBridge, InboundAcceptance, InboundOverloadPolicy,
+    InterfaceFilterBridge, InterfaceRecord, InterfaceTrafficBridge, LinkBridge, LinkInfo,
+    LxmfCodecBridge, OutboundBridge, PaperBridge, PlainBridge, PropagationStoreLimits,
+    ReceivedResourceLimits, ResourceBridge, RpcAuthToken, RpcDaemon, RpcEvent,
+    TransportControlBridge, TransportMetricsBridge, DEFAULT_OUTBOUND_RESUME_MAX_AGE_SECS,
+    DEFAULT_PATH_PERSIST_INTERVAL_SECS, DEFAULT_PATH_TTL_SECS,
+    DEFAULT_PRESENCE_SWEEP_INTERVAL_SECS, DEFAULT_PROPAGATION_PRUNE_INTERVAL_SECS,
+    DELIVERY_DEADLINE_EXCEEDED, RESOURCE_LINK_WAIT_SECS, SETTING_DISPLAY_NAME,
+};
+use reticulum::storage::backup::{apply_pending_restore, BackupPolicy, DEFAULT_BACKUP_KEEP};
 use reticulum::storage::messages::MessagesStore;
-use reticulum::transport::{SendPacketOutcome, SendPacketTrace, Transport, TransportConfig};
+use reticulum::transport::bandwidth::BandwidthControl;
+use reticulum::transport::iface_filter::{
+    InterfaceFilter, InterfaceFilterStatus, InterfaceFilterTable,
+};
+use reticulum::transport::iface_hints::DestinationInterfaceHints;
+use reticulum::transport::{
+    LinkSnapshot, ReceivedData, SendPacketOutcome, SendPacketTrace, Transport, TransportConfig,
+    TransportMetrics,
+};
 use tokio::sync::mpsc::unbounded_channel;
 
 use reticulum_daemon::announce_names::{
     encode_delivery_display_name_app_data, normalize_display_name, parse_peer_name_from_app_data,
 };
-use reticulum_daemon::config::DaemonConfig;
-use reticulum_daemon::direct_delivery::send_via_link;
+use reticulum_daemon::config::{DaemonConfig, DaemonConfigValidator};
+use reticulum_daemon::direct_delivery::{
+    send_resource_via_link, send_via_link, wait_for_active_link,
+};
 use reticulum_daemon::identity_store::load_or_create_identity;
 use reticulum_daemon::inbound_delivery::{
-    decode_inbound_payload, decode_inbound_payload_with_diagnostics,
+    decode_inbound_payload, decode_inbound_payload_with_diagnostics, decode_paper_message,
+    inbound_stamp_value, packed_inbound_message,
 };
-use reticulum_daemon::lxmf_bridge::build_wire_message;
+use reticulum_daemon::lxmf_bridge::{build_wire_message, build_wire_message_at};
+use reticulum_daemon::path_store::{path_records, path_snapshots};
 use reticulum_daemon::receipt_bridge::{
     handle_receipt_event, track_receipt_mapping, ReceiptBridge, ReceiptEvent,
 };
 
+// Bridge event carrying a delivery progress line for the message trace.
+const DELIVERY_STEP_EVENT: &str = "delivery_step";
+// How long to wait for an announce after asking the relay.
+const RELAY_IDENTITY_WAIT_SECS: u64 = 8;
+
 #[derive(Parser, Debug)]
 #[command(name = "reticulumd")]
 struct Args {
@@ -53,6 +88,9 @@ struct Args {
     announce_interval_secs: u64,
     #[arg(long)]
     transport: Option<String>,
+    // Raw length-prefixed RPC for clients that don't speak HTTP.
+    #[arg(long)]
+    rpc_stream: Option<String>,
 }
 
 struct TransportBridge {
@@ -60,10 +98,40 @@ struct TransportBridge {
     signer: PrivateIdentity,
     delivery_source_hash: [u8; 16],
     announce_destination: Arc<tokio::sync::Mutex<SingleInputDestination>>,
-    announce_app_data: Option<Vec<u8>>,
+    announce_app_data: std::sync::Mutex<Option<Vec<u8>>>,
+    extra_announces: Arc<std::sync::Mutex<Vec<ExtraAnnounce>>>,
     peer_crypto: Arc<std::sync::Mutex<HashMap<String, PeerCrypto>>>,
     receipt_map: Arc<std::sync::Mutex<HashMap<String, String>>>,
     receipt_tx: tokio::sync::mpsc::UnboundedSender<ReceiptEvent>,
+    event_tx: tokio::sync::mpsc::UnboundedSender<RpcEvent>,
+    in_flight: Arc<AtomicUsize>,
+}
+
+// Counts a spawned delivery as in flight until its task ends, whichever
+// way it returns.
+struct InFlightDelivery(Arc<AtomicUsize>);
+
+impl InFlightDelivery {
+    fn start(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter.clone())
+    }
+}
+
+impl Drop for InFlightDelivery {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Additional destinations announced next to lxmf.delivery, such as
+// lxmf.propagation on relay nodes.
+#[derive(Clone)]
+struct ExtraAnnounce {
+    aspects: String,
+    destination_hash: String,
+    destination: Arc<tokio::sync::Mutex<SingleInputDestination>>,
+    app_data: Option<Vec<u8>>,
 }
 
 #[derive(Clone, Copy)]
@@ -82,242 +150,1501 @@ impl TransportBridge {
         peer_crypto: Arc<std::sync::Mutex<HashMap<String, PeerCrypto>>>,
         receipt_map: Arc<std::sync::Mutex<HashMap<String, String>>>,
         receipt_tx: tokio::sync::mpsc::UnboundedSender<ReceiptEvent>,
+        event_tx: tokio::sync::mpsc::UnboundedSender<RpcEvent>,
     ) -> Self {
         Self {
             transport,
             signer,
             delivery_source_hash,
             announce_destination,
-            announce_app_data,
+            announce_app_data: std::sync::Mutex::new(announce_app_data),
+            extra_announces: Arc::new(std::sync::Mutex::new(Vec::new())),
             peer_crypto,
             receipt_map,
             receipt_tx,
+            event_tx,
+            in_flight: Arc::new(AtomicUsize::new(0)),
         }
     }
-}
 
-impl OutboundBridge for TransportBridge {
-    fn deliver(
+    // Signs and packs record the way deliver sends it. Messages sent as
+    // another identity are signed with that identity's key.
+    fn build_payload(
         &self,
         record: &reticulum::storage::messages::MessageRecord,
-        _options: &reticulum::rpc::OutboundDeliveryOptions,
-    ) -> Result<(), std::io::Error> {
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> Result<([u8; 16], Vec<u8>), std::io::Error> {
         let destination = parse_destination_hex_required(&record.destination)?;
-        let peer_info = self
-            .peer_crypto
-            .lock()
-            .expect("peer map")
-            .get(&record.destination)
-            .copied();
-        let peer_identity = peer_info.map(|info| info.identity);
-
+        let (source_hash, signer) = match options.source_private_key.as_deref() {
+            Some(key) => {
+                let signer = parse_source_private_key(key)?;
+                let mut source_hash = [0u8; 16];
+                source_hash.copy_from_slice(
+                    lxmf_delivery_hash_from_identity_hash(signer.address_hash()).as_slice(),
+                );
+                (source_hash, signer)
+            }
+            None => (self.delivery_source_hash, self.signer.clone()),
+        };
+        check_signing_source(&record.source, source_hash)?;
         let wire = build_wire_message(
-            self.delivery_source_hash,
+            source_hash,
             destination,
             &record.title,
             &record.content,
             record.fields.clone(),
-            &self.signer,
+            &signer,
         )
         .map_err(std::io::Error::other)?;
+        Ok((destination, wire))
+    }
 
-        let payload = wire;
+    fn delivery_job(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> Result<DeliveryJob, std::io::Error> {
+        let (destination, payload) = self.build_payload(record, options)?;
+        Ok(DeliveryJob {
+            message_id: record.id.clone(),
+            destination_hex: record.destination.clone(),
+            destination,
+            payload,
+        })
+    }
 
-        let destination_hash = AddressHash::new(destination);
-        let transport = self.transport.clone();
-        let peer_crypto = self.peer_crypto.clone();
-        let receipt_map = self.receipt_map.clone();
-        let receipt_tx = self.receipt_tx.clone();
-        let message_id = record.id.clone();
-        let destination_hex = record.destination.clone();
+    fn spawn_deliveries(
+        &self,
+        jobs: Vec<DeliveryJob>,
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) {
+        let context = DeliveryContext {
+            transport: self.transport.clone(),
+            peer_crypto: self.peer_crypto.clone(),
+            receipt_map: self.receipt_map.clone(),
+            receipt_tx: self.receipt_tx.clone(),
+            event_tx: self.event_tx.clone(),
+        };
+        let options = options.clone();
+        let in_flight = InFlightDelivery::start(&self.in_flight);
         tokio::spawn(async move {
-            log_delivery_trace(&message_id, &destination_hex, "start", "delivery requested");
-            let mut identity = peer_identity;
-            // Refresh routing for the destination before link setup.
-            transport.request_path(&destination_hash, None, None).await;
-            log_delivery_trace(&message_id, &destination_hex, "path-request", "requested");
+            let _in_flight = in_flight;
+            // Each record goes out after the one before it, so later ones
+            // reuse the link the first opened. Once one fails the rest are
+            // not sent.
+            for job in jobs {
+                if !context.deliver(job, &options).await {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+// What a spawned delivery needs from the bridge.
+struct DeliveryContext {
+    transport: Arc<Transport>,
+    peer_crypto: Arc<std::sync::Mutex<HashMap<String, PeerCrypto>>>,
+    receipt_map: Arc<std::sync::Mutex<HashMap<String, String>>>,
+    receipt_tx: tokio::sync::mpsc::UnboundedSender<ReceiptEvent>,
+    event_tx: tokio::sync::mpsc::UnboundedSender<RpcEvent>,
+}
 
-            if identity.is_none() {
+// One signed wire message waiting to go out.
+struct DeliveryJob {
+    message_id: String,
+    destination_hex: String,
+    destination: [u8; 16],
+    payload: Vec<u8>,
+}
+
+impl DeliveryContext {
+    // Runs one delivery through to its receipt. False when it failed, which
+    // the receipt already reports.
+    async fn deliver(
+        &self,
+        job: DeliveryJob,
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> bool {
+        let DeliveryJob {
+            message_id,
+            destination_hex,
+            destination,
+            payload,
+        } = job;
+        let Self {
+            transport,
+            peer_crypto,
+            receipt_map,
+            receipt_tx,
+            event_tx,
+        } = self;
+        let destination_hash = AddressHash::new(destination);
+        let peer_identity = peer_crypto
+            .lock()
+            .expect("peer map")
+            .get(&destination_hex)
+            .map(|info| info.identity);
+        let relay = options
+            .identity_relay
+            .as_deref()
+            .and_then(|relay| AddressHash::new_from_hex_string(relay.trim()).ok())
+            .filter(|relay| *relay != destination_hash);
+        log_delivery_trace(&message_id, &destination_hex, "start", "delivery requested");
+        // Caps each wait at whatever is left before deliver_by.
+        let bounded =
+            |wait: std::time::Duration| options.time_left().map_or(wait, |left| wait.min(left));
+        let deadline_exceeded = |message_id: String| {
+            log_delivery_trace(&message_id, &destination_hex, "deadline", "exceeded");
+            let _ = receipt_tx.send(ReceiptEvent {
+                message_id,
+                status: DELIVERY_DEADLINE_EXCEEDED.to_string(),
+                reason_code: None,
+            });
+        };
+        let mut identity = peer_identity;
+        // Refresh routing for the destination before link setup.
+        transport.request_path(&destination_hash, None, None).await;
+        log_delivery_trace(&message_id, &destination_hex, "path-request", "requested");
+
+        // Identity lookups for cold destinations also land in the
+        // message's delivery trace.
+        let identity_step = |step: String| {
+            log_delivery_trace(&message_id, &destination_hex, "identity", &step);
+            let _ = event_tx.send(RpcEvent {
+                event_type: DELIVERY_STEP_EVENT.into(),
+                payload: serde_json::json!({
+                    "message_id": message_id,
+                    "destination": destination_hex,
+                    "step": step,
+                }),
+            });
+        };
+
+        if identity.is_none() {
+            identity_step("identity: waiting for announce".into());
+            identity = wait_for_destination_identity(
+                transport,
+                &destination_hash,
+                bounded(std::time::Duration::from_secs(12)),
+            )
+            .await;
+        }
+        // Retry the path request on the interface the relay sits behind; a
+        // transport node there may have heard the announce we missed.
+        if let (None, Some(relay), false) = (&identity, relay, options.deadline_passed()) {
+            if transport
+                .request_path_on_relay_iface(&destination_hash, &relay)
+                .await
+            {
+                identity_step(format!(
+                    "identity: retrying path request toward relay {relay}"
+                ));
+                identity = wait_for_destination_identity(
+                    transport,
+                    &destination_hash,
+                    bounded(std::time::Duration::from_secs(RELAY_IDENTITY_WAIT_SECS)),
+                )
+                .await;
+            } else {
+                identity_step(format!("identity: relay {relay} unreachable"));
+            }
+        }
+        if identity.is_some() {
+            identity_step("identity: resolved".into());
+        }
+        if options.deadline_passed() {
+            deadline_exceeded(message_id);
+            return false;
+        }
+
+        let Some(identity) = identity else {
+            log_delivery_trace(&message_id, &destination_hex, "identity", "not found");
+            let _ = receipt_tx.send(ReceiptEvent {
+                message_id,
+                status: "failed: peer not announced".to_string(),
+                reason_code: None,
+            });
+            return false;
+        };
+
+        if let Ok(mut peers) = peer_crypto.lock() {
+            peers.insert(destination_hex.clone(), PeerCrypto { identity });
+        }
+
+        let destination_desc = reticulum::destination::DestinationDesc {
+            identity,
+            address_hash: destination_hash,
+            name: DestinationName::new("lxmf", "delivery"),
+        };
+
+        let result = send_via_link(
+            transport.as_ref(),
+            destination_desc,
+            &payload,
+            bounded(std::time::Duration::from_secs(20)),
+        )
+        .await;
+        if diagnostics_enabled() {
+            let payload_starts_with_dst = payload.len() >= 16 && payload[..16] == destination[..];
+            let detail = format!(
+                "payload_len={} payload_prefix={} starts_with_dst={}",
+                payload.len(),
+                payload_preview(&payload, 16),
+                payload_starts_with_dst
+            );
+            log_delivery_trace(&message_id, &destination_hex, "payload", &detail);
+        }
+        match result {
+            Ok(packet) => {
+                let packet_hash = hex::encode(packet.hash().to_bytes());
+                track_receipt_mapping(receipt_map, &packet_hash, &message_id);
+                let detail = if diagnostics_enabled() {
+                    format!(
+                        "packet_hash={} packet_data_len={} packet_data_prefix={}",
+                        packet_hash,
+                        packet.data.len(),
+                        payload_preview(packet.data.as_slice(), 16)
+                    )
+                } else {
+                    format!("packet_hash={packet_hash}")
+                };
+                log_delivery_trace(&message_id, &destination_hex, "link", &detail);
+                let _ = receipt_tx.send(ReceiptEvent {
+                    message_id,
+                    status: "sent: link".to_string(),
+                    reason_code: None,
+                });
+                true
+            }
+            Err(_) if options.deadline_passed() => {
+                deadline_exceeded(message_id);
+                false
+            }
+            Err(err) => {
+                let err_detail = format!("failed err={err}");
+                log_delivery_trace(&message_id, &destination_hex, "link", &err_detail);
+                eprintln!(
+                    "[daemon] link delivery failed dst={} msg_id={} err={}; trying opportunistic",
+                    destination_hex, message_id, err
+                );
+                let _ = receipt_tx.send(ReceiptEvent {
+                    message_id: message_id.clone(),
+                    status: format!("link failed: {err}; trying opportunistic"),
+                    reason_code: None,
+                });
+                // Opportunistic SINGLE packets must carry LXMF wire bytes
+                // without the destination prefix. Receivers prepend the
+                // packet destination hash before unpacking.
+                let opportunistic_payload = opportunistic_payload(&payload, &destination);
+                let mut data = PacketDataBuffer::new();
+                if data.write(opportunistic_payload).is_err() {
+                    log_delivery_trace(
+                        &message_id,
+                        &destination_hex,
+                        "opportunistic",
+                        "payload too large",
+                    );
+                    let _ = receipt_tx.send(ReceiptEvent {
+                        message_id,
+                        status: format!("failed: {}", err),
+                        reason_code: None,
+                    });
+                    return false;
+                }
+
+                let packet = Packet {
+                    header: Header {
+                        ifac_flag: IfacFlag::Open,
+                        header_type: HeaderType::Type1,
+                        context_flag: ContextFlag::Unset,
+                        propagation_type: PropagationType::Broadcast,
+                        destination_type: DestinationType::Single,
+                        packet_type: PacketType::Data,
+                        hops: 0,
+                    },
+                    ifac: None,
+                    destination: destination_hash,
+                    transport: None,
+                    context: PacketContext::None,
+                    data,
+                };
+                let packet_hash = hex::encode(packet.hash().to_bytes());
+                track_receipt_mapping(receipt_map, &packet_hash, &message_id);
+                if diagnostics_enabled() {
+                    let detail = format!(
+                        "sending packet_hash={} payload_len={} payload_prefix={}",
+                        packet_hash,
+                        opportunistic_payload.len(),
+                        payload_preview(opportunistic_payload, 16)
+                    );
+                    log_delivery_trace(&message_id, &destination_hex, "opportunistic", &detail);
+                } else {
+                    log_delivery_trace(&message_id, &destination_hex, "opportunistic", "sending");
+                }
+                let trace = transport.send_packet_with_trace(packet).await;
+                let trace_detail = send_trace_detail(trace);
                 log_delivery_trace(
                     &message_id,
                     &destination_hex,
-                    "identity",
-                    "waiting for announce",
+                    "opportunistic",
+                    &trace_detail,
                 );
-                let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(12);
-                while tokio::time::Instant::now() < deadline {
-                    if let Some(found) = transport.destination_identity(&destination_hash).await {
-                        identity = Some(found);
-                        break;
+                let outcome = trace.outcome;
+                let sent = matches!(
+                    outcome,
+                    SendPacketOutcome::SentDirect | SendPacketOutcome::SentBroadcast
+                );
+                if !sent {
+                    if let Ok(mut map) = receipt_map.lock() {
+                        map.remove(&packet_hash);
+                    }
+                }
+                let _ = receipt_tx.send(ReceiptEvent {
+                    message_id,
+                    status: send_outcome_status("opportunistic", outcome),
+                    reason_code: outcome.reason_code(),
+                });
+                sent
+            }
+        }
+    }
+}
+
+impl OutboundBridge for TransportBridge {
+    fn deliver(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.deliver_packed(record, options).map(|_| ())
+    }
+
+    fn deliver_packed(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> Result<Option<Vec<u8>>, std::io::Error> {
+        let job = self.delivery_job(record, options)?;
+        let packed = job.payload.clone();
+        self.spawn_deliveries(vec![job], options);
+        Ok(Some(packed))
+    }
+
+    fn deliver_sequence(
+        &self,
+        records: &[reticulum::storage::messages::MessageRecord],
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        let jobs = records
+            .iter()
+            .map(|record| self.delivery_job(record, options))
+            .collect::<Result<Vec<_>, std::io::Error>>()?;
+        self.spawn_deliveries(jobs, options);
+        Ok(())
+    }
+
+    fn restore_peer_identity(
+        &self,
+        destination: &str,
+        identity: &Identity,
+    ) -> Result<(), std::io::Error> {
+        self.peer_crypto.lock().expect("peer map").insert(
+            destination.to_string(),
+            PeerCrypto {
+                identity: *identity,
+            },
+        );
+        Ok(())
+    }
+
+    fn pending_deliveries(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    fn has_path(&self, destination: &str) -> Option<bool> {
+        let destination = AddressHash::new_from_hex_string(destination).ok()?;
+        self.transport.try_knows_destination(&destination)
+    }
+
+    fn request_path(&self, destination: &str) -> Result<(), std::io::Error> {
+        let destination = AddressHash::new_from_hex_string(destination).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid destination hash")
+        })?;
+        let transport = self.transport.clone();
+        tokio::spawn(async move {
+            transport.request_path(&destination, None, None).await;
+        });
+        Ok(())
+    }
+
+    fn wire_size(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+        options: &reticulum::rpc::OutboundDeliveryOptions,
+    ) -> Result<Option<usize>, std::io::Error> {
+        let (_, payload) = self.build_payload(record, options)?;
+        Ok(Some(payload.len()))
+    }
+}
+
+impl AnnounceBridge for TransportBridge {
+    fn announce_now(&self) -> Result<(), std::io::Error> {
+        // The outcome still reaches subscribers as announce_failed.
+        tokio::spawn(self.announce_confirmed());
+        Ok(())
+    }
+
+    fn announce_confirmed(&self) -> AnnounceFuture {
+        let transport = self.transport.clone();
+        let event_tx = self.event_tx.clone();
+        let mut announces = vec![(
+            hex::encode(self.delivery_source_hash),
+            self.announce_destination.clone(),
+            self.announce_app_data
+                .lock()
+                .expect("announce app data")
+                .clone(),
+        )];
+        announces.extend(
+            self.extra_announces
+                .lock()
+                .expect("extra announces")
+                .iter()
+                .map(|extra| {
+                    (
+                        extra.destination_hash.clone(),
+                        extra.destination.clone(),
+                        extra.app_data.clone(),
+                    )
+                }),
+        );
+        Box::pin(async move {
+            let mut outcome = Ok(());
+            for (index, (destination_hash, destination, app_data)) in
+                announces.into_iter().enumerate()
+            {
+                let trace = transport
+                    .send_announce_with_trace(&destination, app_data.as_deref())
+                    .await;
+                if trace.dispatch.sent_ifaces == 0 {
+                    let _ = event_tx.send(RpcEvent {
+                        event_type: "announce_failed".into(),
+                        payload: serde_json::json!({
+                            "destination_hash": destination_hash,
+                            "outcome": format!("{:?}", trace.outcome),
+                            "matched_ifaces": trace.dispatch.matched_ifaces,
+                            "failed_ifaces": trace.dispatch.failed_ifaces,
+                            "error": "announce was not sent on any interface",
+                        }),
+                    });
+                    // Only the delivery destination decides the outcome.
+                    if index == 0 {
+                        outcome = Err(std::io::Error::other(
+                            "announce was not sent on any interface",
+                        ));
                     }
-                    tokio::time::sleep(std::time::Duration::from_millis(250)).await;
                 }
             }
+            outcome
+        })
+    }
+
+    fn add_announce_aspect(
+        &self,
+        app: &str,
+        aspects: &str,
+        app_data: Option<Vec<u8>>,
+    ) -> Result<String, std::io::Error> {
+        let name = format!("{app}.{aspects}");
+        let mut extras = self.extra_announces.lock().expect("extra announces");
+        if let Some(extra) = extras.iter_mut().find(|extra| extra.aspects == name) {
+            extra.app_data = app_data;
+            return Ok(extra.destination_hash.clone());
+        }
+        let destination =
+            SingleInputDestination::new(self.signer.clone(), DestinationName::new(app, aspects));
+        let destination_hash = hex::encode(destination.desc.address_hash.as_slice());
+        extras.push(ExtraAnnounce {
+            aspects: name,
+            destination_hash: destination_hash.clone(),
+            destination: Arc::new(tokio::sync::Mutex::new(destination)),
+            app_data,
+        });
+        Ok(destination_hash)
+    }
+
+    fn announce_details(&self) -> Option<AnnounceDetails> {
+        Some(AnnounceDetails {
+            destination_hash: hex::encode(self.delivery_source_hash),
+            app_data_hex: self
+                .announce_app_data
+                .lock()
+                .expect("announce app data")
+                .as_ref()
+                .map(hex::encode),
+            aspects: "lxmf.delivery".into(),
+        })
+    }
+
+    fn set_display_name(&self, display_name: &str) -> Result<String, std::io::Error> {
+        let invalid = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "display_name must be non-empty text without control characters",
+            )
+        };
+        let normalized = normalize_display_name(display_name).ok_or_else(invalid)?;
+        let app_data = encode_delivery_display_name_app_data(&normalized).ok_or_else(invalid)?;
+        *self.announce_app_data.lock().expect("announce app data") = Some(app_data);
+        Ok(normalized)
+    }
+}
 
+impl ResourceBridge for TransportBridge {
+    fn send_resource(
+        &self,
+        transfer_id: &str,
+        destination: &str,
+        data: Vec<u8>,
+        metadata: Option<Vec<u8>>,
+        path_timeout: std::time::Duration,
+    ) -> Result<(), std::io::Error> {
+        let destination_hash = AddressHash::new(parse_destination_hex_required(destination)?);
+        let known_identity = self
+            .peer_crypto
+            .lock()
+            .expect("peer map")
+            .get(destination)
+            .map(|info| info.identity);
+        let transport = self.transport.clone();
+        let event_tx = self.event_tx.clone();
+        let transfer_id = transfer_id.to_string();
+        let destination_hex = destination.to_string();
+        tokio::spawn(async move {
+            let started = tokio::time::Instant::now();
+            transport.request_path(&destination_hash, None, None).await;
+            let identity = match known_identity {
+                Some(identity) => Some(identity),
+                None => {
+                    wait_for_destination_identity(&transport, &destination_hash, path_timeout).await
+                }
+            };
+            let waited_ms = started.elapsed().as_millis() as u64;
             let Some(identity) = identity else {
-                log_delivery_trace(&message_id, &destination_hex, "identity", "not found");
-                let _ = receipt_tx.send(ReceiptEvent {
-                    message_id,
-                    status: "failed: peer not announced".to_string(),
+                let _ = event_tx.send(RpcEvent {
+                    event_type: "resource_failed".into(),
+                    payload: serde_json::json!({
+                        "transfer_id": transfer_id,
+                        "destination": destination_hex,
+                        "error": "no path to destination",
+                        "reason_code": "no_path",
+                        "waited_ms": waited_ms,
+                    }),
                 });
                 return;
             };
-            log_delivery_trace(&message_id, &destination_hex, "identity", "resolved");
-
-            if let Ok(mut peers) = peer_crypto.lock() {
-                peers.insert(destination_hex.clone(), PeerCrypto { identity });
-            }
 
             let destination_desc = reticulum::destination::DestinationDesc {
                 identity,
                 address_hash: destination_hash,
                 name: DestinationName::new("lxmf", "delivery"),
             };
-
-            let result = send_via_link(
+            let result = send_resource_via_link(
                 transport.as_ref(),
                 destination_desc,
-                &payload,
-                std::time::Duration::from_secs(20),
+                data,
+                metadata,
+                std::time::Duration::from_secs(RESOURCE_LINK_WAIT_SECS),
             )
             .await;
-            if diagnostics_enabled() {
-                let payload_starts_with_dst =
-                    payload.len() >= 16 && payload[..16] == destination[..];
-                let detail = format!(
-                    "payload_len={} payload_prefix={} starts_with_dst={}",
-                    payload.len(),
-                    payload_preview(&payload, 16),
-                    payload_starts_with_dst
-                );
-                log_delivery_trace(&message_id, &destination_hex, "payload", &detail);
-            }
-            match result {
-                Ok(packet) => {
-                    let packet_hash = hex::encode(packet.hash().to_bytes());
-                    track_receipt_mapping(&receipt_map, &packet_hash, &message_id);
-                    let detail = if diagnostics_enabled() {
-                        format!(
-                            "packet_hash={} packet_data_len={} packet_data_prefix={}",
-                            packet_hash,
-                            packet.data.len(),
-                            payload_preview(packet.data.as_slice(), 16)
-                        )
-                    } else {
-                        format!("packet_hash={packet_hash}")
-                    };
-                    log_delivery_trace(&message_id, &destination_hex, "link", &detail);
-                    let _ = receipt_tx.send(ReceiptEvent {
-                        message_id,
-                        status: "sent: link".to_string(),
-                    });
+            let event = match result {
+                Ok((resource_hash, link_id)) => RpcEvent {
+                    event_type: "resource_started".into(),
+                    payload: serde_json::json!({
+                        "transfer_id": transfer_id,
+                        "destination": destination_hex,
+                        "resource_hash": hex::encode(resource_hash.as_slice()),
+                        "link_id": hex::encode(link_id.as_slice()),
+                        "waited_ms": waited_ms,
+                    }),
+                },
+                Err(err) => RpcEvent {
+                    event_type: "resource_failed".into(),
+                    payload: serde_json::json!({
+                        "transfer_id": transfer_id,
+                        "destination": destination_hex,
+                        "error": err.to_string(),
+                    }),
+                },
+            };
+            let _ = event_tx.send(event);
+        });
+        Ok(())
+    }
+
+    fn resource_counts(&self) -> Option<ResourceCounts> {
+        Some(self.transport.resource_load().counts())
+    }
+}
+
+// Interface name to address, shared with the RPC bridges and replaced when
+// the transport is restarted.
+type InterfaceNames = Arc<std::sync::Mutex<HashMap<String, AddressHash>>>;
+type InterfaceStatusSender = tokio::sync::mpsc::UnboundedSender<(String, InterfaceStatus)>;
+
+enum InterfaceStatus {
+    Client(TcpClientStatus),
+    Server(TcpServerStatus),
+}
+
+struct TransportInterfaceFilters {
+    filters: InterfaceFilterTable,
+    names: InterfaceNames,
+}
+
+fn resolve_interface(
+    names: &HashMap<String, AddressHash>,
+    interface: &str,
+) -> Result<AddressHash, std::io::Error> {
+    if let Some(address) = names.get(interface) {
+        return Ok(*address);
+    }
+    AddressHash::new_from_hex_string(interface)
+        .ok()
+        .filter(|address| names.values().any(|known| known == address))
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("unknown interface: {interface}"),
+            )
+        })
+}
+
+impl TransportInterfaceFilters {
+    fn resolve(&self, interface: &str) -> Result<AddressHash, std::io::Error> {
+        resolve_interface(&self.names.lock().expect("interface names"), interface)
+    }
+}
+
+impl InterfaceFilterBridge for TransportInterfaceFilters {
+    fn set_interface_filter(
+        &self,
+        interface: &str,
+        filter: Option<InterfaceFilter>,
+    ) -> Result<(), std::io::Error> {
+        let address = self.resolve(interface)?;
+        self.filters.set(address, filter);
+        Ok(())
+    }
+
+    fn interface_filter(
+        &self,
+        interface: &str,
+    ) -> Result<Option<InterfaceFilterStatus>, std::io::Error> {
+        let address = self.resolve(interface)?;
+        Ok(self.filters.get(&address))
+    }
+}
+
+struct TransportDestinationInterfaces {
+    hints: DestinationInterfaceHints,
+    names: InterfaceNames,
+}
+
+impl DestinationInterfaceBridge for TransportDestinationInterfaces {
+    fn set_destination_interface(
+        &self,
+        destination: &str,
+        interface: &str,
+    ) -> Result<(), std::io::Error> {
+        let iface = resolve_interface(&self.names.lock().expect("interface names"), interface)?;
+        let destination = AddressHash::new_from_hex_string(destination).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "bad destination")
+        })?;
+        self.hints.set(destination, iface);
+        Ok(())
+    }
+
+    fn clear_destination_interface(&self, destination: &str) -> Result<bool, std::io::Error> {
+        let destination = AddressHash::new_from_hex_string(destination).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "bad destination")
+        })?;
+        Ok(self.hints.clear(&destination))
+    }
+}
+
+async fn spawn_interfaces(
+    transport: &Transport,
+    server_addr: &str,
+    config: Option<&DaemonConfig>,
+    status_tx: &InterfaceStatusSender,
+) -> HashMap<String, AddressHash> {
+    let mut names = HashMap::new();
+    let iface_manager = transport.iface_manager();
+    let server = TcpServer::new(server_addr.to_string(), iface_manager.clone());
+    let mut server_status_rx = server.subscribe_status();
+    let server_iface = iface_manager.lock().await.spawn(server, TcpServer::spawn);
+    let server_status_tx = status_tx.clone();
+    tokio::spawn(async move {
+        while server_status_rx.changed().await.is_ok() {
+            let status = server_status_rx.borrow_and_update().clone();
+            if server_status_tx
+                .send((
+                    "daemon-transport".to_string(),
+                    InterfaceStatus::Server(status),
+                ))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+    eprintln!(
+        "[daemon] tcp_server enabled iface={} bind={}",
+        server_iface, server_addr
+    );
+    names.insert("daemon-transport".to_string(), server_iface);
+    let backoff = config
+        .map(DaemonConfig::tcp_reconnect_backoff)
+        .unwrap_or_default();
+    for iface in config
+        .iter()
+        .flat_map(|config| config.enabled_tcp_clients())
+    {
+        let (Some(host), Some(port)) = (iface.host.as_ref(), iface.port) else {
+            continue;
+        };
+        let addr = format!("{}:{}", host, port);
+        let name = iface.name.clone().unwrap_or_else(|| addr.clone());
+        let client = TcpClient::new(addr)
+            .with_backoff(iface.reconnect_backoff(backoff))
+            .with_nodelay(iface.nodelay());
+        let mut status_rx = client.subscribe_status();
+        let client_iface = iface_manager.lock().await.spawn(client, TcpClient::spawn);
+        eprintln!(
+            "[daemon] tcp_client enabled iface={} name={} host={} port={}",
+            client_iface, name, host, port
+        );
+        // Forwards connection state until the interface stops.
+        let status_tx = status_tx.clone();
+        let status_name = name.clone();
+        tokio::spawn(async move {
+            loop {
+                let status = status_rx.borrow_and_update().clone();
+                if status_tx
+                    .send((status_name.clone(), InterfaceStatus::Client(status)))
+                    .is_err()
+                    || status_rx.changed().await.is_err()
+                {
+                    break;
                 }
-                Err(err) => {
-                    let err_detail = format!("failed err={err}");
-                    log_delivery_trace(&message_id, &destination_hex, "link", &err_detail);
-                    eprintln!(
-                        "[daemon] link delivery failed dst={} msg_id={} err={}; trying opportunistic",
-                        destination_hex, message_id, err
-                    );
-                    let _ = receipt_tx.send(ReceiptEvent {
-                        message_id: message_id.clone(),
-                        status: format!("link failed: {err}; trying opportunistic"),
-                    });
-                    // Opportunistic SINGLE packets must carry LXMF wire bytes
-                    // without the destination prefix. Receivers prepend the
-                    // packet destination hash before unpacking.
-                    let opportunistic_payload = opportunistic_payload(&payload, &destination);
-                    let mut data = PacketDataBuffer::new();
-                    if data.write(opportunistic_payload).is_err() {
-                        log_delivery_trace(
-                            &message_id,
-                            &destination_hex,
-                            "opportunistic",
-                            "payload too large",
-                        );
-                        let _ = receipt_tx.send(ReceiptEvent {
-                            message_id,
-                            status: format!("failed: {}", err),
+            }
+        });
+        names.insert(name, client_iface);
+    }
+    names
+}
+
+fn server_interface_record(server_addr: &str) -> Option<InterfaceRecord> {
+    let (host, port) = server_addr.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    Some(InterfaceRecord {
+        kind: "tcp_server".into(),
+        enabled: true,
+        host: Some(host.to_string()),
+        port: port.parse::<u16>().ok(),
+        name: Some("daemon-transport".into()),
+        options: Default::default(),
+    })
+}
+
+// Rebuilds the transport's interfaces in place. The Transport itself is kept so
+// every bridge holding it stays valid; only interfaces and the routing state
+// learned through them are replaced.
+struct TransportRestarter {
+    transport: Arc<Transport>,
+    server_addr: String,
+    config_path: Option<PathBuf>,
+    names: InterfaceNames,
+    announcer: Arc<TransportBridge>,
+    interface_status: InterfaceStatusSender,
+}
+
+impl TransportControlBridge for TransportRestarter {
+    fn restart_transport(&self) -> Result<Vec<InterfaceRecord>, std::io::Error> {
+        let config = self
+            .config_path
+            .as_ref()
+            .map(|path| {
+                DaemonConfig::from_path(path).map_err(|err| {
+                    std::io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+                })
+            })
+            .transpose()?;
+        let mut records = config
+            .as_ref()
+            .map(DaemonConfig::interface_records)
+            .unwrap_or_default();
+        records.extend(server_interface_record(&self.server_addr));
+
+        let transport = self.transport.clone();
+        let server_addr = self.server_addr.clone();
+        let names = self.names.clone();
+        let announcer = self.announcer.clone();
+        let interface_status = self.interface_status.clone();
+        tokio::spawn(async move {
+            transport.reinitialize().await;
+            let fresh =
+                spawn_interfaces(&transport, &server_addr, config.as_ref(), &interface_status)
+                    .await;
+            let previous =
+                std::mem::replace(&mut *names.lock().expect("interface names"), fresh.clone());
+            let filters = transport.interface_filters();
+            let hints = transport.destination_interface_hints();
+            for (name, old) in previous {
+                if let Some(new) = fresh.get(&name) {
+                    filters.remap(&old, *new);
+                    hints.remap(&old, *new);
+                }
+            }
+            eprintln!("[daemon] transport restarted ifaces={}", fresh.len());
+            if let Err(err) = announcer.announce_now() {
+                eprintln!("[daemon] post-restart announce failed: {err}");
+            }
+        });
+        Ok(records)
+    }
+}
+
+struct PaperDecoder {
+    identity: PrivateIdentity,
+    delivery_destination: [u8; 16],
+}
+
+impl PaperBridge for PaperDecoder {
+    fn decode_paper_message(
+        &self,
+        packed: &[u8],
+    ) -> Result<reticulum::storage::messages::MessageRecord, String> {
+        decode_paper_message(&self.identity, self.delivery_destination, packed)
+    }
+}
+
+struct LxmfCodec {
+    identity: PrivateIdentity,
+    delivery_destination: [u8; 16],
+}
+
+impl LxmfCodecBridge for LxmfCodec {
+    fn encode_lxmf_message(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+    ) -> Result<Vec<u8>, String> {
+        let source =
+            parse_destination_hex_required(&record.source).map_err(|err| err.to_string())?;
+        if source != self.delivery_destination {
+            return Err("only messages sent from the local delivery identity can be packed".into());
+        }
+        let destination =
+            parse_destination_hex_required(&record.destination).map_err(|err| err.to_string())?;
+        build_wire_message_at(
+            source,
+            destination,
+            &record.title,
+            &record.content,
+            record.fields.clone(),
+            &self.identity,
+            (record.timestamp > 0).then_some(record.timestamp as f64),
+        )
+        .map_err(|err| err.to_string())
+    }
+
+    fn decode_lxmf_message(
+        &self,
+        packed: &[u8],
+    ) -> Result<reticulum::storage::messages::MessageRecord, String> {
+        let (record, diagnostics) =
+            decode_inbound_payload_with_diagnostics(self.delivery_destination, packed);
+        record.ok_or_else(|| format!("decode failed: {}", diagnostics.summary()))
+    }
+}
+
+struct TransportMetricsReader(Arc<Transport>);
+
+impl TransportMetricsBridge for TransportMetricsReader {
+    fn transport_metrics(&self) -> TransportMetrics {
+        self.0.transport_metrics()
+    }
+}
+
+struct TransportInterfaceTraffic {
+    traffic: InterfaceTraffic,
+    names: InterfaceNames,
+}
+
+impl InterfaceTrafficBridge for TransportInterfaceTraffic {
+    fn interface_traffic(&self) -> Vec<(String, TrafficHistory)> {
+        let names = self.names.lock().expect("interface names").clone();
+        names
+            .into_iter()
+            .map(|(name, address)| (name, self.traffic.history(&address)))
+            .collect()
+    }
+
+    fn running_interfaces(&self) -> Vec<String> {
+        self.names
+            .lock()
+            .expect("interface names")
+            .keys()
+            .cloned()
+            .collect()
+    }
+}
+
+struct TransportBandwidth(BandwidthControl);
+
+impl BandwidthBridge for TransportBandwidth {
+    fn bandwidth_limit(&self) -> Option<u64> {
+        self.0.limit()
+    }
+
+    fn set_bandwidth_limit(&self, bytes_per_sec: Option<u64>) -> Result<(), std::io::Error> {
+        self.0.set_limit(bytes_per_sec);
+        Ok(())
+    }
+
+    fn paced_drops(&self) -> u64 {
+        self.0.paced_drops()
+    }
+}
+
+// RPC handlers run synchronously on the local set, so link state is served
+// from a snapshot refreshed by a background task and closes are queued to it.
+struct TransportLinks {
+    snapshot: Arc<std::sync::Mutex<Vec<LinkInfo>>>,
+    close_tx: tokio::sync::mpsc::UnboundedSender<AddressHash>,
+    transport: Arc<Transport>,
+    event_tx: tokio::sync::mpsc::UnboundedSender<RpcEvent>,
+}
+
+impl TransportLinks {
+    fn spawn(
+        transport: Arc<Transport>,
+        event_tx: tokio::sync::mpsc::UnboundedSender<RpcEvent>,
+    ) -> Self {
+        let snapshot = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (close_tx, mut close_rx) = unbounded_channel::<AddressHash>();
+        let task_snapshot = snapshot.clone();
+        let task_transport = transport.clone();
+        let task_event_tx = event_tx.clone();
+        tokio::spawn(async move {
+            let transport = task_transport;
+            let event_tx = task_event_tx;
+            let mut refresh = tokio::time::interval(std::time::Duration::from_secs(1));
+            loop {
+                tokio::select! {
+                    _ = refresh.tick() => {}
+                    link_id = close_rx.recv() => {
+                        let Some(link_id) = link_id else {
+                            break;
+                        };
+                        let closed = transport.close_link(&link_id).await;
+                        let _ = event_tx.send(RpcEvent {
+                            event_type: "link_closed".into(),
+                            payload: serde_json::json!({
+                                "link_id": link_id.to_hex_string(),
+                                "closed": closed,
+                            }),
                         });
-                        return;
                     }
+                }
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|value| value.as_secs() as i64)
+                    .unwrap_or(0);
+                let links = transport
+                    .link_snapshots()
+                    .await
+                    .into_iter()
+                    .map(|link| link_info(link, now))
+                    .collect();
+                *task_snapshot.lock().expect("link snapshot") = links;
+            }
+        });
+        Self {
+            snapshot,
+            close_tx,
+            transport,
+            event_tx,
+        }
+    }
+}
+
+impl LinkBridge for TransportLinks {
+    fn list_links(&self) -> Result<Vec<LinkInfo>, std::io::Error> {
+        Ok(self.snapshot.lock().expect("link snapshot").clone())
+    }
+
+    fn close_link(&self, link_id: &str) -> Result<bool, std::io::Error> {
+        let mut snapshot = self.snapshot.lock().expect("link snapshot");
+        let Some(position) = snapshot.iter().position(|link| link.link_id == link_id) else {
+            return Ok(false);
+        };
+        let address = AddressHash::new_from_hex_string(link_id).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid link_id")
+        })?;
+        self.close_tx
+            .send(address)
+            .map_err(|_| std::io::Error::other("link monitor stopped"))?;
+        snapshot.remove(position);
+        Ok(true)
+    }
 
-                    let packet = Packet {
-                        header: Header {
-                            ifac_flag: IfacFlag::Open,
-                            header_type: HeaderType::Type1,
-                            context_flag: ContextFlag::Unset,
-                            propagation_type: PropagationType::Broadcast,
-                            destination_type: DestinationType::Single,
-                            packet_type: PacketType::Data,
-                            hops: 0,
-                        },
-                        ifac: None,
-                        destination: destination_hash,
-                        transport: None,
-                        context: PacketContext::None,
-                        data,
+    fn establish_link(
+        &self,
+        destination: &str,
+        timeout: std::time::Duration,
+    ) -> Result<(), std::io::Error> {
+        let destination_hash = AddressHash::new(parse_destination_hex_required(destination)?);
+        let transport = self.transport.clone();
+        let event_tx = self.event_tx.clone();
+        let destination_hex = destination.to_string();
+        tokio::spawn(async move {
+            let started = tokio::time::Instant::now();
+            transport.request_path(&destination_hash, None, None).await;
+            let Some(identity) =
+                wait_for_destination_identity(&transport, &destination_hash, timeout).await
+            else {
+                let _ = event_tx.send(RpcEvent {
+                    event_type: "link_failed".into(),
+                    payload: serde_json::json!({
+                        "destination": destination_hex,
+                        "error": "no path to destination",
+                        "reason_code": "no_path",
+                        "waited_ms": started.elapsed().as_millis() as u64,
+                    }),
+                });
+                return;
+            };
+            let destination_desc = reticulum::destination::DestinationDesc {
+                identity,
+                address_hash: destination_hash,
+                name: DestinationName::new("lxmf", "delivery"),
+            };
+            let remaining = timeout.saturating_sub(started.elapsed());
+            let event = match wait_for_active_link(&transport, destination_desc, remaining).await {
+                Ok(link) => {
+                    // Read what is needed and release the link before taking
+                    // snapshots, which lock every link in turn.
+                    let (link_id, rtt) = {
+                        let link = link.lock().await;
+                        (*link.id(), link.rtt())
                     };
-                    let packet_hash = hex::encode(packet.hash().to_bytes());
-                    track_receipt_mapping(&receipt_map, &packet_hash, &message_id);
-                    if diagnostics_enabled() {
-                        let detail = format!(
-                            "sending packet_hash={} payload_len={} payload_prefix={}",
-                            packet_hash,
-                            opportunistic_payload.len(),
-                            payload_preview(opportunistic_payload, 16)
-                        );
-                        log_delivery_trace(&message_id, &destination_hex, "opportunistic", &detail);
-                    } else {
-                        log_delivery_trace(
-                            &message_id,
-                            &destination_hex,
-                            "opportunistic",
-                            "sending",
-                        );
-                    }
-                    let trace = transport.send_packet_with_trace(packet).await;
-                    let trace_detail = send_trace_detail(trace);
-                    log_delivery_trace(
-                        &message_id,
-                        &destination_hex,
-                        "opportunistic",
-                        &trace_detail,
-                    );
-                    let outcome = trace.outcome;
-                    if !matches!(
-                        outcome,
-                        SendPacketOutcome::SentDirect | SendPacketOutcome::SentBroadcast
-                    ) {
-                        if let Ok(mut map) = receipt_map.lock() {
-                            map.remove(&packet_hash);
-                        }
+                    let rtt_avg_ms = transport
+                        .link_snapshots()
+                        .await
+                        .into_iter()
+                        .find(|snapshot| snapshot.id == link_id)
+                        .and_then(|snapshot| snapshot.rtt_avg)
+                        .map(|rtt| rtt.as_millis() as u64);
+                    RpcEvent {
+                        event_type: "link_established".into(),
+                        payload: serde_json::json!({
+                            "destination": destination_hex,
+                            "link_id": link_id.to_hex_string(),
+                            "rtt_ms": rtt.as_millis() as u64,
+                            "rtt_avg_ms": rtt_avg_ms,
+                            "waited_ms": started.elapsed().as_millis() as u64,
+                        }),
                     }
-                    let _ = receipt_tx.send(ReceiptEvent {
-                        message_id,
-                        status: send_outcome_status("opportunistic", outcome),
-                    });
                 }
+                Err(err) => RpcEvent {
+                    event_type: "link_failed".into(),
+                    payload: serde_json::json!({
+                        "destination": destination_hex,
+                        "error": err.to_string(),
+                        "reason_code": "link_timeout",
+                        "waited_ms": started.elapsed().as_millis() as u64,
+                    }),
+                },
+            };
+            let _ = event_tx.send(event);
+        });
+        Ok(())
+    }
+}
+
+// Plain destinations we send to are also listened on, so nodes sharing a
+// beacon aspect hear each other without extra configuration.
+#[derive(Clone)]
+struct TransportPlain {
+    transport: Arc<Transport>,
+    names: Arc<std::sync::Mutex<HashMap<AddressHash, (String, String)>>>,
+}
+
+impl TransportPlain {
+    fn new(transport: Arc<Transport>) -> Self {
+        Self {
+            transport,
+            names: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    async fn listen(&self, app: &str, aspect: &str) -> AddressHash {
+        let address = self
+            .transport
+            .add_plain_destination(DestinationName::new(app, aspect))
+            .await;
+        self.names
+            .lock()
+            .expect("plain names")
+            .insert(address, (app.to_string(), aspect.to_string()));
+        address
+    }
+
+    fn received_event(&self, data: &ReceivedData) -> Option<RpcEvent> {
+        let names = self.names.lock().expect("plain names");
+        let (app, aspect) = names.get(&data.destination)?;
+        Some(RpcEvent {
+            event_type: "plain_received".into(),
+            payload: serde_json::json!({
+                "destination": data.destination.to_hex_string(),
+                "app": app,
+                "aspect": aspect,
+                "data_base64": BASE64_STANDARD.encode(data.data.as_slice()),
+                "hops": data.hops,
+            }),
+        })
+    }
+}
+
+impl PlainBridge for TransportPlain {
+    fn send_plain(&self, app: &str, aspect: &str, data: Vec<u8>) -> Result<(), std::io::Error> {
+        let plain = self.clone();
+        let app = app.to_string();
+        let aspect = aspect.to_string();
+        tokio::spawn(async move {
+            plain.listen(&app, &aspect).await;
+            match plain
+                .transport
+                .send_plain(DestinationName::new(&app, &aspect), &data)
+                .await
+            {
+                Ok(trace) => eprintln!(
+                    "[daemon] plain tx app={} aspect={} len={} {}",
+                    app,
+                    aspect,
+                    data.len(),
+                    send_trace_detail(trace)
+                ),
+                Err(err) => eprintln!(
+                    "[daemon] plain tx failed app={} aspect={} err={:?}",
+                    app, aspect, err
+                ),
             }
         });
         Ok(())
     }
-}
 
-impl AnnounceBridge for TransportBridge {
-    fn announce_now(&self) -> Result<(), std::io::Error> {
+    fn send_group(
+        &self,
+        app: &str,
+        aspect: &str,
+        key: [u8; 16],
+        data: Vec<u8>,
+    ) -> Result<(), std::io::Error> {
         let transport = self.transport.clone();
-        let destination = self.announce_destination.clone();
-        let app_data = self.announce_app_data.clone();
+        let app = app.to_string();
+        let aspect = aspect.to_string();
         tokio::spawn(async move {
-            transport
-                .send_announce(&destination, app_data.as_deref())
-                .await;
+            match transport
+                .send_group(DestinationName::new(&app, &aspect), &key, &data)
+                .await
+            {
+                Ok(trace) => eprintln!(
+                    "[daemon] group tx app={} aspect={} len={} {}",
+                    app,
+                    aspect,
+                    data.len(),
+                    send_trace_detail(trace)
+                ),
+                Err(err) => eprintln!(
+                    "[daemon] group tx failed app={} aspect={} err={:?}",
+                    app, aspect, err
+                ),
+            }
         });
         Ok(())
     }
 }
 
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+async fn save_paths(
+    daemon: &RpcDaemon,
+    transport: &Transport,
+    names: &InterfaceNames,
+    ttl_secs: u64,
+) {
+    let snapshot = transport.path_snapshot().await;
+    let records = {
+        let names = names.lock().expect("interface names");
+        path_records(&snapshot, &names, unix_now(), ttl_secs)
+    };
+    if let Err(err) = daemon.save_paths(&records) {
+        eprintln!("[daemon] save paths failed: {}", err);
+    }
+}
+
+async fn restore_paths(daemon: &RpcDaemon, transport: &Transport, names: &InterfaceNames) {
+    let records = match daemon.load_paths() {
+        Ok(records) => records,
+        Err(err) => {
+            eprintln!("[daemon] load paths failed: {}", err);
+            return;
+        }
+    };
+    let snapshots = {
+        let names = names.lock().expect("interface names");
+        path_snapshots(&records, &names, unix_now())
+    };
+    match transport.restore_paths(snapshots).await {
+        0 => {}
+        count => eprintln!("[daemon] restored {} transport paths", count),
+    }
+}
+
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                .expect("install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+fn link_info(link: LinkSnapshot, now: i64) -> LinkInfo {
+    LinkInfo {
+        link_id: link.id.to_hex_string(),
+        destination: link.destination.to_hex_string(),
+        direction: if link.outbound { "out" } else { "in" }.into(),
+        established_at: link.age.map(|age| now - age.as_secs() as i64),
+        last_activity: now - link.idle.as_secs() as i64,
+        rtt_ms: link.rtt.as_millis() as u64,
+        rtt_avg_ms: link.rtt_avg.map(|rtt| rtt.as_millis() as u64),
+        status: format!("{:?}", link.status).to_ascii_lowercase(),
+    }
+}
+
+async fn wait_for_destination_identity(
+    transport: &Transport,
+    destination_hash: &AddressHash,
+    wait: std::time::Duration,
+) -> Option<Identity> {
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        if let Some(found) = transport.destination_identity(destination_hash).await {
+            return Some(found);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+    }
+}
+
+// Decodes and stores an LXMF payload that arrived as packet data. Returns
+// whether the message was kept.
+async fn receive_inbound_data(
+    daemon: &RpcDaemon,
+    transport: &Transport,
+    event: &ReceivedData,
+    delivery_proofs: bool,
+) -> bool {
+    let data = event.data.as_slice();
+    let destination_hex = hex::encode(event.destination.as_slice());
+    if diagnostics_enabled() {
+        eprintln!(
+            "[daemon-rx] dst={} len={} ratchet_used={} data_prefix={}",
+            destination_hex,
+            data.len(),
+            event.ratchet_used,
+            payload_preview(data, 16)
+        );
+    } else {
+        eprintln!(
+            "[daemon] rx data len={} dst={}",
+            data.len(),
+            destination_hex
+        );
+    }
+    let mut destination = [0u8; 16];
+    destination.copy_from_slice(event.destination.as_slice());
+    let record = if diagnostics_enabled() {
+        let (record, diagnostics) = decode_inbound_payload_with_diagnostics(destination, data);
+        if let Some(ref decoded) = record {
+            eprintln!(
+                "[daemon-rx] decoded msg_id={} src={} dst={} title_len={} content_len={}",
+                decoded.id,
+                decoded.source,
+                decoded.destination,
+                decoded.title.len(),
+                decoded.content.len()
+            );
+        } else {
+            eprintln!(
+                "[daemon-rx] decode-failed dst={} attempts={}",
+                destination_hex,
+                diagnostics.summary()
+            );
+        }
+        record
+    } else {
+        decode_inbound_payload(destination, data)
+    };
+    let stored =
+        record.is_some_and(|record| accept_inbound_message(daemon, destination, data, record));
+    // Only what was kept is proven, so a sender whose message was dropped
+    // retries it. Pieces of a chunked attachment are held, not kept, until the
+    // whole file is reassembled and verified.
+    if let (true, true, Some(packet_hash)) = (stored, delivery_proofs, event.packet_hash) {
+        if !transport
+            .send_delivery_proof(&event.destination, &packet_hash)
+            .await
+        {
+            eprintln!(
+                "[daemon] no local destination to prove delivery dst={}",
+                destination_hex
+            );
+        }
+    }
+    stored
+}
+
+fn accept_inbound_message(
+    daemon: &RpcDaemon,
+    destination: [u8; 16],
+    data: &[u8],
+    record: reticulum::storage::messages::MessageRecord,
+) -> bool {
+    let message_id = record.id.clone();
+    let source = record.source.clone();
+    if !matches!(daemon.accept_inbound(record), Ok(InboundAcceptance::Stored)) {
+        return false;
+    }
+    if let Some(packed) = packed_inbound_message(destination, data) {
+        if let Err(err) = daemon.store_inbound_packed(&message_id, &packed) {
+            eprintln!(
+                "[daemon] keep packed message failed msg_id={} err={}",
+                message_id, err
+            );
+        }
+    }
+    let stamp_value = inbound_stamp_value(destination, data);
+    if let Err(err) = daemon.record_inbound_stamp(&message_id, &source, stamp_value) {
+        eprintln!(
+            "[daemon] record stamp failed msg_id={} err={}",
+            message_id, err
+        );
+    }
+    true
+}
+
+// Large LXMF messages arrive as resources without metadata; anything else is
+// kept in the content store for the client to fetch. Returns the fields that
+// tell the client where the data went.
+fn receive_resource_data(
+    daemon: &RpcDaemon,
+    destination: [u8; 16],
+    complete: &ResourceComplete,
+) -> Option<serde_json::Map<String, serde_json::Value>> {
+    let mut stored = serde_json::Map::new();
+    if complete.metadata.is_none() {
+        if let Some(record) = decode_inbound_payload(destination, &complete.data) {
+            stored.insert("stored_as".into(), "message".into());
+            stored.insert("message_id".into(), record.id.clone().into());
+            accept_inbound_message(daemon, destination, &complete.data, record);
+            return Some(stored);
+        }
+    }
+    match daemon.store_received_resource(&complete.data) {
+        Ok(hash) => {
+            stored.insert("stored_as".into(), "content".into());
+            stored.insert("content_hash".into(), hash.into());
+            Some(stored)
+        }
+        Err(err) => {
+            eprintln!("[daemon] store received resource failed: {err}");
+            None
+        }
+    }
+}
+
+fn resource_event_to_rpc(event: &ResourceEvent) -> RpcEvent {
+    let resource_hash = hex::encode(event.hash.as_slice());
+    let link_id = hex::encode(event.link_id.as_slice());
+    match &event.kind {
+        ResourceEventKind::Progress(progress) => RpcEvent {
+            event_type: "resource_progress".into(),
+            payload: serde_json::json!({
+                "resource_hash": resource_hash,
+                "link_id": link_id,
+                "received_bytes": progress.received_bytes,
+                "total_bytes": progress.total_bytes,
+                "received_parts": progress.received_parts,
+                "total_parts": progress.total_parts,
+                "bytes_per_sec": progress.bytes_per_sec,
+                "eta_secs": progress.eta_secs(),
+            }),
+        },
+        ResourceEventKind::OutboundProgress(progress) => RpcEvent {
+            event_type: "resource_send_progress".into(),
+            payload: serde_json::json!({
+                "resource_hash": resource_hash,
+                "link_id": link_id,
+                "sent_bytes": progress.sent_bytes,
+                "total_bytes": progress.total_bytes,
+                "sent_parts": progress.sent_parts,
+                "requested_parts": progress.requested_parts,
+                "total_parts": progress.total_parts,
+                "bytes_per_sec": progress.bytes_per_sec,
+                "eta_secs": progress.eta_secs(),
+            }),
+        },
+        ResourceEventKind::Complete(complete) => RpcEvent {
+            event_type: "resource_received".into(),
+            payload: serde_json::json!({
+                "resource_hash": resource_hash,
+                "link_id": link_id,
+                "data_len": complete.data.len(),
+                "metadata_len": complete.metadata.as_ref().map(Vec::len),
+                "metadata_hex": complete.metadata.as_ref().map(hex::encode),
+            }),
+        },
+        ResourceEventKind::OutboundComplete => RpcEvent {
+            event_type: "resource_complete".into(),
+            payload: serde_json::json!({
+                "resource_hash": resource_hash,
+                "link_id": link_id,
+            }),
+        },
+    }
+}
+
 fn parse_destination_hex(input: &str) -> Option<[u8; 16]> {
     let bytes = hex::decode(input).ok()?;
     if bytes.len() != 16 {
@@ -337,6 +1664,21 @@ fn parse_destination_hex_required(input: &str) -> Result<[u8; 16], std::io::Erro
     })
 }
 
+// A message may only be signed by the identity its source names; anything
+// else reaches the recipient with a signature it cannot verify.
+fn check_signing_source(source: &str, signer_hash: [u8; 16]) -> Result<(), std::io::Error> {
+    if parse_destination_hex(source.trim()) == Some(signer_hash) {
+        return Ok(());
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!(
+            "message source '{source}' is not the signing identity ({})",
+            hex::encode(signer_hash)
+        ),
+    ))
+}
+
 fn opportunistic_payload<'a>(payload: &'a [u8], destination: &[u8; 16]) -> &'a [u8] {
     if payload.len() > 16 && payload[..16] == destination[..] {
         &payload[16..]
@@ -377,10 +1719,15 @@ fn send_trace_detail(trace: SendPacketTrace) -> String {
         .direct_iface
         .map(|iface| iface.to_string())
         .unwrap_or_else(|| "-".to_string());
+    let hinted_iface = trace
+        .hinted_iface
+        .map(|iface| iface.to_string())
+        .unwrap_or_else(|| "-".to_string());
     format!(
-        "outcome={:?} direct_iface={} broadcast={} dispatch(matched={},sent={},failed={})",
+        "outcome={:?} direct_iface={} hinted_iface={} broadcast={} dispatch(matched={},sent={},failed={})",
         trace.outcome,
         direct_iface,
+        hinted_iface,
         trace.broadcast,
         trace.dispatch.matched_ifaces,
         trace.dispatch.sent_ifaces,
@@ -406,8 +1753,18 @@ fn send_outcome_status(method: &str, outcome: SendPacketOutcome) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{opportunistic_payload, parse_destination_hex_required, send_outcome_status};
-    use reticulum::transport::SendPacketOutcome;
+    use super::{
+        check_signing_source, opportunistic_payload, parse_destination_hex_required,
+        receive_inbound_data, receive_resource_data, send_outcome_status,
+    };
+    use rand_core::OsRng;
+    use reticulum::destination::DestinationName;
+    use reticulum::hash::AddressHash;
+    use reticulum::identity::PrivateIdentity;
+    use reticulum::packet::{PacketDataBuffer, PacketType};
+    use reticulum::resource::ResourceComplete;
+    use reticulum::rpc::{RpcDaemon, RpcRequest};
+    use reticulum::transport::{ReceivedData, SendPacketOutcome, Transport, TransportConfig};
 
     #[test]
     fn opportunistic_payload_strips_destination_prefix() {
@@ -450,11 +1807,161 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dropped_outcomes_have_distinct_reason_codes() {
+        let codes = [
+            SendPacketOutcome::DroppedMissingDestinationIdentity,
+            SendPacketOutcome::DroppedCiphertextTooLarge,
+            SendPacketOutcome::DroppedEncryptFailed,
+            SendPacketOutcome::DroppedNoRoute,
+        ]
+        .map(|outcome| outcome.reason_code().expect("reason code"));
+        let unique = codes.iter().collect::<std::collections::HashSet<_>>();
+        assert_eq!(unique.len(), codes.len());
+        assert_eq!(SendPacketOutcome::SentDirect.reason_code(), None);
+    }
+
     #[test]
     fn parse_destination_hex_required_rejects_invalid_hashes() {
         let err = parse_destination_hex_required("not-hex").expect_err("invalid hash");
         assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
     }
+
+    #[test]
+    fn messages_are_only_signed_as_their_own_source() {
+        let signer = [0x22; 16];
+        assert!(check_signing_source(&hex::encode(signer), signer).is_ok());
+        assert!(check_signing_source(&hex::encode(signer).to_uppercase(), signer).is_ok());
+        for source in ["33".repeat(16), "me".into(), String::new()] {
+            let err = check_signing_source(&source, signer).expect_err("foreign source");
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn raw_resource_is_kept_as_content() {
+        let daemon = RpcDaemon::test_instance();
+        let complete = ResourceComplete {
+            data: b"not an lxmf message".to_vec(),
+            metadata: Some(b"meta".to_vec()),
+        };
+        let stored = receive_resource_data(&daemon, [0x11; 16], &complete).unwrap();
+        assert_eq!(stored["stored_as"], "content");
+        let hash = stored["content_hash"].as_str().unwrap();
+        assert_eq!(hash.len(), 64);
+    }
+
+    // A single LXMF message to `destination`, as the transport hands it over.
+    fn inbound_data(destination: AddressHash, content: &str) -> ReceivedData {
+        let payload = rmp_serde::to_vec(&rmpv::Value::Array(vec![
+            rmpv::Value::from(1_770_000_000_i64),
+            rmpv::Value::from(""),
+            rmpv::Value::from(content),
+            rmpv::Value::Nil,
+        ]))
+        .unwrap();
+        let mut wire = destination.as_slice().to_vec();
+        wire.extend_from_slice(&[0x22; 16]);
+        wire.extend_from_slice(&[0x33; 64]);
+        wire.extend_from_slice(&payload);
+        ReceivedData {
+            destination,
+            data: PacketDataBuffer::new_from_slice(&wire),
+            ratchet_used: false,
+            context: None,
+            request_id: None,
+            hops: None,
+            interface: None,
+            packet_hash: Some([7; 32]),
+        }
+    }
+
+    #[tokio::test]
+    async fn only_stored_inbound_messages_are_proved() {
+        let identity = PrivateIdentity::new_from_rand(OsRng);
+        let mut transport = Transport::new(TransportConfig::new("test", &identity, true));
+        let mut iface = transport.iface_manager().lock().await.new_channel(8);
+        let destination = transport
+            .add_destination(identity.clone(), DestinationName::new("lxmf", "delivery"))
+            .await;
+        let address = destination.lock().await.desc.address_hash;
+        let daemon = RpcDaemon::test_instance();
+
+        let mut garbage = inbound_data(address, "hi");
+        garbage.data = PacketDataBuffer::new_from_slice(b"not an lxmf message");
+        assert!(!receive_inbound_data(&daemon, &transport, &garbage, true).await);
+        assert!(iface.tx_channel.try_recv().is_err());
+
+        let message = inbound_data(address, "hello");
+        assert!(receive_inbound_data(&daemon, &transport, &message, true).await);
+        let proof = iface.tx_channel.try_recv().expect("proof sent").packet;
+        assert_eq!(proof.header.packet_type, PacketType::Proof);
+        assert_eq!(&proof.data.as_slice()[..32], &[7; 32]);
+    }
+
+    #[tokio::test]
+    async fn disabled_delivery_proofs_still_store_messages() {
+        let identity = PrivateIdentity::new_from_rand(OsRng);
+        let mut transport = Transport::new(TransportConfig::new("test", &identity, true));
+        let mut iface = transport.iface_manager().lock().await.new_channel(8);
+        let destination = transport
+            .add_destination(identity.clone(), DestinationName::new("lxmf", "delivery"))
+            .await;
+        let address = destination.lock().await.desc.address_hash;
+        let daemon = RpcDaemon::test_instance();
+
+        let message = inbound_data(address, "quiet");
+        assert!(receive_inbound_data(&daemon, &transport, &message, false).await);
+        assert!(iface.tx_channel.try_recv().is_err());
+        let listed = daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "list_messages".into(),
+                params: None,
+            })
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(listed["messages"][0]["content"], "quiet");
+    }
+}
+
+// Frames carry no credentials, so the stream listener stays off while HTTP
+// tokens are configured.
+async fn spawn_rpc_stream_listener(
+    daemon: Rc<RpcDaemon>,
+    addr: &str,
+    request_limits: http::RequestLimits,
+) {
+    if !daemon.http_auth_tokens().is_empty() {
+        eprintln!("[daemon] rpc stream listener disabled: rpc_tokens are configured");
+        return;
+    }
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("[daemon] rpc stream bind {} failed: {}", addr, err);
+            return;
+        }
+    };
+    println!("reticulumd listening for framed rpc on tcp://{}", addr);
+    tokio::task::spawn_local(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(err) => {
+                    eprintln!("[daemon] rpc stream accept failed: {}", err);
+                    continue;
+                }
+            };
+            let daemon = daemon.clone();
+            tokio::task::spawn_local(async move {
+                if let Err(err) = http::serve_framed_stream(&daemon, stream, request_limits).await {
+                    eprintln!("[daemon] rpc stream closed: {err}");
+                }
+            });
+        }
+    });
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -464,6 +1971,11 @@ async fn main() {
         .run_until(async {
             let args = Args::parse();
             let addr: SocketAddr = args.rpc.parse().expect("invalid rpc address");
+            match apply_pending_restore(&args.db) {
+                Ok(true) => eprintln!("[daemon] restored store from staged backup"),
+                Ok(false) => {}
+                Err(err) => eprintln!("[daemon] staged store restore failed: {}", err),
+            }
             let store = MessagesStore::open(&args.db).expect("open sqlite");
 
             let identity_path = args.identity.clone().unwrap_or_else(|| {
@@ -473,9 +1985,16 @@ async fn main() {
             });
             let identity = load_or_create_identity(&identity_path).expect("load identity");
             let identity_hash = hex::encode(identity.address_hash().as_slice());
-            let local_display_name = std::env::var("LXMF_DISPLAY_NAME")
+            let local_display_name = store
+                .get_setting(SETTING_DISPLAY_NAME)
                 .ok()
-                .and_then(|value| normalize_display_name(&value));
+                .flatten()
+                .and_then(|value| value.as_str().and_then(normalize_display_name))
+                .or_else(|| {
+                    std::env::var("LXMF_DISPLAY_NAME")
+                        .ok()
+                        .and_then(|value| normalize_display_name(&value))
+                });
             let daemon_config =
                 args.config
                     .as_ref()
@@ -488,19 +2007,7 @@ async fn main() {
                     });
             let mut configured_interfaces = daemon_config
                 .as_ref()
-                .map(|config| {
-                    config
-                        .interfaces
-                        .iter()
-                        .map(|iface| InterfaceRecord {
-                            kind: iface.kind.clone(),
-                            enabled: iface.enabled.unwrap_or(false),
-                            host: iface.host.clone(),
-                            port: iface.port,
-                            name: iface.name.clone(),
-                        })
-                        .collect::<Vec<_>>()
-                })
+                .map(DaemonConfig::interface_records)
                 .unwrap_or_default();
 
             let mut transport: Option<Arc<Transport>> = None;
@@ -513,9 +2020,24 @@ async fn main() {
             let receipt_map: Arc<std::sync::Mutex<HashMap<String, String>>> =
                 Arc::new(std::sync::Mutex::new(HashMap::new()));
             let (receipt_tx, mut receipt_rx) = unbounded_channel();
+            let (event_tx, mut event_rx) = unbounded_channel::<RpcEvent>();
+            let iface_names: InterfaceNames = Arc::default();
+            let (iface_status_tx, mut iface_status_rx) = unbounded_channel();
 
             if let Some(addr) = args.transport.clone() {
-                let config = TransportConfig::new("daemon", &identity, true);
+                let mut config = TransportConfig::new("daemon", &identity, true);
+                if let Some(secs) = daemon_config
+                    .as_ref()
+                    .and_then(|config| config.announce_dedup_window_secs)
+                {
+                    config.set_announce_dedup_window_secs(secs);
+                }
+                if let Some(limit) = daemon_config
+                    .as_ref()
+                    .and_then(|config| config.resource_max_concurrent_transfers)
+                {
+                    config.set_resource_max_concurrent_transfers(limit);
+                }
                 let mut transport_instance = Transport::new(config);
                 transport_instance
                     .set_receipt_handler(Box::new(ReceiptBridge::new(
@@ -523,35 +2045,15 @@ async fn main() {
                         receipt_tx.clone(),
                     )))
                     .await;
-                let iface_manager = transport_instance.iface_manager();
-                let server_iface = iface_manager.lock().await.spawn(
-                    TcpServer::new(addr.clone(), iface_manager.clone()),
-                    TcpServer::spawn,
-                );
-                eprintln!("[daemon] tcp_server enabled iface={} bind={}", server_iface, addr);
-                if let Some(config) = daemon_config.as_ref() {
-                    for (host, port) in config.tcp_client_endpoints() {
-                        let addr = format!("{}:{}", host, port);
-                        let client_iface = iface_manager
-                            .lock()
-                            .await
-                            .spawn(TcpClient::new(addr), TcpClient::spawn);
-                        eprintln!(
-                            "[daemon] tcp_client enabled iface={} name={} host={} port={}",
-                            client_iface, host, host, port
-                        );
-                    }
-                }
+                *iface_names.lock().expect("interface names") = spawn_interfaces(
+                    &transport_instance,
+                    &addr,
+                    daemon_config.as_ref(),
+                    &iface_status_tx,
+                )
+                .await;
                 eprintln!("[daemon] transport enabled");
-                if let Some((host, port)) = addr.rsplit_once(':') {
-                    configured_interfaces.push(InterfaceRecord {
-                        kind: "tcp_server".into(),
-                        enabled: true,
-                        host: Some(host.to_string()),
-                        port: port.parse::<u16>().ok(),
-                        name: Some("daemon-transport".into()),
-                    });
-                }
+                configured_interfaces.extend(server_interface_record(&addr));
 
                 let destination = transport_instance
                     .add_destination(identity.clone(), DestinationName::new("lxmf", "delivery"))
@@ -585,6 +2087,7 @@ async fn main() {
                         peer_crypto.clone(),
                         receipt_map.clone(),
                         receipt_tx.clone(),
+                        event_tx.clone(),
                     ))
                 });
 
@@ -595,19 +2098,208 @@ async fn main() {
                 .as_ref()
                 .map(|bridge| bridge.clone() as Arc<dyn AnnounceBridge>);
 
-            let daemon = Rc::new(RpcDaemon::with_store_and_bridges(
+            let mut daemon = RpcDaemon::with_store_and_bridges(
                 store,
                 identity_hash,
                 outbound_bridge,
                 announce_bridge,
-            ));
+            );
+            let mut paper_destination = [0u8; 16];
+            paper_destination.copy_from_slice(
+                lxmf_delivery_hash_from_identity_hash(identity.address_hash()).as_slice(),
+            );
+            daemon = daemon.with_paper_bridge(Arc::new(PaperDecoder {
+                identity: identity.clone(),
+                delivery_destination: paper_destination,
+            }));
+            daemon = daemon.with_lxmf_codec_bridge(Arc::new(LxmfCodec {
+                identity: identity.clone(),
+                delivery_destination: paper_destination,
+            }));
+            daemon = daemon.with_config_bridge(Arc::new(DaemonConfigValidator));
+            if let Some(bridge) = bridge.as_ref() {
+                daemon = daemon.with_resource_bridge(bridge.clone() as Arc<dyn ResourceBridge>);
+            }
+            if let Some(transport) = transport.as_ref() {
+                daemon = daemon.with_destination_interface_bridge(Arc::new(
+                    TransportDestinationInterfaces {
+                        hints: transport.destination_interface_hints(),
+                        names: iface_names.clone(),
+                    },
+                ));
+                daemon = daemon.with_interface_filter_bridge(Arc::new(TransportInterfaceFilters {
+                    filters: transport.interface_filters(),
+                    names: iface_names.clone(),
+                }));
+                let bandwidth = transport.bandwidth_control();
+                if let Some(limit) = daemon_config
+                    .as_ref()
+                    .and_then(|config| config.bandwidth_limit_bytes_per_sec)
+                {
+                    bandwidth.set_limit(Some(limit));
+                    eprintln!("[daemon] bandwidth limit {} bytes/s", limit);
+                }
+                daemon = daemon.with_bandwidth_bridge(Arc::new(TransportBandwidth(bandwidth)));
+                daemon = daemon.with_transport_metrics_bridge(Arc::new(TransportMetricsReader(
+                    transport.clone(),
+                )));
+                daemon =
+                    daemon.with_interface_traffic_bridge(Arc::new(TransportInterfaceTraffic {
+                        traffic: transport.interface_traffic(),
+                        names: iface_names.clone(),
+                    }));
+                daemon = daemon.with_link_bridge(Arc::new(TransportLinks::spawn(
+                    transport.clone(),
+                    event_tx.clone(),
+                )));
+                if let (Some(bridge), Some(addr)) = (bridge.as_ref(), args.transport.clone()) {
+                    daemon = daemon.with_transport_control_bridge(Arc::new(TransportRestarter {
+                        transport: transport.clone(),
+                        server_addr: addr,
+                        config_path: args.config.clone(),
+                        names: iface_names.clone(),
+                        announcer: bridge.clone(),
+                        interface_status: iface_status_tx.clone(),
+                    }));
+                }
+            }
+            let plain = transport.clone().map(TransportPlain::new);
+            if let Some(plain) = plain.as_ref() {
+                for entry in daemon_config
+                    .iter()
+                    .flat_map(|config| config.plain_destinations.iter())
+                {
+                    let address = plain.listen(&entry.app, &entry.aspect).await;
+                    eprintln!(
+                        "[daemon] listening on plain destination {}.{} dst={}",
+                        entry.app, entry.aspect, address
+                    );
+                }
+                daemon = daemon.with_plain_bridge(Arc::new(plain.clone()));
+            }
+            daemon.set_local_identity(identity.clone());
+            if daemon_config
+                .as_ref()
+                .is_some_and(|config| config.sign_events)
+            {
+                daemon.set_sign_events(true);
+                eprintln!("[daemon] signing events served on /events");
+            }
+            let daemon = Rc::new(daemon);
+            match daemon.restore_known_identities() {
+                Ok(0) => {}
+                Ok(count) => eprintln!("[daemon] restored {} known peer identities", count),
+                Err(err) => eprintln!("[daemon] restore known identities failed: {}", err),
+            }
+            // Zero turns resuming off.
+            let resume_max_age_secs = daemon_config
+                .as_ref()
+                .and_then(|config| config.outbound_resume_max_age_secs)
+                .unwrap_or(DEFAULT_OUTBOUND_RESUME_MAX_AGE_SECS);
+            if resume_max_age_secs > 0 {
+                match daemon.resume_pending_outbound(resume_max_age_secs) {
+                    Ok(resumed) if resumed.is_empty() => {}
+                    Ok(resumed) => {
+                        eprintln!(
+                            "[daemon] resumed {} pending outbound messages",
+                            resumed.len()
+                        )
+                    }
+                    Err(err) => eprintln!("[daemon] resume pending outbound failed: {}", err),
+                }
+            }
             daemon.set_delivery_destination_hash(delivery_destination_hash_hex);
             daemon.replace_interfaces(configured_interfaces);
+            if let Some(config) = daemon_config.as_ref() {
+                daemon.set_http_auth_tokens(
+                    config
+                        .rpc_tokens
+                        .iter()
+                        .map(|entry| RpcAuthToken {
+                            label: entry.label.clone(),
+                            token: entry.token.clone(),
+                        })
+                        .collect(),
+                );
+                if !config.rpc_tokens.is_empty() {
+                    eprintln!(
+                        "[daemon] rpc token auth enabled tokens={}",
+                        config.rpc_tokens.len()
+                    );
+                }
+                daemon.set_http_cors_origins(config.rpc_cors_origins.clone());
+                if !config.rpc_cors_origins.is_empty() {
+                    eprintln!(
+                        "[daemon] rpc cors origins={}",
+                        config.rpc_cors_origins.join(",")
+                    );
+                }
+            }
+            if let Some(config) = daemon_config.as_ref() {
+                daemon.set_store_backup_policy(BackupPolicy {
+                    dir: config.store_backup_dir.clone(),
+                    keep: config.store_backup_keep.unwrap_or(DEFAULT_BACKUP_KEEP),
+                });
+                if let Some(max_peers) = config.max_peers {
+                    daemon.set_max_peers(max_peers);
+                }
+                daemon.set_presence_thresholds(config.presence_thresholds());
+                let defaults = PropagationStoreLimits::default();
+                daemon.set_propagation_store_limits(PropagationStoreLimits {
+                    max_bytes: config
+                        .propagation_store_max_bytes
+                        .unwrap_or(defaults.max_bytes),
+                    max_messages: config
+                        .propagation_store_max_messages
+                        .unwrap_or(defaults.max_messages),
+                    max_age_secs: config
+                        .propagation_store_max_age_secs
+                        .unwrap_or(defaults.max_age_secs),
+                });
+                let defaults = ReceivedResourceLimits::default();
+                daemon.set_received_resource_limits(ReceivedResourceLimits {
+                    max_bytes: config
+                        .received_resource_max_bytes
+                        .unwrap_or(defaults.max_bytes),
+                    max_items: config
+                        .received_resource_max_items
+                        .unwrap_or(defaults.max_items),
+                    max_age_secs: config
+                        .received_resource_max_age_secs
+                        .unwrap_or(defaults.max_age_secs),
+                });
+                // Values set in the config file override the persisted policy;
+                // anything left out keeps what was stored.
+                if config.inbound_overload_window_secs.is_some()
+                    || config.inbound_max_writes_per_window.is_some()
+                    || config.inbound_max_queued_events.is_some()
+                {
+                    let current = daemon.inbound_overload_policy();
+                    let policy = InboundOverloadPolicy {
+                        window_secs: config
+                            .inbound_overload_window_secs
+                            .unwrap_or(current.window_secs),
+                        max_writes_per_window: config
+                            .inbound_max_writes_per_window
+                            .unwrap_or(current.max_writes_per_window),
+                        max_queued_events: config
+                            .inbound_max_queued_events
+                            .unwrap_or(current.max_queued_events),
+                    };
+                    if let Err(err) = daemon.set_inbound_overload_policy(policy) {
+                        eprintln!("[daemon] failed to persist inbound overload policy: {err}");
+                    }
+                }
+            }
             daemon.set_propagation_state(transport.is_some(), None, 0);
+            daemon.set_require_hash_addresses(transport.is_some());
+            daemon.set_require_signed_announces(transport.is_some());
 
             // Make the local delivery destination visible on startup.
             if let Some(bridge) = bridge.as_ref() {
-                let _ = bridge.announce_now();
+                if let Err(err) = bridge.announce_now() {
+                    eprintln!("[daemon] startup announce failed: {err}");
+                }
             }
 
             if transport.is_some() {
@@ -629,60 +2321,211 @@ async fn main() {
                 });
             }
 
-            if args.announce_interval_secs > 0 {
-                let _handle = daemon
-                    .clone()
-                    .start_announce_scheduler(args.announce_interval_secs);
+            let daemon_events = daemon.clone();
+            tokio::task::spawn_local(async move {
+                while let Some(event) = event_rx.recv().await {
+                    if event.event_type == DELIVERY_STEP_EVENT {
+                        let message_id = event.payload["message_id"].as_str();
+                        let step = event.payload["step"].as_str();
+                        if let (Some(message_id), Some(step)) = (message_id, step) {
+                            daemon_events.record_delivery_step(message_id, step);
+                        }
+                    }
+                    daemon_events.emit_event(event);
+                }
+            });
+
+            if let Some(interval_secs) = daemon_config
+                .as_ref()
+                .and_then(|config| config.store_backup_interval_secs)
+                .filter(|secs| *secs > 0)
+            {
+                let daemon_backups = daemon.clone();
+                tokio::task::spawn_local(async move {
+                    let mut interval =
+                        tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+                    interval.tick().await;
+                    loop {
+                        interval.tick().await;
+                        match daemon_backups.backup_store(None) {
+                            Ok(record) => eprintln!(
+                                "[daemon] store backup path={} size={} pruned={}",
+                                record.path,
+                                record.size_bytes,
+                                record.pruned.len()
+                            ),
+                            Err(err) => eprintln!("[daemon] store backup failed: {}", err),
+                        }
+                    }
+                });
+            }
+
+            let presence_sweep_interval_secs = daemon_config
+                .as_ref()
+                .and_then(|config| config.presence_sweep_interval_secs)
+                .unwrap_or(DEFAULT_PRESENCE_SWEEP_INTERVAL_SECS);
+            if presence_sweep_interval_secs > 0 {
+                let daemon_presence = daemon.clone();
+                tokio::task::spawn_local(async move {
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                        presence_sweep_interval_secs,
+                    ));
+                    loop {
+                        interval.tick().await;
+                        if let Err(err) = daemon_presence.sweep_peer_presence() {
+                            eprintln!("[daemon] presence sweep failed: {}", err);
+                        }
+                    }
+                });
+            }
+
+            let propagation_prune_interval_secs = daemon_config
+                .as_ref()
+                .and_then(|config| config.propagation_prune_interval_secs)
+                .unwrap_or(DEFAULT_PROPAGATION_PRUNE_INTERVAL_SECS);
+            if propagation_prune_interval_secs > 0 {
+                let daemon_propagation = daemon.clone();
+                tokio::task::spawn_local(async move {
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                        propagation_prune_interval_secs,
+                    ));
+                    loop {
+                        interval.tick().await;
+                        if let Err(err) = daemon_propagation.prune_propagation_store() {
+                            eprintln!("[daemon] propagation prune failed: {}", err);
+                        }
+                    }
+                });
+            }
+
+            let path_ttl_secs = daemon_config
+                .as_ref()
+                .and_then(|config| config.path_ttl_secs)
+                .unwrap_or(DEFAULT_PATH_TTL_SECS);
+            if let Some(transport) = transport.as_ref() {
+                restore_paths(&daemon, transport, &iface_names).await;
+            }
+            // Zero saves only on shutdown.
+            let path_persist_interval_secs = daemon_config
+                .as_ref()
+                .and_then(|config| config.path_persist_interval_secs)
+                .unwrap_or(DEFAULT_PATH_PERSIST_INTERVAL_SECS);
+            if let Some(transport) = transport.clone().filter(|_| path_persist_interval_secs > 0) {
+                let daemon_paths = daemon.clone();
+                let names = iface_names.clone();
+                tokio::task::spawn_local(async move {
+                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                        path_persist_interval_secs,
+                    ));
+                    interval.tick().await;
+                    loop {
+                        interval.tick().await;
+                        save_paths(&daemon_paths, &transport, &names, path_ttl_secs).await;
+                    }
+                });
+            }
+
+            // Started even at zero so set_setting can turn announcing on.
+            let _handle = daemon
+                .clone()
+                .start_announce_scheduler(args.announce_interval_secs);
+            match daemon.apply_persisted_runtime_settings() {
+                Ok(applied) if applied.is_empty() => {}
+                Ok(applied) => {
+                    eprintln!("[daemon] applied stored settings: {}", applied.join(", "))
+                }
+                Err(err) => eprintln!("[daemon] reading stored settings failed: {}", err),
             }
 
             if let Some(transport) = transport.clone() {
                 let daemon_inbound = daemon.clone();
                 let inbound_transport = transport.clone();
+                let inbound_plain = plain.clone();
+                let delivery_proofs = daemon_config
+                    .as_ref()
+                    .and_then(|config| config.delivery_proofs)
+                    .unwrap_or(true);
+                if !delivery_proofs {
+                    eprintln!("[daemon] delivery proofs disabled");
+                }
                 tokio::task::spawn_local(async move {
                     let mut rx = inbound_transport.received_data_events();
                     loop {
                         if let Ok(event) = rx.recv().await {
-                            let data = event.data.as_slice();
-                            let destination_hex = hex::encode(event.destination.as_slice());
-                            if diagnostics_enabled() {
-                                eprintln!(
-                                    "[daemon-rx] dst={} len={} ratchet_used={} data_prefix={}",
-                                    destination_hex,
-                                    data.len(),
-                                    event.ratchet_used,
-                                    payload_preview(data, 16)
-                                );
-                            } else {
-                                eprintln!("[daemon] rx data len={} dst={}", data.len(), destination_hex);
+                            if let Some(plain_event) = inbound_plain
+                                .as_ref()
+                                .and_then(|plain| plain.received_event(&event))
+                            {
+                                daemon_inbound.emit_event(plain_event);
+                                continue;
                             }
-                            let mut destination = [0u8; 16];
-                            destination.copy_from_slice(event.destination.as_slice());
-                            let record = if diagnostics_enabled() {
-                                let (record, diagnostics) =
-                                    decode_inbound_payload_with_diagnostics(destination, data);
-                                if let Some(ref decoded) = record {
-                                    eprintln!(
-                                        "[daemon-rx] decoded msg_id={} src={} dst={} title_len={} content_len={}",
-                                        decoded.id,
-                                        decoded.source,
-                                        decoded.destination,
-                                        decoded.title.len(),
-                                        decoded.content.len()
-                                    );
-                                } else {
-                                    eprintln!(
-                                        "[daemon-rx] decode-failed dst={} attempts={}",
-                                        destination_hex,
-                                        diagnostics.summary()
-                                    );
+                            receive_inbound_data(
+                                &daemon_inbound,
+                                &inbound_transport,
+                                &event,
+                                delivery_proofs,
+                            )
+                            .await;
+                        }
+                    }
+                });
+
+                let daemon_interfaces = daemon.clone();
+                tokio::task::spawn_local(async move {
+                    while let Some((name, status)) = iface_status_rx.recv().await {
+                        match status {
+                            InterfaceStatus::Client(status) => daemon_interfaces
+                                .record_interface_state(
+                                    &name,
+                                    status.state.as_str(),
+                                    status.reconnect_attempts,
+                                    status.retry_in,
+                                ),
+                            InterfaceStatus::Server(status) => daemon_interfaces
+                                .record_interface_binding(
+                                    &name,
+                                    status.bound.map(|addr| addr.to_string()),
+                                    status.error,
+                                ),
+                        }
+                    }
+                });
+
+                let daemon_resources = daemon.clone();
+                let resource_transport = transport.clone();
+                tokio::task::spawn_local(async move {
+                    let mut rx = resource_transport.resource_events();
+                    loop {
+                        match rx.recv().await {
+                            Ok(event) => {
+                                let mut rpc_event = resource_event_to_rpc(&event);
+                                if let ResourceEventKind::Complete(complete) = &event.kind {
+                                    let claimed = daemon_resources
+                                        .accept_content_resource(
+                                            &complete.data,
+                                            complete.metadata.as_deref(),
+                                        )
+                                        .unwrap_or_else(|err| {
+                                            eprintln!("[daemon] content transfer failed: {err}");
+                                            true
+                                        });
+                                    if !claimed {
+                                        let stored = receive_resource_data(
+                                            &daemon_resources,
+                                            delivery_source_hash,
+                                            complete,
+                                        );
+                                        if let (Some(payload), Some(stored)) =
+                                            (rpc_event.payload.as_object_mut(), stored)
+                                        {
+                                            payload.extend(stored);
+                                        }
+                                    }
                                 }
-                                record
-                            } else {
-                                decode_inbound_payload(destination, data)
-                            };
-                            if let Some(record) = record {
-                                let _ = daemon_inbound.accept_inbound(record);
+                                daemon_resources.emit_event(rpc_event);
                             }
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                         }
                     }
                 });
@@ -690,6 +2533,7 @@ async fn main() {
                 let daemon_announce = daemon.clone();
                 let peer_crypto = peer_crypto.clone();
                 let announce_transport = transport.clone();
+                let announce_names = iface_names.clone();
                 tokio::task::spawn_local(async move {
                     let mut rx = announce_transport.recv_announces().await;
                     loop {
@@ -702,10 +2546,25 @@ async fn main() {
                                     .map(|(name, source)| (Some(name), Some(source.to_string())))
                                     .unwrap_or((None, None));
                             let _ratchet = event.ratchet;
+                            let quality = event.quality;
                             peer_crypto
                                 .lock()
                                 .expect("peer map")
                                 .insert(peer.clone(), PeerCrypto { identity });
+                            // Only lxmf.delivery announces bind a destination the
+                            // store will accept; other aspects share the identity.
+                            if lxmf_delivery_hash_from_identity_hash(&identity.address_hash)
+                                == dest.desc.address_hash
+                            {
+                                if let Err(err) = daemon_announce
+                                    .store_peer_identity(&peer, &identity.to_hex_string())
+                                {
+                                    eprintln!(
+                                        "[daemon] persist identity failed peer={} err={}",
+                                        peer, err
+                                    );
+                                }
+                            }
                             if let Some(name) = peer_name.as_ref() {
                                 eprintln!("[daemon] rx announce peer={} name={}", peer, name);
                             } else {
@@ -715,23 +2574,78 @@ async fn main() {
                                 .duration_since(std::time::UNIX_EPOCH)
                                 .map(|value| value.as_secs() as i64)
                                 .unwrap_or(0);
-                            let _ = daemon_announce.accept_announce_with_details(
+                            let app_data_hex = (!event.app_data.as_slice().is_empty())
+                                .then(|| hex::encode(event.app_data.as_slice()));
+                            // Unnamed interfaces are reported by address.
+                            let interface = announce_names
+                                .lock()
+                                .expect("interface names")
+                                .iter()
+                                .find(|(_, address)| {
+                                    address.as_slice() == event.interface.as_slice()
+                                })
+                                .map(|(name, _)| name.clone())
+                                .or_else(|| {
+                                    (!event.interface.is_empty())
+                                        .then(|| hex::encode(&event.interface))
+                                });
+                            daemon_announce.resolve_path_request(&peer);
+                            let _ = daemon_announce.accept_announce_with_metadata(
                                 peer,
                                 timestamp,
                                 peer_name,
                                 peer_name_source,
+                                app_data_hex,
+                                None,
+                                quality.and_then(|quality| quality.rssi),
+                                quality.and_then(|quality| quality.snr),
+                                quality.and_then(|quality| quality.q),
+                                None,
+                                None,
+                                None,
+                                None,
+                                Some(u32::from(event.hops)),
+                                interface,
+                                None,
+                                None,
+                                None,
+                                Some(identity.to_hex_string()),
                             );
                         }
                     }
                 });
             }
 
+            let mut request_limits = http::RequestLimits::default();
+            if let Some(max_body_bytes) = daemon_config
+                .as_ref()
+                .and_then(|config| config.rpc_max_body_bytes)
+            {
+                request_limits.max_body_bytes = max_body_bytes;
+            }
+
+            if let Some(stream_addr) = args.rpc_stream.as_deref() {
+                spawn_rpc_stream_listener(daemon.clone(), stream_addr, request_limits).await;
+            }
+
             let listener = TcpListener::bind(addr).await.unwrap();
             println!("reticulumd listening on http://{}", addr);
 
+            let shutdown = shutdown_signal();
+            tokio::pin!(shutdown);
             loop {
-                let (mut stream, _) = listener.accept().await.unwrap();
+                let (mut stream, _) = tokio::select! {
+                    accepted = listener.accept() => accepted.unwrap(),
+                    _ = &mut shutdown => {
+                        eprintln!("[daemon] shutting down");
+                        if let Some(transport) = transport.as_ref() {
+                            save_paths(&daemon, transport, &iface_names, path_ttl_secs).await;
+                        }
+                        break;
+                    }
+                };
                 let mut buffer = Vec::new();
+                let mut rejected = None;
                 loop {
                     let mut chunk = [0u8; 4096];
                     let read = stream.read(&mut chunk).await.unwrap();
@@ -739,28 +2653,63 @@ async fn main() {
                         break;
                     }
                     buffer.extend_from_slice(&chunk[..read]);
-                    if let Some(header_end) = http::find_header_end(&buffer) {
-                        let headers = &buffer[..header_end];
-                        if let Some(length) = http::parse_content_length(headers) {
-                            let body_start = header_end + 4;
-                            if buffer.len() >= body_start + length {
-                                break;
-                            }
-                        } else {
+                    match http::check_request_progress(&buffer, &request_limits) {
+                        http::RequestProgress::Incomplete => {}
+                        http::RequestProgress::Complete => break,
+                        http::RequestProgress::TooLarge(message) => {
+                            rejected = Some(message);
                             break;
                         }
                     }
                 }
 
+                if let Some(message) = rejected {
+                    eprintln!("[daemon] rpc http rejected: {message}");
+                    let _ = stream
+                        .write_all(&http::build_payload_too_large_response(message))
+                        .await;
+                    let _ = stream.shutdown().await;
+                    continue;
+                }
+
                 if buffer.is_empty() {
                     continue;
                 }
 
-                let response = http::handle_http_request(&daemon, &buffer).unwrap_or_else(|err| {
-                    http::build_error_response(&format!("rpc error: {}", err))
+                if http::is_websocket_upgrade(&buffer) {
+                    match http::websocket_handshake(&daemon, &buffer) {
+                        Ok(response) => {
+                            if stream.write_all(&response).await.is_ok() {
+                                let daemon = daemon.clone();
+                                tokio::task::spawn_local(async move {
+                                    if let Err(err) =
+                                        http::serve_websocket(&daemon, stream, request_limits).await
+                                    {
+                                        eprintln!("[daemon] rpc websocket closed: {err}");
+                                    }
+                                });
+                            }
+                        }
+                        Err(response) => {
+                            let _ = stream.write_all(&response).await;
+                            let _ = stream.shutdown().await;
+                        }
+                    }
+                    continue;
+                }
+
+                // Answered on its own task so a request that waits (request_path
+                // with wait_ms) does not hold up the accept loop.
+                let daemon = daemon.clone();
+                tokio::task::spawn_local(async move {
+                    let response = http::handle_http_request(&daemon, &buffer)
+                        .await
+                        .unwrap_or_else(|err| {
+                            http::build_error_response(&format!("rpc error: {}", err))
+                        });
+                    let _ = stream.write_all(&response).await;
+                    let _ = stream.shutdown().await;
                 });
-                let _ = stream.write_all(&response).await;
-                let _ = stream.shutdown().await;
             }
         })
         .await;
@@ -1,11 +1,81 @@
+use reticulum::iface::tcp_client::ReconnectBackoff;
+use reticulum::rpc::{
+    CheckedConfig, ConfigBridge, ConfigSource, InterfaceRecord, PresenceThresholds,
+};
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct DaemonConfig {
     #[serde(default)]
     pub interfaces: Vec<InterfaceConfig>,
+    #[serde(default)]
+    pub rpc_tokens: Vec<RpcTokenConfig>,
+    #[serde(default)]
+    pub rpc_max_body_bytes: Option<usize>,
+    #[serde(default)]
+    pub rpc_cors_origins: Vec<String>,
+    #[serde(default)]
+    pub plain_destinations: Vec<PlainDestinationConfig>,
+    #[serde(default)]
+    pub store_backup_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub store_backup_keep: Option<usize>,
+    #[serde(default)]
+    pub store_backup_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub bandwidth_limit_bytes_per_sec: Option<u64>,
+    #[serde(default)]
+    pub outbound_resume_max_age_secs: Option<u64>,
+    #[serde(default)]
+    pub inbound_overload_window_secs: Option<u64>,
+    #[serde(default)]
+    pub inbound_max_writes_per_window: Option<u64>,
+    #[serde(default)]
+    pub inbound_max_queued_events: Option<usize>,
+    #[serde(default)]
+    pub sign_events: bool,
+    // Proving received messages lets senders mark them delivered; turn off
+    // for nodes that shouldn't confirm they exist. On when unset.
+    #[serde(default)]
+    pub delivery_proofs: Option<bool>,
+    #[serde(default)]
+    pub tcp_reconnect_initial_secs: Option<u64>,
+    #[serde(default)]
+    pub tcp_reconnect_max_secs: Option<u64>,
+    #[serde(default)]
+    pub max_peers: Option<usize>,
+    #[serde(default)]
+    pub announce_dedup_window_secs: Option<u64>,
+    #[serde(default)]
+    pub resource_max_concurrent_transfers: Option<usize>,
+    #[serde(default)]
+    pub path_ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub path_persist_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub presence_online_secs: Option<u64>,
+    #[serde(default)]
+    pub presence_stale_secs: Option<u64>,
+    #[serde(default)]
+    pub presence_sweep_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub propagation_store_max_bytes: Option<u64>,
+    #[serde(default)]
+    pub propagation_store_max_messages: Option<u64>,
+    #[serde(default)]
+    pub propagation_store_max_age_secs: Option<u64>,
+    #[serde(default)]
+    pub propagation_prune_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub received_resource_max_bytes: Option<u64>,
+    #[serde(default)]
+    pub received_resource_max_items: Option<u64>,
+    #[serde(default)]
+    pub received_resource_max_age_secs: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -16,6 +86,52 @@ pub struct InterfaceConfig {
     pub host: Option<String>,
     pub port: Option<u16>,
     pub name: Option<String>,
+    #[serde(default)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+impl InterfaceConfig {
+    // Per-interface reconnect delays override the daemon-wide ones.
+    pub fn reconnect_backoff(&self, defaults: ReconnectBackoff) -> ReconnectBackoff {
+        let secs = |name: &str| {
+            self.options
+                .get(name)
+                .and_then(serde_json::Value::as_u64)
+                .map(Duration::from_secs)
+        };
+        ReconnectBackoff {
+            initial: secs("reconnect_initial_secs").unwrap_or(defaults.initial),
+            max: secs("reconnect_max_secs").unwrap_or(defaults.max),
+        }
+    }
+
+    pub fn nodelay(&self) -> bool {
+        self.options
+            .get("nodelay")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RpcTokenConfig {
+    pub label: String,
+    pub token: String,
+}
+
+impl std::fmt::Debug for RpcTokenConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcTokenConfig")
+            .field("label", &self.label)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlainDestinationConfig {
+    pub app: String,
+    pub aspect: String,
 }
 
 impl DaemonConfig {
@@ -29,6 +145,109 @@ impl DaemonConfig {
             .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
     }
 
+    pub fn from_json(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    pub fn interface_records(&self) -> Vec<InterfaceRecord> {
+        self.interfaces
+            .iter()
+            .map(|iface| InterfaceRecord {
+                kind: iface.kind.clone(),
+                enabled: iface.enabled.unwrap_or(false),
+                host: iface.host.clone(),
+                port: iface.port,
+                name: iface.name.clone(),
+                options: iface.options.clone(),
+            })
+            .collect()
+    }
+
+    // Returns (errors, warnings) for settings outside the interface list, which
+    // the RPC layer validates the same way as set_interfaces.
+    pub fn check(&self) -> (Vec<String>, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        for (index, iface) in self.interfaces.iter().enumerate() {
+            if iface.enabled.unwrap_or(false) && iface.kind != "tcp_client" {
+                warnings.push(format!(
+                    "interfaces[{index}]: reticulumd does not start \"{}\" interfaces from config",
+                    iface.kind
+                ));
+            }
+        }
+        let mut labels = HashSet::new();
+        for (index, token) in self.rpc_tokens.iter().enumerate() {
+            if token.label.trim().is_empty() || token.token.trim().is_empty() {
+                errors.push(format!("rpc_tokens[{index}]: label and token are required"));
+            }
+            if !labels.insert(token.label.trim()) {
+                warnings.push(format!(
+                    "rpc_tokens[{index}]: label \"{}\" is used more than once",
+                    token.label
+                ));
+            }
+        }
+        if self.rpc_max_body_bytes == Some(0) {
+            errors.push("rpc_max_body_bytes must be greater than zero".into());
+        }
+        for (index, origin) in self.rpc_cors_origins.iter().enumerate() {
+            let origin = origin.trim();
+            if origin == "*" {
+                if self.rpc_tokens.is_empty() {
+                    warnings.push(format!(
+                        "rpc_cors_origins[{index}]: any web page may call the rpc endpoint without a token"
+                    ));
+                }
+            } else if !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                errors.push(format!(
+                    "rpc_cors_origins[{index}]: expected \"*\" or an http(s) origin, got \"{origin}\""
+                ));
+            }
+        }
+        for (index, plain) in self.plain_destinations.iter().enumerate() {
+            let app = plain.app.trim();
+            if app.is_empty() || app.contains('.') {
+                errors.push(format!(
+                    "plain_destinations[{index}]: app must be non-empty and must not contain dots"
+                ));
+            }
+            if plain.aspect.trim().is_empty() {
+                errors.push(format!("plain_destinations[{index}]: aspect is required"));
+            }
+        }
+        if self.store_backup_keep == Some(0) {
+            errors.push("store_backup_keep must be at least 1".into());
+        }
+        if self.store_backup_interval_secs == Some(0) {
+            warnings.push("store_backup_interval_secs of 0 disables scheduled backups".into());
+        }
+        if self.tcp_reconnect_initial_secs == Some(0) {
+            errors.push("tcp_reconnect_initial_secs must be at least 1".into());
+        }
+        let backoff = self.tcp_reconnect_backoff();
+        if backoff.max < backoff.initial {
+            warnings.push(
+                "tcp_reconnect_max_secs is below tcp_reconnect_initial_secs; every retry waits the maximum"
+                    .into(),
+            );
+        }
+        if self.max_peers == Some(0) {
+            warnings.push("max_peers of 0 lets the peer table grow without bound".into());
+        }
+        let presence = self.presence_thresholds();
+        if presence.stale_secs < presence.online_secs {
+            warnings.push(
+                "presence_stale_secs is below presence_online_secs; peers go straight from online to offline"
+                    .into(),
+            );
+        }
+        if self.bandwidth_limit_bytes_per_sec == Some(0) {
+            warnings.push("bandwidth_limit_bytes_per_sec of 0 leaves bandwidth unlimited".into());
+        }
+        (errors, warnings)
+    }
+
     pub fn enabled_tcp_clients(&self) -> Vec<&InterfaceConfig> {
         self.interfaces
             .iter()
@@ -36,6 +255,28 @@ impl DaemonConfig {
             .collect()
     }
 
+    pub fn tcp_reconnect_backoff(&self) -> ReconnectBackoff {
+        let defaults = ReconnectBackoff::default();
+        ReconnectBackoff {
+            initial: self
+                .tcp_reconnect_initial_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.initial),
+            max: self
+                .tcp_reconnect_max_secs
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max),
+        }
+    }
+
+    pub fn presence_thresholds(&self) -> PresenceThresholds {
+        let defaults = PresenceThresholds::default();
+        PresenceThresholds {
+            online_secs: self.presence_online_secs.unwrap_or(defaults.online_secs),
+            stale_secs: self.presence_stale_secs.unwrap_or(defaults.stale_secs),
+        }
+    }
+
     pub fn tcp_client_endpoints(&self) -> Vec<(String, u16)> {
         self.enabled_tcp_clients()
             .iter()
@@ -47,3 +288,25 @@ impl DaemonConfig {
             .collect()
     }
 }
+
+// Backs the validate_config RPC; never touches the running daemon.
+pub struct DaemonConfigValidator;
+
+impl ConfigBridge for DaemonConfigValidator {
+    fn check_config(&self, source: ConfigSource) -> Result<CheckedConfig, String> {
+        let config = match source {
+            ConfigSource::Toml(text) => {
+                DaemonConfig::from_toml(&text).map_err(|err| err.to_string())?
+            }
+            ConfigSource::Json(value) => {
+                DaemonConfig::from_json(value).map_err(|err| err.to_string())?
+            }
+        };
+        let (errors, warnings) = config.check();
+        Ok(CheckedConfig {
+            interfaces: config.interface_records(),
+            errors,
+            warnings,
+        })
+    }
+}
@@ -1,9 +1,12 @@
 use std::io;
+use std::sync::Arc;
 
-use reticulum::destination::link::{LinkEvent, LinkStatus};
+use reticulum::destination::link::{Link, LinkEvent, LinkStatus};
 use reticulum::destination::DestinationDesc;
+use reticulum::hash::{AddressHash, Hash};
 use reticulum::packet::Packet;
 use reticulum::transport::{SendPacketOutcome, Transport};
+use tokio::sync::Mutex;
 use tokio::time::{timeout, Duration, Instant};
 
 pub async fn send_via_link(
@@ -12,6 +15,49 @@ pub async fn send_via_link(
     payload: &[u8],
     wait_timeout: Duration,
 ) -> io::Result<Packet> {
+    let link = wait_for_active_link(transport, destination, wait_timeout).await?;
+
+    let packet = link
+        .lock()
+        .await
+        .data_packet(payload)
+        .map_err(|err| io::Error::other(format!("{:?}", err)))?;
+
+    let outcome = transport.send_packet_with_outcome(packet).await;
+    if !matches!(
+        outcome,
+        SendPacketOutcome::SentDirect | SendPacketOutcome::SentBroadcast
+    ) {
+        return Err(io::Error::other(format!(
+            "link packet not sent: {}",
+            send_outcome_label(outcome)
+        )));
+    }
+
+    Ok(packet)
+}
+
+pub async fn send_resource_via_link(
+    transport: &Transport,
+    destination: DestinationDesc,
+    data: Vec<u8>,
+    metadata: Option<Vec<u8>>,
+    wait_timeout: Duration,
+) -> io::Result<(Hash, AddressHash)> {
+    let link = wait_for_active_link(transport, destination, wait_timeout).await?;
+    let link_id = *link.lock().await.id();
+    let resource_hash = transport
+        .send_resource(&link_id, data, metadata)
+        .await
+        .map_err(|err| io::Error::other(format!("resource send failed: {:?}", err)))?;
+    Ok((resource_hash, link_id))
+}
+
+pub async fn wait_for_active_link(
+    transport: &Transport,
+    destination: DestinationDesc,
+    wait_timeout: Duration,
+) -> io::Result<Arc<Mutex<Link>>> {
     let link = transport.link(destination).await;
     let link_id = *link.lock().await.id();
 
@@ -55,24 +101,7 @@ pub async fn send_via_link(
         }
     }
 
-    let packet = link
-        .lock()
-        .await
-        .data_packet(payload)
-        .map_err(|err| io::Error::other(format!("{:?}", err)))?;
-
-    let outcome = transport.send_packet_with_outcome(packet).await;
-    if !matches!(
-        outcome,
-        SendPacketOutcome::SentDirect | SendPacketOutcome::SentBroadcast
-    ) {
-        return Err(io::Error::other(format!(
-            "link packet not sent: {}",
-            send_outcome_label(outcome)
-        )));
-    }
-
-    Ok(packet)
+    Ok(link)
 }
 
 fn send_outcome_label(outcome: SendPacketOutcome) -> &'static str {
@@ -1,3 +1,7 @@
+use lxmf::constants::WORKBLOCK_EXPAND_ROUNDS;
+use lxmf::stamper::{stamp_value, stamp_workblock};
+use reticulum::identity::PrivateIdentity;
+use reticulum::ratchets::decrypt_with_identity;
 use reticulum::storage::messages::MessageRecord;
 use sha2::{Digest, Sha256};
 
@@ -37,24 +41,21 @@ impl InboundDecodeDiagnostics {
     }
 }
 
+// The packed LXMF message a payload decodes as, in the form its message id
+// is computed from.
+pub fn packed_inbound_message(destination: [u8; 16], payload: &[u8]) -> Option<Vec<u8>> {
+    wire_candidates(destination, payload)
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .find(|candidate| decode_wire_candidate(destination, candidate).is_some())
+}
+
 pub fn decode_inbound_payload_with_diagnostics(
     destination: [u8; 16],
     payload: &[u8],
 ) -> (Option<MessageRecord>, InboundDecodeDiagnostics) {
-    let mut decode_candidates: Vec<(&'static str, Vec<u8>)> = Vec::with_capacity(3);
-    decode_candidates.push(("raw", payload.to_vec()));
-
-    let mut with_destination_prefix = Vec::with_capacity(16 + payload.len());
-    with_destination_prefix.extend_from_slice(&destination);
-    with_destination_prefix.extend_from_slice(payload);
-    decode_candidates.push(("dst_prefix+raw", with_destination_prefix));
-
-    if payload.len() > 16 && payload[..16] == destination {
-        decode_candidates.push(("raw_without_dst_prefix", payload[16..].to_vec()));
-    }
-
     let mut diagnostics = InboundDecodeDiagnostics::default();
-    for (label, candidate) in decode_candidates {
+    for (label, candidate) in wire_candidates(destination, payload) {
         match decode_wire_candidate(destination, &candidate) {
             Some(record) => return (Some(record), diagnostics),
             None => {
@@ -74,6 +75,49 @@ pub fn decode_inbound_payload_with_diagnostics(
     (None, diagnostics)
 }
 
+// Payloads arrive with or without the destination prefix depending on how
+// they were sent, so each form is tried in turn.
+fn wire_candidates(destination: [u8; 16], payload: &[u8]) -> Vec<(&'static str, Vec<u8>)> {
+    let mut candidates: Vec<(&'static str, Vec<u8>)> = Vec::with_capacity(3);
+    candidates.push(("raw", payload.to_vec()));
+
+    let mut with_destination_prefix = Vec::with_capacity(16 + payload.len());
+    with_destination_prefix.extend_from_slice(&destination);
+    with_destination_prefix.extend_from_slice(payload);
+    candidates.push(("dst_prefix+raw", with_destination_prefix));
+
+    if payload.len() > 16 && payload[..16] == destination {
+        candidates.push(("raw_without_dst_prefix", payload[16..].to_vec()));
+    }
+    candidates
+}
+
+// Paper messages carry the 16-byte destination hash in the clear, followed by
+// the rest of the packed LXMF message encrypted for that destination.
+pub fn decode_paper_message(
+    identity: &PrivateIdentity,
+    delivery_destination: [u8; 16],
+    packed: &[u8],
+) -> Result<MessageRecord, String> {
+    if packed.len() <= 16 {
+        return Err(format!("paper message too short ({} bytes)", packed.len()));
+    }
+    let mut destination = [0u8; 16];
+    destination.copy_from_slice(&packed[..16]);
+    if destination != delivery_destination {
+        return Err(format!(
+            "paper message is addressed to {}, not {}",
+            hex::encode(destination),
+            hex::encode(delivery_destination)
+        ));
+    }
+    let salt = identity.as_identity().address_hash;
+    let plaintext = decrypt_with_identity(identity, salt.as_slice(), &packed[16..])
+        .map_err(|err| format!("decrypt failed: {err:?}"))?;
+    let (record, diagnostics) = decode_inbound_payload_with_diagnostics(destination, &plaintext);
+    record.ok_or_else(|| format!("decode failed: {}", diagnostics.summary()))
+}
+
 fn decode_wire_candidate(
     fallback_destination: [u8; 16],
     candidate: &[u8],
@@ -92,6 +136,9 @@ fn decode_wire_candidate(
             direction: "in".into(),
             fields: message.fields.as_ref().and_then(rmpv_to_json),
             receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
         });
     }
 
@@ -106,6 +153,9 @@ fn decode_wire_candidate(
         direction: "in".into(),
         fields: decoded.fields.as_ref().and_then(rmpv_to_json),
         receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
     })
 }
 
@@ -199,6 +249,37 @@ fn wire_message_id_hex(candidate: &[u8]) -> Option<String> {
     ))
 }
 
+// Value of the proof-of-work stamp carried by an inbound message, or None
+// when it has no stamp. Tries the same framings as decode_inbound_payload.
+pub fn inbound_stamp_value(destination: [u8; 16], payload: &[u8]) -> Option<u32> {
+    let mut with_destination_prefix = Vec::with_capacity(16 + payload.len());
+    with_destination_prefix.extend_from_slice(&destination);
+    with_destination_prefix.extend_from_slice(payload);
+    wire_stamp_value(payload).or_else(|| wire_stamp_value(&with_destination_prefix))
+}
+
+fn wire_stamp_value(candidate: &[u8]) -> Option<u32> {
+    const SIGNATURE_LEN: usize = 64;
+    const HEADER_LEN: usize = 16 + 16 + SIGNATURE_LEN;
+    if candidate.len() <= HEADER_LEN {
+        return None;
+    }
+    let payload_value = rmp_serde::from_slice::<rmpv::Value>(&candidate[HEADER_LEN..]).ok()?;
+    let rmpv::Value::Array(items) = payload_value else {
+        return None;
+    };
+    let Some(rmpv::Value::Binary(stamp)) = items.get(4) else {
+        return None;
+    };
+    let payload_without_stamp = payload_without_stamp_bytes(&items)?;
+    let mut hasher = Sha256::new();
+    hasher.update(&candidate[..32]);
+    hasher.update(&payload_without_stamp);
+    let message_hash = hasher.finalize();
+    let workblock = stamp_workblock(&message_hash, WORKBLOCK_EXPAND_ROUNDS);
+    Some(stamp_value(&workblock, stamp))
+}
+
 fn payload_without_stamp_bytes(items: &[rmpv::Value]) -> Option<Vec<u8>> {
     if items.len() < 4 || items.len() > 5 {
         return None;
@@ -224,7 +305,10 @@ fn compute_message_id_hex(
 
 #[cfg(test)]
 mod tests {
-    use super::decode_inbound_payload_with_diagnostics;
+    use super::{decode_inbound_payload_with_diagnostics, inbound_stamp_value};
+    use lxmf::constants::WORKBLOCK_EXPAND_ROUNDS;
+    use lxmf::stamper::generate_stamp;
+    use sha2::{Digest, Sha256};
 
     #[test]
     fn decode_inbound_payload_accepts_integer_timestamp_wire() {
@@ -253,4 +337,36 @@ mod tests {
         assert_eq!(record.timestamp, 1_770_000_000_i64);
         assert_eq!(record.direction, "in");
     }
+
+    #[test]
+    fn inbound_stamp_value_scores_the_carried_stamp() {
+        let destination = [0x11; 16];
+        let source = [0x22; 16];
+        let items = vec![
+            rmpv::Value::from(1_770_000_000_i64),
+            rmpv::Value::from("title"),
+            rmpv::Value::from("stamped"),
+            rmpv::Value::Nil,
+        ];
+        let unstamped = rmp_serde::to_vec(&rmpv::Value::Array(items.clone())).expect("payload");
+        let mut hasher = Sha256::new();
+        hasher.update(destination);
+        hasher.update(source);
+        hasher.update(&unstamped);
+        let stamp = generate_stamp(&hasher.finalize(), 4, WORKBLOCK_EXPAND_ROUNDS).expect("stamp");
+
+        let mut stamped = items;
+        stamped.push(rmpv::Value::Binary(stamp));
+        let wire = |payload: &[u8]| {
+            let mut wire = Vec::new();
+            wire.extend_from_slice(&destination);
+            wire.extend_from_slice(&source);
+            wire.extend_from_slice(&[0x33; 64]);
+            wire.extend_from_slice(payload);
+            wire
+        };
+        let stamped = rmp_serde::to_vec(&rmpv::Value::Array(stamped)).expect("payload");
+        assert!(inbound_stamp_value(destination, &wire(&stamped)).expect("stamp value") >= 4);
+        assert_eq!(inbound_stamp_value(destination, &wire(&unstamped)), None);
+    }
 }
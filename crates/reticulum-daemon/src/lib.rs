@@ -4,5 +4,6 @@ pub mod direct_delivery;
 pub mod identity_store;
 pub mod inbound_delivery;
 pub mod lxmf_bridge;
+pub mod path_store;
 pub mod receipt_bridge;
 pub mod rns_crypto;
@@ -13,10 +13,25 @@ pub fn build_wire_message(
     content: &str,
     fields: Option<JsonValue>,
     signer: &PrivateIdentity,
+) -> Result<Vec<u8>, LxmfError> {
+    build_wire_message_at(source, destination, title, content, fields, signer, None)
+}
+
+// Like build_wire_message, but keeps the given LXMF timestamp instead of
+// stamping the current time, so exported messages keep their original date.
+pub fn build_wire_message_at(
+    source: [u8; 16],
+    destination: [u8; 16],
+    title: &str,
+    content: &str,
+    fields: Option<JsonValue>,
+    signer: &PrivateIdentity,
+    timestamp: Option<f64>,
 ) -> Result<Vec<u8>, LxmfError> {
     let mut message = Message::new();
     message.destination_hash = Some(destination);
     message.source_hash = Some(source);
+    message.timestamp = timestamp;
     message.set_title_from_string(title);
     message.set_content_from_string(content);
     if let Some(fields) = fields {
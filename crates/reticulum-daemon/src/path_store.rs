@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reticulum::hash::AddressHash;
+use reticulum::storage::messages::PathRecord;
+use reticulum::transport::path_table::PathSnapshot;
+
+// Paths are saved against interface names. Paths through unnamed interfaces,
+// such as connections accepted by the server, would not survive a restart
+// anyway and are left out.
+pub fn path_records(
+    paths: &[PathSnapshot],
+    names: &HashMap<String, AddressHash>,
+    now: i64,
+    ttl_secs: u64,
+) -> Vec<PathRecord> {
+    let by_address: HashMap<&AddressHash, &String> = names
+        .iter()
+        .map(|(name, address)| (address, name))
+        .collect();
+    let ttl = i64::try_from(ttl_secs).unwrap_or(i64::MAX);
+    paths
+        .iter()
+        .filter_map(|path| {
+            let interface = by_address.get(&path.iface)?;
+            let learned_at =
+                now.saturating_sub(i64::try_from(path.age.as_secs()).unwrap_or(i64::MAX));
+            Some(PathRecord {
+                destination: path.destination.to_hex_string(),
+                next_hop: path.received_from.to_hex_string(),
+                interface: (*interface).clone(),
+                hops: path.hops,
+                learned_at,
+                expires_at: learned_at.saturating_add(ttl),
+            })
+        })
+        .filter(|record| record.expires_at > now)
+        .collect()
+}
+
+// Records naming an interface that is not running now are skipped.
+pub fn path_snapshots(
+    records: &[PathRecord],
+    names: &HashMap<String, AddressHash>,
+    now: i64,
+) -> Vec<PathSnapshot> {
+    records
+        .iter()
+        .filter(|record| record.expires_at > now)
+        .filter_map(|record| {
+            Some(PathSnapshot {
+                destination: AddressHash::new_from_hex_string(&record.destination).ok()?,
+                received_from: AddressHash::new_from_hex_string(&record.next_hop).ok()?,
+                hops: record.hops,
+                iface: *names.get(&record.interface)?,
+                age: Duration::from_secs(
+                    u64::try_from(now.saturating_sub(record.learned_at)).unwrap_or(0),
+                ),
+            })
+        })
+        .collect()
+}
@@ -9,6 +9,8 @@ use tokio::sync::mpsc::UnboundedSender;
 pub struct ReceiptEvent {
     pub message_id: String,
     pub status: String,
+    // Set when the failure has a structured cause, such as a dropped send.
+    pub reason_code: Option<&'static str>,
 }
 
 #[derive(Clone)]
@@ -34,6 +36,7 @@ impl ReceiptHandler for ReceiptBridge {
             let _ = self.tx.send(ReceiptEvent {
                 message_id,
                 status: "delivered".into(),
+                reason_code: None,
             });
         }
     }
@@ -46,6 +49,7 @@ pub fn handle_receipt_event(daemon: &RpcDaemon, event: ReceiptEvent) -> Result<(
         params: Some(json!({
             "message_id": event.message_id,
             "status": event.status,
+            "reason_code": event.reason_code,
         })),
     })?;
     Ok(())
@@ -1,5 +1,8 @@
-use reticulum_daemon::config::{DaemonConfig, InterfaceConfig};
+use reticulum::rpc::{RpcDaemon, RpcRequest};
+use reticulum_daemon::config::{DaemonConfig, DaemonConfigValidator, InterfaceConfig};
+use serde_json::json;
 use std::fs;
+use std::sync::Arc;
 use tempfile::NamedTempFile;
 
 #[test]
@@ -28,6 +31,7 @@ fn filters_enabled_tcp_clients() {
                 host: Some("rmap.world".into()),
                 port: Some(4242),
                 name: None,
+                options: Default::default(),
             },
             InterfaceConfig {
                 kind: "tcp_client".into(),
@@ -35,8 +39,10 @@ fn filters_enabled_tcp_clients() {
                 host: Some("example.com".into()),
                 port: Some(1),
                 name: None,
+                options: Default::default(),
             },
         ],
+        ..Default::default()
     };
     let endpoints = cfg.tcp_client_endpoints();
     assert_eq!(endpoints.len(), 1);
@@ -60,3 +66,188 @@ interfaces = [
     assert_eq!(endpoints[0].0, "rmap.world");
     assert_eq!(endpoints[0].1, 4242);
 }
+
+#[test]
+fn parses_rpc_tokens() {
+    let input = r#"
+rpc_tokens = [
+  { label = "ops", token = "secret-a" },
+  { label = "map-client", token = "secret-b" }
+]
+"#;
+    let cfg = DaemonConfig::from_toml(input).expect("parse");
+    assert!(cfg.interfaces.is_empty());
+    assert_eq!(cfg.rpc_tokens.len(), 2);
+    assert_eq!(cfg.rpc_tokens[1].label, "map-client");
+    assert_eq!(cfg.rpc_tokens[1].token, "secret-b");
+}
+
+#[test]
+fn validate_config_reports_problems_without_applying() {
+    let daemon = RpcDaemon::test_instance().with_config_bridge(Arc::new(DaemonConfigValidator));
+    let input = r#"
+store_backup_keep = 0
+rpc_cors_origins = ["https://ui.example", "localhost:3000"]
+interfaces = [
+  { type = "tcp_client", enabled = true, host = "rmap.world", port = 4242, name = "rmap" },
+  { type = "tcp_client", enabled = true, host = "rmap.world", port = 4242, name = "rmap" },
+  { type = "tcp_server", enabled = false },
+  { type = "tcp_server", enabled = true, host = "lan.local", port = 0 },
+]
+"#;
+    let result = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "validate_config".into(),
+            params: Some(json!({ "config": input })),
+        })
+        .expect("validate_config")
+        .result
+        .expect("result");
+    assert_eq!(result["valid"], false);
+    let errors = result["errors"].as_array().expect("errors");
+    let has_error = |needle: &str| {
+        errors
+            .iter()
+            .any(|err| err.as_str().is_some_and(|err| err.contains(needle)))
+    };
+    assert!(has_error("name \"rmap\" is already used by interfaces[0]"));
+    assert!(has_error("same endpoint as interfaces[0]"));
+    assert!(has_error("interfaces[2]: tcp_server requires port"));
+    assert!(has_error(
+        "interfaces[3]: tcp_server host \"lan.local\" must be an IP address"
+    ));
+    assert!(has_error("store_backup_keep"));
+    assert!(has_error("rpc_cors_origins[1]"));
+    assert!(!has_error("rpc_cors_origins[0]"));
+    assert!(result["warnings"]
+        .as_array()
+        .expect("warnings")
+        .iter()
+        .any(|warning| warning == "interfaces[2]: interface is disabled"));
+
+    let interfaces = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_interfaces".into(),
+            params: None,
+        })
+        .expect("list_interfaces")
+        .result
+        .expect("result");
+    assert!(interfaces["interfaces"]
+        .as_array()
+        .expect("list")
+        .is_empty());
+
+    let mut file = NamedTempFile::new().expect("temp file");
+    std::io::Write::write_all(
+        &mut file,
+        br#"interfaces = [{ type = "tcp_client", enabled = true, host = "a", port = 1 }]"#,
+    )
+    .expect("write");
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "validate_config".into(),
+            params: Some(json!({ "path": file.path() })),
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let result = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "validate_config".into(),
+            params: Some(json!({ "config": "interfaces = 3" })),
+        })
+        .expect("validate_config")
+        .result
+        .expect("result");
+    assert_eq!(result["valid"], false);
+}
+
+#[test]
+fn tcp_reconnect_backoff_defaults_and_overrides() {
+    let cfg = DaemonConfig::from_toml("tcp_reconnect_max_secs = 30").expect("config");
+    let backoff = cfg.tcp_reconnect_backoff();
+    assert_eq!(backoff.initial, std::time::Duration::from_secs(1));
+    assert_eq!(backoff.max, std::time::Duration::from_secs(30));
+
+    let cfg = DaemonConfig::from_toml("tcp_reconnect_initial_secs = 0").expect("config");
+    let (errors, _) = cfg.check();
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn presence_thresholds_default_and_warn_when_inverted() {
+    let cfg = DaemonConfig::from_toml("presence_online_secs = 120").expect("config");
+    let thresholds = cfg.presence_thresholds();
+    assert_eq!(thresholds.online_secs, 120);
+    assert_eq!(thresholds.stale_secs, 3600);
+
+    let cfg = DaemonConfig::from_toml("presence_online_secs = 600\npresence_stale_secs = 60")
+        .expect("config");
+    let (errors, warnings) = cfg.check();
+    assert!(errors.is_empty());
+    assert!(warnings
+        .iter()
+        .any(|warning| warning.contains("presence_stale_secs")));
+}
+
+#[test]
+fn interface_options_are_parsed_validated_and_applied() {
+    let input = r#"
+tcp_reconnect_max_secs = 30
+
+[[interfaces]]
+type = "tcp_client"
+enabled = true
+host = "rmap.world"
+port = 4242
+
+[interfaces.options]
+nodelay = true
+reconnect_initial_secs = 5
+"#;
+    let cfg = DaemonConfig::from_toml(input).expect("parse");
+    let iface = &cfg.interfaces[0];
+    assert!(iface.nodelay());
+    let backoff = iface.reconnect_backoff(cfg.tcp_reconnect_backoff());
+    assert_eq!(backoff.initial, std::time::Duration::from_secs(5));
+    assert_eq!(backoff.max, std::time::Duration::from_secs(30));
+    assert_eq!(cfg.interface_records()[0].options["nodelay"], true);
+
+    let daemon = RpcDaemon::test_instance();
+    let set = |options| {
+        daemon.handle_rpc(RpcRequest {
+            id: 1,
+            method: "set_interfaces".into(),
+            params: Some(json!({ "interfaces": [{
+                "type": "tcp_client",
+                "enabled": true,
+                "host": "a",
+                "port": 1,
+                "options": options,
+            }] })),
+        })
+    };
+    let err = set(json!({ "baud": 9600 })).expect_err("unknown option");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err
+        .to_string()
+        .contains("tcp_client has no option \"baud\""));
+    let err = set(json!({ "reconnect_max_secs": "soon" })).expect_err("bad value");
+    assert!(err.to_string().contains("reconnect_max_secs"));
+    set(json!({ "nodelay": false })).expect("valid options");
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_interfaces".into(),
+            params: None,
+        })
+        .expect("list_interfaces")
+        .result
+        .expect("result");
+    assert_eq!(listed["interfaces"][0]["options"]["nodelay"], false);
+}
@@ -1,8 +1,12 @@
 use lxmf::constants::DESTINATION_LENGTH;
 use rand_core::OsRng;
+use reticulum::destination::lxmf_delivery_hash_from_identity_hash;
 use reticulum::identity::PrivateIdentity;
-use reticulum_daemon::inbound_delivery::decode_inbound_payload;
-use reticulum_daemon::lxmf_bridge::build_wire_message;
+use reticulum::ratchets::encrypt_for_public_key;
+use reticulum_daemon::inbound_delivery::{
+    decode_inbound_payload, decode_paper_message, packed_inbound_message,
+};
+use reticulum_daemon::lxmf_bridge::{build_wire_message, build_wire_message_at};
 
 #[test]
 fn inbound_link_payload_is_decoded() {
@@ -21,4 +25,85 @@ fn inbound_link_payload_is_decoded() {
     assert_eq!(record.destination, hex::encode(destination));
     assert_eq!(record.content, "hello inbound");
     assert_eq!(record.direction, "in");
+
+    // The kept bytes are the full message the id was computed from.
+    let packed = packed_inbound_message(destination, &payload).expect("packed message");
+    assert_eq!(packed, wire);
+    assert_eq!(
+        decode_inbound_payload(destination, &packed).unwrap().id,
+        record.id
+    );
+}
+
+#[test]
+fn paper_message_is_decrypted_and_decoded() {
+    let signer = PrivateIdentity::new_from_rand(OsRng);
+    let mut source = [0u8; 16];
+    source.copy_from_slice(signer.address_hash().as_slice());
+    let recipient = PrivateIdentity::new_from_rand(OsRng);
+    let mut destination = [0u8; 16];
+    destination.copy_from_slice(
+        lxmf_delivery_hash_from_identity_hash(recipient.address_hash()).as_slice(),
+    );
+
+    let wire = build_wire_message(
+        source,
+        destination,
+        "paper",
+        "hello on paper",
+        None,
+        &signer,
+    )
+    .expect("wire message");
+    let salt = recipient.as_identity().address_hash;
+    let encrypted = encrypt_for_public_key(
+        &recipient.as_identity().public_key,
+        salt.as_slice(),
+        &wire[DESTINATION_LENGTH..],
+        OsRng,
+    )
+    .expect("encrypt");
+    let mut packed = destination.to_vec();
+    packed.extend_from_slice(&encrypted);
+
+    let record = decode_paper_message(&recipient, destination, &packed).expect("decoded");
+    assert_eq!(record.source, hex::encode(source));
+    assert_eq!(record.destination, hex::encode(destination));
+    assert_eq!(record.title, "paper");
+    assert_eq!(record.content, "hello on paper");
+
+    let err = decode_paper_message(&signer, source, &packed).unwrap_err();
+    assert!(err.contains("addressed to"), "{err}");
+    let mut corrupted = packed.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0xff;
+    let err = decode_paper_message(&recipient, destination, &corrupted).unwrap_err();
+    assert!(err.starts_with("decrypt failed"), "{err}");
+}
+
+#[test]
+fn exported_wire_message_keeps_its_timestamp_and_fields() {
+    let signer = PrivateIdentity::new_from_rand(OsRng);
+    let mut source = [0u8; 16];
+    source.copy_from_slice(signer.address_hash().as_slice());
+    let destination = [9u8; 16];
+    let fields = serde_json::json!({ "k": "v" });
+
+    let wire = build_wire_message_at(
+        source,
+        destination,
+        "archived",
+        "from last year",
+        Some(fields.clone()),
+        &signer,
+        Some(1_700_000_000.0),
+    )
+    .expect("wire message");
+
+    let record = decode_inbound_payload([0u8; 16], &wire).expect("decoded record");
+    assert_eq!(record.source, hex::encode(source));
+    assert_eq!(record.destination, hex::encode(destination));
+    assert_eq!(record.title, "archived");
+    assert_eq!(record.timestamp, 1_700_000_000);
+    assert_eq!(record.fields, Some(fields));
 }
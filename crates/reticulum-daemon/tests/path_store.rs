@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use reticulum::hash::AddressHash;
+use reticulum::storage::messages::MessagesStore;
+use reticulum::transport::path_table::PathSnapshot;
+use reticulum_daemon::path_store::{path_records, path_snapshots};
+
+fn address(byte: u8) -> AddressHash {
+    AddressHash::new([byte; 16])
+}
+
+#[test]
+fn saved_paths_follow_interface_names_and_expire() {
+    let before: HashMap<String, AddressHash> = [("uplink".to_string(), address(1))].into();
+    let paths = vec![
+        PathSnapshot {
+            destination: address(10),
+            received_from: address(11),
+            hops: 2,
+            iface: address(1),
+            age: Duration::from_secs(30),
+        },
+        // Learned on an accepted connection, which has no configured name.
+        PathSnapshot {
+            destination: address(20),
+            received_from: address(21),
+            hops: 1,
+            iface: address(9),
+            age: Duration::ZERO,
+        },
+        PathSnapshot {
+            destination: address(30),
+            received_from: address(31),
+            hops: 4,
+            iface: address(1),
+            age: Duration::from_secs(500),
+        },
+    ];
+    let records = path_records(&paths, &before, 1_000, 100);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].interface, "uplink");
+    assert_eq!(records[0].learned_at, 970);
+    assert_eq!(records[0].expires_at, 1_070);
+
+    let store = MessagesStore::in_memory().unwrap();
+    store.replace_paths(&records).unwrap();
+    assert_eq!(store.load_paths(1_050).unwrap(), records);
+
+    // The interface comes back with a new address after a restart.
+    let after: HashMap<String, AddressHash> = [("uplink".to_string(), address(2))].into();
+    let restored = path_snapshots(&store.load_paths(1_050).unwrap(), &after, 1_050);
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].destination, address(10));
+    assert_eq!(restored[0].received_from, address(11));
+    assert_eq!(restored[0].hops, 2);
+    assert_eq!(restored[0].iface, address(2));
+    assert_eq!(restored[0].age, Duration::from_secs(80));
+    assert!(path_snapshots(&records, &HashMap::new(), 1_050).is_empty());
+
+    assert!(store.load_paths(1_070).unwrap().is_empty());
+    assert!(store.load_paths(1_050).unwrap().is_empty());
+}
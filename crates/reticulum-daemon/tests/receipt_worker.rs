@@ -1,4 +1,5 @@
 use reticulum::rpc::{RpcDaemon, RpcRequest};
+use reticulum::transport::SendPacketOutcome;
 use reticulum_daemon::receipt_bridge::{handle_receipt_event, ReceiptEvent};
 use serde_json::json;
 
@@ -24,6 +25,7 @@ fn receipt_event_updates_store_and_emits_event() {
         ReceiptEvent {
             message_id: "msg-1".into(),
             status: "delivered".into(),
+            reason_code: None,
         },
     )
     .expect("handle receipt");
@@ -40,3 +42,54 @@ fn receipt_event_updates_store_and_emits_event() {
     let messages = result.get("messages").unwrap().as_array().unwrap();
     assert_eq!(messages[0].get("receipt_status").unwrap(), "delivered");
 }
+
+#[test]
+fn dropped_send_reason_reaches_event_and_trace() {
+    let daemon = RpcDaemon::test_instance();
+    let _ = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "msg-2",
+                "source": "peer-a",
+                "destination": "peer-b",
+                "content": "hello"
+            })),
+        })
+        .unwrap();
+    while daemon.take_event().is_some() {}
+
+    let outcome = SendPacketOutcome::DroppedEncryptFailed;
+    handle_receipt_event(
+        &daemon,
+        ReceiptEvent {
+            message_id: "msg-2".into(),
+            status: "failed: opportunistic".into(),
+            reason_code: outcome.reason_code(),
+        },
+    )
+    .expect("handle receipt");
+
+    let mut failed = None;
+    while let Some(event) = daemon.take_event() {
+        if event.event_type == "delivery_failed" {
+            failed = Some(event);
+        }
+    }
+    assert_eq!(
+        failed.expect("delivery_failed").payload["reason_code"],
+        "encrypt_failed"
+    );
+
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "msg-2" })),
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(trace["reason_code"], "encrypt_failed");
+}
@@ -0,0 +1,60 @@
+// Stamps the build with the git revision and build time that
+// daemon_status_ex reports. Only the workspace's own checkout counts, so a
+// copy vendored into another repository reports "unknown" rather than that
+// repository's revision. SOURCE_DATE_EPOCH pins the time for reproducible
+// builds; otherwise a checkout uses the commit time of HEAD, which changes
+// exactly when the script reruns.
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git(repo: &Path, args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn main() {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let repo = manifest_dir.join("../..");
+    let git_dir = repo.join(".git");
+    let checkout = git_dir.exists().then_some(repo.as_path());
+
+    let git_sha = checkout
+        .and_then(|repo| git(repo, &["rev-parse", "--short=12", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+    let build_time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .or_else(|| {
+            checkout
+                .and_then(|repo| git(repo, &["log", "-1", "--format=%ct", "HEAD"]))
+                .and_then(|value| value.parse::<u64>().ok())
+        })
+        .or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs())
+        })
+        .map_or_else(|| "unknown".to_string(), |secs| secs.to_string());
+
+    println!("cargo:rustc-env=RETICULUM_GIT_SHA={git_sha}");
+    println!("cargo:rustc-env=RETICULUM_BUILD_TIME={build_time}");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // Rebuild when HEAD moves rather than on every compile. Refs that git has
+    // packed only change in packed-refs.
+    for path in ["HEAD", "refs/heads", "packed-refs"] {
+        let path = git_dir.join(path);
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
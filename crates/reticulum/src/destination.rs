@@ -672,6 +672,75 @@ impl<D: Direction> Destination<EmptyIdentity, D, Plain> {
     }
 }
 
+impl Destination<EmptyIdentity, Output, Plain> {
+    // Plain destinations carry the payload as-is; callers are responsible for
+    // only sending data that is meant to be public.
+    pub fn data_packet(&self, data: &[u8]) -> Result<Packet, RnsError> {
+        let mut packet_data = PacketDataBuffer::new();
+        packet_data.write(data)?;
+
+        Ok(Packet {
+            header: Header {
+                ifac_flag: IfacFlag::Open,
+                header_type: HeaderType::Type1,
+                context_flag: ContextFlag::Unset,
+                propagation_type: PropagationType::Broadcast,
+                destination_type: DestinationType::Plain,
+                packet_type: PacketType::Data,
+                hops: 0,
+            },
+            ifac: None,
+            destination: self.desc.address_hash,
+            transport: None,
+            context: PacketContext::None,
+            data: packet_data,
+        })
+    }
+}
+
+impl<D: Direction> Destination<EmptyIdentity, D, Group> {
+    pub fn new(identity: EmptyIdentity, name: DestinationName) -> Self {
+        let address_hash = create_address_hash(&identity, &name);
+        Self {
+            direction: PhantomData,
+            r#type: PhantomData,
+            identity,
+            desc: DestinationDesc {
+                identity: Default::default(),
+                name,
+                address_hash,
+            },
+            ratchet_state: RatchetState::default(),
+        }
+    }
+}
+
+impl Destination<EmptyIdentity, Output, Group> {
+    // The payload is encrypted with the shared group key, so anyone holding
+    // the key can read it.
+    pub fn data_packet(&self, key: &[u8; 16], data: &[u8]) -> Result<Packet, RnsError> {
+        let mut packet_data = PacketDataBuffer::new();
+        packet_data.write(&group_encrypt(key, data)?)?;
+
+        Ok(Packet {
+            header: Header {
+                ifac_flag: IfacFlag::Open,
+                header_type: HeaderType::Type1,
+                context_flag: ContextFlag::Unset,
+                propagation_type: PropagationType::Broadcast,
+                destination_type: DestinationType::Group,
+                packet_type: PacketType::Data,
+                hops: 0,
+            },
+            ifac: None,
+            destination: self.desc.address_hash,
+            transport: None,
+            context: PacketContext::None,
+            data: packet_data,
+        })
+    }
+}
+
 fn create_address_hash<I: HashIdentity>(identity: &I, name: &DestinationName) -> AddressHash {
     AddressHash::new_from_hash(&Hash::new(
         Hash::generator()
@@ -700,6 +769,7 @@ pub type SingleInputDestination = Destination<PrivateIdentity, Input, Single>;
 pub type SingleOutputDestination = Destination<Identity, Output, Single>;
 pub type PlainInputDestination = Destination<EmptyIdentity, Input, Plain>;
 pub type PlainOutputDestination = Destination<EmptyIdentity, Output, Plain>;
+pub type GroupOutputDestination = Destination<EmptyIdentity, Output, Group>;
 
 pub fn new_in(identity: PrivateIdentity, app_name: &str, aspect: &str) -> SingleInputDestination {
     SingleInputDestination::new(identity, DestinationName::new(app_name, aspect))
@@ -709,6 +779,23 @@ pub fn new_out(identity: Identity, app_name: &str, aspect: &str) -> SingleOutput
     SingleOutputDestination::new(identity, DestinationName::new(app_name, aspect))
 }
 
+pub fn destination_hash_from_identity_hash(
+    identity_hash: &AddressHash,
+    name: &DestinationName,
+) -> AddressHash {
+    AddressHash::new_from_hash(&Hash::new(
+        Hash::generator()
+            .chain_update(name.as_name_hash_slice())
+            .chain_update(identity_hash.as_slice())
+            .finalize()
+            .into(),
+    ))
+}
+
+pub fn lxmf_delivery_hash_from_identity_hash(identity_hash: &AddressHash) -> AddressHash {
+    destination_hash_from_identity_hash(identity_hash, &DestinationName::new("lxmf", "delivery"))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ratchets::now_secs;
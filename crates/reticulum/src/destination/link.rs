@@ -181,6 +181,8 @@ pub struct Link {
     signalling: Option<[u8; LINK_MTU_SIZE]>,
     status: LinkStatus,
     request_time: Instant,
+    established_at: Option<Instant>,
+    last_activity: Instant,
     rtt: Duration,
     event_tx: tokio::sync::broadcast::Sender<LinkEventData>,
 }
@@ -199,6 +201,8 @@ impl Link {
             signalling: None,
             status: LinkStatus::Pending,
             request_time: Instant::now(),
+            established_at: None,
+            last_activity: Instant::now(),
             rtt: Duration::from_secs(0),
             event_tx,
         }
@@ -241,6 +245,8 @@ impl Link {
             signalling,
             status: LinkStatus::Pending,
             request_time: Instant::now(),
+            established_at: None,
+            last_activity: Instant::now(),
             rtt: Duration::from_secs(0),
             event_tx,
         };
@@ -280,6 +286,7 @@ impl Link {
 
         if self.status != LinkStatus::Active {
             self.status = LinkStatus::Active;
+            self.established_at = Some(Instant::now());
             self.post_event(LinkEvent::Activated);
         }
 
@@ -399,7 +406,9 @@ impl Link {
         if packet.destination != self.id {
             return LinkHandleResult::None;
         }
+        self.last_activity = Instant::now();
 
+        #[allow(clippy::collapsible_match)]
         match packet.header.packet_type {
             PacketType::Data => return self.handle_data_packet(packet),
             PacketType::Proof => {
@@ -413,6 +422,7 @@ impl Link {
                         self.handshake(identity);
 
                         self.status = LinkStatus::Active;
+                        self.established_at = Some(Instant::now());
                         self.rtt = self.request_time.elapsed();
 
                         log::debug!("link({}): activated", self.id);
@@ -459,6 +469,30 @@ impl Link {
         })
     }
 
+    pub fn teardown_packet(&self) -> Result<Packet, RnsError> {
+        let mut packet_data = PacketDataBuffer::new();
+
+        let cipher_text_len = {
+            let cipher_text = self.encrypt(self.id.as_slice(), packet_data.accuire_buf_max())?;
+            cipher_text.len()
+        };
+
+        packet_data.resize(cipher_text_len);
+
+        Ok(Packet {
+            header: Header {
+                destination_type: DestinationType::Link,
+                packet_type: PacketType::Data,
+                ..Default::default()
+            },
+            ifac: None,
+            destination: self.id,
+            transport: None,
+            context: PacketContext::LinkClose,
+            data: packet_data,
+        })
+    }
+
     pub fn keep_alive_packet(&self, data: u8) -> Packet {
         log::trace!("link({}): create keep alive {}", self.id, data);
 
@@ -575,6 +609,18 @@ impl Link {
         self.status
     }
 
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+
+    pub fn established_at(&self) -> Option<Instant> {
+        self.established_at
+    }
+
+    pub fn last_activity(&self) -> Instant {
+        self.last_activity
+    }
+
     pub fn id(&self) -> &LinkId {
         &self.id
     }
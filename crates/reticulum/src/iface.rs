@@ -2,6 +2,7 @@ pub mod driver;
 pub mod hdlc;
 pub mod tcp_client;
 pub mod tcp_server;
+pub mod traffic;
 pub mod udp;
 
 use std::sync::Arc;
@@ -18,6 +19,7 @@ use crate::hash::Hash;
 use crate::packet::Packet;
 
 pub use driver::{InterfaceDriver, InterfaceDriverFactory};
+pub use traffic::{InterfaceTraffic, TrafficHistory, TrafficSample};
 
 pub type InterfaceTxSender = mpsc::Sender<TxMessage>;
 pub type InterfaceTxReceiver = mpsc::Receiver<TxMessage>;
@@ -44,10 +46,20 @@ pub struct TxDispatchTrace {
     pub failed_ifaces: usize,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+// Signal quality an interface reported for a received packet. Radio
+// interfaces fill in what their modem measures; wired ones report none.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct LinkQuality {
+    pub rssi: Option<f64>,
+    pub snr: Option<f64>,
+    pub q: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub struct RxMessage {
-    pub address: AddressHash, // Address of source interface
-    pub packet: Packet,       // Received packet
+    pub address: AddressHash,         // Address of source interface
+    pub packet: Packet,               // Received packet
+    pub quality: Option<LinkQuality>, // Link quality, if the interface measures it
 }
 
 pub struct InterfaceChannel {
@@ -95,6 +107,7 @@ pub trait Interface {
 
 struct LocalInterface {
     address: AddressHash,
+    parent: Option<AddressHash>,
     tx_send: InterfaceTxSender,
     stop: CancellationToken,
 }
@@ -111,6 +124,7 @@ pub struct InterfaceManager {
     rx_send: InterfaceRxSender,
     cancel: CancellationToken,
     ifaces: Vec<LocalInterface>,
+    traffic: InterfaceTraffic,
 }
 
 const DEFAULT_IFACE_TX_QUEUE_CAPACITY: usize = 128;
@@ -143,6 +157,7 @@ impl InterfaceManager {
             rx_send,
             cancel: CancellationToken::new(),
             ifaces: Vec::new(),
+            traffic: InterfaceTraffic::default(),
         }
     }
 
@@ -160,6 +175,7 @@ impl InterfaceManager {
 
         self.ifaces.push(LocalInterface {
             address,
+            parent: None,
             tx_send,
             stop: stop.clone(),
         });
@@ -198,12 +214,62 @@ impl InterfaceManager {
         address
     }
 
+    // Used by listening interfaces so accepted connections can be traced back
+    // to the interface that accepted them.
+    pub fn spawn_child<T: Interface, F, R>(
+        &mut self,
+        parent: AddressHash,
+        inner: T,
+        worker: F,
+    ) -> AddressHash
+    where
+        F: FnOnce(InterfaceContext<T>) -> R,
+        R: std::future::Future<Output = ()> + Send + 'static,
+        R::Output: Send + 'static,
+    {
+        let address = self.spawn(inner, worker);
+        if let Some(iface) = self
+            .ifaces
+            .iter_mut()
+            .find(|iface| iface.address == address)
+        {
+            iface.parent = Some(parent);
+        }
+        self.traffic.set_parent(address, parent);
+        address
+    }
+
+    pub fn parent_of(&self, address: &AddressHash) -> Option<AddressHash> {
+        self.ifaces
+            .iter()
+            .find(|iface| iface.address == *address)
+            .and_then(|iface| iface.parent)
+    }
+
     pub fn receiver(&self) -> Arc<tokio::sync::Mutex<InterfaceRxReceiver>> {
         self.rx_recv.clone()
     }
 
+    pub fn traffic(&self) -> InterfaceTraffic {
+        self.traffic.clone()
+    }
+
     pub fn cleanup(&mut self) {
         self.ifaces.retain(|iface| !iface.stop.is_cancelled());
+        let ifaces = &self.ifaces;
+        self.traffic
+            .retain(|address| ifaces.iter().any(|iface| iface.address == *address));
+    }
+
+    // Stops every interface worker and forgets them. Interfaces spawned
+    // afterwards get a fresh cancellation token so they keep running.
+    pub fn shutdown_all(&mut self) {
+        self.cancel.cancel();
+        self.cancel = CancellationToken::new();
+        for iface in self.ifaces.drain(..) {
+            iface.stop.cancel();
+        }
+        self.traffic.retain(|_| false);
     }
 
     pub async fn send(&self, message: TxMessage) -> TxDispatchTrace {
@@ -226,6 +292,8 @@ impl InterfaceManager {
                 match iface.tx_send.try_send(message) {
                     Ok(()) => {
                         trace.sent_ifaces += 1;
+                        self.traffic
+                            .record_tx(iface.address, traffic::packet_wire_len(&message.packet));
                     }
                     Err(mpsc::error::TrySendError::Full(_)) => {
                         // Fall back to a short async wait before dropping. This avoids
@@ -238,6 +306,10 @@ impl InterfaceManager {
                         {
                             Ok(Ok(())) => {
                                 trace.sent_ifaces += 1;
+                                self.traffic.record_tx(
+                                    iface.address,
+                                    traffic::packet_wire_len(&message.packet),
+                                );
                                 log::warn!(
                                     "iface: recovered from full tx queue on {} for {:?}",
                                     iface.address,
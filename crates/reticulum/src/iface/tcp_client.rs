@@ -1,8 +1,10 @@
 use std::sync::Arc;
 use std::sync::OnceLock;
+use std::time::Duration;
 
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 
 use crate::buffer::{InputBuffer, OutputBuffer};
@@ -37,9 +39,62 @@ fn tx_diag_enabled() -> bool {
     })
 }
 
+// Delay before each reconnect attempt, doubling from `initial` up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectBackoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for ReconnectBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ReconnectBackoff {
+    // `attempt` counts failed connects since the last successful one, from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpClientState {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+impl TcpClientState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TcpClientState::Connecting => "connecting",
+            TcpClientState::Connected => "connected",
+            TcpClientState::Disconnected => "disconnected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpClientStatus {
+    pub state: TcpClientState,
+    pub reconnect_attempts: u32,
+    pub retry_in: Option<Duration>,
+}
+
 pub struct TcpClient {
     addr: String,
     stream: Option<TcpStream>,
+    backoff: ReconnectBackoff,
+    nodelay: bool,
+    status: Arc<watch::Sender<TcpClientStatus>>,
 }
 
 impl TcpClient {
@@ -47,6 +102,9 @@ impl TcpClient {
         Self {
             addr: addr.into(),
             stream: None,
+            backoff: ReconnectBackoff::default(),
+            nodelay: false,
+            status: Self::status_channel(),
         }
     }
 
@@ -54,12 +112,52 @@ impl TcpClient {
         Self {
             addr: addr.into(),
             stream: Some(stream),
+            backoff: ReconnectBackoff::default(),
+            nodelay: false,
+            status: Self::status_channel(),
         }
     }
 
+    pub fn with_backoff(mut self, backoff: ReconnectBackoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    // Disables Nagle's algorithm on every connection the client makes.
+    pub fn with_nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    // Follows connection state changes; the channel closes once the
+    // interface stops.
+    pub fn subscribe_status(&self) -> watch::Receiver<TcpClientStatus> {
+        self.status.subscribe()
+    }
+
+    fn status_channel() -> Arc<watch::Sender<TcpClientStatus>> {
+        Arc::new(
+            watch::channel(TcpClientStatus {
+                state: TcpClientState::Connecting,
+                reconnect_attempts: 0,
+                retry_in: None,
+            })
+            .0,
+        )
+    }
+
     pub async fn spawn(context: InterfaceContext<TcpClient>) {
         let iface_stop = context.channel.stop.clone();
-        let addr = { context.inner.lock().unwrap().addr.clone() };
+        let (addr, backoff, nodelay, status) = {
+            let inner = context.inner.lock().unwrap();
+            (
+                inner.addr.clone(),
+                inner.backoff,
+                inner.nodelay,
+                inner.status.clone(),
+            )
+        };
+        let mut failed_attempts: u32 = 0;
         let iface_address = context.channel.address;
         let mut stream = { context.inner.lock().unwrap().stream.take() };
 
@@ -85,18 +183,48 @@ impl TcpClient {
             };
 
             if stream.is_err() {
-                log::info!("tcp_client: couldn't connect to <{}>", addr);
-                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                failed_attempts = failed_attempts.saturating_add(1);
+                let delay = backoff.delay(failed_attempts);
+                log::info!(
+                    "tcp_client: couldn't connect to <{}>, retrying in {:?}",
+                    addr,
+                    delay
+                );
+                status.send_replace(TcpClientStatus {
+                    state: TcpClientState::Disconnected,
+                    reconnect_attempts: failed_attempts,
+                    retry_in: Some(delay),
+                });
+                tokio::select! {
+                    _ = context.cancel.cancelled() => break,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                status.send_replace(TcpClientStatus {
+                    state: TcpClientState::Connecting,
+                    reconnect_attempts: failed_attempts,
+                    retry_in: None,
+                });
                 continue;
             }
+            failed_attempts = 0;
 
             let cancel = context.cancel.clone();
             let stop = CancellationToken::new();
 
             let stream = stream.unwrap();
+            if nodelay {
+                if let Err(err) = stream.set_nodelay(true) {
+                    log::warn!("tcp_client: couldn't set nodelay on <{}>: {}", addr, err);
+                }
+            }
             let (read_stream, write_stream) = stream.into_split();
 
             log::info!("tcp_client connected to <{}>", addr);
+            status.send_replace(TcpClientStatus {
+                state: TcpClientState::Connected,
+                reconnect_attempts: 0,
+                retry_in: None,
+            });
 
             // Use protocol MTU-scale buffers, not size_of::<Packet>(), since packet
             // struct size does not reflect serialized wire size and can silently drop
@@ -158,6 +286,7 @@ impl TcpClient {
                                                             .send(RxMessage {
                                                                 address: iface_address,
                                                                 packet,
+                                                                quality: None,
                                                             })
                                                             .await;
                                                     } else {
@@ -292,6 +421,11 @@ impl TcpClient {
             rx_task.await.unwrap();
 
             log::info!("tcp_client: disconnected from <{}>", addr);
+            status.send_replace(TcpClientStatus {
+                state: TcpClientState::Disconnected,
+                reconnect_attempts: 0,
+                retry_in: None,
+            });
         }
 
         iface_stop.cancel();
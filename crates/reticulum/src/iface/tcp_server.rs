@@ -1,16 +1,25 @@
-use alloc::string::String;
+use alloc::string::{String, ToString};
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use tokio::net::TcpListener;
-
-use crate::error::RnsError;
+use tokio::sync::watch;
 
 use super::tcp_client::TcpClient;
 use super::{Interface, InterfaceContext, InterfaceManager};
 
+// Outcome of the latest bind attempt. `bound` carries the address actually
+// listened on, which differs from the configured one when binding port 0.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TcpServerStatus {
+    pub bound: Option<SocketAddr>,
+    pub error: Option<String>,
+}
+
 pub struct TcpServer {
     addr: String,
     iface_manager: Arc<tokio::sync::Mutex<InterfaceManager>>,
+    status: Arc<watch::Sender<TcpServerStatus>>,
 }
 
 impl TcpServer {
@@ -21,14 +30,23 @@ impl TcpServer {
         Self {
             addr: addr.into(),
             iface_manager,
+            status: Arc::new(watch::channel(TcpServerStatus::default()).0),
         }
     }
 
+    // Follows bind results; the channel closes once the interface stops.
+    pub fn subscribe_status(&self) -> watch::Receiver<TcpServerStatus> {
+        self.status.subscribe()
+    }
+
     pub async fn spawn(context: InterfaceContext<Self>) {
         let addr = { context.inner.lock().unwrap().addr.clone() };
 
         let iface_manager = { context.inner.lock().unwrap().iface_manager.clone() };
 
+        let status = { context.inner.lock().unwrap().status.clone() };
+
+        let server_address = *context.channel.address();
         let (_, tx_channel) = context.channel.split();
         let tx_channel = Arc::new(tokio::sync::Mutex::new(tx_channel));
 
@@ -37,19 +55,25 @@ impl TcpServer {
                 break;
             }
 
-            let listener = TcpListener::bind(addr.clone())
-                .await
-                .map_err(|_| RnsError::ConnectionError);
-
-            if listener.is_err() {
-                log::warn!("tcp_server: couldn't bind to <{}>", addr);
-                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                continue;
-            }
-
-            log::info!("tcp_server: listen on <{}>", addr);
+            let listener = match TcpListener::bind(addr.clone()).await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    log::warn!("tcp_server: couldn't bind to <{}>: {}", addr, err);
+                    status.send_replace(TcpServerStatus {
+                        bound: None,
+                        error: Some(err.to_string()),
+                    });
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
 
-            let listener = listener.unwrap();
+            let bound = listener.local_addr().ok();
+            log::info!(
+                "tcp_server: listen on <{}>",
+                bound.map_or_else(|| addr.clone(), |bound| bound.to_string())
+            );
+            status.send_replace(TcpServerStatus { bound, error: None });
 
             let tx_task = {
                 let cancel = context.cancel.clone();
@@ -96,7 +120,8 @@ impl TcpServer {
 
                             let mut iface_manager = iface_manager.lock().await;
 
-                            iface_manager.spawn(
+                            iface_manager.spawn_child(
+                                server_address,
                                 TcpClient::new_from_stream(client.1.to_string(), client.0),
                                 TcpClient::spawn,
                             );
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::hash::{AddressHash, ADDRESS_HASH_SIZE};
+use crate::packet::{HeaderType, Packet};
+
+// One sample per second, so this is the span a sparkline can show.
+pub const TRAFFIC_HISTORY_SECS: usize = 60;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TrafficSample {
+    pub at: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TrafficHistory {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub samples: Vec<TrafficSample>,
+}
+
+#[derive(Default)]
+struct IfaceCounters {
+    rx_bytes: u64,
+    tx_bytes: u64,
+    samples: VecDeque<TrafficSample>,
+}
+
+impl IfaceCounters {
+    fn sample(&mut self, now: u64) -> &mut TrafficSample {
+        if self.samples.back().map(|sample| sample.at) != Some(now) {
+            if self.samples.len() == TRAFFIC_HISTORY_SECS {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(TrafficSample {
+                at: now,
+                ..Default::default()
+            });
+        }
+        self.samples.back_mut().expect("sample just pushed")
+    }
+
+    // Seconds without traffic have no stored sample; they are filled with
+    // zeros so every history covers the same window.
+    fn history(&self, now: u64) -> TrafficHistory {
+        let start = now.saturating_sub(TRAFFIC_HISTORY_SECS as u64 - 1);
+        let samples = (start..=now)
+            .map(|at| {
+                self.samples
+                    .iter()
+                    .find(|sample| sample.at == at)
+                    .copied()
+                    .unwrap_or(TrafficSample {
+                        at,
+                        ..Default::default()
+                    })
+            })
+            .collect();
+        TrafficHistory {
+            rx_bytes: self.rx_bytes,
+            tx_bytes: self.tx_bytes,
+            samples,
+        }
+    }
+}
+
+#[derive(Default)]
+struct TrafficState {
+    ifaces: HashMap<AddressHash, IfaceCounters>,
+    parents: HashMap<AddressHash, AddressHash>,
+}
+
+impl TrafficState {
+    // Connections accepted by a listening interface count towards it.
+    fn counters(&mut self, iface: AddressHash) -> &mut IfaceCounters {
+        let iface = self.parents.get(&iface).copied().unwrap_or(iface);
+        self.ifaces.entry(iface).or_default()
+    }
+}
+
+// Per-interface byte counters with a rolling one-second history.
+#[derive(Clone, Default)]
+pub struct InterfaceTraffic {
+    state: Arc<Mutex<TrafficState>>,
+}
+
+impl InterfaceTraffic {
+    pub fn record_rx(&self, iface: AddressHash, bytes: usize) {
+        self.record_rx_at(iface, bytes, now_secs());
+    }
+
+    pub fn record_tx(&self, iface: AddressHash, bytes: usize) {
+        self.record_tx_at(iface, bytes, now_secs());
+    }
+
+    pub fn record_rx_at(&self, iface: AddressHash, bytes: usize, now: u64) {
+        let mut state = self.state.lock().expect("interface traffic mutex poisoned");
+        let counters = state.counters(iface);
+        counters.rx_bytes += bytes as u64;
+        counters.sample(now).rx_bytes += bytes as u64;
+    }
+
+    pub fn record_tx_at(&self, iface: AddressHash, bytes: usize, now: u64) {
+        let mut state = self.state.lock().expect("interface traffic mutex poisoned");
+        let counters = state.counters(iface);
+        counters.tx_bytes += bytes as u64;
+        counters.sample(now).tx_bytes += bytes as u64;
+    }
+
+    pub fn history(&self, iface: &AddressHash) -> TrafficHistory {
+        self.history_at(iface, now_secs())
+    }
+
+    pub fn history_at(&self, iface: &AddressHash, now: u64) -> TrafficHistory {
+        let state = self.state.lock().expect("interface traffic mutex poisoned");
+        state
+            .ifaces
+            .get(iface)
+            .map(|counters| counters.history(now))
+            .unwrap_or_else(|| IfaceCounters::default().history(now))
+    }
+
+    pub(super) fn set_parent(&self, child: AddressHash, parent: AddressHash) {
+        self.state
+            .lock()
+            .expect("interface traffic mutex poisoned")
+            .parents
+            .insert(child, parent);
+    }
+
+    pub(super) fn retain(&self, keep: impl Fn(&AddressHash) -> bool) {
+        let mut state = self.state.lock().expect("interface traffic mutex poisoned");
+        state.ifaces.retain(|iface, _| keep(iface));
+        state.parents.retain(|child, _| keep(child));
+    }
+}
+
+// Size of the packet as framed on the wire, without serializing it.
+pub fn packet_wire_len(packet: &Packet) -> usize {
+    let transport = match packet.header.header_type {
+        HeaderType::Type2 => ADDRESS_HASH_SIZE,
+        HeaderType::Type1 => 0,
+    };
+    2 + transport + ADDRESS_HASH_SIZE + 1 + packet.data.len()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
@@ -14,6 +14,9 @@ use crate::hash::ADDRESS_HASH_SIZE;
 pub const PACKET_MDU: usize = 464usize;
 pub const LXMF_MAX_PAYLOAD: usize = PACKET_MDU - FERNET_OVERHEAD_SIZE - FERNET_MAX_PADDING_SIZE;
 pub const PACKET_IFAC_MAX_LENGTH: usize = 64usize;
+// Revision of the packet and announce wire format. Bumped on any change that
+// stops peers on the previous revision from understanding us.
+pub const RNS_PROTOCOL_VERSION: u32 = 1;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum IfacFlag {
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::Read;
+use std::sync::{Arc, Mutex};
 use tokio::time::{Duration, Instant};
 
 use bzip2::read::BzDecoder;
@@ -38,7 +39,15 @@ const FLAG_REQUEST: u8 = 0x08;
 const FLAG_RESPONSE: u8 = 0x10;
 const FLAG_METADATA: u8 = 0x20;
 
-const METADATA_MAX_SIZE: usize = 16 * 1024 * 1024 - 1;
+pub const METADATA_MAX_SIZE: usize = 16 * 1024 * 1024 - 1;
+
+// Advertisements held back while every incoming slot is busy. Anything past
+// this is dropped; the sender re-advertises or gives up on its own.
+const MAX_QUEUED_ADVERTISEMENTS: usize = 64;
+
+// Progress samples kept for the rolling transfer rate. A handful of request
+// windows smooths out bursts without lagging far behind a change in speed.
+const RATE_SAMPLES: usize = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResourceStatus {
@@ -76,6 +85,7 @@ pub struct ResourceEvent {
 pub enum ResourceEventKind {
     Progress(ResourceProgress),
     Complete(ResourceComplete),
+    OutboundProgress(ResourceSendProgress),
     OutboundComplete,
 }
 
@@ -85,6 +95,63 @@ pub struct ResourceProgress {
     pub total_bytes: u64,
     pub received_parts: usize,
     pub total_parts: usize,
+    pub bytes_per_sec: Option<f64>,
+}
+
+impl ResourceProgress {
+    pub fn eta_secs(&self) -> Option<f64> {
+        eta_secs(
+            self.total_bytes.saturating_sub(self.received_bytes),
+            self.bytes_per_sec,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceSendProgress {
+    pub sent_bytes: u64,
+    pub total_bytes: u64,
+    pub sent_parts: usize,
+    // Counts every part the receiver asked for, including re-requests.
+    pub requested_parts: usize,
+    pub total_parts: usize,
+    pub bytes_per_sec: Option<f64>,
+}
+
+impl ResourceSendProgress {
+    pub fn eta_secs(&self) -> Option<f64> {
+        eta_secs(
+            self.total_bytes.saturating_sub(self.sent_bytes),
+            self.bytes_per_sec,
+        )
+    }
+}
+
+fn eta_secs(remaining_bytes: u64, bytes_per_sec: Option<f64>) -> Option<f64> {
+    bytes_per_sec
+        .filter(|rate| *rate > 0.0)
+        .map(|rate| remaining_bytes as f64 / rate)
+}
+
+#[derive(Debug, Clone, Default)]
+struct TransferRate {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl TransferRate {
+    fn record(&mut self, at: Instant, transferred_bytes: u64) {
+        if self.samples.len() >= RATE_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, transferred_bytes));
+    }
+
+    fn bytes_per_sec(&self) -> Option<f64> {
+        let (first_at, first_bytes) = self.samples.front()?;
+        let (last_at, last_bytes) = self.samples.back()?;
+        let elapsed = last_at.duration_since(*first_at).as_secs_f64();
+        (elapsed > 0.0).then(|| last_bytes.saturating_sub(*first_bytes) as f64 / elapsed)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -306,22 +373,38 @@ impl ResourceProof {
 #[derive(Debug, Clone)]
 struct ResourceSender {
     resource_hash: Hash,
+    link_id: AddressHash,
     random_hash: [u8; RANDOM_HASH_SIZE],
     original_hash: Hash,
     parts: Vec<Vec<u8>>,
     map_hashes: Vec<[u8; MAPHASH_LEN]>,
+    // First part index of each map hash; requests name parts by hash.
+    map_index: HashMap<[u8; MAPHASH_LEN], usize>,
     expected_proof: Hash,
     data_size: u64,
     has_metadata: bool,
+    sent: Vec<bool>,
+    sent_parts: usize,
+    sent_bytes: u64,
+    requested_parts: usize,
+    rate: TransferRate,
     status: ResourceStatus,
+    // Last advertisement or request; a receiver that goes quiet for the
+    // whole retry budget has given up.
+    last_activity: Instant,
 }
 
 impl ResourceSender {
-    fn new(link: &Link, data: Vec<u8>, metadata: Option<Vec<u8>>) -> Result<Self, RnsError> {
+    fn new(
+        link: &Link,
+        data: Vec<u8>,
+        metadata: Option<Vec<u8>>,
+        metadata_max_size: usize,
+    ) -> Result<Self, RnsError> {
         let has_metadata = metadata.is_some();
         let metadata_prefix = if let Some(payload) = metadata.as_ref() {
-            if payload.len() > METADATA_MAX_SIZE {
-                return Err(RnsError::InvalidArgument);
+            if payload.len() > metadata_max_size {
+                return Err(RnsError::MetadataTooLarge);
             }
             let size = payload.len() as u32;
             let size_bytes = size.to_be_bytes();
@@ -361,20 +444,31 @@ impl ResourceSender {
         }
 
         let mut map_hashes = Vec::with_capacity(parts.len());
-        for part in &parts {
-            map_hashes.push(map_hash(part, &random_hash));
+        let mut map_index = HashMap::with_capacity(parts.len());
+        for (index, part) in parts.iter().enumerate() {
+            let hash = map_hash(part, &random_hash);
+            map_index.entry(hash).or_insert(index);
+            map_hashes.push(hash);
         }
 
         Ok(Self {
             resource_hash,
+            link_id: *link.id(),
             random_hash,
             original_hash: resource_hash,
+            sent: vec![false; parts.len()],
             parts,
             map_hashes,
+            map_index,
             expected_proof,
             data_size,
             has_metadata,
+            sent_parts: 0,
+            sent_bytes: 0,
+            requested_parts: 0,
+            rate: TransferRate::default(),
             status: ResourceStatus::Advertised,
+            last_activity: Instant::now(),
         })
     }
 
@@ -405,25 +499,34 @@ impl ResourceSender {
         }
 
         let mut packets = Vec::new();
+        self.last_activity = Instant::now();
+        self.requested_parts += request.requested_hashes.len();
+        if self.rate.samples.is_empty() {
+            self.rate.record(Instant::now(), 0);
+        }
         for hash in &request.requested_hashes {
-            if let Some(index) = self.map_hashes.iter().position(|entry| entry == hash) {
+            if let Some(&index) = self.map_index.get(hash) {
                 if let Some(part) = self.parts.get(index) {
                     if let Ok(packet) =
                         build_link_packet(link, PacketType::Data, PacketContext::Resource, part)
                     {
                         packets.push(packet);
+                        if !self.sent[index] {
+                            self.sent[index] = true;
+                            self.sent_parts += 1;
+                            self.sent_bytes = self.sent_bytes.saturating_add(part.len() as u64);
+                        }
                     } else {
                         log::warn!("resource: failed to build resource packet");
                     }
                 }
             }
         }
+        self.rate.record(Instant::now(), self.sent_bytes);
 
         if request.hashmap_exhausted {
             if let Some(last_hash) = request.last_map_hash {
-                if let Some(last_index) =
-                    self.map_hashes.iter().position(|entry| *entry == last_hash)
-                {
+                if let Some(&last_index) = self.map_index.get(&last_hash) {
                     let next_segment = (last_index / HASHMAP_MAX_LEN) + 1;
                     if next_segment * HASHMAP_MAX_LEN < self.map_hashes.len() {
                         let update = ResourceHashUpdate {
@@ -456,6 +559,17 @@ impl ResourceSender {
         packets
     }
 
+    fn progress(&self) -> ResourceSendProgress {
+        ResourceSendProgress {
+            sent_bytes: self.sent_bytes,
+            total_bytes: self.parts.iter().map(|part| part.len() as u64).sum(),
+            sent_parts: self.sent_parts,
+            requested_parts: self.requested_parts,
+            total_parts: self.parts.len(),
+            bytes_per_sec: self.rate.bytes_per_sec(),
+        }
+    }
+
     fn handle_proof(&mut self, proof: &ResourceProof) -> bool {
         if proof.resource_hash != self.resource_hash {
             return false;
@@ -475,6 +589,11 @@ struct ResourceReceiver {
     random_hash: [u8; RANDOM_HASH_SIZE],
     parts: Vec<Option<Vec<u8>>>,
     hashmap: Vec<Option<[u8; MAPHASH_LEN]>>,
+    // Lowest known index of each map hash, so parts are matched without
+    // scanning the whole hashmap.
+    hashmap_index: HashMap<[u8; MAPHASH_LEN], usize>,
+    // Every part below this index has been received.
+    consecutive: usize,
     received: usize,
     received_bytes: u64,
     total_bytes: u64,
@@ -482,9 +601,11 @@ struct ResourceReceiver {
     compressed: bool,
     split: bool,
     has_metadata: bool,
+    metadata_max_size: usize,
     last_progress: Instant,
     last_request: Instant,
     retry_count: u8,
+    rate: TransferRate,
     status: ResourceStatus,
 }
 
@@ -502,7 +623,7 @@ enum PartOutcome {
 }
 
 impl ResourceReceiver {
-    fn new(adv: &ResourceAdvertisement, link_id: AddressHash) -> Self {
+    fn new(adv: &ResourceAdvertisement, link_id: AddressHash, metadata_max_size: usize) -> Self {
         let now = Instant::now();
         let total_parts = adv.parts as usize;
         let mut receiver = Self {
@@ -511,6 +632,8 @@ impl ResourceReceiver {
             random_hash: adv.random_hash,
             parts: vec![None; total_parts],
             hashmap: vec![None; total_parts],
+            hashmap_index: HashMap::new(),
+            consecutive: 0,
             received: 0,
             received_bytes: 0,
             total_bytes: adv.transfer_size,
@@ -518,11 +641,14 @@ impl ResourceReceiver {
             compressed: adv.compressed(),
             split: (adv.flags & FLAG_SPLIT) == FLAG_SPLIT,
             has_metadata: (adv.flags & FLAG_METADATA) == FLAG_METADATA,
+            metadata_max_size,
             last_progress: now,
             last_request: now,
             retry_count: 0,
+            rate: TransferRate::default(),
             status: ResourceStatus::Advertised,
         };
+        receiver.rate.record(now, 0);
         receiver.apply_hashmap_segment(adv.segment_index.saturating_sub(1) as usize, &adv.hashmap);
         receiver
     }
@@ -536,16 +662,23 @@ impl ResourceReceiver {
             let idx = segment * HASHMAP_MAX_LEN + i;
             if idx < self.hashmap.len() {
                 self.hashmap[idx] = Some(entry);
+                self.hashmap_index
+                    .entry(entry)
+                    .and_modify(|known| *known = (*known).min(idx))
+                    .or_insert(idx);
             }
         }
     }
 
     fn build_request(&self) -> ResourceRequest {
         let mut requested = Vec::new();
-        let mut last_known: Option<[u8; MAPHASH_LEN]> = None;
+        let mut last_known = self
+            .consecutive
+            .checked_sub(1)
+            .and_then(|idx| self.hashmap[idx]);
         let mut hashmap_exhausted = false;
 
-        for (idx, entry) in self.hashmap.iter().enumerate() {
+        for (idx, entry) in self.hashmap.iter().enumerate().skip(self.consecutive) {
             if let Some(hash) = entry {
                 last_known = Some(*hash);
                 if self.parts[idx].is_none() {
@@ -582,19 +715,23 @@ impl ResourceReceiver {
         }
 
         let hash = map_hash(part, &self.random_hash);
-        let Some(index) = self
-            .hashmap
-            .iter()
-            .position(|entry| entry.as_ref() == Some(&hash))
-        else {
+        let Some(&index) = self.hashmap_index.get(&hash) else {
             return PartOutcome::NoMatch;
         };
 
         if self.parts[index].is_none() {
             self.parts[index] = Some(part.to_vec());
+            while self
+                .parts
+                .get(self.consecutive)
+                .is_some_and(|part| part.is_some())
+            {
+                self.consecutive += 1;
+            }
             self.received += 1;
             self.received_bytes = self.received_bytes.saturating_add(part.len() as u64);
             self.last_progress = Instant::now();
+            self.rate.record(self.last_progress, self.received_bytes);
         }
 
         if self.received == self.parts.len() && !self.parts.is_empty() {
@@ -641,7 +778,13 @@ impl ResourceReceiver {
                 let size = ((payload[0] as usize) << 16)
                     | ((payload[1] as usize) << 8)
                     | payload[2] as usize;
-                if size > METADATA_MAX_SIZE {
+                if size > self.metadata_max_size {
+                    log::warn!(
+                        "resource: metadata size {} exceeds limit {} for {}",
+                        size,
+                        self.metadata_max_size,
+                        self.resource_hash
+                    );
                     self.status = ResourceStatus::Failed;
                     return PartOutcome::Incomplete;
                 }
@@ -733,17 +876,57 @@ impl ResourceReceiver {
             total_bytes: self.total_bytes,
             received_parts: self.received,
             total_parts: self.parts.len(),
+            bytes_per_sec: self.rate.bytes_per_sec(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceCounts {
+    pub incoming: usize,
+    pub outgoing: usize,
+    pub queued: usize,
+    // None when transfers are not limited.
+    pub max_concurrent: Option<usize>,
+}
+
+// Transfer counts published by the manager so they can be read without
+// taking the transport lock.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLoad {
+    counts: Arc<Mutex<ResourceCounts>>,
+}
+
+impl ResourceLoad {
+    pub fn counts(&self) -> ResourceCounts {
+        *self.counts.lock().expect("resource load mutex poisoned")
+    }
+
+    fn publish(&self, counts: ResourceCounts) {
+        *self.counts.lock().expect("resource load mutex poisoned") = counts;
+    }
+}
+
+#[derive(Debug)]
+struct QueuedAdvertisement {
+    advertisement: ResourceAdvertisement,
+    link_id: AddressHash,
+    // When the advertisement last arrived; the sender gives up on it after
+    // the same retry budget as an idle transfer.
+    queued_at: Instant,
+}
+
 #[derive(Debug)]
 pub struct ResourceManager {
     outgoing: HashMap<Hash, ResourceSender>,
     incoming: HashMap<Hash, ResourceReceiver>,
+    queued: VecDeque<QueuedAdvertisement>,
     events: Vec<ResourceEvent>,
     retry_interval: Duration,
     retry_limit: u8,
+    metadata_max_size: usize,
+    max_concurrent: usize,
+    load: ResourceLoad,
 }
 
 impl ResourceManager {
@@ -755,19 +938,60 @@ impl ResourceManager {
         Self {
             outgoing: HashMap::new(),
             incoming: HashMap::new(),
+            queued: VecDeque::new(),
             events: Vec::new(),
             retry_interval,
             retry_limit,
+            metadata_max_size: METADATA_MAX_SIZE,
+            max_concurrent: 0,
+            load: ResourceLoad::default(),
         }
     }
 
+    // The wire format caps metadata at METADATA_MAX_SIZE, so larger limits are clamped.
+    pub fn set_metadata_max_size(&mut self, limit: usize) {
+        self.metadata_max_size = limit.min(METADATA_MAX_SIZE);
+    }
+
+    pub fn metadata_max_size(&self) -> usize {
+        self.metadata_max_size
+    }
+
+    // Caps transfers in each direction. Incoming advertisements past the cap
+    // wait in a queue until a slot frees up; new sends are refused. Zero
+    // removes the cap.
+    pub fn set_max_concurrent_transfers(&mut self, limit: usize) {
+        self.max_concurrent = limit;
+        self.publish_load();
+    }
+
+    pub fn load(&self) -> ResourceLoad {
+        self.load.clone()
+    }
+
+    fn at_capacity(&self, active: usize) -> bool {
+        self.max_concurrent > 0 && active >= self.max_concurrent
+    }
+
+    fn publish_load(&self) {
+        self.load.publish(ResourceCounts {
+            incoming: self.incoming.len(),
+            outgoing: self.outgoing.len(),
+            queued: self.queued.len(),
+            max_concurrent: (self.max_concurrent > 0).then_some(self.max_concurrent),
+        });
+    }
+
     pub fn start_send(
         &mut self,
         link: &Link,
         data: Vec<u8>,
         metadata: Option<Vec<u8>>,
     ) -> Result<(Hash, Packet), RnsError> {
-        let sender = ResourceSender::new(link, data, metadata)?;
+        if self.at_capacity(self.outgoing.len()) {
+            return Err(RnsError::ResourceLimitReached);
+        }
+        let sender = ResourceSender::new(link, data, metadata, self.metadata_max_size)?;
         let resource_hash = sender.resource_hash;
         let advertisement = sender.advertisement(0);
         let payload = advertisement.pack()?;
@@ -778,6 +1002,7 @@ impl ResourceManager {
             &payload,
         )?;
         self.outgoing.insert(resource_hash, sender);
+        self.publish_load();
         Ok((resource_hash, packet))
     }
 
@@ -801,11 +1026,62 @@ impl ResourceManager {
         for hash in failed {
             self.incoming.remove(&hash);
         }
+        let expiry = self.retry_interval * u32::from(self.retry_limit.max(1));
+        self.outgoing.retain(|hash, sender| {
+            let alive = now.duration_since(sender.last_activity) < expiry;
+            if !alive {
+                log::warn!("resource: {hash} got no request or proof in time, dropping it");
+            }
+            alive
+        });
+        self.queued.retain(|queued| {
+            let alive = now.duration_since(queued.queued_at) < expiry;
+            if !alive {
+                log::warn!(
+                    "resource: {} waited past its sender's deadline, dropping it",
+                    queued.advertisement.hash
+                );
+            }
+            alive
+        });
+        requests.extend(self.start_queued());
+        self.publish_load();
+        requests
+    }
+
+    // Forgets every transfer and queued advertisement on a link that closed;
+    // none of them can make progress without it.
+    pub fn drop_link(&mut self, link_id: &AddressHash) {
+        self.outgoing.retain(|_, sender| sender.link_id != *link_id);
+        self.incoming
+            .retain(|_, receiver| receiver.link_id != *link_id);
+        self.queued.retain(|queued| queued.link_id != *link_id);
+        self.publish_load();
+    }
+
+    // Moves queued advertisements into free incoming slots and returns the
+    // first request for each.
+    fn start_queued(&mut self) -> Vec<(AddressHash, ResourceRequest)> {
+        let mut requests = Vec::new();
+        while !self.at_capacity(self.incoming.len()) {
+            let Some(queued) = self.queued.pop_front() else {
+                break;
+            };
+            let mut receiver = ResourceReceiver::new(
+                &queued.advertisement,
+                queued.link_id,
+                self.metadata_max_size,
+            );
+            let request = receiver.build_request();
+            receiver.mark_request();
+            self.incoming.insert(queued.advertisement.hash, receiver);
+            requests.push((queued.link_id, request));
+        }
         requests
     }
 
     pub fn handle_packet(&mut self, packet: &Packet, link: &mut Link) -> Vec<Packet> {
-        match packet.context {
+        let packets = match packet.context {
             PacketContext::ResourceAdvrtisement => self.handle_advertisement(packet, link),
             PacketContext::ResourceRequest => self.handle_request(packet, link),
             PacketContext::ResourceHashUpdate => self.handle_hash_update(packet, link),
@@ -815,7 +1091,9 @@ impl ResourceManager {
                 self.cancel(packet)
             }
             _ => Vec::new(),
-        }
+        };
+        self.publish_load();
+        packets
     }
 
     fn handle_advertisement(&mut self, packet: &Packet, link: &mut Link) -> Vec<Packet> {
@@ -830,7 +1108,26 @@ impl ResourceManager {
             return Vec::new();
         }
         let resource_hash = advertisement.hash;
-        let mut receiver = ResourceReceiver::new(&advertisement, *link.id());
+        if !self.incoming.contains_key(&resource_hash) && self.at_capacity(self.incoming.len()) {
+            let already_queued = self
+                .queued
+                .iter_mut()
+                .find(|queued| queued.advertisement.hash == resource_hash);
+            if let Some(queued) = already_queued {
+                queued.queued_at = Instant::now();
+            } else if self.queued.len() >= MAX_QUEUED_ADVERTISEMENTS {
+                log::warn!("resource: advertisement queue full, dropping advertisement");
+            } else {
+                self.queued.push_back(QueuedAdvertisement {
+                    advertisement,
+                    link_id: *link.id(),
+                    queued_at: Instant::now(),
+                });
+            }
+            return Vec::new();
+        }
+        let mut receiver =
+            ResourceReceiver::new(&advertisement, *link.id(), self.metadata_max_size);
         let request = receiver.build_request();
         receiver.mark_request();
         self.incoming.insert(resource_hash, receiver);
@@ -852,11 +1149,19 @@ impl ResourceManager {
         let Ok(request) = ResourceRequest::decode(packet.data.as_slice()) else {
             return Vec::new();
         };
-        if let Some(sender) = self.outgoing.get_mut(&request.resource_hash) {
-            sender.handle_request(&request, link)
-        } else {
-            Vec::new()
+        let Some(sender) = self.outgoing.get_mut(&request.resource_hash) else {
+            return Vec::new();
+        };
+        let sent_before = sender.sent_parts;
+        let packets = sender.handle_request(&request, link);
+        if sender.sent_parts > sent_before {
+            self.events.push(ResourceEvent {
+                hash: request.resource_hash,
+                link_id: *link.id(),
+                kind: ResourceEventKind::OutboundProgress(sender.progress()),
+            });
         }
+        packets
     }
 
     fn handle_hash_update(&mut self, packet: &Packet, link: &mut Link) -> Vec<Packet> {
@@ -967,6 +1272,8 @@ impl ResourceManager {
             let hash = Hash::new(hash_bytes);
             self.incoming.remove(&hash);
             self.outgoing.remove(&hash);
+            self.queued
+                .retain(|queued| queued.advertisement.hash != hash);
         }
         Vec::new()
     }
@@ -1080,8 +1387,29 @@ mod tests {
         let data = vec![0u8; 4];
         let metadata = vec![0u8; METADATA_MAX_SIZE + 1];
 
-        let result = ResourceSender::new(&link, data, Some(metadata));
-        assert!(matches!(result, Err(RnsError::InvalidArgument)));
+        let result = ResourceSender::new(&link, data, Some(metadata), METADATA_MAX_SIZE);
+        assert!(matches!(result, Err(RnsError::MetadataTooLarge)));
+    }
+
+    #[test]
+    fn resource_manager_enforces_configured_metadata_limit() {
+        let signer = PrivateIdentity::new_from_rand(OsRng);
+        let identity = *signer.as_identity();
+        let destination = DestinationDesc {
+            identity,
+            address_hash: identity.address_hash,
+            name: DestinationName::new("lxmf", "resource"),
+        };
+        let (tx, _) = tokio::sync::broadcast::channel(1);
+        let link = Link::new(destination, tx);
+        let mut manager = ResourceManager::new();
+        manager.set_metadata_max_size(64);
+
+        let result = manager.start_send(&link, vec![0u8; 4], Some(vec![0u8; 65]));
+        assert!(matches!(result, Err(RnsError::MetadataTooLarge)));
+
+        manager.set_metadata_max_size(usize::MAX);
+        assert_eq!(manager.metadata_max_size(), METADATA_MAX_SIZE);
     }
 
     #[test]
@@ -1125,4 +1453,246 @@ mod tests {
         assert!(responses.is_empty());
         assert!(manager.incoming.is_empty());
     }
+
+    #[test]
+    fn advertisements_past_the_transfer_limit_are_queued() {
+        let signer = PrivateIdentity::new_from_rand(OsRng);
+        let identity = *signer.as_identity();
+        let destination = DestinationDesc {
+            identity,
+            address_hash: identity.address_hash,
+            name: DestinationName::new("lxmf", "resource"),
+        };
+        let (tx, _) = tokio::sync::broadcast::channel(1);
+        let mut link = Link::new(destination, tx);
+        link.request();
+        let advertisement = |seed: u8| {
+            let hash = Hash::new_from_slice(&[seed]);
+            ResourceAdvertisement {
+                transfer_size: 1,
+                data_size: 1,
+                parts: 1,
+                hash,
+                random_hash: [0u8; RANDOM_HASH_SIZE],
+                original_hash: hash,
+                segment_index: 1,
+                total_segments: 1,
+                request_id: None,
+                flags: 0,
+                hashmap: vec![0u8; MAPHASH_LEN],
+            }
+        };
+        // Link payloads reach the manager already decrypted.
+        let packet = |context: PacketContext, data: &[u8]| Packet {
+            context,
+            data: PacketDataBuffer::new_from_slice(data),
+            ..Default::default()
+        };
+
+        let mut manager = ResourceManager::new_with_config(Duration::from_secs(1), 5);
+        manager.set_max_concurrent_transfers(1);
+        let first = advertisement(1);
+        let second = advertisement(2);
+        let started = manager.handle_packet(
+            &packet(PacketContext::ResourceAdvrtisement, &first.pack().unwrap()),
+            &mut link,
+        );
+        assert_eq!(started.len(), 1);
+        let deferred = manager.handle_packet(
+            &packet(PacketContext::ResourceAdvrtisement, &second.pack().unwrap()),
+            &mut link,
+        );
+        assert!(deferred.is_empty());
+        assert!(!manager.incoming.contains_key(&second.hash));
+        assert_eq!(
+            manager.load().counts(),
+            ResourceCounts {
+                incoming: 1,
+                outgoing: 0,
+                queued: 1,
+                max_concurrent: Some(1),
+            }
+        );
+        assert!(manager.start_send(&link, vec![1u8; 4], None).is_ok());
+        assert!(matches!(
+            manager.start_send(&link, vec![2u8; 4], None),
+            Err(RnsError::ResourceLimitReached)
+        ));
+
+        manager.handle_packet(
+            &packet(
+                PacketContext::ResourceInitiatorCancel,
+                first.hash.as_slice(),
+            ),
+            &mut link,
+        );
+        let requests = manager.retry_requests(Instant::now());
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].1.resource_hash, second.hash);
+        assert!(manager.incoming.contains_key(&second.hash));
+        assert_eq!(manager.load().counts().queued, 0);
+
+        // The sender stops waiting after retry_interval × retry_limit, so a
+        // queued advertisement is dropped on that deadline instead of
+        // starting a transfer nobody will serve.
+        let third = advertisement(3);
+        manager.handle_packet(
+            &packet(PacketContext::ResourceAdvrtisement, &third.pack().unwrap()),
+            &mut link,
+        );
+        assert_eq!(manager.load().counts().queued, 1);
+        manager.retry_requests(Instant::now() + Duration::from_secs(6));
+        assert_eq!(manager.load().counts().queued, 0);
+        assert!(!manager.incoming.contains_key(&third.hash));
+    }
+
+    #[test]
+    fn idle_and_orphaned_senders_are_dropped() {
+        let signer = PrivateIdentity::new_from_rand(OsRng);
+        let identity = *signer.as_identity();
+        let destination = DestinationDesc {
+            identity,
+            address_hash: identity.address_hash,
+            name: DestinationName::new("lxmf", "resource"),
+        };
+        let (tx, _) = tokio::sync::broadcast::channel(1);
+        let mut link = Link::new(destination, tx);
+        link.request();
+
+        let mut manager = ResourceManager::new_with_config(Duration::from_secs(1), 3);
+        let (idle, _) = manager.start_send(&link, vec![1u8; 4], None).unwrap();
+        manager.retry_requests(Instant::now() + Duration::from_secs(2));
+        assert!(manager.outgoing.contains_key(&idle));
+        manager.retry_requests(Instant::now() + Duration::from_secs(3));
+        assert!(!manager.outgoing.contains_key(&idle));
+
+        let (orphan, _) = manager.start_send(&link, vec![2u8; 4], None).unwrap();
+        manager.queued.push_back(QueuedAdvertisement {
+            advertisement: manager.outgoing[&orphan].advertisement(0),
+            link_id: *link.id(),
+            queued_at: Instant::now(),
+        });
+        manager.drop_link(link.id());
+        assert!(manager.outgoing.is_empty());
+        assert_eq!(manager.load().counts().queued, 0);
+    }
+
+    #[test]
+    fn transfer_rate_follows_recent_samples() {
+        let start = Instant::now();
+        let mut rate = TransferRate::default();
+        rate.record(start, 0);
+        assert!(rate.bytes_per_sec().is_none());
+        rate.record(start + Duration::from_secs(2), 1_000);
+        assert_eq!(rate.bytes_per_sec(), Some(500.0));
+
+        let progress = ResourceProgress {
+            received_bytes: 1_000,
+            total_bytes: 3_000,
+            received_parts: 2,
+            total_parts: 6,
+            bytes_per_sec: rate.bytes_per_sec(),
+        };
+        assert_eq!(progress.eta_secs(), Some(4.0));
+
+        // Once the slow start falls out of the window only the faster recent
+        // samples count.
+        for step in 1..=RATE_SAMPLES as u64 {
+            rate.record(start + Duration::from_secs(2 + step), 1_000 + step * 2_000);
+        }
+        assert_eq!(rate.bytes_per_sec(), Some(2_000.0));
+    }
+
+    #[test]
+    fn resource_request_emits_sender_progress() {
+        let signer = PrivateIdentity::new_from_rand(OsRng);
+        let identity = *signer.as_identity();
+        let destination = DestinationDesc {
+            identity,
+            address_hash: identity.address_hash,
+            name: DestinationName::new("lxmf", "resource"),
+        };
+        let (tx, _) = tokio::sync::broadcast::channel(1);
+        let mut link = Link::new(destination, tx);
+        link.request();
+        let mut manager = ResourceManager::new();
+        let (hash, _) = manager
+            .start_send(&link, vec![7u8; PACKET_MDU * 3], None)
+            .expect("start send");
+        let sender = manager.outgoing.get(&hash).expect("sender");
+        let total_parts = sender.parts.len();
+        let request = ResourceRequest {
+            hashmap_exhausted: false,
+            last_map_hash: None,
+            resource_hash: hash,
+            requested_hashes: sender.map_hashes[..2].to_vec(),
+        };
+        // Link payloads reach the manager already decrypted.
+        let packet = Packet {
+            context: PacketContext::ResourceRequest,
+            data: PacketDataBuffer::new_from_slice(&request.encode()),
+            ..Default::default()
+        };
+
+        let parts = manager.handle_packet(&packet, &mut link);
+        assert_eq!(parts.len(), 2);
+        let events = manager.drain_events();
+        let [ResourceEvent {
+            kind: ResourceEventKind::OutboundProgress(progress),
+            ..
+        }] = events.as_slice()
+        else {
+            panic!("expected a single outbound progress event");
+        };
+        assert_eq!(progress.sent_parts, 2);
+        assert_eq!(progress.requested_parts, 2);
+        assert_eq!(progress.total_parts, total_parts);
+        assert!(progress.sent_bytes > 0 && progress.sent_bytes < progress.total_bytes);
+    }
+
+    #[test]
+    fn receiver_matches_parts_by_index_and_requests_past_the_received_prefix() {
+        let signer = PrivateIdentity::new_from_rand(OsRng);
+        let identity = *signer.as_identity();
+        let destination = DestinationDesc {
+            identity,
+            address_hash: identity.address_hash,
+            name: DestinationName::new("lxmf", "resource"),
+        };
+        let (tx, _) = tokio::sync::broadcast::channel(1);
+        let mut link = Link::new(destination, tx);
+        link.request();
+        let data: Vec<u8> = (0..PACKET_MDU * 8).map(|index| index as u8).collect();
+        let sender =
+            ResourceSender::new(&link, data.clone(), None, METADATA_MAX_SIZE).expect("sender");
+        let mut receiver =
+            ResourceReceiver::new(&sender.advertisement(0), *link.id(), METADATA_MAX_SIZE);
+
+        for index in [1, 0, 3] {
+            assert!(matches!(
+                receiver.handle_part(&sender.parts[index], &link),
+                PartOutcome::Incomplete
+            ));
+        }
+        assert_eq!(receiver.consecutive, 2);
+        let request = receiver.build_request();
+        assert_eq!(
+            request.requested_hashes,
+            [2, 4, 5, 6].map(|index| sender.map_hashes[index])
+        );
+        assert!(matches!(
+            receiver.handle_part(b"not a part", &link),
+            PartOutcome::NoMatch
+        ));
+
+        let remaining = (0..sender.parts.len()).filter(|index| ![0, 1, 3].contains(index));
+        let mut outcome = PartOutcome::NoMatch;
+        for index in remaining {
+            outcome = receiver.handle_part(&sender.parts[index], &link);
+        }
+        let PartOutcome::Complete(_, payload) = outcome else {
+            panic!("expected the transfer to complete");
+        };
+        assert_eq!(payload.data, data);
+    }
 }
@@ -0,0 +1,289 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+use sha2::{Digest, Sha256};
+
+use crate::storage::messages::MessageRecord;
+
+// Carried by each message of an attachment split across opportunistic
+// sends. Every piece names its transfer, position and the piece count. The
+// first piece holds no data; it rides with the title and content and carries
+// the filename, total size and SHA-256 of the whole file.
+pub const FIELD_ATTACHMENT_CHUNK: &str = "attachment_chunk";
+// Raw bytes per data piece; base64 and the field keys bring a piece to about
+// 270 bytes of fields, inside a single LXMF packet.
+pub const ATTACHMENT_CHUNK_BYTES: usize = 144;
+pub const MAX_CHUNKED_ATTACHMENT_BYTES: usize = 32 * 1024;
+pub const MAX_ATTACHMENT_CHUNKS: usize = 256;
+
+const FIELD_FILE_ATTACHMENTS: &str = "5";
+// Clients name the LXMF attachment field either way; see the wire encoder.
+const ATTACHMENT_FIELD_KEYS: &[&str] = &[FIELD_FILE_ATTACHMENTS, "attachments", "files"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment {
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentChunk {
+    pub transfer_id: String,
+    pub index: usize,
+    pub total: usize,
+    pub filename: Option<String>,
+    pub size: Option<usize>,
+    pub sha256: Option<String>,
+    pub data: Vec<u8>,
+}
+
+// The attachments a fields map carries, under any of the accepted keys.
+pub fn attachment_entries(fields: Option<&JsonValue>) -> Vec<&JsonValue> {
+    let Some(map) = fields.and_then(JsonValue::as_object) else {
+        return Vec::new();
+    };
+    ATTACHMENT_FIELD_KEYS
+        .iter()
+        .filter_map(|key| map.get(*key).and_then(JsonValue::as_array))
+        .flatten()
+        .collect()
+}
+
+// Accepts `[filename, data]` or `{filename|name, data}` with data as a byte
+// array, hex or base64.
+pub fn parse_attachment(entry: &JsonValue) -> io::Result<Attachment> {
+    let (filename, data) = match entry {
+        JsonValue::Array(items) if items.len() >= 2 => (items[0].as_str(), &items[1]),
+        JsonValue::Object(map) => (
+            map.get("filename")
+                .or_else(|| map.get("name"))
+                .and_then(JsonValue::as_str),
+            map.get("data").unwrap_or(&JsonValue::Null),
+        ),
+        _ => (None, &JsonValue::Null),
+    };
+    let data = match data {
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| item.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>(),
+        JsonValue::String(text) => hex::decode(text.trim())
+            .ok()
+            .or_else(|| BASE64_STANDARD.decode(text.trim()).ok()),
+        _ => None,
+    };
+    match (filename, data) {
+        (Some(filename), Some(data)) => Ok(Attachment {
+            filename: filename.to_string(),
+            data,
+        }),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "attachment must be [filename, data] with data as bytes, hex or base64",
+        )),
+    }
+}
+
+// Removes every attachment field, leaving the rest of the map to ride on
+// the first piece.
+pub fn without_attachments(fields: Option<&JsonValue>) -> JsonMap<String, JsonValue> {
+    let mut map = fields
+        .and_then(JsonValue::as_object)
+        .cloned()
+        .unwrap_or_default();
+    for key in ATTACHMENT_FIELD_KEYS {
+        map.remove(*key);
+    }
+    map
+}
+
+// Field values for each piece of attachment, in order.
+pub fn split_attachment(transfer_id: &str, attachment: &Attachment) -> io::Result<Vec<JsonValue>> {
+    if attachment.data.is_empty() || attachment.data.len() > MAX_CHUNKED_ATTACHMENT_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("chunked attachments must be 1..={MAX_CHUNKED_ATTACHMENT_BYTES} bytes"),
+        ));
+    }
+    let total = 1 + attachment.data.len().div_ceil(ATTACHMENT_CHUNK_BYTES);
+    if total > MAX_ATTACHMENT_CHUNKS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("attachment would need more than {MAX_ATTACHMENT_CHUNKS} chunks"),
+        ));
+    }
+    let head = json!({
+        "transfer_id": transfer_id,
+        "index": 0,
+        "total": total,
+        "data": "",
+        "filename": attachment.filename,
+        "size": attachment.data.len(),
+        "sha256": hex::encode(Sha256::digest(&attachment.data)),
+    });
+    let pieces = attachment
+        .data
+        .chunks(ATTACHMENT_CHUNK_BYTES)
+        .enumerate()
+        .map(|(index, data)| {
+            json!({
+                "transfer_id": transfer_id,
+                "index": index + 1,
+                "total": total,
+                "data": BASE64_STANDARD.encode(data),
+            })
+        });
+    Ok(std::iter::once(head).chain(pieces).collect())
+}
+
+// None when fields carry no piece; Err for a piece that can't be used.
+pub fn parse_chunk(fields: Option<&JsonValue>) -> Option<io::Result<AttachmentChunk>> {
+    let chunk = fields?.as_object()?.get(FIELD_ATTACHMENT_CHUNK)?;
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let number = |key: &str| {
+        chunk
+            .get(key)
+            .and_then(JsonValue::as_u64)
+            .and_then(|value| usize::try_from(value).ok())
+    };
+    let text = |key: &str| {
+        chunk
+            .get(key)
+            .and_then(JsonValue::as_str)
+            .map(str::to_string)
+    };
+    let parsed = (|| {
+        let transfer_id = text("transfer_id")
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| invalid("attachment chunk without transfer_id"))?;
+        let (Some(index), Some(total)) = (number("index"), number("total")) else {
+            return Err(invalid("attachment chunk without index and total"));
+        };
+        if total == 0 || total > MAX_ATTACHMENT_CHUNKS || index >= total {
+            return Err(invalid("attachment chunk position out of range"));
+        }
+        let size = number("size");
+        if size.is_some_and(|size| size > MAX_CHUNKED_ATTACHMENT_BYTES) {
+            return Err(invalid("chunked attachment too large"));
+        }
+        let data = text("data")
+            .and_then(|data| BASE64_STANDARD.decode(data).ok())
+            .filter(|data| data.len() <= ATTACHMENT_CHUNK_BYTES)
+            .ok_or_else(|| invalid("attachment chunk data is not valid base64"))?;
+        Ok(AttachmentChunk {
+            transfer_id,
+            index,
+            total,
+            filename: text("filename"),
+            size,
+            sha256: text("sha256"),
+            data,
+        })
+    })();
+    Some(parsed)
+}
+
+// Pieces of an outbound transfer whose receipts are still due. The message
+// they belong to is delivered once every piece is.
+#[derive(Debug)]
+pub struct OutboundChunkTransfer {
+    pub peer: String,
+    pub total: usize,
+    pub pending: HashSet<String>,
+}
+
+// Pieces of one inbound transfer collected so far. The record that carried
+// the first piece supplies the title, content and other fields of the
+// reassembled message.
+#[derive(Debug)]
+pub struct ChunkAssembly {
+    total: usize,
+    head: Option<(AttachmentChunk, MessageRecord)>,
+    parts: BTreeMap<usize, Vec<u8>>,
+    pub updated_at: i64,
+}
+
+impl ChunkAssembly {
+    pub fn new(total: usize, now: i64) -> Self {
+        Self {
+            total,
+            head: None,
+            parts: BTreeMap::new(),
+            updated_at: now,
+        }
+    }
+
+    pub fn add(
+        &mut self,
+        chunk: AttachmentChunk,
+        record: MessageRecord,
+        now: i64,
+    ) -> io::Result<()> {
+        if chunk.total != self.total {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "attachment chunk disagrees on the piece count",
+            ));
+        }
+        self.updated_at = now;
+        self.parts.insert(chunk.index, chunk.data.clone());
+        if chunk.index == 0 {
+            self.head = Some((chunk, record));
+        }
+        Ok(())
+    }
+
+    pub fn received(&self) -> usize {
+        self.parts.len()
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.parts.len() == self.total && self.head.is_some()
+    }
+
+    // Joins the pieces back into one message carrying the file as a
+    // regular LXMF attachment, after checking size and digest.
+    pub fn finish(self) -> io::Result<MessageRecord> {
+        let Some((head, mut record)) = self.head else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "attachment transfer is missing its first piece",
+            ));
+        };
+        let data: Vec<u8> = self.parts.into_values().flatten().collect();
+        let invalid =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        if head.size.is_some_and(|size| size != data.len()) {
+            return Err(invalid("reassembled attachment has the wrong size"));
+        }
+        if head
+            .sha256
+            .as_deref()
+            .is_some_and(|digest| !digest.eq_ignore_ascii_case(&hex::encode(Sha256::digest(&data))))
+        {
+            return Err(invalid("reassembled attachment failed its digest check"));
+        }
+        let mut fields = record
+            .fields
+            .take()
+            .and_then(|fields| match fields {
+                JsonValue::Object(map) => Some(map),
+                _ => None,
+            })
+            .unwrap_or_default();
+        fields.remove(FIELD_ATTACHMENT_CHUNK);
+        fields.insert(
+            FIELD_FILE_ATTACHMENTS.into(),
+            json!([[head.filename.unwrap_or_default(), data]]),
+        );
+        record.fields = Some(JsonValue::Object(fields));
+        Ok(record)
+    }
+}
@@ -3,8 +3,38 @@ use std::io::{self, ErrorKind};
 use rmp_serde::{from_slice, to_vec};
 use serde::{de::DeserializeOwned, Serialize};
 
+// Frames carry a 4-byte big-endian length followed by the payload. MessagePack
+// is the default; JSON payloads are told apart by their leading `{`, which is
+// never the first byte of a MessagePack-encoded struct.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameFormat {
+    #[default]
+    MsgPack,
+    Json,
+}
+
+impl FrameFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            FrameFormat::MsgPack => "application/msgpack",
+            FrameFormat::Json => "application/json",
+        }
+    }
+}
+
 pub fn encode_frame<T: Serialize>(msg: &T) -> io::Result<Vec<u8>> {
-    let payload = to_vec(msg).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+    encode_frame_as(msg, FrameFormat::MsgPack)
+}
+
+pub fn encode_frame_as<T: Serialize>(msg: &T, format: FrameFormat) -> io::Result<Vec<u8>> {
+    let payload = match format {
+        FrameFormat::MsgPack => {
+            to_vec(msg).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?
+        }
+        FrameFormat::Json => {
+            serde_json::to_vec(msg).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?
+        }
+    };
     let len = u32::try_from(payload.len())
         .map_err(|_| io::Error::new(ErrorKind::InvalidData, "frame too large"))?;
     let mut framed = Vec::with_capacity(4 + payload.len());
@@ -14,6 +44,72 @@ pub fn encode_frame<T: Serialize>(msg: &T) -> io::Result<Vec<u8>> {
 }
 
 pub fn decode_frame<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T> {
+    decode_frame_with_format(bytes).map(|(msg, _)| msg)
+}
+
+pub fn decode_frame_with_format<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<(T, FrameFormat)> {
+    let payload = frame_payload(bytes)?;
+    let format = payload_format(payload);
+    let msg = match format {
+        FrameFormat::MsgPack => {
+            from_slice(payload).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?
+        }
+        FrameFormat::Json => serde_json::from_slice(payload)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?,
+    };
+    Ok((msg, format))
+}
+
+pub fn detect_frame_format(bytes: &[u8]) -> io::Result<FrameFormat> {
+    frame_payload(bytes).map(payload_format)
+}
+
+// Reassembles frames from a byte stream. Reads may split a frame or carry
+// several; each complete frame comes out header included, as
+// decode_frame and handle_framed_request expect it.
+#[derive(Debug)]
+pub struct FrameReader {
+    buffer: Vec<u8>,
+    max_len: usize,
+}
+
+impl FrameReader {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_len,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // Bytes received that do not make up a complete frame yet.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    // A length over max_len is an error: the stream cannot be resynced.
+    pub fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(header) = self.buffer.get(..4) else {
+            return Ok(None);
+        };
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if len > self.max_len {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("frame of {len} bytes exceeds limit of {}", self.max_len),
+            ));
+        }
+        if self.buffer.len() < 4 + len {
+            return Ok(None);
+        }
+        Ok(Some(self.buffer.drain(..4 + len).collect()))
+    }
+}
+
+fn frame_payload(bytes: &[u8]) -> io::Result<&[u8]> {
     if bytes.len() < 4 {
         return Err(io::Error::new(
             ErrorKind::UnexpectedEof,
@@ -26,6 +122,12 @@ pub fn decode_frame<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T> {
     if bytes.len() < 4 + len {
         return Err(io::Error::new(ErrorKind::UnexpectedEof, "incomplete frame"));
     }
-    let payload = &bytes[4..4 + len];
-    from_slice(payload).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))
+    Ok(&bytes[4..4 + len])
+}
+
+fn payload_format(payload: &[u8]) -> FrameFormat {
+    match payload.first() {
+        Some(b'{') => FrameFormat::Json,
+        _ => FrameFormat::MsgPack,
+    }
 }
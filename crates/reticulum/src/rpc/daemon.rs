@@ -1,8 +1,18 @@
 use super::*;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
+
+const MAX_DELIVERY_TRACE_ENTRIES: usize = 32;
+const MAX_TRACKED_MESSAGE_TRACES: usize = 2048;
+const MAX_PROPAGATION_SYNC_HISTORY: usize = 32;
+const MAX_PENDING_TELEMETRY_REQUESTS: usize = 256;
+const TELEMETRY_REQUEST_TIMEOUT_SECS: i64 = 600;
+const MAX_PENDING_CONTENT_FETCHES: usize = 256;
 
 impl RpcDaemon {
     pub fn with_store(store: MessagesStore, identity_hash: String) -> Self {
         let (events, _rx) = broadcast::channel(64);
+        let settings = PersistedSettings::load(&store);
         Self {
             store,
             identity_hash,
@@ -10,17 +20,56 @@ impl RpcDaemon {
             events,
             event_queue: Mutex::new(VecDeque::new()),
             peers: Mutex::new(HashMap::new()),
+            max_peers: Mutex::new(DEFAULT_MAX_PEERS),
+            presence_thresholds: Mutex::new(PresenceThresholds::default()),
+            announce_interval: tokio::sync::watch::channel(0).0,
+            peer_presence: Mutex::new(HashMap::new()),
+            pending_path_requests: Mutex::new(HashMap::new()),
             interfaces: Mutex::new(Vec::new()),
-            delivery_policy: Mutex::new(DeliveryPolicy::default()),
+            interface_connections: Mutex::new(HashMap::new()),
+            interface_bindings: Mutex::new(HashMap::new()),
+            delivery_policy: Mutex::new(settings.delivery_policy),
             propagation_state: Mutex::new(PropagationState::default()),
-            propagation_payloads: Mutex::new(HashMap::new()),
+            propagation_sync_history: Mutex::new(VecDeque::new()),
+            propagation_sync_cancel: Mutex::new(None),
+            pending_telemetry_requests: Mutex::new(Vec::new()),
+            store_backup_policy: Mutex::new(BackupPolicy::default()),
+            propagation_store_limits: Mutex::new(PropagationStoreLimits::default()),
+            received_resource_limits: Mutex::new(ReceivedResourceLimits::default()),
             outbound_propagation_node: Mutex::new(None),
             paper_ingest_seen: Mutex::new(HashSet::new()),
-            stamp_policy: Mutex::new(StampPolicy::default()),
+            stamp_policy: Mutex::new(settings.stamp_policy),
+            stamp_policy_overrides: Mutex::new(settings.stamp_policy_overrides),
+            announce_aspects: Mutex::new(Vec::new()),
+            inbound_overload_policy: Mutex::new(settings.inbound_overload_policy),
+            inbound_load: Mutex::new(InboundLoad::default()),
+            attachment_assemblies: Mutex::new(HashMap::new()),
+            outbound_chunk_transfers: Mutex::new(HashMap::new()),
             ticket_cache: Mutex::new(HashMap::new()),
             delivery_traces: Mutex::new(HashMap::new()),
+            http_auth_tokens: Mutex::new(Vec::new()),
+            http_cors_origins: Mutex::new(Vec::new()),
+            require_hash_addresses: Mutex::new(false),
+            require_signed_announces: Mutex::new(false),
+            accept_unaddressed_inbound: Mutex::new(false),
+            local_identity: Mutex::new(None),
+            sign_events: Mutex::new(false),
             outbound_bridge: None,
             announce_bridge: None,
+            resource_bridge: None,
+            content_fetches: Mutex::new(VecDeque::new()),
+            content_requests: Mutex::new(HashMap::new()),
+            interface_filter_bridge: None,
+            link_bridge: None,
+            plain_bridge: None,
+            bandwidth_bridge: None,
+            paper_bridge: None,
+            lxmf_codec_bridge: None,
+            destination_interface_bridge: None,
+            config_bridge: None,
+            transport_control_bridge: None,
+            transport_metrics_bridge: None,
+            interface_traffic_bridge: None,
         }
     }
 
@@ -30,6 +79,7 @@ impl RpcDaemon {
         outbound_bridge: Arc<dyn OutboundBridge>,
     ) -> Self {
         let (events, _rx) = broadcast::channel(64);
+        let settings = PersistedSettings::load(&store);
         Self {
             store,
             identity_hash,
@@ -37,17 +87,56 @@ impl RpcDaemon {
             events,
             event_queue: Mutex::new(VecDeque::new()),
             peers: Mutex::new(HashMap::new()),
+            max_peers: Mutex::new(DEFAULT_MAX_PEERS),
+            presence_thresholds: Mutex::new(PresenceThresholds::default()),
+            announce_interval: tokio::sync::watch::channel(0).0,
+            peer_presence: Mutex::new(HashMap::new()),
+            pending_path_requests: Mutex::new(HashMap::new()),
             interfaces: Mutex::new(Vec::new()),
-            delivery_policy: Mutex::new(DeliveryPolicy::default()),
+            interface_connections: Mutex::new(HashMap::new()),
+            interface_bindings: Mutex::new(HashMap::new()),
+            delivery_policy: Mutex::new(settings.delivery_policy),
             propagation_state: Mutex::new(PropagationState::default()),
-            propagation_payloads: Mutex::new(HashMap::new()),
+            propagation_sync_history: Mutex::new(VecDeque::new()),
+            propagation_sync_cancel: Mutex::new(None),
+            pending_telemetry_requests: Mutex::new(Vec::new()),
+            store_backup_policy: Mutex::new(BackupPolicy::default()),
+            propagation_store_limits: Mutex::new(PropagationStoreLimits::default()),
+            received_resource_limits: Mutex::new(ReceivedResourceLimits::default()),
             outbound_propagation_node: Mutex::new(None),
             paper_ingest_seen: Mutex::new(HashSet::new()),
-            stamp_policy: Mutex::new(StampPolicy::default()),
+            stamp_policy: Mutex::new(settings.stamp_policy),
+            stamp_policy_overrides: Mutex::new(settings.stamp_policy_overrides),
+            announce_aspects: Mutex::new(Vec::new()),
+            inbound_overload_policy: Mutex::new(settings.inbound_overload_policy),
+            inbound_load: Mutex::new(InboundLoad::default()),
+            attachment_assemblies: Mutex::new(HashMap::new()),
+            outbound_chunk_transfers: Mutex::new(HashMap::new()),
             ticket_cache: Mutex::new(HashMap::new()),
             delivery_traces: Mutex::new(HashMap::new()),
+            http_auth_tokens: Mutex::new(Vec::new()),
+            http_cors_origins: Mutex::new(Vec::new()),
+            require_hash_addresses: Mutex::new(false),
+            require_signed_announces: Mutex::new(false),
+            accept_unaddressed_inbound: Mutex::new(false),
+            local_identity: Mutex::new(None),
+            sign_events: Mutex::new(false),
             outbound_bridge: Some(outbound_bridge),
             announce_bridge: None,
+            resource_bridge: None,
+            content_fetches: Mutex::new(VecDeque::new()),
+            content_requests: Mutex::new(HashMap::new()),
+            interface_filter_bridge: None,
+            link_bridge: None,
+            plain_bridge: None,
+            bandwidth_bridge: None,
+            paper_bridge: None,
+            lxmf_codec_bridge: None,
+            destination_interface_bridge: None,
+            config_bridge: None,
+            transport_control_bridge: None,
+            transport_metrics_bridge: None,
+            interface_traffic_bridge: None,
         }
     }
 
@@ -58,6 +147,7 @@ impl RpcDaemon {
         announce_bridge: Option<Arc<dyn AnnounceBridge>>,
     ) -> Self {
         let (events, _rx) = broadcast::channel(64);
+        let settings = PersistedSettings::load(&store);
         Self {
             store,
             identity_hash,
@@ -65,20 +155,134 @@ impl RpcDaemon {
             events,
             event_queue: Mutex::new(VecDeque::new()),
             peers: Mutex::new(HashMap::new()),
+            max_peers: Mutex::new(DEFAULT_MAX_PEERS),
+            presence_thresholds: Mutex::new(PresenceThresholds::default()),
+            announce_interval: tokio::sync::watch::channel(0).0,
+            peer_presence: Mutex::new(HashMap::new()),
+            pending_path_requests: Mutex::new(HashMap::new()),
             interfaces: Mutex::new(Vec::new()),
-            delivery_policy: Mutex::new(DeliveryPolicy::default()),
+            interface_connections: Mutex::new(HashMap::new()),
+            interface_bindings: Mutex::new(HashMap::new()),
+            delivery_policy: Mutex::new(settings.delivery_policy),
             propagation_state: Mutex::new(PropagationState::default()),
-            propagation_payloads: Mutex::new(HashMap::new()),
+            propagation_sync_history: Mutex::new(VecDeque::new()),
+            propagation_sync_cancel: Mutex::new(None),
+            pending_telemetry_requests: Mutex::new(Vec::new()),
+            store_backup_policy: Mutex::new(BackupPolicy::default()),
+            propagation_store_limits: Mutex::new(PropagationStoreLimits::default()),
+            received_resource_limits: Mutex::new(ReceivedResourceLimits::default()),
             outbound_propagation_node: Mutex::new(None),
             paper_ingest_seen: Mutex::new(HashSet::new()),
-            stamp_policy: Mutex::new(StampPolicy::default()),
+            stamp_policy: Mutex::new(settings.stamp_policy),
+            stamp_policy_overrides: Mutex::new(settings.stamp_policy_overrides),
+            announce_aspects: Mutex::new(Vec::new()),
+            inbound_overload_policy: Mutex::new(settings.inbound_overload_policy),
+            inbound_load: Mutex::new(InboundLoad::default()),
+            attachment_assemblies: Mutex::new(HashMap::new()),
+            outbound_chunk_transfers: Mutex::new(HashMap::new()),
             ticket_cache: Mutex::new(HashMap::new()),
             delivery_traces: Mutex::new(HashMap::new()),
+            http_auth_tokens: Mutex::new(Vec::new()),
+            http_cors_origins: Mutex::new(Vec::new()),
+            require_hash_addresses: Mutex::new(false),
+            require_signed_announces: Mutex::new(false),
+            accept_unaddressed_inbound: Mutex::new(false),
+            local_identity: Mutex::new(None),
+            sign_events: Mutex::new(false),
             outbound_bridge,
             announce_bridge,
+            resource_bridge: None,
+            content_fetches: Mutex::new(VecDeque::new()),
+            content_requests: Mutex::new(HashMap::new()),
+            interface_filter_bridge: None,
+            link_bridge: None,
+            plain_bridge: None,
+            bandwidth_bridge: None,
+            paper_bridge: None,
+            lxmf_codec_bridge: None,
+            destination_interface_bridge: None,
+            config_bridge: None,
+            transport_control_bridge: None,
+            transport_metrics_bridge: None,
+            interface_traffic_bridge: None,
         }
     }
 
+    pub fn with_resource_bridge(mut self, resource_bridge: Arc<dyn ResourceBridge>) -> Self {
+        self.resource_bridge = Some(resource_bridge);
+        self
+    }
+
+    pub fn with_interface_filter_bridge(
+        mut self,
+        interface_filter_bridge: Arc<dyn InterfaceFilterBridge>,
+    ) -> Self {
+        self.interface_filter_bridge = Some(interface_filter_bridge);
+        self
+    }
+
+    pub fn with_link_bridge(mut self, link_bridge: Arc<dyn LinkBridge>) -> Self {
+        self.link_bridge = Some(link_bridge);
+        self
+    }
+
+    pub fn with_plain_bridge(mut self, plain_bridge: Arc<dyn PlainBridge>) -> Self {
+        self.plain_bridge = Some(plain_bridge);
+        self
+    }
+
+    pub fn with_bandwidth_bridge(mut self, bandwidth_bridge: Arc<dyn BandwidthBridge>) -> Self {
+        self.bandwidth_bridge = Some(bandwidth_bridge);
+        self
+    }
+
+    pub fn with_paper_bridge(mut self, paper_bridge: Arc<dyn PaperBridge>) -> Self {
+        self.paper_bridge = Some(paper_bridge);
+        self
+    }
+
+    pub fn with_lxmf_codec_bridge(mut self, lxmf_codec_bridge: Arc<dyn LxmfCodecBridge>) -> Self {
+        self.lxmf_codec_bridge = Some(lxmf_codec_bridge);
+        self
+    }
+
+    pub fn with_destination_interface_bridge(
+        mut self,
+        destination_interface_bridge: Arc<dyn DestinationInterfaceBridge>,
+    ) -> Self {
+        self.destination_interface_bridge = Some(destination_interface_bridge);
+        self
+    }
+
+    pub fn with_config_bridge(mut self, config_bridge: Arc<dyn ConfigBridge>) -> Self {
+        self.config_bridge = Some(config_bridge);
+        self
+    }
+
+    pub fn with_transport_control_bridge(
+        mut self,
+        transport_control_bridge: Arc<dyn TransportControlBridge>,
+    ) -> Self {
+        self.transport_control_bridge = Some(transport_control_bridge);
+        self
+    }
+
+    pub fn with_transport_metrics_bridge(
+        mut self,
+        transport_metrics_bridge: Arc<dyn TransportMetricsBridge>,
+    ) -> Self {
+        self.transport_metrics_bridge = Some(transport_metrics_bridge);
+        self
+    }
+
+    pub fn with_interface_traffic_bridge(
+        mut self,
+        interface_traffic_bridge: Arc<dyn InterfaceTrafficBridge>,
+    ) -> Self {
+        self.interface_traffic_bridge = Some(interface_traffic_bridge);
+        self
+    }
+
     pub fn test_instance() -> Self {
         let store = MessagesStore::in_memory().expect("in-memory store");
         Self::with_store(store, "test-identity".into())
@@ -105,11 +309,340 @@ impl RpcDaemon {
         });
     }
 
+    pub fn set_http_auth_tokens(&self, tokens: Vec<RpcAuthToken>) {
+        let mut guard = self
+            .http_auth_tokens
+            .lock()
+            .expect("http auth tokens mutex poisoned");
+        *guard = tokens
+            .into_iter()
+            .filter(|entry| !entry.token.trim().is_empty())
+            .collect();
+    }
+
+    pub fn http_auth_tokens(&self) -> Vec<RpcAuthToken> {
+        self.http_auth_tokens
+            .lock()
+            .expect("http auth tokens mutex poisoned")
+            .clone()
+    }
+
+    // Origins allowed to call the HTTP endpoint from a browser; "*" allows any.
+    // Empty keeps CORS headers off entirely.
+    pub fn set_http_cors_origins(&self, origins: Vec<String>) {
+        let mut guard = self
+            .http_cors_origins
+            .lock()
+            .expect("http cors origins mutex poisoned");
+        *guard = origins
+            .iter()
+            .map(|origin| origin.trim().trim_end_matches('/').to_string())
+            .filter(|origin| !origin.is_empty())
+            .collect();
+    }
+
+    pub fn http_cors_origins(&self) -> Vec<String> {
+        self.http_cors_origins
+            .lock()
+            .expect("http cors origins mutex poisoned")
+            .clone()
+    }
+
     pub fn replace_interfaces(&self, interfaces: Vec<InterfaceRecord>) {
         let mut guard = self.interfaces.lock().expect("interfaces mutex poisoned");
         *guard = interfaces;
     }
 
+    pub fn set_max_peers(&self, max_peers: usize) {
+        *self.max_peers.lock().expect("max_peers mutex poisoned") = max_peers;
+        let evicted = {
+            let mut guard = self.peers.lock().expect("peers mutex poisoned");
+            evict_stale_peers(&mut guard, max_peers, None)
+        };
+        self.forget_evicted_peers(&evicted);
+    }
+
+    pub fn set_presence_thresholds(&self, thresholds: PresenceThresholds) {
+        *self
+            .presence_thresholds
+            .lock()
+            .expect("presence mutex poisoned") = thresholds;
+    }
+
+    pub fn set_announce_interval_secs(&self, interval_secs: u64) {
+        self.announce_interval.send_replace(interval_secs);
+    }
+
+    // Applies runtime settings stored by set_setting over whatever the config
+    // file and command line chose. Returns the keys that were applied.
+    pub fn apply_persisted_runtime_settings(&self) -> Result<Vec<&'static str>, std::io::Error> {
+        let mut applied = Vec::new();
+        for (key, _, _) in RUNTIME_SETTINGS {
+            let Some(value) = self.store.get_setting(key).map_err(std::io::Error::other)? else {
+                continue;
+            };
+            match runtime_setting_value(key, &value) {
+                Ok(value) => {
+                    self.apply_runtime_setting(key, value);
+                    applied.push(*key);
+                }
+                Err(err) => log::warn!("ignoring stored setting {key}: {err}"),
+            }
+        }
+        Ok(applied)
+    }
+
+    fn apply_runtime_setting(&self, key: &str, value: u64) {
+        let mut thresholds = *self
+            .presence_thresholds
+            .lock()
+            .expect("presence mutex poisoned");
+        match key {
+            SETTING_ANNOUNCE_INTERVAL_SECS => self.set_announce_interval_secs(value),
+            SETTING_MAX_PEERS => {
+                self.set_max_peers(usize::try_from(value).unwrap_or(usize::MAX));
+            }
+            SETTING_PRESENCE_ONLINE_SECS => {
+                thresholds.online_secs = value;
+                self.set_presence_thresholds(thresholds);
+            }
+            SETTING_PRESENCE_STALE_SECS => {
+                thresholds.stale_secs = value;
+                self.set_presence_thresholds(thresholds);
+            }
+            _ => {}
+        }
+    }
+
+    // Recomputes every peer's presence and emits peer_presence_changed for
+    // each one that crossed a threshold since the last sweep, including
+    // peers seen for the first time. Returns the number of changes.
+    pub fn sweep_peer_presence(&self) -> Result<usize, std::io::Error> {
+        let thresholds = *self
+            .presence_thresholds
+            .lock()
+            .expect("presence mutex poisoned");
+        let peers = self
+            .peers
+            .lock()
+            .expect("peers mutex poisoned")
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        let activity = self
+            .store
+            .peer_message_activity(
+                &peers
+                    .iter()
+                    .map(|record| record.peer.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .map_err(std::io::Error::other)?;
+        let now = now_i64();
+        let current = peers
+            .iter()
+            .map(|record| {
+                let (last_message, _) = activity.get(&record.peer).copied().unwrap_or((0, 0));
+                let last_contact = record.last_seen.max(last_message);
+                (
+                    record.peer.clone(),
+                    last_contact,
+                    thresholds.presence(now - last_contact),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut changed = Vec::new();
+        {
+            let mut known = self.peer_presence.lock().expect("presence mutex poisoned");
+            known.retain(|peer, _| current.iter().any(|(id, _, _)| id == peer));
+            for (peer, last_contact, presence) in current {
+                let previous = known.insert(peer.clone(), presence);
+                if previous != Some(presence) {
+                    changed.push(json!({
+                        "peer": peer,
+                        "presence": presence,
+                        "previous": previous,
+                        "last_contact": last_contact,
+                    }));
+                }
+            }
+        }
+        let count = changed.len();
+        for payload in changed {
+            self.emit_event(RpcEvent {
+                event_type: "peer_presence_changed".into(),
+                payload,
+            });
+        }
+        Ok(count)
+    }
+
+    // Called when a path to destination shows up; reports it if a client
+    // asked for it and was not waiting when it arrived.
+    pub fn resolve_path_request(&self, destination: &str) -> bool {
+        let requested_at = self
+            .pending_path_requests
+            .lock()
+            .expect("pending_path_requests mutex poisoned")
+            .remove(destination);
+        let Some(requested_at) = requested_at else {
+            return false;
+        };
+        self.emit_event(RpcEvent {
+            event_type: "path_resolved".into(),
+            payload: json!({
+                "destination": destination,
+                "requested_at": requested_at,
+                "resolved_at": now_i64(),
+            }),
+        });
+        true
+    }
+
+    pub fn set_require_hash_addresses(&self, required: bool) {
+        *self
+            .require_hash_addresses
+            .lock()
+            .expect("require_hash_addresses mutex poisoned") = required;
+    }
+
+    // Unsigned RPC announces are still accepted for peers without a known
+    // identity unless this is set.
+    pub fn set_require_signed_announces(&self, required: bool) {
+        *self
+            .require_signed_announces
+            .lock()
+            .expect("require_signed_announces mutex poisoned") = required;
+    }
+
+    pub fn set_local_identity(&self, identity: PrivateIdentity) {
+        *self
+            .local_identity
+            .lock()
+            .expect("local_identity mutex poisoned") = Some(identity);
+    }
+
+    // Signing needs the local identity; without one events stay unsigned.
+    pub fn set_sign_events(&self, enabled: bool) {
+        *self.sign_events.lock().expect("sign_events mutex poisoned") = enabled;
+    }
+
+    pub fn sign_event(&self, event: &RpcEvent) -> Option<SignedRpcEvent> {
+        if !*self.sign_events.lock().expect("sign_events mutex poisoned") {
+            return None;
+        }
+        let guard = self
+            .local_identity
+            .lock()
+            .expect("local_identity mutex poisoned");
+        let identity = guard.as_ref()?;
+        let bytes = event_signing_bytes(event).ok()?;
+        Some(SignedRpcEvent {
+            event: event.clone(),
+            sig: hex::encode(lxmf_sign(identity, &bytes)),
+        })
+    }
+
+    // Lets receive_message store records for any destination. Only meant for
+    // tests that inject synthetic inbound history.
+    pub fn set_accept_unaddressed_inbound(&self, accept: bool) {
+        *self
+            .accept_unaddressed_inbound
+            .lock()
+            .expect("accept_unaddressed_inbound mutex poisoned") = accept;
+    }
+
+    pub fn set_store_backup_policy(&self, policy: BackupPolicy) {
+        *self
+            .store_backup_policy
+            .lock()
+            .expect("store_backup_policy mutex poisoned") = policy;
+    }
+
+    pub fn backup_store(&self, keep: Option<usize>) -> Result<StoreBackupRecord, std::io::Error> {
+        let store_path = self.store.path().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "in-memory store cannot be backed up",
+            )
+        })?;
+        let policy = self
+            .store_backup_policy
+            .lock()
+            .expect("store_backup_policy mutex poisoned")
+            .clone();
+        let dir = policy.backup_dir(&store_path);
+        std::fs::create_dir_all(&dir)?;
+
+        let mut created_at_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|value| value.as_millis() as u64)
+            .unwrap_or(0);
+        let mut path = dir.join(backup::backup_file_name(&store_path, created_at_ms));
+        while path.exists() {
+            created_at_ms += 1;
+            path = dir.join(backup::backup_file_name(&store_path, created_at_ms));
+        }
+        self.store.backup_to(&path).map_err(std::io::Error::other)?;
+        let size_bytes = std::fs::metadata(&path)?.len();
+        let keep = keep.unwrap_or(policy.keep).max(1);
+        let pruned = backup::prune_backups(&dir, &store_path, keep)?;
+
+        let record = StoreBackupRecord {
+            path: path.display().to_string(),
+            size_bytes,
+            created_at: (created_at_ms / 1000) as i64,
+            pruned: pruned
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect(),
+        };
+        let event = RpcEvent {
+            event_type: "store_backup_created".into(),
+            payload: json!({
+                "path": record.path,
+                "size_bytes": record.size_bytes,
+                "pruned": record.pruned,
+            }),
+        };
+        self.push_event(event.clone());
+        let _ = self.events.send(event);
+        Ok(record)
+    }
+
+    // Only backups in the configured backup directory can be restored, so the
+    // RPC cannot be used to swap in arbitrary files.
+    pub fn restore_store(&self, backup_path: &str) -> Result<Option<String>, std::io::Error> {
+        let store_path = self.store.path().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "in-memory store cannot be restored",
+            )
+        })?;
+        let dir = self
+            .store_backup_policy
+            .lock()
+            .expect("store_backup_policy mutex poisoned")
+            .backup_dir(&store_path);
+        let requested = std::path::Path::new(backup_path.trim());
+        let requested = if requested.components().count() == 1 {
+            dir.join(requested)
+        } else {
+            requested.to_path_buf()
+        };
+        let Ok(requested) = std::fs::canonicalize(&requested) else {
+            return Ok(None);
+        };
+        let known = backup::list_backups(&dir, &store_path)?
+            .into_iter()
+            .find(|path| std::fs::canonicalize(path).ok().as_ref() == Some(&requested));
+        let Some(known) = known else {
+            return Ok(None);
+        };
+        let staged = backup::stage_restore(&store_path, &known)?;
+        Ok(Some(staged.display().to_string()))
+    }
+
     pub fn set_propagation_state(
         &self,
         enabled: bool,
@@ -125,6 +658,21 @@ impl RpcDaemon {
         guard.target_cost = target_cost;
     }
 
+    // A sync task watches the returned token; propagation_sync_abort cancels
+    // it. Starting a new sync cancels any previous one.
+    pub fn begin_propagation_sync(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+        let previous = self
+            .propagation_sync_cancel
+            .lock()
+            .expect("propagation_sync_cancel mutex poisoned")
+            .replace(token.clone());
+        if let Some(previous) = previous {
+            previous.cancel();
+        }
+        token
+    }
+
     pub fn update_propagation_sync_state<F>(&self, update: F)
     where
         F: FnOnce(&mut PropagationState),
@@ -133,13 +681,59 @@ impl RpcDaemon {
             .propagation_state
             .lock()
             .expect("propagation mutex poisoned");
+        let was_running = is_active_propagation_sync_state(&guard.state_name);
+        let previous_started = guard.last_sync_started;
         update(&mut guard);
+
+        // A sync has finished when the update takes it out of the active
+        // states, or starts and ends a new one in one step. Progress updates
+        // and resets of an idle state are not recorded.
+        let started_new = guard.last_sync_started != previous_started;
+        if is_active_propagation_sync_state(&guard.state_name) || !(was_running || started_new) {
+            return;
+        }
+        let record = propagation_sync_record(&guard);
+        drop(guard);
+        self.push_propagation_sync_record(record);
+    }
+
+    fn push_propagation_sync_record(&self, record: PropagationSyncRecord) {
+        let mut history = self
+            .propagation_sync_history
+            .lock()
+            .expect("propagation sync history mutex poisoned");
+        if history.len() >= MAX_PROPAGATION_SYNC_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(record);
     }
 
-    fn store_inbound_record(&self, record: MessageRecord) -> Result<(), std::io::Error> {
+    fn store_inbound_record(&self, mut record: MessageRecord) -> Result<(), std::io::Error> {
+        if let Some(chunk) = attachment_chunks::parse_chunk(record.fields.as_ref()) {
+            return self.accept_attachment_chunk(chunk, record).map(|_| ());
+        }
+        // A message deleted here stays deleted when the sender repeats it.
+        if self
+            .store
+            .message_deleted_at(&record.id)
+            .map_err(std::io::Error::other)?
+            .is_some()
+        {
+            return Ok(());
+        }
+        if self.apply_inbound_edit(&record)? {
+            return Ok(());
+        }
+        if record.content_type.is_none() {
+            record.content_type = Some(
+                content_type_from_fields(record.fields.as_ref())
+                    .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string()),
+            );
+        }
         self.store
             .insert_message(&record)
             .map_err(std::io::Error::other)?;
+        self.store_inbound_telemetry(&record)?;
         let event = RpcEvent {
             event_type: "inbound".into(),
             payload: json!({ "message": record }),
@@ -149,106 +743,687 @@ impl RpcDaemon {
         Ok(())
     }
 
-    pub fn accept_inbound(&self, record: MessageRecord) -> Result<(), std::io::Error> {
-        self.store_inbound_record(record)
+    // Collects one piece of a chunked attachment. The reassembled message is
+    // stored like any other inbound message once the last piece arrives, and
+    // only then is the transfer reported as stored.
+    fn accept_attachment_chunk(
+        &self,
+        chunk: Result<AttachmentChunk, std::io::Error>,
+        record: MessageRecord,
+    ) -> Result<InboundAcceptance, std::io::Error> {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                log::warn!("dropping attachment chunk from {}: {err}", record.source);
+                return Ok(InboundAcceptance::Dropped);
+            }
+        };
+        let transfer_id = chunk.transfer_id.clone();
+        let peer = record.source.clone();
+        let key = format!("{peer}:{transfer_id}");
+        let now = now_i64();
+        let (done, total, complete) = {
+            let mut assemblies = self
+                .attachment_assemblies
+                .lock()
+                .expect("attachment_assemblies mutex poisoned");
+            if !assemblies.contains_key(&key)
+                && assemblies.len() >= MAX_PENDING_ATTACHMENT_TRANSFERS
+            {
+                let stalest = assemblies
+                    .iter()
+                    .min_by_key(|(_, assembly)| assembly.updated_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(stalest) = stalest {
+                    assemblies.remove(&stalest);
+                }
+            }
+            let assembly = assemblies
+                .entry(key.clone())
+                .or_insert_with(|| ChunkAssembly::new(chunk.total, now));
+            if let Err(err) = assembly.add(chunk, record, now) {
+                log::warn!("dropping attachment chunk from {peer}: {err}");
+                return Ok(InboundAcceptance::Dropped);
+            }
+            let (done, total) = (assembly.received(), assembly.total());
+            let complete = if assembly.is_complete() {
+                assemblies.remove(&key)
+            } else {
+                None
+            };
+            (done, total, complete)
+        };
+        let (status, acceptance) = match complete.map(ChunkAssembly::finish) {
+            None => ("receiving", InboundAcceptance::Held),
+            Some(Ok(assembled)) => {
+                self.store_inbound_record(assembled)?;
+                ("complete", InboundAcceptance::Stored)
+            }
+            Some(Err(err)) => {
+                log::warn!("attachment transfer {transfer_id} from {peer} failed: {err}");
+                ("failed", InboundAcceptance::Dropped)
+            }
+        };
+        self.emit_event(RpcEvent {
+            event_type: "attachment_progress".into(),
+            payload: json!({
+                "transfer_id": transfer_id,
+                "peer": peer,
+                "direction": "in",
+                "chunks_done": done,
+                "chunks_total": total,
+                "status": status,
+            }),
+        });
+        Ok(acceptance)
     }
 
-    pub fn accept_announce(&self, peer: String, timestamp: i64) -> Result<(), std::io::Error> {
-        self.accept_announce_with_metadata(
-            peer, timestamp, None, None, None, None, None, None, None, None, None, None, None,
-            None, None, None, None, None,
-        )
+    // The stamp policy that applies to a peer: its own override when one is
+    // set, the global policy otherwise. The flag tells which.
+    pub fn effective_stamp_policy(&self, peer: &str) -> (StampPolicy, bool) {
+        let peer = peer.trim().to_ascii_lowercase();
+        let overridden = self
+            .stamp_policy_overrides
+            .lock()
+            .expect("stamp overrides mutex poisoned")
+            .get(&peer)
+            .cloned();
+        match overridden {
+            Some(policy) => (policy, true),
+            None => (
+                self.stamp_policy
+                    .lock()
+                    .expect("stamp mutex poisoned")
+                    .clone(),
+                false,
+            ),
+        }
     }
 
-    pub fn accept_announce_with_details(
+    // Records the value an inbound message's stamp achieved against the
+    // stamp policy for its sender. Returns true when the message was
+    // under-stamped.
+    pub fn record_inbound_stamp(
         &self,
-        peer: String,
-        timestamp: i64,
-        name: Option<String>,
-        name_source: Option<String>,
-    ) -> Result<(), std::io::Error> {
-        self.accept_announce_with_metadata(
-            peer,
-            timestamp,
-            name,
-            name_source,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-            None,
-        )
+        message_id: &str,
+        source: &str,
+        stamp_value: Option<u32>,
+    ) -> Result<bool, std::io::Error> {
+        let target_cost = self.effective_stamp_policy(source).0.target_cost;
+        if target_cost == 0 && stamp_value.is_none() {
+            return Ok(false);
+        }
+        let under_stamped = stamp_value.unwrap_or(0) < target_cost;
+        self.store
+            .record_message_stamp(&MessageStampRecord {
+                message_id: message_id.to_string(),
+                direction: "in".into(),
+                stamp_cost: (target_cost > 0).then_some(target_cost),
+                stamp_value,
+                under_stamped,
+                recorded_at: now_i64(),
+            })
+            .map_err(std::io::Error::other)?;
+        Ok(under_stamped)
     }
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn accept_announce_with_metadata(
-        &self,
-        peer: String,
-        timestamp: i64,
-        name: Option<String>,
-        name_source: Option<String>,
-        app_data_hex: Option<String>,
-        capabilities: Option<Vec<String>>,
-        rssi: Option<f64>,
-        snr: Option<f64>,
-        q: Option<f64>,
-        stamp_cost: Option<u32>,
-        stamp_cost_flexibility: Option<Option<u32>>,
-        peering_cost: Option<Option<u32>>,
-        aspect: Option<String>,
-        hops: Option<u32>,
-        interface: Option<String>,
-        source_private_key: Option<String>,
-        source_identity: Option<String>,
-        source_node: Option<String>,
-    ) -> Result<(), std::io::Error> {
-        let _ = stamp_cost;
-        let stamp_cost_flexibility = stamp_cost_flexibility.flatten();
-        let peering_cost = peering_cost.flatten();
-        let record = self.upsert_peer(peer, timestamp, name, name_source);
-        let capability_list = if let Some(caps) = capabilities {
-            normalize_capabilities(caps)
-        } else {
-            parse_capabilities_from_app_data_hex(app_data_hex.as_deref())
+    // Edits replace an earlier message instead of being stored themselves.
+    // Only the original sender may edit a message; anything else carrying
+    // the edit field is dropped. Returns true when the record was an edit.
+    fn apply_inbound_edit(&self, record: &MessageRecord) -> Result<bool, std::io::Error> {
+        let Some(edit) = record
+            .fields
+            .as_ref()
+            .and_then(|fields| fields.get(FIELD_EDIT))
+        else {
+            return Ok(false);
         };
+        let Some(message_id) = edit.get("message_id").and_then(JsonValue::as_str) else {
+            return Ok(true);
+        };
+        let original = self
+            .store
+            .get_message(message_id)
+            .map_err(std::io::Error::other)?;
+        if !original
+            .is_some_and(|original| original.direction == "in" && original.source == record.source)
+        {
+            return Ok(true);
+        }
+        let edited_at = edit
+            .get("edited_at")
+            .and_then(JsonValue::as_i64)
+            .unwrap_or(record.timestamp);
+        let version = self
+            .store
+            .edit_message(message_id, &record.title, &record.content, edited_at)
+            .map_err(std::io::Error::other)?;
+        self.emit_event(RpcEvent {
+            event_type: "message_edited".into(),
+            payload: json!({
+                "message_id": message_id,
+                "direction": "in",
+                "source": record.source,
+                "version": version,
+                "edited_at": edited_at,
+                "title": record.title,
+                "content": record.content,
+            }),
+        });
+        Ok(true)
+    }
 
-        let announce_record = AnnounceRecord {
-            id: format!(
-                "announce-{}-{}-{}",
-                record.last_seen, record.peer, record.seen_count
-            ),
-            peer: record.peer.clone(),
-            timestamp: record.last_seen,
-            name: record.name.clone(),
-            name_source: record.name_source.clone(),
-            first_seen: record.first_seen,
-            seen_count: record.seen_count,
-            app_data_hex: clean_optional_text(app_data_hex),
-            capabilities: capability_list.clone(),
-            rssi,
-            snr,
-            q,
-            stamp_cost_flexibility,
-            peering_cost,
+    fn store_inbound_telemetry(&self, record: &MessageRecord) -> Result<(), std::io::Error> {
+        let Some(location) = telemetry::location_from_fields(record.fields.as_ref()) else {
+            return Ok(());
+        };
+        let telemetry = TelemetryRecord {
+            peer: record.source.clone(),
+            lat: location.lat,
+            lon: location.lon,
+            altitude: location.altitude,
+            speed: location.speed,
+            bearing: location.bearing,
+            accuracy: location.accuracy,
+            timestamp: location.timestamp.unwrap_or(record.timestamp),
         };
         self.store
-            .insert_announce(&announce_record)
+            .insert_telemetry(&telemetry)
             .map_err(std::io::Error::other)?;
-
         let event = RpcEvent {
-            event_type: "announce_received".into(),
+            event_type: "telemetry_received".into(),
             payload: json!({
-                "id": announce_record.id,
-                "peer": record.peer,
+                "message_id": record.id,
+                "telemetry": telemetry,
+            }),
+        };
+        self.push_event(event.clone());
+        let _ = self.events.send(event);
+
+        // Answer the oldest open request to this peer whose timebase the
+        // telemetry satisfies.
+        let answered = {
+            let mut pending = self
+                .pending_telemetry_requests
+                .lock()
+                .expect("pending telemetry mutex poisoned");
+            pending
+                .iter()
+                .position(|request| {
+                    request.peer == record.source && telemetry.timestamp >= request.timebase
+                })
+                .map(|index| pending.remove(index))
+        };
+        if let Some(request) = answered {
+            let event = RpcEvent {
+                event_type: "telemetry_response".into(),
+                payload: json!({
+                    "request_message_id": request.message_id,
+                    "message_id": record.id,
+                    "peer": request.peer,
+                    "timebase": request.timebase,
+                    "telemetry": telemetry,
+                }),
+            };
+            self.push_event(event.clone());
+            let _ = self.events.send(event);
+        }
+        Ok(())
+    }
+
+    pub fn set_inbound_overload_policy(
+        &self,
+        policy: InboundOverloadPolicy,
+    ) -> Result<(), std::io::Error> {
+        self.persist_setting(SETTING_INBOUND_OVERLOAD_POLICY, &policy)?;
+        *self
+            .inbound_overload_policy
+            .lock()
+            .expect("inbound_overload_policy mutex poisoned") = policy;
+        Ok(())
+    }
+
+    pub fn inbound_overload_policy(&self) -> InboundOverloadPolicy {
+        self.inbound_overload_policy
+            .lock()
+            .expect("inbound_overload_policy mutex poisoned")
+            .clone()
+    }
+
+    pub fn set_propagation_store_limits(&self, limits: PropagationStoreLimits) {
+        *self
+            .propagation_store_limits
+            .lock()
+            .expect("propagation limits mutex poisoned") = limits;
+    }
+
+    pub fn propagation_store_limits(&self) -> PropagationStoreLimits {
+        *self
+            .propagation_store_limits
+            .lock()
+            .expect("propagation limits mutex poisoned")
+    }
+
+    pub fn set_received_resource_limits(&self, limits: ReceivedResourceLimits) {
+        *self
+            .received_resource_limits
+            .lock()
+            .expect("received resource limits mutex poisoned") = limits;
+    }
+
+    pub fn received_resource_limits(&self) -> ReceivedResourceLimits {
+        *self
+            .received_resource_limits
+            .lock()
+            .expect("received resource limits mutex poisoned")
+    }
+
+    // Applies the store limits, reporting anything expired or evicted. The
+    // byte-limit pass scans the whole table, so reticulumd runs this on a
+    // timer rather than per ingest or fetch.
+    pub fn prune_propagation_store(&self) -> Result<usize, std::io::Error> {
+        let limits = self.propagation_store_limits();
+        let bound = |value: u64| (value > 0).then_some(value);
+        let removed = self
+            .store
+            .prune_propagation_store(
+                bound(limits.max_age_secs)
+                    .map(|secs| now_i64().saturating_sub(i64::try_from(secs).unwrap_or(i64::MAX))),
+                bound(limits.max_messages),
+                bound(limits.max_bytes),
+            )
+            .map_err(std::io::Error::other)?;
+        if removed > 0 {
+            let (messages, bytes) = self
+                .store
+                .propagation_store_usage()
+                .map_err(std::io::Error::other)?;
+            self.emit_event(RpcEvent {
+                event_type: "propagation_store_pruned".into(),
+                payload: json!({ "removed": removed, "messages": messages, "bytes": bytes }),
+            });
+        }
+        Ok(removed)
+    }
+
+    fn persist_setting<T: Serialize>(&self, key: &str, value: &T) -> Result<(), std::io::Error> {
+        let value = serde_json::to_value(value).map_err(std::io::Error::other)?;
+        self.store
+            .put_setting(key, &value, now_i64())
+            .map_err(std::io::Error::other)
+    }
+
+    pub fn accept_inbound(
+        &self,
+        record: MessageRecord,
+    ) -> Result<InboundAcceptance, std::io::Error> {
+        if !self.admit_inbound(&record) {
+            return Ok(InboundAcceptance::Dropped);
+        }
+        if let Some(chunk) = attachment_chunks::parse_chunk(record.fields.as_ref()) {
+            return self.accept_attachment_chunk(chunk, record);
+        }
+        self.store_inbound_record(record)?;
+        Ok(InboundAcceptance::Stored)
+    }
+
+    // Counts the write against the current window and decides whether the
+    // record may be stored. Once a threshold is hit only prioritised sources
+    // get through; the first drop in a window emits an `overload` event.
+    fn admit_inbound(&self, record: &MessageRecord) -> bool {
+        let policy = self
+            .inbound_overload_policy
+            .lock()
+            .expect("inbound_overload_policy mutex poisoned")
+            .clone();
+        let queued_events = self
+            .event_queue
+            .lock()
+            .expect("event_queue mutex poisoned")
+            .len();
+        let now = now_i64();
+        let mut load = self
+            .inbound_load
+            .lock()
+            .expect("inbound_load mutex poisoned");
+        let window = i64::try_from(policy.window_secs.max(1)).unwrap_or(i64::MAX);
+        if now.saturating_sub(load.window_started_at) >= window {
+            load.window_started_at = now;
+            load.writes = 0;
+            load.dropped = 0;
+            load.overload_reported = false;
+        }
+
+        let reason =
+            if policy.max_writes_per_window > 0 && load.writes >= policy.max_writes_per_window {
+                Some("write_rate")
+            } else if policy.max_queued_events > 0 && queued_events >= policy.max_queued_events {
+                Some("event_queue")
+            } else {
+                None
+            };
+        if reason.is_none() || self.is_prioritised_source(&record.source) {
+            load.writes += 1;
+            return true;
+        }
+
+        load.dropped += 1;
+        load.total_dropped += 1;
+        let report = !load.overload_reported;
+        load.overload_reported = true;
+        let payload = json!({
+            "reason": reason,
+            "window_secs": policy.window_secs,
+            "writes": load.writes,
+            "queued_events": queued_events,
+            "dropped": load.dropped,
+            "message_id": record.id,
+            "source": record.source,
+        });
+        drop(load);
+        if report {
+            self.emit_event(RpcEvent {
+                event_type: "overload".into(),
+                payload,
+            });
+        }
+        false
+    }
+
+    fn is_prioritised_source(&self, source: &str) -> bool {
+        self.delivery_policy
+            .lock()
+            .expect("policy mutex poisoned")
+            .prioritised_destinations
+            .iter()
+            .any(|entry| entry.eq_ignore_ascii_case(source))
+    }
+
+    pub fn store_peer_identity(
+        &self,
+        destination: &str,
+        identity_hex: &str,
+    ) -> Result<KnownIdentityRecord, std::io::Error> {
+        let destination = normalize_hash_hex(destination).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "destination must be a 32-character hex hash",
+            )
+        })?;
+        let identity = parse_identity_hex(identity_hex).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid identity_hex")
+        })?;
+        // Binding a destination to any other key would encrypt to the wrong
+        // peer and let that key pass the known-identity signature check.
+        let derived = lxmf_delivery_hash_from_identity_hash(&identity.address_hash).to_hex_string();
+        if derived != destination {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("identity delivers to {derived}, not {destination}"),
+            ));
+        }
+        let record = KnownIdentityRecord {
+            destination,
+            identity_hex: identity.to_hex_string(),
+            updated_at: now_i64(),
+        };
+        self.store
+            .upsert_known_identity(&record)
+            .map_err(std::io::Error::other)?;
+        if let Some(bridge) = self.outbound_bridge.as_ref() {
+            bridge.restore_peer_identity(&record.destination, &identity)?;
+        }
+        Ok(record)
+    }
+
+    // Looks up the identity behind a destination hash. Explicitly stored
+    // identities win; otherwise the public key from the latest announce seen
+    // for the destination is used, so lookups keep working after a restart.
+    pub fn recall_identity(
+        &self,
+        destination: &str,
+    ) -> Result<Option<(Identity, &'static str)>, std::io::Error> {
+        let destination = normalize_hash_hex(destination).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "destination must be a 32-character hex hash",
+            )
+        })?;
+        let known = self
+            .store
+            .get_known_identity(&destination)
+            .map_err(std::io::Error::other)?
+            .and_then(|record| parse_identity_hex(&record.identity_hex));
+        if let Some(identity) = known {
+            return Ok(Some((identity, "known_identity")));
+        }
+        Ok(self
+            .store
+            .latest_announce_public_key(&destination)
+            .map_err(std::io::Error::other)?
+            .and_then(|value| parse_identity_hex(&value))
+            .map(|identity| (identity, "announce")))
+    }
+
+    // Called on startup to hand outbound messages that were still in flight
+    // when the daemon stopped back to the outbound bridge. Messages older than
+    // max_age_secs are left alone. Returns the ids that were resumed.
+    pub fn resume_pending_outbound(
+        &self,
+        max_age_secs: u64,
+    ) -> Result<Vec<String>, std::io::Error> {
+        let Some(bridge) = &self.outbound_bridge else {
+            return Ok(Vec::new());
+        };
+        let since = now_i64().saturating_sub(i64::try_from(max_age_secs).unwrap_or(i64::MAX));
+        // Settled messages are most of the history; only the ones still in
+        // flight are kept in memory.
+        let mut records = Vec::new();
+        self.store
+            .for_each_outbound_since(since, |record| {
+                if is_resumable_outbound_status(record.receipt_status.as_deref()) {
+                    records.push(record);
+                }
+            })
+            .map_err(std::io::Error::other)?;
+        let mut resumed = Vec::new();
+        for mut record in records {
+            // Keys passed as source_private_key are never stored, so a message
+            // sent as another identity cannot be signed again.
+            if !record
+                .source
+                .trim()
+                .eq_ignore_ascii_case(&self.local_delivery_hash())
+            {
+                let status = SOURCE_KEY_UNAVAILABLE.to_string();
+                let _ = self.store.update_receipt_status(&record.id, &status);
+                self.append_delivery_trace(&record.id, status.clone());
+                self.emit_delivery_failed(&record.id, &status, delivery_reason_code(&status));
+                continue;
+            }
+            let options = outbound_options_from_fields(record.fields.as_ref());
+            if options.deadline_passed() {
+                let status = DELIVERY_DEADLINE_EXCEEDED.to_string();
+                let _ = self.store.update_receipt_status(&record.id, &status);
+                self.append_delivery_trace(&record.id, status.clone());
+                self.emit_delivery_failed(&record.id, &status, delivery_reason_code(&status));
+                continue;
+            }
+            self.append_delivery_trace(&record.id, "resumed".to_string());
+            if let Some(attempts) = self
+                .store
+                .increment_message_attempts(&record.id)
+                .map_err(std::io::Error::other)?
+            {
+                record.attempts = attempts;
+            }
+            if let Err(err) = self.deliver_keeping_packed(bridge.as_ref(), &record, &options) {
+                let status = format!("failed: {err}");
+                let _ = self.store.update_receipt_status(&record.id, &status);
+                self.append_delivery_trace(&record.id, status.clone());
+                self.emit_delivery_failed(&record.id, &status, delivery_reason_code(&status));
+                continue;
+            }
+            resumed.push(record.id);
+        }
+        if !resumed.is_empty() {
+            let event = RpcEvent {
+                event_type: "outbound_resumed".into(),
+                payload: json!({ "message_ids": resumed }),
+            };
+            self.push_event(event.clone());
+            let _ = self.events.send(event);
+        }
+        Ok(resumed)
+    }
+
+    // Called on startup to hand persisted identities back to the outbound
+    // bridge; entries that no longer parse are skipped.
+    pub fn restore_known_identities(&self) -> Result<usize, std::io::Error> {
+        let Some(bridge) = self.outbound_bridge.as_ref() else {
+            return Ok(0);
+        };
+        let records = self
+            .store
+            .list_known_identities()
+            .map_err(std::io::Error::other)?;
+        let mut restored = 0;
+        for record in records {
+            let Some(identity) = parse_identity_hex(&record.identity_hex) else {
+                continue;
+            };
+            if lxmf_delivery_hash_from_identity_hash(&identity.address_hash).to_hex_string()
+                != record.destination
+            {
+                continue;
+            }
+            bridge.restore_peer_identity(&record.destination, &identity)?;
+            restored += 1;
+        }
+        Ok(restored)
+    }
+
+    pub fn save_paths(&self, paths: &[PathRecord]) -> Result<(), std::io::Error> {
+        self.store
+            .replace_paths(paths)
+            .map_err(std::io::Error::other)
+    }
+
+    // Saved paths that expired while the daemon was down are dropped here.
+    pub fn load_paths(&self) -> Result<Vec<PathRecord>, std::io::Error> {
+        self.store
+            .load_paths(now_i64())
+            .map_err(std::io::Error::other)
+    }
+
+    pub fn accept_announce(&self, peer: String, timestamp: i64) -> Result<(), std::io::Error> {
+        self.accept_announce_with_metadata(
+            peer, timestamp, None, None, None, None, None, None, None, None, None, None, None,
+            None, None, None, None, None, None,
+        )
+    }
+
+    pub fn accept_announce_with_details(
+        &self,
+        peer: String,
+        timestamp: i64,
+        name: Option<String>,
+        name_source: Option<String>,
+    ) -> Result<(), std::io::Error> {
+        self.accept_announce_with_metadata(
+            peer,
+            timestamp,
+            name,
+            name_source,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn accept_announce_with_metadata(
+        &self,
+        peer: String,
+        timestamp: i64,
+        name: Option<String>,
+        name_source: Option<String>,
+        app_data_hex: Option<String>,
+        capabilities: Option<Vec<String>>,
+        rssi: Option<f64>,
+        snr: Option<f64>,
+        q: Option<f64>,
+        stamp_cost: Option<u32>,
+        stamp_cost_flexibility: Option<Option<u32>>,
+        peering_cost: Option<Option<u32>>,
+        aspect: Option<String>,
+        hops: Option<u32>,
+        interface: Option<String>,
+        source_private_key: Option<String>,
+        source_identity: Option<String>,
+        source_node: Option<String>,
+        public_key_hex: Option<String>,
+    ) -> Result<(), std::io::Error> {
+        // Costs not supplied by the caller fall back to what the app data
+        // advertises; an explicit Some(None) clears them.
+        let (parsed_stamp_cost, parsed_stamp_cost_flexibility, parsed_peering_cost) =
+            parse_announce_costs_from_app_data_hex(app_data_hex.as_deref());
+        let stamp_cost = stamp_cost.or(parsed_stamp_cost);
+        let stamp_cost_flexibility =
+            stamp_cost_flexibility.unwrap_or(parsed_stamp_cost_flexibility);
+        let peering_cost = peering_cost.unwrap_or(parsed_peering_cost);
+        let interface = clean_optional_text(interface);
+        let record = self.upsert_peer(peer, timestamp, name, name_source, interface.clone());
+        let capability_list = if let Some(caps) = capabilities {
+            normalize_capabilities(caps)
+        } else {
+            parse_capabilities_from_app_data_hex(app_data_hex.as_deref())
+        };
+
+        let announce_record = AnnounceRecord {
+            id: format!(
+                "announce-{}-{}-{}",
+                record.last_seen, record.peer, record.seen_count
+            ),
+            peer: record.peer.clone(),
+            timestamp: record.last_seen,
+            name: record.name.clone(),
+            name_source: record.name_source.clone(),
+            first_seen: record.first_seen,
+            seen_count: record.seen_count,
+            app_data_hex: clean_optional_text(app_data_hex),
+            capabilities: capability_list.clone(),
+            rssi,
+            snr,
+            q,
+            stamp_cost_flexibility,
+            peering_cost,
+            stamp_cost,
+            hops,
+            public_key_hex: clean_optional_text(public_key_hex),
+            interface: interface.clone(),
+        };
+        self.store
+            .insert_announce(&announce_record)
+            .map_err(std::io::Error::other)?;
+
+        let event = RpcEvent {
+            event_type: "announce_received".into(),
+            payload: json!({
+                "id": announce_record.id,
+                "peer": record.peer,
                 "timestamp": record.last_seen,
                 "name": record.name,
                 "name_source": record.name_source,
@@ -261,6 +1436,7 @@ impl RpcDaemon {
                 "q": q,
                 "stamp_cost_flexibility": stamp_cost_flexibility,
                 "peering_cost": peering_cost,
+                "stamp_cost": stamp_cost,
                 "aspect": aspect,
                 "hops": hops,
                 "interface": interface,
@@ -280,6 +1456,7 @@ impl RpcDaemon {
         timestamp: i64,
         name: Option<String>,
         name_source: Option<String>,
+        interface: Option<String>,
     ) -> PeerRecord {
         let cleaned_name = clean_optional_text(name);
         let cleaned_name_source = clean_optional_text(name_source);
@@ -292,6 +1469,10 @@ impl RpcDaemon {
                 existing.name = Some(name);
                 existing.name_source = cleaned_name_source;
             }
+            // Keep the last known interface when the update doesn't carry one.
+            if interface.is_some() {
+                existing.interface = interface;
+            }
             return existing.clone();
         }
 
@@ -302,11 +1483,22 @@ impl RpcDaemon {
             name_source: cleaned_name_source,
             first_seen: timestamp,
             seen_count: 1,
+            interface,
         };
-        guard.insert(peer, record.clone());
+        guard.insert(peer.clone(), record.clone());
+        let max_peers = *self.max_peers.lock().expect("max_peers mutex poisoned");
+        let evicted = evict_stale_peers(&mut guard, max_peers, Some(&peer));
+        drop(guard);
+        self.forget_evicted_peers(&evicted);
         record
     }
 
+    fn forget_evicted_peers(&self, evicted: &[String]) {
+        for peer in evicted {
+            let _ = self.store.clear_announces_for_peer(peer);
+        }
+    }
+
     #[cfg_attr(not(test), allow(dead_code))]
     pub(crate) fn accept_inbound_for_test(
         &self,
@@ -326,6 +1518,25 @@ impl RpcDaemon {
                 })),
                 error: None,
             }),
+            "get_identity_info" => {
+                let public_key = self
+                    .local_identity
+                    .lock()
+                    .expect("local_identity mutex poisoned")
+                    .as_ref()
+                    .map(|identity| identity.as_identity().to_hex_string());
+                let sign_events = *self.sign_events.lock().expect("sign_events mutex poisoned");
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "identity_hash": self.identity_hash,
+                        "delivery_destination_hash": self.local_delivery_hash(),
+                        "public_key": public_key,
+                        "sign_events": sign_events && public_key.is_some(),
+                    })),
+                    error: None,
+                })
+            }
             "daemon_status_ex" => {
                 let peer_count = self.peers.lock().expect("peers mutex poisoned").len();
                 let interfaces = self
@@ -333,11 +1544,7 @@ impl RpcDaemon {
                     .lock()
                     .expect("interfaces mutex poisoned")
                     .clone();
-                let message_count = self
-                    .store
-                    .list_messages(10_000, None)
-                    .map_err(std::io::Error::other)?
-                    .len();
+                let message_count = self.store.count_messages().map_err(std::io::Error::other)?;
                 let delivery_policy = self
                     .delivery_policy
                     .lock()
@@ -353,6 +1560,16 @@ impl RpcDaemon {
                     .lock()
                     .expect("stamp mutex poisoned")
                     .clone();
+                let inbound_overload = self
+                    .inbound_overload_policy
+                    .lock()
+                    .expect("inbound_overload_policy mutex poisoned")
+                    .clone();
+                let inbound_dropped = self
+                    .inbound_load
+                    .lock()
+                    .expect("inbound_load mutex poisoned")
+                    .total_dropped;
 
                 Ok(RpcResponse {
                     id: request.id,
@@ -360,57 +1577,172 @@ impl RpcDaemon {
                         "identity_hash": self.identity_hash,
                         "delivery_destination_hash": self.local_delivery_hash(),
                         "running": true,
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "git_sha": BUILD_GIT_SHA,
+                        "build_time": BUILD_TIME.parse::<i64>().ok(),
+                        "rns_protocol_version": RNS_PROTOCOL_VERSION,
+                        "contract_version": RPC_CONTRACT_VERSION,
                         "peer_count": peer_count,
+                        "max_peers": *self.max_peers.lock().expect("max_peers mutex poisoned"),
                         "message_count": message_count,
                         "interface_count": interfaces.len(),
                         "interfaces": interfaces,
                         "delivery_policy": delivery_policy,
                         "propagation": propagation,
                         "stamp_policy": stamp_policy,
+                        "announce_aspects": self.advertised_aspects(),
+                        "inbound_overload": {
+                            "policy": inbound_overload,
+                            "dropped": inbound_dropped,
+                        },
                         "capabilities": Self::capabilities(),
                     })),
                     error: None,
                 })
             }
             "list_messages" => {
-                let items = self
-                    .store
-                    .list_messages(100, None)
-                    .map_err(std::io::Error::other)?;
-                Ok(RpcResponse {
-                    id: request.id,
-                    result: Some(json!({
-                        "messages": items,
-                        "meta": self.response_meta(),
-                    })),
-                    error: None,
-                })
-            }
-            "list_announces" => {
                 let parsed = request
                     .params
-                    .map(serde_json::from_value::<ListAnnouncesParams>)
+                    .map(serde_json::from_value::<ListMessagesParams>)
                     .transpose()
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
                     .unwrap_or_default();
-                let limit = parsed.limit.unwrap_or(200).clamp(1, 5000);
-                let (before_ts, before_id) = match parsed.before_ts {
-                    Some(timestamp) => (Some(timestamp), None),
-                    None => parse_announce_cursor(parsed.cursor.as_deref()).unwrap_or((None, None)),
-                };
-                let items = self
-                    .store
-                    .list_announces(limit, before_ts, before_id.as_deref())
-                    .map_err(std::io::Error::other)?;
-                let next_cursor = if items.len() >= limit {
-                    items
-                        .last()
-                        .map(|record| format!("{}:{}", record.timestamp, record.id))
-                } else {
-                    None
-                };
-                Ok(RpcResponse {
-                    id: request.id,
+                let direction = parsed
+                    .direction
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty());
+                if let Some(direction) = direction {
+                    if direction != "in" && direction != "out" {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "direction must be \"in\" or \"out\"",
+                        ));
+                    }
+                }
+                let peer = parsed
+                    .peer
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty());
+                if let Some(unknown) = parsed.fields_projection.iter().flatten().find(|column| {
+                    !crate::storage::messages::MESSAGE_COLUMNS.contains(&column.as_str())
+                }) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("unknown message column {unknown:?}"),
+                    ));
+                }
+                let total = if direction.is_none() && peer.is_none() {
+                    self.store.count_messages()
+                } else {
+                    self.store.count_messages_filtered(direction, peer)
+                }
+                .map_err(std::io::Error::other)?;
+                let projected = parsed.fields_projection.is_some();
+                let mut messages = if let Some(columns) = &parsed.fields_projection {
+                    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+                    self.store
+                        .list_message_columns(100, direction, peer, &columns)
+                        .map_err(std::io::Error::other)?
+                        .into_iter()
+                        .map(JsonValue::Object)
+                        .collect::<Vec<_>>()
+                } else {
+                    if direction.is_none() && peer.is_none() {
+                        self.store.list_messages(100, None)
+                    } else {
+                        self.store.list_messages_filtered(100, direction, peer)
+                    }
+                    .map_err(std::io::Error::other)?
+                    .into_iter()
+                    .map(|item| json!(item))
+                    .collect()
+                };
+                if parsed.enrich {
+                    // Resolve every source under a single peers lock rather
+                    // than looking each row up on its own. Projections that
+                    // leave out the source are returned as they are.
+                    let peers = self.peers.lock().expect("peers mutex poisoned");
+                    for message in &mut messages {
+                        let Some(source) = message
+                            .get("source")
+                            .and_then(JsonValue::as_str)
+                            .map(str::to_string)
+                        else {
+                            continue;
+                        };
+                        let name = peers
+                            .get(source.as_str())
+                            .and_then(|peer| peer.name.clone());
+                        let alias = peer_display_alias(&source, name.as_deref());
+                        if let Some(object) = message.as_object_mut() {
+                            object.insert("source_name".into(), json!(name));
+                            object.insert("source_alias".into(), json!(alias));
+                        }
+                    }
+                }
+                // A projection returns exactly the columns asked for.
+                if !projected {
+                    for message in &mut messages {
+                        let stamp = match message.get("id").and_then(JsonValue::as_str) {
+                            Some(id) => self
+                                .store
+                                .get_message_stamp(id)
+                                .map_err(std::io::Error::other)?,
+                            None => None,
+                        };
+                        if let Some(object) = message.as_object_mut() {
+                            object.insert("stamp".into(), json!(stamp));
+                        }
+                    }
+                }
+                let messages = if parsed.collapse_groups {
+                    collapse_message_groups(messages)
+                } else {
+                    messages
+                };
+                let mut meta = self.response_meta();
+                meta["total"] = json!(total);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "messages": messages,
+                        "meta": meta,
+                    })),
+                    error: None,
+                })
+            }
+            "list_announces" => {
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<ListAnnouncesParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or_default();
+                let limit = parsed.limit.unwrap_or(200).clamp(1, 5000);
+                let (before_ts, before_id) = match parsed.before_ts {
+                    Some(timestamp) => (Some(timestamp), None),
+                    None => parse_announce_cursor(parsed.cursor.as_deref()).unwrap_or((None, None)),
+                };
+                let items = self
+                    .store
+                    .list_announces(
+                        limit,
+                        before_ts,
+                        before_id.as_deref(),
+                        parsed.interface.as_deref(),
+                    )
+                    .map_err(std::io::Error::other)?;
+                let next_cursor = if items.len() >= limit {
+                    items
+                        .last()
+                        .map(|record| format!("{}:{}", record.timestamp, record.id))
+                } else {
+                    None
+                };
+                Ok(RpcResponse {
+                    id: request.id,
                     result: Some(json!({
                         "announces": items,
                         "next_cursor": next_cursor,
@@ -420,69 +1752,371 @@ impl RpcDaemon {
                 })
             }
             "list_peers" => {
-                let mut peers = self
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<ListPeersParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or_default();
+                let peers = self
                     .peers
                     .lock()
                     .expect("peers mutex poisoned")
                     .values()
+                    .filter(|record| {
+                        parsed.interface.is_none() || record.interface == parsed.interface
+                    })
                     .cloned()
                     .collect::<Vec<_>>();
-                peers.sort_by(|a, b| {
-                    b.last_seen
-                        .cmp(&a.last_seen)
-                        .then_with(|| a.peer.cmp(&b.peer))
-                });
+                let activity = self
+                    .store
+                    .peer_message_activity(
+                        &peers
+                            .iter()
+                            .map(|record| record.peer.as_str())
+                            .collect::<Vec<_>>(),
+                    )
+                    .map_err(std::io::Error::other)?;
+                let thresholds = *self
+                    .presence_thresholds
+                    .lock()
+                    .expect("presence mutex poisoned");
+                let now = now_i64();
+                let mut entries = peers
+                    .into_iter()
+                    .map(|record| {
+                        let (last_message, message_count) =
+                            activity.get(&record.peer).copied().unwrap_or((0, 0));
+                        (record.last_seen.max(last_message), message_count, record)
+                    })
+                    .collect::<Vec<_>>();
+                entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.peer.cmp(&b.2.peer)));
+                let peers = entries
+                    .into_iter()
+                    .map(|(last_contact, message_count, record)| {
+                        let mut value = serde_json::to_value(record).unwrap_or_default();
+                        if let Some(object) = value.as_object_mut() {
+                            object.insert("last_contact".into(), json!(last_contact));
+                            object.insert("message_count".into(), json!(message_count));
+                            object.insert(
+                                "presence".into(),
+                                json!(thresholds.presence(now - last_contact)),
+                            );
+                        }
+                        value
+                    })
+                    .collect::<Vec<_>>();
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({
                         "peers": peers,
+                        "presence_thresholds": thresholds,
                         "meta": self.response_meta(),
                     })),
                     error: None,
                 })
             }
             "list_interfaces" => {
+                let records = self
+                    .interfaces
+                    .lock()
+                    .expect("interfaces mutex poisoned")
+                    .clone();
+                let statuses = self.interface_runtime_statuses(&records);
+                let interfaces = records
+                    .into_iter()
+                    .zip(statuses)
+                    .map(|(record, status)| {
+                        let mut value = serde_json::to_value(record).unwrap_or_default();
+                        if let Some(object) = value.as_object_mut() {
+                            object.insert("status".into(), json!(status));
+                        }
+                        value
+                    })
+                    .collect::<Vec<_>>();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "interfaces": interfaces,
+                        "meta": self.response_meta(),
+                    })),
+                    error: None,
+                })
+            }
+            "interface_stats" => {
                 let interfaces = self
                     .interfaces
                     .lock()
                     .expect("interfaces mutex poisoned")
                     .clone();
+                let connections = self
+                    .interface_connections
+                    .lock()
+                    .expect("interface_connections mutex poisoned")
+                    .clone();
+                let bindings = self
+                    .interface_bindings
+                    .lock()
+                    .expect("interface_bindings mutex poisoned")
+                    .clone();
+                let statuses = self.interface_runtime_statuses(&interfaces);
+                let stats: Vec<JsonValue> = interfaces
+                    .iter()
+                    .zip(statuses)
+                    .map(|(record, status)| {
+                        let connection =
+                            record.name.as_ref().and_then(|name| connections.get(name));
+                        let binding = record.name.as_ref().and_then(|name| bindings.get(name));
+                        json!({
+                            "type": record.kind,
+                            "name": record.name,
+                            "enabled": record.enabled,
+                            "status": status,
+                            "host": record.host,
+                            "port": record.port,
+                            "connection": connection,
+                            "bound_addr": binding.and_then(|binding| binding.bound_addr.clone()),
+                            "bind_error": binding.and_then(|binding| binding.error.clone()),
+                        })
+                    })
+                    .collect();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "interfaces": stats,
+                        "meta": self.response_meta(),
+                    })),
+                    error: None,
+                })
+            }
+            "interface_stats_history" => {
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<InterfaceStatsHistoryParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or_default();
+                let Some(bridge) = &self.interface_traffic_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "interface history requires an attached transport".into(),
+                        }),
+                    });
+                };
+                let mut traffic = bridge.interface_traffic();
+                if let Some(interface) = parsed.interface.as_deref() {
+                    traffic.retain(|(name, _)| name == interface);
+                    if traffic.is_empty() {
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: None,
+                            error: Some(RpcError {
+                                code: "INTERFACE_NOT_FOUND".into(),
+                                message: format!("no interface {interface}"),
+                            }),
+                        });
+                    }
+                }
+                traffic.sort_by(|(a, _), (b, _)| a.cmp(b));
+                let interfaces: Vec<JsonValue> = traffic
+                    .into_iter()
+                    .map(|(name, history)| {
+                        json!({
+                            "name": name,
+                            "rx_bytes": history.rx_bytes,
+                            "tx_bytes": history.tx_bytes,
+                            "samples": history.samples,
+                        })
+                    })
+                    .collect();
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({
                         "interfaces": interfaces,
+                        "sample_interval_secs": 1,
                         "meta": self.response_meta(),
                     })),
                     error: None,
                 })
             }
-            "set_interfaces" => {
+            "truncate_hash" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
                 })?;
-                let parsed: SetInterfacesParams = serde_json::from_value(params)
+                let parsed: HashHexParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
-
-                for iface in &parsed.interfaces {
-                    if iface.kind.trim().is_empty() {
-                        return Err(std::io::Error::new(
-                            std::io::ErrorKind::InvalidInput,
-                            "interface type is required",
-                        ));
+                let hash = full_hash_from_hex(&parsed.hash_hex, "hash_hex")?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "hash_hex": hex::encode(hash.as_slice()),
+                        "address_hash": AddressHash::new_from_hash(&hash).to_hex_string(),
+                    })),
+                    error: None,
+                })
+            }
+            // Never fails on malformed input; reporting what is wrong with it
+            // is the point of the call.
+            "validate_hash" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: HashHexParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let length_bytes = decode_hash_hex(&parsed.hash_hex).map(|bytes| bytes.len());
+                let is_address_hash = length_bytes == Some(ADDRESS_HASH_SIZE);
+                let is_full_hash = length_bytes == Some(HASH_SIZE);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "valid": is_address_hash || is_full_hash,
+                        "is_hex": length_bytes.is_some(),
+                        "length_bytes": length_bytes,
+                        "is_address_hash": is_address_hash,
+                        "is_full_hash": is_full_hash,
+                        "expected_address_hash_bytes": ADDRESS_HASH_SIZE,
+                        "expected_full_hash_bytes": HASH_SIZE,
+                    })),
+                    error: None,
+                })
+            }
+            // hash_hex is a full hash truncated the same way lxmf_address_hash
+            // does; identity_hash yields the identity's lxmf.delivery address.
+            "lxmf_address_hash" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: LxmfAddressHashParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let address = match (parsed.hash_hex, parsed.identity_hash) {
+                    (Some(hash_hex), None) => {
+                        lxmf_address_hash(&full_hash_from_hex(&hash_hex, "hash_hex")?)
                     }
-                    if iface.kind == "tcp_client" && (iface.host.is_none() || iface.port.is_none())
-                    {
+                    (None, Some(identity_hash)) => lxmf_delivery_hash_from_identity_hash(
+                        &address_hash_from_hex(&identity_hash, "identity_hash")?,
+                    ),
+                    _ => {
                         return Err(std::io::Error::new(
                             std::io::ErrorKind::InvalidInput,
-                            "tcp_client requires host and port",
-                        ));
+                            "provide exactly one of hash_hex or identity_hash",
+                        ))
+                    }
+                };
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "address_hash": address.to_hex_string() })),
+                    error: None,
+                })
+            }
+            "restart_transport" => {
+                let Some(bridge) = &self.transport_control_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "no transport is attached".into(),
+                        }),
+                    });
+                };
+                let interfaces = match bridge.restart_transport() {
+                    Ok(interfaces) => interfaces,
+                    Err(err) => {
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: None,
+                            error: Some(RpcError {
+                                code: "TRANSPORT_RESTART_FAILED".into(),
+                                message: err.to_string(),
+                            }),
+                        });
                     }
-                    if iface.kind == "tcp_server" && iface.port.is_none() {
+                };
+                self.replace_interfaces(interfaces.clone());
+                let payload = json!({
+                    "timestamp": now_i64(),
+                    "interfaces": interfaces,
+                });
+                let event = RpcEvent {
+                    event_type: "transport_restarted".into(),
+                    payload: payload.clone(),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(payload),
+                    error: None,
+                })
+            }
+            "validate_config" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ValidateConfigParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                // Files are never read on a caller's behalf: parse errors quote
+                // the offending lines, which would leak arbitrary files.
+                if parsed.path.is_some() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "validate_config does not read files; pass the config inline",
+                    ));
+                }
+                // A string config is TOML text as it would appear on disk; an
+                // object is the same structure as JSON.
+                let source = match parsed.config {
+                    Some(JsonValue::String(text)) => ConfigSource::Toml(text),
+                    Some(value @ JsonValue::Object(_)) => ConfigSource::Json(value),
+                    _ => {
                         return Err(std::io::Error::new(
                             std::io::ErrorKind::InvalidInput,
-                            "tcp_server requires port",
-                        ));
+                            "provide an inline config string or object",
+                        ))
+                    }
+                };
+                let Some(bridge) = &self.config_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "CONFIG_VALIDATOR_UNAVAILABLE".into(),
+                            message: "config validation is not available in this daemon".into(),
+                        }),
+                    });
+                };
+                let (errors, warnings) = match bridge.check_config(source) {
+                    Ok(checked) => {
+                        let (mut errors, mut warnings) =
+                            check_interface_records(&checked.interfaces);
+                        errors.extend(checked.errors);
+                        warnings.extend(checked.warnings);
+                        (errors, warnings)
                     }
+                    Err(err) => (vec![err], Vec::new()),
+                };
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "valid": errors.is_empty(),
+                        "errors": errors,
+                        "warnings": warnings,
+                    })),
+                    error: None,
+                })
+            }
+            "set_interfaces" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SetInterfacesParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+
+                if let Some(err) = parsed.interfaces.iter().find_map(interface_record_error) {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, err));
                 }
 
                 {
@@ -525,7 +2159,7 @@ impl RpcDaemon {
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
 
                 let timestamp = now_i64();
-                let record = self.upsert_peer(parsed.peer, timestamp, None, None);
+                let record = self.upsert_peer(parsed.peer, timestamp, None, None, None);
                 let event = RpcEvent {
                     event_type: "peer_sync".into(),
                     payload: json!({
@@ -575,23 +2209,34 @@ impl RpcDaemon {
                 })?;
                 let parsed: SendMessageParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let source =
+                    source_for_private_key(parsed.source, parsed.source_private_key.as_deref())?;
+                let (source, destination) = self.validate_outbound_addresses(
+                    source,
+                    parsed.destination,
+                    parsed.allow_logical_destination,
+                )?;
                 let options = OutboundDeliveryOptions {
                     source_private_key: parsed.source_private_key,
+                    deliver_by: validate_deliver_by(parsed.deliver_by)?,
                     ..Default::default()
                 };
+                let fields = self.with_content_refs(parsed.fields, &parsed.content_refs)?;
 
                 self.store_outbound(
                     request.id,
                     parsed.id,
-                    parsed.source,
-                    parsed.destination,
+                    source,
+                    destination,
                     parsed.title,
                     parsed.content,
-                    parsed.fields,
+                    fields,
+                    parsed.content_type,
                     None,
                     None,
                     options,
                     None,
+                    parsed.dry_run,
                 )
             }
             "send_message_v2" => {
@@ -600,16 +2245,26 @@ impl RpcDaemon {
                 })?;
                 let parsed: SendMessageV2Params = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let source =
+                    source_for_private_key(parsed.source, parsed.source_private_key.as_deref())?;
+                let (source, destination) = self.validate_outbound_addresses(
+                    source,
+                    parsed.destination,
+                    parsed.allow_logical_destination,
+                )?;
                 let outbound_method = parsed.method.clone();
+                let deliver_by = validate_deliver_by(parsed.deliver_by)?;
+                let fields = self.with_content_refs(parsed.fields, &parsed.content_refs)?;
 
                 self.store_outbound(
                     request.id,
                     parsed.id,
-                    parsed.source,
-                    parsed.destination,
+                    source,
+                    destination,
                     parsed.title,
                     parsed.content,
-                    parsed.fields,
+                    fields,
+                    parsed.content_type,
                     outbound_method.clone(),
                     parsed.stamp_cost,
                     OutboundDeliveryOptions {
@@ -619,100 +2274,1727 @@ impl RpcDaemon {
                         try_propagation_on_fail: parsed.try_propagation_on_fail.unwrap_or_default(),
                         ticket: None,
                         source_private_key: parsed.source_private_key,
+                        deliver_by,
+                        identity_relay: None,
                     },
                     parsed.include_ticket,
+                    parsed.dry_run,
                 )
             }
-            "receive_message" => {
+            // Sends one message to several recipients, each copy encrypted for
+            // its own recipient. Every address is checked before anything is
+            // stored, so a bad recipient rejects the whole call.
+            "send_multicast" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
                 })?;
-                let parsed: SendMessageParams = serde_json::from_value(params)
+                let parsed: SendMulticastParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
-                let timestamp = now_i64();
-                let record = MessageRecord {
-                    id: parsed.id.clone(),
-                    source: parsed.source,
-                    destination: parsed.destination,
-                    title: parsed.title,
-                    content: parsed.content,
-                    timestamp,
-                    direction: "in".into(),
-                    fields: parsed.fields,
-                    receipt_status: None,
-                };
-                self.store_inbound_record(record)?;
+                let source =
+                    source_for_private_key(parsed.source, parsed.source_private_key.as_deref())?;
+                let mut recipients: Vec<String> = Vec::with_capacity(parsed.destinations.len());
+                for destination in parsed.destinations {
+                    let (_, destination) = self.validate_outbound_addresses(
+                        source.clone(),
+                        destination,
+                        parsed.allow_logical_destination,
+                    )?;
+                    if !recipients.contains(&destination) {
+                        recipients.push(destination);
+                    }
+                }
+                if recipients.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destinations must not be empty",
+                    ));
+                }
+                validate_known_fields(parsed.fields.as_ref())?;
+                let group_id = parsed
+                    .group_id
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .unwrap_or_else(|| {
+                        let mut hasher = Sha256::new();
+                        hasher.update(source.as_bytes());
+                        for recipient in &recipients {
+                            hasher.update(recipient.as_bytes());
+                        }
+                        hasher.update(parsed.content.as_bytes());
+                        hasher.update(request.id.to_be_bytes());
+                        hasher.update(now_i64().to_be_bytes());
+                        encode_hex(&hasher.finalize()[..16])
+                    });
+                let fields = with_group_id_field(parsed.fields, &group_id);
+                // Callers reuse group ids, so each send gets a fresh nonce and
+                // a copy never replaces one stored by an earlier multicast.
+                let mut nonce = [0u8; 16];
+                OsRng.fill_bytes(&mut nonce);
+                let mut results = Vec::with_capacity(recipients.len());
+                for destination in recipients {
+                    let message_id = {
+                        let mut hasher = Sha256::new();
+                        hasher.update(group_id.as_bytes());
+                        hasher.update([0]);
+                        hasher.update(destination.as_bytes());
+                        hasher.update(nonce);
+                        encode_hex(&hasher.finalize()[..16])
+                    };
+                    // A failure for one recipient is reported in its result
+                    // rather than abandoning the recipients after it.
+                    let response = self.store_outbound(
+                        request.id,
+                        message_id.clone(),
+                        source.clone(),
+                        destination.clone(),
+                        parsed.title.clone(),
+                        parsed.content.clone(),
+                        fields.clone(),
+                        parsed.content_type.clone(),
+                        None,
+                        None,
+                        OutboundDeliveryOptions {
+                            source_private_key: parsed.source_private_key.clone(),
+                            ..Default::default()
+                        },
+                        None,
+                        false,
+                    );
+                    let error = match response {
+                        Ok(response) => response.error.map(|error| error.message),
+                        Err(err) => Some(err.to_string()),
+                    };
+                    results.push(match error {
+                        Some(error) => json!({
+                            "destination": destination,
+                            "message_id": message_id,
+                            "status": "failed",
+                            "error": error,
+                        }),
+                        None => json!({
+                            "destination": destination,
+                            "message_id": message_id,
+                            "status": "sent",
+                        }),
+                    });
+                }
                 Ok(RpcResponse {
                     id: request.id,
-                    result: Some(json!({ "message_id": parsed.id })),
+                    result: Some(json!({ "group_id": group_id, "results": results })),
                     error: None,
                 })
             }
-            "record_receipt" => {
+            "edit_message" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
                 })?;
-                let parsed: RecordReceiptParams = serde_json::from_value(params)
+                let parsed: EditMessageParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
-                self.store
-                    .update_receipt_status(&parsed.message_id, &parsed.status)
-                    .map_err(std::io::Error::other)?;
-                let message_id = parsed.message_id;
-                let status = parsed.status;
-                self.append_delivery_trace(&message_id, status.clone());
-                let reason_code = delivery_reason_code(&status);
-                let event = RpcEvent {
-                    event_type: "receipt".into(),
+                let Some(original) = self
+                    .store
+                    .get_message(&parsed.message_id)
+                    .map_err(std::io::Error::other)?
+                else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "MESSAGE_NOT_FOUND".into(),
+                            message: format!("no message {}", parsed.message_id),
+                        }),
+                    });
+                };
+                if original.direction != "out" {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "only sent messages can be edited",
+                    ));
+                }
+                let title = parsed.title.unwrap_or(original.title);
+                let edited_at = now_i64();
+                let version = self
+                    .store
+                    .edit_message(&original.id, &title, &parsed.content, edited_at)
+                    .map_err(std::io::Error::other)?
+                    .ok_or_else(|| std::io::Error::other("message vanished during edit"))?;
+
+                // The edit travels as its own message so peers that do not
+                // understand the field still see the corrected text.
+                let carrier = MessageRecord {
+                    id: format!("{}:edit:{version}", original.id),
+                    source: original.source,
+                    destination: original.destination,
+                    title: title.clone(),
+                    content: parsed.content.clone(),
+                    timestamp: edited_at,
+                    direction: "out".into(),
+                    fields: Some(json!({
+                        FIELD_EDIT: {
+                            "message_id": original.id,
+                            "version": version,
+                            "edited_at": edited_at,
+                        }
+                    })),
+                    receipt_status: None,
+                    delivery_method: None,
+                    content_type: original.content_type,
+                    attempts: 1,
+                };
+                let delivery = match &self.outbound_bridge {
+                    Some(bridge) => bridge.deliver(&carrier, &OutboundDeliveryOptions::default()),
+                    None => {
+                        let _delivered = crate::transport::test_bridge::deliver_outbound(&carrier);
+                        Ok(())
+                    }
+                };
+                let delivery = match delivery {
+                    Ok(()) => "sent".to_string(),
+                    Err(err) => format!("failed: {err}"),
+                };
+                self.emit_event(RpcEvent {
+                    event_type: "message_edited".into(),
                     payload: json!({
-                        "message_id": message_id,
-                        "status": status,
-                        "reason_code": reason_code,
+                        "message_id": original.id,
+                        "direction": "out",
+                        "version": version,
+                        "edited_at": edited_at,
+                        "title": title,
+                        "content": parsed.content,
+                        "delivery": delivery,
                     }),
-                };
-                self.push_event(event.clone());
-                let _ = self.events.send(event);
+                });
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({
-                        "message_id": message_id,
-                        "status": status,
-                        "reason_code": reason_code,
+                        "message_id": original.id,
+                        "version": version,
+                        "edited_at": edited_at,
+                        "delivery": delivery,
                     })),
                     error: None,
                 })
             }
-            "message_delivery_trace" => {
+            "get_message" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
                 })?;
-                let parsed: MessageDeliveryTraceParams = serde_json::from_value(params)
+                let parsed: MessageIdParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
-                let traces = self
-                    .delivery_traces
-                    .lock()
-                    .expect("delivery traces mutex poisoned")
-                    .get(parsed.message_id.as_str())
-                    .cloned()
-                    .unwrap_or_default();
+                let Some(message) = self
+                    .store
+                    .get_message(&parsed.message_id)
+                    .map_err(std::io::Error::other)?
+                else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "MESSAGE_NOT_FOUND".into(),
+                            message: format!("no message {}", parsed.message_id),
+                        }),
+                    });
+                };
+                let stamp = self
+                    .store
+                    .get_message_stamp(&parsed.message_id)
+                    .map_err(std::io::Error::other)?;
+                let mut message = json!(message);
+                if let Some(object) = message.as_object_mut() {
+                    object.insert("stamp".into(), json!(stamp));
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "message": message })),
+                    error: None,
+                })
+            }
+            "stamp_stats" => {
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<StampStatsParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or_default();
+                let since = parsed.window_secs.map_or(0, |window| {
+                    now_i64().saturating_sub(i64::try_from(window).unwrap_or(i64::MAX))
+                });
+                let stats = self
+                    .store
+                    .stamp_stats(since)
+                    .map_err(std::io::Error::other)?;
+                let target_cost = self
+                    .stamp_policy
+                    .lock()
+                    .expect("stamp mutex poisoned")
+                    .target_cost;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "since": since,
+                        "target_cost": target_cost,
+                        "stats": stats,
+                    })),
+                    error: None,
+                })
+            }
+            "delete_message" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: MessageIdParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let deleted_at = now_i64();
+                let deleted = self
+                    .store
+                    .delete_message(&parsed.message_id, deleted_at)
+                    .map_err(std::io::Error::other)?;
+                if !deleted {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "MESSAGE_NOT_FOUND".into(),
+                            message: format!("no message {}", parsed.message_id),
+                        }),
+                    });
+                }
+                self.emit_event(RpcEvent {
+                    event_type: "message_deleted".into(),
+                    payload: json!({
+                        "message_id": parsed.message_id,
+                        "deleted_at": deleted_at,
+                    }),
+                });
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "message_id": parsed.message_id,
+                        "deleted_at": deleted_at,
+                    })),
+                    error: None,
+                })
+            }
+            "list_deleted_since" => {
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<ListDeletedSinceParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or(ListDeletedSinceParams { since: 0 });
+                let tombstones = self
+                    .store
+                    .list_deleted_since(parsed.since)
+                    .map_err(std::io::Error::other)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "tombstones": tombstones })),
+                    error: None,
+                })
+            }
+            "purge_deleted" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: PurgeDeletedParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let purged_ids: Vec<String> = self
+                    .store
+                    .list_deleted_since(0)
+                    .map_err(std::io::Error::other)?
+                    .into_iter()
+                    .filter(|tombstone| tombstone.deleted_at < parsed.before)
+                    .map(|tombstone| tombstone.id)
+                    .collect();
+                let purged = self
+                    .store
+                    .purge_deleted(parsed.before)
+                    .map_err(std::io::Error::other)?;
+                {
+                    let mut traces = self
+                        .delivery_traces
+                        .lock()
+                        .expect("delivery traces mutex poisoned");
+                    for id in &purged_ids {
+                        traces.remove(id);
+                    }
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "purged": purged })),
+                    error: None,
+                })
+            }
+            "get_message_versions" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: MessageIdParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let Some(message) = self
+                    .store
+                    .get_message(&parsed.message_id)
+                    .map_err(std::io::Error::other)?
+                else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "MESSAGE_NOT_FOUND".into(),
+                            message: format!("no message {}", parsed.message_id),
+                        }),
+                    });
+                };
+                let versions = self
+                    .store
+                    .list_message_versions(&message.id)
+                    .map_err(std::io::Error::other)?;
+                let edited_at = self
+                    .store
+                    .message_edited_at(&message.id)
+                    .map_err(std::io::Error::other)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "message_id": message.id,
+                        "edited_at": edited_at,
+                        "current": { "title": message.title, "content": message.content },
+                        "versions": versions,
+                    })),
+                    error: None,
+                })
+            }
+            "receive_message" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SendMessageParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                if !self.is_local_inbound_destination(&parsed.destination) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destination is not a local delivery destination",
+                    ));
+                }
+                let timestamp = now_i64();
+                let record = MessageRecord {
+                    id: parsed.id.clone(),
+                    source: parsed.source,
+                    destination: parsed.destination,
+                    title: parsed.title,
+                    content: parsed.content,
+                    timestamp,
+                    direction: "in".into(),
+                    fields: parsed.fields,
+                    receipt_status: None,
+                    delivery_method: None,
+                    content_type: normalize_content_type(parsed.content_type),
+                    attempts: 0,
+                };
+                self.store_inbound_record(record)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "message_id": parsed.id })),
+                    error: None,
+                })
+            }
+            "record_receipt" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let mut parsed: RecordReceiptParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                match self.chunk_receipt(&parsed.message_id, &parsed.status) {
+                    None => {}
+                    Some(Some(parent)) => parsed.message_id = parent,
+                    Some(None) => {
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: Some(json!({
+                                "message_id": parsed.message_id,
+                                "status": parsed.status,
+                            })),
+                            error: None,
+                        })
+                    }
+                }
+                self.store
+                    .update_receipt_status(&parsed.message_id, &parsed.status)
+                    .map_err(std::io::Error::other)?;
+                let message_id = parsed.message_id;
+                let status = parsed.status;
+                let delivery_method = delivery_method_from_status(&status);
+                if let Some(method) = delivery_method.as_deref() {
+                    self.store
+                        .update_delivery_method(&message_id, method)
+                        .map_err(std::io::Error::other)?;
+                }
+                let reason_code = clean_optional_text(parsed.reason_code)
+                    .or_else(|| delivery_reason_code(&status).map(ToOwned::to_owned));
+                self.append_delivery_trace_with_reason(
+                    &message_id,
+                    status.clone(),
+                    reason_code.clone(),
+                );
+                let event = RpcEvent {
+                    event_type: "receipt".into(),
+                    payload: json!({
+                        "message_id": message_id,
+                        "status": status,
+                        "reason_code": reason_code,
+                        "delivery_method": delivery_method,
+                    }),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                if status.starts_with("failed") {
+                    self.emit_delivery_failed(&message_id, &status, reason_code.as_deref());
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "message_id": message_id,
+                        "status": status,
+                        "reason_code": reason_code,
+                        "delivery_method": delivery_method,
+                    })),
+                    error: None,
+                })
+            }
+            "message_delivery_trace" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: MessageDeliveryTraceParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let traces = self.delivery_trace(parsed.message_id.as_str())?;
+                let stage = traces.last().map(|entry| entry.stage);
+                let reason_code = traces
+                    .iter()
+                    .rev()
+                    .find_map(|entry| entry.reason_code.clone());
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "message_id": parsed.message_id,
+                        "stage": stage,
+                        "reason_code": reason_code,
+                        "transitions": traces,
+                        "meta": self.response_meta(),
+                    })),
+                    error: None,
+                })
+            }
+            "derive_delivery_hash" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: DeriveDeliveryHashParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let identity_bytes = hex::decode(parsed.identity_hash.trim()).map_err(|err| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("invalid identity_hash: {err}"),
+                    )
+                })?;
+                let identity_bytes: [u8; 16] =
+                    identity_bytes.as_slice().try_into().map_err(|_| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!(
+                                "identity_hash must be 16 bytes, got {}",
+                                identity_bytes.len()
+                            ),
+                        )
+                    })?;
+                let app = parsed
+                    .app
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .unwrap_or("lxmf");
+                let aspect = parsed
+                    .aspect
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .unwrap_or("delivery");
+                let identity_hash = AddressHash::new(identity_bytes);
+                let destination_hash = destination_hash_from_identity_hash(
+                    &identity_hash,
+                    &DestinationName::new(app, aspect),
+                );
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "identity_hash": hex::encode(identity_bytes),
+                        "app": app,
+                        "aspect": aspect,
+                        "delivery_destination_hash": hex::encode(destination_hash.as_slice()),
+                    })),
+                    error: None,
+                })
+            }
+            "store_peer_identity" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: StorePeerIdentityParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let record = self.store_peer_identity(&parsed.destination, &parsed.identity_hex)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "identity": record })),
+                    error: None,
+                })
+            }
+            "recall_identity" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: RecallIdentityParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let identity =
+                    self.recall_identity(&parsed.destination)?
+                        .map(|(identity, source)| {
+                            json!({
+                                "identity_hex": identity.to_hex_string(),
+                                "identity_hash": hex::encode(identity.address_hash.as_slice()),
+                                "source": source,
+                            })
+                        });
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "identity": identity })),
+                    error: None,
+                })
+            }
+            "bulk_restore_peer_identities" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: BulkRestorePeerIdentitiesParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let mut restored = 0;
+                let mut rejected = Vec::new();
+                for entry in parsed.identities {
+                    match self.store_peer_identity(&entry.destination, &entry.identity_hex) {
+                        Ok(_) => restored += 1,
+                        Err(err) if err.kind() == std::io::ErrorKind::InvalidInput => {
+                            rejected.push(json!({
+                                "destination": entry.destination,
+                                "error": err.to_string(),
+                            }));
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "restored": restored, "rejected": rejected })),
+                    error: None,
+                })
+            }
+            "list_known_identities" => {
+                let identities: Vec<JsonValue> = self
+                    .store
+                    .list_known_identities()
+                    .map_err(std::io::Error::other)?
+                    .iter()
+                    .map(known_identity_json)
+                    .collect();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "identities": identities })),
+                    error: None,
+                })
+            }
+            // Same shape bulk_restore_peer_identities accepts, so an export can
+            // be replayed on another node as-is.
+            "export_known_identities" => {
+                let identities: Vec<JsonValue> = self
+                    .store
+                    .list_known_identities()
+                    .map_err(std::io::Error::other)?
+                    .into_iter()
+                    .map(|record| {
+                        json!({
+                            "destination": record.destination,
+                            "identity_hex": record.identity_hex,
+                        })
+                    })
+                    .collect();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "identities": identities })),
+                    error: None,
+                })
+            }
+            "clear_known_identities" => {
+                let cleared = self
+                    .store
+                    .clear_known_identities()
+                    .map_err(std::io::Error::other)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "cleared": cleared })),
+                    error: None,
+                })
+            }
+            "set_interface_filter" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SetInterfaceFilterParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let interface = parsed.interface.trim().to_string();
+                if interface.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "interface is required",
+                    ));
+                }
+                let filter = parse_interface_filter(
+                    &parsed.drop_packet_types,
+                    &parsed.drop_destination_types,
+                )?;
+                let Some(bridge) = &self.interface_filter_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "interface filters require an attached transport".into(),
+                        }),
+                    });
+                };
+                bridge.set_interface_filter(&interface, Some(filter).filter(|f| !f.is_empty()))?;
+                let status = bridge.interface_filter(&interface)?;
+                let filter_json = interface_filter_json(&interface, status.as_ref());
+                let event = RpcEvent {
+                    event_type: "interface_filter_updated".into(),
+                    payload: filter_json.clone(),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(filter_json),
+                    error: None,
+                })
+            }
+            "get_interface_filter" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: InterfaceFilterParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let interface = parsed.interface.trim().to_string();
+                let Some(bridge) = &self.interface_filter_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "interface filters require an attached transport".into(),
+                        }),
+                    });
+                };
+                let status = bridge.interface_filter(&interface)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(interface_filter_json(&interface, status.as_ref())),
+                    error: None,
+                })
+            }
+            "transport_metrics" => {
+                let Some(bridge) = &self.transport_metrics_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "transport metrics require an attached transport".into(),
+                        }),
+                    });
+                };
+                let metrics = bridge.transport_metrics();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "announces_deduped": metrics.announces_deduped,
+                        "announce_dedup_window_secs": metrics.announce_dedup_window_secs,
+                    })),
+                    error: None,
+                })
+            }
+            "get_bandwidth_limit" => {
+                let Some(bridge) = &self.bandwidth_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "bandwidth limits require an attached transport".into(),
+                        }),
+                    });
+                };
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "bytes_per_sec": bridge.bandwidth_limit(),
+                        "paced_drops": bridge.paced_drops(),
+                    })),
+                    error: None,
+                })
+            }
+            // A missing, null or zero bytes_per_sec removes the limit.
+            "set_bandwidth_limit" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SetBandwidthLimitParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let Some(bridge) = &self.bandwidth_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "bandwidth limits require an attached transport".into(),
+                        }),
+                    });
+                };
+                let limit = parsed.bytes_per_sec.filter(|limit| *limit > 0);
+                bridge.set_bandwidth_limit(limit)?;
+                let event = RpcEvent {
+                    event_type: "bandwidth_limit_changed".into(),
+                    payload: json!({ "bytes_per_sec": limit }),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "bytes_per_sec": limit })),
+                    error: None,
+                })
+            }
+            "set_destination_interface" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SetDestinationInterfaceParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let destination = normalize_hash_hex(&parsed.destination).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destination must be a 16-byte hex hash",
+                    )
+                })?;
+                let interface = parsed.interface.trim().to_string();
+                if interface.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "interface is required",
+                    ));
+                }
+                let Some(bridge) = &self.destination_interface_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "interface hints require an attached transport".into(),
+                        }),
+                    });
+                };
+                bridge.set_destination_interface(&destination, &interface)?;
+                let payload = json!({
+                    "destination": destination,
+                    "interface": interface,
+                });
+                let event = RpcEvent {
+                    event_type: "destination_interface_updated".into(),
+                    payload: payload.clone(),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(payload),
+                    error: None,
+                })
+            }
+            "clear_destination_interface" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: DestinationInterfaceParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let destination = normalize_hash_hex(&parsed.destination).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destination must be a 16-byte hex hash",
+                    )
+                })?;
+                let Some(bridge) = &self.destination_interface_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "interface hints require an attached transport".into(),
+                        }),
+                    });
+                };
+                let cleared = bridge.clear_destination_interface(&destination)?;
+                if cleared {
+                    let event = RpcEvent {
+                        event_type: "destination_interface_updated".into(),
+                        payload: json!({
+                            "destination": destination,
+                            "interface": JsonValue::Null,
+                        }),
+                    };
+                    self.push_event(event.clone());
+                    let _ = self.events.send(event);
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "cleared": cleared })),
+                    error: None,
+                })
+            }
+            // An already active link answers with its measured RTT straight
+            // away, and one still in its handshake is left to finish rather
+            // than raced by a second. Otherwise the handshake runs in the
+            // background and its RTT is reported on the link_established event.
+            "establish_link" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: EstablishLinkParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let destination = normalize_hash_hex(&parsed.destination).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destination must be a 16-byte hex hash",
+                    )
+                })?;
+                let Some(bridge) = &self.link_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "links require an attached transport".into(),
+                        }),
+                    });
+                };
+                let usable = bridge
+                    .list_links()?
+                    .into_iter()
+                    .filter(|link| link.destination == destination && link.direction == "out")
+                    .filter(|link| {
+                        matches!(link.status.as_str(), "active" | "pending" | "handshake")
+                    })
+                    .min_by_key(|link| link.status != "active");
+                if let Some(link) = usable {
+                    let status = if link.status == "active" {
+                        "active"
+                    } else {
+                        "pending"
+                    };
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: Some(json!({
+                            "destination": destination,
+                            "status": status,
+                            "already_existed": true,
+                            "link_id": link.link_id,
+                            "established_at": link.established_at,
+                            "last_activity": link.last_activity,
+                            "rtt_ms": link.rtt_ms,
+                            "rtt_avg_ms": link.rtt_avg_ms,
+                        })),
+                        error: None,
+                    });
+                }
+                let timeout_secs = parsed.timeout_seconds.unwrap_or(20).clamp(1, 120);
+                bridge.establish_link(&destination, Duration::from_secs(timeout_secs))?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "destination": destination,
+                        "status": "pending",
+                        "already_existed": false,
+                        "timeout_ms": timeout_secs * 1000,
+                    })),
+                    error: None,
+                })
+            }
+            "list_active_links" => {
+                let Some(bridge) = &self.link_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "links require an attached transport".into(),
+                        }),
+                    });
+                };
+                let links = bridge.list_links()?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "links": links })),
+                    error: None,
+                })
+            }
+            "close_link" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: CloseLinkParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let link_id = normalize_hash_hex(&parsed.link_id).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "link_id must be a 16-byte hex hash",
+                    )
+                })?;
+                let Some(bridge) = &self.link_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "links require an attached transport".into(),
+                        }),
+                    });
+                };
+                if !bridge.close_link(&link_id)? {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "LINK_NOT_FOUND".into(),
+                            message: format!("no active link {link_id}"),
+                        }),
+                    });
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "link_id": link_id, "closed": true })),
+                    error: None,
+                })
+            }
+            "resource_send" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ResourceSendParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let destination = normalize_hash_hex(&parsed.destination).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destination must be a 16-byte hex hash",
+                    )
+                })?;
+                let data = BASE64_STANDARD
+                    .decode(parsed.data_base64.trim())
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("invalid data_base64: {err}"),
+                        )
+                    })?;
+                if data.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "data_base64 must not be empty",
+                    ));
+                }
+                let metadata = parsed
+                    .metadata_base64
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(|value| BASE64_STANDARD.decode(value))
+                    .transpose()
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("invalid metadata_base64: {err}"),
+                        )
+                    })?;
+                let Some(bridge) = &self.resource_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "resource transfers require an attached transport".into(),
+                        }),
+                    });
+                };
+
+                let data_len = data.len();
+                let transfer_id = {
+                    let mut hasher = Sha256::new();
+                    hasher.update(destination.as_bytes());
+                    hasher.update(&data);
+                    hasher.update(request.id.to_be_bytes());
+                    hasher.update(now_i64().to_be_bytes());
+                    encode_hex(&hasher.finalize()[..16])
+                };
+                let path_timeout_secs = parsed.timeout_seconds.unwrap_or(12).clamp(1, 120);
+                bridge.send_resource(
+                    &transfer_id,
+                    &destination,
+                    data,
+                    metadata,
+                    Duration::from_secs(path_timeout_secs),
+                )?;
+                let event = RpcEvent {
+                    event_type: "resource_queued".into(),
+                    payload: json!({
+                        "transfer_id": transfer_id,
+                        "destination": destination,
+                        "data_len": data_len,
+                    }),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "transfer_id": transfer_id,
+                        // Known once the link is up; handle_rpc_async waits
+                        // for it.
+                        "resource_hash": null,
+                        "destination": destination,
+                        "data_len": data_len,
+                        "path_timeout_ms": path_timeout_secs * 1000,
+                        "status": "queued",
+                    })),
+                    error: None,
+                })
+            }
+            "store_content" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: StoreContentParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let data = BASE64_STANDARD
+                    .decode(parsed.data_base64.trim())
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("invalid data_base64: {err}"),
+                        )
+                    })?;
+                if data.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "data_base64 must not be empty",
+                    ));
+                }
+                let hash = self
+                    .store
+                    .put_content(&data, now_i64())
+                    .map_err(std::io::Error::other)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "hash": hash, "size": data.len() })),
+                    error: None,
+                })
+            }
+            "get_content" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ContentHashParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let hash = normalize_content_hash(&parsed.hash).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "hash must be a 32-byte hex SHA-256 digest",
+                    )
+                })?;
+                let Some(data) = self
+                    .store
+                    .get_content(&hash)
+                    .map_err(std::io::Error::other)?
+                else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "CONTENT_NOT_FOUND".into(),
+                            message: format!("no stored content {hash}"),
+                        }),
+                    });
+                };
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "hash": hash,
+                        "size": data.len(),
+                        "data_base64": BASE64_STANDARD.encode(&data),
+                    })),
+                    error: None,
+                })
+            }
+            "fetch_content" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: FetchContentParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let hash = normalize_content_hash(&parsed.hash).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "hash must be a 32-byte hex SHA-256 digest",
+                    )
+                })?;
+                let peer = normalize_hash_hex(&parsed.peer).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "peer must be a 16-byte hex hash",
+                    )
+                })?;
+                if self
+                    .store
+                    .content_size(&hash)
+                    .map_err(std::io::Error::other)?
+                    .is_some()
+                {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: Some(json!({ "hash": hash, "status": "available" })),
+                        error: None,
+                    });
+                }
+                let Some(bridge) = &self.resource_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "fetching content requires an attached transport".into(),
+                        }),
+                    });
+                };
+
+                let metadata = serde_json::to_vec(&ContentTransfer::Request {
+                    hash: hash.clone(),
+                    reply_to: self.local_delivery_hash(),
+                })
+                .map_err(std::io::Error::other)?;
+                let transfer_id = {
+                    let mut hasher = Sha256::new();
+                    hasher.update(peer.as_bytes());
+                    hasher.update(hash.as_bytes());
+                    hasher.update(request.id.to_be_bytes());
+                    hasher.update(now_i64().to_be_bytes());
+                    encode_hex(&hasher.finalize()[..16])
+                };
+                let path_timeout_secs = parsed.timeout_seconds.unwrap_or(12).clamp(1, 120);
+                bridge.send_resource(
+                    &transfer_id,
+                    &peer,
+                    hash.as_bytes().to_vec(),
+                    Some(metadata),
+                    Duration::from_secs(path_timeout_secs),
+                )?;
+                {
+                    let mut fetches = self
+                        .content_fetches
+                        .lock()
+                        .expect("content_fetches mutex poisoned");
+                    if !fetches.contains(&hash) {
+                        fetches.push_back(hash.clone());
+                    }
+                    while fetches.len() > MAX_PENDING_CONTENT_FETCHES {
+                        fetches.pop_front();
+                    }
+                }
+                let event = RpcEvent {
+                    event_type: "content_requested".into(),
+                    payload: json!({
+                        "hash": hash,
+                        "peer": peer,
+                        "transfer_id": transfer_id,
+                    }),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "hash": hash,
+                        "peer": peer,
+                        "transfer_id": transfer_id,
+                        "status": "requested",
+                    })),
+                    error: None,
+                })
+            }
+            "backup_store" => {
+                let parsed: BackupStoreParams = request
+                    .params
+                    .map(serde_json::from_value)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or_default();
+                let record = self.backup_store(parsed.keep)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "path": record.path,
+                        "size_bytes": record.size_bytes,
+                        "created_at": record.created_at,
+                        "pruned": record.pruned,
+                    })),
+                    error: None,
+                })
+            }
+            "restore_store" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: RestoreStoreParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let Some(staged) = self.restore_store(&parsed.path)? else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "BACKUP_NOT_FOUND".into(),
+                            message: format!("no store backup {}", parsed.path),
+                        }),
+                    });
+                };
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "backup": parsed.path,
+                        "staged": staged,
+                        "applies_on_restart": true,
+                    })),
+                    error: None,
+                })
+            }
+            "send_plain" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SendPlainParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let group = match parsed.group.as_deref().map(str::trim) {
+                    Some(name) => {
+                        let Some(group) = self.open_group(name)? else {
+                            return Ok(RpcResponse {
+                                id: request.id,
+                                result: None,
+                                error: Some(RpcError {
+                                    code: "GROUP_NOT_FOUND".into(),
+                                    message: format!("no group named {name}"),
+                                }),
+                            });
+                        };
+                        Some(group)
+                    }
+                    None => None,
+                };
+                if group.is_none() && !parsed.acknowledge_unencrypted {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "send_plain requires acknowledge_unencrypted: true",
+                    ));
+                }
+                let (app, aspect) = match &group {
+                    Some((record, _)) => (record.app.clone(), record.aspect.clone()),
+                    None => (
+                        parsed.app.trim().to_string(),
+                        parsed.aspect.trim().to_string(),
+                    ),
+                };
+                validate_plain_name(&app, &aspect)?;
+                let data = BASE64_STANDARD
+                    .decode(parsed.data_base64.trim())
+                    .map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("invalid data_base64: {err}"),
+                        )
+                    })?;
+                // Group payloads pay the same Fernet overhead as LXMF packets.
+                let max_len = if group.is_some() {
+                    LXMF_MAX_PAYLOAD
+                } else {
+                    PACKET_MDU
+                };
+                if data.is_empty() || data.len() > max_len {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("data_base64 must decode to 1..={max_len} bytes"),
+                    ));
+                }
+                let Some(bridge) = &self.plain_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "plain broadcasts require an attached transport".into(),
+                        }),
+                    });
+                };
+                let data_len = data.len();
+                match &group {
+                    Some((_, key)) => bridge.send_group(&app, &aspect, *key, data)?,
+                    None => bridge.send_plain(&app, &aspect, data)?,
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "destination": plain_destination_hash(&app, &aspect),
+                        "app": app,
+                        "aspect": aspect,
+                        "group": group.as_ref().map(|(record, _)| record.name.clone()),
+                        "data_len": data_len,
+                        "status": "broadcast",
+                    })),
+                    error: None,
+                })
+            }
+            "create_group" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: CreateGroupParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let name = parsed.name.trim();
+                if self
+                    .store
+                    .get_group_key(name)
+                    .map_err(std::io::Error::other)?
+                    .is_some()
+                {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "GROUP_EXISTS".into(),
+                            message: format!("group {name} already exists"),
+                        }),
+                    });
+                }
+                let mut key = [0u8; 16];
+                OsRng.fill_bytes(&mut key);
+                let record =
+                    self.save_group_key(name, parsed.app.trim(), parsed.aspect.trim(), &key)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "group": group_json(&record),
+                        "key_hex": hex::encode(key),
+                    })),
+                    error: None,
+                })
+            }
+            "import_group_key" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ImportGroupKeyParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let key: [u8; 16] = hex::decode(parsed.key_hex.trim())
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "key_hex must be a 16-byte hex key",
+                        )
+                    })?;
+                let record = self.save_group_key(
+                    parsed.name.trim(),
+                    parsed.app.trim(),
+                    parsed.aspect.trim(),
+                    &key,
+                )?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "group": group_json(&record) })),
+                    error: None,
+                })
+            }
+            "export_group_key" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: GroupNameParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let name = parsed.name.trim();
+                let Some((record, key)) = self.open_group(name)? else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "GROUP_NOT_FOUND".into(),
+                            message: format!("no group named {name}"),
+                        }),
+                    });
+                };
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "group": group_json(&record),
+                        "key_hex": hex::encode(key),
+                    })),
+                    error: None,
+                })
+            }
+            "list_groups" => {
+                let groups = self
+                    .store
+                    .list_group_keys()
+                    .map_err(std::io::Error::other)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "groups": groups.iter().map(group_json).collect::<Vec<_>>(),
+                    })),
+                    error: None,
+                })
+            }
+            "send_telemetry_request" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SendTelemetryRequestParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let (source, destination) = self.validate_outbound_addresses(
+                    parsed.source,
+                    parsed.destination,
+                    parsed.allow_logical_destination,
+                )?;
+                let requested_at = now_i64();
+                let timebase = parsed.timebase.unwrap_or(0);
+                let message_id = parsed
+                    .id
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .unwrap_or_else(|| format!("telemetry-request-{destination}-{requested_at}"));
+                let response = self.store_outbound(
+                    request.id,
+                    message_id.clone(),
+                    source,
+                    destination.clone(),
+                    String::new(),
+                    String::new(),
+                    Some(telemetry::telemetry_request_fields(timebase)),
+                    None,
+                    None,
+                    None,
+                    OutboundDeliveryOptions {
+                        try_propagation_on_fail: parsed.try_propagation_on_fail,
+                        ..Default::default()
+                    },
+                    None,
+                    false,
+                )?;
+                if response.error.is_some() {
+                    return Ok(response);
+                }
+                {
+                    let mut pending = self
+                        .pending_telemetry_requests
+                        .lock()
+                        .expect("pending telemetry mutex poisoned");
+                    if pending.len() >= MAX_PENDING_TELEMETRY_REQUESTS {
+                        pending.remove(0);
+                    }
+                    pending.push(PendingTelemetryRequest {
+                        message_id: message_id.clone(),
+                        peer: destination,
+                        timebase,
+                        requested_at,
+                    });
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "message_id": message_id, "timebase": timebase })),
+                    error: None,
+                })
+            }
+            "list_pending_telemetry_requests" => {
+                let now = now_i64();
+                let requests = self
+                    .pending_telemetry_requests
+                    .lock()
+                    .expect("pending telemetry mutex poisoned")
+                    .iter()
+                    .map(|request| {
+                        json!({
+                            "message_id": request.message_id,
+                            "peer": request.peer,
+                            "timebase": request.timebase,
+                            "requested_at": request.requested_at,
+                            "timed_out": now - request.requested_at > TELEMETRY_REQUEST_TIMEOUT_SECS,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "requests": requests })),
+                    error: None,
+                })
+            }
+            "get_telemetry_history" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: TelemetryHistoryParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let limit = parsed.limit.unwrap_or(100).clamp(1, 5000);
+                let items = self
+                    .store
+                    .list_telemetry(parsed.peer.trim(), limit, parsed.since)
+                    .map_err(std::io::Error::other)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "peer": parsed.peer,
+                        "telemetry": items,
+                        "meta": self.response_meta(),
+                    })),
+                    error: None,
+                })
+            }
+            "get_delivery_policy" => {
+                let policy = self
+                    .delivery_policy
+                    .lock()
+                    .expect("policy mutex poisoned")
+                    .clone();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "policy": policy })),
+                    error: None,
+                })
+            }
+            "get_settings" => {
+                let mut persisted = Vec::new();
+                for key in PERSISTED_SETTINGS {
+                    if self
+                        .store
+                        .get_setting(key)
+                        .map_err(std::io::Error::other)?
+                        .is_some()
+                    {
+                        persisted.push(*key);
+                    }
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "settings": self.settings_snapshot(),
+                        "persisted": persisted,
+                    })),
+                    error: None,
+                })
+            }
+            // Runtime knobs from set_setting keep their current values until
+            // the next start, when the config file and command line apply.
+            "reset_settings" => {
+                let cleared = self.store.clear_settings().map_err(std::io::Error::other)?;
+                let defaults = PersistedSettings::default();
+                *self.delivery_policy.lock().expect("policy mutex poisoned") =
+                    defaults.delivery_policy;
+                *self.stamp_policy.lock().expect("stamp mutex poisoned") = defaults.stamp_policy;
+                *self
+                    .stamp_policy_overrides
+                    .lock()
+                    .expect("stamp overrides mutex poisoned") = defaults.stamp_policy_overrides;
+                *self
+                    .inbound_overload_policy
+                    .lock()
+                    .expect("inbound_overload_policy mutex poisoned") =
+                    defaults.inbound_overload_policy;
+                let settings = self.settings_snapshot();
+                self.emit_event(RpcEvent {
+                    event_type: "settings_reset".into(),
+                    payload: json!({ "cleared": cleared }),
+                });
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "settings": settings, "cleared": cleared })),
+                    error: None,
+                })
+            }
+            "get_setting" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SettingParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let key = parsed.key.trim();
+                if !PERSISTED_SETTINGS.contains(&key) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("unknown setting {key:?}"),
+                    ));
+                }
+                let persisted = self
+                    .store
+                    .get_setting(key)
+                    .map_err(std::io::Error::other)?
+                    .is_some();
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({
-                        "message_id": parsed.message_id,
-                        "transitions": traces,
-                        "meta": self.response_meta(),
+                        "key": key,
+                        "value": self.settings_snapshot()[key],
+                        "persisted": persisted,
                     })),
                     error: None,
                 })
             }
-            "get_delivery_policy" => {
-                let policy = self
-                    .delivery_policy
-                    .lock()
-                    .expect("policy mutex poisoned")
-                    .clone();
+            // Only the scalar knobs in RUNTIME_SETTINGS go through here; the
+            // policies keep their own methods and validation.
+            "set_setting" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: SettingParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let key = RUNTIME_SETTINGS
+                    .iter()
+                    .map(|(key, _, _)| *key)
+                    .find(|key| *key == parsed.key.trim())
+                    .ok_or_else(|| {
+                        let message = if PERSISTED_SETTINGS.contains(&parsed.key.trim()) {
+                            format!("{} has its own set method", parsed.key.trim())
+                        } else {
+                            format!("unknown setting {:?}", parsed.key.trim())
+                        };
+                        std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+                    })?;
+                let value = runtime_setting_value(key, &parsed.value)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                self.persist_setting(key, &value)?;
+                self.apply_runtime_setting(key, value);
+                self.emit_event(RpcEvent {
+                    event_type: "setting_changed".into(),
+                    payload: json!({ "key": key, "value": value }),
+                });
                 Ok(RpcResponse {
                     id: request.id,
-                    result: Some(json!({ "policy": policy })),
+                    result: Some(json!({ "key": key, "value": value })),
                     error: None,
                 })
             }
@@ -742,6 +4024,7 @@ impl RpcDaemon {
                     }
                     guard.clone()
                 };
+                self.persist_setting(SETTING_DELIVERY_POLICY, &policy)?;
 
                 Ok(RpcResponse {
                     id: request.id,
@@ -755,9 +4038,101 @@ impl RpcDaemon {
                     .lock()
                     .expect("propagation mutex poisoned")
                     .clone();
+                let (messages, bytes) = self
+                    .store
+                    .propagation_store_usage()
+                    .map_err(std::io::Error::other)?;
                 Ok(RpcResponse {
                     id: request.id,
-                    result: Some(json!({ "propagation": state })),
+                    result: Some(json!({
+                        "propagation": state,
+                        "store": {
+                            "messages": messages,
+                            "bytes": bytes,
+                            "limits": self.propagation_store_limits(),
+                        },
+                    })),
+                    error: None,
+                })
+            }
+            "propagation_sync_history" => {
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<PropagationSyncHistoryParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?
+                    .unwrap_or_default();
+                let limit = parsed
+                    .limit
+                    .unwrap_or(MAX_PROPAGATION_SYNC_HISTORY)
+                    .min(MAX_PROPAGATION_SYNC_HISTORY);
+                let syncs = self
+                    .propagation_sync_history
+                    .lock()
+                    .expect("propagation sync history mutex poisoned")
+                    .iter()
+                    .rev()
+                    .take(limit)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "syncs": syncs })),
+                    error: None,
+                })
+            }
+            // Aborting while no sync is running is a successful no-op.
+            "propagation_sync_abort" => {
+                let token = self
+                    .propagation_sync_cancel
+                    .lock()
+                    .expect("propagation_sync_cancel mutex poisoned")
+                    .take();
+                let running_task = token.as_ref().is_some_and(|token| !token.is_cancelled());
+                if let Some(token) = token {
+                    token.cancel();
+                }
+                let active_state = is_active_propagation_sync_state(
+                    &self
+                        .propagation_state
+                        .lock()
+                        .expect("propagation mutex poisoned")
+                        .state_name,
+                );
+                let aborted = running_task || active_state;
+                if aborted {
+                    self.update_propagation_sync_state(|state| {
+                        state.sync_state = 0;
+                        state.state_name = "idle".into();
+                        state.sync_progress = 0.0;
+                        state.last_sync_error = Some("aborted".into());
+                    });
+                }
+                // A task cancelled before it reported any state still counts
+                // as an aborted sync.
+                if running_task && !active_state {
+                    let record = propagation_sync_record(
+                        &self
+                            .propagation_state
+                            .lock()
+                            .expect("propagation mutex poisoned"),
+                    );
+                    self.push_propagation_sync_record(record);
+                }
+                let state = self
+                    .propagation_state
+                    .lock()
+                    .expect("propagation mutex poisoned")
+                    .clone();
+                if aborted {
+                    self.emit_event(RpcEvent {
+                        event_type: "propagation_state".into(),
+                        payload: json!({ "propagation": state }),
+                    });
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "aborted": aborted, "propagation": state })),
                     error: None,
                 })
             }
@@ -803,10 +4178,9 @@ impl RpcDaemon {
                 });
 
                 if !payload_hex.is_empty() {
-                    self.propagation_payloads
-                        .lock()
-                        .expect("propagation payload mutex poisoned")
-                        .insert(transient_id.clone(), payload_hex);
+                    self.store
+                        .put_propagation_payload(&transient_id, &payload_hex, now_i64(), true)
+                        .map_err(std::io::Error::other)?;
                 }
 
                 let state = {
@@ -829,6 +4203,75 @@ impl RpcDaemon {
                     error: None,
                 })
             }
+            "propagation_ingest_bulk" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: PropagationIngestBulkParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+
+                let items = parsed
+                    .items
+                    .into_iter()
+                    .map(|item| {
+                        let payload_hex = item.payload_hex.unwrap_or_default();
+                        let transient_id = item.transient_id.unwrap_or_else(|| {
+                            let mut hasher = Sha256::new();
+                            hasher.update(payload_hex.as_bytes());
+                            encode_hex(hasher.finalize())
+                        });
+                        (transient_id, payload_hex)
+                    })
+                    .collect::<Vec<_>>();
+                // Rows without an id or payload are reported but never written.
+                let writable = (0..items.len())
+                    .filter(|index| {
+                        let (transient_id, payload_hex) = &items[*index];
+                        !transient_id.is_empty() && !payload_hex.is_empty()
+                    })
+                    .collect::<Vec<_>>();
+                let written = self
+                    .store
+                    .put_propagation_payloads(
+                        &writable
+                            .iter()
+                            .map(|index| items[*index].clone())
+                            .collect::<Vec<_>>(),
+                        now_i64(),
+                    )
+                    .map_err(std::io::Error::other)?;
+                let mut stored = vec![false; items.len()];
+                for (index, written) in writable.into_iter().zip(written) {
+                    stored[index] = written;
+                }
+                let stored_count = stored.iter().filter(|stored| **stored).count();
+                let results = items
+                    .iter()
+                    .zip(&stored)
+                    .map(|((transient_id, _), stored)| {
+                        json!({ "transient_id": transient_id, "stored": stored })
+                    })
+                    .collect::<Vec<_>>();
+
+                // Counted only once the batch has committed.
+                {
+                    let mut guard = self
+                        .propagation_state
+                        .lock()
+                        .expect("propagation mutex poisoned");
+                    guard.last_ingest_count = stored_count;
+                    guard.total_ingested += stored_count;
+                }
+
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "ingested_count": stored_count,
+                        "items": results,
+                    })),
+                    error: None,
+                })
+            }
             "propagation_fetch" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
@@ -837,11 +4280,9 @@ impl RpcDaemon {
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
 
                 let payload = self
-                    .propagation_payloads
-                    .lock()
-                    .expect("propagation payload mutex poisoned")
-                    .get(&parsed.transient_id)
-                    .cloned()
+                    .store
+                    .get_propagation_payload(&parsed.transient_id)
+                    .map_err(std::io::Error::other)?
                     .ok_or_else(|| {
                         std::io::Error::new(std::io::ErrorKind::NotFound, "transient_id not found")
                     })?;
@@ -855,16 +4296,112 @@ impl RpcDaemon {
                     error: None,
                 })
             }
+            // Pulls many payloads in one round-trip, either by explicit id or by
+            // walking the store in transient_id order from the `after` marker.
+            "propagation_fetch_batch" => {
+                let parsed: PropagationFetchBatchParams = match request.params {
+                    Some(params) => serde_json::from_value(params).map_err(|err| {
+                        std::io::Error::new(std::io::ErrorKind::InvalidInput, err)
+                    })?,
+                    None => PropagationFetchBatchParams::default(),
+                };
+                let mut items = Vec::new();
+                let mut missing = Vec::new();
+                let next = match parsed.transient_ids {
+                    Some(transient_ids) => {
+                        for transient_id in transient_ids {
+                            match self
+                                .store
+                                .get_propagation_payload(&transient_id)
+                                .map_err(std::io::Error::other)?
+                            {
+                                Some(payload) => items.push(json!({
+                                    "transient_id": transient_id,
+                                    "payload_hex": payload,
+                                })),
+                                None => missing.push(transient_id),
+                            }
+                        }
+                        None
+                    }
+                    None => {
+                        let limit = parsed.limit.unwrap_or(100).clamp(1, 1000);
+                        let mut page = self
+                            .store
+                            .list_propagation_payloads(parsed.after.as_deref(), limit + 1)
+                            .map_err(std::io::Error::other)?;
+                        let more = page.len() > limit;
+                        page.truncate(limit);
+                        let next = more
+                            .then(|| page.last().map(|(id, _)| id.clone()))
+                            .flatten();
+                        for (transient_id, payload) in page {
+                            items.push(json!({
+                                "transient_id": transient_id,
+                                "payload_hex": payload,
+                            }));
+                        }
+                        next
+                    }
+                };
+
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "items": items,
+                        "missing": missing,
+                        "next": next,
+                    })),
+                    error: None,
+                })
+            }
+            "request_path" => {
+                // Sync callers get the immediate answer; handle_rpc_async is
+                // the entry point that honours wait_ms.
+                let params = request_path_params(request.params)?;
+                let (known, requested) = self.begin_path_request(&params.destination)?;
+                Ok(self.finish_path_request(
+                    request.id,
+                    params.destination,
+                    known,
+                    requested,
+                    Duration::ZERO,
+                ))
+            }
             "get_outbound_propagation_node" => {
                 let selected = self
                     .outbound_propagation_node
                     .lock()
                     .expect("propagation node mutex poisoned")
                     .clone();
+                // Lets a client warn about an unreachable relay before it
+                // tries to sync through it.
+                let (has_path, last_seen) = match selected.as_deref() {
+                    Some(peer) => {
+                        let has_path = self
+                            .outbound_bridge
+                            .as_ref()
+                            .and_then(|bridge| bridge.has_path(peer));
+                        let announced = self
+                            .store
+                            .latest_announce_timestamp(peer)
+                            .map_err(std::io::Error::other)?;
+                        let seen = self
+                            .peers
+                            .lock()
+                            .expect("peers mutex poisoned")
+                            .get(peer)
+                            .map(|record| record.last_seen);
+                        (has_path, announced.max(seen))
+                    }
+                    None => (None, None),
+                };
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({
                         "peer": selected,
+                        "has_path": has_path,
+                        "last_seen": last_seen,
                         "meta": self.response_meta(),
                     })),
                     error: None,
@@ -910,7 +4447,7 @@ impl RpcDaemon {
                     .clone();
                 let announces = self
                     .store
-                    .list_announces(500, None, None)
+                    .list_announces(500, None, None, None)
                     .map_err(std::io::Error::other)?;
                 let mut by_peer: HashMap<String, PropagationNodeRecord> = HashMap::new();
                 for announce in announces {
@@ -954,6 +4491,83 @@ impl RpcDaemon {
                     error: None,
                 })
             }
+            "request_alternative_propagation_relay" => {
+                let parsed = request
+                    .params
+                    .map(serde_json::from_value::<AlternativePropagationRelayParams>)
+                    .transpose()
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let previous = self
+                    .outbound_propagation_node
+                    .lock()
+                    .expect("propagation node mutex poisoned")
+                    .clone();
+                let mut excluded = parsed
+                    .map(|value| value.exclude)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|value| value.trim().to_string())
+                    .collect::<HashSet<_>>();
+                excluded.extend(previous.clone());
+                let announces = self
+                    .store
+                    .list_announces(500, None, None, None)
+                    .map_err(std::io::Error::other)?;
+                let now = now_i64();
+                // Announces come newest first, so the first one seen per peer
+                // is the one its score is based on.
+                let mut seen = HashSet::new();
+                let mut candidates = Vec::new();
+                for announce in announces {
+                    if !announce.capabilities.iter().any(|cap| cap == "propagation")
+                        || excluded.contains(&announce.peer)
+                        || !seen.insert(announce.peer.clone())
+                    {
+                        continue;
+                    }
+                    let score = propagation_relay_score(&announce, now);
+                    candidates.push((announce.peer, score));
+                }
+                candidates.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                let considered = candidates.len();
+                let Some((peer, score)) = candidates.into_iter().next() else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: Some(json!({
+                            "peer": JsonValue::Null,
+                            "score": JsonValue::Null,
+                            "previous": previous,
+                            "candidates": 0,
+                            "meta": self.response_meta(),
+                        })),
+                        error: None,
+                    });
+                };
+                {
+                    let mut guard = self
+                        .outbound_propagation_node
+                        .lock()
+                        .expect("propagation node mutex poisoned");
+                    *guard = Some(peer.clone());
+                }
+                let event = RpcEvent {
+                    event_type: "propagation_node_selected".into(),
+                    payload: json!({ "peer": peer, "score": score, "previous": previous }),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "peer": peer,
+                        "score": score,
+                        "previous": previous,
+                        "candidates": considered,
+                        "meta": self.response_meta(),
+                    })),
+                    error: None,
+                })
+            }
             "paper_ingest_uri" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
@@ -961,42 +4575,238 @@ impl RpcDaemon {
                 let parsed: PaperIngestUriParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
 
-                if !parsed.uri.starts_with("lxm://") {
+                let uri = parsed.uri.trim();
+                let Some(body) = uri.strip_prefix("lxm://") else {
                     return Err(std::io::Error::new(
                         std::io::ErrorKind::InvalidInput,
                         "paper URI must start with lxm://",
                     ));
-                }
+                };
+                let packed = decode_paper_uri_body(body)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let Some(bridge) = &self.paper_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "PAPER_DECODER_UNAVAILABLE".into(),
+                            message: "paper ingest requires a local delivery identity".into(),
+                        }),
+                    });
+                };
+                let mut record = match bridge.decode_paper_message(&packed) {
+                    Ok(record) => record,
+                    Err(diagnostics) => {
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: None,
+                            error: Some(RpcError {
+                                code: "PAPER_DECODE_FAILED".into(),
+                                message: diagnostics,
+                            }),
+                        });
+                    }
+                };
+                record.direction = "in".into();
+                record.delivery_method = Some("paper".into());
 
                 let transient_id = {
                     let mut hasher = Sha256::new();
-                    hasher.update(parsed.uri.as_bytes());
+                    hasher.update(&packed);
                     encode_hex(hasher.finalize())
                 };
+                let duplicate = !self
+                    .paper_ingest_seen
+                    .lock()
+                    .expect("paper ingest mutex poisoned")
+                    .insert(transient_id.clone());
+                if !duplicate {
+                    self.store_inbound_record(record.clone())?;
+                }
 
-                let duplicate = {
-                    let mut guard = self
-                        .paper_ingest_seen
-                        .lock()
-                        .expect("paper ingest mutex poisoned");
-                    if guard.contains(&transient_id) {
-                        true
-                    } else {
-                        guard.insert(transient_id.clone());
-                        false
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "message_id": record.id,
+                        "source": record.source,
+                        "destination": record.destination,
+                        "title": record.title,
+                        "content": record.content,
+                        "fields": record.fields,
+                        "transient_id": transient_id,
+                        "duplicate": duplicate,
+                        "bytes_len": packed.len(),
+                    })),
+                    error: None,
+                })
+            }
+            "export_message_lxmf" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: MessageIdParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let Some(bridge) = &self.lxmf_codec_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "LXMF_CODEC_UNAVAILABLE".into(),
+                            message: "lxmf export and import require a local delivery identity"
+                                .into(),
+                        }),
+                    });
+                };
+                let Some(record) = self
+                    .store
+                    .get_message(&parsed.message_id)
+                    .map_err(std::io::Error::other)?
+                else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "MESSAGE_NOT_FOUND".into(),
+                            message: format!("no message {}", parsed.message_id),
+                        }),
+                    });
+                };
+                // Messages go back out as the bytes they were sent, received
+                // or imported as. Without those a message can only be rebuilt
+                // if we signed it, stamped with its stored timestamp.
+                let stored = self
+                    .store
+                    .message_packed(&record.id)
+                    .map_err(std::io::Error::other)?;
+                let packed = match stored {
+                    Some(packed) => packed,
+                    None if !record
+                        .source
+                        .eq_ignore_ascii_case(&self.local_delivery_hash()) =>
+                    {
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: None,
+                            error: Some(RpcError {
+                                code: "LXMF_EXPORT_UNSUPPORTED".into(),
+                                message: format!(
+                                    "message {} was not sent from this identity and has no original lxmf bytes",
+                                    record.id
+                                ),
+                            }),
+                        });
                     }
+                    None => match bridge.encode_lxmf_message(&record) {
+                        Ok(packed) => packed,
+                        Err(err) => {
+                            return Ok(RpcResponse {
+                                id: request.id,
+                                result: None,
+                                error: Some(RpcError {
+                                    code: "LXMF_ENCODE_FAILED".into(),
+                                    message: err,
+                                }),
+                            });
+                        }
+                    },
                 };
-
-                let body = parsed.uri.trim_start_matches("lxm://");
-                let destination = first_n_chars(body, 32).unwrap_or_default();
-
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({
-                        "destination": destination,
-                        "transient_id": transient_id,
+                        "message_id": record.id,
+                        "lxmf_hex": encode_hex(&packed),
+                        "lxmf_base64": BASE64_STANDARD.encode(&packed),
+                        "bytes_len": packed.len(),
+                    })),
+                    error: None,
+                })
+            }
+            "import_message_lxmf" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ImportMessageLxmfParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let packed = match (parsed.lxmf_hex.as_deref(), parsed.lxmf_base64.as_deref()) {
+                    (Some(hex), None) => hex::decode(hex.trim()).map_err(|err| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("invalid lxmf_hex: {err}"),
+                        )
+                    })?,
+                    (None, Some(base64)) => {
+                        BASE64_STANDARD.decode(base64.trim()).map_err(|err| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidInput,
+                                format!("invalid lxmf_base64: {err}"),
+                            )
+                        })?
+                    }
+                    _ => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "exactly one of lxmf_hex or lxmf_base64 is required",
+                        ))
+                    }
+                };
+                let Some(bridge) = &self.lxmf_codec_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "LXMF_CODEC_UNAVAILABLE".into(),
+                            message: "lxmf export and import require a local delivery identity"
+                                .into(),
+                        }),
+                    });
+                };
+                let mut record = match bridge.decode_lxmf_message(&packed) {
+                    Ok(record) => record,
+                    Err(diagnostics) => {
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: None,
+                            error: Some(RpcError {
+                                code: "LXMF_DECODE_FAILED".into(),
+                                message: diagnostics,
+                            }),
+                        });
+                    }
+                };
+                let local = self.local_delivery_hash();
+                record.direction = if record.source.eq_ignore_ascii_case(&local) {
+                    "out".into()
+                } else {
+                    "in".into()
+                };
+                if record.content_type.is_none() {
+                    record.content_type = Some(
+                        content_type_from_fields(record.fields.as_ref())
+                            .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string()),
+                    );
+                }
+                // Imports are history, not arrivals: no inbound event, and an
+                // existing copy of the message is left untouched.
+                let duplicate = self
+                    .store
+                    .get_message(&record.id)
+                    .map_err(std::io::Error::other)?
+                    .is_some();
+                if !duplicate {
+                    self.store
+                        .insert_message(&record)
+                        .map_err(std::io::Error::other)?;
+                    self.store
+                        .set_message_packed(&record.id, &packed)
+                        .map_err(std::io::Error::other)?;
+                }
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "message_id": record.id,
+                        "direction": record.direction,
                         "duplicate": duplicate,
-                        "bytes_len": parsed.uri.len(),
+                        "message": record,
                     })),
                     error: None,
                 })
@@ -1007,9 +4817,14 @@ impl RpcDaemon {
                     .lock()
                     .expect("stamp mutex poisoned")
                     .clone();
+                let overrides = self
+                    .stamp_policy_overrides
+                    .lock()
+                    .expect("stamp overrides mutex poisoned")
+                    .clone();
                 Ok(RpcResponse {
                     id: request.id,
-                    result: Some(json!({ "stamp_policy": policy })),
+                    result: Some(json!({ "stamp_policy": policy, "overrides": overrides })),
                     error: None,
                 })
             }
@@ -1030,6 +4845,7 @@ impl RpcDaemon {
                     }
                     guard.clone()
                 };
+                self.persist_setting(SETTING_STAMP_POLICY, &policy)?;
 
                 Ok(RpcResponse {
                     id: request.id,
@@ -1037,6 +4853,73 @@ impl RpcDaemon {
                     error: None,
                 })
             }
+            "set_stamp_policy_for" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: StampPolicyForParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let destination = normalize_hash_hex(&parsed.destination).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "destination must be a 16-byte hex hash",
+                    )
+                })?;
+                let overrides = {
+                    let mut guard = self
+                        .stamp_policy_overrides
+                        .lock()
+                        .expect("stamp overrides mutex poisoned");
+                    if parsed.clear {
+                        guard.remove(&destination);
+                    } else {
+                        // Fields left out keep the override's value, or the
+                        // global one for a new override.
+                        let global = self
+                            .stamp_policy
+                            .lock()
+                            .expect("stamp mutex poisoned")
+                            .clone();
+                        let policy = guard.entry(destination.clone()).or_insert(global);
+                        if let Some(value) = parsed.target_cost {
+                            policy.target_cost = value;
+                        }
+                        if let Some(value) = parsed.flexibility {
+                            policy.flexibility = value;
+                        }
+                    }
+                    guard.clone()
+                };
+                self.persist_setting(SETTING_STAMP_POLICY_OVERRIDES, &overrides)?;
+                let (policy, overridden) = self.effective_stamp_policy(&destination);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "destination": destination,
+                        "stamp_policy": policy,
+                        "overridden": overridden,
+                    })),
+                    error: None,
+                })
+            }
+            "get_stamp_policy_for" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: StampPolicyForParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let destination = parsed.destination.trim().to_ascii_lowercase();
+                let (policy, overridden) = self.effective_stamp_policy(&destination);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "destination": destination,
+                        "stamp_policy": policy,
+                        "overridden": overridden,
+                    })),
+                    error: None,
+                })
+            }
             "ticket_generate" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
@@ -1086,35 +4969,152 @@ impl RpcDaemon {
             }
             "announce_now" => {
                 let timestamp = now_i64();
-                if let Some(bridge) = &self.announce_bridge {
-                    let _ = bridge.announce_now();
+                let outcome = match &self.announce_bridge {
+                    Some(bridge) => bridge.announce_now(),
+                    None => Ok(()),
+                };
+                Ok(self.announce_response(request.id, timestamp, outcome))
+            }
+            "add_announce_aspect" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: AddAnnounceAspectParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let app = parsed.app.trim().to_string();
+                let aspects = parsed.aspects.trim().to_string();
+                if app.is_empty() || aspects.is_empty() || app.contains('.') {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "app and aspects are required and app must not contain '.'",
+                    ));
+                }
+                let name = format!("{app}.{aspects}");
+                if name == "lxmf.delivery" {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "lxmf.delivery is always announced",
+                    ));
+                }
+                let app_data = parsed
+                    .app_data_hex
+                    .as_deref()
+                    .map(hex::decode)
+                    .transpose()
+                    .map_err(|_| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "invalid app_data_hex",
+                        )
+                    })?;
+                let Some(bridge) = &self.announce_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "announce aspects require an attached transport".into(),
+                        }),
+                    });
+                };
+                let destination_hash =
+                    match bridge.add_announce_aspect(&app, &aspects, app_data.clone()) {
+                        Ok(hash) => hash,
+                        Err(err) => {
+                            return Ok(RpcResponse {
+                                id: request.id,
+                                result: None,
+                                error: Some(RpcError {
+                                    code: "ANNOUNCE_FAILED".into(),
+                                    message: err.to_string(),
+                                }),
+                            });
+                        }
+                    };
+                let details = AnnounceDetails {
+                    destination_hash,
+                    app_data_hex: app_data.as_ref().map(hex::encode),
+                    aspects: name,
+                };
+                {
+                    let mut guard = self
+                        .announce_aspects
+                        .lock()
+                        .expect("announce_aspects mutex poisoned");
+                    match guard
+                        .iter_mut()
+                        .find(|entry| entry.aspects == details.aspects)
+                    {
+                        Some(entry) => *entry = details.clone(),
+                        None => guard.push(details.clone()),
+                    }
                 }
                 let event = RpcEvent {
-                    event_type: "announce_sent".into(),
-                    payload: json!({ "timestamp": timestamp }),
+                    event_type: "announce_aspect_added".into(),
+                    payload: json!(details),
                 };
                 self.push_event(event.clone());
                 let _ = self.events.send(event);
                 Ok(RpcResponse {
                     id: request.id,
-                    result: Some(json!({ "announce_id": request.id })),
+                    result: Some(json!({
+                        "destination_hash": details.destination_hash,
+                        "app_data_hex": details.app_data_hex,
+                        "aspects": details.aspects,
+                        "announce_aspects": self.advertised_aspects(),
+                    })),
                     error: None,
                 })
             }
+            "set_display_name" => {
+                let id = request.id;
+                let display_name = match self.apply_display_name(request)? {
+                    Ok(display_name) => display_name,
+                    Err(response) => return Ok(response),
+                };
+                let outcome = match &self.announce_bridge {
+                    Some(bridge) => bridge.announce_now(),
+                    None => Ok(()),
+                };
+                Ok(self.display_name_response(id, display_name, outcome))
+            }
             "announce_received" => {
                 let params = request.params.ok_or_else(|| {
                     std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
                 })?;
                 let parsed: AnnounceReceivedParams = serde_json::from_value(params)
                     .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let carried_identity = match self.verify_announce(&parsed) {
+                    Ok(identity) => identity,
+                    Err(reason) => {
+                        self.emit_event(RpcEvent {
+                            event_type: "announce_rejected".into(),
+                            payload: json!({ "peer": parsed.peer, "reason": reason }),
+                        });
+                        return Ok(RpcResponse {
+                            id: request.id,
+                            result: None,
+                            error: Some(RpcError {
+                                code: "ANNOUNCE_REJECTED".into(),
+                                message: reason,
+                            }),
+                        });
+                    }
+                };
+                if let Some(identity) = carried_identity {
+                    self.store_peer_identity(&parsed.peer, &identity.to_hex_string())?;
+                }
                 let timestamp = parsed.timestamp.unwrap_or_else(now_i64);
                 let peer = parsed.peer.clone();
-                let (parsed_stamp_cost_flexibility, parsed_peering_cost) =
+                let (_, parsed_stamp_cost_flexibility, parsed_peering_cost) =
                     parse_announce_costs_from_app_data_hex(parsed.app_data_hex.as_deref());
                 let stamp_cost_flexibility = parsed
                     .stamp_cost_flexibility
                     .or(parsed_stamp_cost_flexibility);
                 let peering_cost = parsed.peering_cost.or(parsed_peering_cost);
+                let public_key_hex = carried_identity
+                    .as_ref()
+                    .map(|identity| identity.to_hex_string());
                 self.accept_announce_with_metadata(
                     parsed.peer,
                     timestamp,
@@ -1125,15 +5125,16 @@ impl RpcDaemon {
                     parsed.rssi,
                     parsed.snr,
                     parsed.q,
-                    None,
+                    parsed.stamp_cost,
                     Some(stamp_cost_flexibility),
                     Some(peering_cost),
                     None,
+                    parsed.hops,
+                    parsed.interface,
                     None,
                     None,
                     None,
-                    None,
-                    None,
+                    public_key_hex,
                 )?;
                 let record = self
                     .peers
@@ -1148,48 +5149,134 @@ impl RpcDaemon {
                 })
             }
             "clear_messages" => {
-                self.store.clear_messages().map_err(std::io::Error::other)?;
+                self.clear_scopes(&["messages"])?;
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({ "cleared": "messages" })),
                     error: None,
                 })
             }
+            "clear" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ClearParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let mut scopes: Vec<&str> = Vec::with_capacity(parsed.scopes.len());
+                for scope in &parsed.scopes {
+                    // In-flight transfers live in the transport, which offers
+                    // no way to cancel them from here.
+                    if scope.trim() == "resources" {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "scope \"resources\" is not supported",
+                        ));
+                    }
+                    let Some(known) = CLEAR_SCOPES.iter().find(|known| **known == scope.trim())
+                    else {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("unknown scope \"{scope}\""),
+                        ));
+                    };
+                    if !scopes.contains(known) {
+                        scopes.push(known);
+                    }
+                }
+                if scopes.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "scopes must not be empty",
+                    ));
+                }
+                self.clear_scopes(&scopes)?;
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({ "cleared": scopes })),
+                    error: None,
+                })
+            }
+            "list_resources" => {
+                let Some(bridge) = &self.resource_bridge else {
+                    return Ok(RpcResponse {
+                        id: request.id,
+                        result: None,
+                        error: Some(RpcError {
+                            code: "TRANSPORT_UNAVAILABLE".into(),
+                            message: "resource transfers require an attached transport".into(),
+                        }),
+                    });
+                };
+                let counts = bridge.resource_counts().unwrap_or_default();
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "incoming": counts.incoming,
+                        "outgoing": counts.outgoing,
+                        "queued": counts.queued,
+                        "max_concurrent": counts.max_concurrent,
+                    })),
+                    error: None,
+                })
+            }
             "clear_resources" => Ok(RpcResponse {
                 id: request.id,
                 result: Some(json!({ "cleared": "resources" })),
                 error: None,
             }),
             "clear_peers" => {
-                {
-                    let mut guard = self.peers.lock().expect("peers mutex poisoned");
-                    guard.clear();
-                }
-                self.store
-                    .clear_announces()
-                    .map_err(std::io::Error::other)?;
+                self.clear_scopes(&["peers", "announces"])?;
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({ "cleared": "peers" })),
                     error: None,
                 })
             }
-            "clear_all" => {
-                self.store.clear_messages().map_err(std::io::Error::other)?;
-                self.store
-                    .clear_announces()
-                    .map_err(std::io::Error::other)?;
-                {
-                    let mut guard = self.peers.lock().expect("peers mutex poisoned");
-                    guard.clear();
+            "clear_peer_announces" => {
+                let params = request.params.ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+                })?;
+                let parsed: ClearPeerAnnouncesParams = serde_json::from_value(params)
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+                let peer = parsed.peer.trim();
+                if peer.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "peer must not be empty",
+                    ));
                 }
-                {
-                    let mut guard = self
-                        .delivery_traces
+                let removed_announces = self
+                    .store
+                    .clear_announces_for_peer(peer)
+                    .map_err(std::io::Error::other)?;
+                let removed_peer = parsed.remove_peer
+                    && self
+                        .peers
                         .lock()
-                        .expect("delivery traces mutex poisoned");
-                    guard.clear();
-                }
+                        .expect("peers mutex poisoned")
+                        .remove(peer)
+                        .is_some();
+                let event = RpcEvent {
+                    event_type: "peer_announces_cleared".into(),
+                    payload: json!({
+                        "peer": peer,
+                        "removed_announces": removed_announces,
+                        "removed_peer": removed_peer,
+                    }),
+                };
+                self.push_event(event.clone());
+                let _ = self.events.send(event);
+                Ok(RpcResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "removed_announces": removed_announces,
+                        "removed_peer": removed_peer,
+                    })),
+                    error: None,
+                })
+            }
+            "clear_all" => {
+                self.clear_scopes(&["messages", "announces", "telemetry", "peers", "traces"])?;
                 Ok(RpcResponse {
                     id: request.id,
                     result: Some(json!({ "cleared": "all" })),
@@ -1207,18 +5294,44 @@ impl RpcDaemon {
         }
     }
 
+    // Adds a step reported by the outbound bridge to the message's delivery
+    // trace without touching its receipt status.
+    pub fn record_delivery_step(&self, message_id: &str, step: &str) {
+        self.append_delivery_trace(message_id, step.to_string());
+    }
+
     fn append_delivery_trace(&self, message_id: &str, status: String) {
-        const MAX_DELIVERY_TRACE_ENTRIES: usize = 32;
-        const MAX_TRACKED_MESSAGE_TRACES: usize = 2048;
+        let reason_code = delivery_reason_code(&status).map(ToOwned::to_owned);
+        self.append_delivery_trace_with_reason(message_id, status, reason_code);
+    }
 
+    fn append_delivery_trace_with_reason(
+        &self,
+        message_id: &str,
+        status: String,
+        reason_code: Option<String>,
+    ) {
         let timestamp = now_i64();
-        let reason_code = delivery_reason_code(&status).map(ToOwned::to_owned);
+        let record = DeliveryTraceRecord {
+            message_id: message_id.to_string(),
+            status: status.clone(),
+            reason_code: reason_code.clone(),
+            timestamp,
+        };
+        if let Err(err) = self
+            .store
+            .append_delivery_trace(&record, MAX_DELIVERY_TRACE_ENTRIES)
+        {
+            log::warn!("failed to persist delivery trace for {message_id}: {err}");
+        }
+
         let mut guard = self
             .delivery_traces
             .lock()
             .expect("delivery traces mutex poisoned");
         let entry = guard.entry(message_id.to_string()).or_default();
         entry.push(DeliveryTraceEntry {
+            stage: delivery_stage(&status),
             status,
             timestamp,
             reason_code,
@@ -1227,38 +5340,46 @@ impl RpcDaemon {
             let drain_count = entry.len().saturating_sub(MAX_DELIVERY_TRACE_ENTRIES);
             entry.drain(0..drain_count);
         }
+        evict_delivery_traces(&mut guard, message_id);
+    }
 
-        if guard.len() > MAX_TRACKED_MESSAGE_TRACES {
-            let overflow = guard.len() - MAX_TRACKED_MESSAGE_TRACES;
-            let mut evicted_ids = Vec::with_capacity(overflow);
-            for key in guard.keys() {
-                if key != message_id {
-                    evicted_ids.push(key.clone());
-                    if evicted_ids.len() == overflow {
-                        break;
-                    }
-                }
-            }
-            for id in evicted_ids {
-                guard.remove(&id);
-            }
-
-            if guard.len() > MAX_TRACKED_MESSAGE_TRACES {
-                let still_over = guard.len() - MAX_TRACKED_MESSAGE_TRACES;
-                let mut fallback = Vec::with_capacity(still_over);
-                for key in guard.keys().take(still_over).cloned() {
-                    fallback.push(key);
-                }
-                for id in fallback {
-                    guard.remove(&id);
-                }
-            }
+    // The in-memory map is a cache; traces for messages sent before a restart
+    // (or evicted from the cache) are loaded back from the store.
+    fn delivery_trace(&self, message_id: &str) -> Result<Vec<DeliveryTraceEntry>, std::io::Error> {
+        if let Some(traces) = self
+            .delivery_traces
+            .lock()
+            .expect("delivery traces mutex poisoned")
+            .get(message_id)
+        {
+            return Ok(traces.clone());
+        }
+        let traces = self
+            .store
+            .list_delivery_traces(message_id, MAX_DELIVERY_TRACE_ENTRIES)
+            .map_err(std::io::Error::other)?
+            .into_iter()
+            .map(|record| DeliveryTraceEntry {
+                stage: delivery_stage(&record.status),
+                status: record.status,
+                timestamp: record.timestamp,
+                reason_code: record.reason_code,
+            })
+            .collect::<Vec<_>>();
+        if !traces.is_empty() {
+            let mut guard = self
+                .delivery_traces
+                .lock()
+                .expect("delivery traces mutex poisoned");
+            guard.insert(message_id.to_string(), traces.clone());
+            evict_delivery_traces(&mut guard, message_id);
         }
+        Ok(traces)
     }
 
     fn response_meta(&self) -> JsonValue {
         json!({
-            "contract_version": "v2",
+            "contract_version": RPC_CONTRACT_VERSION,
             "profile": JsonValue::Null,
             "rpc_endpoint": JsonValue::Null,
         })
@@ -1274,13 +5395,55 @@ impl RpcDaemon {
         title: String,
         content: String,
         fields: Option<JsonValue>,
+        content_type: Option<String>,
         method: Option<String>,
         stamp_cost: Option<u32>,
         options: OutboundDeliveryOptions,
         include_ticket: Option<bool>,
+        dry_run: bool,
     ) -> Result<RpcResponse, std::io::Error> {
+        validate_known_fields(fields.as_ref())?;
+        // Messages with no source go out from the local delivery destination,
+        // which is also the identity the bridge signs them with.
+        let source = if source.trim().is_empty() && options.source_private_key.is_none() {
+            self.local_delivery_hash()
+        } else {
+            source
+        };
+        let mut options = options;
+        // A destination with its own stamp policy asks the bridge for a stamp
+        // of that cost unless the caller asked for one. The cost only travels
+        // in the delivery options; reticulumd does not generate stamps yet, so
+        // nothing is attached and no outbound stamp is recorded there.
+        if stamp_cost.is_none() && options.stamp_cost.is_none() {
+            let (policy, overridden) = self.effective_stamp_policy(&destination);
+            if overridden && policy.target_cost > 0 {
+                options.stamp_cost = Some(policy.target_cost);
+            }
+        }
+        if options.identity_relay.is_none() {
+            options.identity_relay = self
+                .outbound_propagation_node
+                .lock()
+                .expect("propagation node mutex poisoned")
+                .clone()
+                .filter(|relay| !relay.eq_ignore_ascii_case(destination.trim()));
+        }
         let timestamp = now_i64();
-        self.append_delivery_trace(&id, "queued".to_string());
+        let (content_type, fields) = match normalize_content_type(content_type) {
+            Some(content_type) => {
+                let fields = with_renderer_field(fields, &content_type);
+                (content_type, fields)
+            }
+            None => (DEFAULT_CONTENT_TYPE.to_string(), fields),
+        };
+        // Sending again under an existing id is a retry of that message.
+        let attempts = self
+            .store
+            .get_message(&id)
+            .map_err(std::io::Error::other)?
+            .map_or(0, |existing| existing.attempts)
+            .saturating_add(1);
         let mut record = MessageRecord {
             id: id.clone(),
             source,
@@ -1289,16 +5452,64 @@ impl RpcDaemon {
             content,
             timestamp,
             direction: "out".into(),
-            fields: merge_fields_with_options(fields, method.clone(), stamp_cost, include_ticket),
+            fields: merge_fields_with_options(
+                fields,
+                method.clone(),
+                stamp_cost,
+                include_ticket,
+                options.deliver_by,
+            ),
             receipt_status: None,
+            delivery_method: None,
+            content_type: Some(content_type),
+            attempts,
         };
+        if dry_run {
+            return Ok(self.preview_outbound(
+                request_id,
+                &record,
+                method.as_deref(),
+                stamp_cost.or(options.stamp_cost),
+                &options,
+            ));
+        }
+
+        let chunks = self.attachment_chunk_records(&record, method.as_deref())?;
 
         self.store
             .insert_message(&record)
             .map_err(std::io::Error::other)?;
+        self.append_delivery_trace(&id, "queued".to_string());
+        // Messages to our own delivery hash never touch the network; the
+        // transport cannot open a link to itself.
+        let loopback = record
+            .destination
+            .trim()
+            .eq_ignore_ascii_case(&self.local_delivery_hash());
+        // queue_position counts deliveries the bridge still has in flight;
+        // nothing is stamped on this path, so they are the whole estimate.
+        let (queue_position, est_delay_ms) = if loopback {
+            (0, 0)
+        } else {
+            let in_flight = self
+                .outbound_bridge
+                .as_ref()
+                .map_or(0, |bridge| bridge.pending_deliveries());
+            (in_flight, estimate_send_delay_ms(in_flight))
+        };
+        if est_delay_ms > 0 {
+            self.append_delivery_trace(
+                &id,
+                format!("queued: {queue_position} deliveries in flight, est {est_delay_ms}ms"),
+            );
+        }
         self.append_delivery_trace(&id, "sending".to_string());
-        let deliver_result = if let Some(bridge) = &self.outbound_bridge {
-            bridge.deliver(&record, &options)
+        let deliver_result = if loopback {
+            Ok(())
+        } else if let Some(chunks) = &chunks {
+            self.deliver_attachment_chunks(&record, chunks, &options)
+        } else if let Some(bridge) = &self.outbound_bridge {
+            self.deliver_keeping_packed(bridge.as_ref(), &record, &options)
         } else {
             let _delivered = crate::transport::test_bridge::deliver_outbound(&record);
             Ok(())
@@ -1321,6 +5532,7 @@ impl RpcDaemon {
             };
             self.push_event(event.clone());
             let _ = self.events.send(event);
+            self.emit_delivery_failed(&id, &resolved_status, reason_code);
             return Ok(RpcResponse {
                 id: request_id,
                 result: None,
@@ -1330,8 +5542,29 @@ impl RpcDaemon {
                 }),
             });
         }
-        let sent_status = format!("sent: {}", method.as_deref().unwrap_or("direct"));
+        let sent_status = if loopback {
+            "sent: loopback".to_string()
+        } else {
+            format!("sent: {}", method.as_deref().unwrap_or("direct"))
+        };
         self.append_delivery_trace(&id, sent_status.clone());
+        if let Some(delivery_method) = delivery_method_from_status(&sent_status) {
+            let _ = self.store.update_delivery_method(&id, &delivery_method);
+            record.delivery_method = Some(delivery_method);
+        }
+        if loopback {
+            self.store
+                .update_receipt_status(&id, &sent_status)
+                .map_err(std::io::Error::other)?;
+            record.receipt_status = Some(sent_status.clone());
+            self.store_inbound_record(MessageRecord {
+                id: format!("{id}:loopback"),
+                direction: "in".into(),
+                receipt_status: None,
+                attempts: 0,
+                ..record.clone()
+            })?;
+        }
         let event = RpcEvent {
             event_type: "outbound".into(),
             payload: json!({
@@ -1343,13 +5576,536 @@ impl RpcDaemon {
         self.push_event(event.clone());
         let _ = self.events.send(event);
 
+        let mut result = json!({
+            "message_id": id,
+            "attempts": record.attempts,
+            "queue_position": queue_position,
+            "est_delay_ms": est_delay_ms,
+        });
+        if let Some(chunks) = &chunks {
+            result["attachment_chunks"] = json!(chunks.len());
+        }
         Ok(RpcResponse {
             id: request_id,
-            result: Some(json!({ "message_id": id })),
+            result: Some(result),
+            error: None,
+        })
+    }
+
+    // An opportunistic message is a single packet, so an attachment that
+    // does not fit is split across several messages the receiver puts back
+    // together. None when the record goes out as is.
+    fn attachment_chunk_records(
+        &self,
+        record: &MessageRecord,
+        method: Option<&str>,
+    ) -> Result<Option<Vec<MessageRecord>>, std::io::Error> {
+        let opportunistic =
+            method.is_some_and(|method| method.trim().eq_ignore_ascii_case("opportunistic"));
+        let loopback = record
+            .destination
+            .trim()
+            .eq_ignore_ascii_case(&self.local_delivery_hash());
+        if !opportunistic || loopback || estimate_wire_size(record) <= LXMF_MAX_PAYLOAD {
+            return Ok(None);
+        }
+        let attachment = match attachment_chunks::attachment_entries(record.fields.as_ref())[..] {
+            [] => return Ok(None),
+            [entry] => attachment_chunks::parse_attachment(entry)?,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "opportunistic sends can split only a single attachment",
+                ))
+            }
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(record.id.as_bytes());
+        hasher.update(record.destination.as_bytes());
+        hasher.update(record.timestamp.to_be_bytes());
+        let transfer_id = encode_hex(&hasher.finalize()[..8]);
+        let head_fields = attachment_chunks::without_attachments(record.fields.as_ref());
+        let pieces = attachment_chunks::split_attachment(&transfer_id, &attachment)?;
+        let mut chunks = Vec::with_capacity(pieces.len());
+        for (index, piece) in pieces.into_iter().enumerate() {
+            let mut fields = if index == 0 {
+                head_fields.clone()
+            } else {
+                JsonMap::new()
+            };
+            fields.insert(attachment_chunks::FIELD_ATTACHMENT_CHUNK.into(), piece);
+            let chunk = MessageRecord {
+                id: format!("{}:chunk:{index}", record.id),
+                title: if index == 0 {
+                    record.title.clone()
+                } else {
+                    String::new()
+                },
+                content: if index == 0 {
+                    record.content.clone()
+                } else {
+                    String::new()
+                },
+                fields: Some(JsonValue::Object(fields)),
+                ..record.clone()
+            };
+            if estimate_wire_size(&chunk) > LXMF_MAX_PAYLOAD {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "title, content and fields leave no room for an attachment chunk",
+                ));
+            }
+            chunks.push(chunk);
+        }
+        Ok(Some(chunks))
+    }
+
+    // The bytes a message went out as are what export_message_lxmf returns,
+    // since rebuilding them would not reproduce the wire timestamp.
+    fn deliver_keeping_packed(
+        &self,
+        bridge: &dyn OutboundBridge,
+        record: &MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        if let Some(packed) = bridge.deliver_packed(record, options)? {
+            if let Err(err) = self.store.set_message_packed(&record.id, &packed) {
+                log::warn!("failed to keep packed bytes for {}: {err}", record.id);
+            }
+            // Only a stamp that actually went out counts as outbound work.
+            if wire_stamp(&packed).is_some() {
+                let stamp_cost = record
+                    .fields
+                    .as_ref()
+                    .and_then(|fields| fields.get("_lxmf"))
+                    .and_then(|lxmf| lxmf.get("stamp_cost"))
+                    .and_then(JsonValue::as_u64)
+                    .and_then(|cost| u32::try_from(cost).ok())
+                    .or(options.stamp_cost);
+                let recorded = self.store.record_message_stamp(&MessageStampRecord {
+                    message_id: record.id.clone(),
+                    direction: "out".into(),
+                    stamp_cost,
+                    stamp_value: None,
+                    under_stamped: false,
+                    recorded_at: now_i64(),
+                });
+                if let Err(err) = recorded {
+                    log::warn!("failed to record the stamp of {}: {err}", record.id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Keeps the LXMF bytes an inbound message arrived as, for export.
+    pub fn store_inbound_packed(
+        &self,
+        message_id: &str,
+        packed: &[u8],
+    ) -> Result<(), std::io::Error> {
+        self.store
+            .set_message_packed(message_id, packed)
+            .map(|_| ())
+            .map_err(std::io::Error::other)
+    }
+
+    // Hands the pieces to the bridge as one sequence. Their receipts are
+    // gathered under the message itself; see chunk_receipt.
+    fn deliver_attachment_chunks(
+        &self,
+        record: &MessageRecord,
+        chunks: &[MessageRecord],
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        let total = chunks.len();
+        let Some(bridge) = &self.outbound_bridge else {
+            for (index, chunk) in chunks.iter().enumerate() {
+                let _delivered = crate::transport::test_bridge::deliver_outbound(chunk);
+                self.emit_outbound_chunk_progress(
+                    &record.id,
+                    &record.destination,
+                    index + 1,
+                    total,
+                );
+            }
+            return Ok(());
+        };
+        self.outbound_chunk_transfers
+            .lock()
+            .expect("outbound_chunk_transfers mutex poisoned")
+            .insert(
+                record.id.clone(),
+                OutboundChunkTransfer {
+                    peer: record.destination.clone(),
+                    total,
+                    pending: chunks.iter().map(|chunk| chunk.id.clone()).collect(),
+                },
+            );
+        bridge.deliver_sequence(chunks, options).map_err(|err| {
+            self.outbound_chunk_transfers
+                .lock()
+                .expect("outbound_chunk_transfers mutex poisoned")
+                .remove(&record.id);
+            err
+        })
+    }
+
+    fn emit_outbound_chunk_progress(
+        &self,
+        message_id: &str,
+        peer: &str,
+        done: usize,
+        total: usize,
+    ) {
+        self.emit_event(RpcEvent {
+            event_type: "attachment_progress".into(),
+            payload: json!({
+                "message_id": message_id,
+                "peer": peer,
+                "direction": "out",
+                "chunks_done": done,
+                "chunks_total": total,
+                "status": if done == total { "complete" } else { "sending" },
+            }),
+        });
+    }
+
+    // Folds a receipt for one piece of a chunked attachment into the message
+    // it belongs to. None when message_id is not such a piece. Otherwise the
+    // receipt the message itself gets, if any: delivered once every piece
+    // is, failed as soon as one piece fails.
+    fn chunk_receipt(&self, message_id: &str, status: &str) -> Option<Option<String>> {
+        let (parent, _) = message_id.rsplit_once(":chunk:")?;
+        let mut transfers = self
+            .outbound_chunk_transfers
+            .lock()
+            .expect("outbound_chunk_transfers mutex poisoned");
+        let transfer = transfers.get_mut(parent)?;
+        if !transfer.pending.contains(message_id) {
+            return None;
+        }
+        let failed = status.starts_with("failed");
+        if status == "delivered" {
+            transfer.pending.remove(message_id);
+        } else if !failed {
+            return Some(None);
+        }
+        let (peer, total, pending) = (
+            transfer.peer.clone(),
+            transfer.total,
+            transfer.pending.len(),
+        );
+        if failed || pending == 0 {
+            transfers.remove(parent);
+        }
+        drop(transfers);
+        if failed {
+            return Some(Some(parent.to_string()));
+        }
+        self.emit_outbound_chunk_progress(parent, &peer, total - pending, total);
+        Some((pending == 0).then(|| parent.to_string()))
+    }
+
+    // Answers a dry-run send: everything store_outbound checks up to the
+    // point of storing, plus what the bridge can tell without sending.
+    fn preview_outbound(
+        &self,
+        request_id: u64,
+        record: &MessageRecord,
+        method: Option<&str>,
+        stamp_cost: Option<u32>,
+        options: &OutboundDeliveryOptions,
+    ) -> RpcResponse {
+        let loopback = record
+            .destination
+            .trim()
+            .eq_ignore_ascii_case(&self.local_delivery_hash());
+        let mut reasons = Vec::new();
+        let mut estimated_size = None;
+        if let Some(bridge) = self.outbound_bridge.as_ref().filter(|_| !loopback) {
+            match bridge.wire_size(record, options) {
+                Ok(size) => estimated_size = size,
+                Err(err) => reasons.push(format!("encode_failed: {err}")),
+            }
+            if bridge.has_path(&record.destination) == Some(false) {
+                reasons.push("no_path".to_string());
+            }
+        }
+        let method = if loopback {
+            "loopback"
+        } else {
+            method.unwrap_or("direct")
+        };
+        RpcResponse {
+            id: request_id,
+            result: Some(json!({
+                "message_id": record.id,
+                "dry_run": true,
+                "would_send": reasons.is_empty(),
+                "method": method,
+                "estimated_size": estimated_size.unwrap_or_else(|| estimate_wire_size(record)),
+                "stamp_required": stamp_cost.is_some_and(|cost| cost > 0),
+                "stamp_cost": stamp_cost,
+                "reasons": reasons,
+            })),
             error: None,
+        }
+    }
+
+    // With a live transport only 16-byte hex hashes are routable, so reject
+    // anything else before it lands in the store as an undeliverable record.
+    fn validate_outbound_addresses(
+        &self,
+        source: String,
+        destination: String,
+        allow_logical_destination: bool,
+    ) -> Result<(String, String), std::io::Error> {
+        let required = *self
+            .require_hash_addresses
+            .lock()
+            .expect("require_hash_addresses mutex poisoned");
+        if !required || allow_logical_destination {
+            return Ok((source, destination));
+        }
+        let destination = normalize_hash_hex(&destination).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid destination '{destination}' (expected 16-byte hex hash)"),
+            )
+        })?;
+        if source.trim().is_empty() {
+            return Ok((source, destination));
+        }
+        let source = normalize_hash_hex(&source).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid source '{source}' (expected 16-byte hex hash)"),
+            )
+        })?;
+        Ok((source, destination))
+    }
+
+    fn is_local_inbound_destination(&self, destination: &str) -> bool {
+        if *self
+            .accept_unaddressed_inbound
+            .lock()
+            .expect("accept_unaddressed_inbound mutex poisoned")
+        {
+            return true;
+        }
+        let Some(destination) = normalize_hash_hex(destination) else {
+            return false;
+        };
+        self.advertised_aspects()
+            .iter()
+            .any(|details| details.destination_hash.eq_ignore_ascii_case(&destination))
+    }
+
+    // The delivery destination first, then any aspects added through
+    // add_announce_aspect in registration order.
+    fn advertised_aspects(&self) -> Vec<AnnounceDetails> {
+        let primary = self
+            .announce_bridge
+            .as_ref()
+            .and_then(|bridge| bridge.announce_details())
+            .unwrap_or_else(|| AnnounceDetails {
+                destination_hash: self.local_delivery_hash(),
+                app_data_hex: None,
+                aspects: "lxmf.delivery".into(),
+            });
+        let mut aspects = vec![primary];
+        aspects.extend(
+            self.announce_aspects
+                .lock()
+                .expect("announce_aspects mutex poisoned")
+                .iter()
+                .cloned(),
+        );
+        aspects
+    }
+
+    fn settings_snapshot(&self) -> JsonValue {
+        let thresholds = *self
+            .presence_thresholds
+            .lock()
+            .expect("presence mutex poisoned");
+        json!({
+            SETTING_DELIVERY_POLICY: self
+                .delivery_policy
+                .lock()
+                .expect("policy mutex poisoned")
+                .clone(),
+            SETTING_STAMP_POLICY: self.stamp_policy.lock().expect("stamp mutex poisoned").clone(),
+            SETTING_STAMP_POLICY_OVERRIDES: self
+                .stamp_policy_overrides
+                .lock()
+                .expect("stamp overrides mutex poisoned")
+                .clone(),
+            SETTING_INBOUND_OVERLOAD_POLICY: self.inbound_overload_policy(),
+            SETTING_DISPLAY_NAME: self
+                .store
+                .get_setting(SETTING_DISPLAY_NAME)
+                .ok()
+                .flatten(),
+            SETTING_ANNOUNCE_INTERVAL_SECS: *self.announce_interval.borrow(),
+            SETTING_MAX_PEERS: *self.max_peers.lock().expect("max_peers mutex poisoned"),
+            SETTING_PRESENCE_ONLINE_SECS: thresholds.online_secs,
+            SETTING_PRESENCE_STALE_SECS: thresholds.stale_secs,
         })
     }
 
+    // Checks an RPC-injected announce against the identity it claims. A peer
+    // with a known identity must sign every announce; a new peer may carry
+    // its public key, which must hash to the peer's lxmf delivery address.
+    // Returns the carried identity when it should be remembered.
+    fn verify_announce(&self, parsed: &AnnounceReceivedParams) -> Result<Option<Identity>, String> {
+        let peer = normalize_hash_hex(&parsed.peer);
+        let known = match &peer {
+            Some(peer) => self
+                .store
+                .get_known_identity(peer)
+                .map_err(|err| err.to_string())?
+                .and_then(|record| parse_identity_hex(&record.identity_hex)),
+            None => None,
+        };
+        let carried = parsed
+            .identity_hex
+            .as_deref()
+            .map(|value| {
+                parse_identity_hex(value)
+                    .ok_or_else(|| "identity_hex is not a public key".to_string())
+            })
+            .transpose()?;
+        let (identity, remember) = match (known, carried) {
+            (Some(known), Some(carried)) if known.to_hex_string() != carried.to_hex_string() => {
+                return Err("identity does not match the known identity for this peer".into());
+            }
+            (Some(known), _) => (known, false),
+            (None, Some(carried)) => {
+                let delivery = lxmf_delivery_hash_from_identity_hash(&carried.address_hash);
+                if peer.as_deref() != Some(delivery.to_hex_string().as_str()) {
+                    return Err("peer is not the delivery hash of identity_hex".into());
+                }
+                (carried, true)
+            }
+            (None, None) => {
+                if parsed.signature_hex.is_some() {
+                    return Err("signed announce needs identity_hex for an unknown peer".into());
+                }
+                if *self
+                    .require_signed_announces
+                    .lock()
+                    .expect("require_signed_announces mutex poisoned")
+                {
+                    return Err("announce must be signed".into());
+                }
+                return Ok(None);
+            }
+        };
+        let signature = parsed
+            .signature_hex
+            .as_deref()
+            .ok_or_else(|| "announce for a peer with a known identity must be signed".to_string())
+            .and_then(|value| {
+                hex::decode(value.trim()).map_err(|_| "signature_hex is not hex".to_string())
+            })?;
+        let decode = |field: &str, value: Option<&str>| {
+            value
+                .map(|value| hex::decode(value.trim()).map_err(|_| format!("{field} is not hex")))
+                .transpose()
+        };
+        let app_data = decode("app_data_hex", parsed.app_data_hex.as_deref())?.unwrap_or_default();
+        let name_hash =
+            decode("name_hash_hex", parsed.name_hash_hex.as_deref())?.unwrap_or_else(|| {
+                DestinationName::new("lxmf", "delivery")
+                    .as_name_hash_slice()
+                    .to_vec()
+            });
+        let random_hash = decode("random_hash_hex", parsed.random_hash_hex.as_deref())?
+            .ok_or_else(|| "signed announce needs random_hash_hex".to_string())?;
+        let ratchet = decode("ratchet_hex", parsed.ratchet_hex.as_deref())?;
+        let signed = announce_signing_bytes(
+            &parsed.peer,
+            &identity,
+            &name_hash,
+            &random_hash,
+            ratchet.as_deref(),
+            &app_data,
+        )
+        .ok_or_else(|| {
+            "peer, name_hash_hex, random_hash_hex or ratchet_hex has the wrong length".to_string()
+        })?;
+        if !lxmf_verify(&identity, &signed, &signature) {
+            return Err("announce signature does not verify".into());
+        }
+        Ok(remember.then_some(identity))
+    }
+
+    fn group_seal_key(&self) -> Result<[u8; 16], std::io::Error> {
+        self.local_identity
+            .lock()
+            .expect("local_identity mutex poisoned")
+            .as_ref()
+            .map(group_seal_key)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "group keys require a local identity",
+                )
+            })
+    }
+
+    fn save_group_key(
+        &self,
+        name: &str,
+        app: &str,
+        aspect: &str,
+        key: &[u8; 16],
+    ) -> Result<GroupKeyRecord, std::io::Error> {
+        if name.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "group name must not be empty",
+            ));
+        }
+        validate_plain_name(app, aspect)?;
+        let sealed_key = group_encrypt(&self.group_seal_key()?, key)
+            .map_err(|err| std::io::Error::other(format!("{err:?}")))?;
+        let record = GroupKeyRecord {
+            name: name.to_string(),
+            app: app.to_string(),
+            aspect: aspect.to_string(),
+            destination: plain_destination_hash(app, aspect),
+            sealed_key,
+            created_at: now_i64(),
+        };
+        self.store
+            .put_group_key(&record)
+            .map_err(std::io::Error::other)?;
+        Ok(record)
+    }
+
+    fn open_group(&self, name: &str) -> Result<Option<(GroupKeyRecord, [u8; 16])>, std::io::Error> {
+        let Some(record) = self
+            .store
+            .get_group_key(name)
+            .map_err(std::io::Error::other)?
+        else {
+            return Ok(None);
+        };
+        let key = group_decrypt(&self.group_seal_key()?, &record.sealed_key)
+            .ok()
+            .and_then(|key| <[u8; 16]>::try_from(key).ok())
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("group key for {name} cannot be unsealed with this identity"),
+                )
+            })?;
+        Ok(Some((record, key)))
+    }
+
     fn local_delivery_hash(&self) -> String {
         self.delivery_destination_hash
             .lock()
@@ -1362,39 +6118,456 @@ impl RpcDaemon {
         vec![
             "status",
             "daemon_status_ex",
+            "get_identity_info",
             "list_messages",
             "list_announces",
             "list_peers",
             "send_message",
             "send_message_v2",
+            "send_multicast",
+            "edit_message",
+            "get_message_versions",
+            "get_message",
+            "stamp_stats",
+            "delete_message",
+            "list_deleted_since",
+            "purge_deleted",
             "announce_now",
+            "add_announce_aspect",
+            "set_display_name",
+            "request_path",
             "list_interfaces",
+            "interface_stats",
+            "interface_stats_history",
             "set_interfaces",
+            "validate_config",
+            "truncate_hash",
+            "validate_hash",
+            "lxmf_address_hash",
             "reload_config",
+            "restart_transport",
             "peer_sync",
             "peer_unpeer",
+            "clear_peer_announces",
             "set_delivery_policy",
             "get_delivery_policy",
+            "get_settings",
+            "get_setting",
+            "set_setting",
+            "reset_settings",
             "propagation_status",
+            "propagation_sync_history",
+            "propagation_sync_abort",
             "propagation_enable",
             "propagation_ingest",
+            "propagation_ingest_bulk",
             "propagation_fetch",
+            "propagation_fetch_batch",
             "get_outbound_propagation_node",
             "set_outbound_propagation_node",
             "list_propagation_nodes",
+            "request_alternative_propagation_relay",
             "paper_ingest_uri",
+            "export_message_lxmf",
+            "import_message_lxmf",
             "stamp_policy_get",
             "stamp_policy_set",
+            "set_stamp_policy_for",
+            "get_stamp_policy_for",
             "ticket_generate",
             "message_delivery_trace",
+            "get_telemetry_history",
+            "send_telemetry_request",
+            "list_pending_telemetry_requests",
+            "derive_delivery_hash",
+            "store_peer_identity",
+            "recall_identity",
+            "list_known_identities",
+            "bulk_restore_peer_identities",
+            "export_known_identities",
+            "clear_known_identities",
+            "set_interface_filter",
+            "get_interface_filter",
+            "transport_metrics",
+            "get_bandwidth_limit",
+            "set_bandwidth_limit",
+            "set_destination_interface",
+            "clear_destination_interface",
+            "list_active_links",
+            "establish_link",
+            "close_link",
+            "send_plain",
+            "create_group",
+            "import_group_key",
+            "export_group_key",
+            "list_groups",
+            "backup_store",
+            "restore_store",
+            "resource_send",
+            "list_resources",
+            "store_content",
+            "get_content",
+            "fetch_content",
         ]
     }
 
     pub fn handle_framed_request(&self, bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
-        let request: RpcRequest = codec::decode_frame(bytes)
+        let (request, format): (RpcRequest, _) = codec::decode_frame_with_format(bytes)
             .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
         let response = self.handle_rpc(request)?;
-        codec::encode_frame(&response).map_err(std::io::Error::other)
+        codec::encode_frame_as(&response, format).map_err(std::io::Error::other)
+    }
+
+    pub async fn handle_framed_request_async(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Vec<u8>, std::io::Error> {
+        let (request, format): (RpcRequest, _) = codec::decode_frame_with_format(bytes)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        let response = self.handle_rpc_async(request).await?;
+        codec::encode_frame_as(&response, format).map_err(std::io::Error::other)
+    }
+
+    // Same as handle_rpc, except that methods which wait (request_path with
+    // wait_ms) yield to the runtime instead of blocking the thread.
+    pub async fn handle_rpc_async(
+        &self,
+        request: RpcRequest,
+    ) -> Result<RpcResponse, std::io::Error> {
+        match request.method.as_str() {
+            "request_path" => self.request_path_async(request).await,
+            "resource_send" => self.resource_send_async(request).await,
+            "establish_link" => self.establish_link_async(request).await,
+            "announce_now" => {
+                let timestamp = now_i64();
+                let outcome = self.announce_confirmed().await;
+                Ok(self.announce_response(request.id, timestamp, outcome))
+            }
+            "set_display_name" => {
+                let id = request.id;
+                let display_name = match self.apply_display_name(request)? {
+                    Ok(display_name) => display_name,
+                    Err(response) => return Ok(response),
+                };
+                let outcome = self.announce_confirmed().await;
+                Ok(self.display_name_response(id, display_name, outcome))
+            }
+            _ => self.handle_rpc(request),
+        }
+    }
+
+    async fn announce_confirmed(&self) -> Result<(), std::io::Error> {
+        match &self.announce_bridge {
+            Some(bridge) => bridge.announce_confirmed().await,
+            None => Ok(()),
+        }
+    }
+
+    fn announce_response(
+        &self,
+        id: u64,
+        timestamp: i64,
+        outcome: Result<(), std::io::Error>,
+    ) -> RpcResponse {
+        if let Err(err) = outcome {
+            return RpcResponse {
+                id,
+                result: None,
+                error: Some(RpcError {
+                    code: "ANNOUNCE_FAILED".into(),
+                    message: err.to_string(),
+                }),
+            };
+        }
+        let announced = self.advertised_aspects();
+        let details = announced[0].clone();
+        self.emit_event(RpcEvent {
+            event_type: "announce_sent".into(),
+            payload: json!({
+                "timestamp": timestamp,
+                "announce_id": id,
+                "destination_hash": details.destination_hash,
+                "app_data_hex": details.app_data_hex,
+                "aspects": details.aspects,
+                "announced_aspects": announced,
+            }),
+        });
+        RpcResponse {
+            id,
+            result: Some(json!({
+                "announce_id": id,
+                "destination_hash": details.destination_hash,
+                "app_data_hex": details.app_data_hex,
+                "aspects": details.aspects,
+                "announced_aspects": announced,
+            })),
+            error: None,
+        }
+    }
+
+    // Validates, applies and persists a new display name. The inner error is
+    // the response to return when no transport is attached.
+    fn apply_display_name(
+        &self,
+        request: RpcRequest,
+    ) -> Result<Result<String, RpcResponse>, std::io::Error> {
+        let params = request.params.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "missing params")
+        })?;
+        let parsed: SetDisplayNameParams = serde_json::from_value(params)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+        let Some(bridge) = &self.announce_bridge else {
+            return Ok(Err(RpcResponse {
+                id: request.id,
+                result: None,
+                error: Some(RpcError {
+                    code: "TRANSPORT_UNAVAILABLE".into(),
+                    message: "display names require an attached transport".into(),
+                }),
+            }));
+        };
+        let display_name = bridge.set_display_name(&parsed.display_name)?;
+        self.persist_setting(SETTING_DISPLAY_NAME, &display_name)?;
+        Ok(Ok(display_name))
+    }
+
+    // The new name is already in effect; a failed announce only delays when
+    // peers hear about it.
+    fn display_name_response(
+        &self,
+        id: u64,
+        display_name: String,
+        outcome: Result<(), std::io::Error>,
+    ) -> RpcResponse {
+        let announce_error = outcome.err().map(|err| err.to_string());
+        let app_data_hex = self
+            .announce_bridge
+            .as_ref()
+            .and_then(|bridge| bridge.announce_details())
+            .and_then(|details| details.app_data_hex);
+        self.emit_event(RpcEvent {
+            event_type: "display_name_changed".into(),
+            payload: json!({
+                "display_name": display_name,
+                "app_data_hex": app_data_hex,
+                "announced": announce_error.is_none(),
+            }),
+        });
+        RpcResponse {
+            id,
+            result: Some(json!({
+                "display_name": display_name,
+                "app_data_hex": app_data_hex,
+                "announced": announce_error.is_none(),
+                "announce_error": announce_error,
+            })),
+            error: None,
+        }
+    }
+
+    // Queues the transfer, then waits for the bridge to report that it
+    // started or failed so the caller gets the resource hash back.
+    async fn resource_send_async(
+        &self,
+        request: RpcRequest,
+    ) -> Result<RpcResponse, std::io::Error> {
+        let mut events = self.events.subscribe();
+        let mut response = self.handle_rpc(request)?;
+        let Some(result) = response.result.as_mut() else {
+            return Ok(response);
+        };
+        let transfer_id = result["transfer_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let wait = Duration::from_millis(result["path_timeout_ms"].as_u64().unwrap_or(0))
+            + Duration::from_secs(RESOURCE_LINK_WAIT_SECS);
+        let outcome = tokio::time::timeout(wait, async {
+            loop {
+                match events.recv().await {
+                    Ok(event)
+                        if matches!(
+                            event.event_type.as_str(),
+                            "resource_started" | "resource_failed"
+                        ) && event.payload["transfer_id"] == transfer_id.as_str() =>
+                    {
+                        return Some(event);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .await;
+        let Ok(Some(event)) = outcome else {
+            return Ok(response);
+        };
+        if event.event_type == "resource_failed" {
+            let code = if event.payload["reason_code"] == "no_path" {
+                "NO_PATH"
+            } else {
+                "RESOURCE_SEND_FAILED"
+            };
+            return Ok(RpcResponse {
+                id: response.id,
+                result: None,
+                error: Some(RpcError {
+                    code: code.into(),
+                    message: event.payload["error"]
+                        .as_str()
+                        .unwrap_or("resource transfer failed")
+                        .to_string(),
+                }),
+            });
+        }
+        result["resource_hash"] = event.payload["resource_hash"].clone();
+        result["link_id"] = event.payload["link_id"].clone();
+        result["status"] = "started".into();
+        Ok(response)
+    }
+
+    // Starts the link, then waits up to timeout_seconds for the bridge to
+    // report that it came up or failed.
+    async fn establish_link_async(
+        &self,
+        request: RpcRequest,
+    ) -> Result<RpcResponse, std::io::Error> {
+        let mut events = self.events.subscribe();
+        let started = std::time::Instant::now();
+        let mut response = self.handle_rpc(request)?;
+        let Some(result) = response.result.as_mut() else {
+            return Ok(response);
+        };
+        // Only a link this call started reports its outcome as an event.
+        if result["already_existed"] != false {
+            return Ok(response);
+        }
+        let destination = result["destination"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let wait = Duration::from_millis(result["timeout_ms"].as_u64().unwrap_or(20_000));
+        let outcome = tokio::time::timeout(wait, async {
+            loop {
+                match events.recv().await {
+                    Ok(event)
+                        if matches!(
+                            event.event_type.as_str(),
+                            "link_established" | "link_failed"
+                        ) && event.payload["destination"] == destination.as_str() =>
+                    {
+                        return Some(event);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .await;
+        result["waited_ms"] = json!(started.elapsed().as_millis() as u64);
+        let Ok(Some(event)) = outcome else {
+            return Ok(response);
+        };
+        if event.event_type == "link_failed" {
+            let code = if event.payload["reason_code"] == "no_path" {
+                "NO_PATH"
+            } else {
+                "LINK_FAILED"
+            };
+            return Ok(RpcResponse {
+                id: response.id,
+                result: None,
+                error: Some(RpcError {
+                    code: code.into(),
+                    message: event.payload["error"]
+                        .as_str()
+                        .unwrap_or("link could not be established")
+                        .to_string(),
+                }),
+            });
+        }
+        result["status"] = "active".into();
+        for key in ["link_id", "rtt_ms", "rtt_avg_ms"] {
+            result[key] = event.payload[key].clone();
+        }
+        Ok(response)
+    }
+
+    async fn request_path_async(&self, request: RpcRequest) -> Result<RpcResponse, std::io::Error> {
+        let params = request_path_params(request.params)?;
+        let wait = Duration::from_millis(params.wait_ms.unwrap_or(0).min(MAX_PATH_WAIT_MS));
+        let (mut known, requested) = self.begin_path_request(&params.destination)?;
+        let started = std::time::Instant::now();
+        if requested {
+            while known != Some(true) && started.elapsed() < wait {
+                let left = wait.saturating_sub(started.elapsed());
+                tokio::time::sleep(left.min(Duration::from_millis(PATH_POLL_INTERVAL_MS))).await;
+                known = self
+                    .outbound_bridge
+                    .as_ref()
+                    .and_then(|bridge| bridge.has_path(&params.destination));
+            }
+        }
+        Ok(self.finish_path_request(
+            request.id,
+            params.destination,
+            known,
+            requested,
+            started.elapsed(),
+        ))
+    }
+
+    // Asks the bridge for a path unless one is already known. Returns what the
+    // bridge knows about the path and whether a request went out.
+    fn begin_path_request(
+        &self,
+        destination: &str,
+    ) -> Result<(Option<bool>, bool), std::io::Error> {
+        let bridge = self.outbound_bridge.as_ref();
+        let known = bridge.and_then(|bridge| bridge.has_path(destination));
+        if known == Some(true) {
+            return Ok((known, false));
+        }
+        let requested = match bridge.map(|bridge| bridge.request_path(destination)) {
+            Some(Ok(())) => true,
+            Some(Err(err)) if err.kind() != std::io::ErrorKind::Unsupported => return Err(err),
+            _ => false,
+        };
+        if requested {
+            let now = now_i64();
+            let mut pending = self
+                .pending_path_requests
+                .lock()
+                .expect("pending_path_requests mutex poisoned");
+            pending.retain(|_, requested_at| now - *requested_at < PATH_REQUEST_TTL_SECS);
+            pending.entry(destination.to_string()).or_insert(now);
+        }
+        Ok((known, requested))
+    }
+
+    fn finish_path_request(
+        &self,
+        id: u64,
+        destination: String,
+        known: Option<bool>,
+        requested: bool,
+        waited: Duration,
+    ) -> RpcResponse {
+        let resolved = requested && known == Some(true);
+        if resolved {
+            self.resolve_path_request(&destination);
+        }
+        RpcResponse {
+            id,
+            result: Some(json!({
+                "destination": destination,
+                "has_path": known,
+                "requested": requested,
+                "resolved": resolved,
+                "waited_ms": waited.as_millis() as u64,
+            })),
+            error: None,
+        }
     }
 
     pub fn subscribe_events(&self) -> broadcast::Receiver<RpcEvent> {
@@ -1414,6 +6587,356 @@ impl RpcDaemon {
         guard.push_back(event);
     }
 
+    // Keeps the bytes of a completed inbound resource that nothing else
+    // claimed, so clients can read them back with get_content. Any peer with a
+    // link can send these, so they are held within received_resource_limits.
+    // Returns the content hash.
+    pub fn store_received_resource(&self, data: &[u8]) -> Result<String, std::io::Error> {
+        let limits = self.received_resource_limits();
+        if limits.max_bytes > 0 && data.len() as u64 > limits.max_bytes {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "received resource of {} bytes exceeds the {} byte limit",
+                    data.len(),
+                    limits.max_bytes
+                ),
+            ));
+        }
+        let hash = self
+            .store
+            .put_received_content(data, now_i64())
+            .map_err(std::io::Error::other)?;
+        let bound = |value: u64| (value > 0).then_some(value);
+        self.store
+            .prune_received_contents(
+                bound(limits.max_age_secs)
+                    .map(|secs| now_i64().saturating_sub(i64::try_from(secs).unwrap_or(i64::MAX))),
+                bound(limits.max_items),
+                bound(limits.max_bytes),
+            )
+            .map_err(std::io::Error::other)?;
+        Ok(hash)
+    }
+
+    // Handles a completed inbound resource that belongs to the content
+    // reference protocol: requests are answered from the local content store
+    // and responses are stored once their digest checks out. Returns false for
+    // ordinary resources so the caller can treat them as usual.
+    pub fn accept_content_resource(
+        &self,
+        data: &[u8],
+        metadata: Option<&[u8]>,
+    ) -> Result<bool, std::io::Error> {
+        let Some(transfer) =
+            metadata.and_then(|metadata| serde_json::from_slice::<ContentTransfer>(metadata).ok())
+        else {
+            return Ok(false);
+        };
+        match transfer {
+            ContentTransfer::Request { hash, reply_to } => {
+                let (Some(hash), Some(reply_to)) =
+                    (normalize_content_hash(&hash), normalize_hash_hex(&reply_to))
+                else {
+                    return Ok(true);
+                };
+                // reply_to is not authenticated, so content only goes to a
+                // peer we offered it to, and only a few times a minute.
+                if !self
+                    .store
+                    .content_offered_to(&hash, &reply_to)
+                    .map_err(std::io::Error::other)?
+                {
+                    log::warn!("content request for {hash} from {reply_to} refused: not offered");
+                    return Ok(true);
+                }
+                if !self.allow_content_request(&reply_to) {
+                    log::warn!("content request for {hash} from {reply_to} refused: rate limited");
+                    return Ok(true);
+                }
+                let Some(content) = self
+                    .store
+                    .get_content(&hash)
+                    .map_err(std::io::Error::other)?
+                else {
+                    return Ok(true);
+                };
+                let Some(bridge) = &self.resource_bridge else {
+                    return Ok(true);
+                };
+                let metadata =
+                    serde_json::to_vec(&ContentTransfer::Response { hash: hash.clone() })
+                        .map_err(std::io::Error::other)?;
+                let transfer_id = {
+                    let mut hasher = Sha256::new();
+                    hasher.update(reply_to.as_bytes());
+                    hasher.update(hash.as_bytes());
+                    hasher.update(now_i64().to_be_bytes());
+                    encode_hex(&hasher.finalize()[..16])
+                };
+                let size = content.len();
+                bridge.send_resource(
+                    &transfer_id,
+                    &reply_to,
+                    content,
+                    Some(metadata),
+                    Duration::from_secs(12),
+                )?;
+                self.emit_event(RpcEvent {
+                    event_type: "content_served".into(),
+                    payload: json!({
+                        "hash": hash,
+                        "peer": reply_to,
+                        "size": size,
+                        "transfer_id": transfer_id,
+                    }),
+                });
+            }
+            ContentTransfer::Response { hash } => {
+                let Some(hash) = normalize_content_hash(&hash) else {
+                    return Ok(true);
+                };
+                // Only content we asked for is kept, and only if it matches
+                // the digest it was requested by.
+                {
+                    let mut fetches = self
+                        .content_fetches
+                        .lock()
+                        .expect("content_fetches mutex poisoned");
+                    let Some(index) = fetches.iter().position(|pending| *pending == hash) else {
+                        return Ok(true);
+                    };
+                    if encode_hex(Sha256::digest(data)) != hash {
+                        return Ok(true);
+                    }
+                    fetches.remove(index);
+                }
+                self.store
+                    .put_content(data, now_i64())
+                    .map_err(std::io::Error::other)?;
+                self.emit_event(RpcEvent {
+                    event_type: "content_fetched".into(),
+                    payload: json!({ "hash": hash, "size": data.len() }),
+                });
+            }
+        }
+        Ok(true)
+    }
+
+    fn allow_content_request(&self, peer: &str) -> bool {
+        let now = now_i64();
+        let mut requests = self
+            .content_requests
+            .lock()
+            .expect("content_requests mutex poisoned");
+        requests.retain(|_, times| {
+            times.retain(|at| now - *at < CONTENT_REQUEST_WINDOW_SECS);
+            !times.is_empty()
+        });
+        let times = requests.entry(peer.to_string()).or_default();
+        if times.len() >= CONTENT_REQUESTS_PER_PEER {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+
+    // Resolves content hashes against the local store and records them in
+    // the message fields so the recipient can fetch them from the sender.
+    fn with_content_refs(
+        &self,
+        fields: Option<JsonValue>,
+        refs: &[String],
+    ) -> Result<Option<JsonValue>, std::io::Error> {
+        if refs.is_empty() {
+            return Ok(fields);
+        }
+        let mut entries = Vec::with_capacity(refs.len());
+        for value in refs {
+            let hash = normalize_content_hash(value).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("content ref {value} is not a SHA-256 hex digest"),
+                )
+            })?;
+            let size = self
+                .store
+                .content_size(&hash)
+                .map_err(std::io::Error::other)?
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("content {hash} is not in the content store"),
+                    )
+                })?;
+            entries.push(json!({ "hash": hash, "size": size }));
+        }
+        let mut map = match fields {
+            Some(JsonValue::Object(map)) => map,
+            None => JsonMap::new(),
+            Some(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "fields must be an object when content_refs are given",
+                ))
+            }
+        };
+        map.insert(FIELD_CONTENT_REFS.into(), JsonValue::Array(entries));
+        Ok(Some(JsonValue::Object(map)))
+    }
+
+    // Emits interface_up when an interface becomes connected and
+    // interface_down when a connected interface drops.
+    pub fn record_interface_state(
+        &self,
+        name: &str,
+        state: &str,
+        reconnect_attempts: u32,
+        retry_in: Option<Duration>,
+    ) {
+        let connection = InterfaceConnection {
+            state: state.to_string(),
+            reconnect_attempts,
+            retry_in_ms: retry_in.map(|delay| delay.as_millis() as u64),
+            changed_at: now_i64(),
+        };
+        let was_connected = self
+            .interface_connections
+            .lock()
+            .expect("interface_connections mutex poisoned")
+            .insert(name.to_string(), connection.clone())
+            .is_some_and(|previous| previous.state == "connected");
+        let event_type = match (was_connected, state == "connected") {
+            (false, true) => "interface_up",
+            (true, false) => "interface_down",
+            _ => return,
+        };
+        self.emit_event(RpcEvent {
+            event_type: event_type.into(),
+            payload: json!({
+                "name": name,
+                "state": connection.state,
+                "reconnect_attempts": connection.reconnect_attempts,
+                "retry_in_ms": connection.retry_in_ms,
+            }),
+        });
+    }
+
+    // What each configured interface is doing right now: disabled, active,
+    // connecting or down. Connection and bind reports win; interfaces that
+    // report neither are active while the transport runs them.
+    fn interface_runtime_statuses(&self, interfaces: &[InterfaceRecord]) -> Vec<&'static str> {
+        let connections = self
+            .interface_connections
+            .lock()
+            .expect("interface_connections mutex poisoned")
+            .clone();
+        let bindings = self
+            .interface_bindings
+            .lock()
+            .expect("interface_bindings mutex poisoned")
+            .clone();
+        let running = self
+            .interface_traffic_bridge
+            .as_ref()
+            .map(|bridge| bridge.running_interfaces())
+            .unwrap_or_default();
+        interfaces
+            .iter()
+            .map(|record| {
+                if !record.enabled {
+                    return "disabled";
+                }
+                let Some(name) = record.name.as_ref() else {
+                    return "down";
+                };
+                if let Some(connection) = connections.get(name) {
+                    return match connection.state.as_str() {
+                        "connected" => "active",
+                        "connecting" => "connecting",
+                        _ => "down",
+                    };
+                }
+                if let Some(binding) = bindings.get(name) {
+                    return if binding.bound_addr.is_some() {
+                        "active"
+                    } else {
+                        "down"
+                    };
+                }
+                if running.contains(name) {
+                    "active"
+                } else {
+                    "down"
+                }
+            })
+            .collect()
+    }
+
+    // Keeps the outcome of a listening interface's latest bind so stats can
+    // show the resolved address, or why binding failed.
+    pub fn record_interface_binding(
+        &self,
+        name: &str,
+        bound_addr: Option<String>,
+        error: Option<String>,
+    ) {
+        self.interface_bindings
+            .lock()
+            .expect("interface_bindings mutex poisoned")
+            .insert(
+                name.to_string(),
+                InterfaceBinding {
+                    bound_addr,
+                    error,
+                    changed_at: now_i64(),
+                },
+            );
+    }
+
+    // Persisted scopes are cleared in one store transaction before the
+    // in-memory state, so a failed transaction leaves everything in place.
+    fn clear_scopes(&self, scopes: &[&str]) -> Result<(), std::io::Error> {
+        self.store
+            .clear_scopes(scopes)
+            .map_err(std::io::Error::other)?;
+        for scope in scopes {
+            match *scope {
+                "peers" => self.peers.lock().expect("peers mutex poisoned").clear(),
+                "traces" => self
+                    .delivery_traces
+                    .lock()
+                    .expect("delivery traces mutex poisoned")
+                    .clear(),
+                "tickets" => self
+                    .ticket_cache
+                    .lock()
+                    .expect("ticket mutex poisoned")
+                    .clear(),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn emit_delivery_failed(&self, message_id: &str, status: &str, reason_code: Option<&str>) {
+        let attempts = self
+            .store
+            .get_message(message_id)
+            .ok()
+            .flatten()
+            .map(|record| record.attempts);
+        self.emit_event(RpcEvent {
+            event_type: "delivery_failed".into(),
+            payload: json!({
+                "message_id": message_id,
+                "status": status,
+                "reason_code": reason_code,
+                "attempts": attempts,
+            }),
+        });
+    }
+
     pub fn emit_event(&self, event: RpcEvent) {
         self.push_event(event.clone());
         let _ = self.events.send(event);
@@ -1429,39 +6952,65 @@ impl RpcDaemon {
         let _ = self.events.send(event);
     }
 
+    // Announces once on start and then every interval. A new interval from
+    // set_setting restarts the wait; turning it on from zero announces
+    // straight away.
     pub fn start_announce_scheduler(
         self: std::rc::Rc<Self>,
         interval_secs: u64,
     ) -> tokio::task::JoinHandle<()> {
+        self.set_announce_interval_secs(interval_secs);
+        let mut interval_rx = self.announce_interval.subscribe();
         tokio::task::spawn_local(async move {
-            if interval_secs == 0 {
-                return;
-            }
-
-            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            let mut due = true;
             loop {
-                // First tick is immediate, so we announce once at scheduler start.
-                interval.tick().await;
-                let id = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|value| value.as_secs())
-                    .unwrap_or(0);
-
-                if let Some(bridge) = &self.announce_bridge {
-                    let _ = bridge.announce_now();
+                let interval_secs = *interval_rx.borrow_and_update();
+                if interval_secs == 0 {
+                    if interval_rx.changed().await.is_err() {
+                        return;
+                    }
+                    due = true;
+                    continue;
+                }
+                if due {
+                    self.send_scheduled_announce().await;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(interval_secs)) => due = true,
+                    changed = interval_rx.changed() => {
+                        if changed.is_err() {
+                            return;
+                        }
+                        due = false;
+                    }
                 }
-
-                let timestamp = now_i64();
-                let event = RpcEvent {
-                    event_type: "announce_sent".into(),
-                    payload: json!({ "timestamp": timestamp, "announce_id": id }),
-                };
-                self.push_event(event.clone());
-                let _ = self.events.send(event);
             }
         })
     }
 
+    async fn send_scheduled_announce(&self) {
+        let id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|value| value.as_secs())
+            .unwrap_or(0);
+
+        if let Err(err) = self.announce_confirmed().await {
+            log::warn!("scheduled announce failed: {err}");
+        }
+
+        let timestamp = now_i64();
+        let event = RpcEvent {
+            event_type: "announce_sent".into(),
+            payload: json!({
+                "timestamp": timestamp,
+                "announce_id": id,
+                "announced_aspects": self.advertised_aspects(),
+            }),
+        };
+        self.push_event(event.clone());
+        let _ = self.events.send(event);
+    }
+
     pub fn inject_inbound_test_message(&self, content: &str) {
         let timestamp = now_i64();
         let record = crate::storage::messages::MessageRecord {
@@ -1474,6 +7023,9 @@ impl RpcDaemon {
             direction: "in".into(),
             fields: None,
             receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
         };
         let _ = self.store.insert_message(&record);
         let event = RpcEvent {
@@ -1494,6 +7046,22 @@ impl RpcDaemon {
     }
 }
 
+fn evict_delivery_traces(traces: &mut HashMap<String, Vec<DeliveryTraceEntry>>, keep: &str) {
+    if traces.len() <= MAX_TRACKED_MESSAGE_TRACES {
+        return;
+    }
+    let overflow = traces.len() - MAX_TRACKED_MESSAGE_TRACES;
+    let evicted_ids = traces
+        .keys()
+        .filter(|key| key.as_str() != keep)
+        .take(overflow)
+        .cloned()
+        .collect::<Vec<_>>();
+    for id in evicted_ids {
+        traces.remove(&id);
+    }
+}
+
 fn parse_announce_cursor(cursor: Option<&str>) -> Option<(Option<i64>, Option<String>)> {
     let raw = cursor?.trim();
     if raw.is_empty() {
@@ -1513,11 +7081,66 @@ fn parse_announce_cursor(cursor: Option<&str>) -> Option<(Option<i64>, Option<St
         .map(|timestamp| (Some(timestamp), None))
 }
 
+// Only successful sends pin down the path a message actually took, e.g.
+// "sent: link" or "sent: propagated"; failures keep the previous method.
+fn delivery_method_from_status(status: &str) -> Option<String> {
+    let normalized = status.trim().to_ascii_lowercase();
+    let method = normalized
+        .strip_prefix("sent:")?
+        .split_whitespace()
+        .next()?;
+    Some(method.to_string())
+}
+
+// No status yet means the delivery never reported back; the interim
+// opportunistic retry status and timeouts are likewise unresolved.
+fn is_resumable_outbound_status(status: Option<&str>) -> bool {
+    let Some(status) = status.map(|value| value.trim().to_ascii_lowercase()) else {
+        return true;
+    };
+    status.is_empty()
+        || status == "queued"
+        || status == "sending"
+        || status.contains("trying opportunistic")
+        || (status.starts_with("failed") && delivery_reason_code(&status) == Some("timeout"))
+}
+
+// Checked in order: a fallback such as "link failed: ...; trying
+// opportunistic" is still in flight, so it must not read as a failure.
+fn delivery_stage(status: &str) -> DeliveryStage {
+    let normalized = status.trim().to_ascii_lowercase();
+    if normalized.starts_with("failed") {
+        DeliveryStage::Failed
+    } else if normalized == "delivered" || normalized == "sent: loopback" {
+        DeliveryStage::Delivered
+    } else if normalized.contains("opportunistic") {
+        DeliveryStage::Opportunistic
+    } else if normalized.contains("receipt") {
+        DeliveryStage::Receipt
+    } else if normalized.contains("path") {
+        DeliveryStage::PathRequested
+    } else if normalized.contains("propagat") {
+        DeliveryStage::Propagated
+    } else if normalized.starts_with("sent: link") || normalized.starts_with("sent: direct") {
+        DeliveryStage::Link
+    } else if normalized == "queued" || normalized.starts_with("queued:") || normalized == "resumed"
+    {
+        DeliveryStage::Queued
+    } else if delivery_reason_code(&normalized).is_some() {
+        DeliveryStage::Failed
+    } else {
+        DeliveryStage::Sending
+    }
+}
+
 fn delivery_reason_code(status: &str) -> Option<&'static str> {
     let normalized = status.trim().to_ascii_lowercase();
     if normalized.is_empty() {
         return None;
     }
+    if normalized.contains("deadline exceeded") {
+        return Some("deadline_exceeded");
+    }
     if normalized.contains("receipt timeout") {
         return Some("receipt_timeout");
     }
@@ -1530,6 +7153,15 @@ fn delivery_reason_code(status: &str) -> Option<&'static str> {
     {
         return Some("no_path");
     }
+    if normalized.contains("missing destination identity") {
+        return Some("missing_destination_identity");
+    }
+    if normalized.contains("encrypt failed") {
+        return Some("encrypt_failed");
+    }
+    if normalized.contains("ciphertext too large") || normalized.contains("payload too large") {
+        return Some("ciphertext_too_large");
+    }
     if normalized.contains("no propagation relay selected") {
         return Some("relay_unset");
     }
@@ -1,20 +1,122 @@
+use std::collections::VecDeque;
+use std::future::Future;
 use std::io;
+use std::pin::Pin;
+use std::time::Duration;
 
-use crate::rpc::{codec, handle_framed_request, RpcDaemon};
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::rpc::{
+    codec, handle_framed_request_async, RpcDaemon, RpcError, RpcEvent, RpcRequest, RpcResponse,
+};
 
 const HEADER_END: &[u8] = b"\r\n\r\n";
+const WEBSOCKET_PATH: &str = "/ws";
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+pub const WEBSOCKET_PING_INTERVAL: Duration = Duration::from_secs(30);
+// Requests read but not yet answered; past this the socket is not read until
+// the backlog drains.
+const WEBSOCKET_MAX_PENDING_REQUESTS: usize = 32;
+
+const CORS_ALLOW_METHODS: &str = "GET, POST, OPTIONS";
+const CORS_ALLOW_HEADERS: &str = "Authorization, Content-Type";
+const CORS_MAX_AGE_SECS: u64 = 600;
+
+pub const DEFAULT_MAX_HEADER_BYTES: usize = 16 * 1024;
+pub const DEFAULT_MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestLimits {
+    pub max_header_bytes: usize,
+    pub max_body_bytes: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_header_bytes: DEFAULT_MAX_HEADER_BYTES,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+}
 
-pub fn handle_http_request(daemon: &RpcDaemon, request: &[u8]) -> io::Result<Vec<u8>> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestProgress {
+    Incomplete,
+    Complete,
+    TooLarge(&'static str),
+}
+
+// Called after every read so oversized requests are refused from the declared
+// Content-Length, before the body is buffered.
+pub fn check_request_progress(buffer: &[u8], limits: &RequestLimits) -> RequestProgress {
+    let Some(header_end) = find_header_end(buffer) else {
+        if buffer.len() > limits.max_header_bytes {
+            return RequestProgress::TooLarge("request headers too large");
+        }
+        return RequestProgress::Incomplete;
+    };
+    if header_end > limits.max_header_bytes {
+        return RequestProgress::TooLarge("request headers too large");
+    }
+    let Some(length) = parse_content_length(&buffer[..header_end]) else {
+        return RequestProgress::Complete;
+    };
+    if length > limits.max_body_bytes {
+        return RequestProgress::TooLarge("request body too large");
+    }
+    if buffer.len() >= header_end + HEADER_END.len() + length {
+        RequestProgress::Complete
+    } else {
+        RequestProgress::Incomplete
+    }
+}
+
+pub async fn handle_http_request(daemon: &RpcDaemon, request: &[u8]) -> io::Result<Vec<u8>> {
     let header_end = find_header_end(request)
         .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "missing headers"))?;
     let headers = &request[..header_end];
     let body_start = header_end + HEADER_END.len();
     let (method, path) = parse_request_line(headers)
         .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid request line"))?;
-    match (method.as_str(), path.as_str()) {
+    let cors = cors_headers(daemon, headers);
+    // Preflights never carry credentials, so they are answered before auth.
+    if method == "OPTIONS" {
+        return Ok(preflight_response(headers, cors.as_deref()));
+    }
+    let response = match authorize_request(daemon, headers) {
+        Ok(()) => route_request(daemon, request, headers, body_start, &method, &path).await?,
+        Err(message) => {
+            log::warn!("rpc http: rejected {method} {path}: {message}");
+            build_unauthorized_response(message)?
+        }
+    };
+    Ok(match cors {
+        Some(cors) => with_headers(response, &cors),
+        None => response,
+    })
+}
+
+async fn route_request(
+    daemon: &RpcDaemon,
+    request: &[u8],
+    headers: &[u8],
+    body_start: usize,
+    method: &str,
+    path: &str,
+) -> io::Result<Vec<u8>> {
+    match (method, path) {
         ("GET", "/events") => {
             if let Some(event) = daemon.take_event() {
-                let body = codec::encode_frame(&event).map_err(io::Error::other)?;
+                let body = match daemon.sign_event(&event) {
+                    Some(signed) => codec::encode_frame(&signed),
+                    None => codec::encode_frame(&event),
+                }
+                .map_err(io::Error::other)?;
                 Ok(build_response(StatusCode::Ok, &body))
             } else {
                 Ok(build_response(StatusCode::NoContent, &[]))
@@ -31,8 +133,13 @@ pub fn handle_http_request(daemon: &RpcDaemon, request: &[u8]) -> io::Result<Vec
                 ));
             }
             let body = &request[body_start..body_start + content_length];
-            let response_body = handle_framed_request(daemon, body)?;
-            Ok(build_response(StatusCode::Ok, &response_body))
+            let format = codec::detect_frame_format(body)?;
+            let response_body = handle_framed_request_async(daemon, body).await?;
+            Ok(build_response_as(
+                StatusCode::Ok,
+                format.content_type(),
+                &response_body,
+            ))
         }
         _ => Err(io::Error::new(
             io::ErrorKind::InvalidInput,
@@ -61,6 +168,78 @@ pub fn parse_content_length(headers: &[u8]) -> Option<usize> {
     None
 }
 
+pub fn parse_bearer_token(headers: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(headers);
+    for line in text.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            continue;
+        }
+        let value = value.trim();
+        let (scheme, token) = value.split_once(' ')?;
+        if scheme.eq_ignore_ascii_case("bearer") {
+            let token = token.trim();
+            return (!token.is_empty()).then(|| token.to_string());
+        }
+    }
+    None
+}
+
+fn authorize_request(daemon: &RpcDaemon, headers: &[u8]) -> Result<(), &'static str> {
+    let tokens = daemon.http_auth_tokens();
+    if tokens.is_empty() {
+        return Ok(());
+    }
+    let presented = parse_bearer_token(headers).ok_or("missing bearer token")?;
+    let matched = tokens
+        .iter()
+        .find(|entry| constant_time_eq(entry.token.as_bytes(), presented.as_bytes()))
+        .ok_or("invalid bearer token")?;
+    log::debug!("rpc http: authorized token label={}", matched.label);
+    Ok(())
+}
+
+// Requests without an Origin header, or from an origin outside the
+// allowlist, get no CORS headers and behave exactly as before.
+fn cors_headers(daemon: &RpcDaemon, headers: &[u8]) -> Option<String> {
+    let origin = header_value(headers, "origin")?;
+    let allowed = daemon.http_cors_origins();
+    let normalized = origin.trim_end_matches('/');
+    allowed
+        .iter()
+        .any(|entry| entry == "*" || entry.eq_ignore_ascii_case(normalized))
+        .then(|| format!("Access-Control-Allow-Origin: {origin}\r\nVary: Origin\r\n"))
+}
+
+fn preflight_response(headers: &[u8], cors: Option<&str>) -> Vec<u8> {
+    match cors {
+        Some(cors) => {
+            let allow = format!(
+                "{cors}Access-Control-Allow-Methods: {CORS_ALLOW_METHODS}\r\n\
+                 Access-Control-Allow-Headers: {CORS_ALLOW_HEADERS}\r\n\
+                 Access-Control-Max-Age: {CORS_MAX_AGE_SECS}\r\n"
+            );
+            with_headers(build_response(StatusCode::NoContent, &[]), &allow)
+        }
+        None if header_value(headers, "origin").is_some() => {
+            build_response(StatusCode::Forbidden, b"origin not allowed")
+        }
+        None => with_headers(
+            build_response(StatusCode::NoContent, &[]),
+            &format!("Allow: {CORS_ALLOW_METHODS}\r\n"),
+        ),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 fn parse_request_line(headers: &[u8]) -> Option<(String, String)> {
     let text = String::from_utf8_lossy(headers);
     let mut lines = text.lines();
@@ -71,28 +250,468 @@ fn parse_request_line(headers: &[u8]) -> Option<(String, String)> {
     Some((method, path))
 }
 
+// A GET to /ws carrying the RFC 6455 upgrade headers. The caller hands such
+// requests to websocket_handshake instead of handle_http_request.
+pub fn is_websocket_upgrade(request: &[u8]) -> bool {
+    let Some(header_end) = find_header_end(request) else {
+        return false;
+    };
+    let headers = &request[..header_end];
+    let Some((method, path)) = parse_request_line(headers) else {
+        return false;
+    };
+    method == "GET"
+        && path == WEBSOCKET_PATH
+        && header_value(headers, "upgrade")
+            .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+// Ok carries the 101 response to send before serve_websocket takes over the
+// connection; Err carries the response to send before closing it.
+pub fn websocket_handshake(daemon: &RpcDaemon, request: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+    let header_end =
+        find_header_end(request).ok_or_else(|| build_error_response("missing headers"))?;
+    let headers = &request[..header_end];
+    // Browsers do not apply CORS to websockets, so the allowlist is enforced
+    // here; clients that send no Origin are unaffected.
+    if header_value(headers, "origin").is_some() && cors_headers(daemon, headers).is_none() {
+        log::warn!("rpc http: rejected websocket upgrade: origin not allowed");
+        return Err(build_response(StatusCode::Forbidden, b"origin not allowed"));
+    }
+    if let Err(message) = authorize_request(daemon, headers) {
+        log::warn!("rpc http: rejected websocket upgrade: {message}");
+        return Err(
+            build_unauthorized_response(message).unwrap_or_else(|_| build_error_response(message))
+        );
+    }
+    if header_value(headers, "sec-websocket-version").as_deref() != Some("13") {
+        return Err(build_error_response("unsupported websocket version"));
+    }
+    let key = header_value(headers, "sec-websocket-key")
+        .ok_or_else(|| build_error_response("missing sec-websocket-key"))?;
+    let mut response = Vec::new();
+    response.extend_from_slice(b"HTTP/1.1 101 Switching Protocols\r\n");
+    response.extend_from_slice(b"Upgrade: websocket\r\nConnection: Upgrade\r\n");
+    response.extend_from_slice(
+        format!(
+            "Sec-WebSocket-Accept: {}\r\n\r\n",
+            websocket_accept_key(&key)
+        )
+        .as_bytes(),
+    );
+    Ok(response)
+}
+
+pub fn websocket_accept_key(key: &str) -> String {
+    let mut input = key.trim().as_bytes().to_vec();
+    input.extend_from_slice(WEBSOCKET_GUID.as_bytes());
+    // SHA-1 is only used here because RFC 6455 fixes it for the accept key.
+    BASE64_STANDARD.encode(Sha1::digest(&input))
+}
+
+// Multiplexes RPC and events over one upgraded connection. Binary messages
+// carry codec frames and text messages bare JSON requests; each response goes
+// back the way its request came, and events follow the form the client last
+// used (binary MessagePack until the first text message). Requests are
+// answered one at a time in arrival order, but alongside reads, events and
+// pings, so a slow handler does not stall the rest of the connection.
+pub async fn serve_websocket<S>(
+    daemon: &RpcDaemon,
+    stream: S,
+    limits: RequestLimits,
+) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut events = daemon.subscribe_events();
+    let mut ping = tokio::time::interval(WEBSOCKET_PING_INTERVAL);
+    ping.tick().await;
+    let mut awaiting_pong = false;
+    let mut text_mode = false;
+    let mut buffer = Vec::new();
+    let mut fragments: Option<(u8, Vec<u8>)> = None;
+    let mut requests: VecDeque<(u8, Vec<u8>)> = VecDeque::new();
+    let mut in_flight: Option<WsReplyFuture<'_>> = None;
+    loop {
+        loop {
+            let (frame, used) = match parse_ws_frame(&buffer, limits.max_body_bytes) {
+                Ok(Some(parsed)) => parsed,
+                Ok(None) => break,
+                Err(code) => {
+                    write_ws_close(&mut writer, code).await?;
+                    return Ok(());
+                }
+            };
+            buffer.drain(..used);
+            // Any traffic shows the peer is alive.
+            awaiting_pong = false;
+            let message = match frame.opcode {
+                WS_OP_CLOSE => {
+                    let code = frame.payload.get(..2).map_or(WS_CLOSE_NORMAL, |bytes| {
+                        u16::from_be_bytes([bytes[0], bytes[1]])
+                    });
+                    write_ws_close(&mut writer, code).await?;
+                    return Ok(());
+                }
+                WS_OP_PING => {
+                    write_ws_frame(&mut writer, WS_OP_PONG, &frame.payload).await?;
+                    continue;
+                }
+                WS_OP_PONG => continue,
+                WS_OP_CONTINUATION => {
+                    let Some((opcode, mut payload)) = fragments.take() else {
+                        write_ws_close(&mut writer, WS_CLOSE_PROTOCOL_ERROR).await?;
+                        return Ok(());
+                    };
+                    if payload.len() + frame.payload.len() > limits.max_body_bytes {
+                        write_ws_close(&mut writer, WS_CLOSE_TOO_BIG).await?;
+                        return Ok(());
+                    }
+                    payload.extend_from_slice(&frame.payload);
+                    if !frame.fin {
+                        fragments = Some((opcode, payload));
+                        continue;
+                    }
+                    (opcode, payload)
+                }
+                // A new data message may not start inside a fragmented one.
+                WS_OP_TEXT | WS_OP_BINARY if fragments.is_some() => {
+                    write_ws_close(&mut writer, WS_CLOSE_PROTOCOL_ERROR).await?;
+                    return Ok(());
+                }
+                WS_OP_TEXT | WS_OP_BINARY if !frame.fin => {
+                    fragments = Some((frame.opcode, frame.payload));
+                    continue;
+                }
+                WS_OP_TEXT | WS_OP_BINARY => (frame.opcode, frame.payload),
+                _ => {
+                    write_ws_close(&mut writer, WS_CLOSE_PROTOCOL_ERROR).await?;
+                    return Ok(());
+                }
+            };
+            text_mode = message.0 == WS_OP_TEXT;
+            requests.push_back(message);
+        }
+
+        if in_flight.is_none() {
+            if let Some((opcode, payload)) = requests.pop_front() {
+                in_flight = Some(Box::pin(async move {
+                    let reply = websocket_rpc(daemon, &payload, opcode == WS_OP_TEXT).await;
+                    (opcode, reply)
+                }));
+            }
+        }
+
+        let mut chunk = [0u8; 4096];
+        tokio::select! {
+            (opcode, reply) = next_ws_reply(&mut in_flight), if in_flight.is_some() => {
+                in_flight = None;
+                write_ws_frame(&mut writer, opcode, &reply?).await?;
+            }
+            read = reader.read(&mut chunk), if requests.len() < WEBSOCKET_MAX_PENDING_REQUESTS => {
+                let read = read?;
+                if read == 0 {
+                    return Ok(());
+                }
+                buffer.extend_from_slice(&chunk[..read]);
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("rpc websocket: dropped {skipped} events");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => {
+                        write_ws_close(&mut writer, WS_CLOSE_GOING_AWAY).await?;
+                        return Ok(());
+                    }
+                };
+                let (opcode, body) = websocket_event(daemon, &event, text_mode)?;
+                write_ws_frame(&mut writer, opcode, &body).await?;
+            }
+            _ = ping.tick() => {
+                if awaiting_pong {
+                    log::warn!("rpc websocket: peer stopped answering pings");
+                    write_ws_close(&mut writer, WS_CLOSE_GOING_AWAY).await?;
+                    return Ok(());
+                }
+                write_ws_frame(&mut writer, WS_OP_PING, &[]).await?;
+                awaiting_pong = true;
+            }
+        }
+    }
+}
+
+// Serves length-prefixed frames over a raw stream such as a plain TCP
+// socket. Each request frame gets one response frame in the same format;
+// a frame over the body limit closes the stream.
+pub async fn serve_framed_stream<S>(
+    daemon: &RpcDaemon,
+    stream: S,
+    limits: RequestLimits,
+) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let mut frames = codec::FrameReader::new(limits.max_body_bytes);
+    let mut chunk = [0u8; 4096];
+    loop {
+        while let Some(frame) = frames.next_frame()? {
+            let response = match handle_framed_request_async(daemon, &frame).await {
+                Ok(response) => response,
+                Err(err) => codec::encode_frame(&websocket_error_response(0, &err))?,
+            };
+            writer.write_all(&response).await?;
+        }
+        let read = reader.read(&mut chunk).await?;
+        if read == 0 {
+            if frames.buffered() > 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "stream closed mid-frame",
+                ));
+            }
+            return Ok(());
+        }
+        frames.push(&chunk[..read]);
+    }
+}
+
+// Handler errors are answered in-band so one bad request doesn't cost the
+// client its event stream.
+async fn websocket_rpc(daemon: &RpcDaemon, payload: &[u8], text: bool) -> io::Result<Vec<u8>> {
+    if !text {
+        return match handle_framed_request_async(daemon, payload).await {
+            Ok(response) => Ok(response),
+            Err(err) => codec::encode_frame(&websocket_error_response(0, &err)),
+        };
+    }
+    let response = match serde_json::from_slice::<RpcRequest>(payload) {
+        Ok(request) => {
+            let id = request.id;
+            daemon
+                .handle_rpc_async(request)
+                .await
+                .unwrap_or_else(|err| websocket_error_response(id, &err))
+        }
+        Err(err) => websocket_error_response(0, &io::Error::new(io::ErrorKind::InvalidInput, err)),
+    };
+    serde_json::to_vec(&response).map_err(io::Error::other)
+}
+
+fn websocket_error_response(id: u64, err: &io::Error) -> RpcResponse {
+    RpcResponse {
+        id,
+        result: None,
+        error: Some(RpcError {
+            code: "BAD_REQUEST".into(),
+            message: err.to_string(),
+        }),
+    }
+}
+
+fn websocket_event(daemon: &RpcDaemon, event: &RpcEvent, text: bool) -> io::Result<(u8, Vec<u8>)> {
+    let signed = daemon.sign_event(event);
+    if text {
+        let body = match signed {
+            Some(signed) => serde_json::to_vec(&signed),
+            None => serde_json::to_vec(event),
+        }
+        .map_err(io::Error::other)?;
+        return Ok((WS_OP_TEXT, body));
+    }
+    let body = match signed {
+        Some(signed) => codec::encode_frame(&signed),
+        None => codec::encode_frame(event),
+    }?;
+    Ok((WS_OP_BINARY, body))
+}
+
+const WS_OP_CONTINUATION: u8 = 0x0;
+const WS_OP_TEXT: u8 = 0x1;
+const WS_OP_BINARY: u8 = 0x2;
+const WS_OP_CLOSE: u8 = 0x8;
+const WS_OP_PING: u8 = 0x9;
+const WS_OP_PONG: u8 = 0xA;
+
+const WS_CLOSE_NORMAL: u16 = 1000;
+const WS_CLOSE_GOING_AWAY: u16 = 1001;
+const WS_CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const WS_CLOSE_TOO_BIG: u16 = 1009;
+
+type WsReply = (u8, io::Result<Vec<u8>>);
+type WsReplyFuture<'a> = Pin<Box<dyn Future<Output = WsReply> + 'a>>;
+
+// Only polled while a request is in flight.
+async fn next_ws_reply(in_flight: &mut Option<WsReplyFuture<'_>>) -> WsReply {
+    match in_flight {
+        Some(reply) => reply.await,
+        None => std::future::pending().await,
+    }
+}
+
+struct WsFrame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+// Ok(None) until a whole frame is buffered; Err carries the close code for a
+// frame the server refuses. Clients must mask every frame.
+fn parse_ws_frame(buffer: &[u8], max_payload: usize) -> Result<Option<(WsFrame, usize)>, u16> {
+    if buffer.len() < 2 {
+        return Ok(None);
+    }
+    let fin = buffer[0] & 0x80 != 0;
+    let opcode = buffer[0] & 0x0F;
+    if buffer[1] & 0x80 == 0 {
+        return Err(WS_CLOSE_PROTOCOL_ERROR);
+    }
+    // Control frames may not be fragmented and carry at most 125 bytes.
+    if opcode & 0x08 != 0 && (!fin || buffer[1] & 0x7F > 125) {
+        return Err(WS_CLOSE_PROTOCOL_ERROR);
+    }
+    let (length, mut offset) = match buffer[1] & 0x7F {
+        126 => {
+            let Some(bytes) = buffer.get(2..4) else {
+                return Ok(None);
+            };
+            (u64::from(u16::from_be_bytes([bytes[0], bytes[1]])), 4)
+        }
+        127 => {
+            let Some(bytes) = buffer.get(2..10) else {
+                return Ok(None);
+            };
+            let mut raw = [0u8; 8];
+            raw.copy_from_slice(bytes);
+            (u64::from_be_bytes(raw), 10)
+        }
+        length => (u64::from(length), 2),
+    };
+    let length = usize::try_from(length).map_err(|_| WS_CLOSE_TOO_BIG)?;
+    if length > max_payload {
+        return Err(WS_CLOSE_TOO_BIG);
+    }
+    let Some(mask) = buffer.get(offset..offset + 4) else {
+        return Ok(None);
+    };
+    let mask = [mask[0], mask[1], mask[2], mask[3]];
+    offset += 4;
+    let Some(masked) = buffer.get(offset..offset + length) else {
+        return Ok(None);
+    };
+    let payload = masked
+        .iter()
+        .enumerate()
+        .map(|(index, byte)| byte ^ mask[index % 4])
+        .collect();
+    Ok(Some((
+        WsFrame {
+            fin,
+            opcode,
+            payload,
+        },
+        offset + length,
+    )))
+}
+
+async fn write_ws_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    opcode: u8,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    if payload.len() < 126 {
+        frame.push(payload.len() as u8);
+    } else if let Ok(length) = u16::try_from(payload.len()) {
+        frame.push(126);
+        frame.extend_from_slice(&length.to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(payload.len() as u64).to_be_bytes());
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame).await?;
+    writer.flush().await
+}
+
+async fn write_ws_close<W: AsyncWrite + Unpin>(writer: &mut W, code: u16) -> io::Result<()> {
+    write_ws_frame(writer, WS_OP_CLOSE, &code.to_be_bytes()).await?;
+    writer.shutdown().await
+}
+
+fn header_value(headers: &[u8], name: &str) -> Option<String> {
+    let text = String::from_utf8_lossy(headers);
+    text.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_string())
+    })
+}
+
 enum StatusCode {
     Ok,
     NoContent,
     BadRequest,
+    Unauthorized,
+    Forbidden,
+    PayloadTooLarge,
 }
 
 fn build_response(status: StatusCode, body: &[u8]) -> Vec<u8> {
+    build_response_as(status, codec::FrameFormat::MsgPack.content_type(), body)
+}
+
+fn build_response_as(status: StatusCode, content_type: &str, body: &[u8]) -> Vec<u8> {
     let status_line = match status {
         StatusCode::Ok => "HTTP/1.1 200 OK",
         StatusCode::NoContent => "HTTP/1.1 204 No Content",
         StatusCode::BadRequest => "HTTP/1.1 400 Bad Request",
+        StatusCode::Unauthorized => "HTTP/1.1 401 Unauthorized",
+        StatusCode::Forbidden => "HTTP/1.1 403 Forbidden",
+        StatusCode::PayloadTooLarge => "HTTP/1.1 413 Payload Too Large",
     };
     let mut response = Vec::new();
     response.extend_from_slice(status_line.as_bytes());
-    response.extend_from_slice(b"\r\nContent-Type: application/msgpack\r\n");
+    response.extend_from_slice(format!("\r\nContent-Type: {content_type}\r\n").as_bytes());
     response.extend_from_slice(format!("Content-Length: {}\r\n", body.len()).as_bytes());
     response.extend_from_slice(b"\r\n");
     response.extend_from_slice(body);
     response
 }
 
+// Appends header lines, each ending in CRLF, to a built response.
+fn with_headers(mut response: Vec<u8>, lines: &str) -> Vec<u8> {
+    if let Some(header_end) = find_header_end(&response) {
+        let at = header_end + 2;
+        response.splice(at..at, lines.bytes());
+    }
+    response
+}
+
 pub fn build_error_response(message: &str) -> Vec<u8> {
     let body = message.as_bytes();
     build_response(StatusCode::BadRequest, body)
 }
+
+pub fn build_payload_too_large_response(message: &str) -> Vec<u8> {
+    build_response(StatusCode::PayloadTooLarge, message.as_bytes())
+}
+
+fn build_unauthorized_response(message: &str) -> io::Result<Vec<u8>> {
+    let response = RpcResponse {
+        id: 0,
+        result: None,
+        error: Some(RpcError {
+            code: "UNAUTHORIZED".into(),
+            message: message.into(),
+        }),
+    };
+    let body = codec::encode_frame(&response).map_err(io::Error::other)?;
+    Ok(build_response(StatusCode::Unauthorized, &body))
+}
@@ -1,16 +1,40 @@
+mod attachment_chunks;
 pub mod codec;
 mod daemon;
 pub mod http;
+pub mod telemetry;
+use base64::Engine as _;
 use rmpv::Value as MsgPackValue;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map as JsonMap, Value as JsonValue};
 
-use crate::storage::messages::{AnnounceRecord, MessageRecord, MessagesStore};
+use crate::destination::{
+    destination_hash_from_identity_hash, group_decrypt, group_encrypt,
+    lxmf_delivery_hash_from_identity_hash, DestinationName, PlainOutputDestination,
+    NAME_HASH_LENGTH, RAND_HASH_LENGTH, RATCHET_LENGTH,
+};
+use crate::hash::{lxmf_address_hash, AddressHash, Hash, ADDRESS_HASH_SIZE, HASH_SIZE};
+use crate::identity::{lxmf_sign, lxmf_verify, EmptyIdentity, Identity, PrivateIdentity};
+use crate::iface::TrafficHistory;
+use crate::packet::{
+    DestinationType, PacketType, LXMF_MAX_PAYLOAD, PACKET_MDU, RNS_PROTOCOL_VERSION,
+};
+use crate::resource::ResourceCounts;
+use crate::storage::backup::{self, BackupPolicy};
+use crate::storage::messages::{
+    AnnounceRecord, DeliveryTraceRecord, GroupKeyRecord, KnownIdentityRecord, MessageRecord,
+    MessageStampRecord, MessagesStore, PathRecord, TelemetryRecord,
+};
+use crate::transport::iface_filter::{InterfaceFilter, InterfaceFilterStatus};
+use crate::transport::TransportMetrics;
+use attachment_chunks::{AttachmentChunk, ChunkAssembly, OutboundChunkTransfer};
+use rand_core::{OsRng, RngCore};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct RpcRequest {
@@ -40,6 +64,28 @@ pub struct InterfaceRecord {
     pub host: Option<String>,
     pub port: Option<u16>,
     pub name: Option<String>,
+    // Type-specific settings, checked against INTERFACE_OPTIONS.
+    #[serde(default, skip_serializing_if = "JsonMap::is_empty")]
+    pub options: JsonMap<String, JsonValue>,
+}
+
+// Last reported link state of a named interface, for interfaces that
+// maintain a connection of their own.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct InterfaceConnection {
+    pub state: String,
+    pub reconnect_attempts: u32,
+    pub retry_in_ms: Option<u64>,
+    pub changed_at: i64,
+}
+
+// Result of the latest bind attempt of a listening interface. `bound_addr`
+// is the resolved local address, including the port picked for port 0.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct InterfaceBinding {
+    pub bound_addr: Option<String>,
+    pub error: Option<String>,
+    pub changed_at: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
@@ -69,12 +115,233 @@ pub struct PropagationState {
     pub last_sync_error: Option<String>,
 }
 
+// Bounds on the payloads a propagation node holds for offline peers. Expired
+// payloads go first, then the oldest until both caps hold. Zero turns a
+// bound off.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PropagationStoreLimits {
+    pub max_bytes: u64,
+    pub max_messages: u64,
+    pub max_age_secs: u64,
+}
+
+impl Default for PropagationStoreLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 256 * 1024 * 1024,
+            max_messages: 50_000,
+            max_age_secs: 30 * 24 * 60 * 60,
+        }
+    }
+}
+
+// Bounds on resources peers sent without being asked, kept in the content
+// store until a client fetches them. Content we stored or requested ourselves
+// is never pruned. Zero turns a bound off.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ReceivedResourceLimits {
+    pub max_bytes: u64,
+    pub max_items: u64,
+    pub max_age_secs: u64,
+}
+
+impl Default for ReceivedResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 * 1024 * 1024,
+            max_items: 256,
+            max_age_secs: 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+// States an LXMF propagation sync passes through before it completes or
+// fails; anything else means no sync is running.
+const ACTIVE_PROPAGATION_SYNC_STATES: &[&str] = &[
+    "path_requested",
+    "link_establishing",
+    "link_established",
+    "request_sent",
+    "receiving",
+    "response_received",
+];
+
+fn is_active_propagation_sync_state(state_name: &str) -> bool {
+    ACTIVE_PROPAGATION_SYNC_STATES.contains(&state_name)
+}
+
+// Summarises a sync that just left the active states. Only the "complete"
+// state counts as a success; anything else reports the sync's error.
+fn propagation_sync_record(state: &PropagationState) -> PropagationSyncRecord {
+    let completed = state.state_name == "complete";
+    PropagationSyncRecord {
+        started_at: state.last_sync_started,
+        completed_at: state.last_sync_completed.filter(|_| completed),
+        messages_received: state.messages_received,
+        state_name: state.state_name.clone(),
+        error: state.last_sync_error.clone().filter(|_| !completed),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PendingTelemetryRequest {
+    pub message_id: String,
+    pub peer: String,
+    pub timebase: i64,
+    pub requested_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PropagationSyncRecord {
+    pub started_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub messages_received: usize,
+    pub state_name: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct StoreBackupRecord {
+    pub path: String,
+    pub size_bytes: u64,
+    pub created_at: i64,
+    pub pruned: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 pub struct StampPolicy {
     pub target_cost: u32,
     pub flexibility: u32,
 }
 
+// Thresholds for shedding inbound messages from non-prioritised sources when
+// the daemon is being flooded. A limit of zero disables that check.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct InboundOverloadPolicy {
+    pub window_secs: u64,
+    pub max_writes_per_window: u64,
+    pub max_queued_events: usize,
+}
+
+impl Default for InboundOverloadPolicy {
+    fn default() -> Self {
+        Self {
+            window_secs: 10,
+            max_writes_per_window: 200,
+            max_queued_events: 0,
+        }
+    }
+}
+
+// Seconds since a peer was last heard from before list_peers stops calling
+// it online, and then stale. Past stale_secs the peer is offline.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PresenceThresholds {
+    pub online_secs: u64,
+    pub stale_secs: u64,
+}
+
+impl Default for PresenceThresholds {
+    fn default() -> Self {
+        // One announce interval plus slack, then a few missed announces.
+        Self {
+            online_secs: 900,
+            stale_secs: 3600,
+        }
+    }
+}
+
+impl PresenceThresholds {
+    pub fn presence(&self, age_secs: i64) -> &'static str {
+        let age = age_secs.max(0) as u64;
+        if age <= self.online_secs {
+            "online"
+        } else if age <= self.stale_secs.max(self.online_secs) {
+            "stale"
+        } else {
+            "offline"
+        }
+    }
+}
+
+const SETTING_DELIVERY_POLICY: &str = "delivery_policy";
+const SETTING_STAMP_POLICY: &str = "stamp_policy";
+const SETTING_STAMP_POLICY_OVERRIDES: &str = "stamp_policy_overrides";
+const SETTING_INBOUND_OVERLOAD_POLICY: &str = "inbound_overload_policy";
+// Read by the daemon before the announce bridge is built, so a name set over
+// RPC takes precedence over LXMF_DISPLAY_NAME after a restart.
+pub const SETTING_DISPLAY_NAME: &str = "display_name";
+const SETTING_ANNOUNCE_INTERVAL_SECS: &str = "announce_interval_secs";
+const SETTING_MAX_PEERS: &str = "max_peers";
+const SETTING_PRESENCE_ONLINE_SECS: &str = "presence_online_secs";
+const SETTING_PRESENCE_STALE_SECS: &str = "presence_stale_secs";
+const PERSISTED_SETTINGS: &[&str] = &[
+    SETTING_DELIVERY_POLICY,
+    SETTING_STAMP_POLICY,
+    SETTING_STAMP_POLICY_OVERRIDES,
+    SETTING_INBOUND_OVERLOAD_POLICY,
+    SETTING_DISPLAY_NAME,
+    SETTING_ANNOUNCE_INTERVAL_SECS,
+    SETTING_MAX_PEERS,
+    SETTING_PRESENCE_ONLINE_SECS,
+    SETTING_PRESENCE_STALE_SECS,
+];
+// Knobs set_setting changes one at a time, with the inclusive range each
+// value must fall in. Stored values win over the config file and command
+// line when the daemon starts.
+const RUNTIME_SETTINGS: &[(&str, u64, u64)] = &[
+    (SETTING_ANNOUNCE_INTERVAL_SECS, 0, 86_400),
+    (SETTING_MAX_PEERS, 0, 1_000_000),
+    (SETTING_PRESENCE_ONLINE_SECS, 1, 604_800),
+    (SETTING_PRESENCE_STALE_SECS, 1, 604_800),
+];
+
+// Operator settings restored from the store on startup. Anything missing or
+// unreadable falls back to its default.
+#[derive(Debug, Clone, Default)]
+struct PersistedSettings {
+    delivery_policy: DeliveryPolicy,
+    stamp_policy: StampPolicy,
+    stamp_policy_overrides: HashMap<String, StampPolicy>,
+    inbound_overload_policy: InboundOverloadPolicy,
+}
+
+impl PersistedSettings {
+    fn load(store: &MessagesStore) -> Self {
+        fn read<T: serde::de::DeserializeOwned + Default>(store: &MessagesStore, key: &str) -> T {
+            store
+                .get_setting(key)
+                .ok()
+                .flatten()
+                .and_then(|value| serde_json::from_value(value).ok())
+                .unwrap_or_default()
+        }
+        Self {
+            delivery_policy: read(store, SETTING_DELIVERY_POLICY),
+            stamp_policy: read(store, SETTING_STAMP_POLICY),
+            stamp_policy_overrides: read(store, SETTING_STAMP_POLICY_OVERRIDES),
+            inbound_overload_policy: read(store, SETTING_INBOUND_OVERLOAD_POLICY),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboundAcceptance {
+    Stored,
+    // A piece of a chunked attachment, kept until the rest arrives.
+    Held,
+    Dropped,
+}
+
+#[derive(Debug, Default)]
+struct InboundLoad {
+    window_started_at: i64,
+    writes: u64,
+    dropped: u64,
+    total_dropped: u64,
+    overload_reported: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct TicketRecord {
     pub destination: String,
@@ -82,12 +349,55 @@ pub struct TicketRecord {
     pub expires_at: i64,
 }
 
+// Where a delivery stood at a trace transition, independent of the delivery
+// method's own status wording.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStage {
+    #[default]
+    Queued,
+    Sending,
+    PathRequested,
+    Link,
+    Opportunistic,
+    Propagated,
+    Receipt,
+    Failed,
+    Delivered,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct DeliveryTraceEntry {
     pub status: String,
     pub timestamp: i64,
     #[serde(default)]
     pub reason_code: Option<String>,
+    #[serde(default)]
+    pub stage: DeliveryStage,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AnnounceDetails {
+    pub destination_hash: String,
+    pub app_data_hex: Option<String>,
+    pub aspects: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RpcAuthToken {
+    pub label: String,
+    pub token: String,
+}
+
+// Tokens end up in config dumps and logs through Debug; only the label is
+// shown.
+impl std::fmt::Debug for RpcAuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcAuthToken")
+            .field("label", &self.label)
+            .field("token", &"<redacted>")
+            .finish()
+    }
 }
 
 pub struct RpcDaemon {
@@ -97,17 +407,57 @@ pub struct RpcDaemon {
     events: broadcast::Sender<RpcEvent>,
     event_queue: Mutex<VecDeque<RpcEvent>>,
     peers: Mutex<HashMap<String, PeerRecord>>,
+    max_peers: Mutex<usize>,
+    presence_thresholds: Mutex<PresenceThresholds>,
+    // Zero leaves the announce scheduler idle until an interval is set.
+    announce_interval: tokio::sync::watch::Sender<u64>,
+    peer_presence: Mutex<HashMap<String, &'static str>>,
+    pending_path_requests: Mutex<HashMap<String, i64>>,
     interfaces: Mutex<Vec<InterfaceRecord>>,
+    interface_connections: Mutex<HashMap<String, InterfaceConnection>>,
+    interface_bindings: Mutex<HashMap<String, InterfaceBinding>>,
     delivery_policy: Mutex<DeliveryPolicy>,
     propagation_state: Mutex<PropagationState>,
-    propagation_payloads: Mutex<HashMap<String, String>>,
+    propagation_sync_history: Mutex<VecDeque<PropagationSyncRecord>>,
+    propagation_sync_cancel: Mutex<Option<CancellationToken>>,
+    pending_telemetry_requests: Mutex<Vec<PendingTelemetryRequest>>,
+    store_backup_policy: Mutex<BackupPolicy>,
+    propagation_store_limits: Mutex<PropagationStoreLimits>,
+    received_resource_limits: Mutex<ReceivedResourceLimits>,
     outbound_propagation_node: Mutex<Option<String>>,
     paper_ingest_seen: Mutex<HashSet<String>>,
     stamp_policy: Mutex<StampPolicy>,
+    stamp_policy_overrides: Mutex<HashMap<String, StampPolicy>>,
+    announce_aspects: Mutex<Vec<AnnounceDetails>>,
+    inbound_overload_policy: Mutex<InboundOverloadPolicy>,
+    inbound_load: Mutex<InboundLoad>,
+    attachment_assemblies: Mutex<HashMap<String, ChunkAssembly>>,
+    outbound_chunk_transfers: Mutex<HashMap<String, OutboundChunkTransfer>>,
     ticket_cache: Mutex<HashMap<String, TicketRecord>>,
     delivery_traces: Mutex<HashMap<String, Vec<DeliveryTraceEntry>>>,
+    http_auth_tokens: Mutex<Vec<RpcAuthToken>>,
+    http_cors_origins: Mutex<Vec<String>>,
+    require_hash_addresses: Mutex<bool>,
+    require_signed_announces: Mutex<bool>,
+    accept_unaddressed_inbound: Mutex<bool>,
+    local_identity: Mutex<Option<PrivateIdentity>>,
+    sign_events: Mutex<bool>,
     outbound_bridge: Option<Arc<dyn OutboundBridge>>,
     announce_bridge: Option<Arc<dyn AnnounceBridge>>,
+    resource_bridge: Option<Arc<dyn ResourceBridge>>,
+    content_fetches: Mutex<VecDeque<String>>,
+    content_requests: Mutex<HashMap<String, VecDeque<i64>>>,
+    interface_filter_bridge: Option<Arc<dyn InterfaceFilterBridge>>,
+    link_bridge: Option<Arc<dyn LinkBridge>>,
+    plain_bridge: Option<Arc<dyn PlainBridge>>,
+    bandwidth_bridge: Option<Arc<dyn BandwidthBridge>>,
+    paper_bridge: Option<Arc<dyn PaperBridge>>,
+    lxmf_codec_bridge: Option<Arc<dyn LxmfCodecBridge>>,
+    destination_interface_bridge: Option<Arc<dyn DestinationInterfaceBridge>>,
+    config_bridge: Option<Arc<dyn ConfigBridge>>,
+    transport_control_bridge: Option<Arc<dyn TransportControlBridge>>,
+    transport_metrics_bridge: Option<Arc<dyn TransportMetricsBridge>>,
+    interface_traffic_bridge: Option<Arc<dyn InterfaceTrafficBridge>>,
 }
 
 pub trait OutboundBridge: Send + Sync {
@@ -116,16 +466,275 @@ pub trait OutboundBridge: Send + Sync {
         record: &MessageRecord,
         options: &OutboundDeliveryOptions,
     ) -> Result<(), std::io::Error>;
+
+    // Like deliver, but also returns the packed LXMF message handed to the
+    // transport so the exact bytes sent can be kept for export. None when the
+    // bridge does not pack messages itself.
+    fn deliver_packed(
+        &self,
+        record: &MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<Option<Vec<u8>>, std::io::Error> {
+        self.deliver(record, options).map(|()| None)
+    }
+
+    // Sends records in order, each once the one before it has gone out, so
+    // the pieces of one message share a link. Stops at the first failure.
+    fn deliver_sequence(
+        &self,
+        records: &[MessageRecord],
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        for record in records {
+            self.deliver(record, options)?;
+        }
+        Ok(())
+    }
+
+    // Seeds the bridge with an identity recalled from the store so delivery
+    // does not have to wait for the peer to announce again.
+    fn restore_peer_identity(
+        &self,
+        _destination: &str,
+        _identity: &Identity,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    // Deliveries handed to the bridge that have not finished yet.
+    fn pending_deliveries(&self) -> usize {
+        0
+    }
+
+    // Whether the transport currently knows how to reach destination, when
+    // that can be answered without waiting.
+    fn has_path(&self, _destination: &str) -> Option<bool> {
+        None
+    }
+
+    // Asks the network for a path to destination without waiting for it.
+    fn request_path(&self, _destination: &str) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "path requests are not supported",
+        ))
+    }
+
+    // Length of the wire message deliver would build for record, without
+    // sending anything. None when the bridge cannot tell.
+    fn wire_size(
+        &self,
+        _record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<Option<usize>, std::io::Error> {
+        Ok(None)
+    }
 }
 
+// Resolves once the delivery announce has been handed to the interfaces.
+pub type AnnounceFuture =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<(), std::io::Error>> + Send>>;
+
 pub trait AnnounceBridge: Send + Sync {
     fn announce_now(&self) -> Result<(), std::io::Error>;
+
+    // Like announce_now, but fails when the delivery announce went out on no
+    // interface.
+    fn announce_confirmed(&self) -> AnnounceFuture {
+        Box::pin(std::future::ready(self.announce_now()))
+    }
+
+    fn announce_details(&self) -> Option<AnnounceDetails> {
+        None
+    }
+
+    // Registers an extra destination that announce_now advertises alongside
+    // the delivery destination. Returns the destination hash.
+    fn add_announce_aspect(
+        &self,
+        _app: &str,
+        _aspects: &str,
+        _app_data: Option<Vec<u8>>,
+    ) -> Result<String, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "announce aspects are not supported",
+        ))
+    }
+
+    // Validates the name and swaps it into the delivery announce app data.
+    // Returns the name as it will be announced.
+    fn set_display_name(&self, _display_name: &str) -> Result<String, std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "display names are not supported",
+        ))
+    }
+}
+
+pub trait InterfaceFilterBridge: Send + Sync {
+    fn set_interface_filter(
+        &self,
+        interface: &str,
+        filter: Option<InterfaceFilter>,
+    ) -> Result<(), std::io::Error>;
+
+    fn interface_filter(
+        &self,
+        interface: &str,
+    ) -> Result<Option<InterfaceFilterStatus>, std::io::Error>;
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LinkInfo {
+    pub link_id: String,
+    pub destination: String,
+    pub direction: String,
+    pub established_at: Option<i64>,
+    pub last_activity: i64,
+    pub rtt_ms: u64,
+    // Rolling handshake RTT over recent links to the same destination.
+    #[serde(default)]
+    pub rtt_avg_ms: Option<u64>,
+    pub status: String,
+}
+
+pub trait LinkBridge: Send + Sync {
+    fn list_links(&self) -> Result<Vec<LinkInfo>, std::io::Error>;
+
+    fn close_link(&self, link_id: &str) -> Result<bool, std::io::Error>;
+
+    // Starts bringing up a link to destination without waiting for it. The
+    // outcome arrives as a link_established or link_failed event.
+    fn establish_link(&self, _destination: &str, _timeout: Duration) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "link establishment is not supported by this transport",
+        ))
+    }
+}
+
+pub trait BandwidthBridge: Send + Sync {
+    fn bandwidth_limit(&self) -> Option<u64>;
+
+    fn set_bandwidth_limit(&self, bytes_per_sec: Option<u64>) -> Result<(), std::io::Error>;
+
+    // Bulk packets dropped because too many were waiting on the limit.
+    fn paced_drops(&self) -> u64 {
+        0
+    }
+}
+
+pub trait DestinationInterfaceBridge: Send + Sync {
+    fn set_destination_interface(
+        &self,
+        destination: &str,
+        interface: &str,
+    ) -> Result<(), std::io::Error>;
+
+    fn clear_destination_interface(&self, destination: &str) -> Result<bool, std::io::Error>;
+}
+
+pub trait PaperBridge: Send + Sync {
+    // Decrypts and decodes a packed paper message; the error describes every
+    // decode attempt that was made.
+    fn decode_paper_message(&self, packed: &[u8]) -> Result<MessageRecord, String>;
+}
+
+// Converts between stored records and canonical LXMF wire messages, so
+// messages can move to and from reference LXMF tools.
+pub trait LxmfCodecBridge: Send + Sync {
+    // Packs a record the local delivery identity sent as an LXMF message,
+    // stamped with the record's timestamp. Used for sent messages whose
+    // original bytes were not kept.
+    fn encode_lxmf_message(&self, record: &MessageRecord) -> Result<Vec<u8>, String>;
+    // The error describes every decode attempt that was made.
+    fn decode_lxmf_message(&self, packed: &[u8]) -> Result<MessageRecord, String>;
+}
+
+pub enum ConfigSource {
+    Toml(String),
+    Json(JsonValue),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckedConfig {
+    pub interfaces: Vec<InterfaceRecord>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+pub trait ConfigBridge: Send + Sync {
+    // Parses a daemon config without applying it. Interface records are
+    // validated by the caller; the bridge reports problems with everything
+    // else, and a parse failure is returned as the error.
+    fn check_config(&self, source: ConfigSource) -> Result<CheckedConfig, String>;
+}
+
+pub trait TransportControlBridge: Send + Sync {
+    // Tears the transport's interfaces down and brings them back up from the
+    // current config. Returns the interfaces that will be running.
+    fn restart_transport(&self) -> Result<Vec<InterfaceRecord>, std::io::Error>;
+}
+
+pub trait TransportMetricsBridge: Send + Sync {
+    fn transport_metrics(&self) -> TransportMetrics;
+}
+
+pub trait InterfaceTrafficBridge: Send + Sync {
+    // Byte counters and the recent per-second samples of every running
+    // interface, keyed by interface name.
+    fn interface_traffic(&self) -> Vec<(String, TrafficHistory)>;
+
+    // Names of the interfaces the transport is running right now.
+    fn running_interfaces(&self) -> Vec<String> {
+        self.interface_traffic()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+pub trait PlainBridge: Send + Sync {
+    fn send_plain(&self, app: &str, aspect: &str, data: Vec<u8>) -> Result<(), std::io::Error>;
+
+    // Broadcasts `data` to a group destination, encrypted with `key`.
+    fn send_group(
+        &self,
+        _app: &str,
+        _aspect: &str,
+        _key: [u8; 16],
+        _data: Vec<u8>,
+    ) -> Result<(), std::io::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "group broadcasts are not supported by this transport",
+        ))
+    }
+}
+
+pub trait ResourceBridge: Send + Sync {
+    fn send_resource(
+        &self,
+        transfer_id: &str,
+        destination: &str,
+        data: Vec<u8>,
+        metadata: Option<Vec<u8>>,
+        path_timeout: Duration,
+    ) -> Result<(), std::io::Error>;
+
+    // Active, queued and maximum concurrent transfers, when known.
+    fn resource_counts(&self) -> Option<ResourceCounts> {
+        None
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Default)]
 pub struct OutboundDeliveryOptions {
     #[serde(default)]
     pub method: Option<String>,
+    // Cost of the stamp the bridge should attach; unused by bridges that do
+    // not stamp.
     #[serde(default)]
     pub stamp_cost: Option<u32>,
     #[serde(default)]
@@ -136,14 +745,90 @@ pub struct OutboundDeliveryOptions {
     pub ticket: Option<String>,
     #[serde(default)]
     pub source_private_key: Option<String>,
+    // Unix time after which delivery attempts stop.
+    #[serde(default)]
+    pub deliver_by: Option<i64>,
+    // Node whose interface gets a retried path request when no announce has
+    // been heard. Defaults to the selected outbound propagation node.
+    #[serde(default)]
+    pub identity_relay: Option<String>,
 }
 
+impl OutboundDeliveryOptions {
+    // None without a deadline; zero once it has passed.
+    pub fn time_left(&self) -> Option<Duration> {
+        let deliver_by = self.deliver_by?;
+        let now = now_i64();
+        Some(Duration::from_secs(
+            u64::try_from(deliver_by.saturating_sub(now)).unwrap_or(0),
+        ))
+    }
+
+    pub fn deadline_passed(&self) -> bool {
+        self.time_left().is_some_and(|left| left.is_zero())
+    }
+}
+
+pub const DELIVERY_DEADLINE_EXCEEDED: &str = "failed: deadline exceeded";
+// Resumed messages that were sent as another identity end up here.
+pub const SOURCE_KEY_UNAVAILABLE: &str = "failed: source key unavailable";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RpcEvent {
     pub event_type: String,
     pub payload: JsonValue,
 }
 
+// Envelope served on /events when event signing is enabled. `sig` is the hex
+// encoded lxmf_sign signature over event_signing_bytes(&event).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SignedRpcEvent {
+    pub event: RpcEvent,
+    pub sig: String,
+}
+
+// serde_json orders object keys, so a consumer that decodes the event and
+// serializes it again gets the same bytes back.
+pub fn event_signing_bytes(event: &RpcEvent) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(event)
+}
+
+pub fn verify_signed_event(identity: &Identity, signed: &SignedRpcEvent) -> bool {
+    let Ok(signature) = hex::decode(&signed.sig) else {
+        return false;
+    };
+    event_signing_bytes(&signed.event)
+        .map(|bytes| lxmf_verify(identity, &bytes, &signature))
+        .unwrap_or(false)
+}
+
+// RPC-injected announces are checked against the data a wire announce signs:
+// destination hash, public keys, name hash, random hash, the ratchet when one
+// was announced, then the app data. None when a field has the wrong length.
+pub fn announce_signing_bytes(
+    peer: &str,
+    identity: &Identity,
+    name_hash: &[u8],
+    random_hash: &[u8],
+    ratchet: Option<&[u8]>,
+    app_data: &[u8],
+) -> Option<Vec<u8>> {
+    let mut bytes = decode_hash_hex(peer).filter(|bytes| bytes.len() == ADDRESS_HASH_SIZE)?;
+    if name_hash.len() != NAME_HASH_LENGTH
+        || random_hash.len() != RAND_HASH_LENGTH
+        || ratchet.is_some_and(|ratchet| ratchet.len() != RATCHET_LENGTH)
+    {
+        return None;
+    }
+    bytes.extend_from_slice(identity.public_key_bytes());
+    bytes.extend_from_slice(identity.verifying_key_bytes());
+    bytes.extend_from_slice(name_hash);
+    bytes.extend_from_slice(random_hash);
+    bytes.extend_from_slice(ratchet.unwrap_or_default());
+    bytes.extend_from_slice(app_data);
+    Some(bytes)
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PeerRecord {
     pub peer: String,
@@ -156,6 +841,9 @@ pub struct PeerRecord {
     pub first_seen: i64,
     #[serde(default)]
     pub seen_count: u64,
+    // Interface the most recent announce from this peer arrived on.
+    #[serde(default)]
+    pub interface: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -169,6 +857,34 @@ struct SendMessageParams {
     fields: Option<JsonValue>,
     #[serde(default)]
     source_private_key: Option<String>,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    allow_logical_destination: bool,
+    #[serde(default)]
+    content_refs: Vec<String>,
+    #[serde(default)]
+    deliver_by: Option<i64>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendMulticastParams {
+    #[serde(default)]
+    group_id: Option<String>,
+    source: String,
+    destinations: Vec<String>,
+    #[serde(default)]
+    title: String,
+    content: String,
+    fields: Option<JsonValue>,
+    #[serde(default)]
+    source_private_key: Option<String>,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    allow_logical_destination: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -190,12 +906,56 @@ struct SendMessageV2Params {
     try_propagation_on_fail: Option<bool>,
     #[serde(default)]
     source_private_key: Option<String>,
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    allow_logical_destination: bool,
+    #[serde(default)]
+    content_refs: Vec<String>,
+    #[serde(default)]
+    deliver_by: Option<i64>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EditMessageParams {
+    message_id: String,
+    content: String,
+    #[serde(default)]
+    title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageIdParams {
+    message_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListDeletedSinceParams {
+    #[serde(default)]
+    since: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurgeDeletedParams {
+    before: i64,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct StampStatsParams {
+    #[serde(default)]
+    window_secs: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RecordReceiptParams {
     message_id: String,
     status: String,
+    // Structured failure reason from the transport; derived from status when
+    // absent.
+    #[serde(default)]
+    reason_code: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -220,6 +980,24 @@ struct AnnounceReceivedParams {
     stamp_cost_flexibility: Option<u32>,
     #[serde(default)]
     peering_cost: Option<u32>,
+    #[serde(default)]
+    stamp_cost: Option<u32>,
+    #[serde(default)]
+    hops: Option<u32>,
+    #[serde(default)]
+    interface: Option<String>,
+    // Public key of the announcing identity, for peers not seen before.
+    #[serde(default)]
+    identity_hex: Option<String>,
+    #[serde(default)]
+    signature_hex: Option<String>,
+    // Signed announce fields; the name hash defaults to lxmf.delivery.
+    #[serde(default)]
+    name_hash_hex: Option<String>,
+    #[serde(default)]
+    random_hash_hex: Option<String>,
+    #[serde(default)]
+    ratchet_hex: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -227,11 +1005,63 @@ struct SetInterfacesParams {
     interfaces: Vec<InterfaceRecord>,
 }
 
+#[derive(Debug, Deserialize)]
+struct ValidateConfigParams {
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    config: Option<JsonValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HashHexParams {
+    hash_hex: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LxmfAddressHashParams {
+    #[serde(default)]
+    hash_hex: Option<String>,
+    #[serde(default)]
+    identity_hash: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct PeerOpParams {
     peer: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct ClearParams {
+    scopes: Vec<String>,
+}
+
+// Subsystems the `clear` RPC can reset.
+const CLEAR_SCOPES: &[&str] = &[
+    "messages",
+    "announces",
+    "telemetry",
+    "peers",
+    "traces",
+    "tickets",
+    "known_identities",
+    "propagation",
+];
+
+#[derive(Debug, Deserialize)]
+struct ClearPeerAnnouncesParams {
+    peer: String,
+    #[serde(default)]
+    remove_peer: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct SettingParams {
+    key: String,
+    #[serde(default)]
+    value: JsonValue,
+}
+
 #[derive(Debug, Deserialize)]
 struct DeliveryPolicyParams {
     #[serde(default)]
@@ -263,45 +1093,323 @@ struct PropagationIngestParams {
     payload_hex: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct PropagationIngestBulkParams {
+    items: Vec<PropagationIngestParams>,
+}
+
 #[derive(Debug, Deserialize)]
 struct PropagationFetchParams {
     transient_id: String,
 }
 
+#[derive(Debug, Deserialize, Default)]
+struct PropagationFetchBatchParams {
+    #[serde(default)]
+    transient_ids: Option<Vec<String>>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperIngestUriParams {
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportMessageLxmfParams {
+    #[serde(default)]
+    lxmf_hex: Option<String>,
+    #[serde(default)]
+    lxmf_base64: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StampPolicySetParams {
+    #[serde(default)]
+    target_cost: Option<u32>,
+    #[serde(default)]
+    flexibility: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StampPolicyForParams {
+    destination: String,
+    #[serde(default)]
+    target_cost: Option<u32>,
+    #[serde(default)]
+    flexibility: Option<u32>,
+    // Drops the override so the global policy applies again.
+    #[serde(default)]
+    clear: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TicketGenerateParams {
+    destination: String,
+    #[serde(default)]
+    ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ListMessagesParams {
+    #[serde(default)]
+    direction: Option<String>,
+    #[serde(default)]
+    peer: Option<String>,
+    #[serde(default)]
+    enrich: bool,
+    #[serde(default)]
+    collapse_groups: bool,
+    // Only these columns are read and returned; None keeps the full record.
+    #[serde(default)]
+    fields_projection: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ListAnnouncesParams {
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    before_ts: Option<i64>,
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    interface: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct ListPeersParams {
+    #[serde(default)]
+    interface: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestPathParams {
+    destination: String,
+    #[serde(default)]
+    wait_ms: Option<u64>,
+}
+
+fn request_path_params(params: Option<JsonValue>) -> Result<RequestPathParams, std::io::Error> {
+    let mut params: RequestPathParams =
+        serde_json::from_value(params.unwrap_or(JsonValue::Null))
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    params.destination = params.destination.trim().to_ascii_lowercase();
+    Ok(params)
+}
+
+#[derive(Debug, Deserialize)]
+struct SetOutboundPropagationNodeParams {
+    #[serde(default)]
+    peer: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlternativePropagationRelayParams {
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoreContentParams {
+    data_base64: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentHashParams {
+    hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FetchContentParams {
+    hash: String,
+    peer: String,
+    #[serde(default)]
+    timeout_seconds: Option<u64>,
+}
+
+// Resource metadata used to pull referenced content from the peer that sent
+// it. Requests name the delivery destination the content should be sent back
+// to, since inbound links do not identify the requester.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "lxmf_content", rename_all = "snake_case")]
+enum ContentTransfer {
+    Request { hash: String, reply_to: String },
+    Response { hash: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct ResourceSendParams {
+    destination: String,
+    data_base64: String,
+    #[serde(default)]
+    metadata_base64: Option<String>,
+    #[serde(default)]
+    timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BackupStoreParams {
+    #[serde(default)]
+    keep: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestoreStoreParams {
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddAnnounceAspectParams {
+    app: String,
+    aspects: String,
+    #[serde(default)]
+    app_data_hex: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetDisplayNameParams {
+    display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendPlainParams {
+    #[serde(default)]
+    app: String,
+    #[serde(default)]
+    aspect: String,
+    data_base64: String,
+    #[serde(default)]
+    acknowledge_unencrypted: bool,
+    // Sends to a stored group instead, encrypting with its key.
+    #[serde(default)]
+    group: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateGroupParams {
+    name: String,
+    app: String,
+    aspect: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportGroupKeyParams {
+    name: String,
+    app: String,
+    aspect: String,
+    key_hex: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroupNameParams {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct InterfaceStatsHistoryParams {
+    #[serde(default)]
+    interface: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PropagationSyncHistoryParams {
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetBandwidthLimitParams {
+    #[serde(default)]
+    bytes_per_sec: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CloseLinkParams {
+    link_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EstablishLinkParams {
+    destination: String,
+    #[serde(default)]
+    timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StorePeerIdentityParams {
+    destination: String,
+    identity_hex: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecallIdentityParams {
+    destination: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkRestorePeerIdentitiesParams {
+    identities: Vec<StorePeerIdentityParams>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeriveDeliveryHashParams {
+    identity_hash: String,
+    #[serde(default)]
+    app: Option<String>,
+    #[serde(default)]
+    aspect: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetInterfaceFilterParams {
+    interface: String,
+    #[serde(default)]
+    drop_packet_types: Vec<String>,
+    #[serde(default)]
+    drop_destination_types: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
-struct PaperIngestUriParams {
-    uri: String,
+struct InterfaceFilterParams {
+    interface: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct StampPolicySetParams {
-    #[serde(default)]
-    target_cost: Option<u32>,
-    #[serde(default)]
-    flexibility: Option<u32>,
+struct SetDestinationInterfaceParams {
+    destination: String,
+    interface: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct TicketGenerateParams {
+struct DestinationInterfaceParams {
     destination: String,
-    #[serde(default)]
-    ttl_secs: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-struct ListAnnouncesParams {
+#[derive(Debug, Deserialize)]
+struct SendTelemetryRequestParams {
     #[serde(default)]
-    limit: Option<usize>,
+    id: Option<String>,
+    source: String,
+    destination: String,
     #[serde(default)]
-    before_ts: Option<i64>,
+    timebase: Option<i64>,
     #[serde(default)]
-    cursor: Option<String>,
+    allow_logical_destination: bool,
+    #[serde(default)]
+    try_propagation_on_fail: bool,
 }
 
 #[derive(Debug, Deserialize)]
-struct SetOutboundPropagationNodeParams {
+struct TelemetryHistoryParams {
+    peer: String,
     #[serde(default)]
-    peer: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    since: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -325,8 +1433,12 @@ fn merge_fields_with_options(
     method: Option<String>,
     stamp_cost: Option<u32>,
     include_ticket: Option<bool>,
+    deliver_by: Option<i64>,
 ) -> Option<JsonValue> {
-    let has_options = method.is_some() || stamp_cost.is_some() || include_ticket.is_some();
+    let has_options = method.is_some()
+        || stamp_cost.is_some()
+        || include_ticket.is_some()
+        || deliver_by.is_some();
     if !has_options {
         return fields;
     }
@@ -351,11 +1463,343 @@ fn merge_fields_with_options(
     if let Some(value) = include_ticket {
         lxmf.insert("include_ticket".into(), json!(value));
     }
+    if let Some(value) = deliver_by {
+        lxmf.insert("deliver_by".into(), json!(value));
+    }
 
     root.insert("_lxmf".into(), JsonValue::Object(lxmf));
     Some(JsonValue::Object(root))
 }
 
+// Messages older than this are not re-sent on startup; they are more likely
+// stale than wanted.
+pub const DEFAULT_OUTBOUND_RESUME_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+// Saved transport paths older than this are discarded on startup; a week
+// matches the path expiry of the Python implementation.
+pub const DEFAULT_PATH_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+pub const DEFAULT_PATH_PERSIST_INTERVAL_SECS: u64 = 5 * 60;
+
+// How often reticulumd re-evaluates peer presence; well under the online
+// threshold so a transition is reported within a minute.
+pub const DEFAULT_PRESENCE_SWEEP_INTERVAL_SECS: u64 = 60;
+
+// How often reticulumd applies the propagation store limits.
+pub const DEFAULT_PROPAGATION_PRUNE_INTERVAL_SECS: u64 = 60;
+
+// Upper bound for request_path wait_ms; the RPC blocks its caller while it
+// polls.
+pub const MAX_PATH_WAIT_MS: u64 = 30_000;
+const PATH_POLL_INTERVAL_MS: u64 = 50;
+// Requested paths that never arrive stop being reported after this long.
+const PATH_REQUEST_TTL_SECS: i64 = 300;
+
+// How long a resource transfer may wait for its link once a path is known.
+pub const RESOURCE_LINK_WAIT_SECS: u64 = 20;
+
+// Version of the RPC method and event shapes, reported in response meta.
+const RPC_CONTRACT_VERSION: &str = "v2";
+// Set by build.rs.
+const BUILD_GIT_SHA: &str = env!("RETICULUM_GIT_SHA");
+const BUILD_TIME: &str = env!("RETICULUM_BUILD_TIME");
+
+// Inbound chunked attachments being reassembled at once; the stalest is
+// dropped to make room for a new one.
+const MAX_PENDING_ATTACHMENT_TRANSFERS: usize = 16;
+
+// Destination, source and signature ahead of the packed payload, plus a few
+// bytes of msgpack framing. Used when no bridge can build the real message.
+const LXMF_WIRE_OVERHEAD: usize = 16 + 16 + 64 + 8;
+
+fn estimate_wire_size(record: &MessageRecord) -> usize {
+    let fields_len = record
+        .fields
+        .as_ref()
+        .map_or(0, |fields| fields.to_string().len());
+    LXMF_WIRE_OVERHEAD + record.title.len() + record.content.len() + fields_len
+}
+
+// Rough time the transport spends on each delivery already in flight. It
+// only feeds the ETA returned by send calls.
+const DELIVERY_ESTIMATE_MS: u64 = 500;
+
+fn estimate_send_delay_ms(in_flight: usize) -> u64 {
+    (in_flight as u64).saturating_mul(DELIVERY_ESTIMATE_MS)
+}
+
+// The stamp a packed LXMF message carries: a fifth payload element after the
+// destination, source and signature.
+fn wire_stamp(packed: &[u8]) -> Option<Vec<u8>> {
+    let payload = packed.get(16 + 16 + 64..)?;
+    let MsgPackValue::Array(items) = rmp_serde::from_slice::<MsgPackValue>(payload).ok()? else {
+        return None;
+    };
+    match items.into_iter().nth(4)? {
+        MsgPackValue::Binary(stamp) => Some(stamp),
+        _ => None,
+    }
+}
+
+// Peers tracked before the least recently seen ones are evicted, together
+// with their stored announces. Zero disables the cap.
+pub const DEFAULT_MAX_PEERS: usize = 10_000;
+
+// Rebuilds delivery options from the `_lxmf` block merge_fields_with_options
+// stores; options that are not persisted fall back to their defaults.
+fn outbound_options_from_fields(fields: Option<&JsonValue>) -> OutboundDeliveryOptions {
+    let lxmf = fields
+        .and_then(|fields| fields.get("_lxmf"))
+        .and_then(JsonValue::as_object);
+    OutboundDeliveryOptions {
+        method: lxmf
+            .and_then(|lxmf| lxmf.get("method"))
+            .and_then(JsonValue::as_str)
+            .map(ToOwned::to_owned),
+        stamp_cost: lxmf
+            .and_then(|lxmf| lxmf.get("stamp_cost"))
+            .and_then(JsonValue::as_u64)
+            .and_then(|value| u32::try_from(value).ok()),
+        include_ticket: lxmf
+            .and_then(|lxmf| lxmf.get("include_ticket"))
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(false),
+        deliver_by: lxmf
+            .and_then(|lxmf| lxmf.get("deliver_by"))
+            .and_then(JsonValue::as_i64),
+        ..Default::default()
+    }
+}
+
+const DEFAULT_CONTENT_TYPE: &str = "text/plain";
+const FIELD_RENDERER: &str = "15";
+const FIELD_CONTENT_REFS: &str = "content_refs";
+// Content requests answered per peer within CONTENT_REQUEST_WINDOW_SECS.
+const CONTENT_REQUESTS_PER_PEER: usize = 8;
+const CONTENT_REQUEST_WINDOW_SECS: i64 = 60;
+// Marks a message as a replacement for an earlier one from the same sender.
+const FIELD_EDIT: &str = "edit";
+// Ties together the per-recipient copies of one send_multicast message.
+const FIELD_GROUP_ID: &str = "group_id";
+
+// LXMF carries a renderer hint rather than a MIME type, so only content types
+// with a renderer equivalent make it onto the wire.
+const CONTENT_TYPE_RENDERERS: &[(&str, u64)] = &[
+    ("text/plain", 0x00),
+    ("text/micron", 0x01),
+    ("text/markdown", 0x02),
+    ("text/bbcode", 0x03),
+];
+
+fn with_group_id_field(fields: Option<JsonValue>, group_id: &str) -> Option<JsonValue> {
+    let mut root = match fields {
+        Some(JsonValue::Object(map)) => map,
+        Some(other) => {
+            let mut map = JsonMap::new();
+            map.insert("_fields_raw".into(), other);
+            map
+        }
+        None => JsonMap::new(),
+    };
+    root.insert(
+        FIELD_GROUP_ID.into(),
+        JsonValue::String(group_id.to_string()),
+    );
+    Some(JsonValue::Object(root))
+}
+
+// Folds the per-recipient copies of a multicast message into the newest one,
+// which gains the full recipient list. Messages outside a group pass through.
+fn collapse_message_groups(messages: Vec<JsonValue>) -> Vec<JsonValue> {
+    let mut collapsed: Vec<JsonValue> = Vec::with_capacity(messages.len());
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    for message in messages {
+        let Some(group_id) = message
+            .get("fields")
+            .and_then(|fields| fields.get(FIELD_GROUP_ID))
+            .and_then(JsonValue::as_str)
+            .map(ToOwned::to_owned)
+        else {
+            collapsed.push(message);
+            continue;
+        };
+        let destination = message.get("destination").cloned().unwrap_or_default();
+        if let Some(&index) = groups.get(&group_id) {
+            if let Some(recipients) = collapsed[index]
+                .get_mut("recipients")
+                .and_then(JsonValue::as_array_mut)
+            {
+                recipients.push(destination);
+            }
+            continue;
+        }
+        let mut message = message;
+        if let Some(object) = message.as_object_mut() {
+            object.insert("group_id".into(), JsonValue::String(group_id.clone()));
+            object.insert("recipients".into(), JsonValue::Array(vec![destination]));
+        }
+        groups.insert(group_id, collapsed.len());
+        collapsed.push(message);
+    }
+    collapsed
+}
+
+fn normalize_content_type(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty())
+}
+
+fn with_renderer_field(fields: Option<JsonValue>, content_type: &str) -> Option<JsonValue> {
+    let Some((_, renderer)) = CONTENT_TYPE_RENDERERS
+        .iter()
+        .find(|(mime, _)| *mime == content_type)
+    else {
+        return fields;
+    };
+    let mut map = match fields {
+        Some(JsonValue::Object(map)) => map,
+        None => JsonMap::new(),
+        other => return other,
+    };
+    map.entry(FIELD_RENDERER).or_insert_with(|| json!(renderer));
+    Some(JsonValue::Object(map))
+}
+
+fn content_type_from_fields(fields: Option<&JsonValue>) -> Option<String> {
+    let renderer = fields?.as_object()?.get(FIELD_RENDERER)?.as_u64()?;
+    CONTENT_TYPE_RENDERERS
+        .iter()
+        .find(|(_, value)| *value == renderer)
+        .map(|(mime, _)| (*mime).to_string())
+}
+
+// Known LXMF field ids besides those telemetry.rs defines. JSON field maps
+// key them by their decimal form.
+const FIELD_ICON_APPEARANCE: u8 = 4;
+const FIELD_APP_EXTENSIONS: u8 = 16;
+const FIELD_COLUMBA_META: u8 = 112;
+
+fn is_byte_array(value: &JsonValue) -> bool {
+    value.as_array().is_some_and(|items| {
+        items
+            .iter()
+            .all(|item| item.as_u64().is_some_and(|byte| byte <= u64::from(u8::MAX)))
+    })
+}
+
+fn is_bytes(value: &JsonValue) -> bool {
+    is_byte_array(value)
+        || value
+            .as_str()
+            .is_some_and(|text| hex::decode(text.trim()).is_ok())
+}
+
+// Checks the value shapes of the LXMF fields we know about so a malformed map
+// is rejected at send time instead of failing later on the wire. Unknown
+// fields pass through untouched.
+fn validate_known_fields(fields: Option<&JsonValue>) -> Result<(), std::io::Error> {
+    let Some(map) = fields.and_then(JsonValue::as_object) else {
+        return Ok(());
+    };
+    for (key, value) in map {
+        let Ok(id) = key.parse::<u8>() else {
+            continue;
+        };
+        let expected = match id {
+            telemetry::FIELD_TELEMETRY if !is_bytes(value) => "a byte array or hex string",
+            FIELD_ICON_APPEARANCE
+                if !value.as_array().is_some_and(|parts| {
+                    parts.len() == 3 && parts[0].is_string() && parts[1..].iter().all(is_bytes)
+                }) =>
+            {
+                "[icon_name, foreground, background]"
+            }
+            telemetry::FIELD_COMMANDS
+                if !value
+                    .as_array()
+                    .is_some_and(|commands| commands.iter().all(JsonValue::is_object)) =>
+            {
+                "an array of command maps"
+            }
+            FIELD_APP_EXTENSIONS if !value.is_object() => "a map",
+            FIELD_COLUMBA_META if !(value.is_object() || value.is_string()) => "a map or string",
+            _ => continue,
+        };
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("field {key} must be {expected}"),
+        ));
+    }
+    Ok(())
+}
+
+// Checks a set_setting value against the range RUNTIME_SETTINGS gives its
+// key.
+fn runtime_setting_value(key: &str, value: &JsonValue) -> Result<u64, String> {
+    let (_, min, max) = RUNTIME_SETTINGS
+        .iter()
+        .find(|(known, _, _)| *known == key)
+        .ok_or_else(|| format!("unknown setting {key:?}"))?;
+    value
+        .as_u64()
+        .filter(|value| (*min..=*max).contains(value))
+        .ok_or_else(|| format!("{key} must be an integer in {min}..={max}"))
+}
+
+// Drops the least recently seen peers until at most `max_peers` remain,
+// never evicting `keep`. Returns the evicted peer ids.
+fn evict_stale_peers(
+    peers: &mut HashMap<String, PeerRecord>,
+    max_peers: usize,
+    keep: Option<&str>,
+) -> Vec<String> {
+    if max_peers == 0 || peers.len() <= max_peers {
+        return Vec::new();
+    }
+    let mut candidates: Vec<(i64, String)> = peers
+        .values()
+        .filter(|record| Some(record.peer.as_str()) != keep)
+        .map(|record| (record.last_seen, record.peer.clone()))
+        .collect();
+    candidates.sort();
+    let excess = peers.len() - max_peers;
+    let evicted: Vec<String> = candidates
+        .into_iter()
+        .take(excess)
+        .map(|(_, peer)| peer)
+        .collect();
+    for peer in &evicted {
+        peers.remove(peer);
+    }
+    evicted
+}
+
+// Falls back to a short hash so clients always have something to display for
+// peers that have not announced a name.
+fn peer_display_alias(peer: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => name.to_string(),
+        None => peer.chars().take(8).collect(),
+    }
+}
+
+const RELAY_RECENCY_HALF_LIFE_SECS: f64 = 3600.0;
+// Announces that did not say how far away they are rank like a distant relay.
+const RELAY_UNKNOWN_HOPS: u32 = 8;
+const RELAY_COST_SCALE: f64 = 32.0;
+
+// Scores a propagation relay in 0..=1 from how recently it announced, how many
+// hops away it is and the stamp and peering costs it advertises.
+fn propagation_relay_score(announce: &AnnounceRecord, now: i64) -> f64 {
+    let age_secs = now.saturating_sub(announce.timestamp).max(0) as f64;
+    let recency = 0.5_f64.powf(age_secs / RELAY_RECENCY_HALF_LIFE_SECS);
+    let hops = announce.hops.unwrap_or(RELAY_UNKNOWN_HOPS) as f64;
+    let proximity = 1.0 / (1.0 + hops);
+    let cost = announce.stamp_cost.unwrap_or(0) as f64 + announce.peering_cost.unwrap_or(0) as f64;
+    let affordability = 1.0 / (1.0 + cost / RELAY_COST_SCALE);
+    0.5 * recency + 0.3 * proximity + 0.2 * affordability
+}
+
 fn now_i64() -> i64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -363,15 +1807,16 @@ fn now_i64() -> i64 {
         .unwrap_or(0)
 }
 
-fn first_n_chars(input: &str, n: usize) -> Option<String> {
-    if n == 0 {
-        return Some(String::new());
+// Paper URIs carry the packed message as unpadded URL-safe base64, but some
+// generators keep the padding or a trailing slash.
+fn decode_paper_uri_body(body: &str) -> Result<Vec<u8>, String> {
+    let body = body.trim().trim_end_matches('/').trim_end_matches('=');
+    if body.is_empty() {
+        return Err("paper URI has no message body".into());
     }
-    let end = input
-        .char_indices()
-        .nth(n - 1)
-        .map(|(idx, ch)| idx + ch.len_utf8())?;
-    Some(input[..end].to_string())
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(body)
+        .map_err(|err| format!("paper URI body is not base64url: {err}"))
 }
 
 fn clean_optional_text(value: Option<String>) -> Option<String> {
@@ -393,35 +1838,107 @@ fn normalize_capabilities(values: Vec<String>) -> Vec<String> {
     out
 }
 
-fn parse_capabilities_from_app_data_hex(app_data_hex: Option<&str>) -> Vec<String> {
-    let Some(raw_hex) = app_data_hex
+// Announce app-data comes in a few shapes depending on the sender: LXMF
+// delivery announces are `[display_name, stamp_cost, ...]`, propagation nodes
+// send `[legacy_flag | name, timebase, node_state, transfer_limit, sync_limit,
+// costs, metadata, ...]`, and some implementations send a keyed map. Fields are
+// only read from the layout they belong to, and extra trailing entries are
+// ignored so longer arrays from newer senders still parse.
+enum AppDataLayout<'a> {
+    Delivery(&'a [MsgPackValue]),
+    PropagationNode(&'a [MsgPackValue]),
+    Keyed(&'a [(MsgPackValue, MsgPackValue)]),
+    Unknown,
+}
+
+fn decode_app_data_hex(app_data_hex: Option<&str>) -> Option<MsgPackValue> {
+    let raw_hex = app_data_hex
         .map(str::trim)
-        .filter(|value| !value.is_empty())
-    else {
-        return Vec::new();
-    };
-    let Ok(app_data) = hex::decode(raw_hex) else {
-        return Vec::new();
-    };
+        .filter(|value| !value.is_empty())?;
+    let app_data = hex::decode(raw_hex).ok()?;
     if app_data.is_empty() {
-        return Vec::new();
+        return None;
     }
+    rmp_serde::from_slice::<MsgPackValue>(&app_data).ok()
+}
+
+fn classify_app_data(value: &MsgPackValue) -> AppDataLayout<'_> {
+    match value {
+        MsgPackValue::Array(entries) => {
+            let has_timebase = matches!(entries.get(1), Some(MsgPackValue::Integer(_)));
+            let has_node_state = entries.get(2).is_some_and(is_flag_value);
+            if has_timebase && has_node_state {
+                AppDataLayout::PropagationNode(entries)
+            } else {
+                AppDataLayout::Delivery(entries)
+            }
+        }
+        MsgPackValue::Map(entries) => AppDataLayout::Keyed(entries),
+        _ => AppDataLayout::Unknown,
+    }
+}
+
+fn is_flag_value(value: &MsgPackValue) -> bool {
+    match value {
+        MsgPackValue::Boolean(_) => true,
+        MsgPackValue::Integer(value) => matches!(value.as_u64(), Some(0 | 1)),
+        MsgPackValue::String(text) => text.as_str().is_some_and(|text| {
+            matches!(
+                text.trim().to_lowercase().as_str(),
+                "1" | "0" | "true" | "false" | "yes" | "no" | "on" | "off"
+            )
+        }),
+        _ => false,
+    }
+}
+
+fn keyed_app_data_value<'a>(
+    entries: &'a [(MsgPackValue, MsgPackValue)],
+    names: &[&str],
+) -> Option<&'a MsgPackValue> {
+    entries.iter().find_map(|(key, value)| {
+        msgpack_key_to_string(key)
+            .filter(|key| names.contains(&key.as_str()))
+            .map(|_| value)
+    })
+}
 
-    let Ok(value) = rmp_serde::from_slice::<MsgPackValue>(&app_data) else {
+fn parse_capabilities_from_app_data_hex(app_data_hex: Option<&str>) -> Vec<String> {
+    let Some(value) = decode_app_data_hex(app_data_hex) else {
         return Vec::new();
     };
     let mut capabilities = Vec::new();
-    if let Some(entries) = value.as_array() {
-        if entries.len() >= 3 && parse_bool_capability_flag(&entries[2]) {
-            capabilities.push("propagation".to_string());
+    match classify_app_data(&value) {
+        AppDataLayout::PropagationNode(entries) => {
+            if entries.get(2).is_some_and(parse_bool_capability_flag) {
+                capabilities.push("propagation".to_string());
+            }
+            capabilities.extend(
+                entries
+                    .iter()
+                    .filter_map(extract_capabilities_from_msgpack)
+                    .flatten(),
+            );
         }
-        for entry in entries {
-            if let Some(parsed) = extract_capabilities_from_msgpack(entry) {
+        AppDataLayout::Delivery(entries) => {
+            capabilities.extend(
+                entries
+                    .iter()
+                    .filter_map(extract_capabilities_from_msgpack)
+                    .flatten(),
+            );
+        }
+        AppDataLayout::Keyed(entries) => {
+            if keyed_app_data_value(entries, &["propagation", "propagation_node", "node_state"])
+                .is_some_and(parse_bool_capability_flag)
+            {
+                capabilities.push("propagation".to_string());
+            }
+            if let Some(parsed) = extract_capabilities_from_msgpack(&value) {
                 capabilities.extend(parsed);
             }
         }
-    } else if let Some(parsed) = extract_capabilities_from_msgpack(&value) {
-        capabilities.extend(parsed);
+        AppDataLayout::Unknown => {}
     }
 
     normalize_capabilities(capabilities)
@@ -491,50 +2008,31 @@ fn parse_fuzzy_u32(value: &MsgPackValue) -> Option<u32> {
     }
 }
 
+// Returns (stamp_cost, stamp_cost_flexibility, peering_cost).
 fn parse_announce_costs_from_app_data_hex(
     app_data_hex: Option<&str>,
-) -> (Option<u32>, Option<u32>) {
-    let Some(raw_hex) = app_data_hex
-        .map(str::trim)
-        .filter(|value| !value.is_empty())
-    else {
-        return (None, None);
-    };
-    let Ok(app_data) = hex::decode(raw_hex) else {
-        return (None, None);
+) -> (Option<u32>, Option<u32>, Option<u32>) {
+    let Some(value) = decode_app_data_hex(app_data_hex) else {
+        return (None, None, None);
     };
-    let Ok(value) = rmp_serde::from_slice::<MsgPackValue>(&app_data) else {
-        return (None, None);
+    let costs = match classify_app_data(&value) {
+        AppDataLayout::PropagationNode(entries) => entries.get(5),
+        AppDataLayout::Keyed(entries) => keyed_app_data_value(entries, &["costs"]).or(Some(&value)),
+        AppDataLayout::Delivery(_) | AppDataLayout::Unknown => None,
     };
-    let Some(entries) = value.as_array() else {
-        return (None, None);
-    };
-    let Some(costs) = entries.get(5) else {
-        return (None, None);
-    };
-    if let MsgPackValue::Array(values) = costs {
-        return (
+    match costs {
+        Some(MsgPackValue::Array(values)) => (
+            values.first().and_then(parse_fuzzy_u32),
             values.get(1).and_then(parse_fuzzy_u32),
             values.get(2).and_then(parse_fuzzy_u32),
-        );
-    }
-    let MsgPackValue::Map(entries) = costs else {
-        return (None, None);
-    };
-    let mut stamp_cost_flexibility = None;
-    let mut peering_cost = None;
-    for (key, value) in entries {
-        let Some(key) = msgpack_key_to_string(key) else {
-            continue;
-        };
-        if key == "stamp_cost_flexibility" {
-            stamp_cost_flexibility = parse_fuzzy_u32(value);
-        }
-        if key == "peering_cost" {
-            peering_cost = parse_fuzzy_u32(value);
-        }
+        ),
+        Some(MsgPackValue::Map(entries)) => (
+            keyed_app_data_value(entries, &["stamp_cost"]).and_then(parse_fuzzy_u32),
+            keyed_app_data_value(entries, &["stamp_cost_flexibility"]).and_then(parse_fuzzy_u32),
+            keyed_app_data_value(entries, &["peering_cost"]).and_then(parse_fuzzy_u32),
+        ),
+        _ => (None, None, None),
     }
-    (stamp_cost_flexibility, peering_cost)
 }
 
 fn extract_capabilities_from_msgpack(value: &MsgPackValue) -> Option<Vec<String>> {
@@ -580,6 +2078,365 @@ fn msgpack_key_to_string(key: &MsgPackValue) -> Option<String> {
     }
 }
 
+// "path_request" is accepted alongside the wire packet types since path
+// requests are plain data packets to a well-known destination.
+fn parse_interface_filter(
+    drop_packet_types: &[String],
+    drop_destination_types: &[String],
+) -> Result<InterfaceFilter, std::io::Error> {
+    let mut filter = InterfaceFilter::default();
+    for name in drop_packet_types {
+        let packet_type = match name.trim().to_ascii_lowercase().as_str() {
+            "path_request" => {
+                filter.drop_path_requests = true;
+                continue;
+            }
+            "data" => PacketType::Data,
+            "announce" => PacketType::Announce,
+            "link_request" => PacketType::LinkRequest,
+            "proof" => PacketType::Proof,
+            other => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("unknown packet type: {other}"),
+                ))
+            }
+        };
+        if !filter.drop_packet_types.contains(&packet_type) {
+            filter.drop_packet_types.push(packet_type);
+        }
+    }
+    for name in drop_destination_types {
+        let destination_type = match name.trim().to_ascii_lowercase().as_str() {
+            "single" => DestinationType::Single,
+            "group" => DestinationType::Group,
+            "plain" => DestinationType::Plain,
+            "link" => DestinationType::Link,
+            other => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("unknown destination type: {other}"),
+                ))
+            }
+        };
+        if !filter.drop_destination_types.contains(&destination_type) {
+            filter.drop_destination_types.push(destination_type);
+        }
+    }
+    Ok(filter)
+}
+
+fn interface_filter_json(interface: &str, status: Option<&InterfaceFilterStatus>) -> JsonValue {
+    let filter = status.map(|status| &status.filter);
+    let mut drop_packet_types: Vec<&str> = filter
+        .map(|filter| {
+            filter
+                .drop_packet_types
+                .iter()
+                .map(|packet_type| match packet_type {
+                    PacketType::Data => "data",
+                    PacketType::Announce => "announce",
+                    PacketType::LinkRequest => "link_request",
+                    PacketType::Proof => "proof",
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if filter.is_some_and(|filter| filter.drop_path_requests) {
+        drop_packet_types.push("path_request");
+    }
+    let drop_destination_types: Vec<&str> = filter
+        .map(|filter| {
+            filter
+                .drop_destination_types
+                .iter()
+                .map(|destination_type| match destination_type {
+                    DestinationType::Single => "single",
+                    DestinationType::Group => "group",
+                    DestinationType::Plain => "plain",
+                    DestinationType::Link => "link",
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({
+        "interface": interface,
+        "drop_packet_types": drop_packet_types,
+        "drop_destination_types": drop_destination_types,
+        "filtered_packets": status.map(|status| status.filtered_packets).unwrap_or(0),
+    })
+}
+
+fn plain_destination_hash(app: &str, aspect: &str) -> String {
+    PlainOutputDestination::new(EmptyIdentity {}, DestinationName::new(app, aspect))
+        .desc
+        .address_hash
+        .to_hex_string()
+}
+
+// Accepts either case and surrounding whitespace; None for anything that is
+// not an even-length hex string.
+fn decode_hash_hex(value: &str) -> Option<Vec<u8>> {
+    let value = value.trim();
+    if value.is_empty() || value.len() % 2 != 0 {
+        return None;
+    }
+    hex::decode(value).ok()
+}
+
+fn decode_hash_hex_exact<const N: usize>(
+    value: &str,
+    name: &str,
+) -> Result<[u8; N], std::io::Error> {
+    decode_hash_hex(value)
+        .and_then(|bytes| <[u8; N]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{name} must be a {N}-byte hex hash"),
+            )
+        })
+}
+
+fn full_hash_from_hex(value: &str, name: &str) -> Result<Hash, std::io::Error> {
+    decode_hash_hex_exact::<HASH_SIZE>(value, name).map(Hash::new)
+}
+
+fn address_hash_from_hex(value: &str, name: &str) -> Result<AddressHash, std::io::Error> {
+    decode_hash_hex_exact::<ADDRESS_HASH_SIZE>(value, name).map(AddressHash::new)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum InterfaceOptionKind {
+    Bool,
+    Seconds,
+}
+
+// Options understood per interface type. Options of types not listed here are
+// kept as given for whatever spawns them.
+const INTERFACE_OPTIONS: &[(&str, &[(&str, InterfaceOptionKind)])] = &[
+    (
+        "tcp_client",
+        &[
+            ("nodelay", InterfaceOptionKind::Bool),
+            ("reconnect_initial_secs", InterfaceOptionKind::Seconds),
+            ("reconnect_max_secs", InterfaceOptionKind::Seconds),
+        ],
+    ),
+    ("tcp_server", &[]),
+];
+
+fn interface_options_error(iface: &InterfaceRecord) -> Option<String> {
+    let (_, known) = INTERFACE_OPTIONS
+        .iter()
+        .find(|(kind, _)| *kind == iface.kind)?;
+    iface.options.iter().find_map(|(name, value)| {
+        let Some((_, option_kind)) = known.iter().find(|(known, _)| known == name) else {
+            return Some(format!("{} has no option \"{name}\"", iface.kind));
+        };
+        let valid = match option_kind {
+            InterfaceOptionKind::Bool => value.is_boolean(),
+            InterfaceOptionKind::Seconds => value.as_u64().is_some_and(|secs| secs > 0),
+        };
+        (!valid).then(|| match option_kind {
+            InterfaceOptionKind::Bool => format!("option \"{name}\" must be a boolean"),
+            InterfaceOptionKind::Seconds => {
+                format!("option \"{name}\" must be a positive number of seconds")
+            }
+        })
+    })
+}
+
+fn interface_record_error(iface: &InterfaceRecord) -> Option<String> {
+    if iface.kind.trim().is_empty() {
+        return Some("interface type is required".into());
+    }
+    if iface.kind == "tcp_client" && (iface.host.is_none() || iface.port.is_none()) {
+        return Some("tcp_client requires host and port".into());
+    }
+    if iface.kind == "tcp_server" && iface.port.is_none() {
+        return Some("tcp_server requires port".into());
+    }
+    // A listener binds a local address, so its host must be an IP literal such
+    // as 0.0.0.0 or :: rather than a name to resolve.
+    if let Some(host) = iface.host.as_deref().filter(|_| iface.kind == "tcp_server") {
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        if literal.parse::<std::net::IpAddr>().is_err() {
+            return Some(format!("tcp_server host \"{host}\" must be an IP address"));
+        }
+    }
+    interface_options_error(iface)
+}
+
+// Returns (errors, warnings) for a whole interface list: per-record problems
+// plus names or endpoints declared more than once.
+fn check_interface_records(interfaces: &[InterfaceRecord]) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut names: HashMap<&str, usize> = HashMap::new();
+    let mut endpoints: HashMap<(&str, &str, Option<u16>), usize> = HashMap::new();
+    for (index, iface) in interfaces.iter().enumerate() {
+        if let Some(err) = interface_record_error(iface) {
+            errors.push(format!("interfaces[{index}]: {err}"));
+        }
+        if let Some(name) = iface
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if let Some(first) = names.insert(name, index) {
+                errors.push(format!(
+                    "interfaces[{index}]: name \"{name}\" is already used by interfaces[{first}]"
+                ));
+            }
+        }
+        let endpoint = (
+            iface.kind.trim(),
+            iface.host.as_deref().map(str::trim).unwrap_or(""),
+            iface.port,
+        );
+        if iface.port.is_some() {
+            if let Some(first) = endpoints.insert(endpoint, index) {
+                errors.push(format!(
+                    "interfaces[{index}]: same endpoint as interfaces[{first}]"
+                ));
+            }
+        }
+        if !iface.enabled {
+            warnings.push(format!("interfaces[{index}]: interface is disabled"));
+        }
+    }
+    if !interfaces.is_empty() && interfaces.iter().all(|iface| !iface.enabled) {
+        warnings.push("no interfaces are enabled".into());
+    }
+    (errors, warnings)
+}
+
+fn validate_deliver_by(deliver_by: Option<i64>) -> Result<Option<i64>, std::io::Error> {
+    match deliver_by {
+        Some(deadline) if deadline <= now_i64() => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "deliver_by must be in the future",
+        )),
+        other => Ok(other),
+    }
+}
+
+fn group_json(record: &GroupKeyRecord) -> JsonValue {
+    json!({
+        "name": record.name,
+        "app": record.app,
+        "aspect": record.aspect,
+        "destination": record.destination,
+        "created_at": record.created_at,
+    })
+}
+
+// Group keys are sealed at rest with a key derived from the local identity,
+// so a copied store is useless without the identity file.
+const GROUP_KEY_SEAL_LABEL: &[u8] = b"reticulum-rs group key seal";
+
+fn group_seal_key(identity: &PrivateIdentity) -> [u8; 16] {
+    let digest = Sha256::digest(identity.sign(GROUP_KEY_SEAL_LABEL).to_bytes());
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&digest[..16]);
+    key
+}
+
+fn validate_plain_name(app: &str, aspect: &str) -> Result<(), std::io::Error> {
+    if app.is_empty() || app.contains('.') {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "app must be non-empty and must not contain dots",
+        ));
+    }
+    if aspect.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "aspect must not be empty",
+        ));
+    }
+    Ok(())
+}
+
+fn normalize_content_hash(value: &str) -> Option<String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    if normalized.len() == 64 && normalized.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        Some(normalized)
+    } else {
+        None
+    }
+}
+
+fn normalize_hash_hex(value: &str) -> Option<String> {
+    let normalized = value.trim().to_ascii_lowercase();
+    if normalized.len() == 32 && normalized.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        Some(normalized)
+    } else {
+        None
+    }
+}
+
+// Identity::new_from_hex_string panics on non-hex input, so check the shape
+// before handing it over.
+fn parse_identity_hex(value: &str) -> Option<Identity> {
+    let value = value.trim();
+    if value.len() != 128 || !value.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    Identity::new_from_hex_string(value).ok()
+}
+
+// Parses the hex private key a client may send as, so outbound messages can
+// be signed by an identity other than the daemon's own.
+pub fn parse_source_private_key(value: &str) -> Result<PrivateIdentity, std::io::Error> {
+    hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| PrivateIdentity::from_private_key_bytes(&bytes).ok())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid source_private_key (expected 64-byte hex private key)",
+            )
+        })
+}
+
+// A supplied private key decides the source: an empty source becomes its
+// delivery hash and any other source must match it.
+fn source_for_private_key(
+    source: String,
+    source_private_key: Option<&str>,
+) -> Result<String, std::io::Error> {
+    let Some(key) = source_private_key else {
+        return Ok(source);
+    };
+    let identity = parse_source_private_key(key)?;
+    let derived = lxmf_delivery_hash_from_identity_hash(identity.address_hash()).to_hex_string();
+    if source.trim().is_empty() || normalize_hash_hex(&source).as_deref() == Some(&derived) {
+        return Ok(derived);
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("source '{source}' does not match source_private_key ({derived})"),
+    ))
+}
+
+// The identity hex already is the concatenated public keys, so it doubles as
+// `public_key` for clients that expect the Python field names.
+fn known_identity_json(record: &KnownIdentityRecord) -> JsonValue {
+    let identity_hash = parse_identity_hex(&record.identity_hex)
+        .map(|identity| identity.address_hash.to_hex_string());
+    json!({
+        "identity_hash": identity_hash,
+        "delivery_destination_hash": record.destination,
+        "public_key": record.identity_hex,
+        "destination": record.destination,
+        "identity_hex": record.identity_hex,
+        "updated_at": record.updated_at,
+    })
+}
+
 fn encode_hex(bytes: impl AsRef<[u8]>) -> String {
     const HEX: &[u8; 16] = b"0123456789abcdef";
     let bytes = bytes.as_ref();
@@ -594,3 +2451,10 @@ fn encode_hex(bytes: impl AsRef<[u8]>) -> String {
 pub fn handle_framed_request(daemon: &RpcDaemon, bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
     daemon.handle_framed_request(bytes)
 }
+
+pub async fn handle_framed_request_async(
+    daemon: &RpcDaemon,
+    bytes: &[u8],
+) -> Result<Vec<u8>, std::io::Error> {
+    daemon.handle_framed_request_async(bytes).await
+}
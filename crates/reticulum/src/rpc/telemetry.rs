@@ -0,0 +1,139 @@
+use rmpv::Value as MsgPackValue;
+use serde_json::Value as JsonValue;
+
+pub const FIELD_TELEMETRY: u8 = 0x02;
+pub const FIELD_COMMANDS: u8 = 0x09;
+pub const COMMAND_TELEMETRY_REQUEST: u8 = 0x01;
+
+const SID_TIME: u8 = 0x01;
+const SID_LOCATION: u8 = 0x02;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocationTelemetry {
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: Option<f64>,
+    pub speed: Option<f64>,
+    pub bearing: Option<f64>,
+    pub accuracy: Option<f64>,
+    pub timestamp: Option<i64>,
+}
+
+// Sideband-compatible telemeter layout: a msgpack map keyed by sensor id, where
+// the location sensor carries big-endian fixed-point values packed as binaries.
+pub fn pack_location_telemetry(location: &LocationTelemetry) -> Vec<u8> {
+    let fixed_i32 = |value: f64, scale: f64| {
+        MsgPackValue::Binary(((value * scale).round() as i32).to_be_bytes().to_vec())
+    };
+    let timestamp = location.timestamp.unwrap_or(0);
+    let sensor = MsgPackValue::Array(vec![
+        fixed_i32(location.lat, 1e6),
+        fixed_i32(location.lon, 1e6),
+        fixed_i32(location.altitude.unwrap_or(0.0), 1e2),
+        MsgPackValue::Binary(
+            ((location.speed.unwrap_or(0.0) * 1e2).round().max(0.0) as u32)
+                .to_be_bytes()
+                .to_vec(),
+        ),
+        fixed_i32(location.bearing.unwrap_or(0.0), 1e2),
+        MsgPackValue::Binary(
+            ((location.accuracy.unwrap_or(0.0) * 1e2)
+                .round()
+                .clamp(0.0, f64::from(u16::MAX)) as u16)
+                .to_be_bytes()
+                .to_vec(),
+        ),
+        MsgPackValue::from(timestamp),
+    ]);
+    let packed = MsgPackValue::Map(vec![
+        (MsgPackValue::from(SID_TIME), MsgPackValue::from(timestamp)),
+        (MsgPackValue::from(SID_LOCATION), sensor),
+    ]);
+    rmp_serde::to_vec(&packed).unwrap_or_default()
+}
+
+pub fn unpack_location_telemetry(bytes: &[u8]) -> Option<LocationTelemetry> {
+    let value = rmp_serde::from_slice::<MsgPackValue>(bytes).ok()?;
+    let MsgPackValue::Map(entries) = value else {
+        return None;
+    };
+
+    let mut time = None;
+    let mut sensor = None;
+    for (key, value) in &entries {
+        match key.as_u64() {
+            Some(id) if id == u64::from(SID_TIME) => time = value.as_i64(),
+            Some(id) if id == u64::from(SID_LOCATION) => sensor = value.as_array(),
+            _ => {}
+        }
+    }
+
+    let sensor = sensor?;
+    if sensor.len() < 2 {
+        return None;
+    }
+    let lat = fixed_point(sensor.first()?, 1e6)?;
+    let lon = fixed_point(sensor.get(1)?, 1e6)?;
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return None;
+    }
+    let last_update = sensor
+        .get(6)
+        .and_then(MsgPackValue::as_i64)
+        .filter(|ts| *ts > 0);
+
+    Some(LocationTelemetry {
+        lat,
+        lon,
+        altitude: sensor.get(2).and_then(|value| fixed_point(value, 1e2)),
+        speed: sensor.get(3).and_then(|value| fixed_point(value, 1e2)),
+        bearing: sensor.get(4).and_then(|value| fixed_point(value, 1e2)),
+        accuracy: sensor.get(5).and_then(|value| fixed_point(value, 1e2)),
+        timestamp: time.filter(|ts| *ts > 0).or(last_update),
+    })
+}
+
+// Sideband asks peers for telemetry with a command entry keyed by the request
+// id whose argument is the timebase: only telemetry newer than it is wanted.
+pub fn telemetry_request_fields(timebase: i64) -> JsonValue {
+    let mut command = serde_json::Map::new();
+    command.insert(
+        COMMAND_TELEMETRY_REQUEST.to_string(),
+        JsonValue::from(timebase),
+    );
+    let mut fields = serde_json::Map::new();
+    fields.insert(
+        FIELD_COMMANDS.to_string(),
+        JsonValue::Array(vec![JsonValue::Object(command)]),
+    );
+    JsonValue::Object(fields)
+}
+
+pub fn location_from_fields(fields: Option<&JsonValue>) -> Option<LocationTelemetry> {
+    let fields = fields?.as_object()?;
+    let raw = fields.get(&FIELD_TELEMETRY.to_string())?;
+    let bytes = match raw {
+        JsonValue::Array(items) => items
+            .iter()
+            .map(|item| item.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>()?,
+        JsonValue::String(text) => hex::decode(text.trim()).ok()?,
+        _ => return None,
+    };
+    unpack_location_telemetry(&bytes)
+}
+
+fn fixed_point(value: &MsgPackValue, scale: f64) -> Option<f64> {
+    let raw = match value {
+        MsgPackValue::Binary(bytes) => match bytes.len() {
+            2 => f64::from(u16::from_be_bytes([bytes[0], bytes[1]])),
+            4 => f64::from(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            _ => return None,
+        },
+        MsgPackValue::Integer(int) => int.as_i64()? as f64,
+        MsgPackValue::F64(float) => return Some(*float),
+        MsgPackValue::F32(float) => return Some(f64::from(*float)),
+        _ => return None,
+    };
+    Some(raw / scale)
+}
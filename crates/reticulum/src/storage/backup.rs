@@ -0,0 +1,144 @@
+use rusqlite::{Connection, OpenFlags};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_BACKUP_KEEP: usize = 7;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupPolicy {
+    pub dir: Option<PathBuf>,
+    pub keep: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            keep: DEFAULT_BACKUP_KEEP,
+        }
+    }
+}
+
+impl BackupPolicy {
+    pub fn backup_dir(&self, store_path: &Path) -> PathBuf {
+        self.dir.clone().unwrap_or_else(|| {
+            store_path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join("backups")
+        })
+    }
+}
+
+fn store_stem(store_path: &Path) -> String {
+    store_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("store")
+        .to_string()
+}
+
+pub fn backup_file_name(store_path: &Path, created_at_ms: u64) -> String {
+    format!("{}-{}.db", store_stem(store_path), created_at_ms)
+}
+
+fn backup_timestamp(store_path: &Path, file_name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(&format!("{}-", store_stem(store_path)))?
+        .strip_suffix(".db")?
+        .parse()
+        .ok()
+}
+
+// Oldest first, so rotation can drop from the front.
+pub fn list_backups(dir: &Path, store_path: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| backup_timestamp(store_path, name));
+        if let Some(timestamp) = timestamp {
+            backups.push((timestamp, path));
+        }
+    }
+    backups.sort();
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+pub fn prune_backups(dir: &Path, store_path: &Path, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let backups = list_backups(dir, store_path)?;
+    let excess = backups.len().saturating_sub(keep);
+    let mut removed = Vec::with_capacity(excess);
+    for path in backups.into_iter().take(excess) {
+        fs::remove_file(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+pub fn pending_restore_path(store_path: &Path) -> PathBuf {
+    let mut name = store_path.as_os_str().to_owned();
+    name.push(".restore");
+    PathBuf::from(name)
+}
+
+// Copies a verified backup next to the store; it replaces the live database the
+// next time the store is opened through apply_pending_restore.
+pub fn stage_restore(store_path: &Path, backup: &Path) -> io::Result<PathBuf> {
+    let conn = Connection::open_with_flags(backup, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let check: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    if check != "ok" {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("backup failed integrity check: {check}"),
+        ));
+    }
+    drop(conn);
+
+    let staged = pending_restore_path(store_path);
+    fs::copy(backup, &staged)?;
+    Ok(staged)
+}
+
+// Must run before the store is opened. The replaced database is kept alongside
+// as `<store>.pre-restore`, with its write-ahead log checkpointed into it first
+// so the copy holds every committed write. Any log left over moves with it.
+pub fn apply_pending_restore(store_path: &Path) -> io::Result<bool> {
+    let staged = pending_restore_path(store_path);
+    if !staged.exists() {
+        return Ok(false);
+    }
+    let mut previous = store_path.as_os_str().to_owned();
+    previous.push(".pre-restore");
+    if store_path.exists() {
+        let conn = Connection::open(store_path).map_err(io::Error::other)?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(io::Error::other)?;
+        drop(conn);
+        fs::rename(store_path, PathBuf::from(&previous))?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = store_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let mut kept = previous.clone();
+        kept.push(suffix);
+        match fs::rename(PathBuf::from(sidecar), PathBuf::from(kept)) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+    }
+    fs::rename(&staged, store_path)?;
+    Ok(true)
+}
@@ -1,5 +1,32 @@
-use rusqlite::{params, Connection};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
 use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+// Proof-of-work accounting for one message: the stamp cost asked of it and,
+// when known, the value its stamp actually achieved.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MessageStampRecord {
+    pub message_id: String,
+    pub direction: String,
+    pub stamp_cost: Option<u32>,
+    pub stamp_value: Option<u32>,
+    pub under_stamped: bool,
+    pub recorded_at: i64,
+}
+
+// Totals over the stamp records in a window. Work is the expected number of
+// hashes, 2^bits per stamp, saturating at u64::MAX.
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize)]
+pub struct StampStats {
+    pub outbound_messages: u64,
+    pub outbound_total_cost: u64,
+    pub outbound_work: u64,
+    pub inbound_messages: u64,
+    pub inbound_under_stamped: u64,
+    pub inbound_work: u64,
+}
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct MessageRecord {
@@ -12,6 +39,10 @@ pub struct MessageRecord {
     pub direction: String,
     pub fields: Option<JsonValue>,
     pub receipt_status: Option<String>,
+    pub delivery_method: Option<String>,
+    pub content_type: Option<String>,
+    // Delivery attempts made so far; always 0 for inbound messages.
+    pub attempts: u32,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -31,6 +62,82 @@ pub struct AnnounceRecord {
     pub q: Option<f64>,
     pub stamp_cost_flexibility: Option<u32>,
     pub peering_cost: Option<u32>,
+    #[serde(default)]
+    pub stamp_cost: Option<u32>,
+    #[serde(default)]
+    pub hops: Option<u32>,
+    #[serde(default)]
+    pub public_key_hex: Option<String>,
+    // Name of the interface the announce arrived on, when known.
+    #[serde(default)]
+    pub interface: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TelemetryRecord {
+    pub peer: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub altitude: Option<f64>,
+    pub speed: Option<f64>,
+    pub bearing: Option<f64>,
+    pub accuracy: Option<f64>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DeliveryTraceRecord {
+    pub message_id: String,
+    pub status: String,
+    pub reason_code: Option<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct KnownIdentityRecord {
+    pub destination: String,
+    pub identity_hex: String,
+    pub updated_at: i64,
+}
+
+// What remains visible of a soft-deleted message until it is purged.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MessageTombstone {
+    pub id: String,
+    pub deleted_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct MessageVersionRecord {
+    pub message_id: String,
+    pub version: u32,
+    pub title: String,
+    pub content: String,
+    pub replaced_at: i64,
+}
+
+// `sealed_key` is the group key encrypted at rest; the store never sees the
+// plaintext key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupKeyRecord {
+    pub name: String,
+    pub app: String,
+    pub aspect: String,
+    pub destination: String,
+    pub sealed_key: Vec<u8>,
+    pub created_at: i64,
+}
+
+// A transport path kept across restarts. The interface is stored by name
+// because interface addresses are assigned anew on every start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRecord {
+    pub destination: String,
+    pub next_hop: String,
+    pub interface: String,
+    pub hops: u8,
+    pub learned_at: i64,
+    pub expires_at: i64,
 }
 
 pub struct MessagesStore {
@@ -52,13 +159,16 @@ impl MessagesStore {
         Ok(store)
     }
 
+    // Writing an existing id replaces the message but keeps its tombstone,
+    // edit time and packed bytes.
     pub fn insert_message(&self, record: &MessageRecord) -> rusqlite::Result<()> {
         let fields_json = record
             .fields
             .as_ref()
             .map(|value| serde_json::to_string(value).unwrap_or_default());
         self.conn.execute(
-            "INSERT OR REPLACE INTO messages (id, source, destination, title, content, timestamp, direction, fields, receipt_status) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            "INSERT INTO messages (id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(id) DO UPDATE SET source = excluded.source, destination = excluded.destination, title = excluded.title, content = excluded.content, timestamp = excluded.timestamp, direction = excluded.direction, fields = excluded.fields, receipt_status = excluded.receipt_status, delivery_method = excluded.delivery_method, content_type = excluded.content_type, attempts = excluded.attempts",
             params![
                 &record.id,
                 &record.source,
@@ -69,11 +179,27 @@ impl MessagesStore {
                 &record.direction,
                 fields_json,
                 &record.receipt_status,
+                &record.delivery_method,
+                &record.content_type,
+                record.attempts,
             ],
         )?;
         Ok(())
     }
 
+    // Returns the new count, or None when the message does not exist.
+    pub fn increment_message_attempts(&self, id: &str) -> rusqlite::Result<Option<u32>> {
+        self.conn
+            .query_row(
+                "UPDATE messages SET attempts = attempts + 1 WHERE id = ?1 RETURNING attempts",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    // Newest first; messages sharing a timestamp are tie-broken by id so
+    // repeated listings return the same order.
     pub fn list_messages(
         &self,
         limit: usize,
@@ -82,54 +208,419 @@ impl MessagesStore {
         let mut records = Vec::new();
         if let Some(ts) = before_ts {
             let mut stmt = self.conn.prepare(
-                "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status FROM messages WHERE timestamp < ?1 ORDER BY timestamp DESC LIMIT ?2",
+                "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts FROM messages WHERE deleted_at IS NULL AND timestamp < ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2",
             )?;
             let mut rows = stmt.query(params![ts, limit as i64])?;
             while let Some(row) = rows.next()? {
-                let fields_json: Option<String> = row.get(7)?;
-                let fields = fields_json
-                    .as_ref()
-                    .and_then(|value| serde_json::from_str(value).ok());
-                let receipt_status: Option<String> = row.get(8)?;
-                records.push(MessageRecord {
-                    id: row.get(0)?,
-                    source: row.get(1)?,
-                    destination: row.get(2)?,
-                    title: row.get(3)?,
-                    content: row.get(4)?,
-                    timestamp: row.get(5)?,
-                    direction: row.get(6)?,
-                    fields,
-                    receipt_status,
-                });
+                records.push(message_from_row(row)?);
             }
         } else {
             let mut stmt = self.conn.prepare(
-                "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status FROM messages ORDER BY timestamp DESC LIMIT ?1",
+                "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts FROM messages WHERE deleted_at IS NULL ORDER BY timestamp DESC, id DESC LIMIT ?1",
             )?;
             let mut rows = stmt.query(params![limit as i64])?;
             while let Some(row) = rows.next()? {
-                let fields_json: Option<String> = row.get(7)?;
-                let fields = fields_json
-                    .as_ref()
-                    .and_then(|value| serde_json::from_str(value).ok());
-                let receipt_status: Option<String> = row.get(8)?;
-                records.push(MessageRecord {
-                    id: row.get(0)?,
-                    source: row.get(1)?,
-                    destination: row.get(2)?,
-                    title: row.get(3)?,
-                    content: row.get(4)?,
-                    timestamp: row.get(5)?,
-                    direction: row.get(6)?,
-                    fields,
-                    receipt_status,
-                });
+                records.push(message_from_row(row)?);
             }
         }
         Ok(records)
     }
 
+    pub fn list_messages_filtered(
+        &self,
+        limit: usize,
+        direction: Option<&str>,
+        peer: Option<&str>,
+    ) -> rusqlite::Result<Vec<MessageRecord>> {
+        let (sql, mut values) = filtered_messages_query(
+            "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts FROM messages",
+            direction,
+            peer,
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{sql} ORDER BY timestamp DESC, id DESC LIMIT ?"))?;
+        values.push(Value::Integer(limit as i64));
+        let mut rows = stmt.query(params_from_iter(values))?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(message_from_row(row)?);
+        }
+        Ok(records)
+    }
+
+    // Like list_messages_filtered, but reads only the named columns so large
+    // content and fields blobs stay in the database when not asked for.
+    pub fn list_message_columns(
+        &self,
+        limit: usize,
+        direction: Option<&str>,
+        peer: Option<&str>,
+        columns: &[&str],
+    ) -> rusqlite::Result<Vec<serde_json::Map<String, JsonValue>>> {
+        if let Some(unknown) = columns
+            .iter()
+            .find(|column| !MESSAGE_COLUMNS.contains(column))
+        {
+            return Err(rusqlite::Error::InvalidColumnName((*unknown).to_string()));
+        }
+        let (sql, mut values) = filtered_messages_query(
+            &format!("SELECT {} FROM messages", columns.join(", ")),
+            direction,
+            peer,
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{sql} ORDER BY timestamp DESC, id DESC LIMIT ?"))?;
+        values.push(Value::Integer(limit as i64));
+        let mut rows = stmt.query(params_from_iter(values))?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(message_columns_from_row(row, columns)?);
+        }
+        Ok(records)
+    }
+
+    // Oldest first, so anything re-sent from this list goes out in the order
+    // it was composed.
+    pub fn list_outbound_since(&self, since_ts: i64) -> rusqlite::Result<Vec<MessageRecord>> {
+        let mut records = Vec::new();
+        self.for_each_outbound_since(since_ts, |record| records.push(record))?;
+        Ok(records)
+    }
+
+    // Streaming form of list_outbound_since for callers that keep only a few
+    // of the records.
+    pub fn for_each_outbound_since<F>(&self, since_ts: i64, mut visit: F) -> rusqlite::Result<usize>
+    where
+        F: FnMut(MessageRecord),
+    {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts FROM messages
+             WHERE direction = 'out' AND timestamp >= ?1 AND deleted_at IS NULL
+             ORDER BY timestamp ASC, id ASC",
+        )?;
+        let mut rows = stmt.query(params![since_ts])?;
+        let mut visited = 0;
+        while let Some(row) = rows.next()? {
+            visit(message_from_row(row)?);
+            visited += 1;
+        }
+        Ok(visited)
+    }
+
+    // Visits every message oldest first without collecting them, so bulk
+    // scans over large stores stay at one row in memory. Deleted messages are
+    // skipped like in every other listing. Returns the number of messages
+    // visited.
+    pub fn for_each_message<F>(&self, mut visit: F) -> rusqlite::Result<usize>
+    where
+        F: FnMut(MessageRecord),
+    {
+        let (sql, values) = filtered_messages_query(
+            "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts FROM messages",
+            None,
+            None,
+        );
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{sql} ORDER BY timestamp ASC, id ASC"))?;
+        let mut rows = stmt.query(params_from_iter(values))?;
+        let mut visited = 0;
+        while let Some(row) = rows.next()? {
+            visit(message_from_row(row)?);
+            visited += 1;
+        }
+        Ok(visited)
+    }
+
+    pub fn get_message(&self, id: &str) -> rusqlite::Result<Option<MessageRecord>> {
+        self.conn
+            .query_row(
+                "SELECT id, source, destination, title, content, timestamp, direction, fields, receipt_status, delivery_method, content_type, attempts FROM messages WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                message_from_row,
+            )
+            .optional()
+    }
+
+    // Keeps the LXMF bytes a message was imported from, so exporting it
+    // again returns the original signature and message hash.
+    pub fn set_message_packed(&self, id: &str, packed: &[u8]) -> rusqlite::Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE messages SET packed = ?2 WHERE id = ?1",
+            params![id, packed],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn message_packed(&self, id: &str) -> rusqlite::Result<Option<Vec<u8>>> {
+        self.conn
+            .query_row(
+                "SELECT packed FROM messages WHERE id = ?1 AND deleted_at IS NULL",
+                params![id],
+                |row| row.get::<_, Option<Vec<u8>>>(0),
+            )
+            .optional()
+            .map(Option::flatten)
+    }
+
+    // Moves the current title and content into message_versions and replaces
+    // them. Returns the number of the version that was archived, or None when
+    // the message does not exist.
+    pub fn edit_message(
+        &self,
+        id: &str,
+        title: &str,
+        content: &str,
+        edited_at: i64,
+    ) -> rusqlite::Result<Option<u32>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let current: Option<(String, String)> = tx
+            .query_row(
+                "SELECT title, content FROM messages WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((old_title, old_content)) = current else {
+            return Ok(None);
+        };
+        let version: u32 = tx.query_row(
+            "SELECT COALESCE(MAX(version), 0) + 1 FROM message_versions WHERE message_id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        tx.execute(
+            "INSERT INTO message_versions (message_id, version, title, content, replaced_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, version, old_title, old_content, edited_at],
+        )?;
+        tx.execute(
+            "UPDATE messages SET title = ?2, content = ?3, edited_at = ?4, packed = NULL WHERE id = ?1",
+            params![id, title, content, edited_at],
+        )?;
+        tx.commit()?;
+        Ok(Some(version))
+    }
+
+    // Oldest first.
+    pub fn list_message_versions(&self, id: &str) -> rusqlite::Result<Vec<MessageVersionRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id, version, title, content, replaced_at FROM message_versions WHERE message_id = ?1 ORDER BY version ASC",
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(MessageVersionRecord {
+                message_id: row.get(0)?,
+                version: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                replaced_at: row.get(4)?,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn message_deleted_at(&self, id: &str) -> rusqlite::Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT deleted_at FROM messages WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+    }
+
+    pub fn message_edited_at(&self, id: &str) -> rusqlite::Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT edited_at FROM messages WHERE id = ?1",
+                params![id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+    }
+
+    // Hides the message from listings but keeps its row as a tombstone so
+    // mirrors syncing from this store learn of the deletion. Returns false
+    // when there is no visible message with that id.
+    pub fn delete_message(&self, id: &str, deleted_at: i64) -> rusqlite::Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE messages SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![id, deleted_at],
+        )?;
+        Ok(changed > 0)
+    }
+
+    // Tombstones recorded at or after since_ts, oldest first. The bound is
+    // inclusive so a sync resuming from the last seen timestamp misses
+    // nothing deleted within that same second.
+    pub fn list_deleted_since(&self, since_ts: i64) -> rusqlite::Result<Vec<MessageTombstone>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, deleted_at FROM messages WHERE deleted_at >= ?1 ORDER BY deleted_at ASC, id ASC",
+        )?;
+        let mut rows = stmt.query(params![since_ts])?;
+        let mut tombstones = Vec::new();
+        while let Some(row) = rows.next()? {
+            tombstones.push(MessageTombstone {
+                id: row.get(0)?,
+                deleted_at: row.get(1)?,
+            });
+        }
+        Ok(tombstones)
+    }
+
+    // Removes tombstones older than before_ts for good, along with their
+    // edit history. Returns the number of messages purged.
+    pub fn purge_deleted(&self, before_ts: i64) -> rusqlite::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM message_versions WHERE message_id IN (SELECT id FROM messages WHERE deleted_at < ?1)",
+            params![before_ts],
+        )?;
+        tx.execute(
+            "DELETE FROM message_stamps WHERE message_id IN (SELECT id FROM messages WHERE deleted_at < ?1)",
+            params![before_ts],
+        )?;
+        tx.execute(
+            "DELETE FROM delivery_traces WHERE message_id IN (SELECT id FROM messages WHERE deleted_at < ?1)",
+            params![before_ts],
+        )?;
+        let purged = tx.execute(
+            "DELETE FROM messages WHERE deleted_at < ?1",
+            params![before_ts],
+        )?;
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    pub fn record_message_stamp(&self, record: &MessageStampRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO message_stamps (message_id, direction, stamp_cost, stamp_value, under_stamped, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                &record.message_id,
+                &record.direction,
+                record.stamp_cost,
+                record.stamp_value,
+                record.under_stamped,
+                record.recorded_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_message_stamp(
+        &self,
+        message_id: &str,
+    ) -> rusqlite::Result<Option<MessageStampRecord>> {
+        self.conn
+            .prepare_cached(
+                "SELECT message_id, direction, stamp_cost, stamp_value, under_stamped, recorded_at FROM message_stamps WHERE message_id = ?1",
+            )?
+            .query_row(params![message_id], |row| {
+                Ok(MessageStampRecord {
+                    message_id: row.get(0)?,
+                    direction: row.get(1)?,
+                    stamp_cost: row.get(2)?,
+                    stamp_value: row.get(3)?,
+                    under_stamped: row.get(4)?,
+                    recorded_at: row.get(5)?,
+                })
+            })
+            .optional()
+    }
+
+    // Outbound work counts the achieved stamp value when one was recorded and
+    // the requested cost otherwise.
+    pub fn stamp_stats(&self, since_ts: i64) -> rusqlite::Result<StampStats> {
+        let mut stmt = self.conn.prepare(
+            "SELECT direction, stamp_cost, stamp_value, under_stamped FROM message_stamps WHERE recorded_at >= ?1",
+        )?;
+        let mut rows = stmt.query(params![since_ts])?;
+        let work =
+            |bits: Option<u32>| bits.map_or(0, |bits| 1u64.checked_shl(bits).unwrap_or(u64::MAX));
+        let mut stats = StampStats::default();
+        while let Some(row) = rows.next()? {
+            let direction: String = row.get(0)?;
+            let stamp_cost: Option<u32> = row.get(1)?;
+            let stamp_value: Option<u32> = row.get(2)?;
+            let under_stamped: bool = row.get(3)?;
+            if direction == "out" {
+                stats.outbound_messages += 1;
+                stats.outbound_total_cost += u64::from(stamp_cost.unwrap_or(0));
+                stats.outbound_work = stats
+                    .outbound_work
+                    .saturating_add(work(stamp_value.or(stamp_cost)));
+            } else {
+                stats.inbound_messages += 1;
+                stats.inbound_under_stamped += u64::from(under_stamped);
+                stats.inbound_work = stats.inbound_work.saturating_add(work(stamp_value));
+            }
+        }
+        Ok(stats)
+    }
+
+    // Maps each of `peers` that has live messages to the latest message
+    // timestamp and the number of messages exchanged with it. Peers are
+    // looked up in batches through the source and destination indexes
+    // rather than by grouping the whole table.
+    pub fn peer_message_activity(
+        &self,
+        peers: &[&str],
+    ) -> rusqlite::Result<HashMap<String, (i64, u64)>> {
+        let mut activity = HashMap::new();
+        for batch in peers.chunks(PEER_ACTIVITY_BATCH) {
+            let placeholders = (1..=batch.len())
+                .map(|index| format!("?{index}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT peer, MAX(timestamp), COUNT(*) FROM (
+                    SELECT source AS peer, timestamp FROM messages
+                    WHERE source IN ({placeholders}) AND deleted_at IS NULL
+                    UNION ALL
+                    SELECT destination AS peer, timestamp FROM messages
+                    WHERE destination IN ({placeholders}) AND source != destination
+                        AND deleted_at IS NULL
+                 ) GROUP BY peer"
+            ))?;
+            let rows = stmt.query_map(params_from_iter(batch), |row| {
+                Ok((row.get::<_, String>(0)?, row.get(1)?, row.get::<_, i64>(2)?))
+            })?;
+            for row in rows {
+                let (peer, last_timestamp, count) = row?;
+                activity.insert(peer, (last_timestamp, count.max(0) as u64));
+            }
+        }
+        Ok(activity)
+    }
+
+    pub fn count_messages(&self) -> rusqlite::Result<u64> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count.max(0) as u64)
+    }
+
+    // Same filters as list_messages_filtered, so a page and its total agree.
+    pub fn count_messages_filtered(
+        &self,
+        direction: Option<&str>,
+        peer: Option<&str>,
+    ) -> rusqlite::Result<u64> {
+        let (sql, values) =
+            filtered_messages_query("SELECT COUNT(*) FROM messages", direction, peer);
+        let count: i64 = self
+            .conn
+            .query_row(&sql, params_from_iter(values), |row| row.get(0))?;
+        Ok(count.max(0) as u64)
+    }
+
     pub fn update_receipt_status(&self, message_id: &str, status: &str) -> rusqlite::Result<()> {
         self.conn.execute(
             "UPDATE messages SET receipt_status = ?1 WHERE id = ?2",
@@ -138,15 +629,25 @@ impl MessagesStore {
         Ok(())
     }
 
+    pub fn update_delivery_method(&self, message_id: &str, method: &str) -> rusqlite::Result<bool> {
+        let changed = self.conn.execute(
+            "UPDATE messages SET delivery_method = ?1 WHERE id = ?2 AND (delivery_method IS NULL OR delivery_method != ?1)",
+            params![method, message_id],
+        )?;
+        Ok(changed > 0)
+    }
+
     pub fn clear_messages(&self) -> rusqlite::Result<()> {
         self.conn.execute("DELETE FROM messages", [])?;
+        self.conn.execute("DELETE FROM message_versions", [])?;
+        self.conn.execute("DELETE FROM message_stamps", [])?;
         Ok(())
     }
 
     pub fn insert_announce(&self, record: &AnnounceRecord) -> rusqlite::Result<()> {
         let capabilities_json = serde_json::to_string(&record.capabilities).unwrap_or_default();
         self.conn.execute(
-            "INSERT OR REPLACE INTO announces (id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            "INSERT OR REPLACE INTO announces (id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost, stamp_cost, hops, public_key_hex, interface) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
             params![
                 &record.id,
                 &record.peer,
@@ -162,16 +663,22 @@ impl MessagesStore {
                 record.q,
                 record.stamp_cost_flexibility,
                 record.peering_cost,
+                record.stamp_cost,
+                record.hops,
+                &record.public_key_hex,
+                &record.interface,
             ],
         )?;
         Ok(())
     }
 
+    // interface restricts the page to announces heard on that interface.
     pub fn list_announces(
         &self,
         limit: usize,
         before_ts: Option<i64>,
         before_id: Option<&str>,
+        interface: Option<&str>,
     ) -> rusqlite::Result<Vec<AnnounceRecord>> {
         let mut records = Vec::new();
         let parse_row = |row: &rusqlite::Row| -> rusqlite::Result<AnnounceRecord> {
@@ -196,29 +703,33 @@ impl MessagesStore {
                 q: row.get(11)?,
                 stamp_cost_flexibility: row.get(12)?,
                 peering_cost: row.get(13)?,
+                stamp_cost: row.get(14)?,
+                hops: row.get(15)?,
+                public_key_hex: row.get(16)?,
+                interface: row.get(17)?,
             })
         };
         if let Some(ts) = before_ts {
-            let query_with_id = "SELECT id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost FROM announces WHERE (timestamp < ?1 OR (timestamp = ?1 AND id < ?2)) ORDER BY timestamp DESC, id DESC LIMIT ?3";
-            let query_without_id = "SELECT id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost FROM announces WHERE timestamp < ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2";
+            let query_with_id = "SELECT id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost, stamp_cost, hops, public_key_hex, interface FROM announces WHERE (timestamp < ?1 OR (timestamp = ?1 AND id < ?2)) AND (?4 IS NULL OR interface = ?4) ORDER BY timestamp DESC, id DESC LIMIT ?3";
+            let query_without_id = "SELECT id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost, stamp_cost, hops, public_key_hex, interface FROM announces WHERE timestamp < ?1 AND (?3 IS NULL OR interface = ?3) ORDER BY timestamp DESC, id DESC LIMIT ?2";
             if let Some(ann_id) = before_id {
                 let mut stmt = self.conn.prepare(query_with_id)?;
-                let mut rows = stmt.query(params![ts, ann_id, limit as i64])?;
+                let mut rows = stmt.query(params![ts, ann_id, limit as i64, interface])?;
                 while let Some(row) = rows.next()? {
                     records.push(parse_row(row)?);
                 }
             } else {
                 let mut stmt = self.conn.prepare(query_without_id)?;
-                let mut rows = stmt.query(params![ts, limit as i64])?;
+                let mut rows = stmt.query(params![ts, limit as i64, interface])?;
                 while let Some(row) = rows.next()? {
                     records.push(parse_row(row)?);
                 }
             }
         } else {
             let mut stmt = self.conn.prepare(
-                "SELECT id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost FROM announces ORDER BY timestamp DESC LIMIT ?1",
+                "SELECT id, peer, timestamp, name, name_source, first_seen, seen_count, app_data_hex, capabilities, rssi, snr, q, stamp_cost_flexibility, peering_cost, stamp_cost, hops, public_key_hex, interface FROM announces WHERE (?2 IS NULL OR interface = ?2) ORDER BY timestamp DESC LIMIT ?1",
             )?;
-            let mut rows = stmt.query(params![limit as i64])?;
+            let mut rows = stmt.query(params![limit as i64, interface])?;
             while let Some(row) = rows.next()? {
                 records.push(parse_row(row)?);
             }
@@ -226,87 +737,998 @@ impl MessagesStore {
         Ok(records)
     }
 
+    // Public key from the most recent announce for peer that carried one.
+    pub fn latest_announce_public_key(&self, peer: &str) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT public_key_hex FROM announces WHERE peer = ?1 AND public_key_hex IS NOT NULL ORDER BY timestamp DESC, id DESC LIMIT 1",
+                params![peer],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn latest_announce_timestamp(&self, peer: &str) -> rusqlite::Result<Option<i64>> {
+        self.conn.query_row(
+            "SELECT MAX(timestamp) FROM announces WHERE peer = ?1",
+            params![peer],
+            |row| row.get(0),
+        )
+    }
+
+    // Empties the tables behind each named scope in one transaction. Scopes
+    // without persisted state are ignored.
+    pub fn clear_scopes(&self, scopes: &[&str]) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for scope in scopes {
+            let tables: &[&str] = match *scope {
+                "messages" => &["messages", "message_versions", "message_stamps"],
+                "announces" => &["announces"],
+                "telemetry" => &["telemetry"],
+                "traces" => &["delivery_traces"],
+                "known_identities" => &["known_identities"],
+                "propagation" => &["propagation_store"],
+                _ => &[],
+            };
+            for table in tables {
+                tx.execute(&format!("DELETE FROM {table}"), [])?;
+            }
+        }
+        tx.commit()
+    }
+
     pub fn clear_announces(&self) -> rusqlite::Result<()> {
         self.conn.execute("DELETE FROM announces", [])?;
         Ok(())
     }
 
-    fn init_schema(&self) -> rusqlite::Result<()> {
-        self.conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS messages (
-                id TEXT PRIMARY KEY,
-                source TEXT NOT NULL,
-                destination TEXT NOT NULL,
-                title TEXT NOT NULL,
-                content TEXT NOT NULL,
-                timestamp INTEGER NOT NULL,
-                direction TEXT NOT NULL,
-                fields TEXT,
-                receipt_status TEXT
-            );
-            CREATE TABLE IF NOT EXISTS announces (
-                id TEXT PRIMARY KEY,
-                peer TEXT NOT NULL,
-                timestamp INTEGER NOT NULL,
-                name TEXT,
-                name_source TEXT,
-                first_seen INTEGER NOT NULL,
-                seen_count INTEGER NOT NULL,
-                app_data_hex TEXT,
-                capabilities TEXT,
-                rssi REAL,
-                snr REAL,
-                q REAL,
-                stamp_cost_flexibility INTEGER,
-                peering_cost INTEGER
-            );",
-        )?;
-        let _ = self
-            .conn
-            .execute("ALTER TABLE messages ADD COLUMN title TEXT", []);
-        let _ = self
-            .conn
-            .execute("UPDATE messages SET title = '' WHERE title IS NULL", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE messages ADD COLUMN fields TEXT", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE messages ADD COLUMN receipt_status TEXT", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN name TEXT", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN name_source TEXT", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN first_seen INTEGER", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN seen_count INTEGER", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN app_data_hex TEXT", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN capabilities TEXT", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN rssi REAL", []);
-        let _ = self
-            .conn
-            .execute("ALTER TABLE announces ADD COLUMN snr REAL", []);
-        let _ = self
+    pub fn clear_announces_for_peer(&self, peer: &str) -> rusqlite::Result<usize> {
+        self.conn
+            .execute("DELETE FROM announces WHERE peer = ?1", params![peer])
+    }
+
+    pub fn insert_telemetry(&self, record: &TelemetryRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO telemetry (peer, timestamp, lat, lon, altitude, speed, bearing, accuracy) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                &record.peer,
+                record.timestamp,
+                record.lat,
+                record.lon,
+                record.altitude,
+                record.speed,
+                record.bearing,
+                record.accuracy,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_telemetry(
+        &self,
+        peer: &str,
+        limit: usize,
+        since: Option<i64>,
+    ) -> rusqlite::Result<Vec<TelemetryRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT peer, timestamp, lat, lon, altitude, speed, bearing, accuracy FROM telemetry WHERE peer = ?1 AND timestamp >= ?2 ORDER BY timestamp DESC LIMIT ?3",
+        )?;
+        let mut rows = stmt.query(params![peer, since.unwrap_or(i64::MIN), limit as i64])?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(TelemetryRecord {
+                peer: row.get(0)?,
+                timestamp: row.get(1)?,
+                lat: row.get(2)?,
+                lon: row.get(3)?,
+                altitude: row.get(4)?,
+                speed: row.get(5)?,
+                bearing: row.get(6)?,
+                accuracy: row.get(7)?,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn clear_telemetry(&self) -> rusqlite::Result<()> {
+        self.conn.execute("DELETE FROM telemetry", [])?;
+        Ok(())
+    }
+
+    // Keeps only the newest `max_per_message` transitions for the message so the
+    // table stays bounded the same way the in-memory trace cache is. Traces for
+    // ids with no stored message are dropped; purging a message removes its
+    // traces, so nothing else would ever clean them up.
+    pub fn append_delivery_trace(
+        &self,
+        record: &DeliveryTraceRecord,
+        max_per_message: usize,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO delivery_traces (message_id, status, reason_code, timestamp) SELECT ?1, ?2, ?3, ?4 WHERE EXISTS (SELECT 1 FROM messages WHERE id = ?1)",
+            params![
+                &record.message_id,
+                &record.status,
+                &record.reason_code,
+                record.timestamp,
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM delivery_traces WHERE message_id = ?1 AND seq NOT IN (SELECT seq FROM delivery_traces WHERE message_id = ?1 ORDER BY seq DESC LIMIT ?2)",
+            params![&record.message_id, max_per_message as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_delivery_traces(
+        &self,
+        message_id: &str,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<DeliveryTraceRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT message_id, status, reason_code, timestamp FROM (SELECT seq, message_id, status, reason_code, timestamp FROM delivery_traces WHERE message_id = ?1 ORDER BY seq DESC LIMIT ?2) ORDER BY seq ASC",
+        )?;
+        let mut rows = stmt.query(params![message_id, limit as i64])?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(DeliveryTraceRecord {
+                message_id: row.get(0)?,
+                status: row.get(1)?,
+                reason_code: row.get(2)?,
+                timestamp: row.get(3)?,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn clear_delivery_traces(&self) -> rusqlite::Result<()> {
+        self.conn.execute("DELETE FROM delivery_traces", [])?;
+        Ok(())
+    }
+
+    pub fn upsert_known_identity(&self, record: &KnownIdentityRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO known_identities (destination, identity_hex, updated_at) VALUES (?1, ?2, ?3)",
+            params![&record.destination, &record.identity_hex, record.updated_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_known_identity(
+        &self,
+        destination: &str,
+    ) -> rusqlite::Result<Option<KnownIdentityRecord>> {
+        self.conn
+            .query_row(
+                "SELECT destination, identity_hex, updated_at FROM known_identities WHERE destination = ?1",
+                params![destination],
+                |row| {
+                    Ok(KnownIdentityRecord {
+                        destination: row.get(0)?,
+                        identity_hex: row.get(1)?,
+                        updated_at: row.get(2)?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn list_known_identities(&self) -> rusqlite::Result<Vec<KnownIdentityRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT destination, identity_hex, updated_at FROM known_identities ORDER BY updated_at DESC, destination ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(KnownIdentityRecord {
+                destination: row.get(0)?,
+                identity_hex: row.get(1)?,
+                updated_at: row.get(2)?,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn clear_known_identities(&self) -> rusqlite::Result<usize> {
+        self.conn.execute("DELETE FROM known_identities", [])
+    }
+
+    pub fn put_setting(
+        &self,
+        key: &str,
+        value: &JsonValue,
+        updated_at: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            params![key, value.to_string(), updated_at],
+        )?;
+        Ok(())
+    }
+
+    // Rows that no longer parse are treated as unset.
+    pub fn get_setting(&self, key: &str) -> rusqlite::Result<Option<JsonValue>> {
+        let value: Option<String> = self
             .conn
-            .execute("ALTER TABLE announces ADD COLUMN q REAL", []);
-        let _ = self.conn.execute(
-            "ALTER TABLE announces ADD COLUMN stamp_cost_flexibility INTEGER",
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+    }
+
+    pub fn clear_settings(&self) -> rusqlite::Result<usize> {
+        self.conn.execute("DELETE FROM settings", [])
+    }
+
+    // Content is keyed by its SHA-256 digest, so storing the same bytes twice
+    // keeps the original row and returns the same hash. Storing bytes that
+    // were only received unsolicited makes them ours and exempt from pruning.
+    pub fn put_content(&self, data: &[u8], created_at: i64) -> rusqlite::Result<String> {
+        let hash = hex::encode(Sha256::digest(data));
+        self.conn.execute(
+            "INSERT INTO contents (hash, data, size, created_at, received) VALUES (?1, ?2, ?3, ?4, 0)
+             ON CONFLICT (hash) DO UPDATE SET received = 0",
+            params![&hash, data, data.len() as i64, created_at],
+        )?;
+        Ok(hash)
+    }
+
+    // Keeps bytes a peer sent without being asked. These rows are the only
+    // ones prune_received_contents removes.
+    pub fn put_received_content(&self, data: &[u8], created_at: i64) -> rusqlite::Result<String> {
+        let hash = hex::encode(Sha256::digest(data));
+        self.conn.execute(
+            "INSERT OR IGNORE INTO contents (hash, data, size, created_at, received) VALUES (?1, ?2, ?3, ?4, 1)",
+            params![&hash, data, data.len() as i64, created_at],
+        )?;
+        Ok(hash)
+    }
+
+    // Drops received contents stored before `expire_before`, then the oldest
+    // ones until at most `max_items` rows and `max_bytes` bytes remain.
+    // Returns the number removed.
+    pub fn prune_received_contents(
+        &self,
+        expire_before: Option<i64>,
+        max_items: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> rusqlite::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut removed = 0;
+        if let Some(expire_before) = expire_before {
+            removed += tx.execute(
+                "DELETE FROM contents WHERE received = 1 AND created_at < ?1",
+                params![expire_before],
+            )?;
+        }
+        if let Some(max_items) = max_items {
+            removed += tx.execute(
+                "DELETE FROM contents WHERE hash IN (
+                    SELECT hash FROM contents WHERE received = 1
+                    ORDER BY created_at DESC, hash DESC LIMIT -1 OFFSET ?1
+                )",
+                params![max_items as i64],
+            )?;
+        }
+        if let Some(max_bytes) = max_bytes {
+            removed += tx.execute(
+                "DELETE FROM contents WHERE hash IN (
+                    SELECT hash FROM (
+                        SELECT hash, SUM(size) OVER (
+                            ORDER BY created_at DESC, hash DESC
+                        ) AS kept FROM contents WHERE received = 1
+                    ) WHERE kept > ?1
+                )",
+                params![max_bytes as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    pub fn get_content(&self, hash: &str) -> rusqlite::Result<Option<Vec<u8>>> {
+        self.conn
+            .query_row(
+                "SELECT data FROM contents WHERE hash = ?1",
+                params![hash],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    // True when an outbound message to `destination` carried the content as a
+    // content ref, i.e. that peer was offered the content.
+    pub fn content_offered_to(&self, hash: &str, destination: &str) -> rusqlite::Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS (
+                SELECT 1 FROM (
+                    SELECT fields FROM messages
+                    WHERE direction = 'out' AND lower(destination) = ?2
+                      AND deleted_at IS NULL AND json_valid(fields)
+                ) AS offered, json_each(offered.fields, '$.content_refs') AS content_ref
+                WHERE json_extract(content_ref.value, '$.hash') = ?1
+            )",
+            params![hash, destination.to_ascii_lowercase()],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn content_size(&self, hash: &str) -> rusqlite::Result<Option<u64>> {
+        self.conn
+            .query_row(
+                "SELECT size FROM contents WHERE hash = ?1",
+                params![hash],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map(|size| size.map(|size| size as u64))
+    }
+
+    pub fn put_group_key(&self, record: &GroupKeyRecord) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO group_keys (name, app, aspect, destination, sealed_key, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                record.name,
+                record.app,
+                record.aspect,
+                record.destination,
+                record.sealed_key,
+                record.created_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_group_key(&self, name: &str) -> rusqlite::Result<Option<GroupKeyRecord>> {
+        self.conn
+            .query_row(
+                "SELECT name, app, aspect, destination, sealed_key, created_at FROM group_keys WHERE name = ?1",
+                params![name],
+                group_key_from_row,
+            )
+            .optional()
+    }
+
+    pub fn list_group_keys(&self) -> rusqlite::Result<Vec<GroupKeyRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, app, aspect, destination, sealed_key, created_at FROM group_keys ORDER BY name ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(group_key_from_row(row)?);
+        }
+        Ok(records)
+    }
+
+    // Replaces the saved path table with `paths` in one transaction.
+    pub fn replace_paths(&self, paths: &[PathRecord]) -> rusqlite::Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM paths", [])?;
+        for path in paths {
+            tx.execute(
+                "INSERT OR REPLACE INTO paths (destination, next_hop, interface, hops, learned_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    path.destination,
+                    path.next_hop,
+                    path.interface,
+                    path.hops,
+                    path.learned_at,
+                    path.expires_at
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    // Drops paths that expired by `now` and returns the rest.
+    pub fn load_paths(&self, now: i64) -> rusqlite::Result<Vec<PathRecord>> {
+        self.conn
+            .execute("DELETE FROM paths WHERE expires_at <= ?1", params![now])?;
+        let mut stmt = self.conn.prepare(
+            "SELECT destination, next_hop, interface, hops, learned_at, expires_at FROM paths ORDER BY destination ASC",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut records = Vec::new();
+        while let Some(row) = rows.next()? {
+            records.push(PathRecord {
+                destination: row.get(0)?,
+                next_hop: row.get(1)?,
+                interface: row.get(2)?,
+                hops: row.get(3)?,
+                learned_at: row.get(4)?,
+                expires_at: row.get(5)?,
+            });
+        }
+        Ok(records)
+    }
+
+    // Stores a propagated payload under its transient id. With `replace` a
+    // payload already held is overwritten; otherwise it is kept. Returns
+    // whether a row was written.
+    pub fn put_propagation_payload(
+        &self,
+        transient_id: &str,
+        payload_hex: &str,
+        ingested_at: i64,
+        replace: bool,
+    ) -> rusqlite::Result<bool> {
+        let verb = if replace {
+            "INSERT OR REPLACE"
+        } else {
+            "INSERT OR IGNORE"
+        };
+        let changed = self.conn.execute(
+            &format!(
+                "{verb} INTO propagation_store (transient_id, payload_hex, size, ingested_at) VALUES (?1, ?2, ?3, ?4)"
+            ),
+            params![
+                transient_id,
+                payload_hex,
+                (payload_hex.len() / 2) as i64,
+                ingested_at
+            ],
+        )?;
+        Ok(changed > 0)
+    }
+
+    // Stores a batch of (transient_id, payload_hex) pairs in one transaction,
+    // keeping payloads already held. Returns whether each row was written.
+    pub fn put_propagation_payloads(
+        &self,
+        items: &[(String, String)],
+        ingested_at: i64,
+    ) -> rusqlite::Result<Vec<bool>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stored = Vec::with_capacity(items.len());
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO propagation_store (transient_id, payload_hex, size, ingested_at) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (transient_id, payload_hex) in items {
+                let changed = stmt.execute(params![
+                    transient_id,
+                    payload_hex,
+                    (payload_hex.len() / 2) as i64,
+                    ingested_at
+                ])?;
+                stored.push(changed > 0);
+            }
+        }
+        tx.commit()?;
+        Ok(stored)
+    }
+
+    pub fn get_propagation_payload(&self, transient_id: &str) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT payload_hex FROM propagation_store WHERE transient_id = ?1",
+                params![transient_id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    // Payloads in transient_id order, starting after `after`.
+    pub fn list_propagation_payloads(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT transient_id, payload_hex FROM propagation_store WHERE ?1 IS NULL OR transient_id > ?1 ORDER BY transient_id ASC LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![after, limit as i64], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        rows.collect()
+    }
+
+    // Returns (payload count, total payload bytes).
+    pub fn propagation_store_usage(&self) -> rusqlite::Result<(u64, u64)> {
+        self.conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM propagation_store",
             [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    // Drops payloads ingested before `expire_before`, then the oldest ones
+    // until at most `max_messages` payloads and `max_bytes` bytes remain.
+    // Returns the number removed.
+    pub fn prune_propagation_store(
+        &self,
+        expire_before: Option<i64>,
+        max_messages: Option<u64>,
+        max_bytes: Option<u64>,
+    ) -> rusqlite::Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut removed = 0;
+        if let Some(expire_before) = expire_before {
+            removed += tx.execute(
+                "DELETE FROM propagation_store WHERE ingested_at < ?1",
+                params![expire_before],
+            )?;
+        }
+        if let Some(max_messages) = max_messages {
+            removed += tx.execute(
+                "DELETE FROM propagation_store WHERE transient_id IN (
+                    SELECT transient_id FROM propagation_store
+                    ORDER BY ingested_at DESC, transient_id DESC LIMIT -1 OFFSET ?1
+                )",
+                params![max_messages as i64],
+            )?;
+        }
+        if let Some(max_bytes) = max_bytes {
+            removed += tx.execute(
+                "DELETE FROM propagation_store WHERE transient_id IN (
+                    SELECT transient_id FROM (
+                        SELECT transient_id, SUM(size) OVER (
+                            ORDER BY ingested_at DESC, transient_id DESC
+                        ) AS kept FROM propagation_store
+                    ) WHERE kept > ?1
+                )",
+                params![max_bytes as i64],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    // Applies every migration newer than the recorded schema version, each in
+    // its own transaction together with the version bump.
+    fn init_schema(&self) -> rusqlite::Result<()> {
+        self.conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);",
+        )?;
+        let mut version = self.schema_version()?;
+        for migration in MIGRATIONS.iter().skip(version as usize) {
+            let tx = self.conn.unchecked_transaction()?;
+            migration(&tx)?;
+            version += 1;
+            tx.execute("DELETE FROM schema_version", [])?;
+            tx.execute(
+                "INSERT INTO schema_version (version) VALUES (?1)",
+                params![version],
+            )?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    // None for in-memory stores, which have nothing on disk to back up.
+    pub fn path(&self) -> Option<std::path::PathBuf> {
+        self.conn
+            .path()
+            .filter(|path| !path.is_empty())
+            .map(std::path::PathBuf::from)
+    }
+
+    // Uses the SQLite online backup API so the copy is consistent even while
+    // the store is being written to.
+    pub fn backup_to(&self, destination: &std::path::Path) -> rusqlite::Result<()> {
+        self.conn
+            .backup(rusqlite::DatabaseName::Main, destination, None)
+    }
+
+    pub fn schema_version(&self) -> rusqlite::Result<u32> {
+        let version: Option<u32> =
+            self.conn
+                .query_row("SELECT MAX(version) FROM schema_version", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(version.unwrap_or(0))
+    }
+}
+
+fn message_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MessageRecord> {
+    let fields_json: Option<String> = row.get(7)?;
+    Ok(MessageRecord {
+        id: row.get(0)?,
+        source: row.get(1)?,
+        destination: row.get(2)?,
+        title: row.get(3)?,
+        content: row.get(4)?,
+        timestamp: row.get(5)?,
+        direction: row.get(6)?,
+        fields: fields_json
+            .as_ref()
+            .and_then(|value| serde_json::from_str(value).ok()),
+        receipt_status: row.get(8)?,
+        delivery_method: row.get(9)?,
+        content_type: row.get(10)?,
+        attempts: row.get(11)?,
+    })
+}
+
+fn group_key_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<GroupKeyRecord> {
+    Ok(GroupKeyRecord {
+        name: row.get(0)?,
+        app: row.get(1)?,
+        aspect: row.get(2)?,
+        destination: row.get(3)?,
+        sealed_key: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+// Appends only the filters that are set, so SQLite can pick the direction or
+// peer indexes; `?1 IS NULL OR direction = ?1` would force a scan.
+fn filtered_messages_query(
+    select: &str,
+    direction: Option<&str>,
+    peer: Option<&str>,
+) -> (String, Vec<Value>) {
+    let mut sql = format!("{select} WHERE deleted_at IS NULL");
+    let mut values = Vec::new();
+    if let Some(direction) = direction {
+        sql.push_str(" AND direction = ?");
+        values.push(Value::Text(direction.to_string()));
+    }
+    if let Some(peer) = peer {
+        sql.push_str(" AND (source = ? OR destination = ?)");
+        values.push(Value::Text(peer.to_string()));
+        values.push(Value::Text(peer.to_string()));
+    }
+    (sql, values)
+}
+
+// Columns list_message_columns can project; the order matches the full
+// record selects.
+pub const MESSAGE_COLUMNS: &[&str] = &[
+    "id",
+    "source",
+    "destination",
+    "title",
+    "content",
+    "timestamp",
+    "direction",
+    "fields",
+    "receipt_status",
+    "delivery_method",
+    "content_type",
+    "attempts",
+];
+
+// Builds one JSON object per row from whichever columns were selected.
+// The fields column holds JSON text and is decoded like a full record's.
+fn message_columns_from_row(
+    row: &rusqlite::Row<'_>,
+    columns: &[&str],
+) -> rusqlite::Result<serde_json::Map<String, JsonValue>> {
+    let mut object = serde_json::Map::with_capacity(columns.len());
+    for (index, column) in columns.iter().enumerate() {
+        let value = match row.get::<_, Value>(index)? {
+            Value::Null => JsonValue::Null,
+            Value::Integer(number) => JsonValue::from(number),
+            Value::Real(number) => JsonValue::from(number),
+            Value::Text(text) if *column == "fields" => {
+                serde_json::from_str(&text).unwrap_or(JsonValue::Null)
+            }
+            Value::Text(text) => JsonValue::String(text),
+            Value::Blob(bytes) => JsonValue::from(bytes),
+        };
+        object.insert((*column).to_string(), value);
+    }
+    Ok(object)
+}
+
+type Migration = fn(&Connection) -> rusqlite::Result<()>;
+
+// Append new schema changes here; entries must never be reordered or edited
+// once released, since the recorded version is an index into this list.
+const MIGRATIONS: &[Migration] = &[
+    migrate_baseline,
+    migrate_announce_stamp_cost_and_hops,
+    migrate_announce_peer_index,
+    migrate_content_store,
+    migrate_settings,
+    migrate_message_versions,
+    migrate_group_keys,
+    migrate_paths,
+    migrate_announce_public_key,
+    migrate_message_tombstones,
+    migrate_message_stamps,
+    migrate_announce_interface,
+    migrate_message_attempts,
+    migrate_message_listing_indexes,
+    migrate_propagation_store,
+    migrate_received_contents,
+    migrate_message_packed,
+];
+
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// Peers per peer_message_activity query, well under SQLite's parameter limit.
+const PEER_ACTIVITY_BATCH: usize = 500;
+
+// Brings databases created before schema versioning up to the current shape.
+// Those files may be at any earlier layout, so column additions tolerate
+// columns that already exist.
+fn migrate_baseline(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS messages (
+            id TEXT PRIMARY KEY,
+            source TEXT NOT NULL,
+            destination TEXT NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            direction TEXT NOT NULL,
+            fields TEXT,
+            receipt_status TEXT,
+            delivery_method TEXT,
+            content_type TEXT
         );
-        let _ = self
+        CREATE TABLE IF NOT EXISTS announces (
+            id TEXT PRIMARY KEY,
+            peer TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            name TEXT,
+            name_source TEXT,
+            first_seen INTEGER NOT NULL,
+            seen_count INTEGER NOT NULL,
+            app_data_hex TEXT,
+            capabilities TEXT,
+            rssi REAL,
+            snr REAL,
+            q REAL,
+            stamp_cost_flexibility INTEGER,
+            peering_cost INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS telemetry (
+            peer TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            lat REAL NOT NULL,
+            lon REAL NOT NULL,
+            altitude REAL,
+            speed REAL,
+            bearing REAL,
+            accuracy REAL,
+            PRIMARY KEY (peer, timestamp)
+        );
+        CREATE TABLE IF NOT EXISTS delivery_traces (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            message_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            reason_code TEXT,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS known_identities (
+            destination TEXT PRIMARY KEY,
+            identity_hex TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );",
+    )?;
+    let _ = conn.execute("ALTER TABLE messages ADD COLUMN title TEXT", []);
+    let _ = conn.execute("UPDATE messages SET title = '' WHERE title IS NULL", []);
+    let _ = conn.execute("ALTER TABLE messages ADD COLUMN fields TEXT", []);
+    let _ = conn.execute("ALTER TABLE messages ADD COLUMN receipt_status TEXT", []);
+    let _ = conn.execute("ALTER TABLE messages ADD COLUMN delivery_method TEXT", []);
+    let _ = conn.execute("ALTER TABLE messages ADD COLUMN content_type TEXT", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN name TEXT", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN name_source TEXT", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN first_seen INTEGER", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN seen_count INTEGER", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN app_data_hex TEXT", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN capabilities TEXT", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN rssi REAL", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN snr REAL", []);
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN q REAL", []);
+    let _ = conn.execute(
+        "ALTER TABLE announces ADD COLUMN stamp_cost_flexibility INTEGER",
+        [],
+    );
+    let _ = conn.execute("ALTER TABLE announces ADD COLUMN peering_cost INTEGER", []);
+    // The conversation view filters on either side of the exchange, so both
+    // columns need an index for the OR lookup to avoid a full scan.
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_messages_peer ON messages (source, destination, timestamp);
+        CREATE INDEX IF NOT EXISTS idx_messages_destination ON messages (destination, timestamp);
+        CREATE INDEX IF NOT EXISTS idx_delivery_traces_message ON delivery_traces (message_id, seq);",
+    )?;
+    Ok(())
+}
+
+fn migrate_announce_stamp_cost_and_hops(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE announces ADD COLUMN stamp_cost INTEGER;
+        ALTER TABLE announces ADD COLUMN hops INTEGER;",
+    )
+}
+
+fn migrate_announce_peer_index(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_announces_peer ON announces (peer);")
+}
+
+fn migrate_content_store(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS contents (
+            hash TEXT PRIMARY KEY,
+            data BLOB NOT NULL,
+            size INTEGER NOT NULL,
+            created_at INTEGER NOT NULL
+        );",
+    )
+}
+
+fn migrate_settings(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        );",
+    )
+}
+
+fn migrate_message_versions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE messages ADD COLUMN edited_at INTEGER;
+        CREATE TABLE IF NOT EXISTS message_versions (
+            message_id TEXT NOT NULL,
+            version INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            replaced_at INTEGER NOT NULL,
+            PRIMARY KEY (message_id, version)
+        );",
+    )
+}
+
+fn migrate_group_keys(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS group_keys (
+            name TEXT PRIMARY KEY,
+            app TEXT NOT NULL,
+            aspect TEXT NOT NULL,
+            destination TEXT NOT NULL,
+            sealed_key BLOB NOT NULL,
+            created_at INTEGER NOT NULL
+        );",
+    )
+}
+
+fn migrate_paths(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS paths (
+            destination TEXT PRIMARY KEY,
+            next_hop TEXT NOT NULL,
+            interface TEXT NOT NULL,
+            hops INTEGER NOT NULL,
+            learned_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL
+        );",
+    )
+}
+
+fn migrate_announce_public_key(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE announces ADD COLUMN public_key_hex TEXT;")
+}
+
+fn migrate_message_tombstones(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE messages ADD COLUMN deleted_at INTEGER;
+        CREATE INDEX IF NOT EXISTS idx_messages_deleted ON messages (deleted_at);",
+    )
+}
+
+fn migrate_message_stamps(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS message_stamps (
+            message_id TEXT PRIMARY KEY,
+            direction TEXT NOT NULL,
+            stamp_cost INTEGER,
+            stamp_value INTEGER,
+            under_stamped INTEGER NOT NULL DEFAULT 0,
+            recorded_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_message_stamps_recorded ON message_stamps (recorded_at);",
+    )
+}
+
+fn migrate_announce_interface(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE announces ADD COLUMN interface TEXT;
+        CREATE INDEX IF NOT EXISTS idx_announces_interface ON announces (interface, timestamp);",
+    )
+}
+
+fn migrate_message_attempts(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE messages ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;")
+}
+
+// Sources are already covered by idx_messages_peer, which leads with source,
+// and destinations by idx_messages_destination. The tombstone index only
+// holds deleted rows from here on; as a full index SQLite preferred it for
+// `deleted_at IS NULL` and sorted every live message.
+fn migrate_message_listing_indexes(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_messages_timestamp ON messages (timestamp DESC, id DESC);
+        CREATE INDEX IF NOT EXISTS idx_messages_direction ON messages (direction, timestamp);
+        DROP INDEX IF EXISTS idx_messages_deleted;
+        CREATE INDEX idx_messages_deleted ON messages (deleted_at) WHERE deleted_at IS NOT NULL;",
+    )
+}
+
+fn migrate_propagation_store(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS propagation_store (
+            transient_id TEXT PRIMARY KEY,
+            payload_hex TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            ingested_at INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_propagation_store_ingested
+            ON propagation_store (ingested_at, transient_id);",
+    )
+}
+
+fn migrate_received_contents(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "ALTER TABLE contents ADD COLUMN received INTEGER NOT NULL DEFAULT 0;
+        CREATE INDEX IF NOT EXISTS idx_contents_received
+            ON contents (received, created_at, hash);",
+    )
+}
+
+fn migrate_message_packed(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch("ALTER TABLE messages ADD COLUMN packed BLOB;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query_plan(store: &MessagesStore, sql: &str, values: Vec<Value>) -> String {
+        let mut stmt = store
             .conn
-            .execute("ALTER TABLE announces ADD COLUMN peering_cost INTEGER", []);
-        Ok(())
+            .prepare(&format!("EXPLAIN QUERY PLAN {sql}"))
+            .unwrap();
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| row.get::<_, String>(3))
+            .unwrap();
+        rows.map(Result::unwrap).collect::<Vec<_>>().join("; ")
+    }
+
+    #[test]
+    fn listing_queries_use_indexes() {
+        let store = MessagesStore::in_memory().unwrap();
+        let listing = |direction, peer| {
+            let (sql, mut values) =
+                filtered_messages_query("SELECT id FROM messages", direction, peer);
+            values.push(Value::Integer(50));
+            query_plan(
+                &store,
+                &format!("{sql} ORDER BY timestamp DESC, id DESC LIMIT ?"),
+                values,
+            )
+        };
+
+        let plan = listing(None, None);
+        assert!(plan.contains("idx_messages_timestamp"), "{plan}");
+        assert!(!plan.contains("TEMP B-TREE"), "{plan}");
+        let plan = listing(Some("in"), None);
+        assert!(plan.contains("idx_messages_direction"), "{plan}");
+        let plan = listing(None, Some("peer"));
+        assert!(plan.contains("idx_messages_peer"), "{plan}");
+        assert!(plan.contains("idx_messages_destination"), "{plan}");
+
+        let plan = query_plan(
+            &store,
+            "SELECT id FROM messages WHERE deleted_at IS NULL AND timestamp < ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2",
+            vec![Value::Integer(0), Value::Integer(50)],
+        );
+        assert!(plan.contains("idx_messages_timestamp"), "{plan}");
+
+        let plan = query_plan(
+            &store,
+            "SELECT id, deleted_at FROM messages WHERE deleted_at >= ?1 ORDER BY deleted_at ASC, id ASC",
+            vec![Value::Integer(0)],
+        );
+        assert!(plan.contains("idx_messages_deleted"), "{plan}");
     }
 }
@@ -4,6 +4,7 @@ pub(super) async fn handle_announce<'a>(
     packet: &Packet,
     mut handler: MutexGuard<'a, TransportHandler>,
     iface: AddressHash,
+    quality: Option<LinkQuality>,
 ) {
     if let Some(blocked_until) = handler.announce_limits.check(&packet.destination) {
         log::info!(
@@ -67,6 +68,7 @@ pub(super) async fn handle_announce<'a>(
         handler
             .path_table
             .handle_announce(packet, packet.transport, iface);
+        handler.iface_hints.learn(packet.destination, iface);
     }
 
     let retransmit = handler.config.retransmit;
@@ -86,6 +88,17 @@ pub(super) async fn handle_announce<'a>(
     };
     let interface = iface.as_slice().to_vec();
 
+    // Path and retransmit bookkeeping above still sees every copy; only the
+    // subscriber event is suppressed for a repeat within the window.
+    if !handler.announce_dedup.admit(&dest_hash, announce.app_data) {
+        log::trace!(
+            "tp({}): deduped announce for {}",
+            handler.config.name,
+            dest_hash
+        );
+        return;
+    }
+
     let _ = handler.announce_tx.send(AnnounceEvent {
         destination,
         app_data: PacketDataBuffer::new_from_slice(announce.app_data),
@@ -93,6 +106,7 @@ pub(super) async fn handle_announce<'a>(
         name_hash,
         hops: packet.header.hops,
         interface,
+        quality,
     });
 }
 
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use sha2::{Digest, Sha256};
+use tokio::time::{Duration, Instant};
+
+use crate::hash::AddressHash;
+
+pub const DEFAULT_ANNOUNCE_DEDUP_WINDOW_SECS: u64 = 5;
+
+type DedupKey = (AddressHash, [u8; 32]);
+
+struct DedupState {
+    window: Duration,
+    seen: HashMap<DedupKey, Instant>,
+    pruned_at: Instant,
+    deduped: u64,
+}
+
+// Repeats of an announce with the same destination and app data inside the
+// window are not handed to announce subscribers. A re-announce that changes
+// the app data always gets through. A zero window disables deduplication.
+#[derive(Clone)]
+pub struct AnnounceDedup {
+    state: Arc<Mutex<DedupState>>,
+}
+
+impl AnnounceDedup {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(DedupState {
+                window,
+                seen: HashMap::new(),
+                pruned_at: Instant::now(),
+                deduped: 0,
+            })),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.state
+            .lock()
+            .expect("announce dedup mutex poisoned")
+            .window
+    }
+
+    pub fn set_window(&self, window: Duration) {
+        let mut state = self.state.lock().expect("announce dedup mutex poisoned");
+        state.window = window;
+        state.seen.clear();
+    }
+
+    // Total announces suppressed since the transport started.
+    pub fn deduped(&self) -> u64 {
+        self.state
+            .lock()
+            .expect("announce dedup mutex poisoned")
+            .deduped
+    }
+
+    pub(super) fn admit(&self, destination: &AddressHash, app_data: &[u8]) -> bool {
+        self.admit_at(destination, app_data, Instant::now())
+    }
+
+    pub(super) fn admit_at(
+        &self,
+        destination: &AddressHash,
+        app_data: &[u8],
+        now: Instant,
+    ) -> bool {
+        let mut state = self.state.lock().expect("announce dedup mutex poisoned");
+        let window = state.window;
+        if window.is_zero() {
+            return true;
+        }
+        if now.saturating_duration_since(state.pruned_at) >= window {
+            state
+                .seen
+                .retain(|_, seen_at| now.saturating_duration_since(*seen_at) < window);
+            state.pruned_at = now;
+        }
+        let key = (*destination, Sha256::digest(app_data).into());
+        match state.seen.get(&key) {
+            Some(seen_at) if now.saturating_duration_since(*seen_at) < window => {
+                state.deduped += 1;
+                false
+            }
+            _ => {
+                state.seen.insert(key, now);
+                true
+            }
+        }
+    }
+
+    pub(super) fn forget_seen(&self) {
+        self.state
+            .lock()
+            .expect("announce dedup mutex poisoned")
+            .seen
+            .clear();
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+// Bulk packets are paid for up front, so they never exceed the cap; the
+// allowance saved up while idle is bounded by BURST_BYTES. Bytes sent on the
+// priority lane are charged to the same allowance even if that drives it
+// negative, so bulk traffic backs off while interactive packets are flowing.
+const BURST_BYTES: f64 = 1024.0;
+// Priority traffic can push the allowance at most this many seconds of the
+// limit into debt, so a burst of it cannot stall bulk traffic indefinitely.
+const MAX_DEBT_SECS: f64 = 2.0;
+
+#[derive(Debug, Clone)]
+pub struct BandwidthLimiter {
+    bytes_per_sec: Option<u64>,
+    allowance: f64,
+    updated: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_sec: Option<u64>, now: Instant) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.filter(|limit| *limit > 0),
+            allowance: 0.0,
+            updated: now,
+        }
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.bytes_per_sec
+    }
+
+    pub fn set_limit(&mut self, bytes_per_sec: Option<u64>, now: Instant) {
+        *self = Self::new(bytes_per_sec, now);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let Some(limit) = self.bytes_per_sec else {
+            return;
+        };
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.allowance = (self.allowance + elapsed * limit as f64).min(BURST_BYTES);
+        self.updated = now;
+    }
+
+    pub fn try_acquire(&mut self, bytes: usize, now: Instant) -> bool {
+        if self.bytes_per_sec.is_none() {
+            return true;
+        }
+        self.refill(now);
+        let bytes = bytes as f64;
+        if self.allowance < bytes.min(BURST_BYTES) {
+            return false;
+        }
+        self.allowance -= bytes;
+        true
+    }
+
+    pub fn record(&mut self, bytes: usize, now: Instant) {
+        if self.bytes_per_sec.is_none() {
+            return;
+        }
+        self.refill(now);
+        let max_debt = self.bytes_per_sec.unwrap_or(0) as f64 * MAX_DEBT_SECS;
+        self.allowance = (self.allowance - bytes as f64).max(-max_debt);
+    }
+}
+
+// Shared behind a std mutex so the limit can be changed from RPC handlers
+// without taking the async transport handler lock.
+#[derive(Clone)]
+pub struct BandwidthControl {
+    limiter: Arc<Mutex<BandwidthLimiter>>,
+    paced_drops: Arc<AtomicU64>,
+}
+
+impl Default for BandwidthControl {
+    fn default() -> Self {
+        Self {
+            limiter: Arc::new(Mutex::new(BandwidthLimiter::new(None, Instant::now()))),
+            paced_drops: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl BandwidthControl {
+    pub fn limit(&self) -> Option<u64> {
+        self.limiter
+            .lock()
+            .expect("bandwidth mutex poisoned")
+            .limit()
+    }
+
+    pub fn set_limit(&self, bytes_per_sec: Option<u64>) {
+        self.limiter
+            .lock()
+            .expect("bandwidth mutex poisoned")
+            .set_limit(bytes_per_sec, Instant::now());
+    }
+
+    // Bulk packets dropped because the paced queue was full.
+    pub fn paced_drops(&self) -> u64 {
+        self.paced_drops.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn record_paced_drop(&self) {
+        self.paced_drops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn is_limited(&self) -> bool {
+        self.limit().is_some()
+    }
+
+    pub(super) fn try_acquire(&self, bytes: usize) -> bool {
+        self.limiter
+            .lock()
+            .expect("bandwidth mutex poisoned")
+            .try_acquire(bytes, Instant::now())
+    }
+
+    pub(super) fn record(&self, bytes: usize) {
+        self.limiter
+            .lock()
+            .expect("bandwidth mutex poisoned")
+            .record(bytes, Instant::now());
+    }
+}
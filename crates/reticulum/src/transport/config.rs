@@ -11,11 +11,14 @@ impl TransportConfig {
             announce_retry_limit: 5,
             announce_queue_len: 64,
             announce_cap: 128,
+            announce_dedup_window_secs: announce_dedup::DEFAULT_ANNOUNCE_DEDUP_WINDOW_SECS,
             path_request_timeout_secs: 30,
             link_proof_timeout_secs: 600,
             link_idle_timeout_secs: 900,
             resource_retry_interval_secs: 2,
             resource_retry_limit: 5,
+            resource_metadata_max_size: METADATA_MAX_SIZE,
+            resource_max_concurrent_transfers: 0,
             ratchet_store_path: None,
         }
     }
@@ -43,6 +46,11 @@ impl TransportConfig {
         self.announce_cap = cap;
     }
 
+    // Zero disables announce deduplication.
+    pub fn set_announce_dedup_window_secs(&mut self, secs: u64) {
+        self.announce_dedup_window_secs = secs;
+    }
+
     pub fn set_path_request_timeout_secs(&mut self, secs: u64) {
         self.path_request_timeout_secs = secs;
     }
@@ -63,6 +71,15 @@ impl TransportConfig {
         self.resource_retry_limit = limit;
     }
 
+    pub fn set_resource_metadata_max_size(&mut self, limit: usize) {
+        self.resource_metadata_max_size = limit.min(METADATA_MAX_SIZE);
+    }
+
+    // Zero leaves resource transfers unlimited.
+    pub fn set_resource_max_concurrent_transfers(&mut self, limit: usize) {
+        self.resource_max_concurrent_transfers = limit;
+    }
+
     pub fn set_ratchet_store_path(&mut self, path: PathBuf) {
         self.ratchet_store_path = Some(path);
     }
@@ -79,11 +96,14 @@ impl Default for TransportConfig {
             announce_retry_limit: 5,
             announce_queue_len: 64,
             announce_cap: 128,
+            announce_dedup_window_secs: announce_dedup::DEFAULT_ANNOUNCE_DEDUP_WINDOW_SECS,
             path_request_timeout_secs: 30,
             link_proof_timeout_secs: 600,
             link_idle_timeout_secs: 900,
             resource_retry_interval_secs: 2,
             resource_retry_limit: 5,
+            resource_metadata_max_size: METADATA_MAX_SIZE,
+            resource_max_concurrent_transfers: 0,
             ratchet_store_path: None,
         }
     }
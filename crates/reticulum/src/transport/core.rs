@@ -14,6 +14,7 @@ impl Transport {
         let iface_manager = InterfaceManager::new(128);
 
         let rx_receiver = iface_manager.receiver();
+        let iface_traffic = iface_manager.traffic();
 
         let iface_manager = Arc::new(Mutex::new(iface_manager));
 
@@ -26,6 +27,13 @@ impl Transport {
         let link_idle_timeout_secs = config.link_idle_timeout_secs;
         let resource_retry_interval_secs = config.resource_retry_interval_secs;
         let resource_retry_limit = config.resource_retry_limit;
+        let mut resource_manager = ResourceManager::new_with_config(
+            Duration::from_secs(resource_retry_interval_secs),
+            resource_retry_limit,
+        );
+        resource_manager.set_metadata_max_size(config.resource_metadata_max_size);
+        resource_manager.set_max_concurrent_transfers(config.resource_max_concurrent_transfers);
+        let resource_load = resource_manager.load();
         let ratchet_store = config.ratchet_store_path.as_ref().map(|path| {
             let mut store = RatchetStore::new(path.clone());
             store.clean_expired(now_secs());
@@ -49,6 +57,11 @@ impl Transport {
 
         let cancel = CancellationToken::new();
         let name = config.name.clone();
+        let iface_filters = InterfaceFilterTable::default();
+        let iface_hints = DestinationInterfaceHints::default();
+        let bandwidth = BandwidthControl::default();
+        let announce_dedup =
+            AnnounceDedup::new(Duration::from_secs(config.announce_dedup_window_secs));
         let handler = Arc::new(Mutex::new(TransportHandler {
             config,
             iface_manager: iface_manager.clone(),
@@ -60,21 +73,26 @@ impl Transport {
             path_table: PathTable::new(),
             single_in_destinations: HashMap::new(),
             single_out_destinations: HashMap::new(),
+            plain_in_destinations: HashMap::new(),
             announce_limits: AnnounceLimits::new(),
+            announce_dedup: announce_dedup.clone(),
             out_links: HashMap::new(),
             in_links: HashMap::new(),
+            link_rtts: LinkRttHistory::default(),
             packet_cache: Mutex::new(PacketCache::new()),
             path_requests,
             announce_tx,
             link_in_event_tx: link_in_event_tx.clone(),
             received_data_tx: received_data_tx.clone(),
             ratchet_store,
-            resource_manager: ResourceManager::new_with_config(
-                Duration::from_secs(resource_retry_interval_secs),
-                resource_retry_limit,
-            ),
+            resource_manager,
             resource_events_tx: resource_events_tx.clone(),
             fixed_dest_path_requests: path_request_dest,
+            iface_filters: iface_filters.clone(),
+            iface_hints: iface_hints.clone(),
+            bandwidth: bandwidth.clone(),
+            paced_packets: VecDeque::new(),
+            iface_traffic: iface_traffic.clone(),
             cancel: cancel.clone(),
             receipt_handler: None,
         }));
@@ -103,6 +121,7 @@ impl Transport {
                                     request_id: payload.request_id(),
                                     hops: None,
                                     interface: None,
+                                    packet_hash: None,
                                 });
                             }
                         }
@@ -122,6 +141,12 @@ impl Transport {
             iface_messages_tx,
             resource_events_tx,
             handler,
+            iface_filters,
+            iface_hints,
+            bandwidth,
+            announce_dedup,
+            resource_load,
+            iface_traffic,
             cancel,
         }
     }
@@ -152,10 +177,55 @@ impl Transport {
         }
     }
 
+    pub async fn path_snapshot(&self) -> Vec<PathSnapshot> {
+        self.handler.lock().await.path_table.snapshot()
+    }
+
+    // Seeds the path table with paths saved before a restart. Returns how
+    // many were added.
+    pub async fn restore_paths(&self, paths: Vec<PathSnapshot>) -> usize {
+        let mut handler = self.handler.lock().await;
+        paths
+            .into_iter()
+            .filter(|path| handler.path_table.restore(*path))
+            .count()
+    }
+
     pub fn iface_manager(&self) -> Arc<Mutex<InterfaceManager>> {
         self.iface_manager.clone()
     }
 
+    pub fn interface_filters(&self) -> InterfaceFilterTable {
+        self.iface_filters.clone()
+    }
+
+    pub fn destination_interface_hints(&self) -> DestinationInterfaceHints {
+        self.iface_hints.clone()
+    }
+
+    pub fn bandwidth_control(&self) -> BandwidthControl {
+        self.bandwidth.clone()
+    }
+
+    pub fn interface_traffic(&self) -> InterfaceTraffic {
+        self.iface_traffic.clone()
+    }
+
+    pub fn announce_dedup(&self) -> AnnounceDedup {
+        self.announce_dedup.clone()
+    }
+
+    pub fn resource_load(&self) -> ResourceLoad {
+        self.resource_load.clone()
+    }
+
+    pub fn transport_metrics(&self) -> TransportMetrics {
+        TransportMetrics {
+            announces_deduped: self.announce_dedup.deduped(),
+            announce_dedup_window_secs: self.announce_dedup.window().as_secs(),
+        }
+    }
+
     pub fn iface_rx(&self) -> broadcast::Receiver<RxMessage> {
         self.iface_messages_tx.subscribe()
     }
@@ -173,6 +243,38 @@ impl Transport {
         handler.send_packet(packet).await;
     }
 
+    // Proves a packet that reached one of our single destinations, signed
+    // with that destination's identity, so the sender can mark it delivered.
+    // Returns false when the destination isn't registered here.
+    pub async fn send_delivery_proof(
+        &self,
+        destination: &AddressHash,
+        packet_hash: &[u8; HASH_SIZE],
+    ) -> bool {
+        let mut handler = self.handler.lock().await;
+        let Some(input) = handler.single_in_destinations.get(destination).cloned() else {
+            return false;
+        };
+        let signature = input.lock().await.identity.sign(packet_hash).to_bytes();
+        let mut data = PacketDataBuffer::new();
+        data.safe_write(packet_hash);
+        data.safe_write(&signature);
+        let proof = Packet {
+            header: crate::packet::Header {
+                packet_type: PacketType::Proof,
+                destination_type: DestinationType::Single,
+                ..Default::default()
+            },
+            ifac: None,
+            destination: AddressHash::new_from_hash(&Hash::new(*packet_hash)),
+            transport: None,
+            context: PacketContext::None,
+            data,
+        };
+        handler.send_packet(proof).await;
+        true
+    }
+
     pub async fn send_packet_with_outcome(&self, packet: Packet) -> SendPacketOutcome {
         let mut handler = self.handler.lock().await;
         handler.send_packet_with_outcome(packet).await
@@ -188,6 +290,14 @@ impl Transport {
         destination: &Arc<Mutex<SingleInputDestination>>,
         app_data: Option<&[u8]>,
     ) {
+        let _ = self.send_announce_with_trace(destination, app_data).await;
+    }
+
+    pub async fn send_announce_with_trace(
+        &self,
+        destination: &Arc<Mutex<SingleInputDestination>>,
+        app_data: Option<&[u8]>,
+    ) -> SendPacketTrace {
         let mut destination = destination.lock().await;
         eprintln!(
             "[tp] announce_tx dst={} app_data_len={}",
@@ -198,7 +308,7 @@ impl Transport {
             .announce(OsRng, app_data)
             .expect("valid announce packet");
         let mut handler = self.handler.lock().await;
-        handler.send_packet(packet).await;
+        handler.send_packet_with_trace(packet).await
     }
 
     pub async fn set_receipt_handler(&mut self, handler: Box<dyn ReceiptHandler>) {
@@ -241,6 +351,33 @@ impl Transport {
             .await;
     }
 
+    // Stops every interface and drops routing state that refers to them, so
+    // the caller can spawn fresh interfaces on the same transport. Registered
+    // destinations, links and explicit interface hints are kept.
+    pub async fn reinitialize(&self) {
+        self.iface_manager.lock().await.shutdown_all();
+        {
+            let mut handler = self.handler.lock().await;
+            let announce_table = AnnounceTable::new(
+                handler.config.announce_cache_capacity,
+                handler.config.announce_retry_limit,
+            );
+            let link_table = LinkTable::new(
+                Duration::from_secs(handler.config.link_proof_timeout_secs),
+                Duration::from_secs(handler.config.link_idle_timeout_secs),
+            );
+            handler.path_table = PathTable::new();
+            handler.announce_table = announce_table;
+            handler.link_table = link_table;
+            handler.announce_limits = AnnounceLimits::new();
+            handler.announce_dedup.forget_seen();
+            handler.link_rtts.clear();
+            handler.packet_cache = Mutex::new(PacketCache::new());
+            handler.paced_packets.clear();
+        }
+        self.iface_hints.forget_learned();
+    }
+
     pub async fn send_direct(&self, addr: AddressHash, packet: Packet) {
         self.handler
             .lock()
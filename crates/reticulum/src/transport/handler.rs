@@ -57,6 +57,7 @@ impl TransportHandler {
                 return SendPacketTrace {
                     outcome: SendPacketOutcome::DroppedMissingDestinationIdentity,
                     direct_iface: None,
+                    hinted_iface: None,
                     broadcast: false,
                     dispatch: TxDispatchTrace::default(),
                 };
@@ -80,6 +81,7 @@ impl TransportHandler {
                         return SendPacketTrace {
                             outcome: SendPacketOutcome::DroppedCiphertextTooLarge,
                             direct_iface: None,
+                            hinted_iface: None,
                             broadcast: false,
                             dispatch: TxDispatchTrace::default(),
                         };
@@ -96,6 +98,7 @@ impl TransportHandler {
                     return SendPacketTrace {
                         outcome: SendPacketOutcome::DroppedEncryptFailed,
                         direct_iface: None,
+                        hinted_iface: None,
                         broadcast: false,
                         dispatch: TxDispatchTrace::default(),
                     };
@@ -123,6 +126,37 @@ impl TransportHandler {
         }
 
         let (packet, maybe_iface) = self.path_table.handle_packet(&packet);
+        // A known path always wins; the hint only replaces a blind broadcast.
+        let hinted_iface = maybe_iface
+            .is_none()
+            .then(|| self.iface_hints.get(&packet.destination))
+            .flatten()
+            .filter(|_| packet.header.packet_type != PacketType::Announce)
+            .map(|hint| hint.iface);
+        if let Some(iface) = hinted_iface {
+            let dispatch = self
+                .send(TxMessage {
+                    tx_type: TxMessageType::Direct(iface),
+                    packet,
+                })
+                .await;
+            if dispatch.sent_ifaces > 0 {
+                return SendPacketTrace {
+                    outcome: SendPacketOutcome::SentDirect,
+                    direct_iface: Some(iface),
+                    hinted_iface: Some(iface),
+                    broadcast: false,
+                    dispatch,
+                };
+            }
+            log::trace!(
+                "tp({}): hinted iface {} did not take packet dst={}, falling back",
+                self.config.name,
+                iface,
+                packet.destination
+            );
+        }
+
         if let Some(iface) = maybe_iface {
             let dispatch = self
                 .send(TxMessage {
@@ -156,6 +190,7 @@ impl TransportHandler {
             SendPacketTrace {
                 outcome,
                 direct_iface: Some(iface),
+                hinted_iface: None,
                 broadcast: false,
                 dispatch,
             }
@@ -187,6 +222,7 @@ impl TransportHandler {
             SendPacketTrace {
                 outcome,
                 direct_iface: None,
+                hinted_iface,
                 broadcast: true,
                 dispatch,
             }
@@ -199,6 +235,7 @@ impl TransportHandler {
             SendPacketTrace {
                 outcome: SendPacketOutcome::DroppedNoRoute,
                 direct_iface: None,
+                hinted_iface,
                 broadcast: false,
                 dispatch: TxDispatchTrace::default(),
             }
@@ -206,10 +243,44 @@ impl TransportHandler {
     }
 
     pub(super) async fn send(&self, message: TxMessage) -> TxDispatchTrace {
+        // Resource parts were already charged when they left the paced queue.
+        if self.bandwidth.is_limited() && message.packet.context != PacketContext::Resource {
+            self.bandwidth.record(wire_len(&message.packet));
+        }
         self.packet_cache.lock().await.update(&message.packet);
         self.iface_manager.lock().await.send(message).await
     }
 
+    // Bulk packets (resource parts) wait in a queue while the bandwidth limit
+    // is exhausted; everything else goes straight out on the priority lane.
+    pub(super) async fn send_paced(&mut self, packet: Packet) {
+        if !self.bandwidth.is_limited() && self.paced_packets.is_empty() {
+            self.send_packet(packet).await;
+            return;
+        }
+        if self.paced_packets.len() >= MAX_PACED_PACKETS {
+            self.paced_packets.pop_front();
+            self.bandwidth.record_paced_drop();
+            log::debug!(
+                "tp({}): paced queue full, dropped oldest packet",
+                self.config.name
+            );
+        }
+        self.paced_packets.push_back(packet);
+        self.flush_paced_packets().await;
+    }
+
+    pub(super) async fn flush_paced_packets(&mut self) {
+        while let Some(packet) = self.paced_packets.front() {
+            if !self.bandwidth.try_acquire(wire_len(packet)) {
+                break;
+            }
+            if let Some(packet) = self.paced_packets.pop_front() {
+                self.send_packet(packet).await;
+            }
+        }
+    }
+
     pub(super) fn has_destination(&self, address: &AddressHash) -> bool {
         self.single_in_destinations.contains_key(address)
     }
@@ -261,4 +332,33 @@ impl TransportHandler {
         })
         .await;
     }
+
+    // Retries a path request for `address` on the interface we reach `relay`
+    // through, where a transport node may answer from its announce cache. The
+    // request is the usual broadcast one, not addressed to the relay. False
+    // without a path to the relay.
+    pub(super) async fn request_path_on_relay_iface(
+        &mut self,
+        address: &AddressHash,
+        relay: &AddressHash,
+    ) -> bool {
+        let Some(iface) = self.path_table.get(relay).map(|entry| entry.iface) else {
+            return false;
+        };
+        let packet = self.path_requests.generate(address, None);
+        self.send(TxMessage {
+            tx_type: TxMessageType::Direct(iface),
+            packet,
+        })
+        .await
+        .sent_ifaces
+            > 0
+    }
+}
+
+fn wire_len(packet: &Packet) -> usize {
+    packet
+        .to_bytes()
+        .map(|bytes| bytes.len())
+        .unwrap_or(packet.data.len())
 }
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::hash::AddressHash;
+use crate::packet::{DestinationType, Packet, PacketType};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceFilter {
+    pub drop_packet_types: Vec<PacketType>,
+    pub drop_destination_types: Vec<DestinationType>,
+    pub drop_path_requests: bool,
+}
+
+impl InterfaceFilter {
+    pub fn is_empty(&self) -> bool {
+        self.drop_packet_types.is_empty()
+            && self.drop_destination_types.is_empty()
+            && !self.drop_path_requests
+    }
+
+    fn drops(&self, packet: &Packet, is_path_request: bool) -> bool {
+        (self.drop_path_requests && is_path_request)
+            || self.drop_packet_types.contains(&packet.header.packet_type)
+            || self
+                .drop_destination_types
+                .contains(&packet.header.destination_type)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceFilterStatus {
+    pub filter: InterfaceFilter,
+    pub filtered_packets: u64,
+}
+
+// Shared behind a std mutex so RPC handlers can update filters without going
+// through the async transport handler lock.
+#[derive(Clone, Default)]
+pub struct InterfaceFilterTable {
+    entries: Arc<Mutex<HashMap<AddressHash, InterfaceFilterStatus>>>,
+}
+
+impl InterfaceFilterTable {
+    pub fn set(&self, iface: AddressHash, filter: Option<InterfaceFilter>) {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("interface filter mutex poisoned");
+        match filter.filter(|filter| !filter.is_empty()) {
+            Some(filter) => {
+                let entry = entries.entry(iface).or_default();
+                entry.filter = filter;
+            }
+            None => {
+                entries.remove(&iface);
+            }
+        }
+    }
+
+    pub fn get(&self, iface: &AddressHash) -> Option<InterfaceFilterStatus> {
+        self.entries
+            .lock()
+            .expect("interface filter mutex poisoned")
+            .get(iface)
+            .cloned()
+    }
+
+    pub fn remap(&self, from: &AddressHash, to: AddressHash) {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("interface filter mutex poisoned");
+        if let Some(entry) = entries.remove(from) {
+            entries.insert(to, entry);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries
+            .lock()
+            .expect("interface filter mutex poisoned")
+            .is_empty()
+    }
+
+    // Checks the receiving interface first and then the interface it was
+    // accepted on, counting the drop against whichever filter matched.
+    pub(super) fn should_drop(
+        &self,
+        ifaces: &[AddressHash],
+        packet: &Packet,
+        is_path_request: bool,
+    ) -> bool {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("interface filter mutex poisoned");
+        for iface in ifaces {
+            if let Some(entry) = entries.get_mut(iface) {
+                if entry.filter.drops(packet, is_path_request) {
+                    entry.filtered_packets += 1;
+                    return true;
+                }
+                return false;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::hash::AddressHash;
+
+// Learned hints come from announces, so cap them to keep a busy network from
+// growing the table without bound. Explicit hints are never evicted.
+const MAX_LEARNED_HINTS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceHintSource {
+    Explicit,
+    Learned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterfaceHint {
+    pub iface: AddressHash,
+    pub source: InterfaceHintSource,
+}
+
+// Preferred outbound interface per destination, consulted when the path table
+// has no route and the packet would otherwise be broadcast on every interface.
+#[derive(Clone, Default)]
+pub struct DestinationInterfaceHints {
+    entries: Arc<Mutex<HashMap<AddressHash, InterfaceHint>>>,
+}
+
+impl DestinationInterfaceHints {
+    pub fn set(&self, destination: AddressHash, iface: AddressHash) {
+        self.entries
+            .lock()
+            .expect("interface hints mutex poisoned")
+            .insert(
+                destination,
+                InterfaceHint {
+                    iface,
+                    source: InterfaceHintSource::Explicit,
+                },
+            );
+    }
+
+    pub fn clear(&self, destination: &AddressHash) -> bool {
+        self.entries
+            .lock()
+            .expect("interface hints mutex poisoned")
+            .remove(destination)
+            .is_some()
+    }
+
+    pub fn get(&self, destination: &AddressHash) -> Option<InterfaceHint> {
+        self.entries
+            .lock()
+            .expect("interface hints mutex poisoned")
+            .get(destination)
+            .copied()
+    }
+
+    // Points hints at a respawned interface so explicit choices survive a
+    // transport restart.
+    pub fn remap(&self, from: &AddressHash, to: AddressHash) {
+        let mut entries = self.entries.lock().expect("interface hints mutex poisoned");
+        for entry in entries.values_mut().filter(|entry| entry.iface == *from) {
+            entry.iface = to;
+        }
+    }
+
+    pub fn forget_learned(&self) {
+        self.entries
+            .lock()
+            .expect("interface hints mutex poisoned")
+            .retain(|_, entry| entry.source == InterfaceHintSource::Explicit);
+    }
+
+    // Records the interface a destination was last heard on unless an explicit
+    // hint is already in place.
+    pub(super) fn learn(&self, destination: AddressHash, iface: AddressHash) {
+        let mut entries = self.entries.lock().expect("interface hints mutex poisoned");
+        match entries.get_mut(&destination) {
+            Some(entry) if entry.source == InterfaceHintSource::Explicit => {}
+            Some(entry) => entry.iface = iface,
+            None => {
+                let learned = entries
+                    .values()
+                    .filter(|entry| entry.source == InterfaceHintSource::Learned)
+                    .count();
+                if learned < MAX_LEARNED_HINTS {
+                    entries.insert(
+                        destination,
+                        InterfaceHint {
+                            iface,
+                            source: InterfaceHintSource::Learned,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
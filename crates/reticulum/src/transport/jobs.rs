@@ -5,6 +5,7 @@ use super::*;
 
 pub(super) async fn handle_check_links<'a>(mut handler: MutexGuard<'a, TransportHandler>) {
     let mut links_to_remove: Vec<AddressHash> = Vec::new();
+    let mut closed_links: Vec<AddressHash> = Vec::new();
     let mut pending_packets: Vec<Packet> = Vec::new();
 
     // Clean up input links
@@ -13,6 +14,9 @@ pub(super) async fn handle_check_links<'a>(mut handler: MutexGuard<'a, Transport
         if link.elapsed() > INTERVAL_INPUT_LINK_CLEANUP {
             link.close();
             links_to_remove.push(*link_entry.0);
+            closed_links.push(*link.id());
+        } else if link.status() == LinkStatus::Closed {
+            closed_links.push(*link.id());
         }
     }
 
@@ -27,6 +31,7 @@ pub(super) async fn handle_check_links<'a>(mut handler: MutexGuard<'a, Transport
         if link.status() == LinkStatus::Closed {
             link.close();
             links_to_remove.push(*link_entry.0);
+            closed_links.push(*link.id());
         }
     }
 
@@ -34,6 +39,10 @@ pub(super) async fn handle_check_links<'a>(mut handler: MutexGuard<'a, Transport
         handler.out_links.remove(addr);
     }
 
+    for link_id in &closed_links {
+        handler.resource_manager.drop_link(link_id);
+    }
+
     for link_entry in &handler.out_links {
         let mut link = link_entry.1.lock().await;
 
@@ -104,11 +113,36 @@ pub(super) async fn manage_transport(
                         break;
                     },
                     Some(message) = rx_receiver.recv() => {
-                        let _ = iface_messages_tx.send(message);
-
                         let packet = message.packet;
 
                         let mut handler = handler_arc.lock().await;
+                        handler
+                            .iface_traffic
+                            .record_rx(message.address, packet_wire_len(&packet));
+
+                        if !handler.iface_filters.is_empty() {
+                            let parent = handler
+                                .iface_manager
+                                .lock()
+                                .await
+                                .parent_of(&message.address);
+                            let ifaces: Vec<AddressHash> =
+                                std::iter::once(message.address).chain(parent).collect();
+                            let is_path_request = packet.header.packet_type == PacketType::Data
+                                && packet.destination == handler.fixed_dest_path_requests;
+                            if handler.iface_filters.should_drop(&ifaces, &packet, is_path_request) {
+                                log::debug!(
+                                    "tp({}): filtered inbound packet iface={} dst={} type={:?}",
+                                    handler.config.name,
+                                    message.address,
+                                    packet.destination,
+                                    packet.header.packet_type
+                                );
+                                continue;
+                            }
+                        }
+
+                        let _ = iface_messages_tx.send(message);
 
                         if PACKET_TRACE {
                             log::debug!("tp: << rx({}) = {} {}", message.address, packet, packet.hash());
@@ -148,7 +182,8 @@ pub(super) async fn manage_transport(
                             PacketType::Announce => handle_announce(
                                 &packet,
                                 handler,
-                                message.address
+                                message.address,
+                                message.quality
                             ).await,
                             PacketType::LinkRequest => handle_link_request(
                                 &packet,
@@ -263,6 +298,31 @@ pub(super) async fn manage_transport(
         });
     }
 
+    {
+        let handler = handler_arc.clone();
+        let cancel = cancel.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if cancel.is_cancelled() {
+                    break;
+                }
+
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        break;
+                    },
+                    _ = time::sleep(INTERVAL_PACED_FLUSH) => {
+                        let mut handler = handler.lock().await;
+                        if !handler.paced_packets.is_empty() {
+                            handler.flush_paced_packets().await;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     if retransmit {
         let handler = handler_arc.clone();
         let cancel = cancel.clone();
@@ -0,0 +1,38 @@
+use std::collections::{HashMap, VecDeque};
+
+use tokio::time::Duration;
+
+use crate::hash::AddressHash;
+
+// Handshakes remembered per destination. Enough to smooth out one slow
+// handshake without hiding a lasting change in latency for long.
+const RTT_SAMPLES: usize = 8;
+
+// Rolling link handshake RTT per destination, so relay and path choices have
+// a latency signal to go on.
+#[derive(Debug, Default)]
+pub(super) struct LinkRttHistory {
+    samples: HashMap<AddressHash, VecDeque<Duration>>,
+}
+
+impl LinkRttHistory {
+    pub(super) fn record(&mut self, destination: AddressHash, rtt: Duration) {
+        let samples = self.samples.entry(destination).or_default();
+        if samples.len() >= RTT_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(rtt);
+    }
+
+    pub(super) fn average(&self, destination: &AddressHash) -> Option<Duration> {
+        let samples = self.samples.get(destination)?;
+        let count = u32::try_from(samples.len())
+            .ok()
+            .filter(|count| *count > 0)?;
+        Some(samples.iter().sum::<Duration>() / count)
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
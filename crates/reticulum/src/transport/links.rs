@@ -132,6 +132,69 @@ impl Transport {
         Ok(resource_hash)
     }
 
+    pub async fn link_snapshots(&self) -> Vec<LinkSnapshot> {
+        let handler = self.handler.lock().await;
+        let links = handler
+            .out_links
+            .values()
+            .map(|link| (true, link.clone()))
+            .chain(handler.in_links.values().map(|link| (false, link.clone())))
+            .collect::<Vec<_>>();
+        let mut snapshots = Vec::with_capacity(links.len());
+        for (outbound, link) in links {
+            let link = link.lock().await;
+            if link.status() == LinkStatus::Closed {
+                continue;
+            }
+            let destination = link.destination().address_hash;
+            snapshots.push(LinkSnapshot {
+                id: *link.id(),
+                destination,
+                outbound,
+                status: link.status(),
+                rtt: link.rtt(),
+                rtt_avg: handler.link_rtts.average(&destination),
+                age: link.established_at().map(|at| at.elapsed()),
+                idle: link.last_activity().elapsed(),
+            });
+        }
+        snapshots
+    }
+
+    // Sends a teardown to the peer when the link is up, then drops it from the
+    // link tables. Returns false when no link with this id is known.
+    pub async fn close_link(&self, link_id: &LinkId) -> bool {
+        let link = match self.find_in_link(link_id).await {
+            Some(link) => Some(link),
+            None => self.find_out_link(link_id).await,
+        };
+        let Some(link) = link else {
+            return false;
+        };
+
+        let teardown = {
+            let mut link = link.lock().await;
+            let packet = if link.status() == LinkStatus::Active {
+                link.teardown_packet().ok()
+            } else {
+                None
+            };
+            link.close();
+            packet
+        };
+        if let Some(packet) = teardown {
+            self.send_packet(packet).await;
+        }
+
+        let mut handler = self.handler.lock().await;
+        handler.in_links.remove(link_id);
+        handler
+            .out_links
+            .retain(|_, candidate| !Arc::ptr_eq(candidate, &link));
+        handler.resource_manager.drop_link(link_id);
+        true
+    }
+
     pub async fn find_out_link(&self, link_id: &AddressHash) -> Option<Arc<Mutex<Link>>> {
         let links = {
             let handler = self.handler.lock().await;
@@ -203,6 +266,18 @@ impl Transport {
             .await
     }
 
+    pub async fn request_path_on_relay_iface(
+        &self,
+        destination: &AddressHash,
+        relay: &AddressHash,
+    ) -> bool {
+        self.handler
+            .lock()
+            .await
+            .request_path_on_relay_iface(destination, relay)
+            .await
+    }
+
     pub fn out_link_events(&self) -> broadcast::Receiver<LinkEventData> {
         self.link_out_event_tx.subscribe()
     }
@@ -236,6 +311,67 @@ impl Transport {
         destination
     }
 
+    pub async fn add_plain_destination(&self, name: DestinationName) -> AddressHash {
+        let destination = PlainInputDestination::new(EmptyIdentity {}, name);
+        let address_hash = destination.desc.address_hash;
+
+        log::debug!("tp({}): add plain destination {}", self.name, address_hash);
+
+        self.handler
+            .lock()
+            .await
+            .plain_in_destinations
+            .insert(address_hash, destination);
+
+        address_hash
+    }
+
+    // Plain packets are not routed through the path table; they always go out
+    // as a broadcast on every interface.
+    pub async fn send_plain(
+        &self,
+        name: DestinationName,
+        data: &[u8],
+    ) -> Result<SendPacketTrace, RnsError> {
+        let destination = PlainOutputDestination::new(EmptyIdentity {}, name);
+        let packet = destination.data_packet(data)?;
+        Ok(self.broadcast_data_packet(packet).await)
+    }
+
+    pub async fn send_group(
+        &self,
+        name: DestinationName,
+        key: &[u8; 16],
+        data: &[u8],
+    ) -> Result<SendPacketTrace, RnsError> {
+        let destination = GroupOutputDestination::new(EmptyIdentity {}, name);
+        let packet = destination.data_packet(key, data)?;
+        Ok(self.broadcast_data_packet(packet).await)
+    }
+
+    async fn broadcast_data_packet(&self, packet: Packet) -> SendPacketTrace {
+        let dispatch = self
+            .handler
+            .lock()
+            .await
+            .send(TxMessage {
+                tx_type: TxMessageType::Broadcast(None),
+                packet,
+            })
+            .await;
+        SendPacketTrace {
+            outcome: if dispatch.sent_ifaces > 0 {
+                SendPacketOutcome::SentBroadcast
+            } else {
+                SendPacketOutcome::DroppedNoRoute
+            },
+            direct_iface: None,
+            hinted_iface: None,
+            broadcast: true,
+            dispatch,
+        }
+    }
+
     pub async fn has_destination(&self, address: &AddressHash) -> bool {
         self.handler.lock().await.has_destination(address)
     }
@@ -244,6 +380,15 @@ impl Transport {
         self.handler.lock().await.knows_destination(address)
     }
 
+    // Non-blocking variant for synchronous callers; None while the handler
+    // is busy.
+    pub fn try_knows_destination(&self, address: &AddressHash) -> Option<bool> {
+        self.handler
+            .try_lock()
+            .ok()
+            .map(|handler| handler.knows_destination(address))
+    }
+
     pub async fn destination_identity(&self, address: &AddressHash) -> Option<Identity> {
         let destination = {
             self.handler
@@ -1,14 +1,20 @@
 use alloc::sync::Arc;
+use announce_dedup::AnnounceDedup;
 use announce_limits::AnnounceLimits;
 use announce_table::AnnounceTable;
+use bandwidth::BandwidthControl;
+use iface_filter::InterfaceFilterTable;
+use iface_hints::DestinationInterfaceHints;
+use link_rtt::LinkRttHistory;
 use link_table::LinkTable;
 use packet_cache::PacketCache;
 use path_requests::create_path_request_destination;
 use path_requests::PathRequests;
 use path_requests::TagBytes;
-use path_table::PathTable;
+use path_table::{PathSnapshot, PathTable};
 use rand_core::OsRng;
 use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::time::Duration;
 use tokio::time;
@@ -30,15 +36,21 @@ use crate::destination::DestinationAnnounce;
 use crate::destination::DestinationDesc;
 use crate::destination::DestinationHandleStatus;
 use crate::destination::DestinationName;
+use crate::destination::GroupOutputDestination;
+use crate::destination::PlainInputDestination;
+use crate::destination::PlainOutputDestination;
 use crate::destination::SingleInputDestination;
 use crate::destination::SingleOutputDestination;
 
 use crate::error::RnsError;
 use crate::hash::{AddressHash, Hash, HASH_SIZE};
-use crate::identity::{Identity, PrivateIdentity};
+use crate::identity::{EmptyIdentity, Identity, PrivateIdentity};
 
+use crate::iface::traffic::packet_wire_len;
 use crate::iface::InterfaceManager;
 use crate::iface::InterfaceRxReceiver;
+use crate::iface::InterfaceTraffic;
+use crate::iface::LinkQuality;
 use crate::iface::RxMessage;
 use crate::iface::TxDispatchTrace;
 use crate::iface::TxMessage;
@@ -50,11 +62,18 @@ use crate::packet::PacketContext;
 use crate::packet::PacketDataBuffer;
 use crate::packet::PacketType;
 use crate::ratchets::{encrypt_for_public_key, now_secs, RatchetStore};
-use crate::resource::{build_resource_request_packet, ResourceEvent, ResourceManager};
+use crate::resource::{
+    build_resource_request_packet, ResourceEvent, ResourceLoad, ResourceManager, METADATA_MAX_SIZE,
+};
 
+pub mod announce_dedup;
 mod announce_limits;
 pub mod announce_table;
+pub mod bandwidth;
 pub mod discovery;
+pub mod iface_filter;
+pub mod iface_hints;
+mod link_rtt;
 mod link_table;
 mod packet_cache;
 mod path_requests;
@@ -97,6 +116,9 @@ pub mod test_bridge {
             direction: "in".into(),
             fields: record.fields.clone(),
             receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
         };
         let _ = daemon.accept_inbound_for_test(inbound);
         true
@@ -114,10 +136,14 @@ const INTERVAL_OUTPUT_LINK_REPEAT: Duration = Duration::from_secs(6);
 const INTERVAL_OUTPUT_LINK_KEEP: Duration = Duration::from_secs(5);
 const INTERVAL_IFACE_CLEANUP: Duration = Duration::from_secs(10);
 const INTERVAL_ANNOUNCES_RETRANSMIT: Duration = Duration::from_secs(1);
+const INTERVAL_PACED_FLUSH: Duration = Duration::from_millis(50);
 const INTERVAL_KEEP_PACKET_CACHED: Duration = Duration::from_secs(180);
 const INTERVAL_PACKET_CACHE_CLEANUP: Duration = Duration::from_secs(90);
 
 // Other constants
+// Bulk packets held back by the bandwidth limit; the oldest is dropped to
+// make room. Its resource part is requested again by the receiver.
+const MAX_PACED_PACKETS: usize = 256;
 const KEEP_ALIVE_REQUEST: u8 = 0xFF;
 const KEEP_ALIVE_RESPONSE: u8 = 0xFE;
 
@@ -130,6 +156,9 @@ pub struct ReceivedData {
     pub request_id: Option<[u8; 16]>,
     pub hops: Option<u8>,
     pub interface: Option<Vec<u8>>,
+    // Full hash of the carrying packet for data sent to a single
+    // destination, which is what a delivery proof refers to.
+    pub packet_hash: Option<[u8; 32]>,
 }
 
 pub struct TransportConfig {
@@ -141,11 +170,14 @@ pub struct TransportConfig {
     announce_retry_limit: u8,
     announce_queue_len: usize,
     announce_cap: usize,
+    announce_dedup_window_secs: u64,
     path_request_timeout_secs: u64,
     link_proof_timeout_secs: u64,
     link_idle_timeout_secs: u64,
     resource_retry_interval_secs: u64,
     resource_retry_limit: u8,
+    resource_metadata_max_size: usize,
+    resource_max_concurrent_transfers: usize,
     ratchet_store_path: Option<PathBuf>,
 }
 
@@ -171,6 +203,7 @@ pub struct AnnounceEvent {
     pub name_hash: [u8; crate::destination::NAME_HASH_LENGTH],
     pub hops: u8,
     pub interface: Vec<u8>,
+    pub quality: Option<LinkQuality>,
 }
 
 pub(crate) struct TransportHandler {
@@ -183,11 +216,14 @@ pub(crate) struct TransportHandler {
     link_table: LinkTable,
     single_in_destinations: HashMap<AddressHash, Arc<Mutex<SingleInputDestination>>>,
     single_out_destinations: HashMap<AddressHash, Arc<Mutex<SingleOutputDestination>>>,
+    plain_in_destinations: HashMap<AddressHash, PlainInputDestination>,
 
     announce_limits: AnnounceLimits,
+    announce_dedup: AnnounceDedup,
 
     out_links: HashMap<AddressHash, Arc<Mutex<Link>>>,
     in_links: HashMap<AddressHash, Arc<Mutex<Link>>>,
+    link_rtts: LinkRttHistory,
 
     packet_cache: Mutex<PacketCache>,
 
@@ -201,6 +237,11 @@ pub(crate) struct TransportHandler {
     resource_events_tx: broadcast::Sender<ResourceEvent>,
 
     fixed_dest_path_requests: AddressHash,
+    iface_filters: InterfaceFilterTable,
+    iface_hints: DestinationInterfaceHints,
+    bandwidth: BandwidthControl,
+    paced_packets: VecDeque<Packet>,
+    iface_traffic: InterfaceTraffic,
 
     cancel: CancellationToken,
     receipt_handler: Option<Arc<dyn ReceiptHandler>>,
@@ -215,6 +256,12 @@ pub struct Transport {
     resource_events_tx: broadcast::Sender<ResourceEvent>,
     handler: Arc<Mutex<TransportHandler>>,
     iface_manager: Arc<Mutex<InterfaceManager>>,
+    iface_filters: InterfaceFilterTable,
+    iface_hints: DestinationInterfaceHints,
+    bandwidth: BandwidthControl,
+    announce_dedup: AnnounceDedup,
+    resource_load: ResourceLoad,
+    iface_traffic: InterfaceTraffic,
     cancel: CancellationToken,
 }
 
@@ -228,14 +275,50 @@ pub enum SendPacketOutcome {
     DroppedNoRoute,
 }
 
+impl SendPacketOutcome {
+    // Stable code reported to RPC clients for a dropped packet; the codes
+    // match the ones derived from failure statuses.
+    pub fn reason_code(self) -> Option<&'static str> {
+        match self {
+            Self::SentDirect | Self::SentBroadcast => None,
+            Self::DroppedMissingDestinationIdentity => Some("missing_destination_identity"),
+            Self::DroppedCiphertextTooLarge => Some("ciphertext_too_large"),
+            Self::DroppedEncryptFailed => Some("encrypt_failed"),
+            Self::DroppedNoRoute => Some("no_path"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportMetrics {
+    pub announces_deduped: u64,
+    pub announce_dedup_window_secs: u64,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SendPacketTrace {
     pub outcome: SendPacketOutcome,
     pub direct_iface: Option<AddressHash>,
+    // Set when a destination interface hint steered the send, even if it fell
+    // back to broadcast because the hinted interface did not take the packet.
+    pub hinted_iface: Option<AddressHash>,
     pub broadcast: bool,
     pub dispatch: TxDispatchTrace,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkSnapshot {
+    pub id: LinkId,
+    pub destination: AddressHash,
+    pub outbound: bool,
+    pub status: LinkStatus,
+    pub rtt: Duration,
+    // Rolling handshake RTT of links to the same destination.
+    pub rtt_avg: Option<Duration>,
+    pub age: Option<Duration>,
+    pub idle: Duration,
+}
+
 // Transport internals are decomposed by concern for testability and bounded change sets.
 // announce: announce handling and retransmit scheduling primitives.
 mod announce;
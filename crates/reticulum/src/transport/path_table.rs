@@ -1,4 +1,7 @@
-use std::{collections::HashMap, time::Instant};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 use crate::{
     error::RnsError,
@@ -15,6 +18,16 @@ pub struct PathEntry {
     pub packet_hash: Hash,
 }
 
+// A path as carried across a restart; `age` is how long ago it was learned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathSnapshot {
+    pub destination: AddressHash,
+    pub received_from: AddressHash,
+    pub hops: u8,
+    pub iface: AddressHash,
+    pub age: Duration,
+}
+
 pub struct PathTable {
     map: HashMap<AddressHash, PathEntry>,
 }
@@ -125,6 +138,42 @@ impl PathTable {
         )
     }
 
+    pub fn snapshot(&self) -> Vec<PathSnapshot> {
+        self.map
+            .iter()
+            .map(|(destination, entry)| PathSnapshot {
+                destination: *destination,
+                received_from: entry.received_from,
+                hops: entry.hops,
+                iface: entry.iface,
+                age: entry.timestamp.elapsed(),
+            })
+            .collect()
+    }
+
+    // Restored paths never replace one learned since startup. A path older
+    // than the monotonic clock can represent is dropped rather than
+    // restored as fresh.
+    pub fn restore(&mut self, path: PathSnapshot) -> bool {
+        if self.map.contains_key(&path.destination) {
+            return false;
+        }
+        let Some(timestamp) = Instant::now().checked_sub(path.age) else {
+            return false;
+        };
+        self.map.insert(
+            path.destination,
+            PathEntry {
+                timestamp,
+                received_from: path.received_from,
+                hops: path.hops,
+                iface: path.iface,
+                packet_hash: Hash::new_empty(),
+            },
+        );
+        true
+    }
+
     pub fn refresh(&mut self, destination: &AddressHash) {
         if let Some(entry) = self.map.get_mut(destination) {
             entry.timestamp = Instant::now();
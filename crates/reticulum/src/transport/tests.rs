@@ -1,4 +1,5 @@
 use super::announce::handle_announce;
+use super::wire::handle_data;
 use super::*;
 
 use crate::destination::link::{LinkEvent, LinkEventData, LinkPayload};
@@ -61,7 +62,7 @@ async fn drop_duplicates() {
             .await
     );
 
-    handle_announce(&announce, handler.lock().await, next_hop_iface).await;
+    handle_announce(&announce, handler.lock().await, next_hop_iface, None).await;
 
     let data_packet: Packet = Packet {
         data: PacketDataBuffer::new_from_slice(b"foo"),
@@ -137,7 +138,7 @@ async fn announce_retransmit_key_uses_destination_hash() {
     );
 
     let iface = AddressHash::new_from_rand(OsRng);
-    handle_announce(&announce, handler.lock().await, iface).await;
+    handle_announce(&announce, handler.lock().await, iface, None).await;
 
     let mut guard = handler.lock().await;
     let transport_id = *guard.config.identity.address_hash();
@@ -157,6 +158,106 @@ async fn announce_retransmit_key_uses_destination_hash() {
     );
 }
 
+#[tokio::test]
+async fn announce_dedup_lets_changed_app_data_through() {
+    let local_identity = PrivateIdentity::new_from_rand(OsRng);
+    let mut config = TransportConfig::new("test", &local_identity, true);
+    config.set_announce_dedup_window_secs(60);
+    let transport = Transport::new(config);
+    let handler = transport.get_handler();
+    let mut announces = transport.recv_announces().await;
+
+    let mut remote = SingleInputDestination::new(
+        PrivateIdentity::new_from_rand(OsRng),
+        DestinationName::new("lxmf", "delivery"),
+    );
+    let iface = AddressHash::new_from_rand(OsRng);
+    for app_data in [b"v1", b"v1", b"v2"] {
+        let announce = remote.announce(OsRng, Some(app_data)).unwrap();
+        handle_announce(&announce, handler.lock().await, iface, None).await;
+    }
+
+    let first = announces.try_recv().expect("first announce");
+    assert_eq!(first.app_data.as_slice(), b"v1");
+    let changed = announces.try_recv().expect("changed app data");
+    assert_eq!(changed.app_data.as_slice(), b"v2");
+    assert!(announces.try_recv().is_err());
+    assert_eq!(
+        transport.transport_metrics(),
+        TransportMetrics {
+            announces_deduped: 1,
+            announce_dedup_window_secs: 60,
+        }
+    );
+}
+
+#[tokio::test]
+async fn announce_event_carries_interface_link_quality() {
+    let local_identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &local_identity, true));
+    let handler = transport.get_handler();
+    let mut announces = transport.recv_announces().await;
+
+    let mut remote = SingleInputDestination::new(
+        PrivateIdentity::new_from_rand(OsRng),
+        DestinationName::new("lxmf", "delivery"),
+    );
+    let announce = remote.announce(OsRng, None).unwrap();
+    let quality = LinkQuality {
+        rssi: Some(-92.0),
+        snr: Some(7.5),
+        q: None,
+    };
+    let iface = AddressHash::new_from_rand(OsRng);
+    handle_announce(&announce, handler.lock().await, iface, Some(quality)).await;
+
+    let event = announces.try_recv().expect("announce event");
+    assert_eq!(event.quality, Some(quality));
+}
+
+#[test]
+fn announce_dedup_window_expires() {
+    let dedup = announce_dedup::AnnounceDedup::new(Duration::from_secs(5));
+    let destination = AddressHash::new_from_rand(OsRng);
+    let start = Instant::now();
+    assert!(dedup.admit_at(&destination, b"data", start));
+    assert!(!dedup.admit_at(&destination, b"data", start + Duration::from_secs(4)));
+    assert!(dedup.admit_at(&destination, b"data", start + Duration::from_secs(5)));
+
+    dedup.set_window(Duration::ZERO);
+    assert!(dedup.admit_at(&destination, b"data", start + Duration::from_secs(6)));
+    assert_eq!(dedup.deduped(), 1);
+}
+
+#[test]
+fn link_rtt_history_keeps_a_rolling_average_per_destination() {
+    let mut history = link_rtt::LinkRttHistory::default();
+    let destination = AddressHash::new_from_rand(OsRng);
+    let other = AddressHash::new_from_rand(OsRng);
+    assert_eq!(history.average(&destination), None);
+
+    history.record(destination, Duration::from_millis(100));
+    history.record(destination, Duration::from_millis(300));
+    history.record(other, Duration::from_millis(50));
+    assert_eq!(
+        history.average(&destination),
+        Some(Duration::from_millis(200))
+    );
+    assert_eq!(history.average(&other), Some(Duration::from_millis(50)));
+
+    // Only the most recent handshakes count once the window is full.
+    for _ in 0..8 {
+        history.record(destination, Duration::from_millis(40));
+    }
+    assert_eq!(
+        history.average(&destination),
+        Some(Duration::from_millis(40))
+    );
+
+    history.clear();
+    assert_eq!(history.average(&other), None);
+}
+
 #[tokio::test]
 async fn send_packet_with_outcome_reports_missing_identity() {
     let identity = PrivateIdentity::new_from_rand(OsRng);
@@ -214,3 +315,340 @@ async fn send_packet_with_outcome_drops_announce_without_route() {
 
     assert_eq!(outcome, SendPacketOutcome::DroppedNoRoute);
 }
+
+#[tokio::test]
+async fn destination_interface_hint_steers_unrouted_packets() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &identity, false));
+    let (mut radio, mut internet) = {
+        let manager = transport.iface_manager();
+        let mut manager = manager.lock().await;
+        (manager.new_channel(8), manager.new_channel(8))
+    };
+    let destination = AddressHash::new_from_rand(OsRng);
+    let packet = Packet {
+        context: PacketContext::KeepAlive,
+        data: PacketDataBuffer::new_from_slice(&[KEEP_ALIVE_REQUEST]),
+        destination,
+        ..Default::default()
+    };
+
+    let hints = transport.destination_interface_hints();
+    hints.set(destination, radio.address);
+    let trace = transport.send_packet_with_trace(packet).await;
+    assert_eq!(trace.outcome, SendPacketOutcome::SentDirect);
+    assert_eq!(trace.direct_iface, Some(radio.address));
+    assert_eq!(trace.hinted_iface, Some(radio.address));
+    assert!(radio.tx_channel.try_recv().is_ok());
+    assert!(internet.tx_channel.try_recv().is_err());
+
+    // Learning from announces must not override an explicit hint.
+    hints.learn(destination, internet.address);
+    assert_eq!(hints.get(&destination).expect("hint").iface, radio.address);
+
+    hints.set(destination, AddressHash::new_from_rand(OsRng));
+    let trace = transport.send_packet_with_trace(packet).await;
+    assert_eq!(trace.outcome, SendPacketOutcome::DroppedNoRoute);
+    assert!(trace.hinted_iface.is_some());
+
+    assert!(hints.clear(&destination));
+    hints.learn(destination, internet.address);
+    let trace = transport.send_packet_with_trace(packet).await;
+    assert_eq!(trace.direct_iface, Some(internet.address));
+    assert!(internet.tx_channel.try_recv().is_ok());
+}
+
+#[tokio::test]
+async fn delivery_proof_is_signed_by_the_receiving_destination() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let mut transport = Transport::new(TransportConfig::new("test", &identity, true));
+    let mut iface = transport.iface_manager().lock().await.new_channel(8);
+    let receiver = PrivateIdentity::new_from_rand(OsRng);
+    let destination = transport
+        .add_destination(receiver.clone(), DestinationName::new("lxmf", "delivery"))
+        .await;
+    let address = destination.lock().await.desc.address_hash;
+    let packet_hash = [7u8; HASH_SIZE];
+
+    assert!(
+        !transport
+            .send_delivery_proof(&AddressHash::new_from_rand(OsRng), &packet_hash)
+            .await
+    );
+    assert!(iface.tx_channel.try_recv().is_err());
+
+    assert!(transport.send_delivery_proof(&address, &packet_hash).await);
+    let proof = iface.tx_channel.try_recv().expect("proof sent").packet;
+    assert_eq!(proof.header.packet_type, PacketType::Proof);
+    assert_eq!(proof.destination.as_slice(), &packet_hash[..16]);
+    let (hash, signature) = proof.data.as_slice().split_at(HASH_SIZE);
+    assert_eq!(hash, packet_hash);
+    assert!(crate::identity::verify(
+        *receiver.as_identity().verifying_key_bytes(),
+        hash,
+        signature
+    ));
+}
+
+#[tokio::test]
+async fn retried_path_request_goes_out_on_the_relay_interface() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &identity, false));
+    let (mut radio, mut internet) = {
+        let manager = transport.iface_manager();
+        let mut manager = manager.lock().await;
+        (manager.new_channel(8), manager.new_channel(8))
+    };
+    let destination = AddressHash::new_from_rand(OsRng);
+    let relay = AddressHash::new_from_rand(OsRng);
+
+    assert!(
+        !transport
+            .request_path_on_relay_iface(&destination, &relay)
+            .await
+    );
+    assert!(radio.tx_channel.try_recv().is_err());
+    assert!(internet.tx_channel.try_recv().is_err());
+
+    let restored = transport
+        .restore_paths(vec![
+            PathSnapshot {
+                destination: relay,
+                received_from: relay,
+                hops: 1,
+                iface: internet.address,
+                age: Duration::from_secs(5),
+            },
+            // Older than the clock can represent, so it is not restored.
+            PathSnapshot {
+                destination,
+                received_from: relay,
+                hops: 1,
+                iface: internet.address,
+                age: Duration::MAX,
+            },
+        ])
+        .await;
+    assert_eq!(restored, 1);
+
+    assert!(
+        transport
+            .request_path_on_relay_iface(&destination, &relay)
+            .await
+    );
+    let request = internet.tx_channel.try_recv().expect("path request").packet;
+    assert_eq!(request.header.packet_type, PacketType::Data);
+    assert_eq!(&request.data.as_slice()[..16], destination.as_slice());
+    assert!(radio.tx_channel.try_recv().is_err());
+}
+
+#[test]
+fn interface_filter_drops_matching_packets_from_child_ifaces() {
+    use super::iface_filter::InterfaceFilter;
+
+    let table = InterfaceFilterTable::default();
+    let server_iface = AddressHash::new_from_rand(OsRng);
+    let client_iface = AddressHash::new_from_rand(OsRng);
+    table.set(
+        server_iface,
+        Some(InterfaceFilter {
+            drop_packet_types: vec![PacketType::Announce],
+            ..Default::default()
+        }),
+    );
+
+    let announce = Packet {
+        header: Header {
+            packet_type: PacketType::Announce,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let data = Packet::default();
+
+    assert!(table.should_drop(&[client_iface, server_iface], &announce, false));
+    assert!(!table.should_drop(&[client_iface, server_iface], &data, false));
+    assert!(!table.should_drop(&[client_iface], &announce, false));
+    assert_eq!(
+        table.get(&server_iface).expect("filter").filtered_packets,
+        1
+    );
+
+    table.set(server_iface, None);
+    assert!(table.is_empty());
+}
+
+#[tokio::test]
+async fn close_link_removes_link_from_snapshots() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &identity, true));
+    let peer = SingleInputDestination::new(
+        PrivateIdentity::new_from_rand(OsRng),
+        DestinationName::new("test", "link"),
+    );
+
+    let link = transport.link(peer.desc).await;
+    let link_id = *link.lock().await.id();
+
+    let snapshots = transport.link_snapshots().await;
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].id, link_id);
+    assert_eq!(snapshots[0].destination, peer.desc.address_hash);
+    assert!(snapshots[0].outbound);
+    assert_eq!(snapshots[0].status, LinkStatus::Pending);
+    assert!(snapshots[0].age.is_none());
+
+    assert!(transport.close_link(&link_id).await);
+    assert!(transport.link_snapshots().await.is_empty());
+    assert_eq!(link.lock().await.status(), LinkStatus::Closed);
+    assert!(!transport.close_link(&link_id).await);
+}
+
+#[tokio::test]
+async fn plain_packets_reach_registered_destinations() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &identity, true));
+    let address = transport
+        .add_plain_destination(DestinationName::new("beacon", "status"))
+        .await;
+    let mut rx = transport.received_data_events();
+
+    let packet =
+        PlainOutputDestination::new(EmptyIdentity {}, DestinationName::new("beacon", "status"))
+            .data_packet(b"repeater up")
+            .expect("plain packet");
+    assert_eq!(packet.destination, address);
+    assert_eq!(packet.header.destination_type, DestinationType::Plain);
+    assert_eq!(packet.data.as_slice(), b"repeater up");
+
+    let handler = transport.get_handler();
+    let iface = AddressHash::new_from_rand(OsRng);
+    handle_data(&packet, iface, handler.lock().await).await;
+
+    let received = timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("expected plain payload")
+        .expect("broadcast receive");
+    assert_eq!(received.destination, address);
+    assert_eq!(received.data.as_slice(), b"repeater up");
+
+    let other =
+        PlainOutputDestination::new(EmptyIdentity {}, DestinationName::new("beacon", "other"))
+            .data_packet(b"ignored")
+            .expect("plain packet");
+    handle_data(&other, iface, handler.lock().await).await;
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn bandwidth_limiter_keeps_bulk_rate_under_cap() {
+    use super::bandwidth::BandwidthLimiter;
+
+    const CAP: u64 = 1_000;
+    const PART: usize = 480;
+    let start = Instant::now();
+    let window = Duration::from_secs(10);
+    let mut limiter = BandwidthLimiter::new(Some(CAP), start);
+
+    let mut sent = 0usize;
+    let mut now = start;
+    while now < start + window {
+        while limiter.try_acquire(PART, now) {
+            sent += PART;
+        }
+        now += Duration::from_millis(10);
+    }
+    let rate = sent as f64 / window.as_secs_f64();
+    assert!(rate <= CAP as f64, "bulk rate {rate} exceeded cap {CAP}");
+    assert!(
+        rate >= CAP as f64 * 0.9,
+        "bulk rate {rate} far below cap {CAP}"
+    );
+
+    // Interactive traffic is charged to the same budget, so bulk yields to it.
+    let mut limiter = BandwidthLimiter::new(Some(CAP), start);
+    limiter.record(500, start);
+    assert!(!limiter.try_acquire(PART, start + Duration::from_millis(900)));
+    assert!(limiter.try_acquire(PART, start + Duration::from_millis(1000)));
+
+    let mut unlimited = BandwidthLimiter::new(None, start);
+    unlimited.record(usize::MAX, start);
+    assert!(unlimited.try_acquire(PART, start));
+
+    // However much priority traffic goes out, bulk resumes within the debt cap.
+    let mut limiter = BandwidthLimiter::new(Some(CAP), start);
+    limiter.record(1_000_000, start);
+    assert!(!limiter.try_acquire(PART, start + Duration::from_millis(2400)));
+    assert!(limiter.try_acquire(PART, start + Duration::from_millis(2500)));
+}
+
+#[tokio::test]
+async fn paced_queue_drops_its_oldest_packets_when_full() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &identity, false));
+    let bandwidth = transport.bandwidth_control();
+    // Too little for even one packet before the test ends.
+    bandwidth.set_limit(Some(1));
+    let handler = transport.get_handler();
+    let mut handler = handler.lock().await;
+    let packets = (0..MAX_PACED_PACKETS + 3)
+        .map(|_| Packet {
+            destination: AddressHash::new_from_rand(OsRng),
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    for packet in &packets {
+        handler.send_paced(*packet).await;
+    }
+
+    assert_eq!(handler.paced_packets.len(), MAX_PACED_PACKETS);
+    assert_eq!(bandwidth.paced_drops(), 3);
+    assert_eq!(
+        handler
+            .paced_packets
+            .front()
+            .map(|packet| packet.destination),
+        Some(packets[3].destination)
+    );
+}
+
+#[tokio::test]
+async fn reinitialize_stops_interfaces_and_forgets_learned_state() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let transport = Transport::new(TransportConfig::new("test", &identity, false));
+    let (radio, internet) = {
+        let manager = transport.iface_manager();
+        let mut manager = manager.lock().await;
+        (manager.new_channel(8), manager.new_channel(8))
+    };
+    let pinned = AddressHash::new_from_rand(OsRng);
+    let learned = AddressHash::new_from_rand(OsRng);
+    let hints = transport.destination_interface_hints();
+    hints.set(pinned, radio.address);
+    hints.learn(learned, internet.address);
+
+    transport.reinitialize().await;
+
+    assert!(radio.stop.is_cancelled());
+    assert!(internet.stop.is_cancelled());
+    assert!(hints.get(&learned).is_none());
+    assert_eq!(
+        hints.get(&pinned).expect("explicit hint").iface,
+        radio.address
+    );
+
+    let replacement = transport.iface_manager().lock().await.new_channel(8);
+    hints.remap(&radio.address, replacement.address);
+    assert_eq!(
+        hints.get(&pinned).expect("explicit hint").iface,
+        replacement.address
+    );
+    let packet = Packet {
+        context: PacketContext::KeepAlive,
+        data: PacketDataBuffer::new_from_slice(&[KEEP_ALIVE_REQUEST]),
+        destination: pinned,
+        ..Default::default()
+    };
+    let trace = transport.send_packet_with_trace(packet).await;
+    assert_eq!(trace.direct_iface, Some(replacement.address));
+}
@@ -65,12 +65,17 @@ pub(super) async fn handle_proof(packet: Packet, handler: Arc<Mutex<TransportHan
     let mut handler = handler.lock().await;
 
     let mut rtt_packets = Vec::new();
+    let mut rtt_samples = Vec::new();
     for link in handler.out_links.values() {
         let mut link = link.lock().await;
         if let LinkHandleResult::Activated = link.handle_packet(&packet) {
             rtt_packets.push(link.create_rtt());
+            rtt_samples.push((link.destination().address_hash, link.rtt()));
         }
     }
+    for (destination, rtt) in rtt_samples {
+        handler.link_rtts.record(destination, rtt);
+    }
     for packet in rtt_packets {
         handler.send_packet(packet).await;
     }
@@ -215,7 +220,11 @@ pub(super) async fn handle_data<'a>(
                 let events = handler.resource_manager.drain_events();
                 drop(link);
                 for response in responses {
-                    handler.send_packet(response).await;
+                    if response.context == PacketContext::Resource {
+                        handler.send_paced(response).await;
+                    } else {
+                        handler.send_packet(response).await;
+                    }
                 }
                 for event in events {
                     let _ = handler.resource_events_tx.send(event);
@@ -346,6 +355,7 @@ pub(super) async fn handle_data<'a>(
                     },
                     hops: Some(packet.header.hops),
                     interface: packet.transport.map(|value| value.as_slice().to_vec()),
+                    packet_hash: Some(packet.hash().to_bytes()),
                 })
                 .ok();
         } else {
@@ -353,6 +363,27 @@ pub(super) async fn handle_data<'a>(
         }
     }
 
+    if packet.header.destination_type == DestinationType::Plain
+        && handler
+            .plain_in_destinations
+            .contains_key(&packet.destination)
+    {
+        data_handled = true;
+        handler
+            .received_data_tx
+            .send(ReceivedData {
+                destination: packet.destination,
+                data: packet.data,
+                ratchet_used: false,
+                context: Some(packet.context),
+                request_id: None,
+                hops: Some(packet.header.hops),
+                interface: Some(iface.as_slice().to_vec()),
+                packet_hash: None,
+            })
+            .ok();
+    }
+
     if data_handled {
         log::trace!(
             "tp({}): handle data request for {} dst={:2x} ctx={:2x}",
@@ -73,3 +73,42 @@ async fn announce_scheduler_calls_announce_bridge_immediately() {
         })
         .await;
 }
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn announce_scheduler_follows_interval_changes() {
+    let store = MessagesStore::in_memory().expect("in-memory store");
+    let bridge = Arc::new(CounterAnnounceBridge::new());
+    let daemon = Rc::new(RpcDaemon::with_store_and_bridges(
+        store,
+        "test-identity".into(),
+        None,
+        Some(bridge.clone()),
+    ));
+    let local = LocalSet::new();
+
+    local
+        .run_until(async move {
+            let _handle = daemon.clone().start_announce_scheduler(0);
+            tokio::task::yield_now().await;
+            assert_eq!(bridge.calls.load(Ordering::Relaxed), 0);
+
+            // Turning it on announces straight away.
+            daemon.set_announce_interval_secs(60);
+            tokio::task::yield_now().await;
+            assert_eq!(bridge.calls.load(Ordering::Relaxed), 1);
+
+            // A shorter interval restarts the wait without an extra announce.
+            daemon.set_announce_interval_secs(10);
+            tokio::task::yield_now().await;
+            assert_eq!(bridge.calls.load(Ordering::Relaxed), 1);
+            advance(Duration::from_secs(10)).await;
+            tokio::task::yield_now().await;
+            assert_eq!(bridge.calls.load(Ordering::Relaxed), 2);
+
+            daemon.set_announce_interval_secs(0);
+            advance(Duration::from_secs(60)).await;
+            tokio::task::yield_now().await;
+            assert_eq!(bridge.calls.load(Ordering::Relaxed), 2);
+        })
+        .await;
+}
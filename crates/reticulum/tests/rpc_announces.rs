@@ -1,4 +1,9 @@
-use reticulum::rpc::{RpcDaemon, RpcRequest};
+use std::sync::Arc;
+
+use reticulum::rpc::{
+    AnnounceBridge, AnnounceDetails, AnnounceFuture, PresenceThresholds, RpcDaemon, RpcRequest,
+};
+use reticulum::storage::messages::MessagesStore;
 use serde_json::json;
 
 #[test]
@@ -497,3 +502,815 @@ fn list_announces_accepts_cursor_and_returns_next_cursor() {
         .collect();
     assert_eq!(page_2_timestamps, vec![200, 100]);
 }
+
+struct FixedAnnounceBridge {
+    fail: bool,
+}
+
+impl AnnounceBridge for FixedAnnounceBridge {
+    fn announce_now(&self) -> Result<(), std::io::Error> {
+        if self.fail {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "no interfaces up",
+            ));
+        }
+        Ok(())
+    }
+
+    fn announce_details(&self) -> Option<AnnounceDetails> {
+        Some(AnnounceDetails {
+            destination_hash: "00112233445566778899aabbccddeeff".into(),
+            app_data_hex: Some("c0".into()),
+            aspects: "lxmf.delivery".into(),
+        })
+    }
+}
+
+#[test]
+fn announce_now_returns_announced_destination() {
+    let store = MessagesStore::in_memory().expect("in-memory store");
+    let daemon = RpcDaemon::with_store_and_bridges(
+        store,
+        "daemon".into(),
+        None,
+        Some(Arc::new(FixedAnnounceBridge { fail: false })),
+    );
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 7,
+            method: "announce_now".into(),
+            params: None,
+        })
+        .unwrap();
+
+    let result = resp.result.expect("result");
+    assert_eq!(result["announce_id"], json!(7));
+    assert_eq!(
+        result["destination_hash"],
+        json!("00112233445566778899aabbccddeeff")
+    );
+    assert_eq!(result["app_data_hex"], json!("c0"));
+    assert_eq!(result["aspects"], json!("lxmf.delivery"));
+    let event = daemon.take_event().expect("announce event");
+    assert_eq!(
+        event.payload["destination_hash"],
+        json!("00112233445566778899aabbccddeeff")
+    );
+}
+
+#[test]
+fn announce_now_surfaces_bridge_failure() {
+    let store = MessagesStore::in_memory().expect("in-memory store");
+    let daemon = RpcDaemon::with_store_and_bridges(
+        store,
+        "daemon".into(),
+        None,
+        Some(Arc::new(FixedAnnounceBridge { fail: true })),
+    );
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 8,
+            method: "announce_now".into(),
+            params: None,
+        })
+        .unwrap();
+
+    assert!(resp.result.is_none());
+    assert_eq!(resp.error.expect("error").code, "ANNOUNCE_FAILED");
+    assert!(daemon.take_event().is_none());
+}
+
+#[test]
+fn list_peers_reports_last_contact_from_messages() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    for (peer, timestamp) in [("chatty", 100), ("quiet", 200)] {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "announce_received".into(),
+                params: Some(json!({ "peer": peer, "timestamp": timestamp })),
+            })
+            .unwrap();
+    }
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "receive_message".into(),
+            params: Some(json!({
+                "id": "inbound-1",
+                "source": "chatty",
+                "destination": "local",
+                "content": "hello"
+            })),
+        })
+        .unwrap();
+
+    let peers = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "list_peers".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap()["peers"]
+        .clone();
+
+    assert_eq!(peers[0]["peer"], "chatty");
+    assert_eq!(peers[0]["last_seen"], 100);
+    assert!(peers[0]["last_contact"].as_i64().unwrap() > 200);
+    assert_eq!(peers[0]["message_count"], 1);
+    assert_eq!(peers[1]["peer"], "quiet");
+    assert_eq!(peers[1]["last_contact"], 200);
+    assert_eq!(peers[1]["message_count"], 0);
+}
+
+#[test]
+fn peer_presence_follows_thresholds_and_reports_changes() {
+    let daemon = RpcDaemon::test_instance();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    for (peer, timestamp) in [
+        ("near", now),
+        ("fading", now - 2000),
+        ("gone", now - 10_000),
+    ] {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "announce_received".into(),
+                params: Some(json!({ "peer": peer, "timestamp": timestamp })),
+            })
+            .unwrap();
+    }
+    while daemon.take_event().is_some() {}
+
+    let peers = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_peers".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap()["peers"]
+        .clone();
+    let presence = |peer: &str| {
+        peers
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["peer"] == peer)
+            .unwrap()["presence"]
+            .clone()
+    };
+    assert_eq!(presence("near"), "online");
+    assert_eq!(presence("fading"), "stale");
+    assert_eq!(presence("gone"), "offline");
+
+    assert_eq!(daemon.sweep_peer_presence().unwrap(), 3);
+    assert_eq!(daemon.sweep_peer_presence().unwrap(), 0);
+    while daemon.take_event().is_some() {}
+
+    daemon.set_presence_thresholds(PresenceThresholds {
+        online_secs: 3000,
+        stale_secs: 20_000,
+    });
+    assert_eq!(daemon.sweep_peer_presence().unwrap(), 2);
+    let mut changes = Vec::new();
+    while let Some(event) = daemon.take_event() {
+        assert_eq!(event.event_type, "peer_presence_changed");
+        changes.push(event.payload);
+    }
+    changes.sort_by_key(|payload| payload["peer"].as_str().unwrap().to_string());
+    assert_eq!(changes[0]["peer"], "fading");
+    assert_eq!(changes[0]["previous"], "stale");
+    assert_eq!(changes[0]["presence"], "online");
+    assert_eq!(changes[1]["peer"], "gone");
+    assert_eq!(changes[1]["presence"], "stale");
+}
+
+fn announce_event_for_app_data(app_data: serde_json::Value) -> serde_json::Value {
+    let daemon = RpcDaemon::test_instance();
+    let app_data = rmp_serde::to_vec(&app_data).expect("encode app data");
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "announce_received".into(),
+            params: Some(json!({
+                "peer": "peer-layout",
+                "timestamp": 1300,
+                "app_data_hex": hex::encode(app_data),
+            })),
+        })
+        .expect("announce_received");
+    daemon.take_event().expect("announce event").payload
+}
+
+#[test]
+fn announce_app_data_parsing_tolerates_other_layouts() {
+    // Newer propagation node with extra trailing fields.
+    let longer = announce_event_for_app_data(json!([
+        false,
+        1_700_000_321,
+        true,
+        10,
+        20,
+        [40, 4, 9, 1],
+        { "name": "Node" },
+        "future",
+        [1, 2, 3]
+    ]));
+    assert_eq!(longer["capabilities"], json!(["propagation"]));
+    assert_eq!(longer["stamp_cost_flexibility"], 4);
+    assert_eq!(longer["peering_cost"], 9);
+
+    // Truncated propagation node announce without a costs entry.
+    let shorter = announce_event_for_app_data(json!([false, 1_700_000_321, true]));
+    assert_eq!(shorter["capabilities"], json!(["propagation"]));
+    assert!(shorter["stamp_cost_flexibility"].is_null());
+    assert!(shorter["peering_cost"].is_null());
+
+    // Delivery announce padded out past the propagation costs position.
+    let delivery = announce_event_for_app_data(json!(["name", 8, "x", 0, 0, [1, 2, 3]]));
+    assert_eq!(delivery["capabilities"], json!([]));
+    assert!(delivery["stamp_cost_flexibility"].is_null());
+    assert!(delivery["peering_cost"].is_null());
+
+    let keyed = announce_event_for_app_data(json!({
+        "version": 2,
+        "propagation": true,
+        "caps": ["Commands"],
+        "costs": { "stamp_cost_flexibility": 3, "peering_cost": 7 },
+    }));
+    assert_eq!(keyed["capabilities"], json!(["propagation", "commands"]));
+    assert_eq!(keyed["stamp_cost_flexibility"], 3);
+    assert_eq!(keyed["peering_cost"], 7);
+}
+
+#[test]
+fn clear_peer_announces_only_drops_that_peers_history() {
+    let daemon = RpcDaemon::test_instance();
+    for (id, peer, timestamp) in [(1, "chatty", 10), (2, "chatty", 11), (3, "quiet", 12)] {
+        daemon
+            .handle_rpc(RpcRequest {
+                id,
+                method: "announce_received".into(),
+                params: Some(json!({ "peer": peer, "timestamp": timestamp })),
+            })
+            .expect("announce_received");
+    }
+
+    let cleared = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "clear_peer_announces".into(),
+            params: Some(json!({ "peer": "chatty", "remove_peer": true })),
+        })
+        .expect("clear_peer_announces")
+        .result
+        .expect("result");
+    assert_eq!(cleared["removed_announces"], 2);
+    assert_eq!(cleared["removed_peer"], true);
+
+    let announces = daemon
+        .handle_rpc(RpcRequest {
+            id: 5,
+            method: "list_announces".into(),
+            params: None,
+        })
+        .expect("list_announces")
+        .result
+        .expect("result");
+    let announces = announces["announces"].as_array().expect("announce list");
+    assert_eq!(announces.len(), 1);
+    assert_eq!(announces[0]["peer"], "quiet");
+
+    let peers = daemon
+        .handle_rpc(RpcRequest {
+            id: 6,
+            method: "list_peers".into(),
+            params: None,
+        })
+        .expect("list_peers")
+        .result
+        .expect("result");
+    let peers = peers["peers"].as_array().expect("peer list");
+    assert!(peers.iter().all(|peer| peer["peer"] != "chatty"));
+    assert!(peers.iter().any(|peer| peer["peer"] == "quiet"));
+}
+
+struct AspectAnnounceBridge;
+
+impl AnnounceBridge for AspectAnnounceBridge {
+    fn announce_now(&self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn add_announce_aspect(
+        &self,
+        _app: &str,
+        _aspects: &str,
+        _app_data: Option<Vec<u8>>,
+    ) -> Result<String, std::io::Error> {
+        Ok("ffeeddccbbaa99887766554433221100".into())
+    }
+}
+
+#[test]
+fn add_announce_aspect_is_announced_and_listed() {
+    let store = MessagesStore::in_memory().expect("in-memory store");
+    let daemon = RpcDaemon::with_store_and_bridges(
+        store,
+        "daemon".into(),
+        None,
+        Some(Arc::new(AspectAnnounceBridge)),
+    );
+    let added = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "add_announce_aspect".into(),
+            params: Some(json!({
+                "app": "lxmf",
+                "aspects": "propagation",
+                "app_data_hex": "c0",
+            })),
+        })
+        .unwrap()
+        .result
+        .expect("result");
+    assert_eq!(added["aspects"], json!("lxmf.propagation"));
+    assert_eq!(added["app_data_hex"], json!("c0"));
+    assert_eq!(added["announce_aspects"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        daemon.take_event().expect("event").event_type,
+        "announce_aspect_added"
+    );
+
+    let announced = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "announce_now".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .expect("result");
+    let aspects: Vec<&str> = announced["announced_aspects"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["aspects"].as_str().unwrap())
+        .collect();
+    assert_eq!(aspects, vec!["lxmf.delivery", "lxmf.propagation"]);
+
+    let status = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "daemon_status_ex".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .expect("result");
+    assert_eq!(status["announce_aspects"][1]["aspects"], "lxmf.propagation");
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "add_announce_aspect".into(),
+            params: Some(json!({ "app": "lxmf", "aspects": "delivery" })),
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn peers_over_the_cap_evict_the_least_recently_seen() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_max_peers(3);
+    let announce = |peer: &str, timestamp: i64| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "announce_received".into(),
+                params: Some(serde_json::json!({ "peer": peer, "timestamp": timestamp })),
+            })
+            .unwrap();
+    };
+    announce("peer-a", 100);
+    announce("peer-b", 101);
+    announce("peer-c", 102);
+    announce("peer-a", 103);
+    announce("peer-d", 104);
+    announce("peer-e", 105);
+
+    let peers = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_peers".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    let mut names: Vec<&str> = peers["peers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|peer| peer["peer"].as_str().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["peer-a", "peer-d", "peer-e"]);
+
+    let announces = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "list_announces".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    let announces = announces["announces"].as_array().unwrap();
+    assert!(!announces.is_empty());
+    assert!(announces
+        .iter()
+        .all(|announce| !matches!(announce["peer"].as_str(), Some("peer-b" | "peer-c"))));
+
+    let status = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "daemon_status_ex".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(status["peer_count"], 3);
+    assert_eq!(status["max_peers"], 3);
+}
+
+#[test]
+fn announce_received_verifies_signatures_for_known_identities() {
+    use rand_core::OsRng;
+    use reticulum::destination::{lxmf_delivery_hash_from_identity_hash, DestinationName};
+    use reticulum::identity::{lxmf_sign, PrivateIdentity};
+    use reticulum::rpc::announce_signing_bytes;
+
+    let daemon = RpcDaemon::test_instance();
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let public = *identity.as_identity();
+    let peer = lxmf_delivery_hash_from_identity_hash(&public.address_hash).to_hex_string();
+    let app_data = b"app data";
+    let name_hash = DestinationName::new("lxmf", "delivery")
+        .as_name_hash_slice()
+        .to_vec();
+    let random_hash = [7u8; 10];
+    let sign = |identity: &PrivateIdentity, data: &[u8]| {
+        hex::encode(lxmf_sign(
+            identity,
+            &announce_signing_bytes(
+                &peer,
+                identity.as_identity(),
+                &name_hash,
+                &random_hash,
+                None,
+                data,
+            )
+            .unwrap(),
+        ))
+    };
+    let announce = |params: serde_json::Value| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "announce_received".into(),
+                params: Some(params),
+            })
+            .unwrap()
+    };
+    let rejected = |daemon: &RpcDaemon| {
+        let event = daemon.take_event().expect("announce_rejected");
+        assert_eq!(event.event_type, "announce_rejected");
+        event.payload["reason"].as_str().unwrap().to_string()
+    };
+
+    let first = announce(json!({
+        "peer": peer,
+        "timestamp": 1,
+        "app_data_hex": hex::encode(app_data),
+        "identity_hex": public.to_hex_string(),
+        "signature_hex": sign(&identity, app_data),
+        "random_hash_hex": hex::encode(random_hash),
+    }));
+    assert!(first.error.is_none());
+    while daemon.take_event().is_some() {}
+
+    // The identity is remembered, so later announces must be signed by it.
+    let unsigned = announce(json!({ "peer": peer, "timestamp": 2 }));
+    assert_eq!(unsigned.error.unwrap().code, "ANNOUNCE_REJECTED");
+    assert!(rejected(&daemon).contains("must be signed"));
+
+    let forger = PrivateIdentity::new_from_rand(OsRng);
+    let forged = announce(json!({
+        "peer": peer,
+        "timestamp": 3,
+        "app_data_hex": hex::encode(b"fake caps"),
+        "signature_hex": sign(&forger, b"fake caps"),
+        "random_hash_hex": hex::encode(random_hash),
+    }));
+    assert_eq!(forged.error.unwrap().code, "ANNOUNCE_REJECTED");
+    assert!(rejected(&daemon).contains("does not verify"));
+
+    let swapped = announce(json!({
+        "peer": peer,
+        "timestamp": 4,
+        "identity_hex": forger.as_identity().to_hex_string(),
+        "signature_hex": sign(&forger, b""),
+        "random_hash_hex": hex::encode(random_hash),
+    }));
+    assert_eq!(swapped.error.unwrap().code, "ANNOUNCE_REJECTED");
+    rejected(&daemon);
+
+    let signed = announce(json!({
+        "peer": peer,
+        "timestamp": 5,
+        "signature_hex": sign(&identity, b""),
+        "random_hash_hex": hex::encode(random_hash),
+    }));
+    assert_eq!(signed.result.unwrap()["peer"]["last_seen"], 5);
+
+    while daemon.take_event().is_some() {}
+
+    // A signature over the destination hash and app data alone no longer passes.
+    let partial = announce(json!({
+        "peer": peer,
+        "timestamp": 6,
+        "random_hash_hex": hex::encode(random_hash),
+        "signature_hex": hex::encode(lxmf_sign(&identity, &hex::decode(&peer).unwrap())),
+    }));
+    assert_eq!(partial.error.unwrap().code, "ANNOUNCE_REJECTED");
+    assert!(rejected(&daemon).contains("does not verify"));
+}
+
+#[test]
+fn announce_received_accepts_the_fields_of_a_wire_announce() {
+    use rand_core::OsRng;
+    use reticulum::destination::{
+        DestinationName, SingleInputDestination, NAME_HASH_LENGTH, RAND_HASH_LENGTH,
+    };
+    use reticulum::identity::PrivateIdentity;
+
+    let daemon = RpcDaemon::test_instance();
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let mut destination =
+        SingleInputDestination::new(identity.clone(), DestinationName::new("lxmf", "delivery"));
+    let app_data = b"wire app data";
+    let packet = destination.announce(OsRng, Some(app_data)).unwrap();
+
+    // public key | verifying key | name hash | random hash | signature | app data
+    let data = packet.data.as_slice();
+    let (keys, rest) = data.split_at(64);
+    let (name_hash, rest) = rest.split_at(NAME_HASH_LENGTH);
+    let (random_hash, rest) = rest.split_at(RAND_HASH_LENGTH);
+    let (signature, announced) = rest.split_at(64);
+    assert_eq!(announced, app_data);
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "announce_received".into(),
+            params: Some(json!({
+                "peer": destination.desc.address_hash.to_hex_string(),
+                "timestamp": 1,
+                "app_data_hex": hex::encode(app_data),
+                "identity_hex": hex::encode(keys),
+                "name_hash_hex": hex::encode(name_hash),
+                "random_hash_hex": hex::encode(random_hash),
+                "signature_hex": hex::encode(signature),
+            })),
+        })
+        .unwrap();
+    assert!(response.error.is_none(), "{:?}", response.error);
+}
+
+#[test]
+fn unsigned_announces_are_rejected_when_required() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_require_signed_announces(true);
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "announce_received".into(),
+            params: Some(json!({ "peer": "peer-a", "timestamp": 1 })),
+        })
+        .unwrap();
+    assert_eq!(resp.error.unwrap().code, "ANNOUNCE_REJECTED");
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_peers".into(),
+            params: None,
+        })
+        .unwrap();
+    assert_eq!(listed.result.unwrap()["peers"], json!([]));
+}
+
+#[test]
+fn announces_and_peers_filter_by_interface() {
+    let daemon = RpcDaemon::test_instance();
+    let call = |method: &str, params: serde_json::Value| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: method.into(),
+                params: Some(params),
+            })
+            .unwrap()
+            .result
+            .unwrap()
+    };
+    call(
+        "announce_received",
+        json!({ "peer": "peer-a", "timestamp": 1, "interface": "lan" }),
+    );
+    call(
+        "announce_received",
+        json!({ "peer": "peer-b", "timestamp": 2, "interface": "radio" }),
+    );
+    // A later announce without an interface keeps the one already known.
+    call(
+        "announce_received",
+        json!({ "peer": "peer-a", "timestamp": 3 }),
+    );
+
+    let announces = call("list_announces", json!({ "interface": "radio" }));
+    let announces = announces["announces"].as_array().unwrap();
+    assert_eq!(announces.len(), 1);
+    assert_eq!(announces[0]["peer"], "peer-b");
+    assert_eq!(announces[0]["interface"], "radio");
+
+    let peers = call("list_peers", json!({ "interface": "lan" }));
+    let peers = peers["peers"].as_array().unwrap();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0]["peer"], "peer-a");
+    assert_eq!(peers[0]["interface"], "lan");
+
+    let all = call("list_peers", json!({}));
+    assert_eq!(all["peers"].as_array().unwrap().len(), 2);
+}
+
+#[derive(Default)]
+struct NamedAnnounceBridge {
+    app_data_hex: std::sync::Mutex<Option<String>>,
+    announces: std::sync::Mutex<u32>,
+}
+
+impl AnnounceBridge for NamedAnnounceBridge {
+    fn announce_now(&self) -> Result<(), std::io::Error> {
+        *self.announces.lock().unwrap() += 1;
+        Ok(())
+    }
+
+    fn announce_details(&self) -> Option<AnnounceDetails> {
+        Some(AnnounceDetails {
+            destination_hash: "00112233445566778899aabbccddeeff".into(),
+            app_data_hex: self.app_data_hex.lock().unwrap().clone(),
+            aspects: "lxmf.delivery".into(),
+        })
+    }
+
+    fn set_display_name(&self, display_name: &str) -> Result<String, std::io::Error> {
+        let name = display_name.trim();
+        if name.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "empty display name",
+            ));
+        }
+        *self.app_data_hex.lock().unwrap() = Some(hex::encode(name));
+        Ok(name.to_string())
+    }
+}
+
+#[test]
+fn set_display_name_updates_announce_and_persists() {
+    let bridge = Arc::new(NamedAnnounceBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridges(
+        MessagesStore::in_memory().expect("in-memory store"),
+        "daemon".into(),
+        None,
+        Some(bridge.clone()),
+    );
+    let call = |params: serde_json::Value| {
+        daemon.handle_rpc(RpcRequest {
+            id: 1,
+            method: "set_display_name".into(),
+            params: Some(params),
+        })
+    };
+
+    let result = call(json!({ "display_name": "  Alice  " }))
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(result["display_name"], "Alice");
+    assert_eq!(result["app_data_hex"], hex::encode("Alice"));
+    assert_eq!(result["announced"], true);
+    assert_eq!(*bridge.announces.lock().unwrap(), 1);
+
+    let event = daemon.take_event().expect("display_name_changed");
+    assert_eq!(event.event_type, "display_name_changed");
+    assert_eq!(event.payload["display_name"], "Alice");
+
+    assert!(call(json!({ "display_name": "  " })).is_err());
+
+    let settings = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_settings".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(settings["settings"]["display_name"], "Alice");
+    assert!(settings["persisted"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("display_name")));
+
+    let detached = RpcDaemon::test_instance();
+    let response = detached
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "set_display_name".into(),
+            params: Some(json!({ "display_name": "Bob" })),
+        })
+        .unwrap();
+    assert_eq!(response.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+}
+
+// Hands announces to no interface, as a daemon with every interface down does.
+struct UnsentAnnounceBridge;
+
+impl AnnounceBridge for UnsentAnnounceBridge {
+    fn announce_now(&self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn announce_confirmed(&self) -> AnnounceFuture {
+        Box::pin(async {
+            Err(std::io::Error::other(
+                "announce was not sent on any interface",
+            ))
+        })
+    }
+
+    fn set_display_name(&self, display_name: &str) -> Result<String, std::io::Error> {
+        Ok(display_name.to_string())
+    }
+}
+
+#[tokio::test]
+async fn announces_that_reach_no_interface_are_reported_as_failed() {
+    let daemon = RpcDaemon::with_store_and_bridges(
+        MessagesStore::in_memory().expect("in-memory store"),
+        "daemon".into(),
+        None,
+        Some(Arc::new(UnsentAnnounceBridge)),
+    );
+
+    let response = daemon
+        .handle_rpc_async(RpcRequest {
+            id: 1,
+            method: "announce_now".into(),
+            params: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(response.error.unwrap().code, "ANNOUNCE_FAILED");
+    assert!(daemon.take_event().is_none());
+
+    let result = daemon
+        .handle_rpc_async(RpcRequest {
+            id: 2,
+            method: "set_display_name".into(),
+            params: Some(json!({ "display_name": "Alice" })),
+        })
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(result["display_name"], "Alice");
+    assert_eq!(result["announced"], false);
+    assert_eq!(
+        result["announce_error"],
+        "announce was not sent on any interface"
+    );
+}
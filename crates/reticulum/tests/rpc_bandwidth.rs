@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use reticulum::rpc::{BandwidthBridge, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use reticulum::transport::bandwidth::BandwidthControl;
+use serde_json::json;
+
+struct ControlBridge(BandwidthControl);
+
+impl BandwidthBridge for ControlBridge {
+    fn bandwidth_limit(&self) -> Option<u64> {
+        self.0.limit()
+    }
+
+    fn set_bandwidth_limit(&self, bytes_per_sec: Option<u64>) -> Result<(), std::io::Error> {
+        self.0.set_limit(bytes_per_sec);
+        Ok(())
+    }
+
+    fn paced_drops(&self) -> u64 {
+        self.0.paced_drops()
+    }
+}
+
+fn call(daemon: &RpcDaemon, method: &str, params: Option<serde_json::Value>) -> serde_json::Value {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params,
+        })
+        .unwrap()
+        .result
+        .unwrap()
+}
+
+#[test]
+fn set_bandwidth_limit_updates_transport_control() {
+    let control = BandwidthControl::default();
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_bandwidth_bridge(Arc::new(ControlBridge(control.clone())));
+
+    let status = call(&daemon, "get_bandwidth_limit", None);
+    assert_eq!(status["bytes_per_sec"], json!(null));
+    assert_eq!(status["paced_drops"], 0);
+    let set = call(
+        &daemon,
+        "set_bandwidth_limit",
+        Some(json!({ "bytes_per_sec": 1200 })),
+    );
+    assert_eq!(set["bytes_per_sec"], 1200);
+    assert_eq!(control.limit(), Some(1200));
+
+    call(
+        &daemon,
+        "set_bandwidth_limit",
+        Some(json!({ "bytes_per_sec": 0 })),
+    );
+    assert_eq!(control.limit(), None);
+}
+
+#[test]
+fn set_bandwidth_limit_requires_transport() {
+    let daemon = RpcDaemon::test_instance();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "set_bandwidth_limit".into(),
+            params: Some(json!({ "bytes_per_sec": 1200 })),
+        })
+        .unwrap();
+    assert_eq!(response.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+}
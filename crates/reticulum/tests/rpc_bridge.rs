@@ -105,3 +105,723 @@ fn send_message_reports_delivery_failure() {
         .unwrap_or_default()
         .starts_with("failed:"));
 }
+
+#[test]
+fn resending_a_message_counts_delivery_attempts() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().expect("store"),
+        "test".into(),
+        Arc::new(FailingBridge),
+    );
+    let send = |id: u64| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id,
+                method: "send_message".into(),
+                params: Some(json!({
+                    "id": "msg-retry",
+                    "source": "alice",
+                    "destination": "bob",
+                    "content": "hi"
+                })),
+            })
+            .expect("rpc response");
+        let mut failed = None;
+        while let Some(event) = daemon.take_event() {
+            if event.event_type == "delivery_failed" {
+                failed = Some(event);
+            }
+        }
+        failed.expect("delivery_failed").payload["attempts"].clone()
+    };
+
+    assert_eq!(send(1), 1);
+    assert_eq!(send(2), 2);
+}
+
+#[derive(Default)]
+struct RecordingBridge {
+    delivered: Mutex<Vec<(String, Option<String>)>>,
+}
+
+impl OutboundBridge for RecordingBridge {
+    fn deliver(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.delivered
+            .lock()
+            .expect("delivered")
+            .push((record.id.clone(), options.method.clone()));
+        Ok(())
+    }
+}
+
+#[test]
+fn resume_pending_outbound_redelivers_unfinished_messages() {
+    use reticulum::storage::messages::{MessageRecord, MessagesStore};
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let store = MessagesStore::in_memory().expect("store");
+    let messages = [
+        (
+            "pending",
+            now - 60,
+            "out",
+            None,
+            Some(json!({ "_lxmf": { "method": "link" } })),
+        ),
+        ("timed-out", now - 30, "out", Some("failed: timeout"), None),
+        ("delivered", now - 20, "out", Some("delivered"), None),
+        ("stale", now - 7200, "out", None, None),
+        ("inbound", now - 10, "in", None, None),
+        ("sent-as-other", now - 40, "out", None, None),
+    ];
+    for (id, timestamp, direction, status, fields) in messages {
+        let source = if id == "sent-as-other" { "other" } else { "me" };
+        store
+            .insert_message(&MessageRecord {
+                id: id.into(),
+                source: source.into(),
+                destination: "peer".into(),
+                title: String::new(),
+                content: "hi".into(),
+                timestamp,
+                direction: direction.into(),
+                fields,
+                receipt_status: status.map(str::to_string),
+                delivery_method: None,
+                content_type: None,
+                attempts: 0,
+            })
+            .expect("insert");
+    }
+    let bridge = Arc::new(RecordingBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(store, "me".into(), bridge.clone());
+
+    let resumed = daemon.resume_pending_outbound(3600).expect("resume");
+    assert_eq!(
+        resumed,
+        vec!["pending".to_string(), "timed-out".to_string()]
+    );
+    assert_eq!(
+        *bridge.delivered.lock().unwrap(),
+        vec![
+            ("pending".to_string(), Some("link".to_string())),
+            ("timed-out".to_string(), None),
+        ]
+    );
+
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "pending" })),
+        })
+        .expect("trace")
+        .result
+        .expect("result");
+    assert!(trace["transitions"]
+        .as_array()
+        .expect("transitions")
+        .iter()
+        .any(|entry| entry["status"] == "resumed"));
+
+    let pending = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_message".into(),
+            params: Some(json!({ "message_id": "pending" })),
+        })
+        .expect("get_message")
+        .result
+        .expect("result");
+    assert_eq!(pending["message"]["attempts"], 1);
+
+    // The key it was signed with is gone, so it fails instead of going out
+    // under the daemon's signature.
+    let other = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "get_message".into(),
+            params: Some(json!({ "message_id": "sent-as-other" })),
+        })
+        .expect("get_message")
+        .result
+        .expect("result");
+    assert_eq!(
+        other["message"]["receipt_status"],
+        reticulum::rpc::SOURCE_KEY_UNAVAILABLE
+    );
+    assert_eq!(other["message"]["attempts"], 0);
+}
+
+#[derive(Default)]
+struct DeadlineBridge {
+    deadlines: Mutex<Vec<Option<i64>>>,
+}
+
+impl OutboundBridge for DeadlineBridge {
+    fn deliver(
+        &self,
+        _record: &reticulum::storage::messages::MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.deadlines.lock().unwrap().push(options.deliver_by);
+        Ok(())
+    }
+}
+
+#[test]
+fn deliver_by_reaches_the_bridge_and_expired_messages_fail() {
+    use reticulum::storage::messages::{MessageRecord, MessagesStore};
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let store = MessagesStore::in_memory().expect("store");
+    store
+        .insert_message(&MessageRecord {
+            id: "expired".into(),
+            source: "me".into(),
+            destination: "peer".into(),
+            title: String::new(),
+            content: "moving now".into(),
+            timestamp: now - 120,
+            direction: "out".into(),
+            fields: Some(json!({ "_lxmf": { "deliver_by": now - 60 } })),
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .expect("insert");
+    let bridge = Arc::new(DeadlineBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(store, "me".into(), bridge.clone());
+
+    assert!(daemon
+        .resume_pending_outbound(3600)
+        .expect("resume")
+        .is_empty());
+    let event = daemon.take_event().expect("delivery_failed");
+    assert_eq!(event.event_type, "delivery_failed");
+    assert_eq!(event.payload["message_id"], "expired");
+    assert_eq!(event.payload["reason_code"], "deadline_exceeded");
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": "late",
+                "source": "me",
+                "destination": "peer",
+                "content": "too late",
+                "deliver_by": now - 1,
+            })),
+        })
+        .expect_err("past deadline");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": "alert",
+                "source": "me",
+                "destination": "peer",
+                "content": "moving now",
+                "deliver_by": now + 300,
+            })),
+        })
+        .expect("send");
+    assert_eq!(*bridge.deadlines.lock().unwrap(), vec![Some(now + 300)]);
+
+    let receipt = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "record_receipt".into(),
+            params: Some(json!({
+                "message_id": "alert",
+                "status": reticulum::rpc::DELIVERY_DEADLINE_EXCEEDED,
+            })),
+        })
+        .expect("receipt")
+        .result
+        .expect("result");
+    assert_eq!(receipt["reason_code"], "deadline_exceeded");
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "alert" })),
+        })
+        .expect("trace")
+        .result
+        .expect("result");
+    assert!(trace["transitions"]
+        .as_array()
+        .expect("transitions")
+        .iter()
+        .any(|entry| entry["status"] == "failed: deadline exceeded"));
+    assert!(
+        std::iter::from_fn(|| daemon.take_event())
+            .any(|event| event.event_type == "delivery_failed"
+                && event.payload["message_id"] == "alert")
+    );
+}
+
+#[test]
+fn send_message_to_self_loops_back_without_the_bridge() {
+    let calls = Arc::new(Mutex::new(0));
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().expect("store"),
+        "00112233445566778899aabbccddeeff".into(),
+        Arc::new(TestBridge {
+            calls: calls.clone(),
+        }),
+    );
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "note-1",
+                "source": "00112233445566778899aabbccddeeff",
+                "destination": "00112233445566778899AABBCCDDEEFF",
+                "content": "remember the milk",
+            })),
+        })
+        .expect("send_message")
+        .result
+        .expect("result");
+    assert_eq!(*calls.lock().unwrap(), 0);
+
+    let events: Vec<String> = std::iter::from_fn(|| daemon.take_event())
+        .map(|event| event.event_type)
+        .collect();
+    assert_eq!(events, vec!["inbound", "outbound"]);
+
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_messages".into(),
+            params: Some(json!({})),
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    let messages = listed["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 2);
+    let outbound = messages
+        .iter()
+        .find(|message| message["direction"] == "out")
+        .unwrap();
+    assert_eq!(outbound["receipt_status"], "sent: loopback");
+    assert_eq!(outbound["delivery_method"], "loopback");
+    let inbound = messages
+        .iter()
+        .find(|message| message["direction"] == "in")
+        .unwrap();
+    assert_eq!(inbound["id"], "note-1:loopback");
+    assert_eq!(inbound["content"], "remember the milk");
+}
+
+struct UnreachableBridge {
+    calls: Arc<Mutex<u32>>,
+}
+
+impl OutboundBridge for UnreachableBridge {
+    fn deliver(
+        &self,
+        _record: &reticulum::storage::messages::MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        *self.calls.lock().expect("calls") += 1;
+        Ok(())
+    }
+
+    fn has_path(&self, _destination: &str) -> Option<bool> {
+        Some(false)
+    }
+
+    fn wire_size(
+        &self,
+        _record: &reticulum::storage::messages::MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<Option<usize>, std::io::Error> {
+        Ok(Some(321))
+    }
+}
+
+#[test]
+fn dry_run_send_reports_without_storing_or_sending() {
+    let calls = Arc::new(Mutex::new(0));
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().unwrap(),
+        "daemon".into(),
+        Arc::new(UnreachableBridge {
+            calls: calls.clone(),
+        }),
+    );
+
+    let preview = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": "probe",
+                "source": "me",
+                "destination": "peer",
+                "content": "hello",
+                "method": "opportunistic",
+                "stamp_cost": 8,
+                "dry_run": true,
+            })),
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(preview["would_send"], false);
+    assert_eq!(preview["method"], "opportunistic");
+    assert_eq!(preview["estimated_size"], 321);
+    assert_eq!(preview["stamp_required"], true);
+    assert_eq!(preview["reasons"], json!(["no_path"]));
+
+    assert_eq!(*calls.lock().unwrap(), 0);
+    assert!(daemon.take_event().is_none());
+    let message = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_message".into(),
+            params: Some(json!({ "message_id": "probe" })),
+        })
+        .unwrap();
+    assert_eq!(message.error.unwrap().code, "MESSAGE_NOT_FOUND");
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "bad",
+                "source": "me",
+                "destination": "peer",
+                "content": "hello",
+                "fields": { "4": "not an icon" },
+                "dry_run": true,
+            })),
+        })
+        .expect_err("invalid fields");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+// Learns a path a short while after it is asked for one, as a transport
+// would once the path response comes back.
+struct SlowPathBridge {
+    known: Arc<std::sync::atomic::AtomicBool>,
+    delay: std::time::Duration,
+}
+
+impl OutboundBridge for SlowPathBridge {
+    fn deliver(
+        &self,
+        _record: &reticulum::storage::messages::MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn has_path(&self, _destination: &str) -> Option<bool> {
+        Some(self.known.load(std::sync::atomic::Ordering::SeqCst))
+    }
+
+    fn request_path(&self, _destination: &str) -> Result<(), std::io::Error> {
+        let known = self.known.clone();
+        let delay = self.delay;
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            known.store(true, std::sync::atomic::Ordering::SeqCst);
+        });
+        Ok(())
+    }
+}
+
+async fn request_path(daemon: &RpcDaemon, wait_ms: u64) -> serde_json::Value {
+    daemon
+        .handle_rpc_async(RpcRequest {
+            id: 1,
+            method: "request_path".into(),
+            params: Some(json!({ "destination": "AA11", "wait_ms": wait_ms })),
+        })
+        .await
+        .unwrap()
+        .result
+        .unwrap()
+}
+
+#[tokio::test]
+async fn request_path_waits_for_the_path_or_reports_it_later() {
+    let known = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().unwrap(),
+        "daemon".into(),
+        Arc::new(SlowPathBridge {
+            known: known.clone(),
+            delay: std::time::Duration::from_millis(20),
+        }),
+    );
+    let waited = request_path(&daemon, 5_000).await;
+    assert_eq!(waited["has_path"], true);
+    assert_eq!(waited["resolved"], true);
+    assert!(waited["waited_ms"].as_u64().unwrap() < 5_000);
+    let event = daemon.take_event().expect("path_resolved");
+    assert_eq!(event.event_type, "path_resolved");
+    assert_eq!(event.payload["destination"], "aa11");
+
+    let again = request_path(&daemon, 5_000).await;
+    assert_eq!(again["requested"], false);
+    assert_eq!(again["resolved"], false);
+    assert_eq!(again["waited_ms"], 0);
+
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().unwrap(),
+        "daemon".into(),
+        Arc::new(SlowPathBridge {
+            known: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            delay: std::time::Duration::from_secs(60),
+        }),
+    );
+    let pending = request_path(&daemon, 0).await;
+    assert_eq!(pending["requested"], true);
+    assert_eq!(pending["has_path"], false);
+    assert_eq!(pending["resolved"], false);
+    assert!(daemon.take_event().is_none());
+
+    // The sync entry point never blocks on wait_ms.
+    let sync = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "request_path".into(),
+            params: Some(json!({ "destination": "AA11", "wait_ms": 5_000 })),
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(sync["requested"], true);
+    assert_eq!(sync["waited_ms"], 0);
+    assert!(daemon.resolve_path_request("aa11"));
+    assert_eq!(daemon.take_event().unwrap().event_type, "path_resolved");
+    assert!(!daemon.resolve_path_request("aa11"));
+}
+
+#[derive(Default)]
+struct RelayBridge {
+    relays: Mutex<Vec<Option<String>>>,
+}
+
+impl OutboundBridge for RelayBridge {
+    fn deliver(
+        &self,
+        _record: &reticulum::storage::messages::MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.relays
+            .lock()
+            .unwrap()
+            .push(options.identity_relay.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn unknown_destinations_are_looked_up_through_the_propagation_relay() {
+    let bridge = Arc::new(RelayBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().expect("store"),
+        "me".into(),
+        bridge.clone(),
+    );
+    let send = |id: &str, destination: &str| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "send_message".into(),
+                params: Some(json!({
+                    "id": id,
+                    "source": "me",
+                    "destination": destination,
+                    "content": "hi",
+                })),
+            })
+            .expect("send_message");
+    };
+
+    send("before-relay", "bob");
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "set_outbound_propagation_node".into(),
+            params: Some(json!({ "peer": "relay-a" })),
+        })
+        .expect("set_outbound_propagation_node");
+    send("via-relay", "bob");
+    send("to-relay", "relay-a");
+    assert_eq!(
+        *bridge.relays.lock().unwrap(),
+        vec![None, Some("relay-a".to_string()), None]
+    );
+
+    daemon.record_delivery_step(
+        "via-relay",
+        "identity: retrying path request toward relay relay-a",
+    );
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "via-relay" })),
+        })
+        .expect("trace")
+        .result
+        .expect("result");
+    assert!(trace["transitions"]
+        .as_array()
+        .expect("transitions")
+        .iter()
+        .any(|entry| entry["status"] == "identity: retrying path request toward relay relay-a"));
+}
+
+#[derive(Default)]
+struct SequenceBridge {
+    sequences: Mutex<Vec<Vec<String>>>,
+}
+
+impl OutboundBridge for SequenceBridge {
+    fn deliver(
+        &self,
+        record: &reticulum::storage::messages::MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.sequences.lock().unwrap().push(vec![record.id.clone()]);
+        Ok(())
+    }
+
+    fn deliver_sequence(
+        &self,
+        records: &[reticulum::storage::messages::MessageRecord],
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.sequences
+            .lock()
+            .unwrap()
+            .push(records.iter().map(|record| record.id.clone()).collect());
+        Ok(())
+    }
+}
+
+#[test]
+fn chunk_receipts_settle_the_message_they_belong_to() {
+    let bridge = Arc::new(SequenceBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().expect("store"),
+        "test".into(),
+        bridge.clone(),
+    );
+    let call = |method: &str, params: serde_json::Value| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: method.into(),
+                params: Some(params),
+            })
+            .expect(method)
+            .result
+            .unwrap_or_default()
+    };
+    let send = |id: &str| {
+        call(
+            "send_message_v2",
+            json!({
+                "id": id,
+                "source": "alice",
+                "destination": "bob",
+                "content": "see attached",
+                "method": "opportunistic",
+                "fields": { "attachments": [["photo.jpg", hex::encode(vec![7u8; 1000])]] },
+            }),
+        )
+    };
+    let receipt = |message_id: &str, status: &str| {
+        call(
+            "record_receipt",
+            json!({ "message_id": message_id, "status": status }),
+        );
+    };
+    let status = |message_id: &str| {
+        call("get_message", json!({ "message_id": message_id }))["message"]["receipt_status"]
+            .clone()
+    };
+
+    assert_eq!(send("photo")["attachment_chunks"], 8);
+    let chunks = bridge.sequences.lock().unwrap()[0].clone();
+    assert_eq!(chunks.len(), 8);
+    assert_eq!(bridge.sequences.lock().unwrap().len(), 1);
+
+    for chunk in &chunks[..7] {
+        receipt(chunk, "sent: link");
+        receipt(chunk, "delivered");
+    }
+    assert_ne!(status("photo"), "delivered");
+    receipt(&chunks[7], "delivered");
+    assert_eq!(status("photo"), "delivered");
+    let trace = call("message_delivery_trace", json!({ "message_id": chunks[0] }));
+    assert!(trace["transitions"].as_array().unwrap().is_empty());
+
+    send("photo-2");
+    let chunks = bridge.sequences.lock().unwrap()[1].clone();
+    receipt(&chunks[0], "delivered");
+    receipt(&chunks[1], "failed: peer not announced");
+    assert_eq!(status("photo-2"), "failed: peer not announced");
+}
+
+#[test]
+fn messages_without_a_source_go_out_from_the_delivery_destination() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        reticulum::storage::messages::MessagesStore::in_memory().expect("store"),
+        "test".into(),
+        Arc::new(RecordingBridge::default()),
+    );
+    daemon.set_delivery_destination_hash(Some("00112233445566778899aabbccddeeff".into()));
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "unsourced",
+                "source": "",
+                "destination": "bob",
+                "content": "hi",
+            })),
+        })
+        .expect("send_message");
+    let message = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_message".into(),
+            params: Some(json!({ "message_id": "unsourced" })),
+        })
+        .expect("get_message")
+        .result
+        .expect("result");
+    assert_eq!(
+        message["message"]["source"],
+        "00112233445566778899aabbccddeeff"
+    );
+}
@@ -0,0 +1,249 @@
+use base64::Engine as _;
+use reticulum::rpc::{ReceivedResourceLimits, ResourceBridge, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type SentResource = (String, Vec<u8>, Option<Vec<u8>>);
+
+#[derive(Default)]
+struct RecordingResourceBridge {
+    sent: Mutex<Vec<SentResource>>,
+}
+
+impl ResourceBridge for RecordingResourceBridge {
+    fn send_resource(
+        &self,
+        _transfer_id: &str,
+        destination: &str,
+        data: Vec<u8>,
+        metadata: Option<Vec<u8>>,
+        _path_timeout: Duration,
+    ) -> Result<(), std::io::Error> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((destination.to_string(), data, metadata));
+        Ok(())
+    }
+}
+
+fn daemon_with_bridge(identity: &str) -> (RpcDaemon, Arc<RecordingResourceBridge>) {
+    let bridge = Arc::new(RecordingResourceBridge::default());
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), identity.into())
+        .with_resource_bridge(bridge.clone());
+    (daemon, bridge)
+}
+
+fn call(daemon: &RpcDaemon, method: &str, params: Value) -> Result<Value, std::io::Error> {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .map(|response| response.result.unwrap_or(Value::Null))
+}
+
+const SENDER: &str = "00112233445566778899aabbccddeeff";
+const RECEIVER: &str = "ffeeddccbbaa99887766554433221100";
+
+#[test]
+fn send_message_references_stored_content() {
+    let (daemon, _bridge) = daemon_with_bridge(SENDER);
+    let data = base64::engine::general_purpose::STANDARD.encode(b"attachment bytes");
+    let stored = call(&daemon, "store_content", json!({ "data_base64": data })).unwrap();
+    let hash = stored["hash"].as_str().unwrap().to_string();
+    assert_eq!(hash.len(), 64);
+    assert_eq!(stored["size"], 16);
+
+    let again = call(&daemon, "store_content", json!({ "data_base64": data })).unwrap();
+    assert_eq!(again["hash"], hash);
+
+    call(
+        &daemon,
+        "send_message",
+        json!({
+            "id": "m1",
+            "source": SENDER,
+            "destination": RECEIVER,
+            "content": "see attached",
+            "content_refs": [hash.to_ascii_uppercase()],
+        }),
+    )
+    .unwrap();
+    let listed = call(&daemon, "list_messages", json!({})).unwrap();
+    let refs = &listed["messages"][0]["fields"]["content_refs"];
+    assert_eq!(refs[0]["hash"], hash);
+    assert_eq!(refs[0]["size"], 16);
+
+    let err = call(
+        &daemon,
+        "send_message",
+        json!({
+            "id": "m2",
+            "source": SENDER,
+            "destination": RECEIVER,
+            "content": "missing",
+            "content_refs": ["ab".repeat(32)],
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn fetch_content_pulls_bytes_from_the_sender() {
+    let (sender, sender_bridge) = daemon_with_bridge(SENDER);
+    let (receiver, receiver_bridge) = daemon_with_bridge(RECEIVER);
+    let data = base64::engine::general_purpose::STANDARD.encode(b"remote attachment");
+    let hash = call(&sender, "store_content", json!({ "data_base64": data })).unwrap()["hash"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let fetch = call(
+        &receiver,
+        "fetch_content",
+        json!({ "hash": hash, "peer": SENDER }),
+    )
+    .unwrap();
+    assert_eq!(fetch["status"], "requested");
+    let (destination, request, metadata) = receiver_bridge.sent.lock().unwrap().remove(0);
+    assert_eq!(destination, SENDER);
+
+    // Content is only served to a peer it was offered to.
+    assert!(sender
+        .accept_content_resource(&request, metadata.as_deref())
+        .unwrap());
+    assert!(sender_bridge.sent.lock().unwrap().is_empty());
+    call(
+        &sender,
+        "send_message",
+        json!({
+            "id": "offer",
+            "source": SENDER,
+            "destination": RECEIVER,
+            "content": "see attached",
+            "content_refs": [hash],
+        }),
+    )
+    .unwrap();
+
+    assert!(sender
+        .accept_content_resource(&request, metadata.as_deref())
+        .unwrap());
+    let (destination, response, metadata) = sender_bridge.sent.lock().unwrap().remove(0);
+    assert_eq!(destination, RECEIVER);
+    assert_eq!(response, b"remote attachment");
+
+    // Bytes that do not match the requested digest are discarded.
+    assert!(receiver
+        .accept_content_resource(b"tampered", metadata.as_deref())
+        .unwrap());
+    let missing = receiver
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_content".into(),
+            params: Some(json!({ "hash": hash })),
+        })
+        .unwrap();
+    assert_eq!(missing.error.unwrap().code, "CONTENT_NOT_FOUND");
+
+    assert!(receiver
+        .accept_content_resource(&response, metadata.as_deref())
+        .unwrap());
+    let fetched = call(&receiver, "get_content", json!({ "hash": hash })).unwrap();
+    assert_eq!(fetched["data_base64"], data);
+    let again = call(
+        &receiver,
+        "fetch_content",
+        json!({ "hash": hash, "peer": SENDER }),
+    )
+    .unwrap();
+    assert_eq!(again["status"], "available");
+
+    assert!(!receiver.accept_content_resource(b"plain", None).unwrap());
+}
+
+#[test]
+fn content_requests_are_rate_limited_per_peer() {
+    let (sender, sender_bridge) = daemon_with_bridge(SENDER);
+    let data = base64::engine::general_purpose::STANDARD.encode(b"popular attachment");
+    let hash = call(&sender, "store_content", json!({ "data_base64": data })).unwrap()["hash"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    call(
+        &sender,
+        "send_message",
+        json!({
+            "id": "offer",
+            "source": SENDER,
+            "destination": RECEIVER,
+            "content": "see attached",
+            "content_refs": [hash],
+        }),
+    )
+    .unwrap();
+    let request = |reply_to: &str| {
+        let metadata = json!({ "lxmf_content": "request", "hash": hash, "reply_to": reply_to });
+        sender
+            .accept_content_resource(b"", Some(&serde_json::to_vec(&metadata).unwrap()))
+            .unwrap()
+    };
+
+    for _ in 0..20 {
+        assert!(request(RECEIVER));
+    }
+    assert_eq!(sender_bridge.sent.lock().unwrap().len(), 8);
+
+    // A peer that was never offered the content gets nothing.
+    assert!(request("0123456789abcdef0123456789abcdef"));
+    assert_eq!(sender_bridge.sent.lock().unwrap().len(), 8);
+}
+
+#[test]
+fn unclaimed_resource_is_fetchable_by_hash() {
+    let (daemon, _) = daemon_with_bridge("receiver");
+    let hash = daemon.store_received_resource(b"raw resource").unwrap();
+    let fetched = call(&daemon, "get_content", json!({ "hash": hash })).unwrap();
+    assert_eq!(
+        fetched["data_base64"],
+        base64::engine::general_purpose::STANDARD.encode(b"raw resource")
+    );
+}
+
+#[test]
+fn unclaimed_resources_are_held_within_limits() {
+    let (daemon, _) = daemon_with_bridge("receiver");
+    daemon.set_received_resource_limits(ReceivedResourceLimits {
+        max_bytes: 16,
+        max_items: 2,
+        max_age_secs: 0,
+    });
+    let own = base64::engine::general_purpose::STANDARD.encode(b"ours");
+    let own = call(&daemon, "store_content", json!({ "data_base64": own })).unwrap();
+
+    let err = daemon
+        .store_received_resource(b"seventeen bytes!!")
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let hashes: Vec<String> = [b"one", b"two", b"six"]
+        .iter()
+        .map(|data| daemon.store_received_resource(*data).unwrap())
+        .collect();
+    let kept = hashes
+        .iter()
+        .filter(|hash| {
+            !call(&daemon, "get_content", json!({ "hash": hash }))
+                .unwrap()
+                .is_null()
+        })
+        .count();
+    assert_eq!(kept, 2);
+    let own = call(&daemon, "get_content", json!({ "hash": own["hash"] })).unwrap();
+    assert_eq!(own["size"], 4);
+}
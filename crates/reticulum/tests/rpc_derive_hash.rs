@@ -0,0 +1,57 @@
+use reticulum::destination::{DestinationName, SingleInputDestination};
+use reticulum::identity::PrivateIdentity;
+use reticulum::rpc::{RpcDaemon, RpcRequest};
+use serde_json::json;
+
+#[test]
+fn derive_delivery_hash_matches_destination() {
+    let identity = PrivateIdentity::new_from_name("derive");
+    let identity_hash = hex::encode(identity.address_hash().as_slice());
+    let expected =
+        SingleInputDestination::new(identity.clone(), DestinationName::new("lxmf", "delivery"));
+    let daemon = RpcDaemon::test_instance();
+
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "derive_delivery_hash".into(),
+            params: Some(json!({ "identity_hash": identity_hash })),
+        })
+        .expect("derive");
+    let result = resp.result.expect("result");
+    assert_eq!(
+        result["delivery_destination_hash"],
+        json!(hex::encode(expected.desc.address_hash.as_slice()))
+    );
+
+    let custom =
+        SingleInputDestination::new(identity, DestinationName::new("nomadnetwork", "node"));
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "derive_delivery_hash".into(),
+            params: Some(json!({
+                "identity_hash": identity_hash,
+                "app": "nomadnetwork",
+                "aspect": "node",
+            })),
+        })
+        .expect("derive custom");
+    assert_eq!(
+        resp.result.expect("result")["delivery_destination_hash"],
+        json!(hex::encode(custom.desc.address_hash.as_slice()))
+    );
+}
+
+#[test]
+fn derive_delivery_hash_rejects_wrong_length() {
+    let daemon = RpcDaemon::test_instance();
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "derive_delivery_hash".into(),
+            params: Some(json!({ "identity_hash": "00112233" })),
+        })
+        .expect_err("short hash");
+    assert!(err.to_string().contains("16 bytes"));
+}
@@ -0,0 +1,84 @@
+use reticulum::rpc::{DestinationInterfaceBridge, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MemoryHintBridge {
+    hints: Mutex<HashMap<String, String>>,
+}
+
+impl DestinationInterfaceBridge for MemoryHintBridge {
+    fn set_destination_interface(
+        &self,
+        destination: &str,
+        interface: &str,
+    ) -> Result<(), std::io::Error> {
+        self.hints
+            .lock()
+            .unwrap()
+            .insert(destination.to_string(), interface.to_string());
+        Ok(())
+    }
+
+    fn clear_destination_interface(&self, destination: &str) -> Result<bool, std::io::Error> {
+        Ok(self.hints.lock().unwrap().remove(destination).is_some())
+    }
+}
+
+#[test]
+fn destination_interface_hints_round_trip_through_bridge() {
+    let bridge = Arc::new(MemoryHintBridge::default());
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_destination_interface_bridge(bridge.clone());
+    let destination = "00112233445566778899AABBCCDDEEFF";
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "set_destination_interface".into(),
+            params: Some(json!({ "destination": destination, "interface": "radio" })),
+        })
+        .expect("set hint");
+    let result = response.result.expect("result");
+    assert_eq!(result["destination"], destination.to_ascii_lowercase());
+    assert_eq!(
+        bridge.hints.lock().unwrap()[&destination.to_ascii_lowercase()],
+        "radio"
+    );
+    let event = daemon.take_event().expect("event");
+    assert_eq!(event.event_type, "destination_interface_updated");
+
+    let cleared = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "clear_destination_interface".into(),
+            params: Some(json!({ "destination": destination })),
+        })
+        .expect("clear hint");
+    assert_eq!(cleared.result.expect("result")["cleared"], true);
+    assert!(bridge.hints.lock().unwrap().is_empty());
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "set_destination_interface".into(),
+            params: Some(json!({ "destination": "not-a-hash", "interface": "radio" })),
+        })
+        .expect_err("invalid destination");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn destination_interface_requires_transport() {
+    let daemon = RpcDaemon::test_instance();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "clear_destination_interface".into(),
+            params: Some(json!({ "destination": "00112233445566778899aabbccddeeff" })),
+        })
+        .expect("response");
+    assert_eq!(response.error.expect("error").code, "TRANSPORT_UNAVAILABLE");
+}
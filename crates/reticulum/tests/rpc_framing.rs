@@ -1,5 +1,10 @@
-use reticulum::rpc::codec::{decode_frame, encode_frame};
-use reticulum::rpc::RpcRequest;
+use reticulum::rpc::codec::{
+    decode_frame, decode_frame_with_format, encode_frame, encode_frame_as, FrameFormat, FrameReader,
+};
+use reticulum::rpc::http::{serve_framed_stream, RequestLimits};
+use reticulum::rpc::{RpcDaemon, RpcError, RpcRequest, RpcResponse};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 #[test]
 fn round_trips_framed_messagepack() {
@@ -12,3 +17,125 @@ fn round_trips_framed_messagepack() {
     let decoded: RpcRequest = decode_frame(&bytes).unwrap();
     assert_eq!(decoded.method, "status");
 }
+
+#[test]
+fn round_trips_request_and_response_in_both_formats() {
+    let request = RpcRequest {
+        id: 7,
+        method: "send_message".into(),
+        params: Some(json!({ "id": "m1", "content": "hi", "fields": { "15": 2 } })),
+    };
+    let response = RpcResponse {
+        id: 7,
+        result: Some(json!({ "message_id": "m1" })),
+        error: Some(RpcError {
+            code: "DELIVERY_FAILED".into(),
+            message: "no path".into(),
+        }),
+    };
+    for format in [FrameFormat::MsgPack, FrameFormat::Json] {
+        let bytes = encode_frame_as(&request, format).unwrap();
+        let (decoded, detected): (RpcRequest, _) = decode_frame_with_format(&bytes).unwrap();
+        assert_eq!(detected, format);
+        assert_eq!(decoded.id, request.id);
+        assert_eq!(decoded.method, request.method);
+        assert_eq!(decoded.params, request.params);
+
+        let bytes = encode_frame_as(&response, format).unwrap();
+        let (decoded, detected): (RpcResponse, _) = decode_frame_with_format(&bytes).unwrap();
+        assert_eq!(detected, format);
+        assert_eq!(decoded, response);
+    }
+}
+
+#[test]
+fn framed_request_is_answered_in_the_request_format() {
+    let daemon = RpcDaemon::test_instance();
+    let request = RpcRequest {
+        id: 1,
+        method: "status".into(),
+        params: None,
+    };
+    for format in [FrameFormat::MsgPack, FrameFormat::Json] {
+        let reply = daemon
+            .handle_framed_request(&encode_frame_as(&request, format).unwrap())
+            .unwrap();
+        let (response, detected): (RpcResponse, _) = decode_frame_with_format(&reply).unwrap();
+        assert_eq!(detected, format);
+        assert!(response.result.is_some());
+    }
+}
+
+fn status_request(id: u64) -> RpcRequest {
+    RpcRequest {
+        id,
+        method: "status".into(),
+        params: None,
+    }
+}
+
+#[test]
+fn frame_reader_reassembles_split_and_batched_frames() {
+    let first = encode_frame(&status_request(1)).unwrap();
+    let second = encode_frame_as(&status_request(2), FrameFormat::Json).unwrap();
+
+    let mut reader = FrameReader::new(1024);
+    // A header split across reads, then the rest of the frame byte by byte.
+    reader.push(&first[..2]);
+    assert!(reader.next_frame().unwrap().is_none());
+    for byte in &first[2..first.len() - 1] {
+        reader.push(std::slice::from_ref(byte));
+        assert!(reader.next_frame().unwrap().is_none());
+    }
+    reader.push(&first[first.len() - 1..]);
+    assert_eq!(reader.next_frame().unwrap(), Some(first.clone()));
+    assert_eq!(reader.buffered(), 0);
+
+    let mut batch = first.clone();
+    batch.extend_from_slice(&second);
+    batch.extend_from_slice(&first[..5]);
+    reader.push(&batch);
+    let decoded: RpcRequest = decode_frame(&reader.next_frame().unwrap().unwrap()).unwrap();
+    assert_eq!(decoded.id, 1);
+    let decoded: RpcRequest = decode_frame(&reader.next_frame().unwrap().unwrap()).unwrap();
+    assert_eq!(decoded.id, 2);
+    assert!(reader.next_frame().unwrap().is_none());
+    assert_eq!(reader.buffered(), 5);
+
+    let mut reader = FrameReader::new(8);
+    reader.push(&first);
+    let err = reader.next_frame().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn framed_stream_answers_each_request_in_order() {
+    let daemon = RpcDaemon::test_instance();
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let session = serve_framed_stream(&daemon, server, RequestLimits::default());
+    let script = async {
+        let mut requests = encode_frame(&status_request(1)).unwrap();
+        requests
+            .extend_from_slice(&encode_frame_as(&status_request(2), FrameFormat::Json).unwrap());
+        let (head, tail) = requests.split_at(3);
+        client.write_all(head).await.unwrap();
+        client.flush().await.unwrap();
+        client.write_all(tail).await.unwrap();
+
+        for (id, format) in [(1, FrameFormat::MsgPack), (2, FrameFormat::Json)] {
+            let mut header = [0u8; 4];
+            client.read_exact(&mut header).await.unwrap();
+            let mut frame = header.to_vec();
+            frame.resize(4 + u32::from_be_bytes(header) as usize, 0);
+            client.read_exact(&mut frame[4..]).await.unwrap();
+            let (response, detected): (RpcResponse, _) = decode_frame_with_format(&frame).unwrap();
+            assert_eq!(detected, format);
+            assert_eq!(response.id, id);
+            assert!(response.error.is_none());
+        }
+        client.shutdown().await.unwrap();
+        drop(client);
+    };
+    let (served, ()) = tokio::join!(session, script);
+    served.unwrap();
+}
@@ -0,0 +1,159 @@
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
+use rand_core::OsRng;
+use reticulum::destination::{group_decrypt, DestinationName, GroupOutputDestination};
+use reticulum::identity::{EmptyIdentity, PrivateIdentity};
+use reticulum::packet::DestinationType;
+use reticulum::rpc::{PlainBridge, RpcDaemon, RpcRequest, RpcResponse};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::{json, Value};
+
+type SentGroup = (String, String, [u8; 16], Vec<u8>);
+
+#[derive(Default)]
+struct RecordingPlain {
+    sent: Mutex<Vec<SentGroup>>,
+}
+
+impl PlainBridge for RecordingPlain {
+    fn send_plain(&self, _app: &str, _aspect: &str, _data: Vec<u8>) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn send_group(
+        &self,
+        app: &str,
+        aspect: &str,
+        key: [u8; 16],
+        data: Vec<u8>,
+    ) -> Result<(), std::io::Error> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((app.into(), aspect.into(), key, data));
+        Ok(())
+    }
+}
+
+fn rpc(daemon: &RpcDaemon, method: &str, params: Value) -> std::io::Result<RpcResponse> {
+    daemon.handle_rpc(RpcRequest {
+        id: 1,
+        method: method.into(),
+        params: Some(params),
+    })
+}
+
+fn call(daemon: &RpcDaemon, method: &str, params: Value) -> Value {
+    rpc(daemon, method, params)
+        .expect(method)
+        .result
+        .expect("result")
+}
+
+fn open_daemon(path: &std::path::Path, identity: &PrivateIdentity) -> RpcDaemon {
+    let daemon = RpcDaemon::with_store(MessagesStore::open(path).unwrap(), "daemon".into());
+    daemon.set_local_identity(identity.clone());
+    daemon
+}
+
+#[test]
+fn group_keys_are_sealed_and_usable_for_broadcasts() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+
+    let created = {
+        let daemon = open_daemon(&path, &identity);
+        let created = call(
+            &daemon,
+            "create_group",
+            json!({ "name": "ops", "app": "team", "aspect": "ops" }),
+        );
+        let again = rpc(
+            &daemon,
+            "create_group",
+            json!({ "name": "ops", "app": "team", "aspect": "ops" }),
+        )
+        .unwrap();
+        assert_eq!(again.error.unwrap().code, "GROUP_EXISTS");
+        created
+    };
+    let key_hex = created["key_hex"].as_str().unwrap().to_string();
+    assert_eq!(key_hex.len(), 32);
+    let raw = rusqlite::Connection::open(&path).unwrap();
+    let sealed: Vec<u8> = raw
+        .query_row("SELECT sealed_key FROM group_keys", [], |row| row.get(0))
+        .unwrap();
+    assert!(!hex::encode(&sealed).contains(&key_hex));
+
+    let bridge = Arc::new(RecordingPlain::default());
+    let daemon = open_daemon(&path, &identity).with_plain_bridge(bridge.clone());
+    let exported = call(&daemon, "export_group_key", json!({ "name": "ops" }));
+    assert_eq!(exported["key_hex"], key_hex);
+    let listed = call(&daemon, "list_groups", json!({}));
+    assert_eq!(
+        listed["groups"][0]["destination"],
+        created["group"]["destination"]
+    );
+    assert!(listed["groups"][0].get("key_hex").is_none());
+
+    let sent = call(
+        &daemon,
+        "send_plain",
+        json!({ "group": "ops", "data_base64": BASE64_STANDARD.encode(b"regroup") }),
+    );
+    assert_eq!(sent["group"], "ops");
+    assert_eq!(sent["destination"], created["group"]["destination"]);
+    let (app, aspect, key, data) = bridge.sent.lock().unwrap().remove(0);
+    assert_eq!((app.as_str(), aspect.as_str()), ("team", "ops"));
+    assert_eq!(hex::encode(key), key_hex);
+    assert_eq!(data, b"regroup");
+
+    let missing = rpc(
+        &daemon,
+        "send_plain",
+        json!({ "group": "nope", "data_base64": BASE64_STANDARD.encode(b"x") }),
+    )
+    .unwrap();
+    assert_eq!(missing.error.unwrap().code, "GROUP_NOT_FOUND");
+    drop(daemon);
+
+    // Another identity cannot unseal the stored key.
+    let other = open_daemon(&path, &PrivateIdentity::new_from_rand(OsRng));
+    let err = rpc(&other, "export_group_key", json!({ "name": "ops" })).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    call(
+        &other,
+        "import_group_key",
+        json!({ "name": "ops", "app": "team", "aspect": "ops", "key_hex": key_hex }),
+    );
+    let imported = call(&other, "export_group_key", json!({ "name": "ops" }));
+    assert_eq!(imported["key_hex"], key_hex);
+}
+
+#[test]
+fn group_keys_require_a_local_identity() {
+    let daemon = RpcDaemon::test_instance();
+    let err = rpc(
+        &daemon,
+        "create_group",
+        json!({ "name": "ops", "app": "team", "aspect": "ops" }),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+}
+
+#[test]
+fn group_data_packets_decrypt_with_the_group_key() {
+    let key = [7u8; 16];
+    let destination =
+        GroupOutputDestination::new(EmptyIdentity {}, DestinationName::new("team", "ops"));
+    let packet = destination.data_packet(&key, b"regroup").unwrap();
+    assert_eq!(packet.header.destination_type, DestinationType::Group);
+    assert_eq!(
+        group_decrypt(&key, packet.data.as_slice()).unwrap(),
+        b"regroup"
+    );
+}
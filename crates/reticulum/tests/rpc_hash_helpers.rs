@@ -0,0 +1,76 @@
+use reticulum::destination::lxmf_delivery_hash_from_identity_hash;
+use reticulum::hash::{AddressHash, Hash};
+use reticulum::rpc::{RpcDaemon, RpcRequest};
+use serde_json::{json, Value};
+
+fn call(daemon: &RpcDaemon, method: &str, params: Value) -> Result<Value, std::io::Error> {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .map(|response| response.result.expect("result"))
+}
+
+#[test]
+fn truncate_and_lxmf_hashes_match_the_crate() {
+    let daemon = RpcDaemon::test_instance();
+    let full = Hash::new_from_slice(b"identity");
+    let full_hex = hex::encode(full.as_slice()).to_ascii_uppercase();
+
+    let truncated = call(&daemon, "truncate_hash", json!({ "hash_hex": full_hex })).unwrap();
+    let expected = AddressHash::new_from_hash(&full).to_hex_string();
+    assert_eq!(truncated["address_hash"], expected);
+    assert_eq!(truncated["hash_hex"], hex::encode(full.as_slice()));
+
+    let lxmf = call(
+        &daemon,
+        "lxmf_address_hash",
+        json!({ "hash_hex": full_hex }),
+    )
+    .unwrap();
+    assert_eq!(lxmf["address_hash"], expected);
+
+    let identity = AddressHash::new_from_hash(&full);
+    let delivery = call(
+        &daemon,
+        "lxmf_address_hash",
+        json!({ "identity_hash": identity.to_hex_string() }),
+    )
+    .unwrap();
+    assert_eq!(
+        delivery["address_hash"],
+        lxmf_delivery_hash_from_identity_hash(&identity).to_hex_string()
+    );
+
+    let err = call(&daemon, "truncate_hash", json!({ "hash_hex": expected })).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = call(&daemon, "lxmf_address_hash", json!({})).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn validate_hash_reports_lengths() {
+    let daemon = RpcDaemon::test_instance();
+
+    let address = call(
+        &daemon,
+        "validate_hash",
+        json!({ "hash_hex": "00112233445566778899aabbccddeeff" }),
+    )
+    .unwrap();
+    assert_eq!(address["valid"], true);
+    assert_eq!(address["is_address_hash"], true);
+    assert_eq!(address["length_bytes"], 16);
+
+    let odd = call(&daemon, "validate_hash", json!({ "hash_hex": "abc" })).unwrap();
+    assert_eq!(odd["valid"], false);
+    assert_eq!(odd["is_hex"], false);
+    assert!(odd["length_bytes"].is_null());
+
+    let short = call(&daemon, "validate_hash", json!({ "hash_hex": "0011" })).unwrap();
+    assert_eq!(short["valid"], false);
+    assert_eq!(short["is_hex"], true);
+    assert_eq!(short["length_bytes"], 2);
+}
@@ -1,11 +1,15 @@
+use reticulum::rpc::http::{serve_websocket, RequestLimits};
 use reticulum::rpc::{
     codec::{decode_frame, encode_frame},
-    RpcDaemon, RpcEvent, RpcRequest, RpcResponse,
+    RpcAuthToken, RpcDaemon, RpcEvent, RpcRequest, RpcResponse,
 };
+use reticulum::rpc::{AnnounceBridge, AnnounceFuture};
 use reticulum::storage::messages::MessagesStore;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
 
-#[test]
-fn rpc_http_roundtrip() {
+#[tokio::test]
+async fn rpc_http_roundtrip() {
     let store = MessagesStore::in_memory().unwrap();
     let daemon = RpcDaemon::with_store(store, "daemon".into());
 
@@ -24,7 +28,9 @@ fn rpc_http_roundtrip() {
     request_bytes.extend_from_slice(b"\r\n");
     request_bytes.extend_from_slice(&framed);
 
-    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes).unwrap();
+    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes)
+        .await
+        .unwrap();
     let body_start = response
         .windows(4)
         .position(|window| window == b"\r\n\r\n")
@@ -34,14 +40,16 @@ fn rpc_http_roundtrip() {
     assert_eq!(resp.id, 1);
 }
 
-#[test]
-fn rpc_http_events_returns_inbound() {
+#[tokio::test]
+async fn rpc_http_events_returns_inbound() {
     let store = MessagesStore::in_memory().unwrap();
     let daemon = RpcDaemon::with_store(store, "daemon".into());
     daemon.inject_inbound_test_message("hello");
 
     let request_bytes = b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec();
-    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes).unwrap();
+    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes)
+        .await
+        .unwrap();
     let body_start = response
         .windows(4)
         .position(|window| window == b"\r\n\r\n")
@@ -51,8 +59,8 @@ fn rpc_http_events_returns_inbound() {
     assert_eq!(event.event_type, "inbound");
 }
 
-#[test]
-fn rpc_http_events_drains_queue() {
+#[tokio::test]
+async fn rpc_http_events_drains_queue() {
     let store = MessagesStore::in_memory().unwrap();
     let daemon = RpcDaemon::with_store(store, "daemon".into());
     daemon.push_event(RpcEvent {
@@ -61,7 +69,9 @@ fn rpc_http_events_drains_queue() {
     });
 
     let request_bytes = b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec();
-    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes).unwrap();
+    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes)
+        .await
+        .unwrap();
     let body_start = response
         .windows(4)
         .position(|window| window == b"\r\n\r\n")
@@ -71,3 +81,441 @@ fn rpc_http_events_drains_queue() {
     assert_eq!(event.event_type, "one");
     assert!(daemon.take_event().is_none());
 }
+
+fn build_status_request(authorization: Option<&str>) -> Vec<u8> {
+    let framed = encode_frame(&RpcRequest {
+        id: 7,
+        method: "status".into(),
+        params: None,
+    })
+    .unwrap();
+    let mut request_bytes = Vec::new();
+    request_bytes.extend_from_slice(b"POST /rpc HTTP/1.1\r\n");
+    request_bytes.extend_from_slice(b"Host: localhost\r\n");
+    if let Some(value) = authorization {
+        request_bytes.extend_from_slice(format!("Authorization: {value}\r\n").as_bytes());
+    }
+    request_bytes.extend_from_slice(format!("Content-Length: {}\r\n", framed.len()).as_bytes());
+    request_bytes.extend_from_slice(b"\r\n");
+    request_bytes.extend_from_slice(&framed);
+    request_bytes
+}
+
+fn token_daemon() -> RpcDaemon {
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into());
+    daemon.set_http_auth_tokens(vec![
+        RpcAuthToken {
+            label: "ops".into(),
+            token: "secret-a".into(),
+        },
+        RpcAuthToken {
+            label: "map".into(),
+            token: "secret-b".into(),
+        },
+    ]);
+    daemon
+}
+
+#[tokio::test]
+async fn rpc_http_accepts_any_configured_bearer_token() {
+    let daemon = token_daemon();
+    for token in ["secret-a", "secret-b"] {
+        let request = build_status_request(Some(&format!("Bearer {token}")));
+        let response = reticulum::rpc::http::handle_http_request(&daemon, &request)
+            .await
+            .unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+        let body_start = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap()
+            + 4;
+        let resp: RpcResponse = decode_frame(&response[body_start..]).unwrap();
+        assert_eq!(resp.id, 7);
+        assert!(resp.error.is_none());
+    }
+}
+
+#[tokio::test]
+async fn rpc_http_rejects_missing_or_invalid_bearer_token() {
+    let daemon = token_daemon();
+    for authorization in [None, Some("Bearer wrong"), Some("Basic c2VjcmV0LWE=")] {
+        let request = build_status_request(authorization);
+        let response = reticulum::rpc::http::handle_http_request(&daemon, &request)
+            .await
+            .unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 401 Unauthorized"));
+        let body_start = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap()
+            + 4;
+        let resp: RpcResponse = decode_frame(&response[body_start..]).unwrap();
+        assert_eq!(resp.error.expect("error").code, "UNAUTHORIZED");
+    }
+
+    let events = b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec();
+    let response = reticulum::rpc::http::handle_http_request(&daemon, &events)
+        .await
+        .unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 401 Unauthorized"));
+}
+
+#[test]
+fn rpc_http_rejects_oversized_declared_length_before_body() {
+    use reticulum::rpc::http::{check_request_progress, RequestLimits, RequestProgress};
+
+    let limits = RequestLimits {
+        max_header_bytes: 1024,
+        max_body_bytes: 64,
+    };
+    let request = b"POST /rpc HTTP/1.1\r\nContent-Length: 1048576\r\n\r\n{";
+    assert_eq!(
+        check_request_progress(request, &limits),
+        RequestProgress::TooLarge("request body too large")
+    );
+
+    let within = b"POST /rpc HTTP/1.1\r\nContent-Length: 4\r\n\r\n{}";
+    assert_eq!(
+        check_request_progress(within, &limits),
+        RequestProgress::Incomplete
+    );
+
+    let headers = vec![b'a'; 2048];
+    assert_eq!(
+        check_request_progress(&headers, &limits),
+        RequestProgress::TooLarge("request headers too large")
+    );
+
+    let response = reticulum::rpc::http::build_payload_too_large_response("request body too large");
+    assert!(response.starts_with(b"HTTP/1.1 413 Payload Too Large"));
+}
+
+#[tokio::test]
+async fn rpc_http_answers_cors_only_for_allowed_origins() {
+    let daemon = token_daemon();
+    let text = |response: &[u8]| String::from_utf8_lossy(response).to_string();
+    let with_origin = |request: &[u8], origin: &str| {
+        let mut request = request.to_vec();
+        let at = request.windows(2).position(|w| w == b"\r\n").unwrap() + 2;
+        request.splice(at..at, format!("Origin: {origin}\r\n").bytes());
+        request
+    };
+    let preflight = b"OPTIONS /rpc HTTP/1.1\r\nHost: localhost\r\n\
+        Access-Control-Request-Method: POST\r\n\r\n";
+    let status = build_status_request(Some("Bearer secret-a"));
+
+    // Strict by default: browsers get nothing, other clients are unaffected.
+    let response = reticulum::rpc::http::handle_http_request(
+        &daemon,
+        &with_origin(preflight, "https://ui.example"),
+    )
+    .await
+    .unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 403 Forbidden"));
+    let response = reticulum::rpc::http::handle_http_request(&daemon, &status)
+        .await
+        .unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+    assert!(!text(&response).contains("Access-Control-"));
+
+    daemon.set_http_cors_origins(vec!["https://ui.example/".into()]);
+    let response = reticulum::rpc::http::handle_http_request(
+        &daemon,
+        &with_origin(preflight, "https://ui.example"),
+    )
+    .await
+    .unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 204 No Content"));
+    let preflight_text = text(&response);
+    assert!(preflight_text.contains("Access-Control-Allow-Origin: https://ui.example\r\n"));
+    assert!(preflight_text.contains("Access-Control-Allow-Headers: Authorization, Content-Type"));
+
+    let response = reticulum::rpc::http::handle_http_request(
+        &daemon,
+        &with_origin(&status, "https://ui.example"),
+    )
+    .await
+    .unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 200 OK"));
+    assert!(text(&response).contains("Access-Control-Allow-Origin: https://ui.example\r\n"));
+    let body_start = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap()
+        + 4;
+    let resp: RpcResponse = decode_frame(&response[body_start..]).unwrap();
+    assert_eq!(resp.id, 7);
+
+    let response = reticulum::rpc::http::handle_http_request(
+        &daemon,
+        &with_origin(&build_status_request(None), "https://ui.example"),
+    )
+    .await
+    .unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 401 Unauthorized"));
+    assert!(text(&response).contains("Access-Control-Allow-Origin"));
+
+    let response = reticulum::rpc::http::handle_http_request(
+        &daemon,
+        &with_origin(&status, "https://evil.example"),
+    )
+    .await
+    .unwrap();
+    assert!(!text(&response).contains("Access-Control-"));
+}
+
+#[tokio::test]
+async fn rpc_http_events_are_signed_when_enabled() {
+    use rand_core::OsRng;
+    use reticulum::identity::{Identity, PrivateIdentity};
+    use reticulum::rpc::{verify_signed_event, SignedRpcEvent};
+
+    let store = MessagesStore::in_memory().unwrap();
+    let daemon = RpcDaemon::with_store(store, "daemon".into());
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    daemon.set_local_identity(identity.clone());
+    daemon.set_sign_events(true);
+    daemon.push_event(RpcEvent {
+        event_type: "one".into(),
+        payload: serde_json::json!({ "b": 2, "a": 1 }),
+    });
+
+    let request_bytes = b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n".to_vec();
+    let response = reticulum::rpc::http::handle_http_request(&daemon, &request_bytes)
+        .await
+        .unwrap();
+    let body_start = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap()
+        + 4;
+    let mut signed: SignedRpcEvent = decode_frame(&response[body_start..]).unwrap();
+    assert_eq!(signed.event.event_type, "one");
+
+    let info = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "get_identity_info".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .expect("result");
+    assert_eq!(info["sign_events"], true);
+    let public_key = Identity::new_from_hex_string(info["public_key"].as_str().unwrap()).unwrap();
+    assert!(verify_signed_event(&public_key, &signed));
+
+    signed.event.payload["a"] = serde_json::json!(3);
+    assert!(!verify_signed_event(&public_key, &signed));
+}
+
+#[test]
+fn websocket_handshake_returns_accept_key_and_checks_tokens() {
+    use reticulum::rpc::http::{is_websocket_upgrade, websocket_handshake};
+
+    let upgrade = |authorization: &str| {
+        format!(
+            "GET /ws HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n{authorization}\r\n"
+        )
+        .into_bytes()
+    };
+    let request = upgrade("");
+    assert!(is_websocket_upgrade(&request));
+    assert!(!is_websocket_upgrade(
+        b"GET /events HTTP/1.1\r\nHost: localhost\r\n\r\n"
+    ));
+
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into());
+    let response = String::from_utf8(websocket_handshake(&daemon, &request).unwrap()).unwrap();
+    assert!(response.starts_with("HTTP/1.1 101 Switching Protocols"));
+    // Sample handshake from RFC 6455 section 1.3.
+    assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+    let daemon = token_daemon();
+    let rejected = websocket_handshake(&daemon, &request).unwrap_err();
+    assert!(rejected.starts_with(b"HTTP/1.1 401 Unauthorized"));
+    let accepted = websocket_handshake(&daemon, &upgrade("Authorization: Bearer secret-b\r\n"));
+    assert!(accepted.unwrap().starts_with(b"HTTP/1.1 101"));
+
+    // Pages from origins outside the CORS allowlist cannot open the socket,
+    // even with a token.
+    let from_page = upgrade("Authorization: Bearer secret-b\r\nOrigin: https://ui.example\r\n");
+    let rejected = websocket_handshake(&daemon, &from_page).unwrap_err();
+    assert!(rejected.starts_with(b"HTTP/1.1 403 Forbidden"));
+    daemon.set_http_cors_origins(vec!["https://ui.example".into()]);
+    assert!(websocket_handshake(&daemon, &from_page)
+        .unwrap()
+        .starts_with(b"HTTP/1.1 101"));
+}
+
+// Writes one masked client frame; `fin` clear leaves the message fragmented.
+async fn ws_send_frame(client: &mut DuplexStream, fin: bool, opcode: u8, payload: &[u8]) {
+    let mask = [0x11, 0x22, 0x33, 0x44];
+    let mut frame = vec![if fin { 0x80 | opcode } else { opcode }];
+    if payload.len() < 126 {
+        frame.push(0x80 | payload.len() as u8);
+    } else {
+        frame.push(0x80 | 126);
+        frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(
+        payload
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ mask[i % 4]),
+    );
+    client.write_all(&frame).await.unwrap();
+}
+
+async fn send(client: &mut DuplexStream, opcode: u8, payload: &[u8]) {
+    ws_send_frame(client, true, opcode, payload).await;
+}
+
+async fn recv(client: &mut DuplexStream) -> (u8, Vec<u8>) {
+    let mut header = [0u8; 2];
+    client.read_exact(&mut header).await.unwrap();
+    let length = match header[1] {
+        126 => {
+            let mut raw = [0u8; 2];
+            client.read_exact(&mut raw).await.unwrap();
+            usize::from(u16::from_be_bytes(raw))
+        }
+        length => usize::from(length),
+    };
+    let mut payload = vec![0u8; length];
+    client.read_exact(&mut payload).await.unwrap();
+    (header[0] & 0x0F, payload)
+}
+
+#[tokio::test]
+async fn websocket_multiplexes_rpc_events_and_pings() {
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into());
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let session = serve_websocket(&daemon, server, RequestLimits::default());
+    let script = async {
+        send(&mut client, 0x1, br#"{"id":3,"method":"status"}"#).await;
+        let (opcode, body) = recv(&mut client).await;
+        assert_eq!(opcode, 0x1);
+        let response: RpcResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.id, 3);
+        assert!(response.error.is_none());
+
+        send(&mut client, 0x1, br#"{"id":4,"method":"nope""#).await;
+        let (_, body) = recv(&mut client).await;
+        let response: RpcResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.error.unwrap().code, "BAD_REQUEST");
+
+        daemon.emit_event(RpcEvent {
+            event_type: "ws-test".into(),
+            payload: serde_json::json!({ "n": 1 }),
+        });
+        loop {
+            let (opcode, body) = recv(&mut client).await;
+            assert_eq!(opcode, 0x1);
+            let event: RpcEvent = serde_json::from_slice(&body).unwrap();
+            if event.event_type == "ws-test" {
+                break;
+            }
+        }
+
+        send(&mut client, 0x9, b"hi").await;
+        assert_eq!(recv(&mut client).await, (0xA, b"hi".to_vec()));
+
+        send(&mut client, 0x8, &1000u16.to_be_bytes()).await;
+        assert_eq!(
+            recv(&mut client).await,
+            (0x8, 1000u16.to_be_bytes().to_vec())
+        );
+    };
+    let (served, ()) = tokio::join!(session, script);
+    served.unwrap();
+}
+
+#[tokio::test]
+async fn websocket_closes_on_fragment_and_control_frame_violations() {
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into());
+    let protocol_error = (0x8, 1002u16.to_be_bytes().to_vec());
+
+    // A new text message while a fragmented one is still open.
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let session = serve_websocket(&daemon, server, RequestLimits::default());
+    let script = async {
+        ws_send_frame(&mut client, false, 0x1, br#"{"id":1,"#).await;
+        send(&mut client, 0x1, br#"{"id":2,"method":"status"}"#).await;
+        assert_eq!(recv(&mut client).await, protocol_error);
+    };
+    let (served, ()) = tokio::join!(session, script);
+    served.unwrap();
+
+    // A fragmented ping.
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let session = serve_websocket(&daemon, server, RequestLimits::default());
+    let script = async {
+        ws_send_frame(&mut client, false, 0x9, b"hi").await;
+        assert_eq!(recv(&mut client).await, protocol_error);
+    };
+    let (served, ()) = tokio::join!(session, script);
+    served.unwrap();
+
+    // A ping over the 125-byte control frame limit.
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let session = serve_websocket(&daemon, server, RequestLimits::default());
+    let script = async {
+        send(&mut client, 0x9, &[0u8; 126]).await;
+        assert_eq!(recv(&mut client).await, protocol_error);
+    };
+    let (served, ()) = tokio::join!(session, script);
+    served.unwrap();
+}
+
+// An announce that never completes, standing in for a slow handler.
+struct StalledAnnounceBridge;
+
+impl AnnounceBridge for StalledAnnounceBridge {
+    fn announce_now(&self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn announce_confirmed(&self) -> AnnounceFuture {
+        Box::pin(std::future::pending())
+    }
+}
+
+#[tokio::test]
+async fn websocket_keeps_answering_pings_while_a_request_is_pending() {
+    let daemon = RpcDaemon::with_store_and_bridges(
+        MessagesStore::in_memory().unwrap(),
+        "daemon".into(),
+        None,
+        Some(Arc::new(StalledAnnounceBridge)),
+    );
+    let (mut client, server) = tokio::io::duplex(64 * 1024);
+    let session = serve_websocket(&daemon, server, RequestLimits::default());
+    let script = async {
+        send(&mut client, 0x1, br#"{"id":1,"method":"announce_now"}"#).await;
+        send(&mut client, 0x9, b"hi").await;
+        assert_eq!(recv(&mut client).await, (0xA, b"hi".to_vec()));
+
+        send(&mut client, 0x8, &1000u16.to_be_bytes()).await;
+        assert_eq!(
+            recv(&mut client).await,
+            (0x8, 1000u16.to_be_bytes().to_vec())
+        );
+    };
+    let (served, ()) = tokio::join!(session, script);
+    served.unwrap();
+}
+
+#[test]
+fn auth_token_debug_output_hides_the_token() {
+    let token = RpcAuthToken {
+        label: "ops".into(),
+        token: "s3cret-value".into(),
+    };
+    let debug = format!("{token:?}");
+    assert!(debug.contains("ops"), "{debug}");
+    assert!(!debug.contains("s3cret-value"), "{debug}");
+}
@@ -0,0 +1,78 @@
+use reticulum::rpc::{InboundAcceptance, InboundOverloadPolicy, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessageRecord;
+use serde_json::json;
+
+fn inbound(id: &str, source: &str) -> MessageRecord {
+    MessageRecord {
+        id: id.into(),
+        source: source.into(),
+        destination: "local".into(),
+        title: String::new(),
+        content: "hello".into(),
+        timestamp: 1,
+        direction: "in".into(),
+        fields: None,
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    }
+}
+
+#[test]
+fn accept_inbound_sheds_non_prioritised_sources_under_load() {
+    let daemon = RpcDaemon::test_instance();
+    daemon
+        .set_inbound_overload_policy(InboundOverloadPolicy {
+            window_secs: 3600,
+            max_writes_per_window: 2,
+            max_queued_events: 0,
+        })
+        .expect("overload policy");
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "set_delivery_policy".into(),
+            params: Some(json!({ "prioritised_destinations": ["vip"] })),
+        })
+        .expect("policy");
+    while daemon.take_event().is_some() {}
+
+    for id in ["m1", "m2"] {
+        let accepted = daemon.accept_inbound(inbound(id, "peer")).unwrap();
+        assert_eq!(accepted, InboundAcceptance::Stored);
+    }
+    assert_eq!(
+        daemon.accept_inbound(inbound("m3", "peer")).unwrap(),
+        InboundAcceptance::Dropped
+    );
+    assert_eq!(
+        daemon.accept_inbound(inbound("m4", "peer")).unwrap(),
+        InboundAcceptance::Dropped
+    );
+    assert_eq!(
+        daemon.accept_inbound(inbound("m5", "vip")).unwrap(),
+        InboundAcceptance::Stored
+    );
+
+    let mut overload_events = 0;
+    while let Some(event) = daemon.take_event() {
+        if event.event_type == "overload" {
+            overload_events += 1;
+            assert_eq!(event.payload["reason"], "write_rate");
+        }
+    }
+    assert_eq!(overload_events, 1);
+
+    let status = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "daemon_status_ex".into(),
+            params: None,
+        })
+        .expect("status")
+        .result
+        .expect("result");
+    assert_eq!(status["message_count"], 3);
+    assert_eq!(status["inbound_overload"]["dropped"], 2);
+}
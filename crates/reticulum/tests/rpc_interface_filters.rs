@@ -0,0 +1,101 @@
+use reticulum::packet::PacketType;
+use reticulum::rpc::{InterfaceFilterBridge, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use reticulum::transport::iface_filter::{InterfaceFilter, InterfaceFilterStatus};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MemoryFilterBridge {
+    filters: Mutex<HashMap<String, InterfaceFilter>>,
+}
+
+impl InterfaceFilterBridge for MemoryFilterBridge {
+    fn set_interface_filter(
+        &self,
+        interface: &str,
+        filter: Option<InterfaceFilter>,
+    ) -> Result<(), std::io::Error> {
+        let mut filters = self.filters.lock().unwrap();
+        match filter {
+            Some(filter) => filters.insert(interface.to_string(), filter),
+            None => filters.remove(interface),
+        };
+        Ok(())
+    }
+
+    fn interface_filter(
+        &self,
+        interface: &str,
+    ) -> Result<Option<InterfaceFilterStatus>, std::io::Error> {
+        Ok(self
+            .filters
+            .lock()
+            .unwrap()
+            .get(interface)
+            .cloned()
+            .map(|filter| InterfaceFilterStatus {
+                filter,
+                filtered_packets: 3,
+            }))
+    }
+}
+
+#[test]
+fn set_interface_filter_round_trips_through_bridge() {
+    let bridge = Arc::new(MemoryFilterBridge::default());
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_interface_filter_bridge(bridge.clone());
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "set_interface_filter".into(),
+            params: Some(json!({
+                "interface": "public",
+                "drop_packet_types": ["announce", "path_request"],
+            })),
+        })
+        .expect("set filter");
+    let result = response.result.expect("result");
+    assert_eq!(
+        result["drop_packet_types"],
+        json!(["announce", "path_request"])
+    );
+    let stored = bridge.filters.lock().unwrap()["public"].clone();
+    assert_eq!(stored.drop_packet_types, vec![PacketType::Announce]);
+    assert!(stored.drop_path_requests);
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_interface_filter".into(),
+            params: Some(json!({ "interface": "public" })),
+        })
+        .expect("get filter");
+    assert_eq!(response.result.expect("result")["filtered_packets"], 3);
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "set_interface_filter".into(),
+            params: Some(json!({ "interface": "public" })),
+        })
+        .expect("clear filter");
+    assert!(bridge.filters.lock().unwrap().is_empty());
+}
+
+#[test]
+fn set_interface_filter_rejects_unknown_packet_type() {
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_interface_filter_bridge(Arc::new(MemoryFilterBridge::default()));
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "set_interface_filter".into(),
+            params: Some(json!({ "interface": "public", "drop_packet_types": ["bogus"] })),
+        })
+        .expect_err("unknown type");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,197 @@
+use reticulum::hash::AddressHash;
+use reticulum::iface::traffic::TRAFFIC_HISTORY_SECS;
+use reticulum::iface::{InterfaceTraffic, TrafficHistory};
+use reticulum::rpc::{InterfaceRecord, InterfaceTrafficBridge, RpcDaemon, RpcRequest};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[test]
+fn interface_stats_report_connection_state_changes() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.replace_interfaces(vec![InterfaceRecord {
+        kind: "tcp_client".into(),
+        enabled: true,
+        host: Some("relay.example".into()),
+        port: Some(4242),
+        name: Some("relay".into()),
+        options: Default::default(),
+    }]);
+
+    daemon.record_interface_state("relay", "connecting", 0, None);
+    assert!(daemon.take_event().is_none());
+    daemon.record_interface_state("relay", "connected", 0, None);
+    assert_eq!(daemon.take_event().expect("up").event_type, "interface_up");
+    daemon.record_interface_state("relay", "disconnected", 0, None);
+    let down = daemon.take_event().expect("down");
+    assert_eq!(down.event_type, "interface_down");
+    assert_eq!(down.payload["name"], "relay");
+    daemon.record_interface_state("relay", "disconnected", 2, Some(Duration::from_secs(4)));
+    assert!(daemon.take_event().is_none());
+
+    let stats = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "interface_stats".into(),
+            params: Some(json!({})),
+        })
+        .expect("interface_stats")
+        .result
+        .expect("result");
+    let relay = &stats["interfaces"][0];
+    assert_eq!(relay["name"], "relay");
+    assert_eq!(relay["connection"]["state"], "disconnected");
+    assert_eq!(relay["connection"]["reconnect_attempts"], 2);
+    assert_eq!(relay["connection"]["retry_in_ms"], 4000);
+}
+
+#[test]
+fn interface_stats_report_the_resolved_bind_address() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.replace_interfaces(vec![InterfaceRecord {
+        kind: "tcp_server".into(),
+        enabled: true,
+        host: Some("::".into()),
+        port: Some(0),
+        name: Some("listener".into()),
+        options: Default::default(),
+    }]);
+
+    daemon.record_interface_binding("listener", None, Some("address in use".into()));
+    daemon.record_interface_binding("listener", Some("[::]:40123".into()), None);
+
+    let stats = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "interface_stats".into(),
+            params: Some(json!({})),
+        })
+        .expect("interface_stats")
+        .result
+        .expect("result");
+    let listener = &stats["interfaces"][0];
+    assert_eq!(listener["bound_addr"], "[::]:40123");
+    assert!(listener["bind_error"].is_null());
+}
+
+#[test]
+fn interface_traffic_keeps_a_bounded_per_second_history() {
+    let traffic = InterfaceTraffic::default();
+    let iface = AddressHash::new([1u8; 16]);
+    traffic.record_rx_at(iface, 100, 1_000);
+    traffic.record_rx_at(iface, 20, 1_000);
+    traffic.record_tx_at(iface, 50, 1_002);
+
+    let history = traffic.history_at(&iface, 1_002);
+    assert_eq!((history.rx_bytes, history.tx_bytes), (120, 50));
+    assert_eq!(history.samples.len(), TRAFFIC_HISTORY_SECS);
+    let last = &history.samples[TRAFFIC_HISTORY_SECS - 3..];
+    assert_eq!((last[0].at, last[0].rx_bytes), (1_000, 120));
+    assert_eq!((last[1].rx_bytes, last[1].tx_bytes), (0, 0));
+    assert_eq!((last[2].at, last[2].tx_bytes), (1_002, 50));
+
+    // Samples older than the window fall off; the totals keep counting.
+    let later = traffic.history_at(&iface, 1_000 + TRAFFIC_HISTORY_SECS as u64);
+    assert!(later.samples.iter().all(|sample| sample.rx_bytes == 0));
+    assert_eq!(
+        (later.samples[1].at, later.samples[1].tx_bytes),
+        (1_002, 50)
+    );
+    assert_eq!(later.rx_bytes, 120);
+}
+
+struct FixedTraffic;
+
+impl InterfaceTrafficBridge for FixedTraffic {
+    fn interface_traffic(&self) -> Vec<(String, TrafficHistory)> {
+        let traffic = InterfaceTraffic::default();
+        let relay = AddressHash::new([2u8; 16]);
+        traffic.record_tx(relay, 64);
+        vec![
+            ("relay".into(), traffic.history(&relay)),
+            ("lan".into(), TrafficHistory::default()),
+        ]
+    }
+}
+
+#[test]
+fn interface_stats_history_reports_samples_per_interface() {
+    let call = |daemon: &RpcDaemon, params| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "interface_stats_history".into(),
+                params,
+            })
+            .expect("interface_stats_history")
+    };
+    let detached = call(&RpcDaemon::test_instance(), None);
+    assert_eq!(detached.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+
+    let daemon = RpcDaemon::test_instance().with_interface_traffic_bridge(Arc::new(FixedTraffic));
+    let all = call(&daemon, None).result.expect("result");
+    let names: Vec<_> = all["interfaces"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|iface| iface["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["lan", "relay"]);
+
+    let relay = call(&daemon, Some(json!({ "interface": "relay" })))
+        .result
+        .expect("result");
+    let relay = &relay["interfaces"][0];
+    assert_eq!(relay["tx_bytes"], 64);
+    let samples = relay["samples"].as_array().unwrap();
+    assert_eq!(samples.len(), TRAFFIC_HISTORY_SECS);
+    assert_eq!(samples[TRAFFIC_HISTORY_SECS - 1]["tx_bytes"], 64);
+
+    let missing = call(&daemon, Some(json!({ "interface": "nope" })));
+    assert_eq!(missing.error.unwrap().code, "INTERFACE_NOT_FOUND");
+}
+
+#[test]
+fn list_interfaces_reports_runtime_status() {
+    let daemon = RpcDaemon::test_instance().with_interface_traffic_bridge(Arc::new(FixedTraffic));
+    let record = |kind: &str, name: &str, enabled: bool| InterfaceRecord {
+        kind: kind.into(),
+        enabled,
+        host: Some("127.0.0.1".into()),
+        port: Some(4242),
+        name: Some(name.into()),
+        options: Default::default(),
+    };
+    daemon.replace_interfaces(vec![
+        record("tcp_client", "relay", true),
+        record("tcp_client", "backup", true),
+        record("tcp_server", "listener", true),
+        record("udp", "lan", true),
+        record("udp", "spare", true),
+        record("tcp_client", "off", false),
+    ]);
+    daemon.record_interface_state("relay", "connected", 0, None);
+    daemon.record_interface_state("backup", "connecting", 1, None);
+    daemon.record_interface_binding("listener", None, Some("address in use".into()));
+
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "list_interfaces".into(),
+            params: None,
+        })
+        .expect("list_interfaces")
+        .result
+        .expect("result");
+    let statuses: Vec<_> = listed["interfaces"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|iface| iface["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        statuses,
+        ["active", "connecting", "down", "active", "down", "disabled"]
+    );
+    assert_eq!(listed["interfaces"][0]["enabled"], true);
+}
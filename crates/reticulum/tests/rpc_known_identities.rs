@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rand_core::OsRng;
+use reticulum::destination::lxmf_delivery_hash_from_identity_hash;
+use reticulum::identity::{Identity, PrivateIdentity};
+use reticulum::rpc::{OutboundBridge, OutboundDeliveryOptions, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::{MessageRecord, MessagesStore};
+use serde_json::json;
+
+const PEER: &str = "00112233445566778899aabbccddeeff";
+
+fn delivery_hash(identity: &PrivateIdentity) -> String {
+    lxmf_delivery_hash_from_identity_hash(&identity.as_identity().address_hash).to_hex_string()
+}
+
+// Mirrors the daemon bridge: delivery only proceeds immediately when the peer
+// identity is already known, otherwise it would have to wait for an announce.
+#[derive(Default)]
+struct IdentityBridge {
+    known: Mutex<HashMap<String, Identity>>,
+    delivered: Mutex<Vec<String>>,
+}
+
+impl OutboundBridge for IdentityBridge {
+    fn deliver(
+        &self,
+        record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        if !self
+            .known
+            .lock()
+            .expect("known")
+            .contains_key(&record.destination)
+        {
+            return Err(std::io::Error::other("peer not announced"));
+        }
+        self.delivered
+            .lock()
+            .expect("delivered")
+            .push(record.id.clone());
+        Ok(())
+    }
+
+    fn restore_peer_identity(
+        &self,
+        destination: &str,
+        identity: &Identity,
+    ) -> Result<(), std::io::Error> {
+        self.known
+            .lock()
+            .expect("known")
+            .insert(destination.to_string(), *identity);
+        Ok(())
+    }
+}
+
+#[test]
+fn restored_identity_is_usable_for_delivery_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let identity_hex = identity.as_identity().to_hex_string();
+    let peer = delivery_hash(&identity);
+
+    {
+        let daemon = RpcDaemon::with_store(MessagesStore::open(&path).unwrap(), "daemon".into());
+        let response = daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "store_peer_identity".into(),
+                params: Some(json!({
+                    "destination": peer.to_ascii_uppercase(),
+                    "identity_hex": identity_hex,
+                })),
+            })
+            .unwrap();
+        let result = response.result.unwrap();
+        assert_eq!(result["identity"]["destination"], peer);
+    }
+
+    let bridge = Arc::new(IdentityBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::open(&path).unwrap(),
+        "daemon".into(),
+        bridge.clone(),
+    );
+    assert_eq!(daemon.restore_known_identities().unwrap(), 1);
+    assert_eq!(
+        bridge.known.lock().unwrap()[&peer].to_hex_string(),
+        identity_hex
+    );
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "msg-known",
+                "source": "alice",
+                "destination": peer,
+                "title": "",
+                "content": "hi",
+            })),
+        })
+        .unwrap();
+    assert!(response.error.is_none());
+    assert_eq!(*bridge.delivered.lock().unwrap(), vec!["msg-known"]);
+
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "list_known_identities".into(),
+            params: None,
+        })
+        .unwrap();
+    assert_eq!(
+        listed.result.unwrap()["identities"][0]["identity_hex"],
+        identity_hex
+    );
+}
+
+#[test]
+fn store_peer_identity_rejects_malformed_identity() {
+    let daemon = RpcDaemon::test_instance();
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "store_peer_identity".into(),
+            params: Some(json!({ "destination": PEER, "identity_hex": "zz" })),
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn store_peer_identity_rejects_identity_for_another_destination() {
+    let daemon = RpcDaemon::test_instance();
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let err = daemon
+        .store_peer_identity(PEER, &identity.as_identity().to_hex_string())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(daemon.restore_known_identities().unwrap(), 0);
+}
+
+#[test]
+fn exported_identities_round_trip_through_bulk_restore() {
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let identity_hex = identity.as_identity().to_hex_string();
+    let peer = delivery_hash(&identity);
+    let source = RpcDaemon::test_instance();
+    source
+        .store_peer_identity(&peer, &identity_hex)
+        .expect("store identity");
+
+    let call = |daemon: &RpcDaemon, method: &str, params: Option<serde_json::Value>| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: method.into(),
+                params,
+            })
+            .unwrap()
+            .result
+            .unwrap()
+    };
+
+    let listed = call(&source, "list_known_identities", None);
+    let entry = &listed["identities"][0];
+    assert_eq!(entry["delivery_destination_hash"], peer);
+    assert_eq!(entry["public_key"], identity_hex);
+    assert_eq!(
+        entry["identity_hash"],
+        identity.as_identity().address_hash.to_hex_string()
+    );
+
+    let mut exported = call(&source, "export_known_identities", None);
+    let entries = exported["identities"].as_array_mut().unwrap();
+    entries.push(json!({ "destination": "not-a-hash", "identity_hex": identity_hex }));
+    entries.push(json!({ "destination": PEER, "identity_hex": identity_hex }));
+
+    let target = RpcDaemon::test_instance();
+    let restored = call(&target, "bulk_restore_peer_identities", Some(exported));
+    assert_eq!(restored["restored"], 1);
+    assert_eq!(restored["rejected"][0]["destination"], "not-a-hash");
+    assert_eq!(restored["rejected"][1]["destination"], PEER);
+    assert_eq!(
+        call(&target, "export_known_identities", None),
+        call(&source, "export_known_identities", None)
+    );
+
+    assert_eq!(call(&target, "clear_known_identities", None)["cleared"], 1);
+    assert_eq!(
+        call(&target, "list_known_identities", None)["identities"],
+        json!([])
+    );
+}
+
+#[test]
+fn recall_identity_falls_back_to_announced_public_key_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    let identity = PrivateIdentity::new_from_rand(OsRng);
+    let identity_hex = identity.as_identity().to_hex_string();
+    let peer = delivery_hash(&identity);
+
+    {
+        let daemon = RpcDaemon::with_store(MessagesStore::open(&path).unwrap(), "daemon".into());
+        daemon
+            .accept_announce_with_metadata(
+                peer.clone(),
+                1_700_000_000,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(1),
+                None,
+                None,
+                None,
+                None,
+                Some(identity_hex.clone()),
+            )
+            .unwrap();
+    }
+
+    let daemon = RpcDaemon::with_store(MessagesStore::open(&path).unwrap(), "daemon".into());
+    let recall = |destination: &str| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "recall_identity".into(),
+                params: Some(json!({ "destination": destination })),
+            })
+            .unwrap()
+            .result
+            .unwrap()
+    };
+
+    let recalled = recall(&peer.to_ascii_uppercase());
+    assert_eq!(recalled["identity"]["identity_hex"], identity_hex);
+    assert_eq!(recalled["identity"]["source"], "announce");
+    assert_eq!(
+        recall("ffeeddccbbaa99887766554433221100")["identity"],
+        json!(null)
+    );
+
+    daemon.store_peer_identity(&peer, &identity_hex).unwrap();
+    let recalled = recall(&peer);
+    assert_eq!(recalled["identity"]["identity_hex"], identity_hex);
+    assert_eq!(recalled["identity"]["source"], "known_identity");
+}
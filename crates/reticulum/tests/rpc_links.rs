@@ -0,0 +1,233 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reticulum::rpc::{LinkBridge, LinkInfo, RpcDaemon, RpcEvent, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::json;
+
+const LINK_ID: &str = "0123456789abcdef0123456789abcdef";
+const PEER: &str = "ffeeddccbbaa99887766554433221100";
+
+struct FixedLinks {
+    links: Mutex<Vec<LinkInfo>>,
+    establishing: Mutex<Vec<(String, Duration)>>,
+}
+
+impl LinkBridge for FixedLinks {
+    fn list_links(&self) -> Result<Vec<LinkInfo>, std::io::Error> {
+        Ok(self.links.lock().unwrap().clone())
+    }
+
+    fn close_link(&self, link_id: &str) -> Result<bool, std::io::Error> {
+        let mut links = self.links.lock().unwrap();
+        let before = links.len();
+        links.retain(|link| link.link_id != link_id);
+        Ok(links.len() != before)
+    }
+
+    fn establish_link(&self, destination: &str, timeout: Duration) -> Result<(), std::io::Error> {
+        self.establishing
+            .lock()
+            .unwrap()
+            .push((destination.to_string(), timeout));
+        Ok(())
+    }
+}
+
+fn fixed_links() -> Arc<FixedLinks> {
+    Arc::new(FixedLinks {
+        links: Mutex::new(vec![LinkInfo {
+            link_id: LINK_ID.into(),
+            destination: PEER.into(),
+            direction: "out".into(),
+            established_at: Some(1_700_000_000),
+            last_activity: 1_700_000_010,
+            rtt_ms: 42,
+            rtt_avg_ms: Some(50),
+            status: "active".into(),
+        }]),
+        establishing: Mutex::new(Vec::new()),
+    })
+}
+
+fn daemon_with_link() -> RpcDaemon {
+    RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_link_bridge(fixed_links())
+}
+
+fn call(
+    daemon: &RpcDaemon,
+    method: &str,
+    params: Option<serde_json::Value>,
+) -> reticulum::rpc::RpcResponse {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params,
+        })
+        .unwrap()
+}
+
+#[test]
+fn list_active_links_and_close_link() {
+    let daemon = daemon_with_link();
+
+    let listed = call(&daemon, "list_active_links", None).result.unwrap();
+    assert_eq!(listed["links"][0]["link_id"], LINK_ID);
+    assert_eq!(listed["links"][0]["rtt_ms"], 42);
+    assert_eq!(listed["links"][0]["rtt_avg_ms"], 50);
+    assert_eq!(listed["links"][0]["status"], "active");
+
+    let closed = call(
+        &daemon,
+        "close_link",
+        Some(json!({ "link_id": LINK_ID.to_ascii_uppercase() })),
+    );
+    assert_eq!(closed.result.unwrap()["closed"], true);
+
+    let missing = call(&daemon, "close_link", Some(json!({ "link_id": LINK_ID })));
+    assert_eq!(missing.error.unwrap().code, "LINK_NOT_FOUND");
+    let listed = call(&daemon, "list_active_links", None).result.unwrap();
+    assert_eq!(listed["links"], json!([]));
+}
+
+#[test]
+fn list_active_links_requires_transport() {
+    let daemon = RpcDaemon::test_instance();
+    let response = call(&daemon, "list_active_links", None);
+    assert_eq!(response.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+}
+
+#[test]
+fn establish_link_reports_rtt_of_an_active_link_or_starts_one() {
+    let links = fixed_links();
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_link_bridge(links.clone());
+
+    let active = call(
+        &daemon,
+        "establish_link",
+        Some(json!({ "destination": PEER.to_ascii_uppercase() })),
+    )
+    .result
+    .unwrap();
+    assert_eq!(active["status"], "active");
+    assert_eq!(active["link_id"], LINK_ID);
+    assert_eq!(active["rtt_ms"], 42);
+    assert_eq!(active["rtt_avg_ms"], 50);
+    assert_eq!(active["already_existed"], true);
+    assert_eq!(active["last_activity"], 1_700_000_010);
+    assert!(links.establishing.lock().unwrap().is_empty());
+
+    // A link still in its handshake is waited on, not duplicated; a stale one
+    // is replaced.
+    let handshaking = "8899aabbccddeeff0011223344556677";
+    let stale = "77665544332211000011223344556677";
+    for (destination, status) in [(handshaking, "handshake"), (stale, "stale")] {
+        links.links.lock().unwrap().push(LinkInfo {
+            link_id: destination.chars().rev().collect(),
+            destination: destination.into(),
+            direction: "out".into(),
+            established_at: None,
+            last_activity: 1_700_000_020,
+            rtt_ms: 0,
+            rtt_avg_ms: None,
+            status: status.into(),
+        });
+    }
+    let in_progress = call(
+        &daemon,
+        "establish_link",
+        Some(json!({ "destination": handshaking })),
+    )
+    .result
+    .unwrap();
+    assert_eq!(in_progress["status"], "pending");
+    assert_eq!(in_progress["already_existed"], true);
+    assert!(links.establishing.lock().unwrap().is_empty());
+    let replaced = call(
+        &daemon,
+        "establish_link",
+        Some(json!({ "destination": stale })),
+    )
+    .result
+    .unwrap();
+    assert_eq!(replaced["already_existed"], false);
+    links.establishing.lock().unwrap().clear();
+
+    let other = "00112233445566778899aabbccddeeff";
+    let pending = call(
+        &daemon,
+        "establish_link",
+        Some(json!({ "destination": other, "timeout_seconds": 5 })),
+    )
+    .result
+    .unwrap();
+    assert_eq!(pending["status"], "pending");
+    assert_eq!(pending["already_existed"], false);
+    assert_eq!(pending["timeout_ms"], 5_000);
+    assert_eq!(
+        *links.establishing.lock().unwrap(),
+        vec![(other.to_string(), Duration::from_secs(5))]
+    );
+
+    let unavailable = call(
+        &RpcDaemon::test_instance(),
+        "establish_link",
+        Some(json!({ "destination": other })),
+    );
+    assert_eq!(unavailable.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+}
+
+#[tokio::test]
+async fn establish_link_waits_for_the_link_to_come_up_or_fail() {
+    let daemon = daemon_with_link();
+    let establish = |destination: &'static str| {
+        daemon.handle_rpc_async(RpcRequest {
+            id: 1,
+            method: "establish_link".into(),
+            params: Some(json!({ "destination": destination, "timeout_seconds": 5 })),
+        })
+    };
+    let report = |event_type: &str, payload: serde_json::Value| {
+        let event = RpcEvent {
+            event_type: event_type.into(),
+            payload,
+        };
+        let daemon = &daemon;
+        async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            daemon.emit_event(event);
+        }
+    };
+
+    let up = "00112233445566778899aabbccddeeff";
+    let (response, ()) = tokio::join!(
+        establish(up),
+        report(
+            "link_established",
+            json!({ "destination": up, "link_id": LINK_ID, "rtt_ms": 31, "waited_ms": 20 }),
+        )
+    );
+    let result = response.unwrap().result.unwrap();
+    assert_eq!(result["status"], "active");
+    assert_eq!(result["link_id"], LINK_ID);
+    assert_eq!(result["rtt_ms"], 31);
+    assert_eq!(result["rtt_avg_ms"], serde_json::Value::Null);
+    assert!(result["waited_ms"].as_u64().unwrap() >= 20);
+
+    let unreachable = "8899aabbccddeeff0011223344556677";
+    let (response, ()) = tokio::join!(
+        establish(unreachable),
+        report(
+            "link_failed",
+            json!({
+                "destination": unreachable,
+                "error": "no path to destination",
+                "reason_code": "no_path",
+            }),
+        )
+    );
+    assert_eq!(response.unwrap().error.unwrap().code, "NO_PATH");
+}
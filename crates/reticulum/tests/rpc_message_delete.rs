@@ -0,0 +1,96 @@
+use reticulum::rpc::{RpcDaemon, RpcRequest};
+use serde_json::{json, Value};
+
+const LOCAL: &str = "00112233445566778899aabbccddeeff";
+const PEER: &str = "ffeeddccbbaa99887766554433221100";
+
+fn rpc(daemon: &RpcDaemon, method: &str, params: Value) -> reticulum::rpc::RpcResponse {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .expect(method)
+}
+
+#[test]
+fn deleted_messages_are_hidden_and_reported_as_tombstones() {
+    let daemon = RpcDaemon::test_instance();
+    for id in ["m1", "m2"] {
+        rpc(
+            &daemon,
+            "send_message",
+            json!({
+                "id": id,
+                "source": LOCAL,
+                "destination": PEER,
+                "title": "",
+                "content": "hi",
+            }),
+        );
+    }
+    while daemon.take_event().is_some() {}
+
+    let deleted = rpc(&daemon, "delete_message", json!({ "message_id": "m1" }))
+        .result
+        .expect("result");
+    assert_eq!(deleted["message_id"], "m1");
+    let deleted_at = deleted["deleted_at"].as_i64().unwrap();
+    let event = daemon.take_event().expect("event");
+    assert_eq!(event.event_type, "message_deleted");
+    assert_eq!(event.payload["message_id"], "m1");
+
+    let listed = rpc(&daemon, "list_messages", json!({})).result.unwrap();
+    assert_eq!(listed["messages"].as_array().unwrap().len(), 1);
+    assert_eq!(listed["messages"][0]["id"], "m2");
+    assert_eq!(listed["meta"]["total"], 1);
+
+    let again = rpc(&daemon, "delete_message", json!({ "message_id": "m1" }));
+    assert_eq!(again.error.expect("error").code, "MESSAGE_NOT_FOUND");
+
+    // The same message arriving again does not bring it back.
+    daemon
+        .accept_inbound(reticulum::storage::messages::MessageRecord {
+            id: "m1".into(),
+            source: PEER.into(),
+            destination: LOCAL.into(),
+            title: String::new(),
+            content: "hi".into(),
+            timestamp: 1,
+            direction: "in".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .expect("accept_inbound");
+    let listed = rpc(&daemon, "list_messages", json!({})).result.unwrap();
+    assert_eq!(listed["meta"]["total"], 1);
+
+    let tombstones = rpc(
+        &daemon,
+        "list_deleted_since",
+        json!({ "since": deleted_at }),
+    )
+    .result
+    .unwrap();
+    assert_eq!(
+        tombstones["tombstones"],
+        json!([{ "id": "m1", "deleted_at": deleted_at }])
+    );
+
+    let purged = rpc(
+        &daemon,
+        "purge_deleted",
+        json!({ "before": deleted_at + 1 }),
+    )
+    .result
+    .unwrap();
+    assert_eq!(purged["purged"], 1);
+    let tombstones = rpc(&daemon, "list_deleted_since", json!({}))
+        .result
+        .unwrap();
+    assert_eq!(tombstones["tombstones"], json!([]));
+}
@@ -0,0 +1,156 @@
+use reticulum::rpc::{OutboundBridge, OutboundDeliveryOptions, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::{MessageRecord, MessagesStore};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct RecordingBridge {
+    delivered: Mutex<Vec<MessageRecord>>,
+}
+
+impl OutboundBridge for RecordingBridge {
+    fn deliver(
+        &self,
+        record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.delivered.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+const LOCAL: &str = "00112233445566778899aabbccddeeff";
+const PEER: &str = "ffeeddccbbaa99887766554433221100";
+
+fn rpc(daemon: &RpcDaemon, method: &str, params: Value) -> reticulum::rpc::RpcResponse {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .expect(method)
+}
+
+fn drain_events(daemon: &RpcDaemon) -> Vec<String> {
+    std::iter::from_fn(|| daemon.take_event())
+        .map(|event| event.event_type)
+        .collect()
+}
+
+#[test]
+fn edit_message_archives_versions_and_notifies_the_peer() {
+    let bridge = Arc::new(RecordingBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().unwrap(),
+        LOCAL.into(),
+        bridge.clone(),
+    );
+    rpc(
+        &daemon,
+        "send_message",
+        json!({
+            "id": "m1",
+            "source": LOCAL,
+            "destination": PEER,
+            "title": "hi",
+            "content": "helo",
+        }),
+    );
+    drain_events(&daemon);
+
+    let edited = rpc(
+        &daemon,
+        "edit_message",
+        json!({ "message_id": "m1", "content": "hello" }),
+    )
+    .result
+    .expect("result");
+    assert_eq!(edited["version"], 1);
+    assert_eq!(edited["delivery"], "sent");
+    assert_eq!(drain_events(&daemon), vec!["message_edited"]);
+
+    let carrier = bridge.delivered.lock().unwrap().last().cloned().unwrap();
+    assert_eq!(carrier.destination, PEER);
+    assert_eq!(carrier.content, "hello");
+    assert_eq!(carrier.fields.unwrap()["edit"]["message_id"], "m1");
+
+    let listed = rpc(&daemon, "list_messages", json!({})).result.unwrap();
+    assert_eq!(listed["messages"].as_array().unwrap().len(), 1);
+    assert_eq!(listed["messages"][0]["content"], "hello");
+
+    let versions = rpc(
+        &daemon,
+        "get_message_versions",
+        json!({ "message_id": "m1" }),
+    )
+    .result
+    .unwrap();
+    assert_eq!(versions["current"]["content"], "hello");
+    assert_eq!(versions["edited_at"], edited["edited_at"]);
+    assert_eq!(versions["versions"][0]["content"], "helo");
+    assert_eq!(versions["versions"][0]["title"], "hi");
+
+    let missing = rpc(
+        &daemon,
+        "edit_message",
+        json!({ "message_id": "nope", "content": "x" }),
+    );
+    assert_eq!(missing.error.unwrap().code, "MESSAGE_NOT_FOUND");
+}
+
+#[test]
+fn inbound_edits_apply_only_from_the_original_sender() {
+    let daemon = RpcDaemon::test_instance_with_identity(LOCAL);
+    daemon.set_accept_unaddressed_inbound(true);
+    rpc(
+        &daemon,
+        "receive_message",
+        json!({
+            "id": "in1",
+            "source": PEER,
+            "destination": LOCAL,
+            "content": "meet at 5",
+        }),
+    );
+    drain_events(&daemon);
+
+    let edit = |source: &str, id: &str, content: &str| {
+        json!({
+            "id": id,
+            "source": source,
+            "destination": LOCAL,
+            "content": content,
+            "fields": { "edit": { "message_id": "in1", "edited_at": 1_700_000_000 } },
+        })
+    };
+    rpc(
+        &daemon,
+        "receive_message",
+        edit(LOCAL, "spoof:edit:1", "meet at 9"),
+    );
+    assert!(drain_events(&daemon).is_empty());
+
+    rpc(
+        &daemon,
+        "receive_message",
+        edit(PEER, "in1:edit:1", "meet at 6"),
+    );
+    let event = daemon.take_event().expect("message_edited");
+    assert_eq!(event.event_type, "message_edited");
+    assert_eq!(event.payload["message_id"], "in1");
+
+    let listed = rpc(&daemon, "list_messages", json!({})).result.unwrap();
+    let messages = listed["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["content"], "meet at 6");
+    let versions = rpc(
+        &daemon,
+        "get_message_versions",
+        json!({ "message_id": "in1" }),
+    )
+    .result
+    .unwrap();
+    assert_eq!(versions["edited_at"], 1_700_000_000);
+    assert_eq!(versions["versions"][0]["content"], "meet at 5");
+}
@@ -0,0 +1,66 @@
+use reticulum::rpc::{RpcDaemon, RpcRequest};
+use serde_json::{json, Value};
+
+fn send(daemon: &RpcDaemon, id: &str, fields: Value) -> std::io::Result<Value> {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": id,
+                "source": "alice",
+                "destination": "bob",
+                "content": "hi",
+                "fields": fields,
+            })),
+        })
+        .map(|response| response.result.unwrap_or(Value::Null))
+}
+
+#[test]
+fn malformed_known_fields_are_rejected_before_storing() {
+    let daemon = RpcDaemon::test_instance();
+    for (field, value) in [
+        ("2", json!({ "lat": 1.0 })),
+        ("2", json!([1, 2, 300])),
+        ("4", json!(["antenna", "zz", "000000"])),
+        ("9", json!([1])),
+        ("16", json!("ext")),
+        ("112", json!(7)),
+    ] {
+        let err = send(&daemon, "bad", json!({ field: value })).expect_err(field);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(
+            err.to_string().contains(&format!("field {field} ")),
+            "{err}"
+        );
+    }
+
+    send(
+        &daemon,
+        "good",
+        json!({
+            "2": [0x81, 0x01, 0x00],
+            "4": ["antenna", [255, 0, 0], "00ff00"],
+            "9": [{ "1": 0 }],
+            "16": { "x": 1 },
+            "112": "{}",
+            "999": { "anything": true },
+        }),
+    )
+    .expect("valid fields");
+
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    let messages = listed["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["id"], "good");
+    assert_eq!(messages[0]["fields"]["999"]["anything"], true);
+}
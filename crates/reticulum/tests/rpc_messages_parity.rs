@@ -4,6 +4,7 @@ use serde_json::json;
 #[test]
 fn receive_message_persists_fields() {
     let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
     let resp = daemon
         .handle_rpc(RpcRequest {
             id: 1,
@@ -35,6 +36,7 @@ fn receive_message_persists_fields() {
 #[test]
 fn receive_message_persists_title() {
     let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
     let resp = daemon
         .handle_rpc(RpcRequest {
             id: 1,
@@ -62,3 +64,163 @@ fn receive_message_persists_title() {
     let messages = result.get("messages").unwrap().as_array().unwrap();
     assert_eq!(messages[0].get("title").unwrap(), "Hello");
 }
+
+#[test]
+fn list_messages_enrich_resolves_source_names() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "announce_received".into(),
+            params: Some(json!({ "peer": "0011223344556677", "name": "Alice" })),
+        })
+        .unwrap();
+    for (id, source) in [(2, "0011223344556677"), (3, "8899aabbccddeeff")] {
+        daemon
+            .handle_rpc(RpcRequest {
+                id,
+                method: "receive_message".into(),
+                params: Some(json!({
+                    "id": format!("msg-{id}"),
+                    "source": source,
+                    "destination": "peer-b",
+                    "content": "hi",
+                })),
+            })
+            .unwrap();
+    }
+
+    let plain = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert!(plain["messages"][0].get("source_name").is_none());
+
+    let enriched = daemon
+        .handle_rpc(RpcRequest {
+            id: 5,
+            method: "list_messages".into(),
+            params: Some(json!({ "enrich": true })),
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    let messages = enriched["messages"].as_array().unwrap();
+    let named = messages
+        .iter()
+        .find(|message| message["source"] == "0011223344556677")
+        .unwrap();
+    assert_eq!(named["source_name"], "Alice");
+    assert_eq!(named["source_alias"], "Alice");
+    let unnamed = messages
+        .iter()
+        .find(|message| message["source"] == "8899aabbccddeeff")
+        .unwrap();
+    assert!(unnamed["source_name"].is_null());
+    assert_eq!(unnamed["source_alias"], "8899aabb");
+}
+
+#[test]
+fn list_messages_meta_reports_filtered_total() {
+    let store = reticulum::storage::messages::MessagesStore::in_memory().unwrap();
+    for (index, (source, destination, direction)) in [
+        ("alice", "local", "in"),
+        ("bob", "local", "in"),
+        ("local", "alice", "out"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        store
+            .insert_message(&reticulum::storage::messages::MessageRecord {
+                id: format!("msg-{index}"),
+                source: source.into(),
+                destination: destination.into(),
+                title: String::new(),
+                content: "hi".into(),
+                timestamp: index as i64,
+                direction: direction.into(),
+                fields: None,
+                receipt_status: None,
+                delivery_method: None,
+                content_type: None,
+                attempts: 0,
+            })
+            .unwrap();
+    }
+    let daemon = RpcDaemon::with_store(store, "daemon".into());
+    let total = |params: Option<serde_json::Value>| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "list_messages".into(),
+                params,
+            })
+            .unwrap()
+            .result
+            .expect("result")["meta"]["total"]
+            .clone()
+    };
+
+    assert_eq!(total(None), json!(3));
+    assert_eq!(total(Some(json!({ "direction": "in" }))), json!(2));
+    assert_eq!(total(Some(json!({ "peer": "alice" }))), json!(2));
+    assert_eq!(
+        total(Some(json!({ "direction": "out", "peer": "bob" }))),
+        json!(0)
+    );
+}
+
+#[test]
+fn list_messages_projection_returns_only_requested_columns() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "receive_message".into(),
+            params: Some(json!({
+                "id": "msg-1",
+                "source": "peer-a",
+                "destination": "peer-b",
+                "title": "Hello",
+                "content": "a long body",
+                "fields": {"k": "v"},
+            })),
+        })
+        .unwrap();
+
+    let projected = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_messages".into(),
+            params: Some(json!({
+                "peer": "peer-a",
+                "fields_projection": ["id", "title", "fields", "receipt_status"],
+            })),
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(
+        projected["messages"],
+        json!([{ "id": "msg-1", "title": "Hello", "fields": {"k": "v"}, "receipt_status": null }])
+    );
+    assert_eq!(projected["meta"]["total"], json!(1));
+
+    let rejected = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "list_messages".into(),
+            params: Some(json!({ "fields_projection": ["id", "rowid"] })),
+        })
+        .unwrap_err();
+    assert_eq!(rejected.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(rejected.to_string().contains("rowid"));
+}
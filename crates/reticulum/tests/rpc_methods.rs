@@ -1,3 +1,5 @@
+use reticulum::destination::lxmf_delivery_hash_from_identity_hash;
+use reticulum::identity::PrivateIdentity;
 use reticulum::rpc::{RpcDaemon, RpcRequest};
 use reticulum::storage::messages::MessagesStore;
 
@@ -49,6 +51,28 @@ fn status_prefers_configured_delivery_destination_hash() {
     assert_eq!(result["delivery_destination_hash"], "delivery-hash");
 }
 
+#[test]
+fn daemon_status_ex_reports_build_info() {
+    let daemon = RpcDaemon::test_instance();
+    let result = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "daemon_status_ex".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(result["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!result["git_sha"].as_str().unwrap().is_empty());
+    assert!(result["build_time"].is_i64());
+    assert_eq!(
+        result["rns_protocol_version"],
+        reticulum::packet::RNS_PROTOCOL_VERSION
+    );
+    assert_eq!(result["contract_version"], "v2");
+}
+
 #[test]
 fn send_message_persists() {
     let daemon = RpcDaemon::test_instance();
@@ -82,6 +106,7 @@ fn send_message_persists() {
 #[test]
 fn receive_message_persists_and_emits_event() {
     let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
     daemon
         .handle_rpc(RpcRequest {
             id: 5,
@@ -129,3 +154,194 @@ fn list_peers_returns_empty_array() {
     let peers = result["peers"].as_array().unwrap().clone();
     assert_eq!(peers.len(), 0);
 }
+
+#[test]
+fn send_message_rejects_malformed_hashes_when_required() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_require_hash_addresses(true);
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(serde_json::json!({
+                "id": "bad-dst",
+                "source": "00112233445566778899aabbccddeeff",
+                "destination": "bob",
+                "content": "hi"
+            })),
+        })
+        .expect_err("invalid destination");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("destination"));
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "send_message_v2".into(),
+            params: Some(serde_json::json!({
+                "id": "bad-src",
+                "source": "alice",
+                "destination": "00112233445566778899AABBCCDDEEFF",
+                "content": "hi"
+            })),
+        })
+        .expect_err("invalid source");
+    assert!(err.to_string().contains("source"));
+
+    let list = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .unwrap();
+    assert_eq!(list.result.unwrap()["messages"], serde_json::json!([]));
+
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "send_message".into(),
+            params: Some(serde_json::json!({
+                "id": "logical",
+                "source": "alice",
+                "destination": "bob",
+                "content": "hi",
+                "allow_logical_destination": true
+            })),
+        })
+        .unwrap();
+    assert!(resp.error.is_none());
+}
+
+#[test]
+fn send_message_sends_as_the_identity_of_source_private_key() {
+    let daemon = RpcDaemon::test_instance();
+    let sender = PrivateIdentity::new_from_name("alt-sender");
+    let key = hex::encode(sender.to_private_key_bytes());
+    let source = lxmf_delivery_hash_from_identity_hash(sender.address_hash()).to_hex_string();
+    let send = |id: &str, source: &str, key: &str| {
+        daemon.handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(serde_json::json!({
+                "id": id,
+                "source": source,
+                "destination": "00112233445566778899aabbccddeeff",
+                "content": "hi",
+                "source_private_key": key,
+            })),
+        })
+    };
+
+    send("derived", "", &key).unwrap();
+    send("matching", &source.to_uppercase(), &key).unwrap();
+    let err = send("mismatch", "ffeeddccbbaa99887766554433221100", &key).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = send("bad-key", "", "not-a-key").unwrap_err();
+    assert!(err.to_string().contains("source_private_key"));
+
+    let list = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .unwrap();
+    let sources: Vec<_> = list["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["source"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(sources, vec![source.clone(), source]);
+}
+
+#[test]
+fn send_message_records_content_type_and_renderer_field() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    for (id, content_type) in [("plain", None), ("md", Some("Text/Markdown"))] {
+        let mut params = serde_json::json!({
+            "id": id,
+            "source": "alice",
+            "destination": "bob",
+            "content": "**hi**",
+        });
+        if let Some(content_type) = content_type {
+            params["content_type"] = serde_json::json!(content_type);
+        }
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "send_message".into(),
+                params: Some(params),
+            })
+            .unwrap();
+    }
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "receive_message".into(),
+            params: Some(serde_json::json!({
+                "id": "micron",
+                "source": "bob",
+                "destination": "alice",
+                "content": "`!hi`!",
+                "fields": { "15": 1 }
+            })),
+        })
+        .unwrap();
+
+    let list = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .unwrap();
+    let messages = list.result.unwrap()["messages"]
+        .as_array()
+        .cloned()
+        .unwrap();
+    let by_id = |id: &str| {
+        messages
+            .iter()
+            .find(|message| message["id"] == id)
+            .cloned()
+            .unwrap()
+    };
+    assert_eq!(by_id("plain")["content_type"], "text/plain");
+    assert!(by_id("plain")["fields"].is_null());
+    assert_eq!(by_id("md")["content_type"], "text/markdown");
+    assert_eq!(by_id("md")["fields"]["15"], 2);
+    assert_eq!(by_id("micron")["content_type"], "text/micron");
+}
+
+#[test]
+fn receive_message_rejects_foreign_destinations() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_delivery_destination_hash(Some("00112233445566778899aabbccddeeff".into()));
+    let receive = |id: u64, destination: &str| {
+        daemon.handle_rpc(RpcRequest {
+            id,
+            method: "receive_message".into(),
+            params: Some(serde_json::json!({
+                "id": format!("msg-{id}"),
+                "source": "alice",
+                "destination": destination,
+                "title": "",
+                "content": "hi",
+            })),
+        })
+    };
+
+    let err = receive(1, "ffeeddccbbaa99887766554433221100").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    receive(2, "00112233445566778899AABBCCDDEEFF").expect("local destination");
+
+    daemon.set_accept_unaddressed_inbound(true);
+    receive(3, "bob").expect("override");
+}
@@ -1,4 +1,12 @@
-use reticulum::rpc::{RpcDaemon, RpcRequest};
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use reticulum::rpc::{
+    LxmfCodecBridge, OutboundBridge, OutboundDeliveryOptions, PaperBridge, PropagationStoreLimits,
+    RpcDaemon, RpcRequest,
+};
+use reticulum::storage::messages::{MessageRecord, MessagesStore};
 use serde_json::json;
 
 #[test]
@@ -209,45 +217,215 @@ fn propagation_ingest_fetch_roundtrip() {
 }
 
 #[test]
-fn paper_ingest_detects_duplicates() {
+fn propagation_ingest_bulk_stores_new_items_once() {
     let daemon = RpcDaemon::test_instance();
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 40,
+            method: "propagation_ingest".into(),
+            params: Some(json!({ "transient_id": "known", "payload_hex": "00" })),
+        })
+        .expect("propagation_ingest");
+
+    let bulk = daemon
+        .handle_rpc(RpcRequest {
+            id: 41,
+            method: "propagation_ingest_bulk".into(),
+            params: Some(json!({
+                "items": [
+                    { "transient_id": "known", "payload_hex": "01" },
+                    { "transient_id": "fresh", "payload_hex": "02" },
+                    { "transient_id": "fresh", "payload_hex": "03" },
+                    { "transient_id": "empty" }
+                ]
+            })),
+        })
+        .expect("propagation_ingest_bulk");
+    let result = bulk.result.expect("result");
+    assert_eq!(result["ingested_count"], 1);
+    let stored: Vec<_> = result["items"]
+        .as_array()
+        .expect("items")
+        .iter()
+        .map(|item| item["stored"].as_bool().unwrap())
+        .collect();
+    assert_eq!(stored, vec![false, true, false, false]);
+
+    let status = daemon
+        .handle_rpc(RpcRequest {
+            id: 42,
+            method: "propagation_status".into(),
+            params: None,
+        })
+        .expect("propagation_status");
+    let status = &status.result.expect("result")["propagation"];
+    assert_eq!(status["total_ingested"], 2);
+    assert_eq!(status["last_ingest_count"], 1);
+
+    let fetch = daemon
+        .handle_rpc(RpcRequest {
+            id: 43,
+            method: "propagation_fetch".into(),
+            params: Some(json!({ "transient_id": "fresh" })),
+        })
+        .expect("propagation_fetch");
+    assert_eq!(fetch.result.expect("result")["payload_hex"], "02");
+}
+
+#[test]
+fn propagation_store_survives_restart_and_evicts_oldest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    let call = |daemon: &RpcDaemon, method: &str, params: serde_json::Value| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: method.into(),
+                params: Some(params),
+            })
+            .expect(method)
+            .result
+            .expect("result")
+    };
+
+    {
+        let daemon = RpcDaemon::with_store(MessagesStore::open(&path).unwrap(), "daemon".into());
+        daemon.set_propagation_store_limits(PropagationStoreLimits {
+            max_bytes: 4,
+            max_messages: 0,
+            max_age_secs: 0,
+        });
+        for (transient_id, payload_hex) in [("a", "0011"), ("b", "2233"), ("c", "4455")] {
+            call(
+                &daemon,
+                "propagation_ingest",
+                json!({ "transient_id": transient_id, "payload_hex": payload_hex }),
+            );
+        }
+        // Ingest leaves pruning to the periodic pass.
+        assert!(daemon.take_event().is_none());
+        assert_eq!(daemon.prune_propagation_store().unwrap(), 1);
+        let pruned = daemon.take_event().expect("prune event");
+        assert_eq!(pruned.event_type, "propagation_store_pruned");
+        assert_eq!(
+            pruned.payload,
+            json!({ "removed": 1, "messages": 2, "bytes": 4 })
+        );
+    }
+
+    let daemon = RpcDaemon::with_store(MessagesStore::open(&path).unwrap(), "daemon".into());
+    let status = call(&daemon, "propagation_status", json!({}));
+    assert_eq!(status["store"]["messages"], 2);
+    assert_eq!(status["store"]["bytes"], 4);
+    let batch = call(&daemon, "propagation_fetch_batch", json!({ "limit": 1 }));
+    assert_eq!(batch["items"][0]["transient_id"], "b");
+    assert_eq!(batch["next"], "b");
+    let rest = call(&daemon, "propagation_fetch_batch", json!({ "after": "b" }));
+    assert_eq!(rest["items"][0]["payload_hex"], "4455");
+    assert!(rest["next"].is_null());
+    let missing = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "propagation_fetch".into(),
+            params: Some(json!({ "transient_id": "a" })),
+        })
+        .unwrap_err();
+    assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+}
+
+struct FixedPaperDecoder;
+
+impl PaperBridge for FixedPaperDecoder {
+    fn decode_paper_message(&self, packed: &[u8]) -> Result<MessageRecord, String> {
+        if packed.len() <= 16 {
+            return Err("paper message too short".into());
+        }
+        Ok(MessageRecord {
+            id: "paper-1".into(),
+            source: "00112233445566778899aabbccddeeff".into(),
+            destination: hex::encode(&packed[..16]),
+            title: "paper".into(),
+            content: "hello from paper".into(),
+            timestamp: 1_700_000_000,
+            direction: "in".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+    }
+}
+
+#[test]
+fn paper_ingest_decodes_stores_and_detects_duplicates() {
+    let daemon = RpcDaemon::test_instance().with_paper_bridge(Arc::new(FixedPaperDecoder));
+    let packed = [[0x6bu8; 16].as_slice(), b"encrypted body"].concat();
+    let uri = format!("lxm://{}", URL_SAFE_NO_PAD.encode(&packed));
+
     let first = daemon
         .handle_rpc(RpcRequest {
             id: 14,
             method: "paper_ingest_uri".into(),
-            params: Some(json!({
-                "uri": "lxm://6b3362bd2c1dbf87b66a85f79a8d8c75HELLO"
-            })),
+            params: Some(json!({ "uri": uri })),
         })
-        .expect("paper ingest");
-    assert_eq!(first.result.expect("result")["duplicate"], false);
+        .expect("paper ingest")
+        .result
+        .expect("result");
+    assert_eq!(first["duplicate"], false);
+    assert_eq!(first["message_id"], "paper-1");
+    assert_eq!(first["destination"], "6b".repeat(16));
+    assert_eq!(first["content"], "hello from paper");
 
     let second = daemon
         .handle_rpc(RpcRequest {
             id: 15,
             method: "paper_ingest_uri".into(),
-            params: Some(json!({
-                "uri": "lxm://6b3362bd2c1dbf87b66a85f79a8d8c75HELLO"
-            })),
+            params: Some(json!({ "uri": format!("{uri}==") })),
         })
-        .expect("paper ingest");
-    assert_eq!(second.result.expect("result")["duplicate"], true);
+        .expect("paper ingest")
+        .result
+        .expect("result");
+    assert_eq!(second["duplicate"], true);
+
+    let messages = daemon
+        .handle_rpc(RpcRequest {
+            id: 16,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .expect("list messages")
+        .result
+        .expect("result");
+    let messages = messages["messages"].as_array().expect("messages");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["delivery_method"], "paper");
 }
 
 #[test]
-fn paper_ingest_handles_utf8_uri_prefix_safely() {
-    let daemon = RpcDaemon::test_instance();
-    let unicode_body = "é".repeat(40);
-    let uri = format!("lxm://{unicode_body}");
-    let response = daemon
+fn paper_ingest_reports_malformed_uri() {
+    let daemon = RpcDaemon::test_instance().with_paper_bridge(Arc::new(FixedPaperDecoder));
+    let uri = format!("lxm://{}", "é".repeat(40));
+    let err = daemon
         .handle_rpc(RpcRequest {
             id: 16,
             method: "paper_ingest_uri".into(),
             params: Some(json!({ "uri": uri })),
         })
+        .expect_err("malformed uri");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("base64url"));
+
+    let short = daemon
+        .handle_rpc(RpcRequest {
+            id: 17,
+            method: "paper_ingest_uri".into(),
+            params: Some(json!({ "uri": "lxm://AAAA" })),
+        })
         .expect("paper ingest");
-    let result = response.result.expect("result");
-    assert_eq!(result["destination"], "é".repeat(32));
+    let error = short.error.expect("decode error");
+    assert_eq!(error.code, "PAPER_DECODE_FAILED");
+    assert_eq!(error.message, "paper message too short");
 }
 
 #[test]
@@ -342,6 +520,8 @@ fn propagation_node_selection_roundtrip() {
         .expect("get_outbound_propagation_node");
     let selected_result = selected.result.expect("result");
     assert_eq!(selected_result["peer"], "relay-a");
+    assert_eq!(selected_result["last_seen"], 200);
+    assert!(selected_result["has_path"].is_null());
     assert_eq!(selected_result["meta"]["contract_version"], "v2");
 
     let listed = daemon
@@ -406,6 +586,80 @@ fn list_propagation_nodes_only_returns_propagation_nodes() {
     assert!(!nodes.iter().any(|entry| entry["peer"] == "relay-chat"));
 }
 
+#[test]
+fn request_alternative_propagation_relay_prefers_close_cheap_relays() {
+    let daemon = RpcDaemon::test_instance();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let relays = [
+        ("relay-a", 4, 0),
+        ("relay-e", 1, 8),
+        ("relay-b", 1, 8),
+        ("relay-c", 0, 0),
+        ("relay-d", 0, 0),
+    ];
+    for (index, (peer, hops, cost)) in relays.into_iter().enumerate() {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 30 + index as u64,
+                method: "announce_received".into(),
+                params: Some(json!({
+                    "peer": peer,
+                    "timestamp": now,
+                    "capabilities": ["propagation"],
+                    "hops": hops,
+                    "stamp_cost": cost,
+                    "peering_cost": cost,
+                })),
+            })
+            .expect("announce_received");
+    }
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 40,
+            method: "set_outbound_propagation_node".into(),
+            params: Some(json!({ "peer": "relay-c" })),
+        })
+        .expect("set_outbound_propagation_node");
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 41,
+            method: "request_alternative_propagation_relay".into(),
+            params: Some(json!({ "exclude": ["relay-d"] })),
+        })
+        .expect("request_alternative_propagation_relay");
+    let result = response.result.expect("result");
+    // relay-e scores the same as relay-b and loses the tie on its hash.
+    assert_eq!(result["peer"], "relay-b");
+    assert_eq!(result["previous"], "relay-c");
+    assert_eq!(result["candidates"], 3);
+    let score = result["score"].as_f64().expect("score");
+    assert!(score > 0.0 && score <= 1.0);
+
+    let selected = daemon
+        .handle_rpc(RpcRequest {
+            id: 42,
+            method: "get_outbound_propagation_node".into(),
+            params: None,
+        })
+        .expect("get_outbound_propagation_node");
+    assert_eq!(selected.result.expect("result")["peer"], "relay-b");
+
+    let exhausted = daemon
+        .handle_rpc(RpcRequest {
+            id: 43,
+            method: "request_alternative_propagation_relay".into(),
+            params: Some(json!({ "exclude": ["relay-a", "relay-c", "relay-d", "relay-e"] })),
+        })
+        .expect("request_alternative_propagation_relay");
+    let exhausted = exhausted.result.expect("result");
+    assert!(exhausted["peer"].is_null());
+    assert_eq!(exhausted["previous"], "relay-b");
+}
+
 #[test]
 fn message_delivery_trace_records_transitions() {
     let daemon = RpcDaemon::test_instance();
@@ -457,6 +711,62 @@ fn message_delivery_trace_records_transitions() {
         .any(|entry| entry["status"] == "delivered" && entry["reason_code"].is_null()));
 }
 
+#[test]
+fn message_delivery_trace_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    {
+        let daemon = RpcDaemon::with_store(
+            reticulum::storage::messages::MessagesStore::open(&path).unwrap(),
+            "daemon".into(),
+        );
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 30,
+                method: "send_message".into(),
+                params: Some(json!({
+                    "id": "trace-durable",
+                    "source": "alice",
+                    "destination": "bob",
+                    "content": "hello"
+                })),
+            })
+            .expect("send_message");
+        for _ in 0..40 {
+            daemon
+                .handle_rpc(RpcRequest {
+                    id: 31,
+                    method: "record_receipt".into(),
+                    params: Some(json!({
+                        "message_id": "trace-durable",
+                        "status": "failed: receipt timeout"
+                    })),
+                })
+                .expect("record_receipt");
+        }
+    }
+
+    let daemon = RpcDaemon::with_store(
+        reticulum::storage::messages::MessagesStore::open(&path).unwrap(),
+        "daemon".into(),
+    );
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 32,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "trace-durable" })),
+        })
+        .expect("message_delivery_trace");
+    let transitions = trace.result.expect("result")["transitions"]
+        .as_array()
+        .cloned()
+        .expect("transitions");
+    assert_eq!(transitions.len(), 32);
+    assert!(transitions
+        .iter()
+        .all(|entry| entry["reason_code"] == "receipt_timeout"));
+}
+
 #[test]
 fn receipt_event_exposes_reason_code() {
     let daemon = RpcDaemon::test_instance();
@@ -512,3 +822,814 @@ fn receipt_event_exposes_reason_code() {
         .expect("failed transition");
     assert_eq!(timeout_transition["reason_code"], "receipt_timeout");
 }
+
+#[test]
+fn message_delivery_trace_reports_stages_and_rollup() {
+    let daemon = RpcDaemon::test_instance();
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "trace-stages",
+                "source": "alice",
+                "destination": "bob",
+                "content": "hello"
+            })),
+        })
+        .expect("send_message");
+    for status in [
+        "sent: link",
+        "link failed: timeout; trying opportunistic",
+        "sent: opportunistic",
+        "failed: receipt timeout",
+    ] {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 2,
+                method: "record_receipt".into(),
+                params: Some(json!({ "message_id": "trace-stages", "status": status })),
+            })
+            .expect("record_receipt");
+    }
+
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "trace-stages" })),
+        })
+        .expect("message_delivery_trace")
+        .result
+        .expect("result");
+    let stages: Vec<_> = trace["transitions"]
+        .as_array()
+        .expect("transitions")
+        .iter()
+        .map(|entry| entry["stage"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        stages,
+        [
+            "queued",
+            "sending",
+            "link",
+            "link",
+            "opportunistic",
+            "opportunistic",
+            "failed"
+        ]
+    );
+    assert_eq!(trace["stage"], "failed");
+    assert_eq!(trace["reason_code"], "receipt_timeout");
+}
+
+#[test]
+fn propagation_sync_history_records_completed_and_failed_syncs() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.update_propagation_sync_state(|state| {
+        state.state_name = "receiving".into();
+        state.last_sync_started = Some(100);
+        state.messages_received = 2;
+    });
+    daemon.update_propagation_sync_state(|state| {
+        state.state_name = "complete".into();
+        state.last_sync_completed = Some(110);
+    });
+    daemon.update_propagation_sync_state(|state| {
+        state.state_name = "failed".into();
+        state.last_sync_started = Some(200);
+        state.messages_received = 0;
+        state.last_sync_error = Some("link timeout".into());
+    });
+    // The same error again is still a new sync; a reset to idle is not.
+    daemon.update_propagation_sync_state(|state| {
+        state.state_name = "link_establishing".into();
+        state.last_sync_started = Some(300);
+    });
+    daemon.update_propagation_sync_state(|state| {
+        state.state_name = "failed".into();
+    });
+    daemon.update_propagation_sync_state(|state| {
+        state.state_name = "idle".into();
+    });
+
+    let resp = daemon
+        .handle_rpc(RpcRequest {
+            id: 40,
+            method: "propagation_sync_history".into(),
+            params: None,
+        })
+        .expect("propagation_sync_history");
+    let syncs = resp.result.expect("result")["syncs"]
+        .as_array()
+        .cloned()
+        .expect("syncs");
+    assert_eq!(syncs.len(), 3);
+    assert_eq!(syncs[0]["started_at"], 300);
+    assert_eq!(syncs[0]["error"], "link timeout");
+    assert_eq!(syncs[1]["state_name"], "failed");
+    assert_eq!(syncs[1]["error"], "link timeout");
+    assert!(syncs[1]["completed_at"].is_null());
+    assert_eq!(syncs[2]["state_name"], "complete");
+    assert_eq!(syncs[2]["started_at"], 100);
+    assert_eq!(syncs[2]["completed_at"], 110);
+    assert_eq!(syncs[2]["messages_received"], 2);
+    assert!(syncs[2]["error"].is_null());
+}
+
+#[test]
+fn propagation_sync_abort_resets_a_running_sync() {
+    let daemon = RpcDaemon::test_instance();
+    let abort = |daemon: &RpcDaemon| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 41,
+                method: "propagation_sync_abort".into(),
+                params: None,
+            })
+            .expect("propagation_sync_abort")
+            .result
+            .expect("result")
+    };
+
+    let idle = abort(&daemon);
+    assert_eq!(idle["aborted"], false);
+    assert!(daemon.take_event().is_none());
+
+    let token = daemon.begin_propagation_sync();
+    daemon.update_propagation_sync_state(|state| {
+        state.sync_state = 5;
+        state.state_name = "receiving".into();
+        state.sync_progress = 0.4;
+        state.last_sync_started = Some(100);
+    });
+    let aborted = abort(&daemon);
+    assert_eq!(aborted["aborted"], true);
+    assert_eq!(aborted["propagation"]["state_name"], "idle");
+    assert_eq!(aborted["propagation"]["sync_state"], 0);
+    assert_eq!(aborted["propagation"]["last_sync_error"], "aborted");
+    assert!(token.is_cancelled());
+    assert_eq!(
+        daemon.take_event().expect("state event").event_type,
+        "propagation_state"
+    );
+    assert_eq!(abort(&daemon)["aborted"], false);
+}
+
+#[test]
+fn propagation_fetch_batch_reports_missing_ids_and_pages_the_store() {
+    let daemon = RpcDaemon::test_instance();
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 50,
+            method: "propagation_ingest_bulk".into(),
+            params: Some(json!({
+                "items": [
+                    { "transient_id": "a", "payload_hex": "01" },
+                    { "transient_id": "b", "payload_hex": "02" },
+                    { "transient_id": "c", "payload_hex": "03" }
+                ]
+            })),
+        })
+        .expect("propagation_ingest_bulk");
+
+    let by_id = daemon
+        .handle_rpc(RpcRequest {
+            id: 51,
+            method: "propagation_fetch_batch".into(),
+            params: Some(json!({ "transient_ids": ["b", "zz"] })),
+        })
+        .expect("propagation_fetch_batch")
+        .result
+        .expect("result");
+    assert_eq!(by_id["items"][0]["payload_hex"], "02");
+    assert_eq!(by_id["missing"], json!(["zz"]));
+
+    let first = daemon
+        .handle_rpc(RpcRequest {
+            id: 52,
+            method: "propagation_fetch_batch".into(),
+            params: Some(json!({ "limit": 2 })),
+        })
+        .expect("propagation_fetch_batch")
+        .result
+        .expect("result");
+    assert_eq!(first["items"].as_array().unwrap().len(), 2);
+    assert_eq!(first["next"], "b");
+
+    let rest = daemon
+        .handle_rpc(RpcRequest {
+            id: 53,
+            method: "propagation_fetch_batch".into(),
+            params: Some(json!({ "limit": 2, "after": "b" })),
+        })
+        .expect("propagation_fetch_batch")
+        .result
+        .expect("result");
+    assert_eq!(rest["items"][0]["transient_id"], "c");
+    assert!(rest["next"].is_null());
+}
+
+#[test]
+fn clear_resets_only_the_requested_scopes() {
+    let daemon = RpcDaemon::test_instance();
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 60,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "kept",
+                "source": "alice",
+                "destination": "bob",
+                "content": "hello",
+                "allow_logical_destination": true
+            })),
+        })
+        .expect("send_message");
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 61,
+            method: "propagation_ingest".into(),
+            params: Some(json!({ "transient_id": "t1", "payload_hex": "00" })),
+        })
+        .expect("propagation_ingest");
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 62,
+            method: "clear".into(),
+            params: Some(json!({ "scopes": ["propagation", "everything"] })),
+        })
+        .expect_err("unknown scope");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 63,
+            method: "clear".into(),
+            params: Some(json!({ "scopes": ["resources"] })),
+        })
+        .expect_err("unsupported scope");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    let cleared = daemon
+        .handle_rpc(RpcRequest {
+            id: 63,
+            method: "clear".into(),
+            params: Some(json!({ "scopes": ["propagation", "propagation"] })),
+        })
+        .expect("clear")
+        .result
+        .expect("result");
+    assert_eq!(cleared["cleared"], json!(["propagation"]));
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 64,
+            method: "propagation_fetch".into(),
+            params: Some(json!({ "transient_id": "t1" })),
+        })
+        .expect_err("payload cleared");
+    let messages = daemon
+        .handle_rpc(RpcRequest {
+            id: 65,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .expect("list_messages")
+        .result
+        .expect("result");
+    assert_eq!(messages["messages"].as_array().unwrap().len(), 1);
+}
+
+struct PathlessBridge;
+
+impl OutboundBridge for PathlessBridge {
+    fn deliver(
+        &self,
+        _record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn has_path(&self, _destination: &str) -> Option<bool> {
+        Some(false)
+    }
+}
+
+#[test]
+fn outbound_propagation_node_reports_when_it_is_unreachable() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().unwrap(),
+        "daemon".into(),
+        Arc::new(PathlessBridge),
+    );
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 70,
+            method: "set_outbound_propagation_node".into(),
+            params: Some(json!({ "peer": "relay-b" })),
+        })
+        .expect("set_outbound_propagation_node");
+
+    let selected = daemon
+        .handle_rpc(RpcRequest {
+            id: 71,
+            method: "get_outbound_propagation_node".into(),
+            params: None,
+        })
+        .expect("get_outbound_propagation_node")
+        .result
+        .expect("result");
+    assert_eq!(selected["has_path"], false);
+    assert!(selected["last_seen"].is_null());
+}
+
+// Packs every message with an eight-byte stamp after the payload fields,
+// noting the stamp cost each delivery asked for.
+#[derive(Default)]
+struct StampingBridge {
+    requested_costs: Mutex<Vec<Option<u32>>>,
+}
+
+impl OutboundBridge for StampingBridge {
+    fn deliver(
+        &self,
+        _record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn deliver_packed(
+        &self,
+        record: &MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<Option<Vec<u8>>, std::io::Error> {
+        self.requested_costs
+            .lock()
+            .unwrap()
+            .push(options.stamp_cost);
+        let mut packed = vec![0u8; 16 + 16 + 64];
+        let payload = rmpv::Value::Array(vec![
+            rmpv::Value::from(record.timestamp as f64),
+            rmpv::Value::Binary(record.title.clone().into_bytes()),
+            rmpv::Value::Binary(record.content.clone().into_bytes()),
+            rmpv::Value::Map(Vec::new()),
+            rmpv::Value::Binary(vec![7; 8]),
+        ]);
+        rmpv::encode::write_value(&mut packed, &payload).unwrap();
+        Ok(Some(packed))
+    }
+}
+
+#[test]
+fn stamp_accounting_tracks_outbound_cost_and_under_stamped_inbound() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().expect("store"),
+        "daemon".into(),
+        Arc::new(StampingBridge::default()),
+    );
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 80,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": "stamped-out",
+                "source": "alice",
+                "destination": "bob",
+                "content": "work",
+                "stamp_cost": 8
+            })),
+        })
+        .expect("send_message_v2");
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 81,
+            method: "stamp_policy_set".into(),
+            params: Some(json!({ "target_cost": 6 })),
+        })
+        .expect("stamp_policy_set");
+    assert!(daemon
+        .record_inbound_stamp("weak-in", "carol", Some(3))
+        .unwrap());
+    assert!(!daemon
+        .record_inbound_stamp("strong-in", "carol", Some(7))
+        .unwrap());
+
+    let message = daemon
+        .handle_rpc(RpcRequest {
+            id: 82,
+            method: "get_message".into(),
+            params: Some(json!({ "message_id": "stamped-out" })),
+        })
+        .expect("get_message")
+        .result
+        .expect("result");
+    assert_eq!(message["message"]["stamp"]["stamp_cost"], 8);
+    assert!(message["message"]["stamp"]["stamp_value"].is_null());
+
+    let stats = daemon
+        .handle_rpc(RpcRequest {
+            id: 83,
+            method: "stamp_stats".into(),
+            params: Some(json!({ "window_secs": 3600 })),
+        })
+        .expect("stamp_stats")
+        .result
+        .expect("result");
+    assert_eq!(stats["target_cost"], 6);
+    assert_eq!(stats["stats"]["outbound_messages"], 1);
+    assert_eq!(stats["stats"]["outbound_work"], 256);
+    assert_eq!(stats["stats"]["inbound_messages"], 2);
+    assert_eq!(stats["stats"]["inbound_under_stamped"], 1);
+}
+
+#[test]
+fn outbound_messages_sent_without_a_stamp_are_not_counted() {
+    let daemon = RpcDaemon::test_instance();
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 80,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": "unstamped-out",
+                "source": "alice",
+                "destination": "bob",
+                "content": "work",
+                "stamp_cost": 8
+            })),
+        })
+        .expect("send_message_v2");
+
+    let message = daemon
+        .handle_rpc(RpcRequest {
+            id: 81,
+            method: "get_message".into(),
+            params: Some(json!({ "message_id": "unstamped-out" })),
+        })
+        .expect("get_message")
+        .result
+        .expect("result");
+    assert!(message["message"]["stamp"].is_null());
+    let stats = daemon
+        .handle_rpc(RpcRequest {
+            id: 82,
+            method: "stamp_stats".into(),
+            params: Some(json!({ "window_secs": 3600 })),
+        })
+        .expect("stamp_stats")
+        .result
+        .expect("result");
+    assert_eq!(stats["stats"]["outbound_messages"], 0);
+}
+
+#[test]
+fn stamp_policy_overrides_win_over_the_global_policy() {
+    let bridge = Arc::new(StampingBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().expect("store"),
+        "daemon".into(),
+        bridge.clone(),
+    );
+    let trusted = "aa".repeat(16);
+    let stranger = "bb".repeat(16);
+    let call = |id: u64, method: &str, params: serde_json::Value| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id,
+                method: method.into(),
+                params: Some(params),
+            })
+            .expect(method)
+            .result
+            .expect("result")
+    };
+    call(90, "stamp_policy_set", json!({ "target_cost": 8 }));
+    let set = call(
+        91,
+        "set_stamp_policy_for",
+        json!({ "destination": trusted.to_uppercase(), "target_cost": 0 }),
+    );
+    assert_eq!(set["destination"], trusted);
+    assert_eq!(set["stamp_policy"]["target_cost"], 0);
+    assert_eq!(set["overridden"], true);
+
+    let effective = call(
+        92,
+        "get_stamp_policy_for",
+        json!({ "destination": stranger }),
+    );
+    assert_eq!(effective["stamp_policy"]["target_cost"], 8);
+    assert_eq!(effective["overridden"], false);
+
+    assert!(!daemon
+        .record_inbound_stamp("from-trusted", &trusted, None)
+        .unwrap());
+    assert!(daemon
+        .record_inbound_stamp("from-stranger", &stranger, Some(4))
+        .unwrap());
+
+    call(
+        93,
+        "set_stamp_policy_for",
+        json!({ "destination": stranger, "target_cost": 12 }),
+    );
+    call(
+        94,
+        "send_message_v2",
+        json!({ "id": "to-stranger", "source": "me", "destination": stranger, "content": "hi" }),
+    );
+    // The override only reaches the bridge as the requested stamp cost; it
+    // is recorded because this bridge attached a stamp.
+    assert_eq!(*bridge.requested_costs.lock().unwrap(), vec![Some(12)]);
+    let message = call(95, "get_message", json!({ "message_id": "to-stranger" }));
+    assert_eq!(message["message"]["stamp"]["stamp_cost"], 12);
+
+    call(
+        96,
+        "set_stamp_policy_for",
+        json!({ "destination": trusted, "clear": true }),
+    );
+    let effective = call(
+        97,
+        "get_stamp_policy_for",
+        json!({ "destination": trusted }),
+    );
+    assert_eq!(effective["stamp_policy"]["target_cost"], 8);
+    let overrides = call(98, "stamp_policy_get", json!({}));
+    assert_eq!(overrides["overrides"][&stranger]["target_cost"], 12);
+    assert!(overrides["overrides"].get(&trusted).is_none());
+}
+
+struct BusyBridge;
+
+impl OutboundBridge for BusyBridge {
+    fn deliver(
+        &self,
+        _record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn pending_deliveries(&self) -> usize {
+        2
+    }
+}
+
+#[test]
+fn send_message_reports_queue_position_and_eta() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().unwrap(),
+        "daemon".into(),
+        Arc::new(BusyBridge),
+    );
+    let sent = daemon
+        .handle_rpc(RpcRequest {
+            id: 90,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": "eta-1",
+                "source": "alice",
+                "destination": "bob",
+                "content": "slow",
+                "stamp_cost": 10
+            })),
+        })
+        .expect("send_message_v2")
+        .result
+        .expect("result");
+    assert_eq!(sent["queue_position"], 2);
+    // Only the deliveries in flight count; the stamp cost adds nothing.
+    assert_eq!(sent["est_delay_ms"], 2 * 500);
+
+    let trace = daemon
+        .handle_rpc(RpcRequest {
+            id: 91,
+            method: "message_delivery_trace".into(),
+            params: Some(json!({ "message_id": "eta-1" })),
+        })
+        .expect("message_delivery_trace")
+        .result
+        .expect("result");
+    let progress = &trace["transitions"][1];
+    assert_eq!(
+        progress["status"],
+        "queued: 2 deliveries in flight, est 1000ms"
+    );
+    assert_eq!(progress["stage"], "queued");
+}
+
+// Stands in for the LXMF codec with a plain "id|source|destination|content"
+// encoding of the record.
+struct TextLxmfCodec;
+
+impl LxmfCodecBridge for TextLxmfCodec {
+    fn encode_lxmf_message(&self, record: &MessageRecord) -> Result<Vec<u8>, String> {
+        Ok(format!(
+            "{}|{}|{}|{}",
+            record.id, record.source, record.destination, record.content
+        )
+        .into_bytes())
+    }
+
+    fn decode_lxmf_message(&self, packed: &[u8]) -> Result<MessageRecord, String> {
+        let text = String::from_utf8_lossy(packed);
+        let parts: Vec<&str> = text.splitn(4, '|').collect();
+        let [id, source, destination, content] = parts[..] else {
+            return Err("not a text lxmf message".into());
+        };
+        Ok(MessageRecord {
+            id: id.into(),
+            source: source.into(),
+            destination: destination.into(),
+            title: String::new(),
+            content: content.into(),
+            timestamp: 1_700_000_000,
+            direction: "in".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+    }
+}
+
+#[test]
+fn lxmf_export_and_import_round_trip_through_the_codec() {
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().expect("store"), "me".into());
+    let export = |daemon: &RpcDaemon, message_id: &str| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "export_message_lxmf".into(),
+                params: Some(json!({ "message_id": message_id })),
+            })
+            .expect("export_message_lxmf")
+    };
+    daemon.inject_inbound_test_message("archived");
+    let stored = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .expect("list_messages")
+        .result
+        .expect("result");
+    let message_id = stored["messages"][0]["id"]
+        .as_str()
+        .expect("message id")
+        .to_string();
+    assert_eq!(
+        export(&daemon, &message_id).error.expect("error").code,
+        "LXMF_CODEC_UNAVAILABLE"
+    );
+
+    let daemon = daemon.with_lxmf_codec_bridge(Arc::new(TextLxmfCodec));
+    assert_eq!(
+        export(&daemon, "missing").error.expect("error").code,
+        "MESSAGE_NOT_FOUND"
+    );
+    // Received messages cannot be re-signed by us.
+    assert_eq!(
+        export(&daemon, &message_id).error.expect("error").code,
+        "LXMF_EXPORT_UNSUPPORTED"
+    );
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "sent-here",
+                "source": "me",
+                "destination": "peer",
+                "content": "hello",
+            })),
+        })
+        .expect("send_message");
+    let exported = export(&daemon, "sent-here").result.expect("result");
+    let packed = hex::decode(exported["lxmf_hex"].as_str().expect("hex")).expect("hex");
+    assert_eq!(packed, b"sent-here|me|peer|hello");
+    assert_eq!(exported["bytes_len"], packed.len());
+
+    while daemon.take_event().is_some() {}
+    let import = |params: serde_json::Value| {
+        daemon.handle_rpc(RpcRequest {
+            id: 3,
+            method: "import_message_lxmf".into(),
+            params: Some(params),
+        })
+    };
+    let duplicate = import(json!({ "lxmf_base64": exported["lxmf_base64"] }))
+        .expect("import")
+        .result
+        .expect("result");
+    assert_eq!(duplicate["duplicate"], true);
+
+    let imported = import(json!({ "lxmf_hex": hex::encode("sent-elsewhere|me|peer|hi") }))
+        .expect("import")
+        .result
+        .expect("result");
+    assert_eq!(imported["duplicate"], false);
+    assert_eq!(imported["direction"], "out");
+    assert!(daemon.take_event().is_none());
+
+    // An imported message exports as the exact bytes it was imported from.
+    let original = "from-bob|bob|me|signed by bob";
+    let imported = import(json!({ "lxmf_hex": hex::encode(original) }))
+        .expect("import")
+        .result
+        .expect("result");
+    assert_eq!(imported["direction"], "in");
+    let exported = export(&daemon, "from-bob").result.expect("result");
+    assert_eq!(exported["lxmf_hex"], hex::encode(original));
+
+    let err = import(json!({ "lxmf_hex": "00", "lxmf_base64": "AA==" })).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let failed = import(json!({ "lxmf_hex": "00" })).expect("import");
+    assert_eq!(failed.error.expect("error").code, "LXMF_DECODE_FAILED");
+}
+
+// Packs each message as "wire:<id>", the way a transport would stamp it.
+struct PackingBridge;
+
+impl OutboundBridge for PackingBridge {
+    fn deliver(
+        &self,
+        _record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    fn deliver_packed(
+        &self,
+        record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<Option<Vec<u8>>, std::io::Error> {
+        Ok(Some(format!("wire:{}", record.id).into_bytes()))
+    }
+}
+
+#[test]
+fn lxmf_export_returns_the_bytes_a_message_travelled_as() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().expect("store"),
+        "me".into(),
+        Arc::new(PackingBridge),
+    )
+    .with_lxmf_codec_bridge(Arc::new(TextLxmfCodec));
+    let export = |message_id: &str| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "export_message_lxmf".into(),
+                params: Some(json!({ "message_id": message_id })),
+            })
+            .expect("export_message_lxmf")
+            .result
+            .expect("result")["lxmf_hex"]
+            .clone()
+    };
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "sent-here",
+                "source": "me",
+                "destination": "peer",
+                "content": "hello",
+            })),
+        })
+        .expect("send_message");
+    assert_eq!(export("sent-here"), hex::encode("wire:sent-here"));
+
+    daemon
+        .accept_inbound(MessageRecord {
+            id: "from-bob".into(),
+            source: "bob".into(),
+            destination: "me".into(),
+            title: String::new(),
+            content: "hi".into(),
+            timestamp: 1,
+            direction: "in".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .expect("accept_inbound");
+    daemon
+        .store_inbound_packed("from-bob", b"wire:from-bob")
+        .expect("store_inbound_packed");
+    assert_eq!(export("from-bob"), hex::encode("wire:from-bob"));
+}
@@ -0,0 +1,119 @@
+use reticulum::rpc::{OutboundBridge, OutboundDeliveryOptions, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::{MessageRecord, MessagesStore};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct RecordingBridge {
+    delivered: Mutex<Vec<MessageRecord>>,
+}
+
+impl OutboundBridge for RecordingBridge {
+    fn deliver(
+        &self,
+        record: &MessageRecord,
+        _options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        if record.destination == UNREACHABLE {
+            return Err(std::io::Error::other("no path"));
+        }
+        self.delivered.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+const LOCAL: &str = "00112233445566778899aabbccddeeff";
+const PEER_A: &str = "ffeeddccbbaa99887766554433221100";
+const PEER_B: &str = "0123456789abcdef0123456789abcdef";
+const UNREACHABLE: &str = "deadbeefdeadbeefdeadbeefdeadbeef";
+
+fn rpc(daemon: &RpcDaemon, method: &str, params: Value) -> reticulum::rpc::RpcResponse {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .expect(method)
+}
+
+#[test]
+fn send_multicast_delivers_one_copy_per_recipient() {
+    let bridge = Arc::new(RecordingBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().unwrap(),
+        LOCAL.into(),
+        bridge.clone(),
+    );
+
+    let response = rpc(
+        &daemon,
+        "send_multicast",
+        json!({
+            "group_id": "g1",
+            "source": LOCAL,
+            "destinations": [PEER_A, PEER_B, PEER_A, UNREACHABLE],
+            "content": "hello all",
+        }),
+    );
+    let result = response.result.unwrap();
+    assert_eq!(result["group_id"], "g1");
+    let results = result["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["destination"], PEER_A);
+    assert_eq!(results[0]["status"], "sent");
+    assert_eq!(results[1]["status"], "sent");
+    assert_eq!(results[2]["status"], "failed");
+
+    let delivered = bridge.delivered.lock().unwrap().clone();
+    assert_eq!(delivered.len(), 2);
+    assert!(delivered
+        .iter()
+        .all(|record| record.fields.as_ref().unwrap()["group_id"] == "g1"));
+
+    let listed = rpc(&daemon, "list_messages", json!({ "collapse_groups": true }))
+        .result
+        .unwrap();
+    let messages = listed["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["group_id"], "g1");
+    assert_eq!(messages[0]["recipients"].as_array().unwrap().len(), 3);
+
+    // Reusing the group id stores new copies instead of replacing the old.
+    let again = rpc(
+        &daemon,
+        "send_multicast",
+        json!({
+            "group_id": "g1",
+            "source": LOCAL,
+            "destinations": [PEER_A],
+            "content": "hello again",
+        }),
+    )
+    .result
+    .unwrap();
+    assert_ne!(again["results"][0]["message_id"], results[0]["message_id"]);
+    let first = rpc(
+        &daemon,
+        "get_message",
+        json!({ "message_id": results[0]["message_id"] }),
+    );
+    assert!(first.error.is_none(), "{:?}", first.error);
+}
+
+#[test]
+fn send_multicast_rejects_an_empty_recipient_list() {
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().unwrap(),
+        LOCAL.into(),
+        Arc::new(RecordingBridge::default()),
+    );
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_multicast".into(),
+            params: Some(json!({ "source": LOCAL, "destinations": [], "content": "x" })),
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,90 @@
+use std::sync::{Arc, Mutex};
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine as _;
+use reticulum::destination::{DestinationName, PlainOutputDestination};
+use reticulum::identity::EmptyIdentity;
+use reticulum::rpc::{PlainBridge, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::json;
+
+#[derive(Default)]
+struct RecordingPlain {
+    sent: Mutex<Vec<(String, String, Vec<u8>)>>,
+}
+
+impl PlainBridge for RecordingPlain {
+    fn send_plain(&self, app: &str, aspect: &str, data: Vec<u8>) -> Result<(), std::io::Error> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((app.into(), aspect.into(), data));
+        Ok(())
+    }
+}
+
+fn send_plain(daemon: &RpcDaemon, params: serde_json::Value) -> std::io::Result<serde_json::Value> {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_plain".into(),
+            params: Some(params),
+        })
+        .map(|response| response.result.unwrap_or_default())
+}
+
+#[test]
+fn send_plain_requires_acknowledgement_and_broadcasts() {
+    let bridge = Arc::new(RecordingPlain::default());
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_plain_bridge(bridge.clone());
+    let data_base64 = BASE64_STANDARD.encode(b"repeater up");
+
+    let err = send_plain(
+        &daemon,
+        json!({ "app": "beacon", "aspect": "status", "data_base64": data_base64 }),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("acknowledge_unencrypted"));
+    assert!(bridge.sent.lock().unwrap().is_empty());
+
+    let result = send_plain(
+        &daemon,
+        json!({
+            "app": "beacon",
+            "aspect": "status",
+            "data_base64": data_base64,
+            "acknowledge_unencrypted": true,
+        }),
+    )
+    .unwrap();
+    let expected =
+        PlainOutputDestination::new(EmptyIdentity {}, DestinationName::new("beacon", "status"))
+            .desc
+            .address_hash
+            .to_hex_string();
+    assert_eq!(result["destination"], expected);
+    assert_eq!(result["data_len"], 11);
+    assert_eq!(
+        bridge.sent.lock().unwrap().as_slice(),
+        &[("beacon".into(), "status".into(), b"repeater up".to_vec())]
+    );
+}
+
+#[test]
+fn send_plain_requires_transport() {
+    let daemon = RpcDaemon::test_instance();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_plain".into(),
+            params: Some(json!({
+                "app": "beacon",
+                "aspect": "status",
+                "data_base64": BASE64_STANDARD.encode(b"up"),
+                "acknowledge_unencrypted": true,
+            })),
+        })
+        .unwrap();
+    assert_eq!(response.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+}
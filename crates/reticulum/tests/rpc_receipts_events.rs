@@ -48,3 +48,50 @@ fn record_receipt_emits_event_and_updates_store() {
     let messages = result.get("messages").unwrap().as_array().unwrap();
     assert_eq!(messages[0].get("receipt_status").unwrap(), "delivered");
 }
+
+#[test]
+fn record_receipt_updates_delivery_method() {
+    let daemon = RpcDaemon::test_instance();
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_message".into(),
+            params: Some(json!({
+                "id": "msg-2",
+                "source": "peer-a",
+                "destination": "peer-b",
+                "content": "hello"
+            })),
+        })
+        .unwrap();
+
+    let delivery_method = |daemon: &RpcDaemon| {
+        let list = daemon
+            .handle_rpc(RpcRequest {
+                id: 2,
+                method: "list_messages".into(),
+                params: None,
+            })
+            .unwrap();
+        list.result.unwrap()["messages"][0]["delivery_method"].clone()
+    };
+    assert_eq!(delivery_method(&daemon), json!("direct"));
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "record_receipt".into(),
+            params: Some(json!({ "message_id": "msg-2", "status": "sent: propagated" })),
+        })
+        .unwrap();
+    assert_eq!(delivery_method(&daemon), json!("propagated"));
+
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "record_receipt".into(),
+            params: Some(json!({ "message_id": "msg-2", "status": "delivered" })),
+        })
+        .unwrap();
+    assert_eq!(delivery_method(&daemon), json!("propagated"));
+}
@@ -0,0 +1,221 @@
+use reticulum::resource::ResourceCounts;
+use reticulum::rpc::{ResourceBridge, RpcDaemon, RpcEvent, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type SentResource = (String, String, Vec<u8>, Option<Vec<u8>>, Duration);
+
+#[derive(Default)]
+struct RecordingResourceBridge {
+    sent: Mutex<Vec<SentResource>>,
+}
+
+impl ResourceBridge for RecordingResourceBridge {
+    fn send_resource(
+        &self,
+        transfer_id: &str,
+        destination: &str,
+        data: Vec<u8>,
+        metadata: Option<Vec<u8>>,
+        path_timeout: Duration,
+    ) -> Result<(), std::io::Error> {
+        self.sent.lock().unwrap().push((
+            transfer_id.to_string(),
+            destination.to_string(),
+            data,
+            metadata,
+            path_timeout,
+        ));
+        Ok(())
+    }
+
+    fn resource_counts(&self) -> Option<ResourceCounts> {
+        Some(ResourceCounts {
+            incoming: 2,
+            outgoing: 1,
+            queued: 3,
+            max_concurrent: Some(2),
+        })
+    }
+}
+
+fn daemon_with_bridge() -> (RpcDaemon, Arc<RecordingResourceBridge>) {
+    let bridge = Arc::new(RecordingResourceBridge::default());
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_resource_bridge(bridge.clone());
+    (daemon, bridge)
+}
+
+#[test]
+fn resource_send_hands_decoded_bytes_to_bridge() {
+    let (daemon, bridge) = daemon_with_bridge();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "resource_send".into(),
+            params: Some(json!({
+                "destination": "6B3362BD2C1DBF87B66A85F79A8D8C75",
+                "data_base64": "aGVsbG8gcmVzb3VyY2U=",
+                "metadata_base64": "bWV0YQ==",
+            })),
+        })
+        .expect("resource_send");
+    let result = response.result.expect("result");
+    assert_eq!(result["status"], "queued");
+    assert_eq!(result["data_len"], 14);
+
+    let sent = bridge.sent.lock().unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].0, result["transfer_id"].as_str().unwrap());
+    assert_eq!(sent[0].1, "6b3362bd2c1dbf87b66a85f79a8d8c75");
+    assert_eq!(sent[0].2, b"hello resource");
+    assert_eq!(sent[0].3.as_deref(), Some(&b"meta"[..]));
+    assert_eq!(sent[0].4, Duration::from_secs(12));
+
+    let event = daemon.take_event().expect("event");
+    assert_eq!(event.event_type, "resource_queued");
+}
+
+#[test]
+fn resource_send_rejects_invalid_input() {
+    let (daemon, bridge) = daemon_with_bridge();
+    for params in [
+        json!({ "destination": "abcd", "data_base64": "aGk=" }),
+        json!({ "destination": "6b3362bd2c1dbf87b66a85f79a8d8c75", "data_base64": "%%%" }),
+        json!({ "destination": "6b3362bd2c1dbf87b66a85f79a8d8c75", "data_base64": "" }),
+    ] {
+        let err = daemon
+            .handle_rpc(RpcRequest {
+                id: 2,
+                method: "resource_send".into(),
+                params: Some(params),
+            })
+            .expect_err("invalid input");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert!(bridge.sent.lock().unwrap().is_empty());
+}
+
+#[test]
+fn resource_send_without_transport_returns_error() {
+    let daemon = RpcDaemon::test_instance();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 3,
+            method: "resource_send".into(),
+            params: Some(json!({
+                "destination": "6b3362bd2c1dbf87b66a85f79a8d8c75",
+                "data_base64": "aGk=",
+            })),
+        })
+        .expect("resource_send");
+    assert_eq!(response.error.expect("error").code, "TRANSPORT_UNAVAILABLE");
+}
+
+#[test]
+fn resource_send_forwards_path_timeout() {
+    let (daemon, bridge) = daemon_with_bridge();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 4,
+            method: "resource_send".into(),
+            params: Some(json!({
+                "destination": "6b3362bd2c1dbf87b66a85f79a8d8c75",
+                "data_base64": "aGk=",
+                "timeout_seconds": 30,
+            })),
+        })
+        .expect("resource_send");
+    let result = response.result.expect("result");
+    assert_eq!(result["path_timeout_ms"], 30_000);
+    assert_eq!(result["resource_hash"], json!(null));
+    assert_eq!(bridge.sent.lock().unwrap()[0].4, Duration::from_secs(30));
+}
+
+#[tokio::test]
+async fn async_resource_send_returns_the_resource_hash() {
+    let (daemon, bridge) = daemon_with_bridge();
+    let send = || {
+        daemon.handle_rpc_async(RpcRequest {
+            id: 7,
+            method: "resource_send".into(),
+            params: Some(json!({
+                "destination": "6b3362bd2c1dbf87b66a85f79a8d8c75",
+                "data_base64": "aGk=",
+                "timeout_seconds": 1,
+            })),
+        })
+    };
+    // Stands in for the transport reporting back once the bridge was called.
+    let report = |event_type: &str, payload: serde_json::Value| {
+        let event_type = event_type.to_string();
+        let bridge = bridge.clone();
+        let daemon = &daemon;
+        async move {
+            while bridge.sent.lock().unwrap().is_empty() {
+                tokio::task::yield_now().await;
+            }
+            let transfer_id = bridge.sent.lock().unwrap().remove(0).0;
+            let mut payload = payload;
+            payload["transfer_id"] = transfer_id.into();
+            daemon.emit_event(RpcEvent {
+                event_type: "resource_progress".into(),
+                payload: json!({ "transfer_id": "other" }),
+            });
+            daemon.emit_event(RpcEvent {
+                event_type,
+                payload,
+            });
+        }
+    };
+
+    let (response, ()) = tokio::join!(
+        send(),
+        report(
+            "resource_started",
+            json!({ "resource_hash": "ab".repeat(32), "link_id": "cd".repeat(16) }),
+        )
+    );
+    let result = response.expect("resource_send").result.expect("result");
+    assert_eq!(result["resource_hash"], "ab".repeat(32));
+    assert_eq!(result["link_id"], "cd".repeat(16));
+    assert_eq!(result["status"], "started");
+
+    let (response, ()) = tokio::join!(
+        send(),
+        report(
+            "resource_failed",
+            json!({ "error": "no path to destination", "reason_code": "no_path" }),
+        )
+    );
+    let error = response.expect("resource_send").error.expect("error");
+    assert_eq!(error.code, "NO_PATH");
+    assert_eq!(error.message, "no path to destination");
+}
+
+#[test]
+fn list_resources_reports_active_and_queued_transfers() {
+    let (daemon, _) = daemon_with_bridge();
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 5,
+            method: "list_resources".into(),
+            params: None,
+        })
+        .expect("list_resources");
+    assert_eq!(
+        response.result.expect("result"),
+        json!({ "incoming": 2, "outgoing": 1, "queued": 3, "max_concurrent": 2 })
+    );
+
+    let response = RpcDaemon::test_instance()
+        .handle_rpc(RpcRequest {
+            id: 6,
+            method: "list_resources".into(),
+            params: None,
+        })
+        .expect("list_resources");
+    assert_eq!(response.error.expect("error").code, "TRANSPORT_UNAVAILABLE");
+}
@@ -0,0 +1,144 @@
+use reticulum::rpc::{InboundOverloadPolicy, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::MessagesStore;
+use serde_json::{json, Value};
+
+fn call(daemon: &RpcDaemon, method: &str, params: Value) -> Value {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .expect("rpc")
+        .result
+        .expect("result")
+}
+
+fn open_daemon(path: &std::path::Path) -> RpcDaemon {
+    RpcDaemon::with_store(MessagesStore::open(path).unwrap(), "daemon".into())
+}
+
+#[test]
+fn policies_survive_a_restart_until_reset() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+
+    {
+        let daemon = open_daemon(&path);
+        call(
+            &daemon,
+            "set_delivery_policy",
+            json!({ "auth_required": true, "allowed_destinations": ["peer-a"] }),
+        );
+        call(
+            &daemon,
+            "stamp_policy_set",
+            json!({ "target_cost": 8, "flexibility": 2 }),
+        );
+        daemon
+            .set_inbound_overload_policy(InboundOverloadPolicy {
+                window_secs: 30,
+                max_writes_per_window: 50,
+                max_queued_events: 10,
+            })
+            .unwrap();
+    }
+
+    let daemon = open_daemon(&path);
+    let policy = call(&daemon, "get_delivery_policy", json!({}));
+    assert_eq!(policy["policy"]["auth_required"], true);
+    assert_eq!(policy["policy"]["allowed_destinations"], json!(["peer-a"]));
+
+    let settings = call(&daemon, "get_settings", json!({}));
+    assert_eq!(settings["settings"]["stamp_policy"]["target_cost"], 8);
+    assert_eq!(
+        settings["settings"]["inbound_overload_policy"]["max_writes_per_window"],
+        50
+    );
+    assert_eq!(
+        settings["persisted"],
+        json!(["delivery_policy", "stamp_policy", "inbound_overload_policy"])
+    );
+
+    let reset = call(&daemon, "reset_settings", json!({}));
+    assert_eq!(reset["cleared"], 3);
+    assert_eq!(reset["settings"]["delivery_policy"]["auth_required"], false);
+    assert_eq!(
+        daemon.take_event().expect("event").event_type,
+        "settings_reset"
+    );
+    drop(daemon);
+
+    let daemon = open_daemon(&path);
+    let settings = call(&daemon, "get_settings", json!({}));
+    assert_eq!(settings["persisted"], json!([]));
+    assert_eq!(settings["settings"]["stamp_policy"]["target_cost"], 0);
+    assert_eq!(
+        daemon.inbound_overload_policy(),
+        InboundOverloadPolicy::default()
+    );
+}
+
+#[test]
+fn runtime_settings_are_validated_applied_and_restored() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+
+    {
+        let daemon = open_daemon(&path);
+        let set = call(
+            &daemon,
+            "set_setting",
+            json!({ "key": "presence_online_secs", "value": 45 }),
+        );
+        assert_eq!(set, json!({ "key": "presence_online_secs", "value": 45 }));
+        let event = daemon.take_event().expect("event");
+        assert_eq!(event.event_type, "setting_changed");
+        assert_eq!(event.payload["value"], 45);
+        call(
+            &daemon,
+            "set_setting",
+            json!({ "key": "announce_interval_secs", "value": 600 }),
+        );
+
+        for (key, value) in [
+            ("presence_online_secs", json!(0)),
+            ("max_peers", json!("10")),
+            ("stamp_policy", json!({ "target_cost": 8 })),
+            ("retry_forever", json!(true)),
+        ] {
+            let err = daemon
+                .handle_rpc(RpcRequest {
+                    id: 2,
+                    method: "set_setting".into(),
+                    params: Some(json!({ "key": key, "value": value })),
+                })
+                .unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{key}");
+        }
+    }
+
+    let daemon = open_daemon(&path);
+    let before = call(
+        &daemon,
+        "get_setting",
+        json!({ "key": "presence_online_secs" }),
+    );
+    assert_eq!(before["persisted"], true);
+    assert_ne!(before["value"], 45);
+
+    assert_eq!(
+        daemon.apply_persisted_runtime_settings().unwrap(),
+        vec!["announce_interval_secs", "presence_online_secs"]
+    );
+    let after = call(
+        &daemon,
+        "get_setting",
+        json!({ "key": "presence_online_secs" }),
+    );
+    assert_eq!(after["value"], 45);
+    let settings = call(&daemon, "get_settings", json!({}));
+    assert_eq!(settings["settings"]["announce_interval_secs"], 600);
+    let unset = call(&daemon, "get_setting", json!({ "key": "max_peers" }));
+    assert_eq!(unset["persisted"], false);
+}
@@ -0,0 +1,135 @@
+use reticulum::rpc::{RpcDaemon, RpcRequest};
+use reticulum::storage::backup::{apply_pending_restore, pending_restore_path};
+use reticulum::storage::messages::{MessageRecord, MessagesStore};
+use serde_json::json;
+
+fn message(id: &str) -> MessageRecord {
+    MessageRecord {
+        id: id.into(),
+        source: "a".into(),
+        destination: "b".into(),
+        title: String::new(),
+        content: "hello".into(),
+        timestamp: 1,
+        direction: "in".into(),
+        fields: None,
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    }
+}
+
+fn call(
+    daemon: &RpcDaemon,
+    method: &str,
+    params: serde_json::Value,
+) -> reticulum::rpc::RpcResponse {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: method.into(),
+            params: Some(params),
+        })
+        .unwrap()
+}
+
+#[test]
+fn backup_store_rotates_and_restore_applies_on_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("reticulum.db");
+    let store = MessagesStore::open(&db_path).unwrap();
+    store.insert_message(&message("before-backup")).unwrap();
+    let daemon = RpcDaemon::with_store(store, "daemon".into());
+
+    let first = call(&daemon, "backup_store", json!({ "keep": 2 }))
+        .result
+        .unwrap();
+    let first_path = first["path"].as_str().unwrap().to_string();
+    assert!(first["size_bytes"].as_u64().unwrap() > 0);
+    assert!(first_path.starts_with(dir.path().join("backups").to_str().unwrap()));
+    call(&daemon, "backup_store", json!({ "keep": 2 }));
+    let third = call(&daemon, "backup_store", json!({ "keep": 2 }))
+        .result
+        .unwrap();
+    assert_eq!(third["pruned"], json!([first_path]));
+    assert_eq!(
+        std::fs::read_dir(dir.path().join("backups"))
+            .unwrap()
+            .count(),
+        2
+    );
+
+    let missing = call(&daemon, "restore_store", json!({ "path": first_path }));
+    assert_eq!(missing.error.unwrap().code, "BACKUP_NOT_FOUND");
+    let outside = call(&daemon, "restore_store", json!({ "path": db_path }));
+    assert_eq!(outside.error.unwrap().code, "BACKUP_NOT_FOUND");
+
+    let backup_name = std::path::Path::new(third["path"].as_str().unwrap())
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let restored = call(&daemon, "restore_store", json!({ "path": backup_name }))
+        .result
+        .unwrap();
+    assert_eq!(restored["applies_on_restart"], true);
+    assert!(pending_restore_path(&db_path).exists());
+
+    let sent = call(
+        &daemon,
+        "send_message",
+        json!({
+            "id": "after-backup",
+            "source": "a",
+            "destination": "b",
+            "content": "later",
+        }),
+    );
+    assert!(sent.error.is_none());
+    drop(daemon);
+
+    // A write still sitting in the write-ahead log, as after a crash.
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.set_db_config(
+            rusqlite::config::DbConfig::SQLITE_DBCONFIG_NO_CKPT_ON_CLOSE,
+            true,
+        )
+        .unwrap();
+        conn.query_row("PRAGMA journal_mode=WAL", [], |_| Ok(()))
+            .unwrap();
+        conn.execute_batch("CREATE TABLE wal_probe (x); INSERT INTO wal_probe VALUES (1);")
+            .unwrap();
+    }
+
+    assert!(apply_pending_restore(&db_path).unwrap());
+    assert!(!pending_restore_path(&db_path).exists());
+    let previous = rusqlite::Connection::open(dir.path().join("reticulum.db.pre-restore")).unwrap();
+    let probes: i64 = previous
+        .query_row("SELECT COUNT(*) FROM wal_probe", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(probes, 1);
+    let store = MessagesStore::open(&db_path).unwrap();
+    let ids: Vec<String> = store
+        .list_messages(10, None)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.id)
+        .collect();
+    assert_eq!(ids, vec!["before-backup".to_string()]);
+}
+
+#[test]
+fn backup_store_rejects_in_memory_store() {
+    let daemon = RpcDaemon::test_instance();
+    let err = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "backup_store".into(),
+            params: None,
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+}
@@ -0,0 +1,220 @@
+use reticulum::rpc::telemetry::{pack_location_telemetry, LocationTelemetry};
+use reticulum::rpc::{OutboundBridge, OutboundDeliveryOptions, RpcDaemon, RpcRequest};
+use reticulum::storage::messages::{MessageRecord, MessagesStore};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct RecordingBridge {
+    options: Mutex<Vec<OutboundDeliveryOptions>>,
+}
+
+impl OutboundBridge for RecordingBridge {
+    fn deliver(
+        &self,
+        _record: &MessageRecord,
+        options: &OutboundDeliveryOptions,
+    ) -> Result<(), std::io::Error> {
+        self.options.lock().unwrap().push(options.clone());
+        Ok(())
+    }
+}
+
+fn location(lat: f64, lon: f64, timestamp: i64) -> LocationTelemetry {
+    LocationTelemetry {
+        lat,
+        lon,
+        altitude: Some(12.5),
+        speed: Some(3.2),
+        bearing: Some(90.0),
+        accuracy: Some(4.0),
+        timestamp: Some(timestamp),
+    }
+}
+
+fn receive_with_telemetry(daemon: &RpcDaemon, id: &str, packed: Vec<u8>) {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "receive_message".into(),
+            params: Some(json!({
+                "id": id,
+                "source": "peer-a",
+                "destination": "local",
+                "content": "",
+                "fields": { "2": packed },
+            })),
+        })
+        .expect("receive_message");
+}
+
+#[test]
+fn inbound_telemetry_is_stored_and_queryable() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    receive_with_telemetry(
+        &daemon,
+        "tm-1",
+        pack_location_telemetry(&location(52.5, 13.4, 1_000)),
+    );
+    receive_with_telemetry(
+        &daemon,
+        "tm-2",
+        pack_location_telemetry(&location(52.6, 13.5, 2_000)),
+    );
+
+    let mut telemetry_events = 0;
+    while let Some(event) = daemon.take_event() {
+        if event.event_type == "telemetry_received" {
+            assert_eq!(event.payload["telemetry"]["peer"], "peer-a");
+            telemetry_events += 1;
+        }
+    }
+    assert_eq!(telemetry_events, 2);
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_telemetry_history".into(),
+            params: Some(json!({ "peer": "peer-a", "limit": 10, "since": 1_500 })),
+        })
+        .expect("get_telemetry_history");
+    let result = response.result.expect("result");
+    let items = result["telemetry"].as_array().expect("telemetry");
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["timestamp"], 2_000);
+    assert!((items[0]["lat"].as_f64().unwrap() - 52.6).abs() < 1e-6);
+    assert!((items[0]["lon"].as_f64().unwrap() - 13.5).abs() < 1e-6);
+    assert!((items[0]["altitude"].as_f64().unwrap() - 12.5).abs() < 1e-6);
+}
+
+#[test]
+fn messages_without_telemetry_do_not_create_history() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "receive_message".into(),
+            params: Some(json!({
+                "id": "plain-1",
+                "source": "peer-a",
+                "destination": "local",
+                "content": "hello",
+            })),
+        })
+        .expect("receive_message");
+
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "get_telemetry_history".into(),
+            params: Some(json!({ "peer": "peer-a" })),
+        })
+        .expect("get_telemetry_history");
+    let result = response.result.expect("result");
+    assert!(result["telemetry"]
+        .as_array()
+        .expect("telemetry")
+        .is_empty());
+}
+
+#[test]
+fn telemetry_response_is_correlated_with_pending_request() {
+    let daemon = RpcDaemon::test_instance();
+    daemon.set_accept_unaddressed_inbound(true);
+    let response = daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "send_telemetry_request".into(),
+            params: Some(json!({
+                "id": "tr-1",
+                "source": "local",
+                "destination": "peer-a",
+                "timebase": 1_500,
+            })),
+        })
+        .expect("send_telemetry_request");
+    assert_eq!(response.result.expect("result")["message_id"], "tr-1");
+
+    let list = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_messages".into(),
+            params: None,
+        })
+        .expect("list_messages");
+    assert_eq!(
+        list.result.expect("result")["messages"][0]["fields"]["9"][0]["1"],
+        1_500
+    );
+
+    let pending = |daemon: &RpcDaemon| {
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 3,
+                method: "list_pending_telemetry_requests".into(),
+                params: None,
+            })
+            .expect("list_pending_telemetry_requests")
+            .result
+            .expect("result")["requests"]
+            .as_array()
+            .cloned()
+            .expect("requests")
+    };
+    let requests = pending(&daemon);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["peer"], "peer-a");
+    assert_eq!(requests[0]["timed_out"], false);
+
+    // Older telemetry than the requested timebase does not answer the request.
+    receive_with_telemetry(
+        &daemon,
+        "tm-old",
+        pack_location_telemetry(&location(52.5, 13.4, 1_000)),
+    );
+    assert_eq!(pending(&daemon).len(), 1);
+    receive_with_telemetry(
+        &daemon,
+        "tm-new",
+        pack_location_telemetry(&location(52.6, 13.5, 2_000)),
+    );
+    assert!(pending(&daemon).is_empty());
+
+    let mut responses = Vec::new();
+    while let Some(event) = daemon.take_event() {
+        if event.event_type == "telemetry_response" {
+            responses.push(event.payload);
+        }
+    }
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["request_message_id"], "tr-1");
+    assert_eq!(responses[0]["message_id"], "tm-new");
+}
+
+#[test]
+fn telemetry_request_can_fall_back_to_propagation() {
+    let bridge = Arc::new(RecordingBridge::default());
+    let daemon = RpcDaemon::with_store_and_bridge(
+        MessagesStore::in_memory().unwrap(),
+        "local".into(),
+        bridge.clone(),
+    );
+    for (id, fallback) in [("tr-1", None), ("tr-2", Some(true))] {
+        let mut params = json!({ "id": id, "source": "local", "destination": "peer-a" });
+        if let Some(fallback) = fallback {
+            params["try_propagation_on_fail"] = json!(fallback);
+        }
+        daemon
+            .handle_rpc(RpcRequest {
+                id: 1,
+                method: "send_telemetry_request".into(),
+                params: Some(params),
+            })
+            .expect("send_telemetry_request");
+    }
+    let options = bridge.options.lock().unwrap();
+    assert!(!options[0].try_propagation_on_fail);
+    assert!(options[1].try_propagation_on_fail);
+}
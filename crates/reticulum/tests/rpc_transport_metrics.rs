@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use reticulum::rpc::{RpcDaemon, RpcRequest, TransportMetricsBridge};
+use reticulum::storage::messages::MessagesStore;
+use reticulum::transport::TransportMetrics;
+
+struct FixedMetrics(TransportMetrics);
+
+impl TransportMetricsBridge for FixedMetrics {
+    fn transport_metrics(&self) -> TransportMetrics {
+        self.0
+    }
+}
+
+#[test]
+fn transport_metrics_reports_deduped_announces() {
+    let request = || RpcRequest {
+        id: 1,
+        method: "transport_metrics".into(),
+        params: None,
+    };
+    let missing = RpcDaemon::test_instance().handle_rpc(request()).unwrap();
+    assert_eq!(missing.error.unwrap().code, "TRANSPORT_UNAVAILABLE");
+
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_transport_metrics_bridge(Arc::new(FixedMetrics(TransportMetrics {
+            announces_deduped: 7,
+            announce_dedup_window_secs: 5,
+        })));
+    let metrics = daemon.handle_rpc(request()).unwrap().result.unwrap();
+    assert_eq!(metrics["announces_deduped"], 7);
+    assert_eq!(metrics["announce_dedup_window_secs"], 5);
+}
@@ -0,0 +1,65 @@
+use reticulum::rpc::{InterfaceRecord, RpcDaemon, RpcRequest, TransportControlBridge};
+use reticulum::storage::messages::MessagesStore;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+struct CountingRestartBridge {
+    restarts: AtomicUsize,
+}
+
+impl TransportControlBridge for CountingRestartBridge {
+    fn restart_transport(&self) -> Result<Vec<InterfaceRecord>, std::io::Error> {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+        Ok(vec![InterfaceRecord {
+            kind: "tcp_client".into(),
+            enabled: true,
+            host: Some("127.0.0.1".into()),
+            port: Some(4242),
+            name: Some("uplink".into()),
+            options: Default::default(),
+        }])
+    }
+}
+
+fn restart(daemon: &RpcDaemon) -> reticulum::rpc::RpcResponse {
+    daemon
+        .handle_rpc(RpcRequest {
+            id: 1,
+            method: "restart_transport".into(),
+            params: None,
+        })
+        .expect("response")
+}
+
+#[test]
+fn restart_transport_refreshes_interfaces_and_emits_event() {
+    let bridge = Arc::new(CountingRestartBridge::default());
+    let daemon = RpcDaemon::with_store(MessagesStore::in_memory().unwrap(), "daemon".into())
+        .with_transport_control_bridge(bridge.clone());
+
+    let result = restart(&daemon).result.expect("result");
+    assert_eq!(result["interfaces"][0]["name"], "uplink");
+    assert_eq!(bridge.restarts.load(Ordering::Relaxed), 1);
+
+    let event = daemon.take_event().expect("event");
+    assert_eq!(event.event_type, "transport_restarted");
+
+    let listed = daemon
+        .handle_rpc(RpcRequest {
+            id: 2,
+            method: "list_interfaces".into(),
+            params: None,
+        })
+        .unwrap()
+        .result
+        .expect("result");
+    assert_eq!(listed["interfaces"][0]["port"], 4242);
+}
+
+#[test]
+fn restart_transport_requires_transport() {
+    let daemon = RpcDaemon::test_instance();
+    let error = restart(&daemon).error.expect("error");
+    assert_eq!(error.code, "TRANSPORT_UNAVAILABLE");
+}
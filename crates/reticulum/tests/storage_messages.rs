@@ -1,4 +1,4 @@
-use reticulum::storage::messages::{MessageRecord, MessagesStore};
+use reticulum::storage::messages::{DeliveryTraceRecord, MessageRecord, MessagesStore};
 use rusqlite::params;
 
 #[test]
@@ -14,6 +14,9 @@ fn stores_and_reads_message() {
         direction: "in".into(),
         fields: None,
         receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
     })
     .unwrap();
     let items = db.list_messages(10, None).unwrap();
@@ -35,6 +38,9 @@ fn opens_disk_store() {
         direction: "in".into(),
         fields: None,
         receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
     })
     .unwrap();
     drop(db);
@@ -73,3 +79,405 @@ fn migrates_missing_title_to_empty_string() {
     let items = db.list_messages(10, None).unwrap();
     assert_eq!(items[0].title, "");
 }
+
+#[test]
+fn counts_messages_without_listing() {
+    let db = MessagesStore::in_memory().unwrap();
+    assert_eq!(db.count_messages().unwrap(), 0);
+    for idx in 0..3 {
+        db.insert_message(&MessageRecord {
+            id: format!("m{idx}"),
+            source: "a".into(),
+            destination: "b".into(),
+            title: String::new(),
+            content: "hi".into(),
+            timestamp: idx,
+            direction: "in".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .unwrap();
+    }
+    assert_eq!(db.count_messages().unwrap(), 3);
+}
+
+#[test]
+fn streams_messages_oldest_first() {
+    let db = MessagesStore::in_memory().unwrap();
+    for (id, timestamp) in [("m2", 20), ("m1", 10), ("m3", 30)] {
+        db.insert_message(&MessageRecord {
+            id: id.into(),
+            source: "a".into(),
+            destination: "b".into(),
+            title: String::new(),
+            content: "hi".into(),
+            timestamp,
+            direction: "in".into(),
+            fields: Some(serde_json::json!({ "k": id })),
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .unwrap();
+    }
+    db.insert_message(&MessageRecord {
+        id: "gone".into(),
+        source: "a".into(),
+        destination: "b".into(),
+        title: String::new(),
+        content: "deleted".into(),
+        timestamp: 15,
+        direction: "in".into(),
+        fields: Some(serde_json::json!({ "k": "gone" })),
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    })
+    .unwrap();
+    assert!(db.delete_message("gone", 40).unwrap());
+    let mut seen = Vec::new();
+    let visited = db
+        .for_each_message(|record| seen.push((record.id, record.fields.unwrap()["k"].clone())))
+        .unwrap();
+    assert_eq!(visited, 3);
+    assert_eq!(
+        seen,
+        vec![
+            ("m1".to_string(), serde_json::json!("m1")),
+            ("m2".to_string(), serde_json::json!("m2")),
+            ("m3".to_string(), serde_json::json!("m3")),
+        ]
+    );
+}
+
+#[test]
+fn filters_messages_by_peer_and_direction() {
+    let db = MessagesStore::in_memory().unwrap();
+    for (id, source, destination, direction, timestamp) in [
+        ("m1", "peer-a", "local", "in", 1),
+        ("m2", "local", "peer-a", "out", 2),
+        ("m3", "peer-b", "local", "in", 3),
+    ] {
+        db.insert_message(&MessageRecord {
+            id: id.into(),
+            source: source.into(),
+            destination: destination.into(),
+            title: String::new(),
+            content: "hi".into(),
+            timestamp,
+            direction: direction.into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .unwrap();
+    }
+
+    let conversation = db.list_messages_filtered(10, None, Some("peer-a")).unwrap();
+    let ids: Vec<_> = conversation.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids, vec!["m2", "m1"]);
+
+    let inbound = db
+        .list_messages_filtered(10, Some("in"), Some("peer-a"))
+        .unwrap();
+    assert_eq!(inbound.len(), 1);
+    assert_eq!(inbound[0].id, "m1");
+
+    assert_eq!(
+        db.list_messages_filtered(10, Some("in"), None)
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn lists_messages_with_same_timestamp_in_stable_order() {
+    let db = MessagesStore::in_memory().unwrap();
+    for id in ["m-b", "m-d", "m-a", "m-c"] {
+        db.insert_message(&MessageRecord {
+            id: id.into(),
+            source: "a".into(),
+            destination: "b".into(),
+            title: String::new(),
+            content: "same second".into(),
+            timestamp: 42,
+            direction: "in".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .unwrap();
+    }
+
+    for _ in 0..3 {
+        let ids: Vec<_> = db
+            .list_messages(10, None)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec!["m-d", "m-c", "m-b", "m-a"]);
+    }
+}
+
+#[test]
+fn migrates_unversioned_database_without_data_loss() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (
+                id TEXT PRIMARY KEY,
+                source TEXT NOT NULL,
+                destination TEXT NOT NULL,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                direction TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO messages (id, source, destination, content, timestamp, direction) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params!["legacy", "alice", "bob", "kept", 42, "in"],
+        )
+        .unwrap();
+    }
+
+    let db = MessagesStore::open(&path).unwrap();
+    assert_eq!(
+        db.schema_version().unwrap(),
+        reticulum::storage::messages::SCHEMA_VERSION
+    );
+    let items = db.list_messages(10, None).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].id, "legacy");
+    assert_eq!(items[0].content, "kept");
+    assert_eq!(items[0].title, "");
+    assert_eq!(items[0].content_type, None);
+    drop(db);
+
+    // Reopening an up-to-date database applies nothing and keeps the version.
+    let db = MessagesStore::open(&path).unwrap();
+    assert_eq!(
+        db.schema_version().unwrap(),
+        reticulum::storage::messages::SCHEMA_VERSION
+    );
+    assert_eq!(db.list_messages(10, None).unwrap().len(), 1);
+}
+
+#[test]
+fn soft_deleted_messages_leave_tombstones_until_purged() {
+    let db = MessagesStore::in_memory().unwrap();
+    for idx in 0..3 {
+        db.insert_message(&MessageRecord {
+            id: format!("m{idx}"),
+            source: "a".into(),
+            destination: "b".into(),
+            title: String::new(),
+            content: "hi".into(),
+            timestamp: idx,
+            direction: "out".into(),
+            fields: None,
+            receipt_status: None,
+            delivery_method: None,
+            content_type: None,
+            attempts: 0,
+        })
+        .unwrap();
+    }
+
+    for id in ["m0", "m2"] {
+        db.append_delivery_trace(
+            &DeliveryTraceRecord {
+                message_id: id.into(),
+                status: "sent".into(),
+                reason_code: None,
+                timestamp: 1,
+            },
+            10,
+        )
+        .unwrap();
+    }
+
+    assert!(db.delete_message("m0", 100).unwrap());
+    assert!(db.delete_message("m1", 200).unwrap());
+    assert!(!db.delete_message("m1", 300).unwrap());
+    assert!(!db.delete_message("missing", 300).unwrap());
+
+    let listed: Vec<_> = db
+        .list_messages(10, None)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.id)
+        .collect();
+    assert_eq!(listed, vec!["m2"]);
+    assert_eq!(db.count_messages().unwrap(), 1);
+    assert_eq!(db.count_messages_filtered(Some("out"), None).unwrap(), 1);
+    assert!(db.get_message("m0").unwrap().is_none());
+    assert_eq!(db.list_outbound_since(0).unwrap().len(), 1);
+
+    let tombstones = db.list_deleted_since(200).unwrap();
+    assert_eq!(tombstones.len(), 1);
+    assert_eq!(tombstones[0].id, "m1");
+    assert_eq!(tombstones[0].deleted_at, 200);
+
+    assert_eq!(db.purge_deleted(200).unwrap(), 1);
+    assert!(db.list_delivery_traces("m0", 10).unwrap().is_empty());
+    assert_eq!(db.list_delivery_traces("m2", 10).unwrap().len(), 1);
+    let remaining: Vec<_> = db
+        .list_deleted_since(0)
+        .unwrap()
+        .into_iter()
+        .map(|tombstone| tombstone.id)
+        .collect();
+    assert_eq!(remaining, vec!["m1"]);
+}
+
+#[test]
+fn delivery_traces_are_only_kept_for_stored_messages() {
+    let db = MessagesStore::in_memory().unwrap();
+    db.insert_message(&MessageRecord {
+        id: "known".into(),
+        source: "a".into(),
+        destination: "b".into(),
+        title: String::new(),
+        content: "hi".into(),
+        timestamp: 1,
+        direction: "out".into(),
+        fields: None,
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    })
+    .unwrap();
+
+    for id in ["known", "unknown"] {
+        db.append_delivery_trace(
+            &DeliveryTraceRecord {
+                message_id: id.into(),
+                status: "sending".into(),
+                reason_code: None,
+                timestamp: 1,
+            },
+            10,
+        )
+        .unwrap();
+    }
+
+    assert_eq!(db.list_delivery_traces("known", 10).unwrap().len(), 1);
+    assert!(db.list_delivery_traces("unknown", 10).unwrap().is_empty());
+}
+
+#[test]
+fn propagation_store_prunes_expired_then_oldest() {
+    let db = MessagesStore::in_memory().unwrap();
+    for (transient_id, ingested_at) in [("old", 10), ("mid", 20), ("new", 30), ("newest", 40)] {
+        assert!(db
+            .put_propagation_payload(transient_id, "ab", ingested_at, false)
+            .unwrap());
+    }
+    assert!(!db.put_propagation_payload("new", "cd", 50, false).unwrap());
+
+    assert_eq!(
+        db.prune_propagation_store(Some(15), Some(2), None).unwrap(),
+        2
+    );
+    let kept: Vec<String> = db
+        .list_propagation_payloads(None, 10)
+        .unwrap()
+        .into_iter()
+        .map(|(transient_id, _)| transient_id)
+        .collect();
+    assert_eq!(kept, vec!["new", "newest"]);
+    assert_eq!(db.propagation_store_usage().unwrap(), (2, 2));
+}
+
+#[test]
+fn rewriting_a_message_keeps_its_tombstone_edit_and_packed_bytes() {
+    let db = MessagesStore::in_memory().unwrap();
+    let record = |id: &str, content: &str| MessageRecord {
+        id: id.into(),
+        source: "a".into(),
+        destination: "b".into(),
+        title: String::new(),
+        content: content.into(),
+        timestamp: 1,
+        direction: "in".into(),
+        fields: None,
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    };
+
+    db.insert_message(&record("edited", "v1")).unwrap();
+    db.edit_message("edited", "", "v2", 50).unwrap();
+    assert!(db.set_message_packed("edited", &[1, 2, 3]).unwrap());
+    db.insert_message(&record("edited", "v3")).unwrap();
+    assert_eq!(db.message_edited_at("edited").unwrap(), Some(50));
+    assert_eq!(db.message_packed("edited").unwrap(), Some(vec![1, 2, 3]));
+    assert_eq!(db.get_message("edited").unwrap().unwrap().content, "v3");
+
+    db.insert_message(&record("deleted", "hi")).unwrap();
+    assert!(db.delete_message("deleted", 100).unwrap());
+    db.insert_message(&record("deleted", "hi")).unwrap();
+    assert!(db.get_message("deleted").unwrap().is_none());
+    assert_eq!(db.message_deleted_at("deleted").unwrap(), Some(100));
+    assert_eq!(db.list_deleted_since(0).unwrap().len(), 1);
+}
+
+#[test]
+fn peer_message_activity_counts_live_messages_for_the_listed_peers() {
+    let db = MessagesStore::in_memory().unwrap();
+    let message = |id: &str, source: &str, destination: &str, timestamp: i64| MessageRecord {
+        id: id.into(),
+        source: source.into(),
+        destination: destination.into(),
+        title: String::new(),
+        content: "hi".into(),
+        timestamp,
+        direction: "in".into(),
+        fields: None,
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    };
+    for record in [
+        message("m1", "peer-a", "local", 1),
+        message("m2", "local", "peer-a", 4),
+        message("m3", "peer-a", "peer-a", 5),
+        message("m4", "peer-b", "local", 2),
+        message("m5", "peer-a", "local", 9),
+        message("m6", "peer-c", "local", 3),
+    ] {
+        db.insert_message(&record).unwrap();
+    }
+    db.delete_message("m5", 10).unwrap();
+
+    // More peers than one lookup batch holds.
+    let mut peers = (0..1200)
+        .map(|index| format!("idle-{index}"))
+        .collect::<Vec<_>>();
+    peers.push("peer-a".into());
+    peers.push("peer-b".into());
+    let peers = peers.iter().map(String::as_str).collect::<Vec<_>>();
+    let activity = db.peer_message_activity(&peers).unwrap();
+
+    // A note to self counts once; deleted messages and unlisted peers not at all.
+    assert_eq!(activity.len(), 2);
+    assert_eq!(activity["peer-a"], (5, 3));
+    assert_eq!(activity["peer-b"], (2, 1));
+}
@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use reticulum::iface::tcp_client::{ReconnectBackoff, TcpClient, TcpClientState, TcpClientStatus};
+use reticulum::iface::InterfaceManager;
+use tokio::sync::watch;
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let backoff = ReconnectBackoff {
+        initial: Duration::from_secs(1),
+        max: Duration::from_secs(10),
+    };
+    assert_eq!(backoff.delay(1), Duration::from_secs(1));
+    assert_eq!(backoff.delay(2), Duration::from_secs(2));
+    assert_eq!(backoff.delay(4), Duration::from_secs(8));
+    assert_eq!(backoff.delay(5), Duration::from_secs(10));
+    assert_eq!(backoff.delay(64), Duration::from_secs(10));
+}
+
+async fn wait_for(
+    rx: &mut watch::Receiver<TcpClientStatus>,
+    check: impl Fn(&TcpClientStatus) -> bool,
+) -> TcpClientStatus {
+    tokio::time::timeout(Duration::from_secs(5), rx.wait_for(|status| check(status)))
+        .await
+        .expect("status timeout")
+        .expect("status channel")
+        .clone()
+}
+
+#[tokio::test]
+async fn tcp_client_reconnects_after_the_peer_returns() {
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let addr = format!("127.0.0.1:{port}");
+    let client = TcpClient::new(addr.clone()).with_backoff(ReconnectBackoff {
+        initial: Duration::from_millis(20),
+        max: Duration::from_millis(80),
+    });
+    let mut status = client.subscribe_status();
+    let mut manager = InterfaceManager::new(16);
+    manager.spawn(client, TcpClient::spawn);
+
+    let down = wait_for(&mut status, |status| {
+        status.state == TcpClientState::Disconnected && status.reconnect_attempts >= 3
+    })
+    .await;
+    assert_eq!(down.retry_in, Some(Duration::from_millis(80)));
+
+    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
+    let (stream, _) = listener.accept().await.unwrap();
+    let up = wait_for(&mut status, |status| {
+        status.state == TcpClientState::Connected
+    })
+    .await;
+    assert_eq!(up.reconnect_attempts, 0);
+
+    drop(stream);
+    wait_for(&mut status, |status| {
+        status.state == TcpClientState::Disconnected
+    })
+    .await;
+    let (_stream, _) = tokio::time::timeout(Duration::from_secs(5), listener.accept())
+        .await
+        .expect("reconnect timeout")
+        .unwrap();
+    wait_for(&mut status, |status| {
+        status.state == TcpClientState::Connected
+    })
+    .await;
+}
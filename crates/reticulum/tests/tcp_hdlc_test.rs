@@ -122,3 +122,44 @@ async fn packet_overload() {
     log::info!("TX: {}, RX: {}", tx_counter, rx_counter);
     assert!(tx_counter > 0, "producer should send packets");
 }
+
+#[tokio::test]
+async fn tcp_server_reports_the_resolved_address_or_the_bind_error() {
+    let transport = Transport::new(TransportConfig::new(
+        "bind",
+        &PrivateIdentity::new_from_rand(OsRng),
+        true,
+    ));
+
+    let server = TcpServer::new("0.0.0.0:0", transport.iface_manager());
+    let mut status = server.subscribe_status();
+    transport
+        .iface_manager()
+        .lock()
+        .await
+        .spawn(server, TcpServer::spawn);
+    tokio::time::timeout(std::time::Duration::from_secs(5), status.changed())
+        .await
+        .expect("bind result")
+        .expect("status channel");
+    let bound = status.borrow().bound.expect("bound address");
+    assert!(bound.ip().is_unspecified());
+    assert_ne!(bound.port(), 0);
+
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").expect("bind ephemeral port");
+    let taken_addr = taken.local_addr().expect("ephemeral addr");
+    let server = TcpServer::new(taken_addr.to_string(), transport.iface_manager());
+    let mut status = server.subscribe_status();
+    transport
+        .iface_manager()
+        .lock()
+        .await
+        .spawn(server, TcpServer::spawn);
+    tokio::time::timeout(std::time::Duration::from_secs(5), status.changed())
+        .await
+        .expect("bind result")
+        .expect("status channel");
+    let status = status.borrow().clone();
+    assert!(status.bound.is_none());
+    assert!(status.error.is_some());
+}
@@ -28,3 +28,109 @@ fn send_message_emits_inbound_on_peer() {
     let event = daemon_b.take_event().expect("inbound event");
     assert_eq!(event.event_type, "inbound");
 }
+
+#[test]
+fn opportunistic_attachment_is_chunked_and_reassembled() {
+    let daemon_a = Rc::new(RpcDaemon::test_instance_with_identity("daemon-a"));
+    let daemon_b = Rc::new(RpcDaemon::test_instance_with_identity("daemon-b"));
+
+    test_bridge::reset();
+    test_bridge::register("daemon-b", daemon_b.clone());
+
+    let file: Vec<u8> = (0..1000u32).map(|index| (index % 251) as u8).collect();
+    let send = |id: u64, file: &[u8]| {
+        daemon_a.handle_rpc(RpcRequest {
+            id,
+            method: "send_message_v2".into(),
+            params: Some(json!({
+                "id": format!("photo-{id}"),
+                "source": "daemon-a",
+                "destination": "daemon-b",
+                "title": "photo",
+                "content": "see attached",
+                "method": "opportunistic",
+                "fields": { "attachments": [["photo.jpg", hex::encode(file)]] },
+            })),
+        })
+    };
+    let sent = send(1, &file).unwrap().result.unwrap();
+    assert_eq!(sent["attachment_chunks"], 8);
+
+    let events: Vec<_> = std::iter::from_fn(|| daemon_b.take_event()).collect();
+    let progress: Vec<_> = events
+        .iter()
+        .filter(|event| event.event_type == "attachment_progress")
+        .collect();
+    assert_eq!(progress.len(), 8);
+    assert_eq!(progress[7].payload["status"], "complete");
+    let inbound: Vec<_> = events
+        .iter()
+        .filter(|event| event.event_type == "inbound")
+        .collect();
+    assert_eq!(inbound.len(), 1);
+    let message = &inbound[0].payload["message"];
+    assert_eq!(message["content"], "see attached");
+    assert_eq!(message["fields"]["5"][0][0], "photo.jpg");
+    assert_eq!(message["fields"]["5"][0][1], json!(file));
+    assert!(message["fields"].get("attachment_chunk").is_none());
+
+    let outbound_progress = std::iter::from_fn(|| daemon_a.take_event())
+        .filter(|event| event.event_type == "attachment_progress")
+        .count();
+    assert_eq!(outbound_progress, 8);
+
+    let oversized = vec![0u8; 64 * 1024];
+    let err = send(2, &oversized).expect_err("attachment over the chunking cap");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn attachment_pieces_are_stored_only_once_the_file_verifies() {
+    use base64::Engine as _;
+    use reticulum::rpc::InboundAcceptance;
+    use reticulum::storage::messages::MessageRecord;
+    use sha2::{Digest, Sha256};
+
+    let daemon = RpcDaemon::test_instance();
+    let piece = |transfer_id: &str, index: u64, chunk: serde_json::Value| MessageRecord {
+        id: format!("{transfer_id}-{index}"),
+        source: "peer".into(),
+        destination: "local".into(),
+        title: String::new(),
+        content: String::new(),
+        timestamp: 1,
+        direction: "in".into(),
+        fields: Some(json!({ "attachment_chunk": chunk })),
+        receipt_status: None,
+        delivery_method: None,
+        content_type: None,
+        attempts: 0,
+    };
+    let transfer = |transfer_id: &str, sha256: String| {
+        let head = daemon.accept_inbound(piece(
+            transfer_id,
+            0,
+            json!({
+                "transfer_id": transfer_id, "index": 0, "total": 2, "data": "",
+                "filename": "a.bin", "size": 3, "sha256": sha256,
+            }),
+        ));
+        let data = daemon.accept_inbound(piece(
+            transfer_id,
+            1,
+            json!({
+                "transfer_id": transfer_id, "index": 1, "total": 2,
+                "data": base64::engine::general_purpose::STANDARD.encode([1u8, 2, 3]),
+            }),
+        ));
+        (head.unwrap(), data.unwrap())
+    };
+
+    let (head, data) = transfer("bad", hex::encode(Sha256::digest([9u8, 9, 9])));
+    assert_eq!(head, InboundAcceptance::Held);
+    assert_eq!(data, InboundAcceptance::Dropped);
+
+    let (head, data) = transfer("good", hex::encode(Sha256::digest([1u8, 2, 3])));
+    assert_eq!(head, InboundAcceptance::Held);
+    assert_eq!(data, InboundAcceptance::Stored);
+}